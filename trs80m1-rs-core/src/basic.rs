@@ -0,0 +1,311 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::error;
+
+use std::collections::BTreeMap;
+
+use crate::tape;
+
+
+// Conversion between Level II BASIC programs, as they're stored in memory
+// and on tape, and plain text listings.
+//
+// Each keyword is stored as a single byte token, everything else is kept as
+// typed, with the exception of string literals, the text following REM and
+// DATA (up to the next statement), which are never tokenized.  The `'' form
+// of REM is stored as `:REM'', and ELSE is always preceded by a colon, but
+// neither of those colons appear in a listing.
+//
+// The text is handled as raw bytes, so that graphics characters within
+// strings survive the round-trip.
+//
+
+// The tokens from 0x80 onwards, in the order the ROM looks them up.
+const KEYWORDS: [&str; 124] = [
+    "END",    "FOR",    "RESET",  "SET",     "CLS",    "CMD",     "RANDOM", "NEXT",
+    "DATA",   "INPUT",  "DIM",    "READ",    "LET",    "GOTO",    "RUN",    "IF",
+    "RESTORE","GOSUB",  "RETURN", "REM",     "STOP",   "ELSE",    "TRON",   "TROFF",
+    "DEFSTR", "DEFINT", "DEFSNG", "DEFDBL",  "LINE",   "EDIT",    "ERROR",  "RESUME",
+    "OUT",    "ON",     "OPEN",   "FIELD",   "GET",    "PUT",     "CLOSE",  "LOAD",
+    "MERGE",  "NAME",   "KILL",   "LSET",    "RSET",   "SAVE",    "SYSTEM", "LPRINT",
+    "DEF",    "POKE",   "PRINT",  "CONT",    "LIST",   "LLIST",   "DELETE", "AUTO",
+    "CLEAR",  "CLOAD",  "CSAVE",  "NEW",     "TAB(",   "TO",      "FN",     "USING",
+    "VARPTR", "USR",    "ERL",    "ERR",     "STRING$","INSTR",   "POINT",  "TIME$",
+    "MEM",    "INKEY$", "THEN",   "NOT",     "STEP",   "+",       "-",      "*",
+    "/",      "[",      "AND",    "OR",      ">",      "=",       "<",      "SGN",
+    "INT",    "ABS",    "FRE",    "INP",     "POS",    "SQR",     "RND",    "LOG",
+    "EXP",    "COS",    "SIN",    "TAN",     "ATN",    "PEEK",    "CVI",    "CVS",
+    "CVD",    "EOF",    "LOC",    "LOF",     "MKI$",   "MKS$",    "MKD$",   "CINT",
+    "CSNG",   "CDBL",   "FIX",    "LEN",     "STR$",   "VAL",     "ASC",    "CHR$",
+    "LEFT$",  "RIGHT$", "MID$",   "'",
+];
+
+const TOKEN_DATA:         u8 = 0x88;
+const TOKEN_REM:          u8 = 0x93;
+const TOKEN_ELSE:         u8 = 0x95;
+const TOKEN_PRINT:        u8 = 0xB2;
+const TOKEN_EXPONENT:     u8 = 0xD1;
+const TOKEN_APOSTROPHE:   u8 = 0xFB;
+
+// Where Level II places the program, when there's no DOS in the way.
+const PROGRAM_START:      u16 = 0x42E9;
+
+const MAX_LINE_NUMBER:    u16 = 65529;
+
+fn keyword(token: u8) -> Option<&'static str> {
+    if token >= 0x80 {
+        KEYWORDS.get((token - 0x80) as usize).copied()
+    } else {
+        None
+    }
+}
+
+// Produce the listing of a tokenized program.
+pub fn detokenize(program: &[u8]) -> Option<Vec<u8>> {
+    let mut listing = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let next_line = match program.get(position..(position + 2)) {
+            Some(bytes) => { ((bytes[1] as u16) << 8) | (bytes[0] as u16) },
+            None => {
+                error!("The program ends unexpectedly.");
+                return None;
+            },
+        };
+        if next_line == 0 {
+            break;
+        }
+        let line_number = match program.get((position + 2)..(position + 4)) {
+            Some(bytes) => { ((bytes[1] as u16) << 8) | (bytes[0] as u16) },
+            None => {
+                error!("The program ends unexpectedly.");
+                return None;
+            },
+        };
+        position += 4;
+
+        let line_end = match program[position..].iter().position(|byte| *byte == 0x00) {
+            Some(length) => { position + length },
+            None => {
+                error!("Line {} isn't terminated.", line_number);
+                return None;
+            },
+        };
+        listing.extend_from_slice(format!("{} ", line_number).as_bytes());
+        detokenize_line(&program[position..line_end], &mut listing);
+        listing.push(b'\n');
+
+        position = line_end + 1;
+    }
+    Some(listing)
+}
+
+fn detokenize_line(line: &[u8], listing: &mut Vec<u8>) {
+    let mut in_string = false;
+    let mut in_data = false;
+    let mut index = 0;
+
+    while index < line.len() {
+        let byte = line[index];
+        let following = line.get(index + 1).copied();
+
+        if in_string || in_data {
+            if byte == b'"' {
+                in_string = !in_string;
+            } else if byte == b':' && !in_string {
+                in_data = false;
+            }
+            listing.push(byte);
+        } else if byte == b'"' {
+            in_string = true;
+            listing.push(byte);
+        } else if byte == b':' && following == Some(TOKEN_ELSE) {
+            // The colon in front of ELSE isn't listed.
+        } else if byte == b':' && following == Some(TOKEN_REM) && line.get(index + 2) == Some(&TOKEN_APOSTROPHE) {
+            listing.push(b'\'');
+            listing.extend_from_slice(&line[(index + 3)..]);
+            return;
+        } else if byte == TOKEN_REM {
+            listing.extend_from_slice(b"REM");
+            listing.extend_from_slice(&line[(index + 1)..]);
+            return;
+        } else {
+            match keyword(byte) {
+                Some(keyword) => {
+                    if byte == TOKEN_DATA {
+                        in_data = true;
+                    }
+                    listing.extend_from_slice(keyword.as_bytes());
+                },
+                None => {
+                    listing.push(byte);
+                },
+            }
+        }
+        index += 1;
+    }
+}
+
+// Tokenize a listing, the lines are sorted by their number, and later lines
+// replace (or with no text, delete) earlier ones with the same number, just
+// as if they were typed in.
+pub fn tokenize(listing: &[u8]) -> Option<Vec<u8>> {
+    let mut lines = BTreeMap::new();
+
+    for (index, text) in listing.split(|byte| *byte == b'\n').enumerate() {
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let text = trim_start(text);
+        if text.is_empty() {
+            continue;
+        }
+
+        let digits = text.iter().take_while(|byte| byte.is_ascii_digit()).count();
+        let line_number = match std::str::from_utf8(&text[..digits]).ok().and_then(|digits| digits.parse::<u16>().ok()) {
+            Some(line_number) if line_number <= MAX_LINE_NUMBER => { line_number },
+            _ => {
+                error!("Line {} of the listing doesn't start with a valid line number.", index + 1);
+                return None;
+            },
+        };
+        let text = trim_start(&text[digits..]);
+        if text.is_empty() {
+            lines.remove(&line_number);
+        } else {
+            lines.insert(line_number, tokenize_line(text));
+        }
+    }
+
+    let mut program = Vec::new();
+    let mut address = PROGRAM_START;
+    for (line_number, tokens) in lines {
+        address = match address.checked_add((tokens.len() + 5) as u16) {
+            Some(address) => { address },
+            None => {
+                error!("The program is too large.");
+                return None;
+            },
+        };
+        program.extend_from_slice(&address.to_le_bytes());
+        program.extend_from_slice(&line_number.to_le_bytes());
+        program.extend_from_slice(&tokens);
+        program.push(0x00);
+    }
+    program.extend_from_slice(&[0x00, 0x00]);
+
+    Some(program)
+}
+
+fn trim_start(text: &[u8]) -> &[u8] {
+    let spaces = text.iter().take_while(|byte| **byte == b' ' || **byte == b'\t').count();
+    &text[spaces..]
+}
+
+fn tokenize_line(text: &[u8]) -> Vec<u8> {
+    let mut tokens = Vec::new();
+    let mut in_string = false;
+    let mut in_data = false;
+    let mut index = 0;
+
+    while index < text.len() {
+        let byte = text[index];
+
+        if in_string || in_data {
+            if byte == b'"' {
+                in_string = !in_string;
+            } else if byte == b':' && !in_string {
+                in_data = false;
+            }
+            tokens.push(byte);
+            index += 1;
+        } else if byte == b'"' {
+            in_string = true;
+            tokens.push(byte);
+            index += 1;
+        } else if byte == b'?' {
+            tokens.push(TOKEN_PRINT);
+            index += 1;
+        } else if byte == b'^' {
+            tokens.push(TOKEN_EXPONENT);
+            index += 1;
+        } else {
+            match match_keyword(&text[index..]) {
+                Some((token, length)) => {
+                    index += length;
+
+                    if token == TOKEN_APOSTROPHE {
+                        tokens.extend_from_slice(&[b':', TOKEN_REM, TOKEN_APOSTROPHE]);
+                        tokens.extend_from_slice(&text[index..]);
+                        break;
+                    }
+                    if token == TOKEN_ELSE && tokens.last() != Some(&b':') {
+                        tokens.push(b':');
+                    }
+                    tokens.push(token);
+
+                    if token == TOKEN_REM {
+                        tokens.extend_from_slice(&text[index..]);
+                        break;
+                    } else if token == TOKEN_DATA {
+                        in_data = true;
+                    }
+                },
+                None => {
+                    tokens.push(byte.to_ascii_uppercase());
+                    index += 1;
+                },
+            }
+        }
+    }
+    tokens
+}
+
+// Keywords may be typed in lower case, the first matching one wins.
+fn match_keyword(text: &[u8]) -> Option<(u8, usize)> {
+    for (index, keyword) in KEYWORDS.iter().enumerate() {
+        let keyword = keyword.as_bytes();
+        if text.len() >= keyword.len() && text[..keyword.len()].eq_ignore_ascii_case(keyword) {
+            return Some(((index as u8) + 0x80, keyword.len()));
+        }
+    }
+    None
+}
+
+// Find the first BASIC program on a CAS tape, returns its name and the
+// tokenized program.
+pub fn program_from_cas(buffer: &[u8]) -> Option<(char, &[u8])> {
+    for recording in tape::scan_cas(buffer) {
+        if let tape::RecordingKind::Basic { name } = recording.kind {
+            // Skip the three header bytes and the name.
+            let start = (recording.data + 4).min(recording.end);
+            return Some((name, &buffer[start..recording.end]));
+        }
+    }
+    error!("There's no BASIC program on the tape.");
+    None
+}
+
+// Produce a CAS tape with the given program, as if it was saved by CSAVE.
+pub fn program_to_cas(name: char, program: &[u8]) -> Vec<u8> {
+    let mut buffer = vec![0x00; tape::LEADER_LEN];
+
+    buffer.push(tape::SYNC_BYTE);
+    buffer.extend_from_slice(&[0xD3, 0xD3, 0xD3]);
+    buffer.push(name as u8);
+    buffer.extend_from_slice(program);
+
+    buffer
+}
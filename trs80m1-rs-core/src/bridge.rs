@@ -0,0 +1,303 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error};
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::net;
+use std::path;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+use std::io::prelude::*;
+
+use crate::memory::PeripheralIO;
+
+
+// Port bridges, simple peripherals which connect an IO port of the machine
+// to something on the host, without a device of their own being emulated:
+//
+// A bridge for writing passes the bytes the machine writes to its port on to
+// a file, which they're appended to, or to a TCP connection.  A bridge for
+// reading gives the machine the next byte of a file, or the next byte that
+// came through a TCP connection, on each read of its port, and 0xFF when
+// there's nothing more to read, the same as a port with nothing on it.
+//
+// A port can have one bridge for reading and another one for writing, or a
+// single bridge doing both.  The ports of the devices which are emulated go
+// to them rather than to the bridges.
+//
+// Looking the host of a TCP connection up and connecting to it can take
+// seconds, which the machine can't wait for, so it's done in the background.
+// Until the connection is made, the bridge reads as 0xFF, and the bytes
+// written to it are kept for when it's there.
+//
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BridgeDirection {
+    Read,       // `<', the machine reads what comes from the host.
+    Write,      // `>', what the machine writes goes to the host.
+    Both,       // `<>'.
+}
+
+impl BridgeDirection {
+    fn reads(self) -> bool {
+        self != BridgeDirection::Write
+    }
+    fn writes(self) -> bool {
+        self != BridgeDirection::Read
+    }
+    // Whether two bridges on the same port would be in each other's way.
+    pub fn overlaps(self, other: BridgeDirection) -> bool {
+        (self.reads() && other.reads()) || (self.writes() && other.writes())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeTarget {
+    File(path::PathBuf),
+    Tcp(String),        // The `host:port' address.
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeSpec {
+    pub port:      u8,
+    pub direction: BridgeDirection,
+    pub target:    BridgeTarget,
+}
+
+impl fmt::Display for BridgeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = match self.direction {
+            BridgeDirection::Read  => { "<" },
+            BridgeDirection::Write => { ">" },
+            BridgeDirection::Both  => { "<>" },
+        };
+        match self.target {
+            BridgeTarget::File(ref file_path) => { write!(f, "{:02X}h {} {}", self.port, direction, file_path.display()) },
+            BridgeTarget::Tcp(ref address)    => { write!(f, "{:02X}h {} tcp:{}", self.port, direction, address) },
+        }
+    }
+}
+
+// How long to wait for a TCP connection to be accepted.
+const CONNECT_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+enum Channel {
+    File {
+        reader: Option<io::BufReader<fs::File>>,
+        writer: Option<fs::File>,
+    },
+    Connecting {
+        stream_rx: mpsc::Receiver<net::TcpStream>,
+        pending:   Vec<u8>,     // The bytes written in the meantime.
+    },
+    Tcp {
+        stream:  net::TcpStream,
+        pending: Vec<u8>,       // The bytes written while the host was busy.
+    },
+}
+
+struct Bridge {
+    spec:    BridgeSpec,
+    channel: Option<Channel>,   // None once it failed or got closed.
+}
+
+impl Bridge {
+    fn open(spec: &BridgeSpec) -> io::Result<Channel> {
+        match spec.target {
+            BridgeTarget::File(ref file_path) => {
+                let reader = if spec.direction.reads() {
+                    Some(io::BufReader::new(fs::File::open(file_path)?))
+                } else {
+                    None
+                };
+                let writer = if spec.direction.writes() {
+                    Some(fs::OpenOptions::new().create(true).append(true).open(file_path)?)
+                } else {
+                    None
+                };
+                Ok(Channel::File { reader, writer })
+            },
+            BridgeTarget::Tcp(ref address) => {
+                let (stream_tx, stream_rx) = mpsc::channel();
+                let spec = spec.clone();
+                let address = address.clone();
+                thread::Builder::new().name("port_bridge".to_owned()).spawn(move || {
+                    match Bridge::connect(&address) {
+                        Ok(stream) => {
+                            info!("Connected the port bridge `{}'.", spec);
+                            let _ = stream_tx.send(stream);
+                        },
+                        Err(error) => {
+                            error!("Failed to connect the port bridge `{}': {}.", spec, error);
+                        },
+                    }
+                })?;
+                Ok(Channel::Connecting { stream_rx, pending: Vec::new() })
+            },
+        }
+    }
+    fn connect(address: &str) -> io::Result<net::TcpStream> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "the address doesn't resolve to any host");
+        for socket_address in net::ToSocketAddrs::to_socket_addrs(address)? {
+            match net::TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    // The machine can't wait for the host, so the bytes are
+                    // only taken when they're already there.
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                },
+                Err(error) => {
+                    last_error = error;
+                },
+            }
+        }
+        Err(last_error)
+    }
+    // Take the connection once it's made, the failure to make it was already
+    // reported by the thread making it.
+    fn check_connection(&mut self) {
+        let channel = match self.channel {
+            Some(Channel::Connecting { ref stream_rx, ref mut pending }) => {
+                match stream_rx.try_recv() {
+                    Ok(stream) => { Some(Channel::Tcp { stream, pending: mem::take(pending) }) },
+                    Err(mpsc::TryRecvError::Empty) => { return; },
+                    Err(mpsc::TryRecvError::Disconnected) => { None },
+                }
+            },
+            _ => { return; },
+        };
+        self.channel = channel;
+    }
+    fn close(&mut self, reason: &str) {
+        self.channel = None;
+        info!("The port bridge `{}' was closed: {}.", self.spec, reason);
+    }
+    fn read_byte(&mut self) -> u8 {
+        self.check_connection();
+
+        let mut byte = [0xFF];
+        let result = match self.channel {
+            Some(Channel::File { reader: Some(ref mut reader), .. }) => { reader.read(&mut byte) },
+            Some(Channel::Tcp { ref mut stream, .. })               => { stream.read(&mut byte) },
+            _ => { return 0xFF; },
+        };
+        match result {
+            Ok(1) => { byte[0] },
+            Ok(..) => {
+                // The end of the file is kept, in case that more gets
+                // written to it, but a closed connection is gone for good.
+                if let Some(Channel::Tcp { .. }) = self.channel {
+                    self.close("the connection was closed by the host");
+                }
+                0xFF
+            },
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => { 0xFF },
+            Err(error) => {
+                self.close(&error.to_string());
+                0xFF
+            },
+        }
+    }
+    fn write_byte(&mut self, val: u8) {
+        self.check_connection();
+
+        let result = match self.channel {
+            Some(Channel::File { writer: Some(ref mut writer), .. }) => {
+                writer.write_all(&[val])
+            },
+            Some(Channel::Connecting { ref mut pending, .. }) => {
+                pending.push(val);
+                Ok(())
+            },
+            Some(Channel::Tcp { ref mut stream, ref mut pending }) => {
+                pending.push(val);
+                match stream.write(pending) {
+                    Ok(written) => { pending.drain(..written); Ok(()) },
+                    Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => { Ok(()) },
+                    Err(error) => { Err(error) },
+                }
+            },
+            _ => { return; },
+        };
+        if let Err(error) = result {
+            self.close(&error.to_string());
+        }
+    }
+}
+
+pub struct BridgeIO {
+    bridges: Vec<Bridge>,
+}
+
+impl Default for BridgeIO {
+    fn default() -> BridgeIO {
+        BridgeIO::new()
+    }
+}
+
+impl BridgeIO {
+    pub fn new() -> BridgeIO {
+        BridgeIO {
+            bridges: Vec::new(),
+        }
+    }
+    // Replace the installed bridges.  The ones which can't be opened are
+    // reported and left on their ports, reading as 0xFF.
+    pub fn install(&mut self, specs: &[BridgeSpec]) {
+        self.bridges.clear();
+
+        for spec in specs {
+            let channel = match Bridge::open(spec) {
+                Ok(channel) => {
+                    info!("Installed the port bridge `{}'.", spec);
+                    Some(channel)
+                },
+                Err(error) => {
+                    error!("Failed to open the port bridge `{}': {}.", spec, error);
+                    None
+                },
+            };
+            self.bridges.push(Bridge { spec: spec.clone(), channel });
+        }
+    }
+    // The offset of the given port, which is the port itself, if one of the
+    // bridges is on it.
+    pub fn port_offset(&self, port: u16) -> Option<u16> {
+        if self.bridges.iter().any(|bridge| (bridge.spec.port as u16) == port) {
+            Some(port)
+        } else {
+            None
+        }
+    }
+}
+
+impl PeripheralIO for BridgeIO {
+    fn peripheral_read_byte(&mut self, port: u16) -> u8 {
+        match self.bridges.iter_mut().find(|bridge| (bridge.spec.port as u16) == port && bridge.spec.direction.reads()) {
+            Some(bridge) => { bridge.read_byte() },
+            None         => { 0xFF },
+        }
+    }
+    fn peripheral_write_byte(&mut self, port: u16, val: u8) {
+        if let Some(bridge) = self.bridges.iter_mut().find(|bridge| (bridge.spec.port as u16) == port && bridge.spec.direction.writes()) {
+            bridge.write_byte(val);
+        }
+    }
+}
@@ -35,6 +35,7 @@ use std::io::Write;
 
 use crate::memory;
 use crate::machine;
+use crate::tape;
 use crate::util::Sink;
 
 
@@ -46,6 +47,19 @@ const DETECT_250: f32 = 1200.0;   // For level 1 input routine detection.
 pub enum Format {
     CAS,  // Recovered bit/byte stream.
     CPT,  // Cassette pulse train w/ exact machine.
+    WAV,  // Audio recording of a real tape.
+}
+
+impl Format {
+    // Guess the format of a cassette file from its extension.
+    pub fn from_path(path: &path::Path) -> Option<Format> {
+        match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()) {
+            Some(ref ext) if ext == "cas" => { Some(Format::CAS) },
+            Some(ref ext) if ext == "cpt" => { Some(Format::CPT) },
+            Some(ref ext) if ext == "wav" => { Some(Format::WAV) },
+            _ => { None },
+        }
+    }
 }
 
 pub enum CassetteEvent {
@@ -76,6 +90,76 @@ enum OutVal {
 
 const NOISE_FLOOR: i32 = 64;
 
+// WAV input decoding:
+//
+// The samples are converted to signed values (-128 to 127), a slowly moving
+// average of which is tracked to get rid of any DC offset, along with the
+// envelope of the signal.  A pulse starts when the signal swings more than
+// the threshold (by default half of the envelope) away from the average, and
+// ends once it gets back to within half of the threshold.  Swings smaller
+// than the noise floor are ignored.
+//
+// These parameters can be adjusted, as can the channel the samples are
+// taken from and the speed the recording is played back at, so that tapes
+// which are noisy, have a drifting DC offset or were recorded on a deck that
+// ran too fast or too slow can still be read.
+//
+const WAV_NOISE_FLOOR:     u32 = 8;
+const WAV_THRESHOLD:       u32 = 50;
+const WAV_AVG_WEIGHT:      f32 = 1.0 / 512.0;
+const WAV_ENV_DECAY:       f32 = 0.9995;
+const WAV_MAX_SILENCE_US:  f32 = 1_000_000.0;
+const WAV_EOF_DELAY_US:    f32 = 100_000.0;
+
+// WAV output generation:
+//
+// Recordings are turned into square pulses, the positive and negative output
+// levels are mapped onto the respective halves of the waveform, the rest is
+// silence.  New WAV files are created as 8-bit mono at 44.1 kHz, recording
+// into an existing one keeps its format.
+//
+const WAV_OUT_SAMPLE_RATE: u32 = 44_100;
+const WAV_OUT_AMPLITUDE:   f32 = 96.0;
+
+// The channel of a stereo recording the samples are taken from:
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum WavChannel {
+    Left,
+    Right,
+    Mix,   // Average of all channels.
+}
+
+// The adjustable parameters of the WAV input decoding:
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub struct WavDecoding {
+    pub threshold:    u32,   // In percent of the envelope.
+    pub noise_floor:  u32,   // In sample units (out of 128).
+    pub dc_filter:    bool,  // Whether the DC offset is compensated for.
+    pub channel:      WavChannel,
+    pub speed:        u32,   // Playback speed, in percent of the original.
+}
+
+impl Default for WavDecoding {
+    fn default() -> WavDecoding {
+        WavDecoding {
+            threshold:   WAV_THRESHOLD,
+            noise_floor: WAV_NOISE_FLOOR,
+            dc_filter:   true,
+            channel:     WavChannel::Left,
+            speed:       100,
+        }
+    }
+}
+
+// The location and layout of the sample data within a WAV file:
+struct WavInfo {
+    data_start:       usize,
+    data_end:         usize,
+    block_align:      usize,
+    channels:         usize,
+    bits_per_sample:  u16,
+}
+
 // Pulse shapes for conversion from .cas on input:
 struct PulseShape {
     delta_us: i32,
@@ -131,6 +215,11 @@ const   ST_250THRESH:  f32   = 2500.0; // us threshold between 0 and 1
 // Some of the constants are commented out, as the Model I doesn't have a
 // 1500 baud cassette port.
 
+// CAS recordings are lined up at the sync byte, once it shows up after at
+// least two bytes worth of the leader:
+const CAS_SYNC_MASK:    u32 = 0x00FF_FFFF;
+const CAS_SYNC_PATTERN: u32 = tape::SYNC_BYTE as u32;
+
 
 pub struct CassetteIO {
     read_flag:   bool,
@@ -189,6 +278,19 @@ pub struct CassetteRecorder {
     env:              f32,
     noise_floor:      i32,
     sample_rate:      i32,
+    wav_info:         Option<WavInfo>,
+    wav_decoding:     WavDecoding,
+
+    // For keeping a WAV copy of recordings made in other formats:
+    wav_copy_enabled: bool,
+    wav_copy:         Option<Vec<u8>>,
+    wav_copy_roundoff: f32,
+
+    // Set when the data is being handed to the ROM routines directly:
+    fast_load:        bool,
+
+    // The motor state forced by the user, regardless of the relay:
+    motor_override:   Option<bool>,
 
     // For bit-level emulation:
     cpu_delta:        u32,
@@ -205,6 +307,12 @@ pub struct CassetteRecorder {
     cas_speed:        Speed,
     cas_byte:         i32,
     cas_bit_num:      i32,
+
+    // For lining up CAS recordings with the sync byte:
+    cas_synced:       bool,
+    cas_shift:        u32,
+    cas_rec_start:    usize,
+    cas_tape_len:     usize,
 }
 
 impl CassetteRecorder {
@@ -223,6 +331,16 @@ impl CassetteRecorder {
             env:              0.0,
             noise_floor:      0,
             sample_rate:      0,
+            wav_info:         None,
+            wav_decoding:     WavDecoding::default(),
+
+            // For keeping a WAV copy of recordings made in other formats:
+            wav_copy_enabled: false,
+            wav_copy:         None,
+            wav_copy_roundoff: 0.0,
+
+            fast_load:        false,
+            motor_override:   None,
 
             // For bit-level emulation:
             cpu_delta:        0,
@@ -239,6 +357,12 @@ impl CassetteRecorder {
             cas_speed:        Speed::S500,
             cas_byte:         0,
             cas_bit_num:      0,
+
+            // For lining up CAS recordings with the sync byte:
+            cas_synced:       false,
+            cas_shift:        0,
+            cas_rec_start:    0,
+            cas_tape_len:     0,
         };
         recorder.set_cassette_file(cassette_file_path);
         info!("Created the cassette recorder.");
@@ -291,7 +415,7 @@ impl CassetteRecorder {
         else if io.write_flag {
 
             let latch_value = io.out_latch as i8;
-            self.update_motor(io.motor_req, event_sink);
+            self.update_motor(self.motor_override.unwrap_or(io.motor_req), event_sink);
 
             if self.motor {
                 if self.state == State::RecModeUncertain && latch_value != self.latch_lvl {
@@ -299,6 +423,15 @@ impl CassetteRecorder {
                     self.state = State::Recording;
                     event_sink.push(CassetteEvent::RecordingStarted);
                     info!("Started cassette recording.");
+                    if self.data_format == Format::WAV {
+                        self.start_wav_output();
+                    } else if self.data_format == Format::CAS {
+                        self.start_cas_output();
+                    }
+                    if self.data_format != Format::WAV && self.wav_copy_enabled {
+                        self.wav_copy = Some(Vec::new());
+                        self.wav_copy_roundoff = 0.0;
+                    }
                 }
                 match self.state {
                     State::Playback => {
@@ -317,7 +450,7 @@ impl CassetteRecorder {
             io.write_flag = false;
         }
 
-        if self.motor && (self.state == State::Playback || self.state == State::RecModeUncertain) {
+        if self.motor && !self.fast_load && (self.state == State::Playback || self.state == State::RecModeUncertain) {
 
             while self.cpu_delta >= self.in_trans_delta {
 
@@ -396,6 +529,7 @@ impl CassetteRecorder {
 
             self.io_buffer = buffer;
             self.cas_path  = path;
+            self.align_cas_buffer();
             success
         }
     }
@@ -405,6 +539,46 @@ impl CassetteRecorder {
             false
         } else {
             self.data_format = format;
+            self.align_cas_buffer();
+            true
+        }
+    }
+    // CAS tapes with recordings that aren't lined up with their sync bytes
+    // are fixed up as they're inserted.  WAV files are left alone, no matter
+    // what format they're claimed to be in, since their silence can look
+    // like a leader.
+    fn align_cas_buffer(&mut self) {
+        if self.data_format != Format::CAS {
+            return;
+        }
+        if let Some(ref mut buffer) = self.io_buffer {
+            if buffer.starts_with(b"RIFF") {
+                return;
+            }
+            if let Some(aligned) = tape::align_cas(buffer) {
+                *buffer = aligned;
+                if self.io_buffer_iter > buffer.len() {
+                    self.io_buffer_iter = buffer.len();
+                }
+                info!("The recordings on the cassette were shifted to line up with their sync bytes.");
+            }
+        }
+    }
+    pub fn set_wav_copy(&mut self, enabled: bool) -> bool {
+        if self.motor {
+            error!("Cassette drive motor currently running, refusing to change the WAV copy setting.");
+            false
+        } else {
+            self.wav_copy_enabled = enabled;
+            true
+        }
+    }
+    pub fn set_wav_decoding(&mut self, wav_decoding: WavDecoding) -> bool {
+        if self.motor {
+            error!("Cassette drive motor currently running, refusing to change the WAV decoding parameters.");
+            false
+        } else {
+            self.wav_decoding = wav_decoding;
             true
         }
     }
@@ -417,6 +591,89 @@ impl CassetteRecorder {
             true
         }
     }
+
+    // Fast loading, used by the ROM traps in the machine module.
+    //
+    // Instead of being turned into pulses, the contents of a CAS file are
+    // handed to the ROM routines directly.  Once the leader was skipped this
+    // way, the pulse generation is stopped until the motor is turned off.
+    //
+    pub fn fast_read_sync(&mut self) -> bool {
+        if !self.motor || self.state == State::Recording || self.data_format != Format::CAS {
+            return false;
+        }
+        if !self.fast_load {
+            // Undo whatever the pulse generation might have read already.
+            self.io_buffer_iter = self.iter_backup;
+            self.state = State::Playback;
+            self.fast_load = true;
+            info!("Started fast cassette playback.");
+        }
+
+        let buffer_len = match self.io_buffer {
+            Some(ref buffer) => { buffer.len() },
+            None => { 0 },
+        };
+        while self.io_buffer_iter < buffer_len {
+            if self.retrieve_byte() == 0xA5 {
+                return true;
+            }
+        }
+        false
+    }
+    pub fn fast_read_byte(&mut self) -> Option<u8> {
+        let buffer_len = match self.io_buffer {
+            Some(ref buffer) => { buffer.len() },
+            None => { 0 },
+        };
+        if self.motor && self.fast_load && self.io_buffer_iter < buffer_len {
+            Some(self.retrieve_byte())
+        } else {
+            None
+        }
+    }
+    // The tape counter, as the current position and the length of the
+    // cassette, in bytes.  None if the drive is empty.
+    pub fn counter(&self) -> Option<(usize, usize)> {
+        self.io_buffer.as_ref().map(|buffer| (self.io_buffer_iter, buffer.len()))
+    }
+    pub fn contents(&self) -> Option<&[u8]> {
+        self.io_buffer.as_deref()
+    }
+    // Force the motor on or off regardless of the state of the relay, or with
+    // None, leave it up to the relay again.
+    pub fn set_motor_override<ES: Sink<CassetteEvent>>(&mut self, io: &CassetteIO, motor_override: Option<bool>, event_sink: &mut ES) {
+        self.motor_override = motor_override;
+        self.update_motor(motor_override.unwrap_or(io.motor_req), event_sink);
+    }
+    // Decode the whole of a WAV cassette into CAS bytes, along with the
+    // position in the WAV file each of them starts at.
+    pub fn decode_wav(&self) -> Option<(Vec<u8>, Vec<usize>)> {
+        self.decode_wav_with_progress(&mut |_, _| true)
+    }
+    // The same, telling the progress as the bytes of the WAV file done and
+    // the total of them, which can stop the decoding by returning false.
+    pub fn decode_wav_with_progress(&self, progress: &mut dyn FnMut(usize, usize) -> bool) -> Option<(Vec<u8>, Vec<usize>)> {
+        match self.io_buffer {
+            Some(ref buffer) => { decode_wav_with_progress(buffer, &self.wav_decoding, progress) },
+            None => { None },
+        }
+    }
+    pub fn motor_override(&self) -> Option<bool> {
+        self.motor_override
+    }
+    pub fn motor_running(&self) -> bool {
+        self.motor
+    }
+    // The signal level on the cassette port, what's being played back from
+    // the tape while it's being read, otherwise what the machine outputs.
+    pub fn signal_level(&self, io: &CassetteIO) -> i8 {
+        if self.motor && !self.fast_load && (self.state == State::Playback || self.state == State::RecModeUncertain) {
+            self.latch_lvl
+        } else {
+            io.out_latch as i8
+        }
+    }
     pub fn erase_cassette(&mut self) -> bool {
         if self.motor {
             error!("Cassette drive motor currently running, refusing to erase the cassette.");
@@ -453,6 +710,7 @@ impl CassetteRecorder {
     }
     pub fn power_off<ES: Sink<CassetteEvent>>(&mut self, io: &mut CassetteIO, event_sink: &mut ES) {
 
+        self.motor_override = None;
         self.update_motor(false, event_sink);
 
         self.avg              = 0.0;
@@ -498,9 +756,13 @@ impl CassetteRecorder {
                     self.env = 127.0;
 
                     self.noise_floor = NOISE_FLOOR;
+                    if self.data_format == Format::WAV {
+                        self.start_wav_input();
+                    }
                     self.have_read_out_1 = false;
                     self.read_out_1_delta = 0;
                     self.transitions_out = 0;
+                    self.fast_load = false;
                     self.iter_backup = self.io_buffer_iter;
                     self.state = State::RecModeUncertain;
                     event_sink.push(CassetteEvent::MotorStarted(self.io_buffer_iter));
@@ -524,6 +786,12 @@ impl CassetteRecorder {
     fn recording_stop_cleanup(&mut self) {
 
         self.transition_out(OutVal::Flush, self.cpu_delta);
+        if self.data_format == Format::WAV {
+            self.finish_wav_output();
+        } else if self.data_format == Format::CAS {
+            self.finish_cas_output();
+        }
+        self.save_wav_copy();
 
         match self.io_buffer {
             Some(ref buffer) => {
@@ -555,6 +823,50 @@ impl CassetteRecorder {
             },
         }
     }
+    fn start_cas_output(&mut self) {
+        self.cas_synced    = false;
+        self.cas_shift     = 0;
+        self.cas_rec_start = self.io_buffer_iter;
+        self.cas_tape_len  = match self.io_buffer {
+            Some(ref buffer) => { buffer.len() },
+            None => { 0 },
+        };
+    }
+    // Replace whatever leader the machine wrote with one of the standard
+    // length, followed by the sync byte, so that the data that follows
+    // starts on a byte boundary.
+    fn write_cas_leader(&mut self) {
+        self.io_buffer_iter = self.cas_rec_start;
+        for _ in 0..tape::LEADER_LEN {
+            self.record_byte(0x00);
+        }
+        self.record_byte(tape::SYNC_BYTE);
+
+        self.cas_byte    = 0;
+        self.cas_bit_num = 0;
+        self.cas_synced  = true;
+    }
+    fn finish_cas_output(&mut self) {
+        let rec_end = self.io_buffer_iter;
+
+        if let Some(ref mut buffer) = self.io_buffer {
+
+            // Throw away what's left over from a leader that was longer than
+            // the standard one, unless it covers an older recording.
+            if buffer.len() > rec_end && buffer.len() > self.cas_tape_len {
+                buffer.truncate(rec_end.max(self.cas_tape_len));
+            }
+
+            let rec_start = self.cas_rec_start.min(buffer.len());
+            let rec_end = rec_end.min(buffer.len());
+            for recording in tape::scan_cas(&buffer[rec_start..rec_end]) {
+                info!("Recorded a {}.", recording.description());
+                for block in tape::bad_checksums(&buffer[rec_start..rec_end], &recording) {
+                    warn!("The block at offset {} of the recording has a bad checksum.", rec_start + block);
+                }
+            }
+        }
+    }
     fn record_byte(&mut self, to_write: u8) {
         match self.io_buffer {
             Some(ref mut buffer) => {
@@ -583,6 +895,190 @@ impl CassetteRecorder {
         self.io_buffer_iter += 1;
         retval
     }
+    fn start_wav_input(&mut self) {
+        self.avg = 0.0;
+        self.env = 0.0;
+        self.noise_floor = self.wav_decoding.noise_floor as i32;
+
+        self.wav_info = match self.io_buffer {
+            Some(ref buffer) if !buffer.is_empty() => {
+                match parse_wav_header(buffer) {
+                    Some((wav_info, sample_rate)) => {
+                        self.sample_rate = sample_rate as i32;
+                        Some(wav_info)
+                    },
+                    None => {
+                        error!("The cassette doesn't contain a WAV file in a supported format (uncompressed 8 or 16-bit PCM).");
+                        None
+                    },
+                }
+            },
+            _ => { None },
+        };
+
+        // Start off with the average at the first sample, so that any DC
+        // offset in the recording doesn't register as a pulse.
+        if self.wav_decoding.dc_filter {
+            let position = self.io_buffer_iter;
+            if let Some(sample) = self.retrieve_wav_sample() {
+                self.avg = sample;
+            }
+            self.io_buffer_iter = position;
+        }
+    }
+
+    // Retrieve the next sample from the selected channel of the WAV file, as
+    // a signed value between -128 and 127:
+    fn retrieve_wav_sample(&mut self) -> Option<f32> {
+        let (data_start, data_end, block_align) = match self.wav_info {
+            Some(ref info) => { (info.data_start, info.data_end, info.block_align) },
+            None => { return None; },
+        };
+
+        // Keep the position aligned to the sample frames, the offset might
+        // have been set by hand.
+        if self.io_buffer_iter < data_start {
+            self.io_buffer_iter = data_start;
+        }
+        let misalignment = (self.io_buffer_iter - data_start) % block_align;
+        if misalignment != 0 {
+            self.io_buffer_iter += block_align - misalignment;
+        }
+        if (self.io_buffer_iter + block_align) > data_end {
+            return None;
+        }
+
+        let position = self.io_buffer_iter;
+        self.io_buffer_iter += block_align;
+        match (&self.io_buffer, &self.wav_info) {
+            (Some(buffer), Some(info)) => { Some(wav_sample(buffer, info, position, self.wav_decoding.channel)) },
+            _ => { None },
+        }
+    }
+
+    // Prepare the cassette for recording WAV samples, an empty cassette gets
+    // a fresh header, an existing recording is appended to or overwritten in
+    // its own sample format.
+    fn start_wav_output(&mut self) {
+        self.wav_info = match self.io_buffer {
+            Some(ref mut buffer) if buffer.is_empty() => {
+                buffer.extend_from_slice(&wav_header(WAV_OUT_SAMPLE_RATE, 1, 8, 0));
+                self.sample_rate = WAV_OUT_SAMPLE_RATE as i32;
+                Some(WavInfo {
+                    data_start:       buffer.len(),
+                    data_end:         buffer.len(),
+                    block_align:      1,
+                    channels:         1,
+                    bits_per_sample:  8,
+                })
+            },
+            Some(ref buffer) => {
+                match parse_wav_header(buffer) {
+                    Some((wav_info, sample_rate)) => {
+                        self.sample_rate = sample_rate as i32;
+                        Some(wav_info)
+                    },
+                    None => {
+                        error!("The cassette doesn't contain a WAV file in a supported format, the recording will be lost.");
+                        None
+                    },
+                }
+            },
+            None => { None },
+        };
+
+        // Recording can't leave a gap after the existing samples.
+        if let Some(ref info) = self.wav_info {
+            self.io_buffer_iter = self.io_buffer_iter.clamp(info.data_start, info.data_end);
+            let misalignment = (self.io_buffer_iter - info.data_start) % info.block_align;
+            if misalignment != 0 {
+                self.io_buffer_iter += info.block_align - misalignment;
+            }
+        }
+    }
+
+    // Record the given number of sample frames, every channel gets the same
+    // value (between -128 and 127):
+    fn record_wav_samples(&mut self, value: f32, count: usize) {
+        let (block_align, bits_per_sample) = match self.wav_info {
+            Some(ref info) => { (info.block_align, info.bits_per_sample) },
+            None => { return; },
+        };
+        let frame = wav_sample_frame(value, block_align, bits_per_sample);
+
+        for _ in 0..count {
+            for byte in frame.iter() {
+                self.record_byte(*byte);
+            }
+        }
+    }
+
+    // Fix up the chunk sizes in the header once the recording is done:
+    fn finish_wav_output(&mut self) {
+        let (data_start, data_end) = match self.wav_info {
+            Some(ref info) => { (info.data_start, info.data_end) },
+            None => { return; },
+        };
+        if self.io_buffer_iter <= data_end {
+            // Recorded over existing samples, the layout stays the same.
+            return;
+        }
+        let data_end = self.io_buffer_iter;
+
+        if let Some(ref mut buffer) = self.io_buffer {
+            // Any chunks following the sample data were overwritten by the
+            // recording.
+            buffer.truncate(data_end);
+            if ((data_end - data_start) & 1) != 0 {
+                buffer.push(0);
+            }
+            let data_size = (data_end - data_start) as u32;
+            let riff_size = (buffer.len() - 8) as u32;
+            buffer[(data_start - 4)..data_start].copy_from_slice(&data_size.to_le_bytes());
+            buffer[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        }
+        if let Some(ref mut info) = self.wav_info {
+            info.data_end = data_end;
+        }
+    }
+
+    // Write the WAV copy of the recording next to the cassette file, as
+    // `<cassette file>.wav':
+    fn save_wav_copy(&mut self) {
+        let samples = match self.wav_copy.take() {
+            Some(samples) => { samples },
+            None => { return; },
+        };
+        let path = match self.cas_path {
+            Some(ref path) => {
+                let mut name = path.clone().into_os_string();
+                name.push(".wav");
+                path::PathBuf::from(name)
+            },
+            None => { return; },
+        };
+
+        let mut wav_data = wav_header(WAV_OUT_SAMPLE_RATE, 1, 8, samples.len());
+        wav_data.extend_from_slice(&samples);
+        if (samples.len() & 1) != 0 {
+            wav_data.push(0);
+        }
+        match fs::File::create(&path) {
+            Ok(mut file) => {
+                match file.write_all(&wav_data) {
+                    Ok(()) => {
+                        info!("Saved a WAV copy of the recording into `{}'.", path.display())
+                    },
+                    Err(error) => {
+                        error!("Failed to write the WAV copy of the recording into `{}': {}.", path.display(), error)
+                    },
+                }
+            },
+            Err(error) => {
+                error!("Failed to write the WAV copy of the recording into `{}': Couldn't open `{}' for writing: {}.", path.display(), path.display(), error)
+            },
+        }
+    }
 
     // Record an output transition.
     //
@@ -605,6 +1101,13 @@ impl CassetteRecorder {
         }
         let ddelta_us: f32 = (delta as f32) / CPU_MHZ - self.roundoff_error;
 
+        // The time since the last transition was spent at the previous level:
+        if let Some(ref mut samples) = self.wav_copy {
+            let count = wav_sample_count((delta as f32) / CPU_MHZ, WAV_OUT_SAMPLE_RATE, &mut self.wav_copy_roundoff);
+            let value = ((wav_out_value(self.latch_lvl) + 128.0) as i32).clamp(0, 255) as u8;
+            samples.resize(samples.len() + count, value);
+        }
+
         match self.data_format {
             Format::CAS => {
                 if flush && (self.cas_bit_num != 0) {
@@ -681,6 +1184,13 @@ impl CassetteRecorder {
                     },
                     _ => { () },
                 }
+                if sample != 2 && !self.cas_synced {
+                    self.cas_shift = (self.cas_shift << 1) | (sample as u32);
+                    if (self.cas_shift & CAS_SYNC_MASK) == CAS_SYNC_PATTERN {
+                        self.write_cas_leader();
+                        sample = 2;
+                    }
+                }
                 if sample != 2 {
                     self.cas_bit_num -= 1;
                     if self.cas_bit_num < 0 {
@@ -725,6 +1235,11 @@ impl CassetteRecorder {
                     self.record_byte(((delta_us >> 24) & 0xFF) as u8);
                 }
             },
+            Format::WAV => {
+                let sample_rate = if self.sample_rate > 0 { self.sample_rate as u32 } else { WAV_OUT_SAMPLE_RATE };
+                let count = wav_sample_count((delta as f32) / CPU_MHZ, sample_rate, &mut self.roundoff_error);
+                self.record_wav_samples(wav_out_value(self.latch_lvl), count);
+            },
         };
 
         self.latch_lvl = out_lvl;
@@ -830,6 +1345,382 @@ impl CassetteRecorder {
                 self.in_trans_delta = (delta_ts + 0.5) as u32;
                 self.roundoff_error = (self.in_trans_delta as f32) - delta_ts;
             },
+            Format::WAV => {
+                let sample_us = if self.sample_rate > 0 { wav_sample_us(self.sample_rate as u32, &self.wav_decoding) } else { 0.0 };
+                let mut delta_us: f32 = 0.0;
+                let mut level = self.next_in_lvl;
+
+                loop {
+                    let sample = match self.retrieve_wav_sample() {
+                        Some(sample) => { sample },
+                        None => {
+                            // End of the tape, just keep the motor running.
+                            level = 0;
+                            delta_us += WAV_EOF_DELAY_US;
+                            break;
+                        },
+                    };
+                    delta_us += sample_us;
+
+                    let new_level = wav_level(&self.wav_decoding, &mut self.avg, &mut self.env, sample, level);
+                    if new_level != level {
+                        level = new_level;
+                        break;
+                    }
+                    if delta_us >= WAV_MAX_SILENCE_US {
+                        break;
+                    }
+                }
+                self.next_in_lvl = level;
+
+                let delta_ts: f32 = delta_us * CPU_MHZ - self.roundoff_error;
+                self.in_trans_delta = (delta_ts + 0.5) as u32;
+                self.roundoff_error = (self.in_trans_delta as f32) - delta_ts;
+            },
+        }
+    }
+}
+
+// Track the average and envelope of the signal with a new sample, and work
+// out the input level it corresponds to.
+fn wav_level(settings: &WavDecoding, avg: &mut f32, env: &mut f32, sample: f32, level: i8) -> i8 {
+    let deviation = sample - *avg;
+    if settings.dc_filter {
+        *avg += deviation * WAV_AVG_WEIGHT;
+    }
+    if deviation.abs() > *env {
+        *env = deviation.abs();
+    } else {
+        *env *= WAV_ENV_DECAY;
+    }
+
+    let threshold = (*env * (settings.threshold as f32) / 100.0).max(settings.noise_floor as f32);
+    if deviation > threshold {
+        1
+    } else if deviation < -threshold {
+        2
+    } else if deviation.abs() < (threshold / 2.0) {
+        0
+    } else {
+        level
+    }
+}
+
+// The time a single sample takes up, adjusted for the playback speed:
+fn wav_sample_us(sample_rate: u32, settings: &WavDecoding) -> f32 {
+    let speed = settings.speed.max(1) as f32;
+    (1_000_000.0 / (sample_rate as f32)) * 100.0 / speed
+}
+
+// Read the sample frame at the given position, as a signed value between
+// -128 and 127.  If the selected channel doesn't exist, the first one is
+// used instead.
+fn wav_sample(buffer: &[u8], info: &WavInfo, position: usize, channel: WavChannel) -> f32 {
+    let sample_size = (info.bits_per_sample / 8) as usize;
+    let read_channel = |index: usize| {
+        let offset = position + index * sample_size;
+        if info.bits_per_sample == 8 {
+            (buffer[offset] as f32) - 128.0
+        } else {
+            let value = ((buffer[offset + 1] as u16) << 8) | (buffer[offset] as u16);
+            ((value as i16) as f32) / 256.0
+        }
+    };
+    match channel {
+        WavChannel::Left => { read_channel(0) },
+        WavChannel::Right => { read_channel(if info.channels > 1 { 1 } else { 0 }) },
+        WavChannel::Mix => {
+            (0..info.channels).map(read_channel).sum::<f32>() / (info.channels as f32)
+        },
+    }
+}
+
+// Offline decoding of 250 and 500 baud WAV recordings into CAS bytes:
+//
+// Each bit cell starts with a clock pulse, a one has another pulse in the
+// middle of it.  The length of a bit cell is estimated from the spacing of
+// the clock pulses, which lets the decoder follow both speeds and recordings
+// that are off-speed or wobbly.  Pulses closer to the clock than three
+// quarters of a bit cell count as data pulses, and a gap of several bit
+// cells ends a recording.
+//
+const DECODE_CELL_US:      f32 = 2_000.0;
+const DECODE_CELL_WEIGHT:  f32 = 0.1;
+const DECODE_PULSE_MERGE:  f32 = 0.25;
+const DECODE_DATA_LIMIT:   f32 = 0.75;
+const DECODE_GAP:          f32 = 2.5;
+
+// The progress of decoding a whole file is told every this many samples.
+const DECODE_PROGRESS_SAMPLES: usize = 65_536;
+
+struct PulseDecoder {
+    cell_us:          f32,
+    last_pulse_us:    Option<f32>,
+    last_clock:       Option<(f32, usize)>,
+    data_pulse:       bool,
+
+    synced:           bool,
+    shift:            u32,
+    leader_position:  usize,
+    byte:             u8,
+    bit_num:          u32,
+    byte_position:    usize,
+
+    data:             Vec<u8>,
+    positions:        Vec<usize>,
+}
+
+impl PulseDecoder {
+    fn new() -> PulseDecoder {
+        PulseDecoder {
+            cell_us:         DECODE_CELL_US,
+            last_pulse_us:   None,
+            last_clock:      None,
+            data_pulse:      false,
+            synced:          false,
+            shift:           0,
+            leader_position: 0,
+            byte:            0,
+            bit_num:         0,
+            byte_position:   0,
+            data:            Vec::new(),
+            positions:       Vec::new(),
+        }
+    }
+    fn pulse(&mut self, time_us: f32, position: usize) {
+        if let Some(last_pulse_us) = self.last_pulse_us {
+            if (time_us - last_pulse_us) < (self.cell_us * DECODE_PULSE_MERGE) {
+                // The other half of the same pulse.
+                return;
+            }
+        }
+        self.last_pulse_us = Some(time_us);
+
+        let (clock_us, clock_position) = match self.last_clock {
+            Some(clock) => { clock },
+            None => {
+                self.start_recording(time_us, position);
+                return;
+            },
+        };
+        let interval = time_us - clock_us;
+        if interval > (self.cell_us * DECODE_GAP) {
+            self.start_recording(time_us, position);
+        } else if interval < (self.cell_us * DECODE_DATA_LIMIT) {
+            self.data_pulse = true;
+        } else {
+            self.cell_us += (interval - self.cell_us) * DECODE_CELL_WEIGHT;
+            let bit = self.data_pulse;
+            self.bit(bit, clock_position);
+            self.last_clock = Some((time_us, position));
+            self.data_pulse = false;
+        }
+    }
+    fn start_recording(&mut self, time_us: f32, position: usize) {
+        self.end_recording();
+        self.last_clock = Some((time_us, position));
+        self.data_pulse = false;
+        self.synced = false;
+        self.shift = 0;
+        self.leader_position = position;
+    }
+    // The last bit cell of a recording isn't followed by another clock pulse.
+    fn end_recording(&mut self) {
+        if let Some((_, clock_position)) = self.last_clock {
+            if self.synced {
+                let bit = self.data_pulse;
+                self.bit(bit, clock_position);
+            }
         }
     }
+    fn bit(&mut self, bit: bool, position: usize) {
+        if !self.synced {
+            self.shift = (self.shift << 1) | (bit as u32);
+            if bit && (self.shift & CAS_SYNC_MASK) == 1 {
+                // A one after a run of zeros, this is where the sync byte
+                // starts.
+                self.byte_position = position;
+            }
+            if (self.shift & CAS_SYNC_MASK) == CAS_SYNC_PATTERN {
+                self.data.extend_from_slice(&[0x00; tape::LEADER_LEN]);
+                self.positions.extend_from_slice(&[self.leader_position; tape::LEADER_LEN]);
+                self.data.push(tape::SYNC_BYTE);
+                self.positions.push(self.byte_position);
+                self.synced = true;
+                self.bit_num = 0;
+            }
+        } else {
+            if self.bit_num == 0 {
+                self.byte = 0;
+                self.byte_position = position;
+            }
+            self.byte = (self.byte << 1) | (bit as u8);
+            self.bit_num += 1;
+            if self.bit_num == 8 {
+                self.data.push(self.byte);
+                self.positions.push(self.byte_position);
+                self.bit_num = 0;
+            }
+        }
+    }
+}
+
+// Decode a WAV file into CAS bytes, returns them along with the position in
+// the WAV file each of them starts at.
+pub fn decode_wav(buffer: &[u8], settings: &WavDecoding) -> Option<(Vec<u8>, Vec<usize>)> {
+    decode_wav_with_progress(buffer, settings, &mut |_, _| true)
+}
+
+// The same, with the progress told to the given function as the bytes of the
+// samples done and the total of them, which can stop it by returning false.
+pub fn decode_wav_with_progress(buffer: &[u8], settings: &WavDecoding, progress: &mut dyn FnMut(usize, usize) -> bool) -> Option<(Vec<u8>, Vec<usize>)> {
+    let (info, sample_rate) = match parse_wav_header(buffer) {
+        Some(header) => { header },
+        None => {
+            error!("The cassette doesn't contain a WAV file in a supported format (uncompressed 8 or 16-bit PCM).");
+            return None;
+        },
+    };
+    let sample_us = wav_sample_us(sample_rate, settings);
+    let mut decoder = PulseDecoder::new();
+    let mut avg = 0.0;
+    let mut env = 0.0;
+    let mut level = 0;
+    let mut time_us = 0.0;
+
+    if settings.dc_filter && (info.data_start + info.block_align) <= info.data_end {
+        avg = wav_sample(buffer, &info, info.data_start, settings.channel);
+    }
+    let mut position = info.data_start;
+    let mut samples = 0;
+    while (position + info.block_align) <= info.data_end {
+        samples += 1;
+        if samples % DECODE_PROGRESS_SAMPLES == 0 && !progress(position - info.data_start, info.data_end - info.data_start) {
+            return None;
+        }
+        let sample = wav_sample(buffer, &info, position, settings.channel);
+        let new_level = wav_level(settings, &mut avg, &mut env, sample, level);
+        if level == 0 && new_level != 0 {
+            decoder.pulse(time_us, position);
+        }
+        level = new_level;
+        position += info.block_align;
+        time_us += sample_us;
+    }
+    decoder.end_recording();
+    Some((decoder.data, decoder.positions))
+}
+
+// The sample value (between -128 and 127) for an output level:
+fn wav_out_value(level: i8) -> f32 {
+    match level {
+        1 => {  WAV_OUT_AMPLITUDE },
+        2 => { -WAV_OUT_AMPLITUDE },
+        _ => { 0.0 },
+    }
+}
+
+// The number of samples covering delta_us microseconds, roundoff is the time
+// by which the previously generated samples overshot their span.
+fn wav_sample_count(delta_us: f32, sample_rate: u32, roundoff: &mut f32) -> usize {
+    let span_us = delta_us - *roundoff;
+    let count = if span_us > 0.0 { ((span_us * (sample_rate as f32)) / 1_000_000.0 + 0.5) as usize } else { 0 };
+    *roundoff = ((count as f32) * 1_000_000.0) / (sample_rate as f32) - span_us;
+    count
+}
+
+// A single sample frame in the given layout, with all channels set to value:
+fn wav_sample_frame(value: f32, block_align: usize, bits_per_sample: u16) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(block_align);
+
+    if bits_per_sample == 8 {
+        let sample = ((value + 128.0) as i32).clamp(0, 255) as u8;
+        frame.resize(block_align, sample);
+    } else {
+        let sample = ((value * 256.0) as i32).clamp(-32768, 32767) as i16;
+        while (frame.len() + 2) <= block_align {
+            frame.extend_from_slice(&sample.to_le_bytes());
+        }
+        frame.resize(block_align, 0);
+    }
+    frame
+}
+
+// A canonical 44 byte PCM WAV header for data_size bytes of sample data:
+pub(crate) fn wav_header(sample_rate: u32, channels: u16, bits_per_sample: u16, data_size: usize) -> Vec<u8> {
+    let block_align = channels * (bits_per_sample / 8);
+    let mut header = Vec::with_capacity(44);
+
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&((36 + data_size + (data_size & 1)) as u32).to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * (block_align as u32)).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&bits_per_sample.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&(data_size as u32).to_le_bytes());
+    header
+}
+
+fn read_u16_le(buffer: &[u8], position: usize) -> u16 {
+    ((buffer[position + 1] as u16) << 8) | (buffer[position] as u16)
+}
+
+fn read_u32_le(buffer: &[u8], position: usize) -> u32 {
+    ((read_u16_le(buffer, position + 2) as u32) << 16) | (read_u16_le(buffer, position) as u32)
+}
+
+// Walk the chunks of a RIFF WAVE file, looking for the format description
+// and the sample data.  Returns the layout and the sample rate.
+fn parse_wav_header(buffer: &[u8]) -> Option<(WavInfo, u32)> {
+    if buffer.len() < 12 || &buffer[0..4] != b"RIFF" || &buffer[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut position = 12;
+    let mut format: Option<(u16, u32, u16, u16)> = None;
+
+    while (position + 8) <= buffer.len() {
+        let chunk_id = &buffer[position..(position + 4)];
+        let chunk_size = read_u32_le(buffer, position + 4) as usize;
+        let chunk_start = position + 8;
+
+        if chunk_id == b"fmt " {
+            if chunk_size < 16 || (chunk_start + 16) > buffer.len() {
+                return None;
+            }
+            let audio_format    = read_u16_le(buffer, chunk_start);
+            let channels        = read_u16_le(buffer, chunk_start + 2);
+            let sample_rate     = read_u32_le(buffer, chunk_start + 4);
+            let block_align     = read_u16_le(buffer, chunk_start + 12);
+            let bits_per_sample = read_u16_le(buffer, chunk_start + 14);
+
+            if audio_format != 1 || channels == 0 || sample_rate == 0 ||
+               (bits_per_sample != 8 && bits_per_sample != 16) ||
+               (block_align as u32) < (channels as u32) * ((bits_per_sample as u32) / 8) {
+                return None;
+            }
+            format = Some((channels, sample_rate, block_align, bits_per_sample));
+        } else if chunk_id == b"data" {
+            let (channels, sample_rate, block_align, bits_per_sample) = match format {
+                Some(format) => { format },
+                None => { return None; },
+            };
+            return Some((WavInfo {
+                data_start:       chunk_start,
+                data_end:         buffer.len().min(chunk_start + chunk_size),
+                block_align:      block_align as usize,
+                channels:         channels as usize,
+                bits_per_sample,
+            }, sample_rate));
+        }
+
+        // Chunks are padded to an even size.
+        position = chunk_start + chunk_size + (chunk_size & 1);
+    }
+    None
 }
@@ -0,0 +1,93 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::memory;
+use crate::memory::MemIO;
+
+
+// The /CMD files, in which TRSDOS and LDOS keep the machine language
+// programs, made up of records:
+//
+//     0x01 - a load block: a length byte, the load address (little-endian)
+//            and the data.  The length counts the address too, and the
+//            lengths of 0, 1 and 2 stand for 256, 257 and 258.
+//     0x02 - the transfer address: a length byte (2) and the entry point,
+//            which ends the file.
+//
+// The rest of the records, like the module name (0x05) or the copyright
+// notice (0x1F), have a length byte too, and are skipped.
+//
+
+const LOAD_BLOCK:       u8 = 0x01;
+const TRANSFER_ADDRESS: u8 = 0x02;
+
+pub struct CmdFile {
+    pub blocks:      Vec<(u16, Vec<u8>)>,    // The load addresses and the data.
+    pub entry_point: Option<u16>,
+}
+
+impl CmdFile {
+    // Parse the records, None if the file doesn't look like a /CMD file.
+    pub fn parse(bytes: &[u8]) -> Option<CmdFile> {
+        let mut blocks = Vec::new();
+        let mut entry_point = None;
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            let record_type = bytes[pos];
+            let length = *bytes.get(pos + 1)? as usize;
+            pos += 2;
+
+            match record_type {
+                LOAD_BLOCK => {
+                    let length = if length <= 2 { length + 256 } else { length };
+                    let record = bytes.get(pos..(pos + length))?;
+                    let address = u16::from_le_bytes([record[0], record[1]]);
+                    blocks.push((address, record[2..].to_vec()));
+                    pos += length;
+                },
+                TRANSFER_ADDRESS => {
+                    let record = bytes.get(pos..(pos + 2))?;
+                    entry_point = Some(u16::from_le_bytes([record[0], record[1]]));
+                    break;
+                },
+                _ => {
+                    bytes.get(pos..(pos + length))?;
+                    pos += length;
+                },
+            }
+        }
+
+        if blocks.is_empty() {
+            None
+        } else {
+            Some(CmdFile { blocks, entry_point })
+        }
+    }
+    // Write the load blocks into the memory, like the loader of the DOS.
+    pub fn load(&self, memory_system: &mut memory::MemorySystem) {
+        for (address, data) in &self.blocks {
+            for (offset, byte) in data.iter().enumerate() {
+                memory_system.write_byte(address.wrapping_add(offset as u16), *byte);
+            }
+        }
+    }
+    // The lowest and the highest address written.
+    pub fn address_range(&self) -> (u16, u16) {
+        let start = self.blocks.iter().map(|(address, _)| *address).min().unwrap_or(0);
+        let end = self.blocks.iter().map(|(address, data)| address.wrapping_add(data.len().saturating_sub(1) as u16)).max().unwrap_or(0);
+        (start, end)
+    }
+}
@@ -0,0 +1,755 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, warn, error};
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path;
+
+
+// Disk image handling.
+//
+// The emulator doesn't emulate the Expansion Interface and its floppy disk
+// controller yet, so disk images can't be used by the emulated machine, but
+// their contents can still be examined (and modified) from the outside.
+//
+// The supported image formats are the ones used by Jeff Vavasour's emulators,
+// which were later adopted by xtrs and most other TRS-80 emulators:
+//
+//     JV1 - A plain sequence of 256 byte sectors, 10 sectors per track,
+//           single sided, single density.
+//
+//     JV3 - A table of sector headers (track, sector, flags) followed by the
+//           sector data, supporting mixed densities, sizes and two sides.
+//
+// Additionally, David Keil's DMK format is supported, which stores the raw
+// contents of each track, including the ID fields and CRCs.  The sectors are
+// found by walking the table of ID address mark pointers at the start of each
+// track.  Single density data is usually stored with every byte doubled.
+//
+// ImageDisk (IMD) images, common in preservation archives, can be read too.
+// They're converted into a plain sequence of sectors when loaded, and since
+// they can't be written back, their changes can only be kept in an overlay.
+//
+// An image can be put into copy-on-write mode, in which case its changes are
+// saved into a sidecar overlay file (the image's name with `.cow' appended)
+// instead of the image itself, leaving the original untouched until the
+// changes are committed.  The overlay is picked up automatically whenever the
+// image is loaded.  It starts with the magic bytes `TRSCOW', followed by the
+// size of the original image (32-bit, little endian), and a list of changed
+// byte runs, each consisting of the offset and length (both 32-bit, little
+// endian) followed by the new contents.
+//
+
+pub const SECTOR_SIZE:          usize = 256;
+
+const JV1_SECTORS_PER_TRACK:    usize = 10;
+const JV1_TRACK_SIZE:           usize = JV1_SECTORS_PER_TRACK * SECTOR_SIZE;
+
+const JV3_SECTORS_PER_HEADER:   usize = 2901;
+const JV3_HEADER_SIZE:          usize = JV3_SECTORS_PER_HEADER * 3 + 1;
+const JV3_FREE:                 u8    = 0xFF;
+const JV3_FLAG_SIDE:            u8    = 0x10;
+const JV3_FLAG_SIZE:            u8    = 0x03;
+const JV3_WRITE_PROTECT:        usize = JV3_SECTORS_PER_HEADER * 3;
+
+const DMK_HEADER_SIZE:          usize = 16;
+const DMK_IDAM_TABLE_SIZE:      usize = 128;
+const DMK_OPT_SINGLE_SIDED:     u8    = 0x10;
+const DMK_OPT_SINGLE_DENSITY:   u8    = 0x40;
+const DMK_OPT_IGNORE_DENSITY:   u8    = 0x80;
+const DMK_IDAM_DOUBLE_DENSITY:  u16   = 0x8000;
+const DMK_IDAM_OFFSET:          u16   = 0x3FFF;
+const DMK_IDAM:                 u8    = 0xFE;
+const DMK_SD_DAM_SEARCH:        usize = 30;
+const DMK_DD_DAM_SEARCH:        usize = 43;
+
+const IMD_SIGNATURE:            &[u8] = b"IMD ";
+const IMD_COMMENT_END:          u8    = 0x1A;
+const IMD_MAX_MODE:             u8    = 5;
+const IMD_HEAD_CYLINDER_MAP:    u8    = 0x80;
+const IMD_HEAD_HEAD_MAP:        u8    = 0x40;
+const IMD_SIZE_TABLE:           u8    = 0xFF;
+
+const COW_MAGIC:                &[u8] = b"TRSCOW";
+const COW_EXTENSION:            &str  = "cow";
+
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    JV1,
+    JV3,
+    DMK,
+    IMD,
+}
+
+// Where a given sector's data is located within the image file:
+struct SectorLocation {
+    track:   u8,
+    side:    u8,
+    sector:  u8,
+    offset:  usize,
+    size:    usize,
+
+    // Distance between two consecutive bytes of the sector, 2 for the doubled
+    // single density sectors in DMK images, 1 everywhere else.
+    stride:  usize,
+
+    // DMK images also store the data address mark and the CRC of the data
+    // field, which has to be updated whenever the sector is written to.
+    dmk_dam: Option<DmkDataMark>,
+}
+
+struct DmkDataMark {
+    offset:          usize,
+    double_density:  bool,
+}
+
+pub struct DiskImage {
+    format:           Format,
+    image_path:       path::PathBuf,
+    data:             Vec<u8>,
+    sectors:          Vec<SectorLocation>,
+    write_protected:  bool,
+
+    // Contents of the image file itself, kept around in copy-on-write mode
+    // so that the overlay can be generated:
+    original_data:    Option<Vec<u8>>,
+}
+
+impl DiskImage {
+    pub fn load_from_file<P: Into<path::PathBuf>>(image_path: P) -> Option<DiskImage> {
+        let path = image_path.into();
+
+        let file_data = match fs::File::open(&path) {
+            Ok(mut file) => {
+                let mut buffer = Vec::new();
+                match file.read_to_end(&mut buffer) {
+                    Ok(_) => { buffer },
+                    Err(error) => {
+                        error!("Failed to load `{}' into memory: {}.", path.display(), error);
+                        return None;
+                    },
+                }
+            },
+            Err(error) => {
+                error!("Couldn't open `{}' for reading: {}.", path.display(), error);
+                return None;
+            },
+        };
+
+        let (format, data, sectors, write_protected) = if file_data.starts_with(IMD_SIGNATURE) {
+            match convert_imd_image(&file_data) {
+                Some((data, sectors)) => { (Format::IMD, data, sectors, false) },
+                None => {
+                    error!("The ImageDisk file `{}' is corrupted.", path.display());
+                    return None;
+                },
+            }
+        } else if let Some(sectors) = parse_jv3_layout(&file_data) {
+            let write_protected = file_data[JV3_WRITE_PROTECT] == 0x00;
+            (Format::JV3, file_data, sectors, write_protected)
+        } else if let Some(sectors) = parse_dmk_layout(&file_data) {
+            let write_protected = file_data[0] == 0xFF;
+            (Format::DMK, file_data, sectors, write_protected)
+        } else if let Some(sectors) = parse_jv1_layout(&file_data) {
+            (Format::JV1, file_data, sectors, false)
+        } else {
+            error!("The file `{}' doesn't appear to be a disk image in a supported format.", path.display());
+            return None;
+        };
+        info!("The disk image `{}' ({:?}) was loaded into memory.", path.display(), format);
+
+        let mut image = DiskImage {
+            format,
+            image_path: path,
+            data,
+            sectors,
+            write_protected,
+            original_data: None,
+        };
+        if image.overlay_path().exists() {
+            if !image.apply_overlay() {
+                return None;
+            }
+            info!("Pending changes from `{}' were applied, the image is in copy-on-write mode.", image.overlay_path().display());
+        }
+        Some(image)
+    }
+    pub fn overlay_path(&self) -> path::PathBuf {
+        let mut overlay_name = self.image_path.clone().into_os_string();
+        overlay_name.push(".");
+        overlay_name.push(COW_EXTENSION);
+        path::PathBuf::from(overlay_name)
+    }
+    pub fn copy_on_write(&self) -> bool {
+        self.original_data.is_some()
+    }
+    pub fn set_copy_on_write(&mut self, enabled: bool) {
+        if enabled && self.original_data.is_none() {
+            self.original_data = Some(self.data.clone());
+        } else if !enabled && self.original_data.is_some() {
+            if self.overlay_path().exists() {
+                warn!("`{}' has uncommitted changes, staying in copy-on-write mode.", self.image_path.display());
+            } else {
+                self.original_data = None;
+            }
+        }
+    }
+
+    // Writes the in-memory copy of the image out, either into the image file
+    // itself, or into the overlay in copy-on-write mode.
+    pub fn save(&self) -> bool {
+        if self.write_protected {
+            error!("The disk image `{}' is write protected.", self.image_path.display());
+            return false;
+        }
+        match self.original_data {
+            Some(ref original_data) => {
+                let overlay_path = self.overlay_path();
+                if write_file(&overlay_path, &generate_overlay(original_data, &self.data)) {
+                    info!("The changes to `{}' were written into `{}'.", self.image_path.display(), overlay_path.display());
+                    true
+                } else {
+                    false
+                }
+            },
+            None => {
+                if self.format == Format::IMD {
+                    error!("ImageDisk files like `{}' can't be written to, use the copy-on-write mode.", self.image_path.display());
+                    return false;
+                }
+                if write_file(&self.image_path, &self.data) {
+                    info!("The disk image `{}' was written out.", self.image_path.display());
+                    true
+                } else {
+                    false
+                }
+            },
+        }
+    }
+
+    // Writes the changes from the overlay into the image file itself.
+    pub fn commit_overlay(&mut self) -> bool {
+        if self.original_data.is_none() {
+            error!("The disk image `{}' isn't in copy-on-write mode.", self.image_path.display());
+            return false;
+        }
+        if self.write_protected {
+            error!("The disk image `{}' is write protected.", self.image_path.display());
+            return false;
+        }
+        if self.format == Format::IMD {
+            error!("ImageDisk files like `{}' can't be written to, the changes can only be kept in `{}'.", self.image_path.display(), self.overlay_path().display());
+            return false;
+        }
+        if !write_file(&self.image_path, &self.data) {
+            return false;
+        }
+        if !remove_overlay(&self.overlay_path()) {
+            return false;
+        }
+        self.original_data = Some(self.data.clone());
+        info!("The changes to `{}' were committed.", self.image_path.display());
+        true
+    }
+
+    // Throws the changes in the overlay away, reverting to the image file.
+    pub fn discard_overlay(&mut self) -> bool {
+        let original_data = match self.original_data {
+            Some(ref original_data) => { original_data.clone() },
+            None => {
+                error!("The disk image `{}' isn't in copy-on-write mode.", self.image_path.display());
+                return false;
+            },
+        };
+        if !remove_overlay(&self.overlay_path()) {
+            return false;
+        }
+        self.data = original_data;
+        info!("The changes to `{}' were discarded.", self.image_path.display());
+        true
+    }
+    fn apply_overlay(&mut self) -> bool {
+        let overlay_path = self.overlay_path();
+        let overlay = match fs::read(&overlay_path) {
+            Ok(overlay) => { overlay },
+            Err(error) => {
+                error!("Failed to read the overlay `{}': {}.", overlay_path.display(), error);
+                return false;
+            },
+        };
+        let mut data = self.data.clone();
+
+        if overlay.len() < (COW_MAGIC.len() + 4) || &overlay[..COW_MAGIC.len()] != COW_MAGIC ||
+           read_u32(&overlay, COW_MAGIC.len()) != Some(data.len() as u32) {
+            error!("The overlay `{}' doesn't belong to `{}'.", overlay_path.display(), self.image_path.display());
+            return false;
+        }
+        let mut position = COW_MAGIC.len() + 4;
+        while position < overlay.len() {
+            let (offset, length) = match (read_u32(&overlay, position), read_u32(&overlay, position + 4)) {
+                (Some(offset), Some(length)) => { (offset as usize, length as usize) },
+                _ => {
+                    error!("The overlay `{}' is truncated.", overlay_path.display());
+                    return false;
+                },
+            };
+            position += 8;
+            if (position + length) > overlay.len() || (offset + length) > data.len() {
+                error!("The overlay `{}' is corrupted.", overlay_path.display());
+                return false;
+            }
+            data[offset..(offset + length)].copy_from_slice(&overlay[position..(position + length)]);
+            position += length;
+        }
+
+        self.original_data = Some(std::mem::replace(&mut self.data, data));
+        true
+    }
+    pub fn format(&self) -> Format {
+        self.format
+    }
+    pub fn image_path(&self) -> &path::Path {
+        &self.image_path
+    }
+    pub fn track_count(&self) -> usize {
+        match self.sectors.iter().map(|loc| loc.track).max() {
+            Some(last_track) => { (last_track as usize) + 1 },
+            None => { 0 },
+        }
+    }
+    pub fn sectors_on_track(&self, track: u8, side: u8) -> usize {
+        self.sectors.iter().filter(|loc| loc.track == track && loc.side == side).count()
+    }
+    pub fn write_protected(&self) -> bool {
+        self.write_protected
+    }
+    pub fn read_sector(&self, track: u8, side: u8, sector: u8) -> Option<Vec<u8>> {
+        match self.find_sector(track, side, sector) {
+            Some(index) => {
+                let loc = &self.sectors[index];
+                Some(self.data[loc.offset..].iter().step_by(loc.stride).take(loc.size).cloned().collect())
+            },
+            None => { None },
+        }
+    }
+
+    // Only modifies the in-memory copy of the image, use save() to write
+    // the changes out.
+    pub fn write_sector(&mut self, track: u8, side: u8, sector: u8, contents: &[u8]) -> bool {
+        let index = match self.find_sector(track, side, sector) {
+            Some(index) => { index },
+            None => {
+                error!("Sector {} of track {} (side {}) doesn't exist in `{}'.", sector, track, side, self.image_path.display());
+                return false;
+            },
+        };
+        let loc = &self.sectors[index];
+        if contents.len() != loc.size {
+            error!("Can't write {} bytes into a {} byte sector.", contents.len(), loc.size);
+            return false;
+        }
+        for (byte_index, byte) in contents.iter().enumerate() {
+            let position = loc.offset + (byte_index * loc.stride);
+            self.data[position] = *byte;
+            if loc.stride == 2 {
+                self.data[position + 1] = *byte;
+            }
+        }
+
+        if let Some(ref dam) = loc.dmk_dam {
+            let mut crc = 0xFFFF;
+            if dam.double_density {
+                for _ in 0..3 {
+                    crc = crc16_update(crc, 0xA1);
+                }
+            }
+            crc = crc16_update(crc, self.data[dam.offset]);
+            for byte in contents {
+                crc = crc16_update(crc, *byte);
+            }
+            let crc_position = loc.offset + (loc.size * loc.stride);
+            for (byte_index, byte) in [(crc >> 8) as u8, crc as u8].iter().enumerate() {
+                let position = crc_position + (byte_index * loc.stride);
+                self.data[position] = *byte;
+                if loc.stride == 2 {
+                    self.data[position + 1] = *byte;
+                }
+            }
+        }
+        true
+    }
+    fn find_sector(&self, track: u8, side: u8, sector: u8) -> Option<usize> {
+        self.sectors.iter().position(|loc| loc.track == track && loc.side == side && loc.sector == sector)
+    }
+}
+
+fn write_file(file_path: &path::Path, contents: &[u8]) -> bool {
+    match fs::File::create(file_path) {
+        Ok(mut file) => {
+            match file.write_all(contents) {
+                Ok(..) => { true },
+                Err(error) => {
+                    error!("Failed to write out `{}': {}.", file_path.display(), error);
+                    false
+                },
+            }
+        },
+        Err(error) => {
+            error!("Couldn't open `{}' for writing: {}.", file_path.display(), error);
+            false
+        },
+    }
+}
+
+fn remove_overlay(overlay_path: &path::Path) -> bool {
+    if !overlay_path.exists() {
+        return true;
+    }
+    match fs::remove_file(overlay_path) {
+        Ok(..) => { true },
+        Err(error) => {
+            error!("Failed to remove the overlay `{}': {}.", overlay_path.display(), error);
+            false
+        },
+    }
+}
+
+fn read_u32(data: &[u8], position: usize) -> Option<u32> {
+    if (position + 4) <= data.len() {
+        Some((data[position] as u32) | ((data[position + 1] as u32) << 8) |
+             ((data[position + 2] as u32) << 16) | ((data[position + 3] as u32) << 24))
+    } else {
+        None
+    }
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+// Lists the runs of bytes that differ between the original and the modified
+// image contents.
+fn generate_overlay(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut overlay = COW_MAGIC.to_vec();
+    push_u32(&mut overlay, original.len() as u32);
+
+    let mut position = 0;
+    while position < modified.len() {
+        if original[position] == modified[position] {
+            position += 1;
+            continue;
+        }
+        let run_start = position;
+        while position < modified.len() && original[position] != modified[position] {
+            position += 1;
+        }
+        push_u32(&mut overlay, run_start as u32);
+        push_u32(&mut overlay, (position - run_start) as u32);
+        overlay.extend_from_slice(&modified[run_start..position]);
+    }
+    overlay
+}
+
+// The CRC used by the floppy disk controller (CCITT, polynomial 0x1021).
+fn crc16_update(crc: u16, byte: u8) -> u16 {
+    let mut crc = crc ^ ((byte as u16) << 8);
+    for _ in 0..8 {
+        crc = if (crc & 0x8000) != 0 {
+            (crc << 1) ^ 0x1021
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
+fn parse_jv1_layout(data: &[u8]) -> Option<Vec<SectorLocation>> {
+    let track_count = data.len() / JV1_TRACK_SIZE;
+
+    if track_count == 0 || (track_count * JV1_TRACK_SIZE) != data.len() || track_count > 0x100 {
+        return None;
+    }
+    let mut sectors = Vec::new();
+
+    for track in 0..track_count {
+        for sector in 0..JV1_SECTORS_PER_TRACK {
+            sectors.push(SectorLocation {
+                track:   track as u8,
+                side:    0,
+                sector:  sector as u8,
+                offset:  (track * JV1_TRACK_SIZE) + (sector * SECTOR_SIZE),
+                size:    SECTOR_SIZE,
+                stride:  1,
+                dmk_dam: None,
+            });
+        }
+    }
+    Some(sectors)
+}
+
+// A JV3 image may consist of several header blocks, each followed by the data
+// of the sectors it describes, with whatever comes after the data of a block
+// taken as the next header.  The layout is only accepted if the headers and
+// the sectors described in them add up to the size of the file exactly, a
+// header or a sector cut short, or bytes left over after the last sector,
+// make it invalid.
+fn parse_jv3_layout(data: &[u8]) -> Option<Vec<SectorLocation>> {
+    let mut sectors = Vec::new();
+    let mut block_start = 0;
+
+    while block_start < data.len() {
+        if (data.len() - block_start) < JV3_HEADER_SIZE {
+            return None;
+        }
+        let mut offset = block_start + JV3_HEADER_SIZE;
+
+        for entry in 0..JV3_SECTORS_PER_HEADER {
+            let track  = data[block_start + (entry * 3)];
+            let sector = data[block_start + (entry * 3) + 1];
+            let flags  = data[block_start + (entry * 3) + 2];
+
+            // Entirely unused header entries have no data associated with
+            // them, while free (deleted) sectors still take up space.
+            let size = if track == JV3_FREE {
+                if flags == JV3_FREE {
+                    continue;
+                }
+                128 << ((flags & JV3_FLAG_SIZE) ^ 2)
+            } else {
+                128 << ((flags & JV3_FLAG_SIZE) ^ 1)
+            };
+            if (offset + size) > data.len() {
+                return None;
+            }
+            if track != JV3_FREE {
+                sectors.push(SectorLocation {
+                    track,
+                    side:    if (flags & JV3_FLAG_SIDE) != 0 { 1 } else { 0 },
+                    sector,
+                    offset,
+                    size,
+                    stride:  1,
+                    dmk_dam: None,
+                });
+            }
+            offset += size;
+        }
+        block_start = offset;
+    }
+    if block_start != data.len() {
+        return None;
+    }
+    if sectors.is_empty() {
+        None
+    } else {
+        Some(sectors)
+    }
+}
+
+fn parse_dmk_layout(data: &[u8]) -> Option<Vec<SectorLocation>> {
+    if data.len() < DMK_HEADER_SIZE || (data[0] != 0x00 && data[0] != 0xFF) {
+        return None;
+    }
+    // The reserved bytes must be zero, and a non-zero value in the last four
+    // bytes signifies a real floppy drive rather than an image.
+    if data[5..DMK_HEADER_SIZE].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    let track_count = data[1] as usize;
+    let track_size = (data[2] as usize) | ((data[3] as usize) << 8);
+    let options = data[4];
+    let side_count = if (options & DMK_OPT_SINGLE_SIDED) != 0 { 1 } else { 2 };
+    let bytes_doubled = (options & (DMK_OPT_SINGLE_DENSITY | DMK_OPT_IGNORE_DENSITY)) == 0;
+
+    if track_count == 0 || track_size <= DMK_IDAM_TABLE_SIZE || track_size > ((DMK_IDAM_OFFSET as usize) + 1) {
+        return None;
+    }
+    if data.len() < DMK_HEADER_SIZE + (track_count * side_count * track_size) {
+        return None;
+    }
+    let mut sectors = Vec::new();
+
+    for track_index in 0..(track_count * side_count) {
+        let track_start = DMK_HEADER_SIZE + (track_index * track_size);
+        let track_end = track_start + track_size;
+
+        for idam_entry in 0..(DMK_IDAM_TABLE_SIZE / 2) {
+            let pointer = (data[track_start + (idam_entry * 2)] as u16) |
+                         ((data[track_start + (idam_entry * 2) + 1] as u16) << 8);
+            if pointer == 0 {
+                break;
+            }
+            let double_density = (pointer & DMK_IDAM_DOUBLE_DENSITY) != 0;
+            let stride = if double_density || !bytes_doubled { 1 } else { 2 };
+            let idam = track_start + ((pointer & DMK_IDAM_OFFSET) as usize);
+
+            if (idam + (7 * stride)) > track_end || data[idam] != DMK_IDAM {
+                return None;
+            }
+            let track  = data[idam + stride];
+            let sector = data[idam + (3 * stride)];
+            let size   = 128 << (data[idam + (4 * stride)] & 0x03);
+
+            // The data address mark follows the ID field after a gap.
+            let search_length = if double_density { DMK_DD_DAM_SEARCH } else { DMK_SD_DAM_SEARCH };
+            let mut dam = None;
+            for search_index in 0..search_length {
+                let position = idam + ((7 + search_index) * stride);
+                if position >= track_end {
+                    break;
+                }
+                if (0xF8..=0xFB).contains(&data[position]) {
+                    dam = Some(position);
+                    break;
+                }
+            }
+            // Sectors without a data field can't be accessed anyway.
+            let dam = match dam {
+                Some(dam) => { dam },
+                None => { continue; },
+            };
+            let offset = dam + stride;
+            if (offset + ((size + 2) * stride)) > track_end {
+                continue;
+            }
+            sectors.push(SectorLocation {
+                track,
+                side:    (track_index % side_count) as u8,
+                sector,
+                offset,
+                size,
+                stride,
+                dmk_dam: Some(DmkDataMark {
+                    offset:          dam,
+                    double_density,
+                }),
+            });
+        }
+    }
+    if sectors.is_empty() {
+        None
+    } else {
+        Some(sectors)
+    }
+}
+
+// An ImageDisk file starts with an ASCII header and comment, terminated by
+// 0x1A, followed by the tracks.  Each track has a header (recording mode,
+// cylinder, head, sector count and size code), the sector numbering map, the
+// optional cylinder and head maps and sector size table, and then a record
+// for each sector.  A sector record starts with a type byte: 0 means that the
+// data couldn't be read, odd types are followed by the sector data, and even
+// ones by a single byte the whole sector is filled with.
+//
+// The sector data is collected into a new buffer, which is then used the same
+// way as the contents of the other image formats.
+fn convert_imd_image(file_data: &[u8]) -> Option<(Vec<u8>, Vec<SectorLocation>)> {
+    let mut position = match file_data.iter().position(|byte| *byte == IMD_COMMENT_END) {
+        Some(comment_end) => { comment_end + 1 },
+        None => { return None; },
+    };
+    let mut data = Vec::new();
+    let mut sectors = Vec::new();
+
+    while position < file_data.len() {
+        if (position + 5) > file_data.len() || file_data[position] > IMD_MAX_MODE {
+            return None;
+        }
+        let cylinder     = file_data[position + 1];
+        let head         = file_data[position + 2];
+        let sector_count = file_data[position + 3] as usize;
+        let size_code    = file_data[position + 4];
+        position += 5;
+
+        let sector_map = match file_data.get(position..(position + sector_count)) {
+            Some(map) => { map },
+            None => { return None; },
+        };
+        position += sector_count;
+
+        let cylinder_map = if (head & IMD_HEAD_CYLINDER_MAP) != 0 {
+            position += sector_count;
+            file_data.get((position - sector_count)..position)
+        } else {
+            None
+        };
+        if (head & IMD_HEAD_HEAD_MAP) != 0 {
+            position += sector_count;
+        }
+        let sizes: Vec<usize> = if size_code == IMD_SIZE_TABLE {
+            let mut sizes = Vec::new();
+            for sector_index in 0..sector_count {
+                match file_data.get(position + (sector_index * 2)..position + (sector_index * 2) + 2) {
+                    Some(size) => { sizes.push((size[0] as usize) | ((size[1] as usize) << 8)); },
+                    None => { return None; },
+                }
+            }
+            position += sector_count * 2;
+            sizes
+        } else if size_code <= 6 {
+            vec![128 << size_code; sector_count]
+        } else {
+            return None;
+        };
+
+        for sector_index in 0..sector_count {
+            let record_type = match file_data.get(position) {
+                Some(record_type) => { *record_type },
+                None => { return None; },
+            };
+            position += 1;
+            let size = sizes[sector_index];
+            let offset = data.len();
+
+            if record_type == 0 {
+                continue;
+            } else if record_type > 8 {
+                return None;
+            } else if (record_type & 0x01) != 0 {
+                match file_data.get(position..(position + size)) {
+                    Some(contents) => { data.extend_from_slice(contents); },
+                    None => { return None; },
+                }
+                position += size;
+            } else {
+                match file_data.get(position) {
+                    Some(fill) => { data.resize(offset + size, *fill); },
+                    None => { return None; },
+                }
+                position += 1;
+            }
+            sectors.push(SectorLocation {
+                track:   match cylinder_map {
+                             Some(map) => { map[sector_index] },
+                             None => { cylinder },
+                         },
+                side:    head & 0x01,
+                sector:  sector_map[sector_index],
+                offset,
+                size,
+                stride:  1,
+                dmk_dam: None,
+            });
+        }
+    }
+    if sectors.is_empty() {
+        None
+    } else {
+        Some((data, sectors))
+    }
+}
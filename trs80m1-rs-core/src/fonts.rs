@@ -5,6 +5,10 @@
 //
 use crate::video;
 
+use std::fs;
+use std::io;
+use std::path;
+
 pub const FONT_GLYPH_BYTES:  u32   = video::GLYPH_HEIGHT;
 pub const FONT_GLYPH_COUNT:  u32   = 128;
 pub const FONT_SIZE:         usize = (FONT_GLYPH_COUNT * FONT_GLYPH_BYTES) as usize;
@@ -13,6 +17,101 @@ pub const GRAPH_GLYPH_BYTES: u32   = FONT_GLYPH_BYTES;
 pub const GRAPH_GLYPH_COUNT: u32   = 64;
 pub const GRAPH_FONT_SIZE:   usize = (GRAPH_GLYPH_COUNT * GRAPH_GLYPH_BYTES) as usize;
 
+pub const ALT_GLYPH_BYTES:   u32   = FONT_GLYPH_BYTES;
+pub const ALT_GLYPH_COUNT:   u32   = 64;
+pub const ALT_FONT_SIZE:     usize = (ALT_GLYPH_COUNT * ALT_GLYPH_BYTES) as usize;
+
+// All of the glyphs the screen can show: the 128 of the character generator,
+// the 64 block graphics characters, and the 64 characters of the alternate
+// character set, in this order.
+pub const GLYPH_TOTAL:       u32   = FONT_GLYPH_COUNT + GRAPH_GLYPH_COUNT + ALT_GLYPH_COUNT;
+
+// The number of rows of each glyph in a dump of a character generator ROM,
+// which only holds the rows of the 5x7 dot matrix and a blank one:
+pub const ROM_GLYPH_BYTES:   u32   = 8;
+pub const ROM_SIZE:          usize = (FONT_GLYPH_COUNT * ROM_GLYPH_BYTES) as usize;
+
+// The character generators with an alternate character set are 2 KB ROMs,
+// holding 256 glyphs, the alternate set being the last 64 of them.
+pub const ALT_ROM_SIZE:      usize = ROM_SIZE * 2;
+const ALT_ROM_GLYPH_START:   usize = ALT_ROM_SIZE - (ALT_GLYPH_COUNT * ROM_GLYPH_BYTES) as usize;
+
+
+// Load a custom character generator font from a file, either a font bitmap
+// of FONT_SIZE bytes, laid out like the fonts below (FONT_GLYPH_BYTES rows
+// of each of the 128 glyphs, the first row is the top one, and the lowest
+// bit of each row is its leftmost dot), or a ROM dump of ROM_SIZE bytes,
+// laid out the same way, but with only ROM_GLYPH_BYTES rows for each glyph,
+// which become the top rows of the glyph.
+//
+// A font bitmap of FONT_SIZE + ALT_FONT_SIZE bytes or a ROM dump of
+// ALT_ROM_SIZE bytes also has an alternate character set, which is kept
+// after the 128 glyphs in the font, see has_alternate_set().
+pub fn load_font_file<P: AsRef<path::Path>>(path: P) -> io::Result<Box<[u8]>> {
+    let data = fs::read(path)?;
+
+    if data.len() == FONT_SIZE || data.len() == (FONT_SIZE + ALT_FONT_SIZE) {
+        Ok(data.into_boxed_slice())
+    } else if data.len() == ROM_SIZE || data.len() == ALT_ROM_SIZE {
+        let alternate = if data.len() == ALT_ROM_SIZE { &data[ALT_ROM_GLYPH_START..] } else { &[] };
+        let mut font = vec![0u8; FONT_SIZE + if alternate.is_empty() { 0 } else { ALT_FONT_SIZE }];
+        for (glyph, rows) in data[..ROM_SIZE].chunks(ROM_GLYPH_BYTES as usize).chain(alternate.chunks(ROM_GLYPH_BYTES as usize)).enumerate() {
+            let font_index = glyph * (FONT_GLYPH_BYTES as usize);
+            font[font_index..(font_index + rows.len())].copy_from_slice(rows);
+        }
+        Ok(font.into_boxed_slice())
+    } else {
+        Err(io::Error::other(format!("the file is {} bytes long, a character generator font has to be either {} or {} bytes long (a font bitmap) or {} or {} bytes long (a ROM dump)", data.len(), FONT_SIZE, FONT_SIZE + ALT_FONT_SIZE, ROM_SIZE, ALT_ROM_SIZE)))
+    }
+}
+
+// The character generators of the Japanese and the European versions of the
+// machine have 64 more characters, which are shown instead of the block
+// graphics characters 0xC0-0xFF when the alternate character set is switched
+// in, the other half of the graphics characters stays as it is.  None of
+// them are built in, they come from a dump of such a character generator.
+//
+// Since these need the bit 6 of the video RAM, they only show up in machines
+// with the lowercase mod, the bit is cleared in the rest, same as for the
+// lowercase letters.
+pub fn has_alternate_set(font: &[u8]) -> bool {
+    font.len() >= (FONT_SIZE + ALT_FONT_SIZE)
+}
+
+// The index of the glyph shown for the given character code, among the
+// GLYPH_TOTAL glyphs, with or without the alternate character set switched
+// in.
+pub fn glyph_index(code: u8, alternate: bool) -> u32 {
+    match code {
+        0x00..=0x7F => {
+            code as u32
+        },
+        0xC0..=0xFF if alternate => {
+            FONT_GLYPH_COUNT + GRAPH_GLYPH_COUNT + ((code & 0b0011_1111) as u32)
+        },
+        _ => {
+            FONT_GLYPH_COUNT + ((code & 0b0011_1111) as u32)
+        },
+    }
+}
+
+// The rows of the glyph with the given index, see glyph_index(), using the
+// given character generator font for the first 128 glyphs and the alternate
+// character set.  A font without the alternate set shows the block graphics
+// characters in its place.
+pub fn glyph_rows(font: &[u8], index: u32) -> &[u8] {
+    let (glyphs, index) = if index < FONT_GLYPH_COUNT {
+        (font, index)
+    } else if index < (FONT_GLYPH_COUNT + GRAPH_GLYPH_COUNT) {
+        (&GRAPH_FONT[..], index - FONT_GLYPH_COUNT)
+    } else if has_alternate_set(font) {
+        (font, index - GRAPH_GLYPH_COUNT)
+    } else {
+        (&GRAPH_FONT[..], index - FONT_GLYPH_COUNT - GRAPH_GLYPH_COUNT)
+    };
+    &glyphs[((index * FONT_GLYPH_BYTES) as usize)..(((index + 1) * FONT_GLYPH_BYTES) as usize)]
+}
+
 
 // CG 0
 // Source: MCM6674 Data Sheet
@@ -512,3 +611,4 @@ pub static GRAPH_FONT: [u8; GRAPH_FONT_SIZE] =
     0x0f, 0x0f, 0x0f, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
     0xf0, 0xf0, 0xf0, 0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff ];
+
@@ -0,0 +1,161 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::memory::PeripheralIO;
+
+
+// Emulation of the HRG1B high resolution graphics board, which was sold in
+// Germany for the Model I and the Video Genie.  The port map and the layout
+// of its memory follow the emulation of the board in xtrs (`hrg_onoff',
+// `hrg_write_addr', `hrg_write_data' and `hrg_read_data' in
+// trs_xinterface.c, the ports in trs_io.c):
+//
+// The board has 12 KB of its own memory holding a bitmap of 384 by 192
+// pixels, which is mixed into the picture of the text screen, the pixels are
+// 4/3 of a dot wide and 2 dots tall.  It's laid out like the text screen: each
+// of the 1024 characters is covered by 6 by 12 pixels, and each byte holds a
+// row of them, the leftmost pixel in bit 0, bits 6 and 7 aren't shown.  Bits
+// 0-9 of the address select the character, bits 10-13 the row in it.
+//
+// The memory isn't mapped into the address space of the CPU, it's accessed
+// through six IO ports instead:
+//
+//   0x00 - Writing turns the bitmap off.
+//   0x01 - Writing turns the bitmap on.
+//   0x02 - The low byte of the address.
+//   0x03 - The high byte of the address, only bits 0-5 are kept.
+//   0x04 - Reading the byte at the address.
+//   0x05 - Writing the byte at the address.
+//
+// Ports 0x00-0x03 and 0x05 are only written, so reading port 0x00 still
+// reaches a joystick interface on it.  The addresses of rows 12-15 of the
+// characters aren't there, writing them does nothing, reading them gives
+// 0xFF.
+//
+pub const HIRES_BASE:      u16 = 0x00;
+pub const HIRES_SIZE:      u16 = 6;
+
+pub const HIRES_WIDTH:     u32 = 384;
+pub const HIRES_HEIGHT:    u32 = 192;
+
+const CELL_WIDTH:          u32 = 6;
+const CELL_HEIGHT:         u32 = 12;
+const CELLS_PER_ROW:       u32 = HIRES_WIDTH / CELL_WIDTH;
+const CELL_ROW_BYTES:      u32 = 1024; // For each row of the characters.
+const HIRES_MEM_SIZE:      u32 = CELL_ROW_BYTES * CELL_HEIGHT;
+const ADDRESS_HIGH_MASK:   u8  = 0b0011_1111;
+
+pub struct HiResIO {
+    installed: bool,
+    memory:    Box<[u8]>,
+    address:   u16,
+    shown:     bool,
+}
+
+// The bitmap of the board that's mixed into the picture, only kept while
+// it's shown.
+#[derive(Clone)]
+pub struct HiResFrame {
+    pub bitmap: Option<Box<[u8]>>,
+}
+
+impl HiResFrame {
+    // Whether the pixel at the given position of the bitmap is lit.
+    pub fn pixel_at(&self, x: u32, y: u32) -> bool {
+        match &self.bitmap {
+            Some(bitmap) => {
+                let cell = ((y / CELL_HEIGHT) * CELLS_PER_ROW) + (x / CELL_WIDTH);
+                let byte = bitmap[((y % CELL_HEIGHT) * CELL_ROW_BYTES + cell) as usize];
+                (byte & (1 << (x % CELL_WIDTH))) != 0
+            },
+            None => { false },
+        }
+    }
+}
+
+impl Default for HiResIO {
+    fn default() -> HiResIO {
+        HiResIO::new()
+    }
+}
+
+impl HiResIO {
+    pub fn new() -> HiResIO {
+        HiResIO {
+            installed: false,
+            memory:    vec![0; HIRES_MEM_SIZE as usize].into_boxed_slice(),
+            address:   0,
+            shown:     false,
+        }
+    }
+    // The memory of the board is cleared on power-up, and it starts out
+    // with the bitmap turned off.
+    pub fn power_off(&mut self) {
+        for byte in self.memory.iter_mut() {
+            *byte = 0;
+        }
+        self.address = 0;
+        self.shown   = false;
+    }
+    pub fn set_installed(&mut self, installed: bool) {
+        if installed != self.installed {
+            self.power_off();
+            self.installed = installed;
+        }
+    }
+    pub fn installed(&self) -> bool {
+        self.installed
+    }
+    // The offset of the given port on the board, if it belongs to it.
+    pub fn port_offset(&self, port: u16) -> Option<u16> {
+        if self.installed && (HIRES_BASE..(HIRES_BASE + HIRES_SIZE)).contains(&port) {
+            Some(port - HIRES_BASE)
+        } else {
+            None
+        }
+    }
+    // What the board adds to the picture, None if it isn't installed.
+    pub fn frame(&self) -> Option<HiResFrame> {
+        if !self.installed {
+            return None;
+        }
+        Some(HiResFrame {
+            bitmap: if self.shown { Some(self.memory.clone()) } else { None },
+        })
+    }
+}
+
+impl PeripheralIO for HiResIO {
+    fn peripheral_read_byte(&mut self, offset: u16) -> u8 {
+        match offset {
+            4 => { self.memory.get(self.address as usize).copied().unwrap_or(0xFF) },
+            _ => { 0xFF },
+        }
+    }
+    fn peripheral_write_byte(&mut self, offset: u16, val: u8) {
+        match offset {
+            0 => { self.shown = false; },
+            1 => { self.shown = true; },
+            2 => { self.address = (self.address & 0xFF00) | (val as u16); },
+            3 => { self.address = (self.address & 0x00FF) | (((val & ADDRESS_HIGH_MASK) as u16) << 8); },
+            5 => {
+                if let Some(byte) = self.memory.get_mut(self.address as usize) {
+                    *byte = val;
+                }
+            },
+            _ => { },
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::memory::PeripheralIO;
+
+
+// Emulation of a joystick interface in the style of the one made by Alpha
+// Products, which most of the games supporting a joystick expect at port 00h:
+//
+// Reading the port gives the state of the stick and of the fire button, one
+// bit for each direction and one for the button, which reads as 0 while it's
+// pushed that way or pressed, and as 1 otherwise.  The other bits always read
+// as 1, and writes to the port are ignored.
+//
+// I don't know this for certain, as I don't have one of these interfaces,
+// this is how the other emulators treat it.  If some game disagrees, please
+// report it as a bug.
+//
+pub const JOY_UP:    u8 = 0b0000_0001;
+pub const JOY_DOWN:  u8 = 0b0000_0010;
+pub const JOY_LEFT:  u8 = 0b0000_0100;
+pub const JOY_RIGHT: u8 = 0b0000_1000;
+pub const JOY_FIRE:  u8 = 0b0001_0000;
+
+pub struct JoystickIO {
+    port:    Option<u8>,   // None if the interface isn't installed.
+    pressed: u8,           // The JOY_* bits of what's pushed or pressed.
+}
+
+impl Default for JoystickIO {
+    fn default() -> JoystickIO {
+        JoystickIO::new()
+    }
+}
+
+impl JoystickIO {
+    pub fn new() -> JoystickIO {
+        JoystickIO {
+            port:    None,
+            pressed: 0,
+        }
+    }
+    // Install the interface at the given port, or remove it.
+    pub fn set_port(&mut self, port: Option<u8>) {
+        self.port = port;
+    }
+    pub fn port(&self) -> Option<u8> {
+        self.port
+    }
+    // The offset of the given port on the interface, if it belongs to it.
+    pub fn port_offset(&self, port: u16) -> Option<u16> {
+        match self.port {
+            Some(base) if port == (base as u16) => { Some(0) },
+            _ => { None },
+        }
+    }
+    // The state of the stick and of the button, as JOY_* bits, the stick
+    // being held by the user rather than the machine, so it's left alone
+    // when the machine is powered off.
+    pub fn set_pressed(&mut self, pressed: u8) {
+        self.pressed = pressed & (JOY_UP | JOY_DOWN | JOY_LEFT | JOY_RIGHT | JOY_FIRE);
+    }
+    pub fn pressed(&self) -> u8 {
+        self.pressed
+    }
+}
+
+impl PeripheralIO for JoystickIO {
+    fn peripheral_read_byte(&mut self, _offset: u16) -> u8 {
+        !self.pressed
+    }
+    fn peripheral_write_byte(&mut self, _offset: u16, _val: u8) {
+    }
+}
@@ -16,7 +16,9 @@
 use log::{info, warn, error};
 
 use std::collections::VecDeque;
+use std::time;
 
+use crate::machine;
 use crate::memory;
 
 // Even though the keyboard only has (at most) 64 keys, because of the way they
@@ -25,6 +27,7 @@ pub const KBD_MEM_SIZE: u16 = 0x0100;
 
 pub struct KeyboardMemory {
     key_matrix: [u8; 8],
+    scanned:    u8,      // The rows the CPU read, for pacing the typing.
 }
 
 impl memory::MemIO for KeyboardMemory {
@@ -57,11 +60,25 @@ impl KeyboardMemory {
     pub fn new(start_addr: u16) -> KeyboardMemory {
         let memory = KeyboardMemory {
             key_matrix: [0; 8],
+            scanned:    0,
         };
 
         info!("Created the keyboard memory interface, starting address: 0x{:04X}, spanning {} bytes.", start_addr, KBD_MEM_SIZE);
         memory
     }
+
+    // Called for the reads of the CPU, but not for the ones of the debugger,
+    // to note which rows the program looked at.
+    pub fn scan(&mut self, addr: u16) {
+        self.scanned |= (addr & 0x00FF) as u8;
+    }
+    // The keys held down in a row of the matrix, one bit for each column.
+    pub fn key_row(&self, row: usize) -> u8 {
+        self.key_matrix[row]
+    }
+    pub fn set_key_row(&mut self, row: usize, columns: u8) {
+        self.key_matrix[row] = columns;
+    }
 }
 
 
@@ -75,18 +92,64 @@ pub struct KeyboardQueueEntry {
     pub row:    u8,
     pub column: u8,
     pub delay:  u32,  // Minimum delay in CPU cycles since the previous queue entry was processed.
+    pub sent:   Option<time::Instant>, // When the user pressed or released the key, None for typed text.
+}
+
+// The keys of the user are applied at the point of the emulated time which
+// matches when they were pressed or released.  The machine is emulated in
+// slices, each of which makes up for the time since the previous one began,
+// so the keys which came in since then belong somewhere within it, rather
+// than all at its start.
+struct Slice {
+    start:  time::Instant,  // The moment the first cycle of the slice stands for.
+    cycles: u32,            // Executed since.
+}
+
+// How long the keys of the user took to reach the keyboard matrix, from the
+// moment the front-end saw them, for verifying the latency of the input.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct InputLatency {
+    pub count: u32,
+    pub total: time::Duration,
+    pub max:   time::Duration,
 }
 
+impl InputLatency {
+    pub fn average(&self) -> time::Duration {
+        if self.count > 0 { self.total / self.count } else { time::Duration::ZERO }
+    }
+    fn add(&mut self, latency: time::Duration) {
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+}
+
+// When typing text, each key pressed or released waits for the program to
+// read its row of the keyboard matrix before the delay to the next one starts,
+// so that keys aren't missed while the program is busy with something else,
+// like executing the line just entered.  A program which doesn't look at the
+// keyboard at all only holds the typing up for this long.
+const SCAN_TIMEOUT_CYCLES: u32 = machine::CPU_HZ * 5;
+
 pub struct KeyboardQueue {
-    deque:     VecDeque<KeyboardQueueEntry>,
-    cpu_delta: u32,
+    deque:         VecDeque<KeyboardQueueEntry>,
+    cpu_delta:     u32,
+    latency:       InputLatency,
+    wait_for_scan: bool,
+    awaiting_scan: u8,   // The row of the last typed key, until it's read.
+    slice:         Option<Slice>,
 }
 
 impl KeyboardQueue {
     pub fn new() -> KeyboardQueue {
         KeyboardQueue {
-            deque:     VecDeque::with_capacity(4096),
-            cpu_delta: 0,
+            deque:         VecDeque::with_capacity(4096),
+            cpu_delta:     0,
+            latency:       InputLatency::default(),
+            wait_for_scan: true,
+            awaiting_scan: 0,
+            slice:         None,
         }
     }
 
@@ -95,6 +158,18 @@ impl KeyboardQueue {
         self.deque.reserve(4096);
         self.deque.shrink_to(4096);
         self.cpu_delta = 0;
+        self.awaiting_scan = 0;
+        self.slice = None;
+    }
+
+    // Called before emulating a slice of time which began at `start'.
+    pub fn begin_slice(&mut self, start: time::Instant) {
+        self.slice = Some(Slice { start, cycles: 0 });
+    }
+
+    pub fn set_wait_for_scan(&mut self, wait_for_scan: bool) {
+        self.wait_for_scan = wait_for_scan;
+        self.awaiting_scan = 0;
     }
 
     pub fn add_keyboard_event(&mut self, entry: KeyboardQueueEntry) {
@@ -102,14 +177,43 @@ impl KeyboardQueue {
         self.deque.push_back(entry);
     }
 
+    // Whether the point of the slice at which the key belongs was reached.
+    fn is_due(&self, entry: &KeyboardQueueEntry) -> bool {
+        match (&self.slice, entry.sent) {
+            (Some(slice), Some(sent)) => {
+                let offset = sent.saturating_duration_since(slice.start);
+                let due_cycle = (offset.as_nanos() / (machine::NS_PER_CPU_CYCLE as u128)).min(u32::MAX as u128) as u32;
+                slice.cycles >= due_cycle
+            },
+            _ => { true },
+        }
+    }
+
+    // The latency of the keys applied since it was last taken.
+    pub fn take_latency(&mut self) -> InputLatency {
+        std::mem::take(&mut self.latency)
+    }
+
     pub fn tick(&mut self, kbd_mem: &mut KeyboardMemory, cycles: u32) {
 
         self.cpu_delta += cycles;
+        if let Some(slice) = &mut self.slice {
+            slice.cycles = slice.cycles.saturating_add(cycles);
+        }
         let mut entry_used = false;
 
+        if self.awaiting_scan != 0 {
+            if (kbd_mem.scanned & self.awaiting_scan) != 0 || self.cpu_delta >= SCAN_TIMEOUT_CYCLES {
+                self.awaiting_scan = 0;
+                self.cpu_delta = 0;
+            } else {
+                return;
+            }
+        }
+
         match self.deque.get(0) {
             Some(entry) => {
-                if self.cpu_delta >= entry.delay {
+                if self.cpu_delta >= entry.delay && self.is_due(entry) {
                     match entry.action {
                         KeyboardQueueEntryAction::Press => {
                             kbd_mem.key_matrix[entry.row as usize] |= entry.column;
@@ -118,6 +222,16 @@ impl KeyboardQueue {
                             kbd_mem.key_matrix[entry.row as usize] &= !entry.column;
                         },
                     }
+                    match entry.sent {
+                        Some(sent) => {
+                            self.latency.add(sent.elapsed());
+                        },
+                        None if self.wait_for_scan => {
+                            self.awaiting_scan = 1 << entry.row;
+                            kbd_mem.scanned &= !self.awaiting_scan;
+                        },
+                        None => { },
+                    }
                     self.cpu_delta = 0;
                     entry_used = true;
                 }
@@ -130,3 +244,187 @@ impl KeyboardQueue {
         }
     }
 }
+
+// Typing text through the keyboard queue.
+//
+// The keys are laid out in the matrix like this (rows from top to bottom,
+// columns from the least significant bit):
+//
+//     0:  @ A B C D E F G
+//     1:  H I J K L M N O
+//     2:  P Q R S T U V W
+//     3:  X Y Z
+//     4:  0 1 2 3 4 5 6 7
+//     5:  8 9 : ; , - . /
+//     6:  ENTER CLEAR BREAK UP DOWN LEFT RIGHT SPACE
+//     7:  SHIFT
+//
+const SHIFT_ROW:    u8 = 7;
+const SHIFT_COLUMN: u8 = 0b0000_0001;
+
+// The matrix location of the key producing a given character, and whether
+// shift needs to be held down for it:
+fn char_to_key(ch: char) -> Option<(u8, u8, bool)> {
+    let ch = ch.to_ascii_uppercase();
+    match ch {
+        '@'        => { Some((0, 0b0000_0001, false)) },
+        'A'..='G'  => { Some((0, 1 << (ch as u8 - b'A' + 1), false)) },
+        'H'..='O'  => { Some((1, 1 << (ch as u8 - b'H'), false)) },
+        'P'..='W'  => { Some((2, 1 << (ch as u8 - b'P'), false)) },
+        'X'..='Z'  => { Some((3, 1 << (ch as u8 - b'X'), false)) },
+        '0'..='7'  => { Some((4, 1 << (ch as u8 - b'0'), false)) },
+        '8' | '9'  => { Some((5, 1 << (ch as u8 - b'8'), false)) },
+        '\n'       => { Some((6, 0b0000_0001, false)) },
+        ' '        => { Some((6, 0b1000_0000, false)) },
+        _ => {
+            // Punctuation in row 5, and the shifted digits.
+            let unshifted = ":;,-./";
+            let shifted_5 = "*+<=>?";
+            let shifted_4 = "_!\"#$%&'";
+            if let Some(index) = unshifted.find(ch) {
+                Some((5, 1 << (index + 2), false))
+            } else if let Some(index) = shifted_5.find(ch) {
+                Some((5, 1 << (index + 2), true))
+            } else if let Some(index) = shifted_4.find(ch) {
+                if index == 0 { None } else { Some((4, 1 << index, true)) }
+            } else if ch == '(' || ch == ')' {
+                Some((5, if ch == '(' { 0b0000_0001 } else { 0b0000_0010 }, true))
+            } else {
+                None
+            }
+        },
+    }
+}
+
+// Turn a piece of text into key presses and releases, each one happening
+// cycles_per_keypress CPU cycles after the previous one.  Returns None if
+// the text contains characters that can't be typed on the keyboard.
+pub fn text_to_key_events(text: &str, cycles_per_keypress: u32) -> Option<Vec<KeyboardQueueEntry>> {
+    let mut events = Vec::new();
+
+    for ch in text.chars() {
+        let (row, column, shifted) = char_to_key(ch)?;
+        let mut push = |action, row, column| {
+            events.push(KeyboardQueueEntry { action, row, column, delay: cycles_per_keypress, sent: None });
+        };
+
+        if shifted {
+            push(KeyboardQueueEntryAction::Press, SHIFT_ROW, SHIFT_COLUMN);
+        }
+        push(KeyboardQueueEntryAction::Press, row, column);
+        push(KeyboardQueueEntryAction::Release, row, column);
+        if shifted {
+            push(KeyboardQueueEntryAction::Release, SHIFT_ROW, SHIFT_COLUMN);
+        }
+    }
+    Some(events)
+}
+
+// Turn text pasted from elsewhere into key presses, the same way as
+// text_to_key_events(), except that the characters that can't be typed are
+// skipped, line breaks of any kind are typed as ENTER, tabs as spaces, and
+// there's a pause of cycles_per_line after each line, for the program to take
+// it in.  Returns the events, and the number of characters skipped.
+pub fn pasted_text_to_key_events(text: &str, cycles_per_keypress: u32, cycles_per_line: u32) -> (Vec<KeyboardQueueEntry>, usize) {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n").replace('\t', " ");
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    let mut delay = cycles_per_keypress;
+
+    for ch in text.chars() {
+        match text_to_key_events(ch.encode_utf8(&mut [0; 4]), cycles_per_keypress) {
+            Some(mut char_events) => {
+                char_events[0].delay = delay;
+                events.append(&mut char_events);
+                delay = if ch == '\n' { cycles_per_line } else { cycles_per_keypress };
+            },
+            None => { skipped += 1; },
+        }
+    }
+    (events, skipped)
+}
+
+// The keys which don't produce a character, in row 6 of the matrix:
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SpecialKey {
+    Clear,
+    Break,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// Press and release a special key, the same way text_to_key_events()
+// types in characters.
+pub fn special_key_events(key: SpecialKey, cycles_per_keypress: u32) -> Vec<KeyboardQueueEntry> {
+    let column = match key {
+        SpecialKey::Clear => { 0b0000_0010 },
+        SpecialKey::Break => { 0b0000_0100 },
+        SpecialKey::Up    => { 0b0000_1000 },
+        SpecialKey::Down  => { 0b0001_0000 },
+        SpecialKey::Left  => { 0b0010_0000 },
+        SpecialKey::Right => { 0b0100_0000 },
+    };
+
+    vec![KeyboardQueueEntry { action: KeyboardQueueEntryAction::Press,   row: 6, column, delay: cycles_per_keypress, sent: None },
+         KeyboardQueueEntry { action: KeyboardQueueEntryAction::Release, row: 6, column, delay: cycles_per_keypress, sent: None }]
+}
+
+// The keys of a script which can't be written as text, written as tokens
+// in braces, such as `{ENTER}' or `{BREAK}', in any case.
+fn script_token_events(token: &str, cycles_per_keypress: u32) -> Option<Vec<KeyboardQueueEntry>> {
+    match token.to_ascii_uppercase().as_str() {
+        "ENTER" => { text_to_key_events("\n", cycles_per_keypress) },
+        "SPACE" => { text_to_key_events(" ", cycles_per_keypress) },
+        "CLEAR" => { Some(special_key_events(SpecialKey::Clear, cycles_per_keypress)) },
+        "BREAK" => { Some(special_key_events(SpecialKey::Break, cycles_per_keypress)) },
+        "UP"    => { Some(special_key_events(SpecialKey::Up, cycles_per_keypress)) },
+        "DOWN"  => { Some(special_key_events(SpecialKey::Down, cycles_per_keypress)) },
+        "LEFT"  => { Some(special_key_events(SpecialKey::Left, cycles_per_keypress)) },
+        "RIGHT" => { Some(special_key_events(SpecialKey::Right, cycles_per_keypress)) },
+        _ => { None },
+    }
+}
+
+// Turn a script, a text file typed in for a demonstration or a test, into
+// key presses, the same way as pasted_text_to_key_events(), except that the
+// keys which don't type characters can be written as tokens in braces, see
+// script_token_events().  `{ENTER}' pauses for the line, like a line break
+// does.  Returns the events and the number of characters skipped, or a
+// description of the token that isn't understood.
+pub fn script_to_key_events(script: &str, cycles_per_keypress: u32, cycles_per_line: u32) -> Result<(Vec<KeyboardQueueEntry>, usize), String> {
+    let script = script.replace("\r\n", "\n").replace('\r', "\n").replace('\t', " ");
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    let mut delay = cycles_per_keypress;
+    let mut rest = script.as_str();
+
+    while let Some(ch) = rest.chars().next() {
+        let (mut key_events, line_end) = if ch == '{' {
+            let end = match rest.find('}') {
+                Some(end) => { end },
+                None => { return Err(format!("the token starting with `{}' isn't closed", rest.lines().next().unwrap_or(rest))); },
+            };
+            let token = &rest[1..end];
+            rest = &rest[(end + 1)..];
+            match script_token_events(token, cycles_per_keypress) {
+                Some(token_events) => { (token_events, token.eq_ignore_ascii_case("enter")) },
+                None => { return Err(format!("unknown token `{{{}}}'", token)); },
+            }
+        } else {
+            rest = &rest[ch.len_utf8()..];
+            match text_to_key_events(ch.encode_utf8(&mut [0; 4]), cycles_per_keypress) {
+                Some(char_events) => { (char_events, ch == '\n') },
+                None => {
+                    skipped += 1;
+                    continue;
+                },
+            }
+        };
+        key_events[0].delay = delay;
+        events.append(&mut key_events);
+        delay = if line_end { cycles_per_line } else { cycles_per_keypress };
+    }
+    Ok((events, skipped))
+}
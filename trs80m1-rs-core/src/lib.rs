@@ -15,11 +15,23 @@
 
 extern crate log;
 
+pub mod basic;
+pub mod bridge;
 pub mod cassette;
+pub mod cmd_file;
+pub mod disk;
 pub mod fonts;
+pub mod hires;
+pub mod joystick;
 pub mod keyboard;
 pub mod machine;
 pub mod memory;
+pub mod psg;
+pub mod replay;
+pub mod snapshot;
+pub mod sound;
+pub mod tape;
+pub mod trsdos;
 pub mod util;
 pub mod video;
 pub mod z80;
@@ -21,8 +21,12 @@ use std::path;
 use crate::z80::cpu;
 use crate::cassette;
 use crate::keyboard;
+use crate::sound;
 use crate::video;
 use crate::memory;
+use crate::memory::MemIO;
+use crate::replay;
+use crate::snapshot;
 use crate::util::Sink;
 
 // Timing description:
@@ -32,9 +36,16 @@ pub const NS_PER_FRAME:       u32 = 1_000_000_000 / FRAME_RATE;
 pub const CPU_HZ:             u32 = MASTER_HZ     / 6;
 pub const NS_PER_CPU_CYCLE:   u32 = 1_000_000_000 / CPU_HZ;
 
+// Level II ROM cassette routines, trapped for fast loading:
+const ROM_CAS_READ_BYTE:      u16 = 0x0235;  // Read a byte into A.
+const ROM_CAS_READ_SYNC:      u16 = 0x0296;  // Skip the leader, find the sync byte.
+const CAS_TRAP_CYCLES:        u32 = 10;      // Charged as a ret instruction.
+const VID_ASTERISKS:          u16 = 0x3C3E;  // The `**' in the top right corner.
+
 pub struct Devices {
     pub cassette: cassette::CassetteRecorder,
     pub keyboard: keyboard::KeyboardQueue,
+    pub sound:    sound::SoundOutput,
     pub video:    video::Video,
 }
 
@@ -46,6 +57,7 @@ impl Devices {
         Devices {
             cassette: cassette::CassetteRecorder::new(cassette_file_path, cassette_file_format, cassette_file_offset),
             keyboard: keyboard::KeyboardQueue::new(),
+            sound:    sound::SoundOutput::new(),
             video:    video::Video::new(cycles_per_video_frame),
         }
     }
@@ -56,8 +68,11 @@ impl Devices {
     }
     fn tick<ES: Sink<cassette::CassetteEvent>, VS: Sink<video::VideoFrame>>(&mut self, memory_system: &mut memory::MemorySystem, cpu_cycles: u32, cassette_event_sink: &mut ES, video_frame_sink: &mut VS) {
         self.cassette.tick(&mut memory_system.cas_rec, cpu_cycles, cassette_event_sink);
+        let sound_source = if self.cassette.motor_running() { sound::Source::Tape } else { sound::Source::Program };
+        memory_system.ay.tick(cpu_cycles);
+        self.sound.tick(sound_source, self.cassette.signal_level(&memory_system.cas_rec), &memory_system.orch, &memory_system.ay, cpu_cycles);
         self.keyboard.tick(&mut memory_system.kbd_mem, cpu_cycles);
-        self.video.tick(&mut memory_system.vid_mem, cpu_cycles, video_frame_sink);
+        self.video.tick(&mut memory_system.vid_mem, &memory_system.hires, cpu_cycles, video_frame_sink);
     }
 }
 
@@ -65,12 +80,16 @@ pub struct Machine {
     pub cpu:               cpu::CPU,
     pub memory_system:     memory::MemorySystem,
     pub devices:           Devices,
+
+    cassette_fast_load:    bool,
+    cycles:                u64,     // The T-states run since it was created.
+    input_log:             replay::InputLog,
 }
 
 impl Machine {
 
     pub fn new(ram_size: u16,
-               rom_choice: Option<path::PathBuf>,
+               rom_choice: Vec<(path::PathBuf, u16)>,
                lowercase_mod: bool,
                cassette_file_path: Option<path::PathBuf>,
                cassette_file_format: cassette::Format,
@@ -82,6 +101,9 @@ impl Machine {
             cpu: cpu::CPU::new(),
             memory_system: memory::MemorySystem::new(ram_size, rom_choice, lowercase_mod),
             devices: Devices::new(cassette_file_path, cassette_file_format, cassette_file_offset, cycles_per_video_frame),
+            cassette_fast_load: false,
+            cycles: 0,
+            input_log: replay::InputLog::new(),
         }
     }
     pub fn power_on(&mut self) {
@@ -93,11 +115,85 @@ impl Machine {
         self.devices.power_off(&mut self.memory_system, cassette_event_sink);
         self.memory_system.power_off();
     }
+    // Only meant to be enabled with the Level II ROM, the traps are placed at
+    // the addresses of its cassette routines.
+    pub fn set_cassette_fast_load(&mut self, enabled: bool) {
+        self.cassette_fast_load = enabled;
+    }
+    // The T-states run so far, for timing the programs by the emulated time.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+    // Start recording the input, from a snapshot of the machine as it is.
+    pub fn start_input_recording(&mut self) {
+        let snapshot = snapshot::Snapshot::take(self);
+        self.input_log.start_recording(snapshot, self.cycles);
+    }
+    // Returns None if the input wasn't being recorded.
+    pub fn finish_input_recording(&mut self) -> Option<replay::InputRecording> {
+        self.input_log.finish_recording(self.cycles)
+    }
+    // Restore the snapshot of the recording and replay its input, in place
+    // of the keyboard and the joystick, until it ends.
+    pub fn start_input_replay(&mut self, recording: replay::InputRecording) -> Result<(), String> {
+        recording.snapshot().restore(self)?;
+        self.input_log.start_replay(recording, self.cycles);
+        Ok(())
+    }
+    // Returns false if no input was being replayed.
+    pub fn stop_input_replay(&mut self) -> bool {
+        self.input_log.stop_replay(&mut self.memory_system)
+    }
+    pub fn recording_input(&self) -> bool {
+        self.input_log.recording()
+    }
+    pub fn replaying_input(&self) -> bool {
+        self.input_log.replaying()
+    }
     pub fn step<ES: Sink<cassette::CassetteEvent>, VS: Sink<video::VideoFrame>>(&mut self, cassette_event_sink: &mut ES, video_frame_sink: &mut VS) -> u32 {
 
-        let cpu_cycles = self.cpu.step(&mut self.memory_system);
+        self.input_log.step(self.cycles, &mut self.memory_system);
+
+        let trapped_cycles = if self.cassette_fast_load && !self.cpu.halted {
+            self.cassette_trap()
+        } else {
+            None
+        };
+        let cpu_cycles = match trapped_cycles {
+            Some(cycles) => { cycles },
+            None => { self.cpu.step(&mut self.memory_system) },
+        };
         self.devices.tick(&mut self.memory_system, cpu_cycles, cassette_event_sink, video_frame_sink);
+        self.cycles += cpu_cycles as u64;
 
         cpu_cycles
     }
+
+    // Perform the ROM cassette routine about to be executed, if the cassette
+    // can provide the data right away.  If not, the routine runs normally.
+    fn cassette_trap(&mut self) -> Option<u32> {
+        match self.cpu.regs.pc {
+            ROM_CAS_READ_BYTE => {
+                match self.devices.cassette.fast_read_byte() {
+                    Some(byte) => {
+                        self.cpu.regs.a = byte;
+                        self.trap_return();
+                        Some(CAS_TRAP_CYCLES)
+                    },
+                    None => { None },
+                }
+            },
+            ROM_CAS_READ_SYNC if self.devices.cassette.fast_read_sync() => {
+                self.memory_system.write_byte(VID_ASTERISKS, b'*');
+                self.memory_system.write_byte(VID_ASTERISKS + 1, b'*');
+                self.trap_return();
+                Some(CAS_TRAP_CYCLES)
+            },
+            _ => { None },
+        }
+    }
+    fn trap_return(&mut self) {
+        self.cpu.regs.pc = self.memory_system.read_word(self.cpu.regs.sp);
+        self.cpu.regs.sp = self.cpu.regs.sp.wrapping_add(2);
+    }
 }
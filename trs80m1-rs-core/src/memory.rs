@@ -22,6 +22,11 @@ use std::path;
 use crate::keyboard;
 use crate::video;
 use crate::cassette;
+use crate::hires;
+use crate::joystick;
+use crate::bridge;
+use crate::psg;
+use crate::sound;
 
 
 // Memory layout:
@@ -37,13 +42,31 @@ pub const RAM_BASE: u16 = 0x4000;
 pub const KBD_BASE: u16 = 0x3800;
 use keyboard::KBD_MEM_SIZE as KBD_SIZE;
 
+fn in_kbd(addr: u16) -> bool {
+    (KBD_BASE..=(KBD_BASE + (KBD_SIZE - 1))).contains(&addr)
+}
+
 // Video display:
 pub const VID_BASE: u16 = 0x3C00;
 use video::VID_MEM_SIZE as VID_SIZE;
 
+fn in_vid(addr: u16) -> bool {
+    (VID_BASE..=(VID_BASE + (VID_SIZE - 1))).contains(&addr)
+}
+
 // Combined cassette and "mode select" IO port:
 pub const CAS_MODESEL_BASE: u16 = 0xff;
 
+// Orchestra-85 music card, right channel at 0xB5, left channel at 0xB9:
+pub const ORCH_BASE: u16 = 0xb5;
+pub const ORCH_SIZE: u16 = 5;
+
+// The latch switching in the alternate character set of the Japanese and
+// the European character generators, bit 3 of port 0xEC, the same as in the
+// Model III.  It's only there with such a character generator.
+pub const ALTCHR_BASE: u16 = 0xec;
+const ALTCHR_SELECT: u8 = 0b0000_1000;
+
 // A memory device is one that implements the read and write operations.
 pub trait MemIO {
     fn read_byte(&mut self, addr: u16) -> u8;
@@ -342,6 +365,11 @@ pub struct MemorySystem {
     pub vid_mem:  video::VideoMemory,
 
     pub cas_rec:  cassette::CassetteIO,
+    pub orch:     sound::OrchestraIO,
+    pub ay:       psg::AyIO,
+    pub hires:    hires::HiResIO,
+    pub joystick: joystick::JoystickIO,
+    pub bridges:  bridge::BridgeIO,
 
     // The interrupt request interface is a part of the memory system, to
     // allow any peripheral on the system bus to be able to issue an interrupt
@@ -359,7 +387,7 @@ pub struct MemorySystem {
 }
 
 impl MemorySystem {
-    pub fn new(ram_size: u16, rom_choice: Option<path::PathBuf>, lowercase_mod: bool) -> MemorySystem {
+    pub fn new(ram_size: u16, rom_choice: Vec<(path::PathBuf, u16)>, lowercase_mod: bool) -> MemorySystem {
 
         let mut memory_system = MemorySystem {
             ram_chip:          RamChip::new("system ram".to_owned(), ram_size, RAM_BASE),
@@ -367,6 +395,11 @@ impl MemorySystem {
             kbd_mem:           keyboard::KeyboardMemory::new(KBD_BASE),
             vid_mem:           video::VideoMemory::new(lowercase_mod, VID_BASE),
             cas_rec:           cassette::CassetteIO::new(),
+            orch:              sound::OrchestraIO::new(),
+            ay:                psg::AyIO::new(),
+            hires:             hires::HiResIO::new(),
+            joystick:          joystick::JoystickIO::new(),
+            bridges:           bridge::BridgeIO::new(),
             nmi_request:       false,
             int_request:       false,
 
@@ -379,23 +412,31 @@ impl MemorySystem {
     }
     pub fn power_off(&mut self) {
         self.ram_chip.wipe();
+        self.orch.power_off();
+        self.ay.power_off();
+        self.hires.power_off();
         self.nmi_request = false;
         self.int_request = false;
     }
-    pub fn load_system_rom(&mut self, rom_choice: Option<path::PathBuf>) {
+    // The system rom can be put together from several files, like the dumps
+    // of the separate rom chips, each loaded at its offset.  The parts of the
+    // rom no file covers are left blank.
+    pub fn load_system_rom(&mut self, rom_choice: Vec<(path::PathBuf, u16)>) {
 
         let dummy_rom = include_bytes!("dummy_rom/dummy.rom");
-        match rom_choice {
-            Some(rom_file_path) => {
-                if !self.rom_chip.load_from_file(&rom_file_path, 0) {
-                    warn!("Loading the specified rom file failed, resorting to using the built-in dummy rom.");
-                    self.rom_chip.load_from_buffer(dummy_rom, "built-in dummy rom file", 0);
-                }
-            },
-            None => {
-                warn!("No system rom file specified, using a buit-in dummy.");
+        if rom_choice.is_empty() {
+            warn!("No system rom file specified, using a buit-in dummy.");
+            self.rom_chip.load_from_buffer(dummy_rom, "built-in dummy rom file", 0);
+            return;
+        }
+        self.rom_chip.wipe();
+        for (rom_file_path, offset) in rom_choice {
+            if !self.rom_chip.load_from_file(&rom_file_path, offset) {
+                warn!("Loading the specified rom file failed, resorting to using the built-in dummy rom.");
+                self.rom_chip.wipe();
                 self.rom_chip.load_from_buffer(dummy_rom, "built-in dummy rom file", 0);
-            },
+                return;
+            }
         }
     }
 
@@ -407,18 +448,39 @@ impl MemorySystem {
     pub fn reti_notify(&mut self) {
         // Currently, no device needs reti notification.
     }
+
+    fn device_read_byte(&mut self, addr: u16) -> Option<u8> {
+        if addr >= RAM_BASE && addr <= (RAM_BASE + ((self.ram_chip.data.len() as u16) - 1)) {
+            Some(self.ram_chip.read_byte(addr - RAM_BASE))
+        } else if addr >= ROM_BASE && addr <= (ROM_BASE + (ROM_SIZE - 1)) {
+            Some(self.rom_chip.read_byte(addr - ROM_BASE))
+        } else if in_kbd(addr) {
+            Some(self.kbd_mem.read_byte(addr - KBD_BASE))
+        } else if in_vid(addr) {
+            Some(self.vid_mem.read_byte(addr - VID_BASE))
+        } else {
+            None
+        }
+    }
+
+    // Read a byte like the CPU would, but without complaining about
+    // addresses which don't belong to any device, for inspecting the memory
+    // from the outside, like in the debugger.
+    //
+    pub fn peek_byte(&mut self, addr: u16) -> u8 {
+        self.device_read_byte(addr).unwrap_or(0xFF)
+    }
 }
 
 impl MemIO for MemorySystem {
     fn read_byte(&mut self, addr: u16) -> u8 {
-        if addr >= RAM_BASE && addr <= (RAM_BASE + ((self.ram_chip.data.len() as u16) - 1)) {
-            self.ram_chip.read_byte(addr - RAM_BASE)
-        } else if addr >= ROM_BASE && addr <= (ROM_BASE + (ROM_SIZE - 1)) {
-            self.rom_chip.read_byte(addr - ROM_BASE)
-        } else if addr >= KBD_BASE && addr <= (KBD_BASE + (KBD_SIZE - 1)) {
-            self.kbd_mem.read_byte(addr - KBD_BASE)
-        } else if addr >= VID_BASE && addr <= (VID_BASE + (VID_SIZE - 1)) {
-            self.vid_mem.read_byte(addr - VID_BASE)
+        if let Some(val) = self.device_read_byte(addr) {
+            if in_vid(addr) {
+                self.vid_mem.bus_access(val);
+            } else if in_kbd(addr) {
+                self.kbd_mem.scan(addr - KBD_BASE);
+            }
+            val
         } else {
             warn!("Failed read: Address 0x{:04X} doesn't belong to any installed device.", addr);
 
@@ -433,10 +495,11 @@ impl MemIO for MemorySystem {
             self.ram_chip.write_byte(addr - RAM_BASE, val);
         } else if addr >= ROM_BASE && addr <= (ROM_BASE + (ROM_SIZE - 1)) {
             self.rom_chip.write_byte(addr - ROM_BASE, val);
-        } else if addr >= KBD_BASE && addr <= (KBD_BASE + (KBD_SIZE - 1)) {
+        } else if in_kbd(addr) {
             self.kbd_mem.write_byte(addr - KBD_BASE, val);
-        } else if addr >= VID_BASE && addr <= (VID_BASE + (VID_SIZE - 1)) {
+        } else if in_vid(addr) {
             self.vid_mem.write_byte(addr - VID_BASE, val);
+            self.vid_mem.bus_access(val);
         } else {
             warn!("Failed write of 0x{:02X}: Address 0x{:04X} doesn't belong to any installed device.", val, addr);
         }
@@ -454,6 +517,15 @@ impl PeripheralIO for MemorySystem {
             }
 
             val
+        } else if let Some(offset) = self.ay.port_offset(port) {
+            self.ay.peripheral_read_byte(offset)
+        } else if let Some(offset) = self.joystick.port_offset(port) {
+            self.joystick.peripheral_read_byte(offset)
+        } else if let Some(offset) = self.hires.port_offset(port) {
+            // After the joystick, which can share the write-only port 0x00.
+            self.hires.peripheral_read_byte(offset)
+        } else if let Some(offset) = self.bridges.port_offset(port) {
+            self.bridges.peripheral_read_byte(offset)
         } else {
             warn!("Failed read: Port 0x{:02X} doesn't belong to any installed peripheral device.", port);
 
@@ -466,6 +538,18 @@ impl PeripheralIO for MemorySystem {
         if port == CAS_MODESEL_BASE {
             self.vid_mem.modesel = (val & 0b0000_1000) != 0;
             self.cas_rec.peripheral_write_byte(port - CAS_MODESEL_BASE, val);
+        } else if port == ORCH_BASE || port == (ORCH_BASE + (ORCH_SIZE - 1)) {
+            self.orch.peripheral_write_byte(port - ORCH_BASE, val);
+        } else if port == ALTCHR_BASE && self.vid_mem.alternate_installed {
+            self.vid_mem.alternate = (val & ALTCHR_SELECT) != 0;
+        } else if let Some(offset) = self.hires.port_offset(port) {
+            self.hires.peripheral_write_byte(offset, val);
+        } else if let Some(offset) = self.ay.port_offset(port) {
+            self.ay.peripheral_write_byte(offset, val);
+        } else if let Some(offset) = self.joystick.port_offset(port) {
+            self.joystick.peripheral_write_byte(offset, val);
+        } else if let Some(offset) = self.bridges.port_offset(port) {
+            self.bridges.peripheral_write_byte(offset, val);
         } else {
             warn!("Failed write of 0x{:02X}: Port 0x{:02X} doesn't belong to any installed peripheral device.", val, port);
         }
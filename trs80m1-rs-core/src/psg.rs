@@ -0,0 +1,244 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::memory::PeripheralIO;
+
+
+// Emulation of an add-on sound board built around the AY-3-8910 programmable
+// sound generator:
+//
+// The board takes up two IO ports, writing to the first one selects one of
+// the chip's 16 registers, and the second one is used to write and read the
+// selected register.  The chip is clocked from the system bus, so it runs at
+// the CPU clock rate.
+//
+// This is how the chip is wired in the Colour Genie (EACA EG2000), a Model I
+// compatible with the sound built in, whose programs are the most common
+// ones using it.  Its ports, F8h for the register select and F9h for the
+// data, are the default ones, as given in the IO map of the Colour Genie
+// technical manual, and in the `cgenie' driver of MAME.
+//
+// The chip has three square wave tone generators, a noise generator and an
+// envelope generator.  Each of the three channels mixes its tone generator
+// with the noise generator, as set in the mixer register, and its volume is
+// either fixed, or follows the envelope.  The volume steps are logarithmic.
+//
+// The tone and noise generators count in steps of 8 clock cycles, the square
+// wave of a tone generator flips every `period' steps, the noise generator
+// shifts every 2 * `period' steps, and the envelope moves on to the next of
+// its 16 levels every 2 * `period' steps.
+//
+const CLOCK_STEP:       u32 = 8;
+
+pub const DEFAULT_PORT: u8  = 0xF8; // Of the Colour Genie.
+
+const REG_TONE_A_FINE:  usize = 0;
+const REG_NOISE_PERIOD: usize = 6;
+const REG_MIXER:        usize = 7;
+const REG_AMPLITUDE_A:  usize = 8;
+const REG_ENV_FINE:     usize = 11;
+const REG_ENV_SHAPE:    usize = 13;
+
+const ENV_HOLD:         u8 = 0b0001;
+const ENV_ALTERNATE:    u8 = 0b0010;
+const ENV_ATTACK:       u8 = 0b0100;
+const ENV_CONTINUE:     u8 = 0b1000;
+
+// The unused bits of each of the registers, which read back as zeros:
+const REG_MASKS: [u8; 16] = [0xFF, 0x0F, 0xFF, 0x0F, 0xFF, 0x0F, 0x1F, 0xFF,
+                             0x1F, 0x1F, 0x1F, 0xFF, 0xFF, 0x0F, 0xFF, 0xFF];
+
+// The output level for each of the volume steps, relative to 1000:
+const LEVELS: [i64; 16] = [0, 14, 21, 29, 42, 62, 85, 137, 169, 265, 353, 450, 570, 687, 848, 1000];
+
+pub struct AyIO {
+    port:          Option<u8>,   // The register select port, None if absent.
+    registers:     [u8; 16],
+    selected:      usize,
+
+    cycles:        u32,          // Clock cycles left over from the last step.
+    tone_count:    [u32; 3],
+    tone_output:   [bool; 3],
+    noise_count:   u32,
+    noise_shift:   u32,          // 17-bit shift register.
+    env_count:     u32,
+    env_step:      u8,
+    env_attack:    bool,
+    env_holding:   bool,
+}
+
+impl Default for AyIO {
+    fn default() -> AyIO {
+        AyIO::new()
+    }
+}
+
+impl AyIO {
+    pub fn new() -> AyIO {
+        AyIO {
+            port:        None,
+            registers:   [0; 16],
+            selected:    0,
+            cycles:      0,
+            tone_count:  [0; 3],
+            tone_output: [false; 3],
+            noise_count: 0,
+            noise_shift: 1,
+            env_count:   0,
+            env_step:    15,
+            env_attack:  false,
+            env_holding: true,
+        }
+    }
+    pub fn power_off(&mut self) {
+        let port = self.port;
+        *self = AyIO::new();
+        self.port = port;
+    }
+    // Install the board at the given register select port, or remove it.
+    pub fn set_port(&mut self, port: Option<u8>) {
+        if port != self.port {
+            self.power_off();
+            self.port = port;
+        }
+    }
+    pub fn port(&self) -> Option<u8> {
+        self.port
+    }
+    // The offset of the given port on the board, if it belongs to it.
+    pub fn port_offset(&self, port: u16) -> Option<u16> {
+        match self.port {
+            Some(base) if port == (base as u16) || port == ((base as u16) + 1) => {
+                Some(port - (base as u16))
+            },
+            _ => { None },
+        }
+    }
+
+    fn period(&self, fine: usize) -> u32 {
+        let period = ((self.registers[fine + 1] as u32) << 8) | (self.registers[fine] as u32);
+        period.max(1)
+    }
+    fn restart_envelope(&mut self) {
+        self.env_count   = 0;
+        self.env_step    = 0;
+        self.env_attack  = (self.registers[REG_ENV_SHAPE] & ENV_ATTACK) != 0;
+        self.env_holding = false;
+    }
+    fn step_envelope(&mut self) {
+        if self.env_holding {
+            return;
+        }
+        if self.env_step < 15 {
+            self.env_step += 1;
+            return;
+        }
+        let shape = self.registers[REG_ENV_SHAPE];
+        if (shape & ENV_CONTINUE) == 0 {
+            self.env_attack  = false;
+            self.env_holding = true;
+        } else {
+            if (shape & ENV_ALTERNATE) != 0 {
+                self.env_attack = !self.env_attack;
+            }
+            if (shape & ENV_HOLD) != 0 {
+                self.env_holding = true;
+            } else {
+                self.env_step = 0;
+            }
+        }
+    }
+    fn envelope_volume(&self) -> usize {
+        if self.env_attack { self.env_step as usize } else { (15 - self.env_step) as usize }
+    }
+
+    // Advance the generators by the given number of clock cycles.
+    pub fn tick(&mut self, cycles: u32) {
+        if self.port.is_none() {
+            return;
+        }
+        self.cycles += cycles;
+        while self.cycles >= CLOCK_STEP {
+            self.cycles -= CLOCK_STEP;
+
+            for channel in 0..3 {
+                self.tone_count[channel] += 1;
+                if self.tone_count[channel] >= self.period(REG_TONE_A_FINE + channel * 2) {
+                    self.tone_count[channel] = 0;
+                    self.tone_output[channel] = !self.tone_output[channel];
+                }
+            }
+
+            self.noise_count += 1;
+            if self.noise_count >= ((self.registers[REG_NOISE_PERIOD] as u32).max(1) * 2) {
+                self.noise_count = 0;
+                let feedback = (self.noise_shift ^ (self.noise_shift >> 3)) & 1;
+                self.noise_shift = (self.noise_shift >> 1) | (feedback << 16);
+            }
+
+            self.env_count += 1;
+            if self.env_count >= (self.period(REG_ENV_FINE) * 2) {
+                self.env_count = 0;
+                self.step_envelope();
+            }
+        }
+    }
+
+    // The three channels mixed together, scaled to the given amplitude per
+    // channel.
+    pub fn value(&self, amplitude: i64) -> i64 {
+        if self.port.is_none() {
+            return 0;
+        }
+        let mixer = self.registers[REG_MIXER];
+        let noise = (self.noise_shift & 1) != 0;
+        let mut total = 0;
+
+        for channel in 0..3 {
+            let tone_on  = self.tone_output[channel] || (mixer & (0b0000_0001 << channel)) != 0;
+            let noise_on = noise || (mixer & (0b0000_1000 << channel)) != 0;
+            if tone_on && noise_on {
+                let amplitude_reg = self.registers[REG_AMPLITUDE_A + channel];
+                let volume = if (amplitude_reg & 0x10) != 0 { self.envelope_volume() } else { (amplitude_reg & 0x0F) as usize };
+                total += LEVELS[volume];
+            }
+        }
+        (total * amplitude) / 1000
+    }
+}
+
+impl PeripheralIO for AyIO {
+    fn peripheral_read_byte(&mut self, offset: u16) -> u8 {
+        match offset {
+            1 => { self.registers[self.selected] },
+            _ => { 0xFF },
+        }
+    }
+    fn peripheral_write_byte(&mut self, offset: u16, val: u8) {
+        match offset {
+            // Writes of register numbers past 15 are ignored by the chip.
+            0 if val < 16 => {
+                self.selected = val as usize;
+            },
+            1 => {
+                self.registers[self.selected] = val & REG_MASKS[self.selected];
+                if self.selected == REG_ENV_SHAPE {
+                    self.restart_envelope();
+                }
+            },
+            _ => {},
+        }
+    }
+}
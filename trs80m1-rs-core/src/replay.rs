@@ -0,0 +1,256 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::memory;
+use crate::snapshot;
+
+
+// A recording of the input of the emulated machine, to replay it exactly as
+// it happened, for reproducing bugs, for tool-assisted runs of games, or for
+// testing that a program still behaves the same:
+//
+// It starts with a snapshot of the machine, and goes on with the changes of
+// the rows of the keyboard matrix and of the joystick, each with the number
+// of T-states since the start at which the machine first saw it.  Whether
+// the keys were typed, pasted, or pressed by a script doesn't matter, only
+// what the CPU could read is recorded.  Restoring the snapshot and making
+// the changes at the same T-states runs the program the same way again, as
+// long as nothing else reaches the machine: the ROM and the size of the RAM
+// have to be the same, and the cassette, the disks and the port bridges are
+// best left out of it, see the snapshot module for what it doesn't have.
+//
+// The file starts with the magic and the version, the rest of it is
+// little-endian:
+//
+//     "TRS80M1I", 1
+//     the length of the recording in T-states - eight bytes
+//     the size of the snapshot - four bytes, followed by the snapshot
+//     the changes until the end of the file, each of them being:
+//         the T-state - eight bytes
+//         the input, 0 to 7 for the rows of the keyboard matrix and 8 for
+//         the joystick - a byte
+//         the new state of the input, the bits of the columns that are held
+//         down, or the JOY_* bits - a byte
+//
+
+const MAGIC:   &[u8] = b"TRS80M1I";
+const VERSION: u8 = 1;
+
+const INPUT_JOYSTICK: usize = 8;
+const INPUT_COUNT:    usize = 9;
+const CHANGE_SIZE:    usize = 10;
+
+struct InputChange {
+    cycle: u64,
+    input: u8,
+    state: u8,
+}
+
+pub struct InputRecording {
+    snapshot: snapshot::Snapshot,
+    length:   u64,
+    changes:  Vec<InputChange>,
+}
+
+impl InputRecording {
+    pub fn snapshot(&self) -> &snapshot::Snapshot {
+        &self.snapshot
+    }
+    // The length of the recording in T-states.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let snapshot = self.snapshot.to_bytes();
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.length.to_le_bytes());
+        bytes.extend_from_slice(&(snapshot.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&snapshot);
+        for change in &self.changes {
+            bytes.extend_from_slice(&change.cycle.to_le_bytes());
+            bytes.extend_from_slice(&[change.input, change.state]);
+        }
+        bytes
+    }
+    pub fn parse(bytes: &[u8]) -> Result<InputRecording, String> {
+        if !bytes.starts_with(MAGIC) {
+            return Err("it isn't an input recording of the emulator".to_owned());
+        }
+        let mut pos = MAGIC.len();
+        let mut take = |length: usize| -> Result<&[u8], String> {
+            let taken = bytes.get(pos..(pos + length)).ok_or_else(|| "the input recording is cut short".to_owned())?;
+            pos += length;
+            Ok(taken)
+        };
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(format!("the input recording is of version {}, only version {} is supported", version, VERSION));
+        }
+        let length = take(8)?;
+        let length = u64::from_le_bytes([length[0], length[1], length[2], length[3], length[4], length[5], length[6], length[7]]);
+        let snapshot_size = take(4)?;
+        let snapshot_size = u32::from_le_bytes([snapshot_size[0], snapshot_size[1], snapshot_size[2], snapshot_size[3]]);
+        let snapshot = snapshot::Snapshot::parse(take(snapshot_size as usize)?)?;
+
+        let rest = bytes[pos..].chunks_exact(CHANGE_SIZE);
+        if !rest.remainder().is_empty() {
+            return Err("the input recording is cut short".to_owned());
+        }
+        let mut changes = Vec::with_capacity(rest.len());
+        for change in rest {
+            let cycle = u64::from_le_bytes([change[0], change[1], change[2], change[3], change[4], change[5], change[6], change[7]]);
+            if (change[8] as usize) >= INPUT_COUNT {
+                return Err(format!("the input {} is invalid", change[8]));
+            }
+            if cycle > length || changes.last().map(|last: &InputChange| last.cycle > cycle).unwrap_or(false) {
+                return Err(format!("the change of the input at the T-state {} is out of order", cycle));
+            }
+            changes.push(InputChange { cycle, input: change[8], state: change[9] });
+        }
+
+        Ok(InputRecording { snapshot, length, changes })
+    }
+}
+
+// The inputs as the CPU sees them.
+fn read_inputs(memory_system: &memory::MemorySystem) -> [u8; INPUT_COUNT] {
+    let mut inputs = [0; INPUT_COUNT];
+    for (row, state) in inputs.iter_mut().take(INPUT_JOYSTICK).enumerate() {
+        *state = memory_system.kbd_mem.key_row(row);
+    }
+    inputs[INPUT_JOYSTICK] = memory_system.joystick.pressed();
+    inputs
+}
+fn write_inputs(memory_system: &mut memory::MemorySystem, inputs: &[u8; INPUT_COUNT]) {
+    for (row, state) in inputs.iter().take(INPUT_JOYSTICK).enumerate() {
+        memory_system.kbd_mem.set_key_row(row, *state);
+    }
+    memory_system.joystick.set_pressed(inputs[INPUT_JOYSTICK]);
+}
+
+// Both the recording and the replay start out with nothing held down, so
+// what's held down when the recording starts is its first change.
+enum State {
+    Idle,
+    Recording {
+        recording: InputRecording,
+        start:     u64,
+        inputs:    [u8; INPUT_COUNT],
+    },
+    Replaying {
+        recording: InputRecording,
+        start:     u64,
+        inputs:    [u8; INPUT_COUNT],
+        next:      usize,       // The index of the next change to make.
+    },
+}
+
+pub struct InputLog {
+    state: State,
+}
+
+impl Default for InputLog {
+    fn default() -> InputLog {
+        InputLog::new()
+    }
+}
+
+impl InputLog {
+    pub fn new() -> InputLog {
+        InputLog {
+            state: State::Idle,
+        }
+    }
+    pub fn recording(&self) -> bool {
+        matches!(self.state, State::Recording { .. })
+    }
+    pub fn replaying(&self) -> bool {
+        matches!(self.state, State::Replaying { .. })
+    }
+    // Start a recording from the given snapshot, at the given T-state of
+    // the machine.  A replay going on is stopped.
+    pub fn start_recording(&mut self, snapshot: snapshot::Snapshot, cycles: u64) {
+        self.state = State::Recording {
+            recording: InputRecording { snapshot, length: 0, changes: Vec::new() },
+            start:     cycles,
+            inputs:    [0; INPUT_COUNT],
+        };
+    }
+    // Returns None if nothing was being recorded.
+    pub fn finish_recording(&mut self, cycles: u64) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.state, State::Idle) {
+            State::Recording { mut recording, start, .. } => {
+                recording.length = cycles - start;
+                Some(recording)
+            },
+            other => {
+                self.state = other;
+                None
+            },
+        }
+    }
+    // The snapshot of the recording is expected to be restored already.
+    pub fn start_replay(&mut self, recording: InputRecording, cycles: u64) {
+        self.state = State::Replaying {
+            recording,
+            start:  cycles,
+            inputs: [0; INPUT_COUNT],
+            next:   0,
+        };
+    }
+    // Returns false if nothing was being replayed.  What the replay held
+    // down is released.
+    pub fn stop_replay(&mut self, memory_system: &mut memory::MemorySystem) -> bool {
+        if self.replaying() {
+            self.state = State::Idle;
+            write_inputs(memory_system, &[0; INPUT_COUNT]);
+            true
+        } else {
+            false
+        }
+    }
+    // Called before each step of the CPU, to note the changes of the inputs
+    // or to make the ones which are due, in place of what the keyboard and
+    // the joystick did since the previous step.
+    pub fn step(&mut self, cycles: u64, memory_system: &mut memory::MemorySystem) {
+        let finished = match self.state {
+            State::Idle => { false },
+            State::Recording { ref mut recording, start, ref mut inputs } => {
+                let current = read_inputs(memory_system);
+                for (input, (state, previous)) in current.iter().zip(inputs.iter()).enumerate() {
+                    if state != previous {
+                        recording.changes.push(InputChange { cycle: cycles - start, input: input as u8, state: *state });
+                    }
+                }
+                *inputs = current;
+                false
+            },
+            State::Replaying { ref recording, start, ref mut inputs, ref mut next } => {
+                let cycle = cycles - start;
+                while let Some(change) = recording.changes.get(*next).filter(|change| change.cycle <= cycle) {
+                    inputs[change.input as usize] = change.state;
+                    *next += 1;
+                }
+                write_inputs(memory_system, inputs);
+                cycle >= recording.length
+            },
+        };
+        if finished {
+            self.stop_replay(memory_system);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::machine;
+use crate::memory::MemIO;
+use crate::memory::MemoryChip;
+use crate::video;
+use crate::z80::cpu;
+
+
+// A snapshot of the emulated machine, to continue from where it was saved:
+// the registers of the CPU, the contents of the RAM and of the video memory,
+// and the mode of the screen.
+//
+// The rest of the machine isn't in it.  The ROM is the one selected in the
+// config file, a snapshot taken with a different one is only warned about,
+// and the cassette recorder, the keyboard, the sound and the graphics board
+// go on as they were before it was loaded.
+//
+// The file starts with the magic and the version, the rest of it is
+// little-endian:
+//
+//     "TRS80M1S", 1
+//     PC, SP, IX, IY, AF, BC, DE, HL, AF', BC', DE', HL' - two bytes each
+//     I, R, the interrupt mode, IFF1, IFF2, the EI delay, halted - a byte each
+//     the 32-column mode, the alternate character set - a byte each
+//     the sum of the bytes of the ROM - four bytes
+//     the video memory - 1024 bytes
+//     the size of the RAM - two bytes, followed by its contents
+//
+
+const MAGIC:   &[u8] = b"TRS80M1S";
+const VERSION: u8 = 1;
+
+pub struct Snapshot {
+    regs:         cpu::Z80Regs,
+    im:           u8,
+    iff1:         bool,
+    iff2:         bool,
+    int_enabled:  bool,
+    halted:       bool,
+    modesel:      bool,
+    alternate:    bool,
+    rom_checksum: u32,
+    video:        Vec<u8>,
+    ram:          Vec<u8>,
+}
+
+fn rom_checksum(machine: &machine::Machine) -> u32 {
+    machine.memory_system.rom_chip.chip_data().iter().fold(0u32, |sum, byte| sum.wrapping_add(*byte as u32))
+}
+
+impl Snapshot {
+    pub fn take(machine: &mut machine::Machine) -> Snapshot {
+        let video = (0..video::VID_MEM_SIZE).map(|offset| machine.memory_system.vid_mem.read_byte(offset)).collect();
+        let cpu = &machine.cpu;
+        let vid_mem = &machine.memory_system.vid_mem;
+
+        Snapshot {
+            regs:         cpu.regs.clone(),
+            im:           match cpu.im {
+                              cpu::InterruptMode::Mode0         => { 0 },
+                              cpu::InterruptMode::Mode1         => { 1 },
+                              cpu::InterruptMode::Mode2         => { 2 },
+                              cpu::InterruptMode::ModeUndefined => { 3 },
+                          },
+            iff1:         cpu.iff1,
+            iff2:         cpu.iff2,
+            int_enabled:  cpu.int_enabled,
+            halted:       cpu.halted,
+            modesel:      vid_mem.modesel,
+            alternate:    vid_mem.alternate,
+            rom_checksum: rom_checksum(machine),
+            video,
+            ram:          machine.memory_system.ram_chip.chip_data().to_vec(),
+        }
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let regs = &self.regs;
+        let af = ((regs.a as u16) << 8) | (regs.flags.to_byte() as u16);
+        let af_prime = ((regs.a_prime as u16) << 8) | (regs.flags_prime.to_byte() as u16);
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for word in [regs.pc, regs.sp, regs.ix, regs.iy, af, regs.bc, regs.de, regs.hl, af_prime, regs.bc_prime, regs.de_prime, regs.hl_prime] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[regs.i, regs.r, self.im, self.iff1 as u8, self.iff2 as u8, self.int_enabled as u8, self.halted as u8]);
+        bytes.extend_from_slice(&[self.modesel as u8, self.alternate as u8]);
+        bytes.extend_from_slice(&self.rom_checksum.to_le_bytes());
+        bytes.extend_from_slice(&self.video);
+        bytes.extend_from_slice(&(self.ram.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.ram);
+        bytes
+    }
+    pub fn parse(bytes: &[u8]) -> Result<Snapshot, String> {
+        if !bytes.starts_with(MAGIC) {
+            return Err("it isn't a snapshot of the emulator".to_owned());
+        }
+        let mut pos = MAGIC.len();
+        let mut take = |length: usize| -> Result<&[u8], String> {
+            let taken = bytes.get(pos..(pos + length)).ok_or_else(|| "the snapshot is cut short".to_owned())?;
+            pos += length;
+            Ok(taken)
+        };
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(format!("the snapshot is of version {}, only version {} is supported", version, VERSION));
+        }
+        let words: Vec<u16> = take(24)?.chunks(2).map(|word| u16::from_le_bytes([word[0], word[1]])).collect();
+        let flags = take(9)?.to_vec();
+        let rom_checksum = take(4)?;
+        let rom_checksum = u32::from_le_bytes([rom_checksum[0], rom_checksum[1], rom_checksum[2], rom_checksum[3]]);
+        let video = take(video::VID_MEM_SIZE as usize)?.to_vec();
+        let ram_size = take(2)?;
+        let ram_size = u16::from_le_bytes([ram_size[0], ram_size[1]]);
+        let ram = take(ram_size as usize)?.to_vec();
+
+        if flags[2] > 3 {
+            return Err(format!("the interrupt mode {} is invalid", flags[2]));
+        }
+        let regs = cpu::Z80Regs {
+            pc:          words[0],
+            sp:          words[1],
+            ix:          words[2],
+            iy:          words[3],
+            a:           (words[4] >> 8) as u8,
+            flags:       cpu::Z80Flags::from_byte(words[4] as u8),
+            bc:          words[5],
+            de:          words[6],
+            hl:          words[7],
+            a_prime:     (words[8] >> 8) as u8,
+            flags_prime: cpu::Z80Flags::from_byte(words[8] as u8),
+            bc_prime:    words[9],
+            de_prime:    words[10],
+            hl_prime:    words[11],
+            i:           flags[0],
+            r:           flags[1],
+        };
+        Ok(Snapshot {
+            regs,
+            im:           flags[2],
+            iff1:         flags[3] != 0,
+            iff2:         flags[4] != 0,
+            int_enabled:  flags[5] != 0,
+            halted:       flags[6] != 0,
+            modesel:      flags[7] != 0,
+            alternate:    flags[8] != 0,
+            rom_checksum,
+            video,
+            ram,
+        })
+    }
+    // Whether the ROM of the machine is the one the snapshot was taken with.
+    pub fn rom_matches(&self, machine: &machine::Machine) -> bool {
+        self.rom_checksum == rom_checksum(machine)
+    }
+    // The RAM of the machine has to be as large as it was, the rest of it
+    // is only changed if it is.
+    pub fn restore(&self, machine: &mut machine::Machine) -> Result<(), String> {
+        let ram = machine.memory_system.ram_chip.chip_data_mut();
+        if ram.len() != self.ram.len() {
+            return Err(format!("the snapshot was taken with {}K of RAM, the emulated machine has {}K", self.ram.len() / 1024, ram.len() / 1024));
+        }
+        ram.copy_from_slice(&self.ram);
+
+        let vid_mem = &mut machine.memory_system.vid_mem;
+        for (offset, byte) in self.video.iter().enumerate() {
+            vid_mem.write_byte(offset as u16, *byte);
+        }
+        vid_mem.modesel = self.modesel;
+        vid_mem.alternate = self.alternate && vid_mem.alternate_installed;
+
+        let cpu = &mut machine.cpu;
+        cpu.regs = self.regs.clone();
+        cpu.im = match self.im {
+            0 => { cpu::InterruptMode::Mode0 },
+            1 => { cpu::InterruptMode::Mode1 },
+            2 => { cpu::InterruptMode::Mode2 },
+            _ => { cpu::InterruptMode::ModeUndefined },
+        };
+        cpu.iff1 = self.iff1;
+        cpu.iff2 = self.iff2;
+        cpu.int_enabled = self.int_enabled;
+        cpu.halted = self.halted;
+        cpu.added_delay = 0;
+
+        machine.memory_system.nmi_request = false;
+        machine.memory_system.int_request = false;
+        Ok(())
+    }
+}
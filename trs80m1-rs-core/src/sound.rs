@@ -0,0 +1,289 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::fs;
+use std::io;
+use std::io::{Seek, Write};
+use std::path;
+
+use crate::cassette;
+use crate::machine;
+use crate::memory::PeripheralIO;
+use crate::psg;
+
+
+// Sound generation from the cassette port signal:
+//
+// The machine has no sound hardware, but the signal going to and coming from
+// the cassette recorder can be listened to, and many programs toggle the
+// cassette output to make sounds.  The signal is counted as tape noise while
+// the cassette motor runs, and as the program's own sound otherwise.
+//
+// The Orchestra-85 music card adds two 8-bit DACs, for the left and right
+// channels, the sound output is mono, so they're mixed together.  An
+// AY-3-8910 sound board can be installed as well.  Each of the four sources
+// can be muted on its own.
+//
+// With the high resampling quality, the signal level is integrated over the
+// span of each sample, with the low one, the level at the end of the span is
+// taken as it is, which keeps the edges of the waveform sharp, at the cost of
+// high-pitched sounds getting aliased.  Either way, the samples are then
+// passed through a DC blocking filter, so that a level held for a long time
+// fades into silence instead of causing clicks.
+//
+pub const SAMPLE_RATE:    u32 = 44_100;
+
+const AMPLITUDE:          i64 = 8_192;
+const DC_BLOCK_POLE:      f32 = 0.995;
+
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum Resampling {
+    Low,
+    High,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Source {
+    Tape,
+    Program,
+    Orchestra,
+    Ay,
+}
+
+// The ports of the Orchestra-85 card, relative to the first one:
+const ORCH_RIGHT_OFFSET: u16 = 0;
+const ORCH_LEFT_OFFSET:  u16 = 4;
+
+pub struct OrchestraIO {
+    left:   u8,
+    right:  u8,
+}
+
+impl Default for OrchestraIO {
+    fn default() -> OrchestraIO {
+        OrchestraIO::new()
+    }
+}
+
+impl OrchestraIO {
+    pub fn new() -> OrchestraIO {
+        OrchestraIO {
+            left:  0,
+            right: 0,
+        }
+    }
+    pub fn power_off(&mut self) {
+        self.left  = 0;
+        self.right = 0;
+    }
+    // Both channels mixed together, scaled to the amplitude of the output.
+    fn value(&self) -> i64 {
+        (((self.left as i8) as i64) + ((self.right as i8) as i64)) * AMPLITUDE / 256
+    }
+}
+
+impl PeripheralIO for OrchestraIO {
+    fn peripheral_read_byte(&mut self, _offset: u16) -> u8 {
+        // The DACs are write-only.
+        0xFF
+    }
+    fn peripheral_write_byte(&mut self, offset: u16, val: u8) {
+        match offset {
+            ORCH_RIGHT_OFFSET => { self.right = val; },
+            ORCH_LEFT_OFFSET  => { self.left  = val; },
+            _ => {},
+        }
+    }
+}
+
+pub struct SoundOutput {
+    enabled:      bool,
+    sample_rate:  u32,
+    resampling:   Resampling,
+    muted:        [bool; 4],
+
+    // Progress within the current sample, in units of 1/(CPU_HZ*sample_rate)
+    // of a second, and the signal level integrated over it:
+    phase:        u64,
+    accumulator:  i64,
+
+    // The DC blocking filter state:
+    prev_input:   f32,
+    prev_output:  f32,
+
+    samples:      Vec<i16>,
+}
+
+impl Default for SoundOutput {
+    fn default() -> SoundOutput {
+        SoundOutput::new()
+    }
+}
+
+impl SoundOutput {
+    pub fn new() -> SoundOutput {
+        SoundOutput {
+            enabled:     false,
+            sample_rate: SAMPLE_RATE,
+            resampling:  Resampling::High,
+            muted:       [false; 4],
+            phase:       0,
+            accumulator: 0,
+            prev_input:  0.0,
+            prev_output: 0.0,
+            samples:     Vec::new(),
+        }
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled != self.enabled {
+            self.enabled     = enabled;
+            self.phase       = 0;
+            self.accumulator = 0;
+            self.prev_input  = 0.0;
+            self.prev_output = 0.0;
+            self.samples.clear();
+        }
+    }
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn set_format(&mut self, sample_rate: u32, resampling: Resampling) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.phase       = 0;
+            self.accumulator = 0;
+            self.samples.clear();
+        }
+        self.resampling = resampling;
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn set_muted(&mut self, source: Source, muted: bool) {
+        self.muted[source as usize] = muted;
+    }
+    pub fn muted(&self, source: Source) -> bool {
+        self.muted[source as usize]
+    }
+
+    // Advance by the given number of cycles spent at a cassette signal level
+    // (0 for silence, 1 for the positive and 2 for the negative level), which
+    // comes from either the tape or the program, and with the Orchestra DACs
+    // and the AY-3-8910 at their current outputs.
+    pub fn tick(&mut self, source: Source, level: i8, orchestra: &OrchestraIO, ay: &psg::AyIO, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+        let mut value = 0;
+        if !self.muted(source) {
+            value += match level {
+                1 => {  AMPLITUDE },
+                2 => { -AMPLITUDE },
+                _ => { 0 },
+            };
+        }
+        if !self.muted(Source::Orchestra) {
+            value += orchestra.value();
+        }
+        if !self.muted(Source::Ay) {
+            value += ay.value(AMPLITUDE * 2 / 3);
+        }
+        let sample_span = machine::CPU_HZ as u64;
+        let mut units = (cycles as u64) * (self.sample_rate as u64);
+
+        while units > 0 {
+            let remaining = sample_span - self.phase;
+            if units >= remaining {
+                self.accumulator += value * (remaining as i64);
+                units -= remaining;
+
+                let input = match self.resampling {
+                    Resampling::High => { (self.accumulator as f32) / (sample_span as f32) },
+                    Resampling::Low  => { value as f32 },
+                };
+                self.prev_output = input - self.prev_input + DC_BLOCK_POLE * self.prev_output;
+                self.prev_input  = input;
+
+                // Samples that weren't picked up within a second are thrown
+                // away.
+                if self.samples.len() < (self.sample_rate as usize) {
+                    self.samples.push(self.prev_output as i16);
+                }
+
+                self.phase       = 0;
+                self.accumulator = 0;
+            } else {
+                self.accumulator += value * (units as i64);
+                self.phase += units;
+                units = 0;
+            }
+        }
+    }
+
+    // Hand over the samples generated so far.
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+}
+
+// Recording of the generated samples into a 16-bit mono WAV file.  The file
+// is written out as the samples come in, and the sizes in the header are
+// filled in once the recording is finished.
+pub struct WavWriter {
+    file:         io::BufWriter<fs::File>,
+    sample_rate:  u32,
+    samples:      usize,
+}
+
+// The most samples a WAV file can hold, given the 32-bit chunk sizes:
+const WAV_MAX_SAMPLES: usize = ((u32::MAX - 36) / 2) as usize;
+
+impl WavWriter {
+    pub fn create(file_path: &path::Path, sample_rate: u32) -> io::Result<WavWriter> {
+        let mut file = io::BufWriter::new(fs::File::create(file_path)?);
+        file.write_all(&cassette::wav_header(sample_rate, 1, 16, 0))?;
+
+        Ok(WavWriter {
+            file,
+            sample_rate,
+            samples: 0,
+        })
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+    pub fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        if (self.samples + samples.len()) > WAV_MAX_SAMPLES {
+            return Err(io::Error::other("the WAV file is full"));
+        }
+        let mut data = Vec::with_capacity(samples.len() * 2);
+        for sample in samples {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        self.file.write_all(&data)?;
+        self.samples += samples.len();
+        Ok(())
+    }
+    // Fill in the header, returns the number of samples recorded.
+    pub fn finish(mut self) -> io::Result<usize> {
+        self.file.seek(io::SeekFrom::Start(0))?;
+        self.file.write_all(&cassette::wav_header(self.sample_rate, 1, 16, self.samples * 2))?;
+        self.file.flush()?;
+        Ok(self.samples)
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+
+// Recognition of the recordings stored in CAS files.
+//
+// Each recording starts with a leader of zero bytes, followed by the 0xA5
+// sync byte.  What comes after depends on what was saved:
+//
+//     BASIC programs (CSAVE):
+//         0xD3 0xD3 0xD3, a single character program name, and the
+//         tokenized program lines.  Each line starts with the address of
+//         the next line and the line number (both little-endian), and
+//         ends with a zero byte.  A next line address of zero ends the
+//         program.
+//
+//     Machine language programs (SYSTEM tapes):
+//         0x55 and a six character file name, padded with spaces, then
+//         data blocks made up of 0x3C, a byte count (0 meaning 256),
+//         the load address (little-endian), the data and a checksum.
+//         The program ends with 0x78 and the entry point address.
+//
+// Anything else (BASIC data files, Level I tapes) is reported as unknown,
+// and is assumed to last until the next leader.
+//
+
+pub const SYNC_BYTE:      u8    = 0xA5;
+const BASIC_HEADER:       u8    = 0xD3;
+const SYSTEM_HEADER:      u8    = 0x55;
+const SYSTEM_DATA_BLOCK:  u8    = 0x3C;
+const SYSTEM_ENTRY_BLOCK: u8    = 0x78;
+const SYSTEM_NAME_LEN:    usize = 6;
+
+// The minimum number of zero bytes for a leader within unknown data:
+const MIN_LEADER_LEN:     usize = 16;
+
+// The length of the leader written by the ROM, used for new recordings:
+pub const LEADER_LEN:     usize = 256;
+
+pub enum RecordingKind {
+    Basic   { name: char },
+    System  { name: String, load_address: Option<u16>, entry_point: Option<u16> },
+    Unknown,
+}
+
+pub struct Recording {
+    pub offset:  usize,  // Start of the leader.
+    pub data:    usize,  // First byte after the sync byte.
+    pub end:     usize,  // First byte after the recording.
+    pub kind:    RecordingKind,
+}
+
+impl Recording {
+    // The name the program was saved under, if any.
+    pub fn name(&self) -> Option<String> {
+        match self.kind {
+            RecordingKind::Basic { name } => {
+                if name == ' ' || name == '\0' { None } else { Some(name.to_string()) }
+            },
+            RecordingKind::System { ref name, .. } => {
+                if name.is_empty() { None } else { Some(name.clone()) }
+            },
+            RecordingKind::Unknown => { None },
+        }
+    }
+    pub fn description(&self) -> String {
+        match self.kind {
+            RecordingKind::Basic { name } => {
+                if name == ' ' || name == '\0' {
+                    "BASIC program".to_owned()
+                } else {
+                    format!("BASIC program `{}'", name)
+                }
+            },
+            RecordingKind::System { ref name, load_address, entry_point } => {
+                let mut description = format!("SYSTEM file `{}'", name);
+                if let Some(address) = load_address {
+                    description.push_str(&format!(", loads at 0x{:04X}", address));
+                }
+                if let Some(address) = entry_point {
+                    description.push_str(&format!(", entry point 0x{:04X}", address));
+                }
+                description
+            },
+            RecordingKind::Unknown => {
+                "unknown data".to_owned()
+            },
+        }
+    }
+}
+
+// Find all the recordings on a CAS tape.
+pub fn scan_cas(buffer: &[u8]) -> Vec<Recording> {
+    let mut recordings = Vec::new();
+    let mut position = 0;
+
+    while position < buffer.len() {
+        let offset = position;
+        while position < buffer.len() && buffer[position] == 0x00 {
+            position += 1;
+        }
+        if position >= buffer.len() {
+            break;
+        }
+        if buffer[position] != SYNC_BYTE {
+            position += 1;
+            continue;
+        }
+
+        let data = position + 1;
+        let (kind, end) = if buffer[data..].starts_with(&[BASIC_HEADER; 3]) {
+            scan_basic(buffer, data + 3)
+        } else if buffer[data..].first() == Some(&SYSTEM_HEADER) {
+            scan_system(buffer, data + 1)
+        } else {
+            (RecordingKind::Unknown, find_next_leader(buffer, data))
+        };
+
+        recordings.push(Recording { offset, data, end, kind });
+        position = end.max(data);
+    }
+    recordings
+}
+
+// Line up the recordings on a CAS tape with their sync bytes, if they're
+// shifted by a few bits, as in tapes written by some older tools.  Returns
+// None if the first recording on the tape is lined up already, in which case
+// the tape is assumed to be fine.
+pub fn align_cas(buffer: &[u8]) -> Option<Vec<u8>> {
+    let (_, first_sync) = find_sync_bit(buffer, 0)?;
+    if first_sync % 8 == 0 {
+        return None;
+    }
+
+    // Anything in front of the first leader is kept as it is.
+    let mut aligned = Vec::with_capacity(buffer.len());
+    let mut position = 0;
+    loop {
+        match find_sync_bit(buffer, position) {
+            Some((leader_start, sync)) => {
+                pack_bits(buffer, position, leader_start, &mut aligned);
+                aligned.resize(aligned.len() + (sync - leader_start) / 8, 0x00);
+                aligned.push(SYNC_BYTE);
+                position = sync + 8;
+            },
+            None => {
+                pack_bits(buffer, position, buffer.len() * 8, &mut aligned);
+                break;
+            },
+        }
+    }
+    Some(aligned)
+}
+
+fn get_bit(buffer: &[u8], bit: usize) -> u8 {
+    (buffer[bit / 8] >> (7 - (bit % 8))) & 0x01
+}
+
+// Find the next leader followed by a sync byte at any bit position, returns
+// the bit positions of the start of the leader and of the sync byte.
+fn find_sync_bit(buffer: &[u8], start: usize) -> Option<(usize, usize)> {
+    let total_bits = buffer.len() * 8;
+    let mut zero_bits = 0;
+    let mut bit = start;
+
+    while (bit + 8) <= total_bits {
+        if zero_bits >= (MIN_LEADER_LEN * 8) {
+            let byte = (0..8).fold(0u8, |byte, index| (byte << 1) | get_bit(buffer, bit + index));
+            if byte == SYNC_BYTE {
+                return Some((bit - zero_bits, bit));
+            }
+        }
+        if get_bit(buffer, bit) == 0 {
+            zero_bits += 1;
+        } else {
+            zero_bits = 0;
+        }
+        bit += 1;
+    }
+    None
+}
+
+// Append the bits from start to end, the last byte is padded with zeros.
+fn pack_bits(buffer: &[u8], start: usize, end: usize, output: &mut Vec<u8>) {
+    let mut bit = start;
+
+    while bit < end {
+        let mut byte = 0;
+        for index in 0..8 {
+            let value = if (bit + index) < end { get_bit(buffer, bit + index) } else { 0 };
+            byte = (byte << 1) | value;
+        }
+        output.push(byte);
+        bit += 8;
+    }
+}
+
+// A copy of a recording with a leader of the standard length, as the ROM
+// would have written it.
+pub fn standard_recording(buffer: &[u8], recording: &Recording) -> Vec<u8> {
+    let end = recording.end.min(buffer.len());
+    let mut standard = vec![0x00; LEADER_LEN];
+
+    standard.push(SYNC_BYTE);
+    standard.extend_from_slice(&buffer[recording.data.min(end)..end]);
+    standard
+}
+
+// The offsets of the blocks of a SYSTEM recording with a wrong checksum,
+// which is the sum of the load address and the data bytes.
+pub fn bad_checksums(buffer: &[u8], recording: &Recording) -> Vec<usize> {
+    let mut bad_blocks = Vec::new();
+
+    if let RecordingKind::System { .. } = recording.kind {
+        let end = recording.end.min(buffer.len());
+        let mut position = (recording.data + 1 + SYSTEM_NAME_LEN).min(end);
+
+        while (position + 4) <= end && buffer[position] == SYSTEM_DATA_BLOCK {
+            let count = if buffer[position + 1] == 0 { 256 } else { buffer[position + 1] as usize };
+            let checksum_pos = position + 4 + count;
+            if checksum_pos >= end {
+                bad_blocks.push(position);
+                break;
+            }
+            let sum = buffer[(position + 2)..checksum_pos].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            if sum != buffer[checksum_pos] {
+                bad_blocks.push(position);
+            }
+            position = checksum_pos + 1;
+        }
+    }
+    bad_blocks
+}
+
+fn read_u16_le(buffer: &[u8], position: usize) -> Option<u16> {
+    if (position + 2) <= buffer.len() {
+        Some(((buffer[position + 1] as u16) << 8) | (buffer[position] as u16))
+    } else {
+        None
+    }
+}
+
+fn scan_basic(buffer: &[u8], start: usize) -> (RecordingKind, usize) {
+    let name = match buffer.get(start) {
+        Some(byte) => { *byte as char },
+        None => { return (RecordingKind::Basic { name: ' ' }, buffer.len()); },
+    };
+    let mut position = start + 1;
+
+    loop {
+        match read_u16_le(buffer, position) {
+            Some(0) => {
+                position += 2;
+                break;
+            },
+            Some(_) => {
+                // Skip the line number and the tokenized text.
+                position += 4;
+                while position < buffer.len() && buffer[position] != 0x00 {
+                    position += 1;
+                }
+                position += 1;
+            },
+            None => {
+                position = buffer.len();
+                break;
+            },
+        }
+    }
+    (RecordingKind::Basic { name }, position.min(buffer.len()))
+}
+
+fn scan_system(buffer: &[u8], start: usize) -> (RecordingKind, usize) {
+    let name_end = (start + SYSTEM_NAME_LEN).min(buffer.len());
+    let name: String = buffer[start..name_end].iter().map(|byte| *byte as char).collect();
+    let name = name.trim_end().to_owned();
+
+    let mut load_address = None;
+    let mut entry_point = None;
+    let mut position = name_end;
+
+    while position < buffer.len() {
+        if buffer[position] == SYSTEM_DATA_BLOCK && (position + 1) < buffer.len() {
+            let count = if buffer[position + 1] == 0 { 256 } else { buffer[position + 1] as usize };
+            if load_address.is_none() {
+                load_address = read_u16_le(buffer, position + 2);
+            }
+            position += 4 + count + 1;
+        } else if buffer[position] == SYSTEM_ENTRY_BLOCK {
+            entry_point = read_u16_le(buffer, position + 1);
+            position += 3;
+            break;
+        } else {
+            // Corrupted, or not a SYSTEM tape after all.
+            break;
+        }
+    }
+    (RecordingKind::System { name, load_address, entry_point }, position.min(buffer.len()))
+}
+
+fn find_next_leader(buffer: &[u8], start: usize) -> usize {
+    let mut zero_count = 0;
+
+    for (position, byte) in buffer.iter().enumerate().skip(start) {
+        if *byte == 0x00 {
+            zero_count += 1;
+        } else {
+            if *byte == SYNC_BYTE && zero_count >= MIN_LEADER_LEN {
+                return position - zero_count;
+            }
+            zero_count = 0;
+        }
+    }
+    buffer.len()
+}
@@ -0,0 +1,537 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error, warn};
+
+use crate::disk;
+
+
+// TRSDOS 2.3 and LDOS file system support.
+//
+// Both operating systems use the same basic directory layout on the Model I:
+// the location of the directory track is stored in the third byte of the
+// boot sector, the first sector of the directory track holds the Granule
+// Allocation Table (GAT), the second one the Hash Index Table (HIT), and
+// the rest of the track is made up of 32 byte directory entries.
+//
+// Each directory entry looks like this:
+//
+//     +0       attributes (in use, system, invisible, protection level)
+//     +1..+2   flags and date (LDOS only)
+//     +3       end of file byte offset within the last sector
+//     +4       logical record length
+//     +5..+12  file name, padded with spaces
+//     +13..+15 extension, padded with spaces
+//     +16..+19 update and access password hashes
+//     +20..+21 ending record number (count of sectors in use)
+//     +22..+31 up to 5 extents, each a track and a granule specifier
+//
+// The disk space is allocated in granules, which are groups of 5 sectors on
+// single density disks (2 per track), and groups of 6 sectors on double
+// density disks (3 per track).  A bit set in the GAT byte of a given track
+// means that the corresponding granule is in use.
+//
+// In an extent, the granule specifier's top 3 bits hold the number of the
+// first granule on the track, while the bottom 5 bits hold the number of
+// contiguous granules minus one.  A track byte of 0xFF ends the extent list,
+// and a track byte of 0xFE links to an extended directory entry, the code of
+// which is in the following byte.
+//
+// A directory entry code (DEC) is the position of the entry's hash in the
+// HIT, the bottom 5 bits hold the directory sector number (counting from the
+// first entry sector) and the top 3 bits the entry number within the sector.
+//
+
+pub const DIR_ENTRY_SIZE:     usize = 32;
+
+const DEFAULT_DIR_TRACK:      u8    = 17;
+const BOOT_SECTOR_DIR_TRACK:  usize = 2;
+const FIRST_DIR_ENTRY_SECTOR: u8    = 2;
+
+const ATTR_EXTENDED:          u8    = 0b1000_0000;
+const ATTR_SYSTEM:            u8    = 0b0100_0000;
+const ATTR_IN_USE:            u8    = 0b0001_0000;
+const ATTR_INVISIBLE:         u8    = 0b0000_1000;
+const ATTR_PROTECTION:        u8    = 0b0000_0111;
+
+const EXTENT_COUNT:           usize = 5;
+const EXTENT_END:             u8    = 0xFF;
+const EXTENT_LINK:            u8    = 0xFE;
+const MAX_EXTENT_GRANULES:    usize = 32;
+const MAX_GAT_TRACKS:         usize = 0x60;
+
+// Like the DOSes themselves, leave the first two slots of each directory
+// sector for system files.
+const FIRST_USER_DEC:         usize = 0x40;
+
+// Hash of a blank password.
+const BLANK_PASSWORD_HASH:    u16   = 0x4296;
+
+static PROTECTION_NAMES: [&str; 8] = [ "FULL", "REMOVE", "RENAME", "WRITE",
+                                       "UPDATE", "READ", "EXEC", "NONE" ];
+
+
+pub struct DirectoryEntry {
+    pub name:        String,
+    pub extension:   String,
+    pub size:        usize,
+    pub protection:  u8,
+    pub system:      bool,
+    pub invisible:   bool,
+    sector_count:    usize,
+    extents:         Vec<u8>,
+}
+
+// Layout of the file system on a given disk.
+struct Geometry {
+    dir_track:            u8,
+    dir_sectors:          usize,
+    sectors_per_granule:  usize,
+    granules_per_track:   usize,
+    track_count:          usize,
+}
+
+impl Geometry {
+    fn new(image: &disk::DiskImage) -> Option<Geometry> {
+        let dir_track = match directory_track(image) {
+            Some(track) => { track },
+            None => { return None; },
+        };
+        let dir_sectors = image.sectors_on_track(dir_track, 0);
+        let (sectors_per_granule, granules_per_track) = match dir_sectors {
+            10 => { (5, 2) },
+            18 => { (6, 3) },
+            _  => {
+                error!("Unsupported disk geometry in `{}' ({} sectors per track).", image.image_path().display(), dir_sectors);
+                return None;
+            },
+        };
+        Some(Geometry {
+            dir_track,
+            dir_sectors,
+            sectors_per_granule,
+            granules_per_track,
+            track_count:  image.track_count(),
+        })
+    }
+
+    // Returns the track and sector of the directory entry with the given
+    // code, and the entry's offset within the sector.
+    fn dec_location(&self, dec: usize) -> Option<(u8, u8, usize)> {
+        let sector = (FIRST_DIR_ENTRY_SECTOR as usize) + (dec & 0x1F);
+        if sector < self.dir_sectors {
+            Some((self.dir_track, sector as u8, (dec >> 5) * DIR_ENTRY_SIZE))
+        } else {
+            None
+        }
+    }
+
+    // Returns the track and the first sector of a granule, counting from the
+    // start of the disk.
+    fn granule_location(&self, granule: usize) -> (u8, usize) {
+        ((granule / self.granules_per_track) as u8, (granule % self.granules_per_track) * self.sectors_per_granule)
+    }
+}
+
+impl DirectoryEntry {
+    fn from_raw(raw: &[u8]) -> Option<DirectoryEntry> {
+        assert!(raw.len() == DIR_ENTRY_SIZE);
+
+        let attributes = raw[0];
+        if (attributes & ATTR_IN_USE) == 0 || (attributes & ATTR_EXTENDED) != 0 {
+            return None;
+        }
+        let name = match parse_name_field(&raw[5..13]) {
+            Some(name) => { name },
+            None => { return None; },
+        };
+        let extension = match parse_name_field(&raw[13..16]) {
+            Some(extension) => { extension },
+            None => { return None; },
+        };
+
+        let eof_offset = raw[3] as usize;
+        let ern = (raw[20] as usize) | ((raw[21] as usize) << 8);
+        let size = if eof_offset == 0 || ern == 0 {
+            ern * disk::SECTOR_SIZE
+        } else {
+            ((ern - 1) * disk::SECTOR_SIZE) + eof_offset
+        };
+
+        Some(DirectoryEntry {
+            name,
+            extension,
+            size,
+            protection:    attributes & ATTR_PROTECTION,
+            system:        (attributes & ATTR_SYSTEM) != 0,
+            invisible:     (attributes & ATTR_INVISIBLE) != 0,
+            sector_count:  ern,
+            extents:       raw[22..32].to_vec(),
+        })
+    }
+    pub fn full_name(&self) -> String {
+        if self.extension.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}/{}", self.name, self.extension)
+        }
+    }
+    pub fn protection_name(&self) -> &'static str {
+        PROTECTION_NAMES[self.protection as usize]
+    }
+}
+
+// File names are upper-case alphanumeric, left-aligned and padded with spaces.
+fn parse_name_field(field: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    let mut found_padding = false;
+
+    for byte in field {
+        if *byte == b' ' {
+            found_padding = true;
+        } else if found_padding || !(*byte as char).is_ascii_graphic() {
+            return None;
+        } else {
+            text.push(*byte as char);
+        }
+    }
+    Some(text)
+}
+
+pub fn directory_track(image: &disk::DiskImage) -> Option<u8> {
+    let boot_sector = match image.read_sector(0, 0, 0) {
+        Some(sector) => { sector },
+        None => {
+            error!("The disk image `{}' doesn't have a boot sector.", image.image_path().display());
+            return None;
+        },
+    };
+    let dir_track = boot_sector[BOOT_SECTOR_DIR_TRACK];
+
+    if image.sectors_on_track(dir_track, 0) > (FIRST_DIR_ENTRY_SECTOR as usize) {
+        Some(dir_track)
+    } else if image.sectors_on_track(DEFAULT_DIR_TRACK, 0) > (FIRST_DIR_ENTRY_SECTOR as usize) {
+        warn!("The boot sector of `{}' points to an invalid directory track ({}), trying track {}.", image.image_path().display(), dir_track, DEFAULT_DIR_TRACK);
+        Some(DEFAULT_DIR_TRACK)
+    } else {
+        error!("Failed to locate the directory track of `{}'.", image.image_path().display());
+        None
+    }
+}
+
+// The hash stored in the HIT, computed from the space padded name and
+// extension.
+fn file_name_hash(name: &str, extension: &str) -> u8 {
+    let padded = format!("{:<8}{:<3}", name, extension);
+    let mut hash: u8 = 0;
+
+    for byte in padded.bytes() {
+        hash = (hash ^ byte).rotate_left(1);
+    }
+    if hash == 0 {
+        1
+    } else {
+        hash
+    }
+}
+
+// Splits a file specification like `NAME/EXT' into the name and the
+// extension, checking that they're valid.  A `.' is accepted in place of
+// the `/' for convenience.
+pub fn split_file_name(file_name: &str) -> Option<(String, String)> {
+    let upper = file_name.trim().to_uppercase();
+    let (name, extension) = match upper.find(['/', '.']) {
+        Some(position) => { (upper[..position].to_owned(), upper[(position + 1)..].to_owned()) },
+        None => { (upper, String::new()) },
+    };
+
+    let valid_part = |part: &str, max_len: usize| {
+        part.len() <= max_len &&
+        part.chars().enumerate().all(|(index, character)| {
+            character.is_ascii_uppercase() || (index > 0 && character.is_ascii_digit())
+        })
+    };
+    if name.is_empty() || !valid_part(&name, 8) || !valid_part(&extension, 3) {
+        error!("`{}' isn't a valid TRSDOS file name.", file_name);
+        return None;
+    }
+    Some((name, extension))
+}
+
+pub fn read_directory(image: &disk::DiskImage) -> Option<Vec<DirectoryEntry>> {
+    let dir_track = match directory_track(image) {
+        Some(track) => { track },
+        None => { return None; },
+    };
+    let dir_sectors = image.sectors_on_track(dir_track, 0);
+    let mut entries = Vec::new();
+
+    for sector in FIRST_DIR_ENTRY_SECTOR..(dir_sectors as u8) {
+        match image.read_sector(dir_track, 0, sector) {
+            Some(data) => {
+                for raw_entry in data.chunks_exact(DIR_ENTRY_SIZE) {
+                    if let Some(entry) = DirectoryEntry::from_raw(raw_entry) {
+                        entries.push(entry);
+                    }
+                }
+            },
+            None => {
+                warn!("Directory sector {} of `{}' is missing.", sector, image.image_path().display());
+            },
+        }
+    }
+    Some(entries)
+}
+
+fn find_file(image: &disk::DiskImage, file_name: &str) -> Option<DirectoryEntry> {
+    let (name, extension) = match split_file_name(file_name) {
+        Some(parts) => { parts },
+        None => { return None; },
+    };
+    match read_directory(image) {
+        Some(entries) => {
+            entries.into_iter().find(|entry| entry.name == name && entry.extension == extension)
+        },
+        None => { None },
+    }
+}
+
+fn read_dir_entry(image: &disk::DiskImage, geometry: &Geometry, dec: usize) -> Option<Vec<u8>> {
+    let (track, sector, offset) = match geometry.dec_location(dec) {
+        Some(location) => { location },
+        None => { return None; },
+    };
+    image.read_sector(track, 0, sector).map(|data| data[offset..(offset + DIR_ENTRY_SIZE)].to_vec())
+}
+
+// Lists the granules of a file, following the links to extended directory
+// entries as needed.
+fn file_granules(image: &disk::DiskImage, geometry: &Geometry, entry: &DirectoryEntry) -> Option<Vec<usize>> {
+    let mut granules = Vec::new();
+    let mut extents = entry.extents.clone();
+    let mut links_followed = 0;
+
+    'entries: loop {
+        for extent in extents.chunks_exact(2) {
+            let track = extent[0];
+            let specifier = extent[1] as usize;
+
+            if track == EXTENT_END {
+                break 'entries;
+            } else if track == EXTENT_LINK {
+                links_followed += 1;
+                let raw = match read_dir_entry(image, geometry, specifier) {
+                    Some(raw) if links_followed <= 0x100 => { raw },
+                    _ => {
+                        error!("The extended directory entry of `{}' is corrupted.", entry.full_name());
+                        return None;
+                    },
+                };
+                extents = raw[22..32].to_vec();
+                continue 'entries;
+            }
+            let first = ((track as usize) * geometry.granules_per_track) + (specifier >> 5);
+            let count = (specifier & 0x1F) + 1;
+            granules.extend(first..(first + count));
+        }
+        break;
+    }
+    Some(granules)
+}
+
+pub fn read_file(image: &disk::DiskImage, file_name: &str) -> Option<Vec<u8>> {
+    let geometry = match Geometry::new(image) {
+        Some(geometry) => { geometry },
+        None => { return None; },
+    };
+    let entry = match find_file(image, file_name) {
+        Some(entry) => { entry },
+        None => {
+            error!("The file `{}' wasn't found in `{}'.", file_name, image.image_path().display());
+            return None;
+        },
+    };
+    let granules = match file_granules(image, &geometry, &entry) {
+        Some(granules) => { granules },
+        None => { return None; },
+    };
+
+    let mut contents = Vec::new();
+    'granules: for granule in granules {
+        let (track, first_sector) = geometry.granule_location(granule);
+        for sector in first_sector..(first_sector + geometry.sectors_per_granule) {
+            if contents.len() >= (entry.sector_count * disk::SECTOR_SIZE) {
+                break 'granules;
+            }
+            match image.read_sector(track, 0, sector as u8) {
+                Some(data) => { contents.extend_from_slice(&data); },
+                None => {
+                    error!("Sector {} of track {}, belonging to `{}', is missing.", sector, track, entry.full_name());
+                    return None;
+                },
+            }
+        }
+    }
+    if contents.len() < entry.size {
+        error!("The file `{}' is shorter than its directory entry claims.", entry.full_name());
+        return None;
+    }
+    contents.truncate(entry.size);
+    Some(contents)
+}
+
+// Adds a new file to the disk, allocating its granules in the GAT and its
+// directory entry in the HIT.  The changes are only done to the in-memory
+// copy of the image.
+pub fn write_file(image: &mut disk::DiskImage, file_name: &str, contents: &[u8]) -> bool {
+    let geometry = match Geometry::new(image) {
+        Some(geometry) => { geometry },
+        None => { return false; },
+    };
+    let (name, extension) = match split_file_name(file_name) {
+        Some(parts) => { parts },
+        None => { return false; },
+    };
+    if find_file(image, file_name).is_some() {
+        error!("The file `{}' already exists in `{}'.", file_name, image.image_path().display());
+        return false;
+    }
+    let sector_count = contents.len().div_ceil(disk::SECTOR_SIZE);
+    if sector_count > 0xFFFF {
+        error!("The file `{}' is too large.", file_name);
+        return false;
+    }
+    let granule_count = sector_count.div_ceil(geometry.sectors_per_granule);
+
+    let (mut gat, mut hit) = match (image.read_sector(geometry.dir_track, 0, 0), image.read_sector(geometry.dir_track, 0, 1)) {
+        (Some(gat), Some(hit)) => { (gat, hit) },
+        _ => {
+            error!("The GAT or the HIT of `{}' is missing.", image.image_path().display());
+            return false;
+        },
+    };
+
+    // Find a free directory entry:
+    let mut free_dec = None;
+    for (dec, hash) in hit.iter().enumerate().skip(FIRST_USER_DEC) {
+        if *hash == 0 {
+            if let Some(raw) = read_dir_entry(image, &geometry, dec) {
+                if (raw[0] & ATTR_IN_USE) == 0 {
+                    free_dec = Some(dec);
+                    break;
+                }
+            }
+        }
+    }
+    let dec = match free_dec {
+        Some(dec) => { dec },
+        None => {
+            error!("The directory of `{}' is full.", image.image_path().display());
+            return false;
+        },
+    };
+
+    // Allocate the granules, grouping them into extents:
+    let mut extents: Vec<(usize, usize)> = Vec::new();
+    let mut allocated = 0;
+    let tracks = geometry.track_count.min(MAX_GAT_TRACKS);
+    for granule in 0..(tracks * geometry.granules_per_track) {
+        if allocated == granule_count {
+            break;
+        }
+        let track = granule / geometry.granules_per_track;
+        let bit = 1 << (granule % geometry.granules_per_track);
+        if track == (geometry.dir_track as usize) || (gat[track] & bit) != 0 {
+            continue;
+        }
+        gat[track] |= bit;
+        allocated += 1;
+
+        match extents.last_mut() {
+            Some((first, count)) if (*first + *count) == granule && *count < MAX_EXTENT_GRANULES => {
+                *count += 1;
+            },
+            _ => {
+                extents.push((granule, 1));
+            },
+        }
+    }
+    if allocated < granule_count {
+        error!("There isn't enough free space in `{}' for `{}'.", image.image_path().display(), file_name);
+        return false;
+    }
+    if extents.len() > (EXTENT_COUNT - 1) {
+        error!("The free space in `{}' is too fragmented to store `{}'.", image.image_path().display(), file_name);
+        return false;
+    }
+
+    // Write out the contents:
+    let mut chunks = contents.chunks(disk::SECTOR_SIZE);
+    'extents: for (first, count) in &extents {
+        for granule in *first..(*first + *count) {
+            let (track, first_sector) = geometry.granule_location(granule);
+            for sector in first_sector..(first_sector + geometry.sectors_per_granule) {
+                let chunk = match chunks.next() {
+                    Some(chunk) => { chunk },
+                    None => { break 'extents; },
+                };
+                let mut data = chunk.to_vec();
+                data.resize(disk::SECTOR_SIZE, 0);
+                if !image.write_sector(track, 0, sector as u8, &data) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    // Create the directory entry:
+    let mut raw = vec![0; DIR_ENTRY_SIZE];
+    raw[0] = ATTR_IN_USE;
+    raw[3] = (contents.len() % disk::SECTOR_SIZE) as u8;
+    raw[5..13].copy_from_slice(format!("{:<8}", name).as_bytes());
+    raw[13..16].copy_from_slice(format!("{:<3}", extension).as_bytes());
+    for password in 0..2 {
+        raw[16 + (password * 2)] = BLANK_PASSWORD_HASH as u8;
+        raw[17 + (password * 2)] = (BLANK_PASSWORD_HASH >> 8) as u8;
+    }
+    raw[20] = sector_count as u8;
+    raw[21] = (sector_count >> 8) as u8;
+    for byte in raw.iter_mut().skip(22) {
+        *byte = EXTENT_END;
+    }
+    for (index, (first, count)) in extents.iter().enumerate() {
+        raw[22 + (index * 2)] = (first / geometry.granules_per_track) as u8;
+        raw[23 + (index * 2)] = (((first % geometry.granules_per_track) << 5) | (count - 1)) as u8;
+    }
+
+    let (track, sector, offset) = match geometry.dec_location(dec) {
+        Some(location) => { location },
+        None => { return false; },
+    };
+    let mut dir_sector = match image.read_sector(track, 0, sector) {
+        Some(data) => { data },
+        None => { return false; },
+    };
+    dir_sector[offset..(offset + DIR_ENTRY_SIZE)].copy_from_slice(&raw);
+    hit[dec] = file_name_hash(&name, &extension);
+
+    if !image.write_sector(track, 0, sector, &dir_sector) ||
+       !image.write_sector(geometry.dir_track, 0, 0, &gat) ||
+       !image.write_sector(geometry.dir_track, 0, 1, &hit) {
+        return false;
+    }
+    info!("Added `{}' ({} bytes, {} granule(s)) to `{}'.", file_name, contents.len(), granule_count, image.image_path().display());
+    true
+}
@@ -15,6 +15,10 @@
 
 use log::{info, warn, error};
 
+use std::sync::{Arc, Mutex};
+
+use crate::fonts;
+use crate::hires;
 use crate::memory;
 use crate::util::Sink;
 
@@ -30,23 +34,225 @@ pub const GLYPH_WIDTH_W:   u32 = 16; // When `modesel == true`.
 pub const SCREEN_HEIGHT:   u32 = SCREEN_ROWS * GLYPH_HEIGHT_S;
 pub const SCREEN_WIDTH:    u32 = SCREEN_COLS * GLYPH_WIDTH;
 
+// The timing of the picture, in CPU cycles and scanlines: each character
+// takes a CPU cycle to scan, the 64 of a row take up the first 64 cycles of
+// each scanline, and the 16 rows of 12 scanlines the first 192 scanlines of
+// each frame.
+pub const DISPLAY_LINES:   u32 = SCREEN_ROWS * GLYPH_HEIGHT;
+pub const LINES_PER_FRAME: u32 = 264;
+
+// With the bus contention emulated, a CPU access of the video RAM while it's
+// being scanned makes the video circuitry show the byte on the data bus
+// instead of the character it was about to show, for the one scanline of
+// the character the beam was in.  This is the `hash' seen on the screen of
+// the Model I, when programs write to the screen while it's being drawn.
+//
+// The position of the beam is only updated after each instruction, so the
+// accesses are taken as happening at the start of theirs.
+#[derive(Copy, Clone, Debug)]
+pub struct Glitch {
+    pub line:   u32, // The scanline, 0-191.
+    pub column: u32, // The column of the video RAM, 0-63.
+    pub value:  u8,
+}
+
+// The characters of the screen written to since the previous frame, with a
+// bit for each byte of the video RAM, a word for each row.  Only the writes
+// that change the byte count, so that the front-end only has to draw the
+// characters that look different.
+pub type DirtyCells = [u64; SCREEN_ROWS as usize];
+
+const ALL_DIRTY: DirtyCells = [u64::MAX; SCREEN_ROWS as usize];
+
+// Add the characters changed by earlier frames which weren't drawn.
+pub fn add_dirty(dirty: &mut DirtyCells, earlier: &DirtyCells) {
+    for (row, earlier_row) in dirty.iter_mut().zip(earlier.iter()) {
+        *row |= *earlier_row;
+    }
+}
+
+// How the block graphics characters are represented in the text of the
+// screen:
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextGraphics {
+    Unicode, // As the 2x3 block sextants of Unicode.
+    Ascii,   // As `#' if any of the blocks is lit, as a space otherwise.
+}
+
 pub struct VideoMemory {
-    memory:        [u8; VID_MEM_SIZE as usize],
-    pub modesel:   bool, // true => 32-columns; false => 64-columns.
-    lowercase_mod: bool,
+    memory:                  [u8; VID_MEM_SIZE as usize],
+    pub modesel:             bool, // true => 32-columns; false => 64-columns.
+    lowercase_mod:           bool,
+    pub alternate_installed: bool, // The character generator has an alternate set.
+    pub alternate:           bool, // Whether the alternate set is switched in.
+    contention:              bool,
+    beam:                    Option<(u32, u32)>, // Scanline and column being shown.
+    glitches:                Vec<Glitch>,
+    dirty:                   DirtyCells,
 }
 
 pub struct VideoFrame {
-    pub memory:   [u8; VID_MEM_SIZE as usize],
-    pub modesel:  bool, // true => 32-columns; false => 64-columns.
+    pub memory:    [u8; VID_MEM_SIZE as usize],
+    pub modesel:   bool, // true => 32-columns; false => 64-columns.
+    pub alternate: bool, // The alternate set is shown instead of 0xC0-0xFF.
+    pub hires:     Option<hires::HiResFrame>,    // Of the graphics board.
+    pub glitches:  Vec<Glitch>,                  // Of the bus contention.
+    pub dirty:     DirtyCells,                   // Since the previous frame.
 }
 
 impl VideoFrame {
-    pub fn new(memory: &VideoMemory) -> VideoFrame {
+    pub fn new(memory: &VideoMemory, hires: &hires::HiResIO) -> VideoFrame {
         VideoFrame {
-            memory:  memory.memory.clone(),
-            modesel: memory.modesel,
+            memory:    memory.memory.clone(),
+            modesel:   memory.modesel,
+            alternate: memory.alternate,
+            hires:     hires.frame(),
+            glitches:  Vec::new(),
+            dirty:     ALL_DIRTY,
+        }
+    }
+    // The bitmap of the graphics board, if it's shown.
+    pub fn hires_bitmap(&self) -> Option<&hires::HiResFrame> {
+        self.hires.as_ref().filter(|hires| hires.bitmap.is_some())
+    }
+    // The number of characters shown on each row of the screen.
+    pub fn columns(&self) -> u32 {
+        if self.modesel { SCREEN_COLS_W } else { SCREEN_COLS }
+    }
+    // The character shown at the given position of the screen.
+    //
+    // In the 32-column mode, the video circuitry still steps through all of
+    // the 64 bytes of each row, but it only latches the characters at even
+    // addresses, each of which is then shown twice as wide.  The characters
+    // at odd addresses are kept in the video RAM, and can be read back and
+    // shown again once the machine goes back to the 64-column mode.
+    pub fn glyph_at(&self, row: u32, column: u32) -> u8 {
+        let column = if self.modesel { column * 2 } else { column };
+        self.memory[((row * SCREEN_COLS) + column) as usize]
+    }
+    // The glitches shown on the screen, as the row and column of the
+    // character, the row of the glyph and the character shown there.  Only
+    // the characters at even addresses are shown in the 32-column mode.
+    pub fn shown_glitches(&self) -> Vec<(u32, u32, u32, u8)> {
+        self.glitches.iter().filter(|glitch| !self.modesel || (glitch.column % 2) == 0).map(|glitch| {
+            let column = if self.modesel { glitch.column / 2 } else { glitch.column };
+            (glitch.line / GLYPH_HEIGHT, column, glitch.line % GLYPH_HEIGHT, glitch.value)
+        }).collect()
+    }
+    // Draw the screen into a bitmap of SCREEN_WIDTH * SCREEN_HEIGHT dots,
+    // stored row by row, 1 for the lit dots and 0 for the rest, using the
+    // given character generator font (laid out like those in `fonts').
+    // The pixels of the graphics board are mixed in over the text.
+    pub fn rasterize(&self, font: &[u8]) -> Vec<u8> {
+        let mut bitmap = vec![0u8; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize];
+        let dot_width = if self.modesel { 2 } else { 1 };
+
+        // Each row of a glyph is shown twice, as tall.
+        let mut draw_glyph_row = |row: u32, column: u32, glyph_y: u32, dots: u8| {
+            for glyph_x in 0..GLYPH_WIDTH {
+                let lit = ((dots & (1 << glyph_x)) != 0) as u8;
+                for y_offset in 0..2 {
+                    let y = (row * GLYPH_HEIGHT_S) + (glyph_y * 2) + y_offset;
+                    for x_offset in 0..dot_width {
+                        let x = (column * GLYPH_WIDTH * dot_width) + (glyph_x * dot_width) + x_offset;
+                        bitmap[((y * SCREEN_WIDTH) + x) as usize] = lit;
+                    }
+                }
+            }
+        };
+        for row in 0..SCREEN_ROWS {
+            for column in 0..self.columns() {
+                let glyph = fonts::glyph_index(self.glyph_at(row, column), self.alternate);
+                for (glyph_y, dots) in fonts::glyph_rows(font, glyph).iter().enumerate() {
+                    draw_glyph_row(row, column, glyph_y as u32, *dots);
+                }
+            }
         }
+        for (row, column, glyph_y, value) in self.shown_glitches() {
+            let glyph = fonts::glyph_index(value, self.alternate);
+            draw_glyph_row(row, column, glyph_y, fonts::glyph_rows(font, glyph)[glyph_y as usize]);
+        }
+        if let Some(hires) = self.hires_bitmap() {
+            for y in 0..SCREEN_HEIGHT {
+                for x in 0..SCREEN_WIDTH {
+                    if hires.pixel_at((x * hires::HIRES_WIDTH) / SCREEN_WIDTH, (y * hires::HIRES_HEIGHT) / SCREEN_HEIGHT) {
+                        bitmap[((y * SCREEN_WIDTH) + x) as usize] = 1;
+                    }
+                }
+            }
+        }
+        bitmap
+    }
+    // A hash of the picture of the frame, drawn with the given font, for
+    // telling whether it's the same as in an earlier run.  It's the 64-bit
+    // FNV-1a hash of the bitmap from rasterize(), which stays the same on
+    // every platform, unlike the hashers of the standard library.
+    pub fn picture_hash(&self, font: &[u8]) -> u64 {
+        self.rasterize(font).iter().fold(0xCBF2_9CE4_8422_2325, |hash, dot| {
+            (hash ^ (*dot as u64)).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+    }
+    // Convert the screen into plain text, one line for each row, without
+    // the trailing spaces.
+    //
+    // Characters 0-31 are the uppercase letters and symbols of 64-95 with
+    // the bit 6 cleared, which only makes a difference with the lowercase
+    // mod, and some character generators show odd symbols for them instead,
+    // so they're converted into the characters that were meant.  The arrows
+    // of the character generators are kept as the ASCII characters with the
+    // same codes, which is how listings of programs are usually written.
+    // The characters of the alternate character set are written as `?',
+    // since what they are depends on the dump of the character generator.
+    pub fn text(&self, graphics: TextGraphics) -> String {
+        let mut text = String::new();
+
+        for row in 0..SCREEN_ROWS {
+            let mut line = String::new();
+            for column in 0..self.columns() {
+                let glyph = self.glyph_at(row, column);
+                let character = match glyph {
+                    0x00..=0x1F                   => { (glyph + 0x40) as char },
+                    0x20..=0x7E                   => { glyph as char },
+                    0x7F                          => { ' ' },
+                    0xC0..=0xFF if self.alternate => { '?' },
+                    _                             => { graphics_char(glyph & 0b0011_1111, graphics) },
+                };
+                line.push(character);
+            }
+            text.push_str(line.trim_end_matches(' '));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// The blocks of a graphics character are numbered from the top left one,
+// row by row, the same order the sextants of Unicode are in, except that
+// the ones which are also found among the older block elements (a half
+// block on the left or the right, and a full block) were left out.
+fn graphics_char(blocks: u8, graphics: TextGraphics) -> char {
+    match graphics {
+        TextGraphics::Unicode => {
+            match blocks {
+                0b00_0000 => { ' ' },
+                0b01_0101 => { '\u{258C}' },
+                0b10_1010 => { '\u{2590}' },
+                0b11_1111 => { '\u{2588}' },
+                _ => {
+                    let mut index = (blocks as u32) - 1;
+                    if blocks > 0b01_0101 {
+                        index -= 1;
+                    }
+                    if blocks > 0b10_1010 {
+                        index -= 1;
+                    }
+                    char::from_u32(0x1FB00 + index).unwrap()
+                },
+            }
+        },
+        TextGraphics::Ascii => {
+            if blocks != 0 { '#' } else { ' ' }
+        },
     }
 }
 
@@ -73,6 +279,7 @@ impl memory::MemIO for VideoMemory {
             }
             if self.memory[addr as usize] != to_set {
                 self.memory[addr as usize] = to_set;
+                self.dirty[((addr as u32) / SCREEN_COLS) as usize] |= 1 << ((addr as u32) % SCREEN_COLS);
             }
         } else {
             panic!("Failed write: Address offset 0x{:04X} is invalid for the video RAM", addr);
@@ -83,15 +290,30 @@ impl memory::MemIO for VideoMemory {
 impl VideoMemory {
     pub fn new(lowercase_mod: bool, start_addr: u16) -> VideoMemory {
         let video_memory = VideoMemory {
-            memory:        [0; VID_MEM_SIZE as usize],
-            modesel:       false,
+            memory:              [0; VID_MEM_SIZE as usize],
+            modesel:             false,
             lowercase_mod,
+            alternate_installed: false,
+            alternate:           false,
+            contention:          false,
+            beam:                None,
+            glitches:            Vec::new(),
+            dirty:               ALL_DIRTY,
         };
         info!("Created the video memory, starting address: 0x{:04X}, spanning {} bytes.", start_addr, VID_MEM_SIZE);
         video_memory
     }
     pub fn power_off(&mut self) {
 
+        // The mode select latch is cleared on power-up, the machine always
+        // starts out in the 64-column mode, and so is the latch switching
+        // in the alternate character set.
+        self.modesel = false;
+        self.alternate = false;
+        self.beam = None;
+        self.glitches.clear();
+        self.dirty = ALL_DIRTY;
+
         let size = self.memory.len();
         let mut index = 0;
 
@@ -105,6 +327,18 @@ impl VideoMemory {
     pub fn update_lowercase_mod(&mut self, new_value: bool) {
         self.lowercase_mod = new_value;
     }
+    pub fn set_contention(&mut self, enabled: bool) {
+        self.contention = enabled;
+        self.beam = None;
+        self.glitches.clear();
+    }
+    // Called for each access of the video RAM by the CPU, with the byte on
+    // the data bus.
+    pub fn bus_access(&mut self, value: u8) {
+        if let Some((line, column)) = self.beam {
+            self.glitches.push(Glitch { line, column, value });
+        }
+    }
 }
 
 pub struct Video {
@@ -123,11 +357,52 @@ impl Video {
         self.cpu_delta = 0;
         mem.power_off();
     }
-    pub fn tick<VS: Sink<VideoFrame>>(&mut self, vid_mem: &VideoMemory, cpu_cycles: u32, video_frame_sink: &mut VS) {
+    pub fn tick<VS: Sink<VideoFrame>>(&mut self, vid_mem: &mut VideoMemory, hires: &hires::HiResIO, cpu_cycles: u32, video_frame_sink: &mut VS) {
         self.cpu_delta += cpu_cycles;
         if self.cpu_delta >= self.cycles_per_frame {
             self.cpu_delta -= self.cycles_per_frame;
-            video_frame_sink.push(VideoFrame::new(vid_mem));
+            let mut frame = VideoFrame::new(vid_mem, hires);
+            frame.glitches = std::mem::take(&mut vid_mem.glitches);
+            frame.dirty = std::mem::take(&mut vid_mem.dirty);
+            video_frame_sink.push(frame);
         }
+        if vid_mem.contention {
+            let cycles_per_line = self.cycles_per_frame / LINES_PER_FRAME;
+            let line = self.cpu_delta / cycles_per_line;
+            let column = self.cpu_delta % cycles_per_line;
+            vid_mem.beam = if line < DISPLAY_LINES && column < SCREEN_COLS { Some((line, column)) } else { None };
+        }
+    }
+}
+
+// The newest video frame, shared between the thread running the machine and
+// the one drawing the screen, so that the frames don't have to be sent over
+// one by one, only the ones which get drawn are taken out.  The characters
+// changed by the frames replaced before being taken are kept track of.
+#[derive(Clone, Default)]
+pub struct SharedFrame {
+    newest: Arc<Mutex<Option<VideoFrame>>>,
+}
+
+impl SharedFrame {
+    pub fn new() -> SharedFrame {
+        SharedFrame::default()
+    }
+    // Replace the frame with a newer one.  Returns true if the previous one
+    // was already taken out, so the drawing side has to be told about it.
+    pub fn put(&self, mut frame: VideoFrame) -> bool {
+        let mut newest = self.newest.lock().unwrap();
+        let taken = match newest.take() {
+            Some(replaced) => {
+                add_dirty(&mut frame.dirty, &replaced.dirty);
+                false
+            },
+            None => { true },
+        };
+        *newest = Some(frame);
+        taken
+    }
+    pub fn take(&self) -> Option<VideoFrame> {
+        self.newest.lock().unwrap().take()
     }
 }
@@ -59,8 +59,37 @@ pub struct Z80Flags {
     pub carry:            bool,
 }
 
+impl Z80Flags {
+    // The flags as they'd appear in the F register.
+    pub fn to_byte(&self) -> u8 {
+        let flags = [(self.sign,            FLAG_SIGN),
+                     (self.zero,            FLAG_ZERO),
+                     (self.undoc_y,         FLAG_UNDOC_Y),
+                     (self.half_carry,      FLAG_HALF_CARRY),
+                     (self.undoc_x,         FLAG_UNDOC_X),
+                     (self.parity_overflow, FLAG_PARITY_OVERFLOW),
+                     (self.add_sub,         FLAG_ADD_SUB),
+                     (self.carry,           FLAG_CARRY)];
+
+        flags.iter().filter(|(set, _)| *set).fold(0, |byte, (_, flag)| byte | flag)
+    }
+    // The flags out of the F register.
+    pub fn from_byte(byte: u8) -> Z80Flags {
+        Z80Flags {
+            sign:            (byte & FLAG_SIGN)            != 0,
+            zero:            (byte & FLAG_ZERO)            != 0,
+            undoc_y:         (byte & FLAG_UNDOC_Y)         != 0,
+            half_carry:      (byte & FLAG_HALF_CARRY)      != 0,
+            undoc_x:         (byte & FLAG_UNDOC_X)         != 0,
+            parity_overflow: (byte & FLAG_PARITY_OVERFLOW) != 0,
+            add_sub:         (byte & FLAG_ADD_SUB)         != 0,
+            carry:           (byte & FLAG_CARRY)           != 0,
+        }
+    }
+}
+
 // Registers:
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Z80Regs {
     pub pc: u16,
     pub i:  u8,
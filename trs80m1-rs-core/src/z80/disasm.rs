@@ -0,0 +1,289 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+// A disassembler for the Z80 instruction set, undocumented instructions
+// included, producing Zilog mnemonics.
+//
+// Rather than having a table of all the instructions, the opcodes are
+// decoded the way the CPU itself does it, by splitting them into the fields
+// `x' (bits 7-6), `y' (bits 5-3) and `z' (bits 2-0), with `y' further split
+// into `p' (bits 5-4) and `q' (bit 3).  The DD and FD prefixes make the
+// instruction which follows use IX or IY in place of HL, with (HL) becoming
+// (IX+d) or (IY+d), and H and L becoming the halves of the index register.
+
+const REGS:       [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+const REG_PAIRS:  [&str; 4] = ["BC", "DE", "HL", "SP"];
+const REG_PAIRS2: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CONDITIONS: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+const ALU_OPS:    [&str; 8] = ["ADD A,", "ADC A,", "SUB ", "SBC A,", "AND ", "XOR ", "OR ", "CP "];
+const ROTATIONS:  [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SLL", "SRL"];
+const INT_MODES:  [&str; 8] = ["0", "0/1", "1", "2", "0", "0/1", "1", "2"];
+const BLOCK_OPS:  [[&str; 4]; 4] = [["LDI",  "CPI",  "INI",  "OUTI"],
+                                    ["LDD",  "CPD",  "IND",  "OUTD"],
+                                    ["LDIR", "CPIR", "INIR", "OTIR"],
+                                    ["LDDR", "CPDR", "INDR", "OTDR"]];
+
+// Hexadecimal numbers are written with the `H' suffix, with a leading zero
+// if they'd otherwise start with a letter.
+fn hex(value: u16, digits: usize) -> String {
+    let digits = format!("{:01$X}", value, digits);
+    if digits.starts_with(|digit: char| digit.is_ascii_alphabetic()) {
+        format!("0{}H", digits)
+    } else {
+        format!("{}H", digits)
+    }
+}
+
+struct Decoder<F: FnMut(u16) -> u8> {
+    read:    F,
+    address: u16,
+    size:    u16,
+    index:   Option<&'static str>,
+}
+
+impl<F: FnMut(u16) -> u8> Decoder<F> {
+    fn byte(&mut self) -> u8 {
+        let byte = (self.read)(self.address.wrapping_add(self.size));
+        self.size += 1;
+        byte
+    }
+    fn word(&mut self) -> u16 {
+        let low = self.byte() as u16;
+        let high = self.byte() as u16;
+        (high << 8) | low
+    }
+    fn imm8(&mut self) -> String {
+        let value = self.byte();
+        hex(value as u16, 2)
+    }
+    fn imm16(&mut self) -> String {
+        let value = self.word();
+        hex(value, 4)
+    }
+    fn relative(&mut self) -> String {
+        let offset = self.byte() as i8;
+        let target = self.address.wrapping_add(self.size).wrapping_add(offset as u16);
+        hex(target, 4)
+    }
+    fn indexed(&mut self, index: &str) -> String {
+        let offset = self.byte() as i8;
+        if offset < 0 {
+            format!("({}-{})", index, hex(offset.unsigned_abs() as u16, 2))
+        } else {
+            format!("({}+{})", index, hex(offset as u16, 2))
+        }
+    }
+    fn hl(&self) -> &'static str {
+        self.index.unwrap_or("HL")
+    }
+    // An 8-bit register operand, `memory' telling whether the instruction
+    // also accesses (HL), in which case H and L aren't replaced.
+    fn reg(&mut self, reg: u8, memory: bool) -> String {
+        match (self.index, reg) {
+            (Some(index), 6) => { self.indexed(index) },
+            (Some(index), 4) if !memory => { format!("{}H", index) },
+            (Some(index), 5) if !memory => { format!("{}L", index) },
+            _ => { REGS[reg as usize].to_owned() },
+        }
+    }
+    fn reg_pair(&self, pair: u8) -> &'static str {
+        if pair == 2 { self.hl() } else { REG_PAIRS[pair as usize] }
+    }
+    fn reg_pair2(&self, pair: u8) -> &'static str {
+        if pair == 2 { self.hl() } else { REG_PAIRS2[pair as usize] }
+    }
+
+    fn decode(&mut self) -> String {
+        let opcode = self.byte();
+        let (x, y, z) = (opcode >> 6, (opcode >> 3) & 7, opcode & 7);
+        let (p, q) = (y >> 1, y & 1);
+
+        match x {
+            0 => {
+                match z {
+                    0 => {
+                        match y {
+                            0 => { "NOP".to_owned() },
+                            1 => { "EX AF,AF'".to_owned() },
+                            2 => { format!("DJNZ {}", self.relative()) },
+                            3 => { format!("JR {}", self.relative()) },
+                            _ => { format!("JR {},{}", CONDITIONS[(y - 4) as usize], self.relative()) },
+                        }
+                    },
+                    1 => {
+                        if q == 0 {
+                            format!("LD {},{}", self.reg_pair(p), self.imm16())
+                        } else {
+                            format!("ADD {},{}", self.hl(), self.reg_pair(p))
+                        }
+                    },
+                    2 => {
+                        match (q, p) {
+                            (0, 0) => { "LD (BC),A".to_owned() },
+                            (0, 1) => { "LD (DE),A".to_owned() },
+                            (0, 2) => { format!("LD ({}),{}", self.imm16(), self.hl()) },
+                            (0, _) => { format!("LD ({}),A", self.imm16()) },
+                            (_, 0) => { "LD A,(BC)".to_owned() },
+                            (_, 1) => { "LD A,(DE)".to_owned() },
+                            (_, 2) => { format!("LD {},({})", self.hl(), self.imm16()) },
+                            (_, _) => { format!("LD A,({})", self.imm16()) },
+                        }
+                    },
+                    3 => {
+                        let mnemonic = if q == 0 { "INC" } else { "DEC" };
+                        format!("{} {}", mnemonic, self.reg_pair(p))
+                    },
+                    4 => { format!("INC {}", self.reg(y, false)) },
+                    5 => { format!("DEC {}", self.reg(y, false)) },
+                    6 => {
+                        let reg = self.reg(y, false);
+                        format!("LD {},{}", reg, self.imm8())
+                    },
+                    _ => {
+                        ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"][y as usize].to_owned()
+                    },
+                }
+            },
+            1 => {
+                if y == 6 && z == 6 {
+                    "HALT".to_owned()
+                } else {
+                    let memory = y == 6 || z == 6;
+                    let dest = self.reg(y, memory);
+                    let source = self.reg(z, memory);
+                    format!("LD {},{}", dest, source)
+                }
+            },
+            2 => { format!("{}{}", ALU_OPS[y as usize], self.reg(z, false)) },
+            _ => {
+                match z {
+                    0 => { format!("RET {}", CONDITIONS[y as usize]) },
+                    1 => {
+                        match (q, p) {
+                            (0, _) => { format!("POP {}", self.reg_pair2(p)) },
+                            (_, 0) => { "RET".to_owned() },
+                            (_, 1) => { "EXX".to_owned() },
+                            (_, 2) => { format!("JP ({})", self.hl()) },
+                            (_, _) => { format!("LD SP,{}", self.hl()) },
+                        }
+                    },
+                    2 => { format!("JP {},{}", CONDITIONS[y as usize], self.imm16()) },
+                    3 => {
+                        match y {
+                            0 => { format!("JP {}", self.imm16()) },
+                            1 => { self.decode_cb() },
+                            2 => { format!("OUT ({}),A", self.imm8()) },
+                            3 => { format!("IN A,({})", self.imm8()) },
+                            4 => { format!("EX (SP),{}", self.hl()) },
+                            5 => { "EX DE,HL".to_owned() },
+                            6 => { "DI".to_owned() },
+                            _ => { "EI".to_owned() },
+                        }
+                    },
+                    4 => { format!("CALL {},{}", CONDITIONS[y as usize], self.imm16()) },
+                    5 => {
+                        match (q, p) {
+                            (0, _) => { format!("PUSH {}", self.reg_pair2(p)) },
+                            (_, 0) => { format!("CALL {}", self.imm16()) },
+                            (_, 2) => { self.decode_ed() },
+                            (_, _) => { self.decode_index(if p == 1 { "IX" } else { "IY" }) },
+                        }
+                    },
+                    6 => { format!("{}{}", ALU_OPS[y as usize], self.imm8()) },
+                    _ => { format!("RST {}", hex((y as u16) * 8, 2)) },
+                }
+            },
+        }
+    }
+    fn decode_index(&mut self, index: &'static str) -> String {
+        let opcode = (self.read)(self.address.wrapping_add(self.size));
+
+        // A prefix followed by another prefix does nothing on its own, so
+        // it's shown as data.
+        if opcode == 0xDD || opcode == 0xED || opcode == 0xFD {
+            self.size = 1;
+            return format!("DEFB {}", hex((self.read)(self.address) as u16, 2));
+        }
+        self.index = Some(index);
+        self.decode()
+    }
+    fn decode_cb(&mut self) -> String {
+        // With an index prefix, the displacement comes before the opcode.
+        let operand = self.index.map(|index| self.indexed(index));
+        let opcode = self.byte();
+        let (x, y, z) = (opcode >> 6, (opcode >> 3) & 7, opcode & 7);
+
+        let target = match operand {
+            // The undocumented indexed instructions also copy the result
+            // into a register.
+            Some(operand) if z != 6 && x != 1 => { format!("{},{}", operand, REGS[z as usize]) },
+            Some(operand) => { operand },
+            None => { REGS[z as usize].to_owned() },
+        };
+        match x {
+            0 => { format!("{} {}", ROTATIONS[y as usize], target) },
+            1 => { format!("BIT {},{}", y, target) },
+            2 => { format!("RES {},{}", y, target) },
+            _ => { format!("SET {},{}", y, target) },
+        }
+    }
+    fn decode_ed(&mut self) -> String {
+        let opcode = self.byte();
+        let (x, y, z) = (opcode >> 6, (opcode >> 3) & 7, opcode & 7);
+        let (p, q) = (y >> 1, y & 1);
+
+        match (x, z) {
+            (1, 0) => {
+                if y == 6 { "IN (C)".to_owned() } else { format!("IN {},(C)", REGS[y as usize]) }
+            },
+            (1, 1) => {
+                if y == 6 { "OUT (C),0".to_owned() } else { format!("OUT (C),{}", REGS[y as usize]) }
+            },
+            (1, 2) => {
+                let mnemonic = if q == 0 { "SBC" } else { "ADC" };
+                format!("{} HL,{}", mnemonic, REG_PAIRS[p as usize])
+            },
+            (1, 3) => {
+                if q == 0 {
+                    format!("LD ({}),{}", self.imm16(), REG_PAIRS[p as usize])
+                } else {
+                    format!("LD {},({})", REG_PAIRS[p as usize], self.imm16())
+                }
+            },
+            (1, 4) => { "NEG".to_owned() },
+            (1, 5) => { if y == 1 { "RETI".to_owned() } else { "RETN".to_owned() } },
+            (1, 6) => { format!("IM {}", INT_MODES[y as usize]) },
+            (1, _) => {
+                ["LD I,A", "LD R,A", "LD A,I", "LD A,R", "RRD", "RLD", "NOP", "NOP"][y as usize].to_owned()
+            },
+            (2, 0..=3) if y >= 4 => { BLOCK_OPS[(y - 4) as usize][z as usize].to_owned() },
+            _ => { format!("DEFB 0EDH,{}", hex(opcode as u16, 2)) },
+        }
+    }
+}
+
+// Disassemble the instruction at `address', reading the memory through
+// `read', returns its mnemonic and its size in bytes.
+pub fn disassemble<F: FnMut(u16) -> u8>(read: F, address: u16) -> (String, u16) {
+    let mut decoder = Decoder {
+        read,
+        address,
+        size:  0,
+        index: None,
+    };
+    let mnemonic = decoder.decode();
+
+    (mnemonic, decoder.size)
+}
@@ -1,3 +1,4 @@
 #[macro_use]
 pub mod instructions;
 pub mod cpu;
+pub mod disasm;
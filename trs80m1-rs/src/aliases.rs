@@ -0,0 +1,136 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::util;
+
+
+// The command aliases of the configuration file, names which stand for one
+// or more commands of the user interface, separated by semicolons, like
+// `l2 "cassette load games/defense.cas; wait 5; machine type-text SYSTEM\n"'.
+//
+// The words typed after the name of an alias go at the end of its last
+// command, and the commands may use the other aliases, up to a few levels
+// deep, so that an alias using itself doesn't go on forever.
+//
+const MAX_NESTING: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alias {
+    pub name:     String,
+    pub commands: String,
+}
+
+// Parse the aliases of the configuration file, a list of `<name> "<commands>"'
+// aliases, or `none'.  Inside of the quotes, `\"' stands for a quote and `\\'
+// for a backslash, the other backslashes are kept, for `machine type-text'.
+pub fn parse_aliases(text: &str) -> Option<Vec<Alias>> {
+    let mut aliases: Vec<Alias> = Vec::new();
+    let mut rest = text.trim();
+    if rest.eq_ignore_ascii_case("none") {
+        return Some(aliases);
+    }
+    while !rest.is_empty() {
+        let (name, after_name) = rest.split_once('"')?;
+        let name = name.trim().to_lowercase();
+        if !is_valid_alias_name(&name) || name == "none" {
+            return None;
+        }
+
+        let mut commands = String::new();
+        let mut chars = after_name.char_indices();
+        let mut end = None;
+        while let Some((index, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    end = Some(index + 1);
+                    break;
+                },
+                '\\' => {
+                    match chars.next() {
+                        Some((_, escaped @ ('"' | '\\'))) => { commands.push(escaped); },
+                        Some((_, other)) => {
+                            commands.push('\\');
+                            commands.push(other);
+                        },
+                        None => { return None; },
+                    }
+                },
+                _ => { commands.push(ch); },
+            }
+        }
+        rest = after_name[end?..].trim_start();
+
+        // A later alias of the same name replaces the earlier one.
+        aliases.retain(|alias| alias.name != name);
+        aliases.push(Alias { name, commands: commands.trim().to_owned() });
+    }
+    Some(aliases)
+}
+
+pub fn aliases_text(aliases: &[Alias]) -> String {
+    if aliases.is_empty() {
+        return "none".to_owned();
+    }
+    aliases.iter().map(|alias| {
+        format!("{} \"{}\"", alias.name, alias.commands.replace('\\', "\\\\").replace('"', "\\\""))
+    }).collect::<Vec<String>>().join(" ")
+}
+
+pub fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+pub fn find<'a>(aliases: &'a [Alias], name: &str) -> Option<&'a Alias> {
+    let name = name.to_lowercase();
+    aliases.iter().find(|alias| alias.name == name)
+}
+
+// The commands an alias stands for, with the words after its name added,
+// or None if the command doesn't start with the name of an alias.
+pub fn expand(aliases: &[Alias], command: &str) -> Option<Result<Vec<String>, String>> {
+    find(aliases, &util::get_word(command, 1)?)?;
+
+    let mut commands = Vec::new();
+    Some(expand_into(aliases, command, 0, &mut commands).map(|_| commands))
+}
+
+fn expand_into(aliases: &[Alias], command: &str, nesting: usize, commands: &mut Vec<String>) -> Result<(), String> {
+    let alias = match find(aliases, &util::get_word(command, 1).unwrap_or_default()) {
+        Some(alias) => { alias },
+        None => {
+            commands.push(command.to_owned());
+            return Ok(());
+        },
+    };
+    if nesting >= MAX_NESTING {
+        return Err(format!("The alias `{}' is nested too deeply, does it use itself?", alias.name));
+    }
+
+    let mut alias_commands: Vec<String> = alias.commands.split(';').map(|part| part.trim().to_owned()).filter(|part| !part.is_empty()).collect();
+    if let Some(arguments) = util::get_starting_at_word(command, 2) {
+        match alias_commands.last_mut() {
+            Some(last) => {
+                last.push(' ');
+                last.push_str(arguments.trim_end());
+            },
+            None => { alias_commands.push(arguments.trim_end().to_owned()); },
+        }
+    }
+    for alias_command in alias_commands {
+        let alias_command = alias_command.strip_prefix('/').unwrap_or(&alias_command);
+        expand_into(aliases, alias_command, nesting + 1, commands)?;
+    }
+    Ok(())
+}
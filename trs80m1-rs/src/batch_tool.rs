@@ -0,0 +1,357 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error};
+
+use std::fs;
+use std::path;
+
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::cmd_file;
+use trs80m1_rs_core::keyboard;
+use trs80m1_rs_core::machine;
+use trs80m1_rs_core::memory;
+use trs80m1_rs_core::util::Sink;
+use trs80m1_rs_core::video;
+
+use crate::tool_machine;
+
+
+// The `batch' command line mode, which runs the emulated machine without
+// showing it, following a script, for automated tests of BASIC and machine
+// language programs:
+//
+//     trs80m1-rs batch [--expect <text-file>] [--screen <text-file>] <script> <rom>
+//
+// The script has a step per line, the empty lines and the ones starting
+// with `#' are skipped:
+//
+//     cassette <file>          Insert a cassette, it plays when the motor runs.
+//     run <file>               Load a /CMD program and jump to its entry point.
+//     type <text>              Type in the text, with `\n' pressing Enter.
+//     wait <frames>            Run the machine for the given number of frames.
+//     wait-for <frames> <text> Run it until the text shows up on the screen,
+//                              failing if it doesn't in the given frames.
+//     expect <text>            Fail unless the text is on the screen.
+//     print                    Print the screen, for writing the script.
+//
+// Once the script is done, the text on the screen is saved into the file
+// given with --screen, and compared with the one given with --expect, the
+// exit code tells whether all of it passed.  The block graphics are written
+// as `#', like with the `ascii' text graphics.
+//
+// The machine is set up the same way as in the `frames' mode, see
+// tool_machine.rs.
+//
+pub fn print_usage(progname: &str) {
+    println!("Usage: {} batch [--expect <text-file>] [--screen <text-file>] <script> <rom>", progname);
+}
+
+enum Step {
+    Cassette(path::PathBuf, cassette::Format),
+    Run(path::PathBuf),
+    Type(String),
+    Wait(usize),
+    WaitFor(usize, String),
+    Expect(String),
+    Print,
+}
+
+// Counts the frames as the machine produces them.
+struct FrameCounter {
+    frames: usize,
+}
+
+impl Sink<video::VideoFrame> for FrameCounter {
+    fn push(&mut self, _value: video::VideoFrame) {
+        self.frames += 1;
+    }
+}
+
+struct BatchRun {
+    machine: machine::Machine,
+    counter: FrameCounter,
+}
+
+// Returns the exit code of the program.
+pub fn run(progname: &str, args_in: &[String]) -> i32 {
+    let mut expect_path = None;
+    let mut screen_path = None;
+    let mut args = Vec::new();
+    let mut args_iter = args_in.iter();
+
+    while let Some(arg) = args_iter.next() {
+        if arg == "--expect" || arg == "--screen" {
+            let file = match args_iter.next() {
+                Some(file) => { file.clone() },
+                None => {
+                    print_usage(progname);
+                    return 1;
+                },
+            };
+            if arg == "--expect" {
+                expect_path = Some(file);
+            } else {
+                screen_path = Some(file);
+            }
+        } else {
+            args.push(arg.clone());
+        }
+    }
+    if args.len() != 2 {
+        print_usage(progname);
+        return 1;
+    }
+
+    let steps = match read_script(&args[0]) {
+        Some(steps) => { steps },
+        None => { return 1; },
+    };
+    let machine = match tool_machine::power_on(&args[1], None) {
+        Some(machine) => { machine },
+        None => { return 1; },
+    };
+    let mut batch = BatchRun {
+        machine,
+        counter: FrameCounter { frames: 0 },
+    };
+
+    for (line_number, step) in &steps {
+        if let Err(message) = batch.run_step(step) {
+            error!("Line {} of the script `{}' failed at frame {}: {}.", line_number, args[0], batch.counter.frames, message);
+            return 1;
+        }
+    }
+
+    let screen = batch.screen_text();
+    if let Some(screen_path) = screen_path {
+        match fs::write(&screen_path, &screen) {
+            Ok(..) => {
+                info!("The screen was saved into `{}'.", screen_path);
+            },
+            Err(error) => {
+                error!("Failed to write `{}': {}.", screen_path, error);
+                return 1;
+            },
+        }
+    }
+    if let Some(expect_path) = expect_path {
+        if !compare_screen(&screen, &expect_path) {
+            return 1;
+        }
+    }
+    info!("The script `{}' passed after {} frames.", args[0], batch.counter.frames);
+    0
+}
+
+// Parse the whole script before running it, so that a mistake in it doesn't
+// show up only after a long run.  The steps come with their line numbers.
+fn read_script(script_path: &str) -> Option<Vec<(usize, Step)>> {
+    let script = match fs::read_to_string(script_path) {
+        Ok(script) => { script },
+        Err(error) => {
+            error!("Failed to read the script `{}': {}.", script_path, error);
+            return None;
+        },
+    };
+    let mut steps = Vec::new();
+
+    for (line_index, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, argument) = match line.split_once(char::is_whitespace) {
+            Some((keyword, argument)) => { (keyword.to_lowercase(), argument.trim()) },
+            None => { (line.to_lowercase(), "") },
+        };
+        match parse_step(&keyword, argument) {
+            Ok(step) => { steps.push((line_index + 1, step)); },
+            Err(message) => {
+                error!("Line {} of the script `{}' is invalid: {}.", line_index + 1, script_path, message);
+                return None;
+            },
+        }
+    }
+    Some(steps)
+}
+
+fn parse_step(keyword: &str, argument: &str) -> Result<Step, String> {
+    let parse_frames = |text: &str| -> Result<usize, String> {
+        text.parse::<usize>().map_err(|_| format!("`{}' isn't a number of frames", text))
+    };
+    match keyword {
+        "cassette" => {
+            let cas_path = path::PathBuf::from(argument);
+            match cassette::Format::from_path(&cas_path) {
+                Some(format) => { Ok(Step::Cassette(cas_path, format)) },
+                None => { Err(format!("can't tell the format of the cassette file `{}' from its extension", argument)) },
+            }
+        },
+        "run" if !argument.is_empty() => {
+            Ok(Step::Run(path::PathBuf::from(argument)))
+        },
+        "type" => {
+            let text = argument.replace("\\n", "\n");
+            if keyboard::text_to_key_events(&text, 0).is_none() {
+                return Err(format!("the text `{}' can't be typed on the keyboard of the emulated machine", argument));
+            }
+            Ok(Step::Type(text))
+        },
+        "wait" => {
+            Ok(Step::Wait(parse_frames(argument)?))
+        },
+        "wait-for" => {
+            match argument.split_once(char::is_whitespace) {
+                Some((frames, text)) => { Ok(Step::WaitFor(parse_frames(frames)?, text.trim().to_owned())) },
+                None => { Err("`wait-for' needs a number of frames and a text".to_owned()) },
+            }
+        },
+        "expect" if !argument.is_empty() => {
+            Ok(Step::Expect(argument.to_owned()))
+        },
+        "print" => {
+            Ok(Step::Print)
+        },
+        "run" | "expect" => {
+            Err(format!("`{}' needs an argument", keyword))
+        },
+        _ => {
+            Err(format!("unknown step `{}'", keyword))
+        },
+    }
+}
+
+impl BatchRun {
+    fn run_step(&mut self, step: &Step) -> Result<(), String> {
+        match step {
+            Step::Cassette(cas_path, format) => {
+                // The cassette drive would create a missing file.
+                if !cas_path.is_file() {
+                    return Err(format!("the cassette file `{}' doesn't exist", cas_path.display()));
+                }
+                let cassette = &mut self.machine.devices.cassette;
+                if !(cassette.set_cassette_file(Some(cas_path.clone())) && cassette.set_cassette_data_format(*format) && cassette.set_cassette_file_offset(0)) {
+                    return Err(format!("the cassette `{}' couldn't be inserted", cas_path.display()));
+                }
+                Ok(())
+            },
+            Step::Run(cmd_path) => {
+                self.run_cmd_file(cmd_path)
+            },
+            Step::Type(text) => {
+                tool_machine::type_text(&mut self.machine, text);
+                Ok(())
+            },
+            Step::Wait(frames) => {
+                self.run_frames(*frames);
+                Ok(())
+            },
+            Step::WaitFor(frames, text) => {
+                for _ in 0..*frames {
+                    if self.screen_text().contains(text.as_str()) {
+                        return Ok(());
+                    }
+                    self.run_frames(1);
+                }
+                if self.screen_text().contains(text.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("`{}' didn't show up on the screen in {} frames", text, frames))
+                }
+            },
+            Step::Expect(text) => {
+                if self.screen_text().contains(text.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("`{}' isn't on the screen", text))
+                }
+            },
+            Step::Print => {
+                print!("{}", self.screen_text());
+                Ok(())
+            },
+        }
+    }
+    fn run_frames(&mut self, frames: usize) {
+        let last_frame = self.counter.frames + frames;
+        while self.counter.frames < last_frame {
+            self.machine.step(&mut tool_machine::IgnoredEvents, &mut self.counter);
+        }
+    }
+    fn screen_text(&self) -> String {
+        let frame = video::VideoFrame::new(&self.machine.memory_system.vid_mem, &self.machine.memory_system.hires);
+        frame.text(video::TextGraphics::Ascii)
+    }
+    // Load a /CMD program and jump to its entry point, like the DOS does.
+    fn run_cmd_file(&mut self, cmd_path: &path::Path) -> Result<(), String> {
+        let bytes = fs::read(cmd_path).map_err(|error| format!("failed to read `{}': {}", cmd_path.display(), error))?;
+        let program = match cmd_file::CmdFile::parse(&bytes) {
+            Some(program) => { program },
+            None => { return Err(format!("`{}' isn't a valid /CMD file", cmd_path.display())); },
+        };
+        let memory_end = memory::RAM_BASE as u32 + tool_machine::RAM_SIZE as u32;
+        if program.blocks.iter().any(|(address, data)| *address < memory::VID_BASE || (*address as u32 + data.len() as u32) > memory_end) {
+            return Err(format!("`{}' doesn't fit into the memory of the emulated machine", cmd_path.display()));
+        }
+        program.load(&mut self.machine.memory_system);
+
+        match program.entry_point {
+            Some(entry_point) => {
+                self.machine.cpu.regs.pc = entry_point;
+                self.machine.cpu.halted = false;
+                Ok(())
+            },
+            None => {
+                Err(format!("`{}' has no entry point to start it at", cmd_path.display()))
+            },
+        }
+    }
+}
+
+// Compare the screen with the expected one, row by row, without the spaces
+// and the empty rows at the ends.
+fn compare_screen(screen: &str, expect_path: &str) -> bool {
+    let expected = match fs::read_to_string(expect_path) {
+        Ok(expected) => { expected },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", expect_path, error);
+            return false;
+        },
+    };
+    let rows = |text: &str| -> Vec<String> {
+        let mut rows: Vec<String> = text.lines().map(|row| row.trim_end().to_owned()).collect();
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        rows
+    };
+    let screen_rows = rows(screen);
+    let expected_rows = rows(&expected);
+    let row_count = screen_rows.len().max(expected_rows.len());
+
+    for row in 0..row_count {
+        let screen_row = screen_rows.get(row).map(|row| row.as_str()).unwrap_or("");
+        let expected_row = expected_rows.get(row).map(|row| row.as_str()).unwrap_or("");
+        if screen_row != expected_row {
+            error!("The screen differs from the one in `{}' at row {}:", expect_path, row + 1);
+            error!("    expected: {}", expected_row);
+            error!("    found:    {}", screen_row);
+            return false;
+        }
+    }
+    info!("The screen matches the one in `{}'.", expect_path);
+    true
+}
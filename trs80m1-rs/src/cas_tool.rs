@@ -0,0 +1,277 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error};
+
+use std::fs;
+use std::path;
+
+use trs80m1_rs_core::basic;
+use trs80m1_rs_core::tape;
+
+
+// The `cas' command line mode, which converts Level II BASIC programs saved
+// on CAS tapes to text listings and back, and edits CAS tapes, without
+// starting the emulator:
+//
+//     trs80m1-rs cas detokenize <cas-file> [text-file]
+//     trs80m1-rs cas tokenize <text-file> [cas-file] [name]
+//     trs80m1-rs cas split <cas-file> [directory]
+//     trs80m1-rs cas trim <cas-file> [output-file]
+//     trs80m1-rs cas join <output-file> <cas-file>...
+//
+// If the output file isn't specified, it's named after the input file, with
+// the extension changed to `.bas' or `.cas'.  The single character program
+// name defaults to the first letter of the file name.
+//
+// Splitting a tape puts each recording on it into its own file, named after
+// the tape, the number of the recording and the name it was saved under.
+// Trimming a tape (in place, unless an output file is given) cuts the leader
+// of each recording to the standard length and drops anything that isn't a
+// recording, and joining tapes puts all of their recordings onto a new one,
+// trimmed the same way.
+//
+
+pub fn print_usage(progname: &str) {
+    println!("Usage: {} cas detokenize <cas-file> [text-file]", progname);
+    println!("       {} cas tokenize <text-file> [cas-file] [name]", progname);
+    println!("       {} cas split <cas-file> [directory]", progname);
+    println!("       {} cas trim <cas-file> [output-file]", progname);
+    println!("       {} cas join <output-file> <cas-file>...", progname);
+}
+
+// Returns the exit code of the program.
+pub fn run(progname: &str, args: &[String]) -> i32 {
+    let subcommand = match args.first() {
+        Some(subcommand) => { subcommand.to_lowercase() },
+        None => {
+            print_usage(progname);
+            return 1;
+        },
+    };
+    let (min_args, max_args) = if subcommand == "tokenize" {
+        (2, 4)
+    } else if subcommand == "join" {
+        (3, usize::MAX)
+    } else {
+        (2, 3)
+    };
+    if args.len() < min_args || args.len() > max_args {
+        print_usage(progname);
+        return 1;
+    }
+
+    let success = if subcommand == "detokenize" {
+        detokenize(&args[1], args.get(2))
+    } else if subcommand == "tokenize" {
+        tokenize(&args[1], args.get(2), args.get(3))
+    } else if subcommand == "split" {
+        split(&args[1], args.get(2))
+    } else if subcommand == "trim" {
+        trim(&args[1], args.get(2))
+    } else if subcommand == "join" {
+        join(&args[1], &args[2..])
+    } else {
+        eprintln!("{}: Unknown cas subcommand `{}'.", progname, subcommand);
+        print_usage(progname);
+        false
+    };
+    if success { 0 } else { 1 }
+}
+
+fn detokenize(cas_path: &str, text_path: Option<&String>) -> bool {
+    let contents = match fs::read(cas_path) {
+        Ok(contents) => { contents },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", cas_path, error);
+            return false;
+        },
+    };
+    let (name, program) = match basic::program_from_cas(&contents) {
+        Some(found) => { found },
+        None => { return false; },
+    };
+    let listing = match basic::detokenize(program) {
+        Some(listing) => { listing },
+        None => { return false; },
+    };
+
+    let text_path = match text_path {
+        Some(text_path) => { text_path.clone() },
+        None => { path::Path::new(cas_path).with_extension("bas").to_string_lossy().into_owned() },
+    };
+    match fs::write(&text_path, &listing) {
+        Ok(..) => {
+            info!("Wrote the listing of the BASIC program `{}' into `{}'.", name, text_path);
+            true
+        },
+        Err(error) => {
+            error!("Failed to write `{}': {}.", text_path, error);
+            false
+        },
+    }
+}
+
+fn tokenize(text_path: &str, cas_path: Option<&String>, name: Option<&String>) -> bool {
+    let listing = match fs::read(text_path) {
+        Ok(listing) => { listing },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", text_path, error);
+            return false;
+        },
+    };
+    let program = match basic::tokenize(&listing) {
+        Some(program) => { program },
+        None => { return false; },
+    };
+
+    let cas_path = match cas_path {
+        Some(cas_path) => { cas_path.clone() },
+        None => { path::Path::new(text_path).with_extension("cas").to_string_lossy().into_owned() },
+    };
+    let name = match name {
+        Some(name) => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(name), None) if name.is_ascii_alphanumeric() => { name.to_ascii_uppercase() },
+                _ => {
+                    error!("The program name `{}' isn't a single letter or digit.", name);
+                    return false;
+                },
+            }
+        },
+        None => {
+            let stem = path::Path::new(&cas_path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            match stem.chars().find(|ch| ch.is_ascii_alphabetic()) {
+                Some(name) => { name.to_ascii_uppercase() },
+                None => { 'A' },
+            }
+        },
+    };
+
+    match fs::write(&cas_path, basic::program_to_cas(name, &program)) {
+        Ok(..) => {
+            info!("Saved the BASIC program `{}' into `{}'.", name, cas_path);
+            true
+        },
+        Err(error) => {
+            error!("Failed to write `{}': {}.", cas_path, error);
+            false
+        },
+    }
+}
+
+// Read a CAS tape, lining up its recordings with their sync bytes if needed.
+fn read_cas(cas_path: &str) -> Option<Vec<u8>> {
+    match fs::read(cas_path) {
+        Ok(contents) => {
+            match tape::align_cas(&contents) {
+                Some(aligned) => { Some(aligned) },
+                None => { Some(contents) },
+            }
+        },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", cas_path, error);
+            None
+        },
+    }
+}
+
+// The recordings on a tape, each with a leader of the standard length.
+fn standard_recordings(cas_path: &str) -> Option<Vec<(tape::Recording, Vec<u8>)>> {
+    let contents = read_cas(cas_path)?;
+    let recordings = tape::scan_cas(&contents);
+    if recordings.is_empty() {
+        error!("There are no recordings on `{}'.", cas_path);
+        return None;
+    }
+    Some(recordings.into_iter().map(|recording| {
+        let standard = tape::standard_recording(&contents, &recording);
+        (recording, standard)
+    }).collect())
+}
+
+fn split(cas_path: &str, directory: Option<&String>) -> bool {
+    let recordings = match standard_recordings(cas_path) {
+        Some(recordings) => { recordings },
+        None => { return false; },
+    };
+    let directory = match directory {
+        Some(directory) => { path::PathBuf::from(directory) },
+        None => { path::Path::new(cas_path).parent().map(|parent| parent.to_path_buf()).unwrap_or_default() },
+    };
+    let stem = path::Path::new(cas_path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+    for (index, (recording, standard)) in recordings.iter().enumerate() {
+        let file_name = match recording.name() {
+            Some(name) => {
+                let name: String = name.trim().chars().map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' }).collect();
+                format!("{}-{}-{}.cas", stem, index + 1, name)
+            },
+            None => { format!("{}-{}.cas", stem, index + 1) },
+        };
+        let split_path = directory.join(file_name);
+        match fs::write(&split_path, standard) {
+            Ok(..) => {
+                info!("Saved the {} into `{}'.", recording.description(), split_path.display());
+            },
+            Err(error) => {
+                error!("Failed to write `{}': {}.", split_path.display(), error);
+                return false;
+            },
+        }
+    }
+    true
+}
+
+fn trim(cas_path: &str, output_path: Option<&String>) -> bool {
+    let recordings = match standard_recordings(cas_path) {
+        Some(recordings) => { recordings },
+        None => { return false; },
+    };
+    let output_path = match output_path {
+        Some(output_path) => { output_path.as_str() },
+        None => { cas_path },
+    };
+    write_recordings(output_path, &recordings)
+}
+
+fn join(output_path: &str, cas_paths: &[String]) -> bool {
+    let mut recordings = Vec::new();
+    for cas_path in cas_paths {
+        match standard_recordings(cas_path) {
+            Some(found) => { recordings.extend(found); },
+            None => { return false; },
+        }
+    }
+    write_recordings(output_path, &recordings)
+}
+
+fn write_recordings(output_path: &str, recordings: &[(tape::Recording, Vec<u8>)]) -> bool {
+    let contents: Vec<u8> = recordings.iter().flat_map(|(_, standard)| standard.iter().copied()).collect();
+
+    match fs::write(output_path, contents) {
+        Ok(..) => {
+            for (recording, _) in recordings {
+                info!("Saved the {} into `{}'.", recording.description(), output_path);
+            }
+            true
+        },
+        Err(error) => {
+            error!("Failed to write `{}': {}.", output_path, error);
+            false
+        },
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+
+// The commands of the curses-based user interface, with their sub-commands,
+// the words which follow them, how they're used and a few examples.  The
+// list of commands and the usage shown by `/help' are made from here, and
+// so is the completion with Tab, so they don't go out of step with each
+// other.  The parsing of the commands, and the longer explanations of what
+// they do, are in user_interface.rs.
+//
+// The words are either one of a set of choices, or the name of a file, or
+// something which can't be completed, like a number.
+//
+#[derive(Copy, Clone)]
+pub enum Word {
+    Choice(&'static [&'static str]),
+    ChoiceOrFile(&'static [&'static str]),
+    KeyProfile,
+    ConfigFile,
+    WorkingDirFile,
+    Other,
+}
+
+pub struct SubCommand {
+    pub name:     &'static str,
+    pub words:    &'static [Word],
+    pub forms:    &'static [(&'static str, &'static str)], // The arguments, and what it does with them.
+    pub examples: &'static [&'static str],
+}
+
+pub struct Command {
+    pub name:         &'static str,
+    pub summary:      &'static str, // Empty for the aliases of the other commands.
+    pub sub_commands: &'static [SubCommand],
+}
+
+const ON_OFF:        &[&str] = &["on", "off"];
+const ON_OFF_TOGGLE: &[&str] = &["on", "off", "toggle"];
+const MESSAGES:      &[&str] = &["emulator", "machine"];
+const SOUND_SOURCES: &[&str] = &["tape", "program", "orchestra", "ay"];
+const TAPE_FORMATS:  &[&str] = &["cas", "cpt", "wav"];
+
+// The usage lines longer than this have what they do on a line of its own.
+const MAX_USAGE_WIDTH: usize = 40;
+
+pub const COMMANDS: &[Command] = &[
+    Command { name: "help", summary: "shows information about other commands.", sub_commands: &[] },
+    Command { name: "messages", summary: "manages the messages on the curses-based interface.", sub_commands: &[
+        SubCommand { name: "show", words: &[Word::Choice(MESSAGES)],
+                     forms: &[("<machine|emulator>", "makes the given type of messages visible.")],
+                     examples: &["messages show machine"] },
+        SubCommand { name: "hide", words: &[Word::Choice(MESSAGES)],
+                     forms: &[("<machine|emulator>", "makes the given type of messages invisible.")],
+                     examples: &["messages hide emulator"] },
+        SubCommand { name: "toggle", words: &[Word::Choice(MESSAGES)],
+                     forms: &[("<machine|emulator>", "toggles the visibility of messages of the given type.")],
+                     examples: &["messages toggle machine"] },
+        SubCommand { name: "clear", words: &[Word::Choice(&["emulator", "machine", "all"])],
+                     forms: &[("<machine|emulator|all>", "clears/removes messages of the given type.")],
+                     examples: &["messages clear all"] },
+        SubCommand { name: "level", words: &[Word::Choice(&["error", "warning", "info"])],
+                     forms: &[("<error|warning|info>", "shows the logged messages of the given level, or a more severe one.")],
+                     examples: &["messages level warning"] },
+        SubCommand { name: "module", words: &[Word::Other],
+                     forms: &[("<module|all>", "shows only the logged messages of the given module.")],
+                     examples: &["messages module cassette", "messages module all"] },
+        SubCommand { name: "filter", words: &[],
+                     forms: &[("", "shows the filter and the modules which logged messages.")],
+                     examples: &[] },
+        SubCommand { name: "scroll", words: &[Word::Choice(&["up", "down", "top", "bottom"])],
+                     forms: &[("<up|down|top|bottom>", "scrolls through the messages.")],
+                     examples: &["messages scroll top"] },
+        SubCommand { name: "export", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "writes a transcript of the session, with the commands, the messages and the times they came at, into a file.")],
+                     examples: &["messages export session.txt"] },
+    ]},
+    Command { name: "machine", summary: "allows you to change the state of the emulated machine.", sub_commands: &[
+        SubCommand { name: "power", words: &[Word::Choice(ON_OFF)],
+                     forms: &[("<on|off>", "powers the machine on or off.")],
+                     examples: &["machine power off"] },
+        SubCommand { name: "reset", words: &[Word::Choice(&["cpu", "full"])],
+                     forms: &[("[cpu|full]", "performs a CPU reset, or a full reset.")],
+                     examples: &["machine reset", "machine reset full"] },
+        SubCommand { name: "restore", words: &[],
+                     forms: &[("", "puts the machine into a default state.")],
+                     examples: &[] },
+        SubCommand { name: "switch-rom", words: &[Word::Choice(&["1", "2", "3"])],
+                     forms: &[("<num>", "change the currently used BASIC rom (Level 1 or 2, or 3 for misc rom).")],
+                     examples: &["machine switch-rom 2"] },
+        SubCommand { name: "pause", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "pauses or unpauses the machine.")],
+                     examples: &["machine pause", "machine pause toggle"] },
+        SubCommand { name: "unpause", words: &[],
+                     forms: &[("", "alias for `machine pause off'.")],
+                     examples: &[] },
+        SubCommand { name: "warp", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "runs the machine as fast as the computer allows, whatever the speed limit, or back at the speed limit.")],
+                     examples: &["machine warp", "machine warp off"] },
+        SubCommand { name: "speed", words: &[Word::Choice(&["100", "50", "10", "unlimited"])],
+                     forms: &[("", "shows the speed limit."),
+                              ("<percent|unlimited>", "runs the machine at a percentage of the speed of the real one, from 10 to 1000, like 50 or 10 for slow motion, or as fast as the computer allows.")],
+                     examples: &["machine speed 50", "machine speed 100"] },
+        SubCommand { name: "advance", words: &[Word::Other],
+                     forms: &[("[<frames>]", "runs the paused machine for a video frame, or the number of them given, or pauses it if it's running.")],
+                     examples: &["machine advance", "machine advance 10"] },
+        SubCommand { name: "paste", words: &[],
+                     forms: &[("", "types in the text in the clipboard.")],
+                     examples: &[] },
+        SubCommand { name: "type", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "types in the text of a file.")],
+                     examples: &["machine type listing.bas"] },
+        SubCommand { name: "type-text", words: &[],
+                     forms: &[("<text>", "types in the given text, with `\\n' for ENTER.")],
+                     examples: &["machine type-text CLOAD\\n", "machine type-text \\n"] },
+        SubCommand { name: "latency", words: &[],
+                     forms: &[("", "shows how long the keys took to reach the machine.")],
+                     examples: &[] },
+        SubCommand { name: "clock", words: &[],
+                     forms: &[("", "shows how long the machine has been on, in the emulated and in the real time.")],
+                     examples: &[] },
+        SubCommand { name: "status", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "shows or hides the machine status pane.")],
+                     examples: &["machine status on"] },
+        SubCommand { name: "save-state", words: &[Word::ConfigFile],
+                     forms: &[("[file]", "saves the state of the machine into a file.")],
+                     examples: &["machine save-state", "machine save-state defense.sav"] },
+        SubCommand { name: "load-state", words: &[Word::ConfigFile],
+                     forms: &[("[file]", "continues from a saved state.")],
+                     examples: &["machine load-state", "machine load-state defense.sav"] },
+    ]},
+    Command { name: "memory", summary: "allows you to change the state of the memory system.", sub_commands: &[
+        SubCommand { name: "load", words: &[Word::Choice(&["ram", "rom"]), Word::WorkingDirFile],
+                     forms: &[("<rom|ram> <file> [offset]", "loads a file into either ram or rom.")],
+                     examples: &["memory load ram prog.bin 0x5200", "memory load rom default"] },
+        SubCommand { name: "wipe", words: &[Word::Choice(&["ram", "rom", "all"])],
+                     forms: &[("<rom|ram|all>", "clears the contents of rom, ram, or both.")],
+                     examples: &["memory wipe ram"] },
+        SubCommand { name: "dump", words: &[],
+                     forms: &[("<address> [length]", "shows the memory in hex and as text, 64 bytes by default.")],
+                     examples: &["memory dump 0x4200", "memory dump 3C00h 128"] },
+    ]},
+    Command { name: "cassette", summary: "allows you to change the state of the cassette drive.", sub_commands: &[
+        SubCommand { name: "insert", words: &[Word::ChoiceOrFile(TAPE_FORMATS), Word::ConfigFile],
+                     forms: &[("[format] <file>", "loads a file into the cassette drive.")],
+                     examples: &["cassette insert game.cas", "cassette insert wav side-a.wav"] },
+        SubCommand { name: "load", words: &[Word::ChoiceOrFile(TAPE_FORMATS), Word::ConfigFile],
+                     forms: &[("[format] <file>", "loads a file into the cassette drive, and types in the command to load its first program.")],
+                     examples: &["cassette load games/defense.cas"] },
+        SubCommand { name: "eject", words: &[],
+                     forms: &[("", "removes the currently inserted cassette from the drive.")],
+                     examples: &[] },
+        SubCommand { name: "undo", words: &[],
+                     forms: &[("", "puts back the cassette which was in the drive before the last insert or eject.")],
+                     examples: &[] },
+        SubCommand { name: "erase", words: &[],
+                     forms: &[("", "clears the contents of the inserted cassette.")],
+                     examples: &[] },
+        SubCommand { name: "seek", words: &[],
+                     forms: &[("<position>", "rewinds the tape to the specified location.")],
+                     examples: &["cassette seek 1024"] },
+        SubCommand { name: "rewind", words: &[],
+                     forms: &[("", "rewinds the tape to the beginning.")],
+                     examples: &[] },
+        SubCommand { name: "ff", words: &[],
+                     forms: &[("", "winds the tape forward to its end.")],
+                     examples: &[] },
+        SubCommand { name: "index", words: &[],
+                     forms: &[("", "lists the recordings on the tape.")],
+                     examples: &[] },
+        SubCommand { name: "goto", words: &[],
+                     forms: &[("<recording>", "winds the tape to the start of a recording, given by its number or name.")],
+                     examples: &["cassette goto 2", "cassette goto INVADE"] },
+        SubCommand { name: "library", words: &[Word::Other, Word::Choice(&["load"])],
+                     forms: &[("", "opens the file picker in the cassette library, with the programs on the cassettes, or lists them by number without the curses-based interface."),
+                              ("<number>", "inserts the cassette with the given number from the library."),
+                              ("<number> load", "inserts the cassette, and types in the command to load its first program.")],
+                     examples: &["cassette library", "cassette library 3 load"] },
+        SubCommand { name: "motor", words: &[Word::Choice(&["on", "off", "auto"])],
+                     forms: &[("<on|off|auto>", "forces the motor on or off, or leaves it up to the machine.")],
+                     examples: &["cassette motor auto"] },
+    ]},
+    Command { name: "disk", summary: "allows you to examine and modify disk images.", sub_commands: &[
+        SubCommand { name: "dir", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "lists the files stored on a TRSDOS or LDOS formatted disk image file, not on a drive of the machine.")],
+                     examples: &["disk dir ldos.dsk"] },
+        SubCommand { name: "commit", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "writes the pending copy-on-write changes into the disk image file, not into a drive of the machine.")],
+                     examples: &["disk commit ldos.dsk"] },
+        SubCommand { name: "discard", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "throws the pending copy-on-write changes of the disk image file away.")],
+                     examples: &["disk discard ldos.dsk"] },
+        SubCommand { name: "read", words: &[Word::Other, Word::Other, Word::ConfigFile],
+                     forms: &[("<track>[:<side>] <sector> <file>", "shows a hex dump of a single sector of a disk image.")],
+                     examples: &["disk read 17 0 ldos.dsk", "disk read 17:1 3 ldos.dsk"] },
+        SubCommand { name: "write", words: &[Word::Other, Word::Other, Word::Other, Word::Other, Word::ConfigFile],
+                     forms: &[("<track>[:<side>] <sector> <offset> <bytes> <file>", "overwrites part of a sector of a disk image, the bytes are given as a string of hexadecimal digits, like `C30040'.")],
+                     examples: &["disk write 0 0 0x10 C30040 ldos.dsk"] },
+    ]},
+    Command { name: "video", summary: "allows you to change how the emulator window shows the screen.", sub_commands: &[
+        SubCommand { name: "fullscreen", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "switches the emulator window into or out of the full-screen mode.")],
+                     examples: &["video fullscreen", "video fullscreen off"] },
+        SubCommand { name: "scaling", words: &[Word::Choice(&["integer", "aspect", "stretch"])],
+                     forms: &[("<mode>", "changes how the screen is scaled up to the size of the window.")],
+                     examples: &["video scaling aspect"] },
+        SubCommand { name: "colors", words: &[Word::Choice(&["white", "green", "amber"])],
+                     forms: &[("<scheme>", "changes the colors of the screen."),
+                              ("<#fg> [#bg]", "sets the foreground and background colors of the screen.")],
+                     examples: &["video colors green", "video colors #FFB000 #101010"] },
+        SubCommand { name: "screenshot", words: &[Word::Choice(&["native", "scaled"])],
+                     forms: &[("[native|scaled]", "saves the screen into a PNG file.")],
+                     examples: &["video screenshot", "video screenshot native"] },
+        SubCommand { name: "text", words: &[Word::ConfigFile],
+                     forms: &[("[file]", "copies the text on the screen into the clipboard, or saves it into a file.")],
+                     examples: &["video text", "video text screen.txt"] },
+        SubCommand { name: "charset", words: &[Word::Choice(&["alternate", "graphics", "toggle"])],
+                     forms: &[("[alternate|graphics|toggle]", "switches the alternate character set in or out.")],
+                     examples: &["video charset alternate"] },
+        SubCommand { name: "picture", words: &[Word::Choice(&["brightness", "contrast", "scanlines"])],
+                     forms: &[("[<setting> <level>]", "shows or adjusts the brightness, contrast and scanlines of the picture.")],
+                     examples: &["video picture", "video picture brightness +10", "video picture scanlines 40"] },
+        SubCommand { name: "magnifier", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "opens or closes a window showing an enlarged part of the screen.")],
+                     examples: &["video magnifier on"] },
+    ]},
+    Command { name: "keyboard", summary: "allows you to switch between the key profiles for games.", sub_commands: &[
+        SubCommand { name: "profile", words: &[Word::KeyProfile],
+                     forms: &[("[name|none]", "switches to a key profile, or lists them.")],
+                     examples: &["keyboard profile", "keyboard profile numpad", "keyboard profile none"] },
+    ]},
+    Command { name: "sound", summary: "allows you to examine the sound output and set up the mixer.", sub_commands: &[
+        SubCommand { name: "stats", words: &[],
+                     forms: &[("", "shows how well the sound output keeps up with the emulated machine.")],
+                     examples: &[] },
+        SubCommand { name: "mixer", words: &[],
+                     forms: &[("", "shows the master volume and which sound sources are muted.")],
+                     examples: &[] },
+        SubCommand { name: "volume", words: &[],
+                     forms: &[("<0-100>", "sets the master volume, in percent.")],
+                     examples: &["sound volume 50"] },
+        SubCommand { name: "mute", words: &[Word::Choice(SOUND_SOURCES)],
+                     forms: &[("<source>", "mutes one of the sound sources.")],
+                     examples: &["sound mute tape"] },
+        SubCommand { name: "unmute", words: &[Word::Choice(SOUND_SOURCES)],
+                     forms: &[("<source>", "brings a muted sound source back.")],
+                     examples: &["sound unmute tape"] },
+    ]},
+    Command { name: "record", summary: "allows you to record the sound, the screen or the input of the emulated machine, and to replay the input.", sub_commands: &[
+        SubCommand { name: "audio", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "starts recording the sound of the emulated machine into a WAV file.")],
+                     examples: &["record audio session.wav"] },
+        SubCommand { name: "video", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "starts recording the screen of the emulated machine into an animated GIF file.")],
+                     examples: &["record video demo.gif"] },
+        SubCommand { name: "input", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "starts recording the keyboard and the joystick, from a snapshot of the emulated machine.")],
+                     examples: &["record input bug.inp"] },
+        SubCommand { name: "replay", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "restores the snapshot of an input recording and replays its input, in place of the keyboard and the joystick.")],
+                     examples: &["record replay bug.inp"] },
+        SubCommand { name: "stop", words: &[],
+                     forms: &[("", "finishes the recordings, and stops the replay.")],
+                     examples: &[] },
+    ]},
+    Command { name: "timer", summary: "measures how long the emulated machine takes to run a program.", sub_commands: &[
+        SubCommand { name: "start", words: &[],
+                     forms: &[("", "starts the timer, or starts it over.")],
+                     examples: &[] },
+        SubCommand { name: "lap", words: &[],
+                     forms: &[("", "shows the time since the last lap and since the start, and keeps the timer running.")],
+                     examples: &[] },
+        SubCommand { name: "stop", words: &[],
+                     forms: &[("", "shows the time since the start, and stops the timer.")],
+                     examples: &[] },
+    ]},
+    Command { name: "rhai", summary: "runs scripts which control the emulated machine.", sub_commands: &[
+        SubCommand { name: "run", words: &[Word::ConfigFile],
+                     forms: &[("<file>", "runs the script in the given file.")],
+                     examples: &["rhai run trainer.rhai"] },
+        SubCommand { name: "eval", words: &[],
+                     forms: &[("<code>", "runs the given code, and shows the value it ends with.")],
+                     examples: &["rhai eval peek(0x4000)", "rhai eval set_reg(\"pc\", 0x0000)"] },
+        SubCommand { name: "stop", words: &[],
+                     forms: &[("", "stops the script, its breakpoints and callbacks are removed.")],
+                     examples: &[] },
+    ]},
+    Command { name: "debugger", summary: "allows you to watch the CPU and the memory of the emulated machine.", sub_commands: &[
+        SubCommand { name: "window", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "opens or closes the debugger window.")],
+                     examples: &["debugger window on"] },
+        SubCommand { name: "pane", words: &[Word::Choice(ON_OFF_TOGGLE)],
+                     forms: &[("[on|off|toggle]", "shows or hides the debugger pane above the messages.")],
+                     examples: &["debugger pane on"] },
+        SubCommand { name: "memory", words: &[],
+                     forms: &[("<address>", "chooses the part of the memory shown in the debugger window and pane.")],
+                     examples: &["debugger memory 0x3C00", "debugger memory 4200h"] },
+    ]},
+    Command { name: "config", summary: "allows you to change configuration settings.", sub_commands: &[
+        SubCommand { name: "list", words: &[],
+                     forms: &[("", "shows all config entries and their current value.")],
+                     examples: &[] },
+        SubCommand { name: "dump", words: &[],
+                     forms: &[("", "shows the configuration in effect, with the entries overridden for this run.")],
+                     examples: &[] },
+        SubCommand { name: "show", words: &[],
+                     forms: &[("<section>_<entry>", "shows the value of the given config entry.")],
+                     examples: &["config show video_overlay"] },
+        SubCommand { name: "change", words: &[],
+                     forms: &[("<section>_<entry> = <value>", "changes the value of the given config entry.")],
+                     examples: &["config change video_overlay = full", "config change audio_volume = 80"] },
+    ]},
+    Command { name: "browse", summary: "lets you choose a file by going through the directories.", sub_commands: &[
+        SubCommand { name: "cassette", words: &[Word::ConfigFile],
+                     forms: &[("[directory]", "chooses a cassette to insert.")],
+                     examples: &["browse cassette", "browse cassette games"] },
+        SubCommand { name: "disk", words: &[Word::ConfigFile],
+                     forms: &[("[directory]", "chooses a disk image to list the files of.")],
+                     examples: &["browse disk"] },
+        SubCommand { name: "rom", words: &[Word::ConfigFile],
+                     forms: &[("[directory]", "chooses a ROM image, to put into the configuration.")],
+                     examples: &["browse rom"] },
+        SubCommand { name: "script", words: &[Word::ConfigFile],
+                     forms: &[("[directory]", "chooses a script of commands to run.")],
+                     examples: &["browse script"] },
+    ]},
+    Command { name: "aliases", summary: "lists the command aliases of the configuration file.", sub_commands: &[] },
+    Command { name: "nmi", summary: "issues a non-maskable interrupt, like the reset button of the machine.", sub_commands: &[] },
+    Command { name: "clear", summary: "", sub_commands: &[] },
+    Command { name: "cls", summary: "", sub_commands: &[] },
+    Command { name: "pause", summary: "", sub_commands: &[] },
+    Command { name: "unpause", summary: "", sub_commands: &[] },
+    Command { name: "exit", summary: "closes the emulator program.", sub_commands: &[] },
+    Command { name: "quit", summary: "", sub_commands: &[] },
+];
+
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.name.eq_ignore_ascii_case(name))
+}
+
+impl Command {
+    pub fn sub_command(&self, name: &str) -> Option<&'static SubCommand> {
+        self.sub_commands.iter().find(|sub_command| sub_command.name.eq_ignore_ascii_case(name))
+    }
+    // The usage of all of the sub-commands, or of only one of them, lined
+    // up, like `    machine power <on|off> - powers the machine on or off.'.
+    pub fn usage_lines(&self, only: Option<&SubCommand>) -> Vec<String> {
+        let usages: Vec<(String, &str)> = self.sub_commands.iter().filter(|sub_command| {
+            only.is_none_or(|only| only.name == sub_command.name)
+        }).flat_map(|sub_command| {
+            sub_command.forms.iter().map(move |(arguments, description)| {
+                let usage = if arguments.is_empty() {
+                    format!("{} {}", self.name, sub_command.name)
+                } else {
+                    format!("{} {} {}", self.name, sub_command.name, arguments)
+                };
+                (usage, *description)
+            })
+        }).collect();
+
+        let width = usages.iter().map(|(usage, _)| usage.len()).filter(|length| *length <= MAX_USAGE_WIDTH).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for (usage, description) in usages {
+            if usage.len() <= width {
+                lines.push(format!("    {:<width$} - {}", usage, description, width = width));
+            } else {
+                lines.push(format!("    {}", usage));
+                lines.push(format!("    {:<width$} - {}", "", description, width = width));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::fs;
+use std::path;
+
+use crate::aliases;
+use crate::commands;
+use crate::commands::Word;
+use crate::sdl_keyboard;
+
+
+// Completion of the commands typed on the prompt of the curses-based user
+// interface, with Tab.  The words which can follow the commands are taken
+// from the table of commands, see commands.rs.
+//
+// The files are looked up in the configuration directory, like the commands
+// do with the file names which aren't absolute, except for `memory load',
+// which takes them from the working directory.
+//
+
+pub fn is_command(name: &str) -> bool {
+    commands::find(name).is_some()
+}
+
+// The words the word being typed can be completed to, which starts at
+// `word_start', in characters.
+pub struct Completion {
+    pub word_start: usize,
+    pub candidates: Vec<String>,
+}
+
+// Complete the last word of a command, given without the leading slash and
+// up to the cursor.  The aliases of the configuration file are completed
+// along with the commands.
+pub fn complete(text: &str, config_dir: &path::Path, aliases: &[aliases::Alias]) -> Completion {
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let current = if text.ends_with(char::is_whitespace) { "" } else { words.pop().unwrap_or("") };
+    let word_start = text.chars().count() - current.chars().count();
+
+    let command_names = || -> Vec<String> {
+        let mut names: Vec<String> = commands::COMMANDS.iter().map(|command| command.name.to_owned()).collect();
+        names.extend(aliases.iter().map(|alias| alias.name.clone()));
+        names
+    };
+    let command = words.first().and_then(|command| commands::find(command));
+    let sub_commands = command.map(|command| command.sub_commands).unwrap_or(&[]);
+    let word = match (command, words.get(1)) {
+        (Some(command), Some(sub_command)) => {
+            match command.sub_command(sub_command) {
+                Some(sub_command) => { sub_command.words.get(words.len() - 2).copied().unwrap_or(Word::Other) },
+                None => { Word::Other },
+            }
+        },
+        _ => { Word::Other },
+    };
+
+    // The command names and the sub-commands aren't in the table as choices,
+    // as they're the keys of it.
+    let choices: Vec<String> = match words.len() {
+        0 => { command_names() },
+        1 if words[0].eq_ignore_ascii_case("help") => { command_names() },
+        1 => { sub_commands.iter().map(|sub_command| sub_command.name.to_owned()).collect() },
+        _ => {
+            match word {
+                Word::Choice(choices) | Word::ChoiceOrFile(choices) => { choices.iter().map(|choice| (*choice).to_owned()).collect() },
+                Word::KeyProfile => {
+                    let mut choices = vec!["none".to_owned()];
+                    choices.extend(sdl_keyboard::key_profile_names(&[]));
+                    choices
+                },
+                Word::ConfigFile | Word::WorkingDirFile | Word::Other => { Vec::new() },
+            }
+        },
+    };
+    let current_lower = current.to_lowercase();
+    let mut candidates: Vec<String> = choices.into_iter().filter(|choice| choice.starts_with(&current_lower)).collect();
+
+    if words.len() >= 2 {
+        match word {
+            Word::ChoiceOrFile(..) | Word::ConfigFile => { candidates.extend(complete_file(current, config_dir)); },
+            Word::WorkingDirFile => { candidates.extend(complete_file(current, path::Path::new("."))); },
+            Word::Choice(..) | Word::KeyProfile | Word::Other => { },
+        }
+    }
+    Completion { word_start, candidates }
+}
+
+// The files and directories whose path starts with the given one, the ones
+// whose names start with a dot only if it's typed.  The directories end with
+// a slash, so that their contents can be completed next.
+fn complete_file(partial: &str, base_dir: &path::Path) -> Vec<String> {
+    let (dir_part, name_part) = match partial.rfind(['/', path::MAIN_SEPARATOR]) {
+        Some(index) => { partial.split_at(index + 1) },
+        None => { ("", partial) },
+    };
+    let dir_path = if path::Path::new(dir_part).is_absolute() {
+        path::PathBuf::from(dir_part)
+    } else {
+        base_dir.join(dir_part)
+    };
+    let entries = match fs::read_dir(&dir_path) {
+        Ok(entries) => { entries },
+        Err(..) => { return Vec::new(); },
+    };
+
+    let mut candidates = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(name_part) || (name.starts_with('.') && !name_part.starts_with('.')) {
+            continue;
+        }
+        let is_dir = entry.path().is_dir();
+        candidates.push(format!("{}{}{}", dir_part, name, if is_dir { "/" } else { "" }));
+    }
+    candidates.sort();
+    candidates
+}
+
+// The longest start the candidates have in common.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = match candidates.first() {
+        Some(first) => { first.chars().collect() },
+        None => { return String::new(); },
+    };
+    for candidate in candidates.iter().skip(1) {
+        let common = prefix.iter().zip(candidate.chars()).take_while(|(a, b)| **a == *b).count();
+        prefix.truncate(common);
+    }
+    prefix.into_iter().collect()
+}
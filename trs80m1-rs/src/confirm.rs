@@ -0,0 +1,96 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::time;
+
+
+// Confirmation of the actions which throw away work, which are only carried
+// out when they're asked for twice in a row, within a few seconds.  This is
+// done in the logic core, so it works the same for the commands, the keys
+// and the menu of the emulator window, which have no way to ask a question.
+//
+// Which actions need it is set with the `confirm_actions' entry of the
+// configuration file.
+//
+pub const CONFIRM_SECONDS: u64 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Reset,          // The full reset, which loses the running program.
+    Discard,        // Throwing away the changes in a disk overlay.
+    Overwrite,      // Saving over an existing file.
+}
+
+pub const ACTIONS: [(&str, Action); 3] = [
+    ("reset",     Action::Reset),
+    ("discard",   Action::Discard),
+    ("overwrite", Action::Overwrite),
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        let name = name.to_lowercase();
+        ACTIONS.iter().find(|(action_name, _)| *action_name == name).map(|(_, action)| *action)
+    }
+    pub fn name(&self) -> &'static str {
+        ACTIONS.iter().find(|(_, action)| action == self).map(|(name, _)| *name).unwrap_or("reset")
+    }
+}
+
+// Parse the actions of the configuration file, a list of them, or `none'.
+pub fn parse_actions(text: &str) -> Option<Vec<Action>> {
+    let text = text.trim().to_lowercase();
+    let mut actions = Vec::new();
+    if text == "none" {
+        return Some(actions);
+    }
+    for name in text.split_whitespace() {
+        let action = Action::from_name(name)?;
+        if !actions.contains(&action) {
+            actions.push(action);
+        }
+    }
+    Some(actions)
+}
+
+pub fn actions_text(actions: &[Action]) -> String {
+    if actions.is_empty() {
+        return "none".to_owned();
+    }
+    actions.iter().map(|action| action.name()).collect::<Vec<&str>>().join(" ")
+}
+
+// The action waiting for its confirmation, told apart by what it's done to,
+// like the file to be overwritten.
+#[derive(Default)]
+pub struct Confirmation {
+    pending: Option<(String, time::Instant)>,
+}
+
+impl Confirmation {
+    // Whether the action was asked for a moment ago, otherwise it waits for
+    // the confirmation from now on.
+    pub fn confirmed(&mut self, key: &str) -> bool {
+        match self.pending.take() {
+            Some((pending_key, asked)) if pending_key == key && asked.elapsed() <= time::Duration::from_secs(CONFIRM_SECONDS) => {
+                true
+            },
+            _ => {
+                self.pending = Some((key.to_owned(), time::Instant::now()));
+                false
+            },
+        }
+    }
+}
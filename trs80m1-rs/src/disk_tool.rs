@@ -0,0 +1,163 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error};
+
+use std::fs;
+use std::path;
+
+use trs80m1_rs_core::disk;
+use trs80m1_rs_core::trsdos;
+
+
+// The `disk' command line mode, which copies files between the host and
+// TRSDOS/LDOS disk images without starting the emulator:
+//
+//     trs80m1-rs disk extract <image> <file> [host-file]
+//     trs80m1-rs disk insert [--cow] <image> <host-file> [file]
+//     trs80m1-rs disk commit <image>
+//     trs80m1-rs disk discard <image>
+//
+// The file names within the image are in the `NAME/EXT' form, if the second
+// name isn't specified, it's derived from the first one.
+//
+// With `--cow', the image is left untouched and the changes are written into
+// a sidecar overlay file instead, which can later be committed or discarded.
+//
+
+pub fn print_usage(progname: &str) {
+    println!("Usage: {} disk extract <image> <file> [host-file]", progname);
+    println!("       {} disk insert [--cow] <image> <host-file> [file]", progname);
+    println!("       {} disk commit <image>", progname);
+    println!("       {} disk discard <image>", progname);
+}
+
+// Returns the exit code of the program.
+pub fn run(progname: &str, args_in: &[String]) -> i32 {
+    let copy_on_write = args_in.iter().any(|arg| arg == "--cow");
+    let args: Vec<String> = args_in.iter().filter(|arg| *arg != "--cow").cloned().collect();
+
+    let subcommand = match args.first() {
+        Some(subcommand) => { subcommand.to_lowercase() },
+        None => {
+            print_usage(progname);
+            return 1;
+        },
+    };
+    let (min_args, max_args) = if subcommand == "commit" || subcommand == "discard" {
+        (2, 2)
+    } else {
+        (3, 4)
+    };
+    if args.len() < min_args || args.len() > max_args || (copy_on_write && subcommand != "insert") {
+        print_usage(progname);
+        return 1;
+    }
+
+    let success = if subcommand == "extract" {
+        extract(&args[1], &args[2], args.get(3))
+    } else if subcommand == "insert" {
+        insert(&args[1], &args[2], args.get(3), copy_on_write)
+    } else if subcommand == "commit" {
+        match disk::DiskImage::load_from_file(&args[1]) {
+            Some(mut image) => { image.commit_overlay() },
+            None => { false },
+        }
+    } else if subcommand == "discard" {
+        match disk::DiskImage::load_from_file(&args[1]) {
+            Some(mut image) => { image.discard_overlay() },
+            None => { false },
+        }
+    } else {
+        eprintln!("{}: Unknown disk subcommand `{}'.", progname, subcommand);
+        print_usage(progname);
+        false
+    };
+    if success { 0 } else { 1 }
+}
+
+fn extract(image_path: &str, file_name: &str, host_path: Option<&String>) -> bool {
+    let image = match disk::DiskImage::load_from_file(image_path) {
+        Some(image) => { image },
+        None => { return false; },
+    };
+    let contents = match trsdos::read_file(&image, file_name) {
+        Some(contents) => { contents },
+        None => { return false; },
+    };
+
+    // `NAME/EXT' becomes `name.ext' on the host.
+    let host_path = match host_path {
+        Some(host_path) => { host_path.clone() },
+        None => {
+            match trsdos::split_file_name(file_name) {
+                Some((name, extension)) if extension.is_empty() => { name.to_lowercase() },
+                Some((name, extension)) => { format!("{}.{}", name, extension).to_lowercase() },
+                None => { return false; },
+            }
+        },
+    };
+    match fs::write(&host_path, &contents) {
+        Ok(..) => {
+            info!("Extracted `{}' ({} bytes) into `{}'.", file_name, contents.len(), host_path);
+            true
+        },
+        Err(error) => {
+            error!("Failed to write `{}': {}.", host_path, error);
+            false
+        },
+    }
+}
+
+fn insert(image_path: &str, host_path: &str, file_name: Option<&String>, copy_on_write: bool) -> bool {
+    let mut image = match disk::DiskImage::load_from_file(image_path) {
+        Some(image) => { image },
+        None => { return false; },
+    };
+    if copy_on_write {
+        image.set_copy_on_write(true);
+    }
+    if image.write_protected() {
+        error!("The disk image `{}' is write protected.", image_path);
+        return false;
+    }
+    let contents = match fs::read(host_path) {
+        Ok(contents) => { contents },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", host_path, error);
+            return false;
+        },
+    };
+
+    let file_name = match file_name {
+        Some(file_name) => { file_name.clone() },
+        None => {
+            let host_name = path::Path::new(host_path);
+            let stem = match host_name.file_stem() {
+                Some(stem) => { stem.to_string_lossy().into_owned() },
+                None => {
+                    error!("Can't derive a file name from `{}', please specify one.", host_path);
+                    return false;
+                },
+            };
+            match host_name.extension() {
+                Some(extension) => { format!("{}/{}", stem, extension.to_string_lossy()) },
+                None => { stem },
+            }
+        },
+    };
+
+    trsdos::write_file(&mut image, &file_name, &contents) && image.save()
+}
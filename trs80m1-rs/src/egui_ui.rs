@@ -0,0 +1,463 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use eframe::egui;
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::emulator::{EmulatorCommand, EmulatorDebuggerCommand};
+use crate::user_interface::GuiEvent;
+
+
+// The graphical user interface, an alternative to the curses-based one,
+// which runs in a window of its own next to the one of the emulator.
+//
+// It doesn't do anything by itself, the menus, the file dialogs and the
+// command line all send the same commands which can be typed on the command
+// line of the curses-based interface, to the user interface thread, which
+// carries them out and sends back the messages.  The only exception is the
+// debugger panel, which asks the logic core for the state of the machine
+// directly, while it's shown.
+//
+
+const MAX_MESSAGES:     usize = 5000;
+const MAX_HISTORY:      usize = 500;
+
+// The channels are checked this often, even when nothing happens in the
+// window.
+const POLL_INTERVAL:    Duration = Duration::from_millis(50);
+
+// The commands which take a file, chosen with the file dialog.
+#[derive(Copy, Clone)]
+enum FileAction {
+    TypeFile,
+    InsertCassette,
+    LoadCassette,
+    LoadRam,
+    LoadRom,
+    SaveScreenText,
+}
+
+impl FileAction {
+    fn title(self) -> &'static str {
+        match self {
+            FileAction::TypeFile       => { "Type in a file" },
+            FileAction::InsertCassette => { "Insert a cassette" },
+            FileAction::LoadCassette   => { "Load a cassette" },
+            FileAction::LoadRam        => { "Load a file into the RAM" },
+            FileAction::LoadRom        => { "Load a ROM image" },
+            FileAction::SaveScreenText => { "Save the screen as text" },
+        }
+    }
+    fn command(self, file: &path::Path) -> String {
+        let file = file.display();
+        match self {
+            FileAction::TypeFile       => { format!("machine type {}", file) },
+            FileAction::InsertCassette => { format!("cassette insert {}", file) },
+            FileAction::LoadCassette   => { format!("cassette load {}", file) },
+            FileAction::LoadRam        => { format!("memory load ram {}", file) },
+            FileAction::LoadRom        => { format!("memory load rom {}", file) },
+            FileAction::SaveScreenText => { format!("video text {}", file) },
+        }
+    }
+}
+
+struct FileDialog {
+    action:    FileAction,
+    dir:       path::PathBuf,
+    entries:   Vec<(String, bool)>,    // The names, and whether they're directories.
+    file_name: String,
+}
+
+impl FileDialog {
+    fn new(action: FileAction, dir: path::PathBuf) -> FileDialog {
+        let mut dialog = FileDialog {
+            action,
+            dir:       path::PathBuf::new(),
+            entries:   Vec::new(),
+            file_name: String::new(),
+        };
+        dialog.change_dir(dir);
+        dialog
+    }
+    // List the directory, with the subdirectories first, and without the
+    // hidden files.
+    fn change_dir(&mut self, dir: path::PathBuf) {
+        self.entries = match fs::read_dir(&dir) {
+            Ok(entries) => {
+                entries.flatten()
+                       .map(|entry| (entry.file_name().to_string_lossy().into_owned(), entry.path().is_dir()))
+                       .filter(|(name, _)| !name.starts_with('.'))
+                       .collect()
+            },
+            Err(..) => { Vec::new() },
+        };
+        self.entries.sort_by(|(name_a, dir_a), (name_b, dir_b)| dir_b.cmp(dir_a).then_with(|| name_a.cmp(name_b)));
+        self.dir = dir;
+    }
+}
+
+struct GuiApp {
+    command_tx:     mpsc::Sender<String>,
+    event_rx:       mpsc::Receiver<GuiEvent>,
+    emu_cmd_tx:     mpsc::Sender<EmulatorCommand>,
+    config_dir:     path::PathBuf,
+
+    messages:       VecDeque<String>,
+    command_text:   String,
+    history:        VecDeque<String>,
+    history_pos:    usize,             // 0 refers to the text being typed
+
+    file_dialog:    Option<FileDialog>,
+
+    debugger_shown:  bool,
+    debugger_docked: bool,
+    debug_lines:     Vec<String>,
+    debug_address:   String,
+}
+
+impl GuiApp {
+    fn new(config_dir: path::PathBuf, command_tx: mpsc::Sender<String>, event_rx: mpsc::Receiver<GuiEvent>, emu_cmd_tx: mpsc::Sender<EmulatorCommand>) -> GuiApp {
+        GuiApp {
+            command_tx,
+            event_rx,
+            emu_cmd_tx,
+            config_dir,
+
+            messages:        VecDeque::with_capacity(MAX_MESSAGES),
+            command_text:    String::new(),
+            history:         VecDeque::with_capacity(MAX_HISTORY),
+            history_pos:     0,
+
+            file_dialog:     None,
+
+            debugger_shown:  false,
+            debugger_docked: true,
+            debug_lines:     Vec::new(),
+            debug_address:   String::new(),
+        }
+    }
+    fn command(&self, command: &str) {
+        let _ = self.command_tx.send(command.to_owned());
+    }
+    // Returns false once the user interface thread is gone, which is when
+    // the emulator has terminated.
+    fn handle_events(&mut self) -> bool {
+        loop {
+            match self.event_rx.try_recv() {
+                Ok(GuiEvent::Message(message)) => {
+                    if self.messages.len() >= MAX_MESSAGES {
+                        self.messages.pop_front();
+                    }
+                    self.messages.push_back(message);
+                },
+                Ok(GuiEvent::DebugState(lines)) => {
+                    self.debug_lines = lines;
+                },
+                Err(mpsc::TryRecvError::Empty) => { return true; },
+                Err(mpsc::TryRecvError::Disconnected) => { return false; },
+            }
+        }
+    }
+    fn set_debugger_shown(&mut self, shown: bool) {
+        self.debugger_shown = shown;
+        let _ = self.emu_cmd_tx.send(EmulatorCommand::DebuggerCommand(EmulatorDebuggerCommand::Panel { state: shown }));
+    }
+    fn open_file_dialog(&mut self, action: FileAction) {
+        // The memory is loaded from the working directory, like the
+        // `memory load' command does, the rest from the configuration one.
+        let dir = match action {
+            FileAction::LoadRam | FileAction::LoadRom => { env::current_dir().unwrap_or_else(|_| self.config_dir.clone()) },
+            _ => { self.config_dir.clone() },
+        };
+        self.file_dialog = Some(FileDialog::new(action, dir));
+    }
+    fn submit_command(&mut self) {
+        let command = self.command_text.trim().to_owned();
+        self.command_text.clear();
+        self.history_pos = 0;
+        if command.is_empty() {
+            return;
+        }
+        if self.history.front() != Some(&command) {
+            if self.history.len() >= MAX_HISTORY {
+                self.history.pop_back();
+            }
+            self.history.push_front(command.clone());
+        }
+        self.command(&command);
+    }
+    fn recall_history(&mut self, older: bool) {
+        if older && self.history_pos < self.history.len() {
+            self.history_pos += 1;
+        } else if !older && self.history_pos > 0 {
+            self.history_pos -= 1;
+        } else {
+            return;
+        }
+        self.command_text = match self.history_pos {
+            0 => { String::new() },
+            pos => { self.history[pos - 1].clone() },
+        };
+    }
+    fn show_menus(&mut self, ui: &mut egui::Ui) {
+        egui::menu::bar(ui, |ui| {
+            ui.menu_button("Machine", |ui| {
+                for (label, command) in [("Power on", "machine power on"), ("Power off", "machine power off"),
+                                         ("Reset", "machine reset cpu"), ("Full reset", "machine reset full"),
+                                         ("Pause", "machine pause toggle"), ("Paste", "machine paste")] {
+                    if ui.button(label).clicked() {
+                        self.command(command);
+                        ui.close_menu();
+                    }
+                }
+                if ui.button("Type in a file...").clicked() {
+                    self.open_file_dialog(FileAction::TypeFile);
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Exit").clicked() {
+                    self.command("exit");
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Cassette", |ui| {
+                if ui.button("Insert...").clicked() {
+                    self.open_file_dialog(FileAction::InsertCassette);
+                    ui.close_menu();
+                }
+                if ui.button("Insert and load...").clicked() {
+                    self.open_file_dialog(FileAction::LoadCassette);
+                    ui.close_menu();
+                }
+                for (label, command) in [("Eject", "cassette eject"), ("Rewind", "cassette rewind"), ("Library", "cassette library")] {
+                    if ui.button(label).clicked() {
+                        self.command(command);
+                        ui.close_menu();
+                    }
+                }
+            });
+            ui.menu_button("Memory", |ui| {
+                if ui.button("Load into the RAM...").clicked() {
+                    self.open_file_dialog(FileAction::LoadRam);
+                    ui.close_menu();
+                }
+                if ui.button("Load a ROM image...").clicked() {
+                    self.open_file_dialog(FileAction::LoadRom);
+                    ui.close_menu();
+                }
+                if ui.button("Wipe the RAM").clicked() {
+                    self.command("memory wipe ram");
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Video", |ui| {
+                for (label, command) in [("Full screen", "video fullscreen toggle"), ("Screenshot", "video screenshot"),
+                                         ("Magnifier", "video magnifier toggle")] {
+                    if ui.button(label).clicked() {
+                        self.command(command);
+                        ui.close_menu();
+                    }
+                }
+                if ui.button("Save the screen as text...").clicked() {
+                    self.open_file_dialog(FileAction::SaveScreenText);
+                    ui.close_menu();
+                }
+                ui.menu_button("Colors", |ui| {
+                    for colors in ["white", "green", "amber"] {
+                        if ui.button(colors).clicked() {
+                            self.command(&format!("video colors {}", colors));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+            ui.menu_button("Debugger", |ui| {
+                let mut shown = self.debugger_shown;
+                if ui.checkbox(&mut shown, "Debugger panel").changed() {
+                    self.set_debugger_shown(shown);
+                }
+                ui.checkbox(&mut self.debugger_docked, "Docked");
+                if ui.button("Debugger window").clicked() {
+                    self.command("debugger window toggle");
+                    ui.close_menu();
+                }
+            });
+            ui.menu_button("Help", |ui| {
+                if ui.button("Commands").clicked() {
+                    self.command("help");
+                    ui.close_menu();
+                }
+            });
+        });
+    }
+    fn show_debugger(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Memory at:");
+            let response = ui.add(egui::TextEdit::singleline(&mut self.debug_address).desired_width(60.0));
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                self.command(&format!("debugger memory {}", self.debug_address.trim()));
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+            for line in &self.debug_lines {
+                ui.monospace(line);
+            }
+        });
+    }
+    fn show_file_dialog(&mut self, ctx: &egui::Context) {
+        let mut dialog = match self.file_dialog.take() {
+            Some(dialog) => { dialog },
+            None => { return; },
+        };
+        let mut open = true;
+        let mut cancelled = false;
+        let mut new_dir = None;
+        let mut chosen = None;
+
+        egui::Window::new(dialog.action.title()).collapsible(false).open(&mut open).default_size([420.0, 360.0]).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Up").clicked() {
+                    new_dir = dialog.dir.parent().map(|parent| parent.to_owned());
+                }
+                ui.label(dialog.dir.display().to_string());
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(260.0).auto_shrink([false, false]).show(ui, |ui| {
+                for (name, is_dir) in &dialog.entries {
+                    if *is_dir {
+                        if ui.selectable_label(false, format!("{}/", name)).clicked() {
+                            new_dir = Some(dialog.dir.join(name));
+                        }
+                    } else {
+                        let response = ui.selectable_label(dialog.file_name == *name, name.as_str());
+                        if response.double_clicked() {
+                            chosen = Some(dialog.dir.join(name));
+                        } else if response.clicked() {
+                            dialog.file_name = name.clone();
+                        }
+                    }
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.text_edit_singleline(&mut dialog.file_name);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("OK").clicked() && !dialog.file_name.is_empty() {
+                    chosen = Some(dialog.dir.join(&dialog.file_name));
+                }
+                if ui.button("Cancel").clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        // A directory typed in as the file is entered, like the listed ones.
+        if let Some(file) = chosen {
+            if file.is_dir() {
+                new_dir = Some(file);
+            } else {
+                self.command(&dialog.action.command(&file));
+                return;
+            }
+        }
+        if let Some(dir) = new_dir {
+            dialog.file_name.clear();
+            dialog.change_dir(dir);
+        }
+        if open && !cancelled {
+            self.file_dialog = Some(dialog);
+        }
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if !self.handle_events() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+        // Closing the window terminates the emulator, the user interface
+        // thread finishes on its own once it does.
+        if ctx.input(|input| input.viewport().close_requested()) {
+            self.command("exit");
+        }
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            self.show_menus(ui);
+        });
+        egui::TopBottomPanel::bottom("command_line").show(ctx, |ui| {
+            let response = ui.add(egui::TextEdit::singleline(&mut self.command_text).hint_text("/help").desired_width(f32::INFINITY));
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                self.submit_command();
+                response.request_focus();
+            }
+            if response.has_focus() {
+                if ui.input(|input| input.key_pressed(egui::Key::ArrowUp)) {
+                    self.recall_history(true);
+                } else if ui.input(|input| input.key_pressed(egui::Key::ArrowDown)) {
+                    self.recall_history(false);
+                }
+            }
+        });
+
+        if self.debugger_shown {
+            if self.debugger_docked {
+                egui::SidePanel::right("debugger").resizable(true).default_width(360.0).show(ctx, |ui| {
+                    self.show_debugger(ui);
+                });
+            } else {
+                let mut open = true;
+                egui::Window::new("Debugger").open(&mut open).default_size([360.0, 420.0]).show(ctx, |ui| {
+                    self.show_debugger(ui);
+                });
+                if !open {
+                    self.set_debugger_shown(false);
+                }
+            }
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+            egui::ScrollArea::vertical().auto_shrink([false, false]).stick_to_bottom(true).show_rows(ui, row_height, self.messages.len(), |ui, rows| {
+                for message in self.messages.range(rows) {
+                    ui.monospace(message);
+                }
+            });
+        });
+
+        self.show_file_dialog(ctx);
+        ctx.request_repaint_after(POLL_INTERVAL);
+    }
+}
+
+// Run the graphical interface until its window is closed, or the emulator
+// terminates.
+pub fn run(config_dir: path::PathBuf, command_tx: mpsc::Sender<String>, event_rx: mpsc::Receiver<GuiEvent>, emu_cmd_tx: mpsc::Sender<EmulatorCommand>) -> Result<(), eframe::Error> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("trs80m1-rs").with_inner_size([760.0, 520.0]),
+        ..Default::default()
+    };
+    let app = GuiApp::new(config_dir, command_tx, event_rx, emu_cmd_tx);
+    eframe::run_native("trs80m1-rs", options, Box::new(|_creation_context| Ok(Box::new(app))))
+}
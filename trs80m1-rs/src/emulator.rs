@@ -16,31 +16,137 @@
 
 use log::{info, warn, error};
 
+use std::fs;
+use std::io;
 use std::path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::thread;
 use std::time;
 
+use trs80m1_rs_core::bridge;
 use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::cmd_file;
+use trs80m1_rs_core::disk;
 use trs80m1_rs_core::keyboard;
+use crate::aliases;
+use crate::confirm;
+use crate::file_picker;
+use crate::ui_theme;
 use crate::sdl_keyboard;
+use crate::sdl_controller;
+use crate::sdl_audio;
 use trs80m1_rs_core::video;
+use trs80m1_rs_core::fonts;
 use trs80m1_rs_core::machine;
+use trs80m1_rs_core::memory;
+use trs80m1_rs_core::replay;
+use trs80m1_rs_core::snapshot;
+use trs80m1_rs_core::trsdos;
+use trs80m1_rs_core::tape;
+use trs80m1_rs_core::sound;
+use trs80m1_rs_core::z80::cpu;
 use crate::proj_config;
 use trs80m1_rs_core::util::Sink;
 use trs80m1_rs_core::memory::MemoryChipOps;
 use crate::sdl_video;
+use crate::sdl_debug;
+use crate::sdl_magnifier;
+use crate::sdl_menu;
+#[cfg(feature = "opengl")]
+use crate::gl_video;
+#[cfg(feature = "scripting")]
+use crate::scripting;
+use crate::png;
+use crate::gif;
+use crate::titles;
+use crate::util;
+
+// With the turbo, the time is checked after this many instructions, rather
+// than after each one.
+const TURBO_STEPS_PER_CHECK: u32 = 256;
+
+// While waiting for the next frame, the events of the window are still
+// handled this often, so that the keys reach the machine within a fraction
+// of a frame, rather than with the next one.
+const EVENT_POLL_INTERVAL: time::Duration = time::Duration::from_millis(2);
+
+// The snapshot saved and loaded when no file is given, like from the menu,
+// in the configuration directory.
+const DEFAULT_SNAPSHOT_FILE: &str = "snapshot.sav";
+
+// The cassette in the drive, as the file name of the config system, its
+// format and the position of the tape.
+type CassetteSlot = (Option<String>, cassette::Format, usize);
 
 pub enum EmulatorCassetteCommand {
-    Insert { format: cassette::Format, file: String },
+    Insert { format: cassette::Format, file: String, auto_load: bool },
     Eject,
     Erase,
     Seek   { position: usize },
     Rewind,
+    FastForward,
+    Library { browse: bool }, // In the file picker, or as a list.
+    LibraryInsert { index: usize, auto_load: bool },
+    Index,
+    GoTo   { recording: String },
+    Motor  { state: Option<bool> },
+    Undo,                           // Puts back the cassette swapped out.
+}
+
+pub enum EmulatorDiskCommand {
+    Directory { file: String },
+    Commit    { file: String },
+    Discard   { file: String },
+    ReadSector  { file: String, track: u8, side: u8, sector: u8 },
+    WriteSector { file: String, track: u8, side: u8, sector: u8, offset: usize, bytes: Vec<u8> },
+}
+
+pub enum EmulatorSoundCommand {
+    Statistics,
+    Mixer,
+    Volume { volume: u32 },
+    Mute   { source: sound::Source, muted: bool },
+}
+
+pub enum EmulatorRecordCommand {
+    Audio { file: String },
+    Video { file: String },
+    Input { file: String },
+    Replay { file: String },
+    Stop,
+}
+
+pub enum EmulatorTimerCommand {
+    Start,
+    Lap,
+    Stop,
+}
+
+pub enum EmulatorScriptCommand {
+    Run  { file: String },
+    Eval { code: String },
+    Stop,
+}
+
+pub enum EmulatorDebuggerCommand {
+    Window { state: Option<bool> },     // None toggles the window.
+    Memory { address: u16 },
+    Panel { state: bool },              // The debugger panel of the graphical
+                                        // interface, or pane of the curses one.
+}
+
+#[derive(Copy, Clone)]
+pub enum PictureSetting {
+    Brightness,
+    Contrast,
+    Scanlines,
 }
 
 pub enum EmulatorConfigCommand {
     List,
+    Dump,
     Show   { entry_specifier: String },
     Change { entry_specifier: String, invocation_text: String },
 }
@@ -63,7 +169,54 @@ pub enum EmulatorCommand {
     WipeSystemRam,
     LoadSystemRam { path: path::PathBuf, offset: u16 },
     SwitchRom(u32),
+    SetFullscreen(Option<bool>),    // None toggles the mode.
+    SetAlternateCharset(Option<bool>), // None toggles the switch.
+    SetMagnifier(Option<bool>),     // None opens or closes the window.
+    CycleOverlay,
+    SetScaling(sdl_video::Scaling),
+    ShowPicture,
+    SetPicture {
+        setting:  PictureSetting,
+        level:    i32,
+        relative: bool,                 // Whether to add the level to it.
+    },
+    SetColors {
+        fg_color: (u8, u8, u8),
+        bg_color: Option<(u8, u8, u8)>,    // None keeps the background.
+    },
+    Screenshot(Option<bool>),       // Scaled or not, None for the default.
+    ScreenText { file: Option<String> }, // None copies it into the clipboard.
+    TypeText(String),
+    TypeSpecialKey(keyboard::SpecialKey),
+    PasteClipboard,                 // The front-end sends back PasteText.
+    PasteText(String),
+    TypeFile(String),
+    SetJoystick(u8),                // The JOY_* bits of what's held.
+    SetTurbo(bool),                 // While the turbo key is held.
+    SetWarp(Option<bool>),          // None toggles the warp.
+    SetSpeedLimit(Option<u32>),     // In percent, none if unlimited.
+    ShowSpeedLimit,
+    CycleSlowMotion,
+    AdvanceFrames(u32),             // Pauses the machine if it's running.
+    ShowKeyLatency,
+    ShowClock,
+    SetKeyProfile(Option<String>),  // None turns the profile off.
+    ShowKeyProfiles,
+    DumpMemory { address: u16, length: u32 },
+    SetStatusPane(bool),            // Answered with StatusPane while it's on.
+    OpenFile(String),               // Dropped onto the emulator window.
+    ShowMenu,                       // Sends the menu to the front-end.
+    SaveState { file: Option<String> }, // None for the default file.
+    LoadState { file: Option<String> },
+    Acknowledge(usize),             // Answered with Acknowledged.
+    SetUnattended(bool),            // The commands after it aren't confirmed.
     CassetteCommand(EmulatorCassetteCommand),
+    DiskCommand(EmulatorDiskCommand),
+    SoundCommand(EmulatorSoundCommand),
+    RecordCommand(EmulatorRecordCommand),
+    TimerCommand(EmulatorTimerCommand),
+    ScriptCommand(EmulatorScriptCommand),
+    DebuggerCommand(EmulatorDebuggerCommand),
     ConfigCommand(EmulatorConfigCommand),
 }
 
@@ -79,8 +232,22 @@ pub enum EmulatorStatus {
     PoweredOff,
     Paused,
     NotPaused,
+    Warp(bool),
     CpuHalted,
     CpuNotHalted,
+    CassetteCounter(Option<(usize, usize)>),
+    CassetteMotor { running: bool, forced: bool },
+    Performance { speed: Option<u32>, frame_rate: Option<u32> },
+    Clock(Option<u64>),             // The emulated seconds since power-on.
+    Progress(Option<(String, usize, usize)>), // The task, done of total.
+    Acknowledged(usize),            // The commands before it were carried out.
+    StatusPane(Vec<String>),        // The lines of the machine status pane.
+    SuggestCommand(String),         // For the user to finish and confirm.
+    Aliases(Vec<aliases::Alias>),   // When the configuration changes.
+    UiTheme(ui_theme::ThemeColors), // Likewise.
+    Language(String),               // Likewise.
+    DebugState(Vec<String>),        // For the debugger panel, while it's shown.
+    BrowseCassettes(path::PathBuf), // The cassette library, for the file picker.
 }
 
 // Video cross-thread commands:
@@ -92,14 +259,15 @@ pub enum VideoCommand {
         desktop_fullscr_mode:  bool,
         use_hw_accel:          bool,
         use_vsync:             bool,
+        renderer:              sdl_video::Renderer,
         bg_color:              (u8, u8, u8),
         fg_color:              (u8, u8, u8),
-        cg_num:                u32,
+        font:                  Box<[u8]>,
     },
     UpdateTextures {
         bg_color:              (u8, u8, u8),
         fg_color:              (u8, u8, u8),
-        cg_num:                u32,
+        font:                  Box<[u8]>,
     },
     SetFrameDrawing {
         enabled: bool,
@@ -107,8 +275,35 @@ pub enum VideoCommand {
     },
     SetWindowedResolution((u32, u32)),
     SetFullscreenResolution((u32, u32), bool),
+    SetFullscreen(bool),
+    SetScaling(sdl_video::Scaling),
+    SetCrtEffects(sdl_video::CrtEffects),
+    SetFrameSkip(sdl_video::FrameSkip),
+    SetOverlay(sdl_video::OverlayLevel),
+    SetMachineStatus(sdl_video::MachineStatus),
+    SetNotifications(bool),
+    Notify(String),              // Shown for a while in the window.
+    TakeScreenshot {
+        path:   path::PathBuf,
+        scaled: bool,
+    },
+    SetClipboardText(String),
+    PasteClipboard,
+    SetTextGraphics(video::TextGraphics),
+    SetDebugWindow(bool),
+    ShowDebugState(Vec<String>),
+    SetMagnifier(bool),
+    ShowMenu(sdl_menu::Menu),
     SetCyclesPerKeypress(u32),
-    DrawFrame(video::VideoFrame),
+    SetKeyMapping(sdl_keyboard::KeyMapping),
+    SetKeyBindings(Vec<sdl_keyboard::KeyBinding>),
+    SetKeyProfile(Vec<sdl_keyboard::KeyBinding>), // Empty without a profile.
+    SetKeyRepeat(Option<sdl_keyboard::KeyRepeat>),
+    SetControllerBindings(Vec<sdl_controller::ControllerBinding>),
+    SetSound(sdl_audio::AudioSettings),
+    FrameReady, // The newest frame was put into the shared frame.
+    PlaySamples(Vec<i16>),
+    ReportSoundStatistics,
     Terminate,
 }
 
@@ -118,6 +313,121 @@ pub enum VideoStatus {
     Created,
     Destroyed,
     ModeSetStatus(bool),
+    FrameRate(u32),                 // Of the window, about once a second.
+}
+
+// Lets the user interface cancel a long operation of the logic core, like
+// decoding a WAV cassette, which checks it as it goes along, since the
+// commands only get looked at once it's over.
+#[derive(Clone, Default)]
+pub struct CancelFlag {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelFlag {
+    pub fn new() -> CancelFlag {
+        CancelFlag::default()
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    fn take(&self) -> bool {
+        self.cancelled.swap(false, Ordering::Relaxed)
+    }
+}
+
+// The progress of a long operation, reported to the user interface a few
+// times a second, which shows it as a progress bar until it's finished.
+const PROGRESS_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+struct Progress<'a> {
+    status_tx: &'a mpsc::Sender<EmulatorStatus>,
+    cancel:    &'a CancelFlag,
+    task:      &'static str,
+    reported:  Option<time::Instant>,
+    cancelled: bool,
+}
+
+impl<'a> Progress<'a> {
+    fn new(status_tx: &'a mpsc::Sender<EmulatorStatus>, cancel: &'a CancelFlag, task: &'static str) -> Progress<'a> {
+        // A cancellation from before the operation doesn't apply to it.
+        cancel.take();
+        Progress {
+            status_tx,
+            cancel,
+            task,
+            reported:  None,
+            cancelled: false,
+        }
+    }
+    // Whether to go on with the operation.
+    fn update(&mut self, done: usize, total: usize) -> bool {
+        if self.cancel.take() {
+            self.cancelled = true;
+            return false;
+        }
+        if self.reported.map(|reported| reported.elapsed() >= PROGRESS_INTERVAL).unwrap_or(true) {
+            self.status_tx.send(EmulatorStatus::Progress(Some((self.task.to_owned(), done, total)))).unwrap();
+            self.reported = Some(time::Instant::now());
+        }
+        true
+    }
+    fn finish(self) {
+        if self.reported.is_some() {
+            self.status_tx.send(EmulatorStatus::Progress(None)).unwrap();
+        }
+        if self.cancelled {
+            info!("{} cancelled.", self.task);
+        }
+    }
+}
+
+// The stopwatch of the `/timer' command, which measures the time the
+// machine ran for, by its T-states, along with the real time, which goes on
+// while the machine is paused, or slowed down by a busy host.
+struct Stopwatch {
+    started_cycles: u64,
+    started:        time::Instant,
+    lap_cycles:     u64,
+    lap:            time::Instant,
+}
+
+impl Stopwatch {
+    fn new(cycles: u64) -> Stopwatch {
+        let now = time::Instant::now();
+        Stopwatch {
+            started_cycles: cycles,
+            started:        now,
+            lap_cycles:     cycles,
+            lap:            now,
+        }
+    }
+}
+
+// The media given on the command line, which are attached once the
+// emulator starts.  The /CMD program is started once the ROM gets to the
+// BASIC prompt, as is the program on the cassette, with the autostart.
+pub struct StartupMedia {
+    pub cassette:  Option<(cassette::Format, String)>,
+    pub cmd_file:  Option<String>,
+    pub autostart: bool,
+}
+
+// What's left to be started of the startup media, the level II ROM asks
+// about the memory size first, which is answered with the Enter key.
+struct Autostart {
+    cmd_file:          Option<String>,
+    load_cassette:     bool,
+    memory_size_given: bool,
+}
+
+// If the BASIC prompt doesn't show up in this many seconds of emulated time
+// after the power-on, like with a ROM other than the BASIC ones, the
+// autostart is given up on.
+const AUTOSTART_TIMEOUT: u64 = 30;
+
+fn timer_text(cycles: u64, real_time: time::Duration) -> String {
+    format!("{:.6} s of emulated time ({} T-states), {:.3} s of real time", cycles as f64 / machine::CPU_HZ as f64, cycles, real_time.as_secs_f64())
 }
 
 pub struct EmulatorLogicCore {
@@ -125,26 +435,69 @@ pub struct EmulatorLogicCore {
     config_system:        proj_config::ConfigSystem,
 
     cached_cpu_halted:    bool,
+    cached_cas_counter:   Option<(usize, usize)>,
+    cached_clock:         Option<u64>,
+    cached_cas_motor:     (bool, bool),
+    cas_autorun_keys:     Option<String>,
+    audio_recording:      Option<(sound::WavWriter, path::PathBuf)>,
+    video_recording:      Option<VideoRecording>,
+    input_recording:      Option<path::PathBuf>,
+    input_replay:         Option<path::PathBuf>,
     powered_on:           bool,
     paused:               bool,
+    turbo:                bool,
+    warp:                 bool,
+    frame_advance:        u32,            // The video frames left to run while paused.
+    frame_end:            bool,           // Whether the advance stopped at the end of a frame.
+    speed_limit:          Option<u32>,    // In percent, none if unlimited.
+    title:                Option<(String, bool)>, // Its name, and whether it came with the cassette.
+    title_speed_limit:    Option<Option<u32>>,
+    cassette_for_run:     bool,           // The cassette came from the command line.
     exit_request:         bool,
     have_video_thread:    bool,
+    debug_window:         bool,
+    debug_panel:          bool,
+    debug_memory:         u16,
+    debug_updated:        time::Instant,
+    magnifier:            bool,
+    overlay_cycles:       u64,
+    overlay_updated:      time::Instant,
+    speed:                Option<u32>,    // As shown in the overlay.
+    frame_rate:           Option<u32>,    // Of the window, if there's one.
+    cached_performance:   (Option<u32>, Option<u32>),
+    status_pane:          bool,
+    status_updated:       time::Instant,
+    confirmation:         confirm::Confirmation,
+    unattended:           bool,           // The commands come from a script or a client.
+    previous_cassette:    Option<CassetteSlot>, // Before the last insert or eject.
+    stopwatch:            Option<Stopwatch>,
+    power_on_cycles:      u64,
+    power_on_time:        time::Instant,
+    config_checked:       time::Instant,
+    startup_media:        Option<StartupMedia>,
+    autostart:            Option<Autostart>,
+    #[cfg(feature = "scripting")]
+    script:               Option<scripting::Script>,
 
     selected_rom:         u32,
 
     video_cmd_tx:         mpsc::Sender<VideoCommand>,
     video_status_rx:      mpsc::Receiver<VideoStatus>,
+    shared_frame:         video::SharedFrame,
     status_tx:            mpsc::Sender<EmulatorStatus>,
+    cancel_flag:          CancelFlag,
 }
 
 impl EmulatorLogicCore {
     pub fn new(status_tx:       mpsc::Sender<EmulatorStatus>,
                video_cmd_tx:    mpsc::Sender<VideoCommand>,
                video_status_rx: mpsc::Receiver<VideoStatus>,
+               shared_frame:    video::SharedFrame,
                config_system:   proj_config::ConfigSystem,
-               selected_rom:    u32) -> EmulatorLogicCore {
+               selected_rom:    u32,
+               cancel_flag:     CancelFlag) -> EmulatorLogicCore {
 
-        let ram_size = config_system.config_items.general_ram_size as u16;
+        let ram_size = config_system.config_items.machine_ram_size as u16;
         let rom_choice = EmulatorLogicCore::get_rom_choice(selected_rom, &config_system);
         let lowercase_mod = config_system.config_items.video_lowercase_mod;
         let cassette_file_path = EmulatorLogicCore::get_cassette_path(&config_system);
@@ -158,42 +511,480 @@ impl EmulatorLogicCore {
             config_system,
 
             cached_cpu_halted:    false,
+            cached_cas_counter:   None,
+            cached_clock:         None,
+            cached_cas_motor:     (false, false),
+            cas_autorun_keys:     None,
+            audio_recording:      None,
+            video_recording:      None,
+            input_recording:      None,
+            input_replay:         None,
             powered_on:           false,
             paused:               false,
+            turbo:                false,
+            warp:                 false,
+            frame_advance:        0,
+            frame_end:            false,
+            speed_limit:          Some(100),
+            title:                None,
+            title_speed_limit:    None,
+            cassette_for_run:     false,
             exit_request:         false,
             have_video_thread:    false,
+            debug_window:         false,
+            debug_panel:          false,
+            debug_memory:         0,
+            debug_updated:        time::Instant::now(),
+            magnifier:            false,
+            overlay_cycles:       0,
+            overlay_updated:      time::Instant::now(),
+            speed:                None,
+            frame_rate:           None,
+            cached_performance:   (None, None),
+            status_pane:          false,
+            status_updated:       time::Instant::now(),
+            confirmation:         confirm::Confirmation::default(),
+            unattended:           false,
+            previous_cassette:    None,
+            stopwatch:            None,
+            power_on_cycles:      0,
+            power_on_time:        time::Instant::now(),
+            config_checked:       time::Instant::now(),
+            startup_media:        None,
+            autostart:            None,
+            #[cfg(feature = "scripting")]
+            script:               None,
 
             selected_rom,
 
             video_cmd_tx,
             video_status_rx,
+            shared_frame,
             status_tx,
+            cancel_flag,
         };
 
+        emulator.machine.devices.cassette.set_wav_copy(emulator.config_system.config_items.cassette_wav_copy);
+        emulator.update_cassette_wav_decoding();
+        emulator.update_cassette_fast_load();
+        emulator.update_sound_mixer();
+        emulator.update_sound_board();
+        emulator.update_joystick();
+        emulator.update_port_bridges();
+        emulator.update_paste_settings();
+        emulator.machine.memory_system.hires.set_installed(emulator.config_system.config_items.video_hires_board);
+        emulator.machine.memory_system.vid_mem.set_contention(emulator.config_system.config_items.video_bus_contention);
+        emulator.machine.memory_system.vid_mem.alternate_installed = fonts::has_alternate_set(&EmulatorLogicCore::get_character_generator_font(&emulator.config_system));
         emulator.cached_cpu_halted = emulator.machine.cpu.halted;
+        emulator.cached_cas_counter = emulator.machine.devices.cassette.counter();
+        emulator.cached_cas_motor = emulator.cassette_motor_state();
         emulator.init_video_thread();
         emulator.power_on();
         emulator.send_initial_status();
         emulator
     }
-    fn get_rom_choice(selected_rom: u32, config_system: &proj_config::ConfigSystem) -> Option<path::PathBuf> {
+    // The parts of the selected rom and their offsets.  The files are looked
+    // for in the configuration directory, and then in the rom directories,
+    // and the parts without an offset go right after the previous one.
+    fn get_rom_choice(selected_rom: u32, config_system: &proj_config::ConfigSystem) -> Vec<(path::PathBuf, u16)> {
         let rom_choice = match selected_rom {
-            1 => { config_system.config_items.general_level_1_rom.clone() },
-            2 => { config_system.config_items.general_level_2_rom.clone() },
-            3 => { config_system.config_items.general_misc_rom.clone() },
+            1 => { config_system.config_items.paths_level_1_rom.clone() },
+            2 => { config_system.config_items.paths_level_2_rom.clone() },
+            3 => { config_system.config_items.paths_misc_rom.clone() },
             _ => { panic!("Invalid ROM image selected"); }
         };
-        match rom_choice {
-            Some(filename) => {
-                let mut rom_file_path = config_system.config_dir_path.clone();
-                rom_file_path.push(filename);
-                Some(rom_file_path)
+        let rom_parts = match rom_choice.as_deref().and_then(proj_config::parse_rom_parts) {
+            Some(rom_parts) => { rom_parts },
+            None => { return Vec::new(); },
+        };
+
+        let mut search_dirs = vec![config_system.config_dir_path.clone()];
+        for rom_dir in &config_system.config_items.paths_rom_dirs {
+            search_dirs.push(config_system.config_dir_path.join(rom_dir));
+        }
+
+        let mut parts = Vec::new();
+        let mut next_offset: u32 = 0;
+        for (file_name, offset) in rom_parts {
+            let rom_file_path = search_dirs.iter()
+                                           .map(|search_dir| search_dir.join(&file_name))
+                                           .find(|rom_file_path| rom_file_path.is_file())
+                                           .unwrap_or_else(|| config_system.config_dir_path.join(&file_name));
+            let offset = match offset {
+                Some(offset) => { offset as u32 },
+                None => { next_offset },
+            };
+            next_offset = offset + match fs::metadata(&rom_file_path) {
+                Ok(metadata) => { metadata.len().min(memory::ROM_SIZE as u64) as u32 },
+                Err(..) => { 0 },
+            };
+            parts.push((rom_file_path, offset.min(memory::ROM_SIZE as u32) as u16));
+        }
+        parts
+    }
+    // The font of the character generator, either the one loaded from the
+    // character generator file, or one of the built-in ones.
+    fn get_character_generator_font(config_system: &proj_config::ConfigSystem) -> Box<[u8]> {
+        if let Some(filename) = &config_system.config_items.video_character_generator_file {
+            let mut font_file_path = config_system.config_dir_path.clone();
+            font_file_path.push(filename);
+
+            match fonts::load_font_file(&font_file_path) {
+                Ok(font) => {
+                    return font;
+                },
+                Err(error) => {
+                    error!("Failed to load the character generator font `{}', using the built-in character generator {}: {}.", font_file_path.display(), config_system.config_items.video_character_generator, error);
+                },
+            }
+        }
+        sdl_video::font_for_cg_num(config_system.config_items.video_character_generator).into()
+    }
+    // The background and foreground colors of the screen, with the
+    // brightness and contrast applied.
+    fn get_screen_colors(config_system: &proj_config::ConfigSystem) -> ((u8, u8, u8), (u8, u8, u8)) {
+        let config_items = &config_system.config_items;
+        (sdl_video::adjust_color(config_items.video_bg_color, config_items.video_brightness, config_items.video_contrast),
+         sdl_video::adjust_color(config_items.video_fg_color, config_items.video_brightness, config_items.video_contrast))
+    }
+    fn update_screen_textures(&mut self) {
+        let (bg_color, fg_color) = EmulatorLogicCore::get_screen_colors(&self.config_system);
+        let font = EmulatorLogicCore::get_character_generator_font(&self.config_system);
+        let vid_mem = &mut self.machine.memory_system.vid_mem;
+        vid_mem.alternate_installed = fonts::has_alternate_set(&font);
+        vid_mem.alternate &= vid_mem.alternate_installed;
+        self.video_cmd_tx.send(VideoCommand::UpdateTextures { bg_color, fg_color, font }).unwrap();
+    }
+    // A new file in the screenshot directory, named after the current time.
+    fn screenshot_path(&self) -> io::Result<path::PathBuf> {
+        let mut directory = self.config_system.config_dir_path.clone();
+        if let Some(screenshot_dir) = &self.config_system.config_items.paths_screenshot_dir {
+            directory.push(screenshot_dir);
+        }
+        fs::create_dir_all(&directory)?;
+
+        let timestamp = util::timestamp();
+        let mut path = directory.join(format!("screenshot-{}.png", timestamp));
+        let mut number = 2;
+        while path.exists() {
+            path = directory.join(format!("screenshot-{}-{}.png", timestamp, number));
+            number += 1;
+        }
+        Ok(path)
+    }
+    // The fast loading ROM traps only fit the Level II ROM.
+    fn update_cassette_fast_load(&mut self) {
+        self.machine.set_cassette_fast_load(self.config_system.config_items.cassette_fast_load && self.selected_rom == 2);
+    }
+    fn update_cassette_wav_decoding(&mut self) -> bool {
+        let config_items = &self.config_system.config_items;
+        let wav_decoding = cassette::WavDecoding {
+            threshold:   config_items.cassette_wav_threshold,
+            noise_floor: config_items.cassette_wav_noise_floor,
+            dc_filter:   config_items.cassette_wav_dc_filter,
+            channel:     config_items.cassette_wav_channel,
+            speed:       config_items.cassette_wav_speed,
+        };
+        self.machine.devices.cassette.set_wav_decoding(wav_decoding)
+    }
+    fn update_sound(&mut self) {
+        let config_items = &self.config_system.config_items;
+        let settings = sdl_audio::AudioSettings {
+            enabled:     config_items.audio_enabled,
+            volume:      config_items.audio_volume,
+            sample_rate: config_items.audio_sample_rate,
+            buffer_size: config_items.audio_buffer_size,
+            latency:     config_items.audio_latency,
+        };
+
+        // The sound is also generated while it's being recorded, even if it
+        // isn't played.
+        self.machine.devices.sound.set_format(settings.sample_rate, config_items.audio_resampling);
+        self.machine.devices.sound.set_enabled(settings.enabled || self.audio_recording.is_some());
+        self.video_cmd_tx.send(VideoCommand::SetSound(settings)).unwrap();
+    }
+    fn update_sound_mixer(&mut self) {
+        let config_items = &self.config_system.config_items;
+        let sound = &mut self.machine.devices.sound;
+
+        sound.set_muted(sound::Source::Tape,      config_items.audio_mute_tape);
+        sound.set_muted(sound::Source::Program,   config_items.audio_mute_program);
+        sound.set_muted(sound::Source::Orchestra, config_items.audio_mute_orchestra);
+        sound.set_muted(sound::Source::Ay,        config_items.audio_mute_ay);
+    }
+    fn update_sound_board(&mut self) {
+        self.machine.memory_system.ay.set_port(self.config_system.config_items.audio_ay_port);
+    }
+    fn update_key_profile(&self) {
+        let config_items = &self.config_system.config_items;
+        let bindings = match &config_items.keyboard_profile {
+            Some(name) => {
+                sdl_keyboard::key_profile(name, &config_items.keyboard_profiles).unwrap_or_else(|| {
+                    warn!("There's no key profile named `{}', the key bindings are used without one.", name);
+                    Vec::new()
+                })
+            },
+            None => { Vec::new() },
+        };
+        self.video_cmd_tx.send(VideoCommand::SetKeyProfile(bindings)).unwrap();
+    }
+    fn update_key_repeat(&self) {
+        let config_items = &self.config_system.config_items;
+        let repeat = if config_items.keyboard_auto_repeat {
+            Some(sdl_keyboard::KeyRepeat {
+                delay:    time::Duration::from_millis(config_items.keyboard_repeat_delay_ms as u64),
+                interval: time::Duration::from_secs(1) / config_items.keyboard_repeat_per_second,
+            })
+        } else {
+            None
+        };
+        self.video_cmd_tx.send(VideoCommand::SetKeyRepeat(repeat)).unwrap();
+    }
+    fn update_paste_settings(&mut self) {
+        self.machine.devices.keyboard.set_wait_for_scan(self.config_system.config_items.keyboard_paste_wait_for_scan);
+    }
+    fn update_joystick(&mut self) {
+        self.machine.memory_system.joystick.set_port(self.config_system.config_items.controller_joystick_port);
+    }
+    // The files of the bridges are taken relative to the configuration
+    // directory.
+    fn update_port_bridges(&mut self) {
+        let mut specs = self.config_system.config_items.machine_port_bridges.clone();
+        for spec in &mut specs {
+            if let bridge::BridgeTarget::File(ref mut file_path) = spec.target {
+                *file_path = self.config_system.config_dir_path.join(&*file_path);
+            }
+        }
+        self.machine.memory_system.bridges.install(&specs);
+    }
+    fn show_sound_mixer(&self) {
+        let config_items = &self.config_system.config_items;
+        let state = |muted: bool| { if muted { "muted" } else { "playing" } };
+
+        info!("Sound mixer:");
+        info!("");
+        info!("    Output:              {}", if config_items.audio_enabled { "enabled" } else { "disabled" });
+        info!("    Master volume:       {}%", config_items.audio_volume);
+        info!("    Tape:                {}", state(config_items.audio_mute_tape));
+        info!("    Program:             {}", state(config_items.audio_mute_program));
+        info!("    Orchestra-85:        {}", state(config_items.audio_mute_orchestra));
+        match config_items.audio_ay_port {
+            Some(port) => { info!("    AY-3-8910 at {:02X}h:  {}", port, state(config_items.audio_mute_ay)); },
+            None       => { info!("    AY-3-8910:           not installed"); },
+        }
+        info!("");
+    }
+    fn start_audio_recording(&mut self, file: &str) {
+        if let Some((_, ref file_path)) = self.audio_recording {
+            error!("The sound is already being recorded into `{}', use `/record stop' first.", file_path.display());
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        if !self.overwrite_confirmed(&file_path) {
+            return;
+        }
+        match sound::WavWriter::create(&file_path, self.machine.devices.sound.sample_rate()) {
+            Ok(writer) => {
+                self.notify("Recording the sound");
+                info!("Recording the sound into `{}'.", file_path.display());
+                self.audio_recording = Some((writer, file_path));
+                self.update_sound();
+            },
+            Err(error) => {
+                error!("Failed to create `{}': {}.", file_path.display(), error);
+            },
+        }
+    }
+    // Returns false if there was no recording to finish.
+    fn finish_audio_recording(&mut self) -> bool {
+        match self.audio_recording.take() {
+            Some((writer, file_path)) => {
+                let sample_rate = writer.sample_rate() as usize;
+                match writer.finish() {
+                    Ok(samples) => {
+                        info!("Recorded {}.{} seconds of sound into `{}'.", samples / sample_rate, ((samples % sample_rate) * 10) / sample_rate, file_path.display());
+                    },
+                    Err(error) => {
+                        error!("Failed to finish writing `{}': {}.", file_path.display(), error);
+                    },
+                }
+                true
             },
             None => {
-                None
+                false
+            },
+        }
+    }
+    fn stop_audio_recording(&mut self) {
+        if self.finish_audio_recording() {
+            self.update_sound();
+        } else {
+            info!("The sound isn't being recorded.");
+        }
+    }
+    fn record_samples(&mut self, samples: &[i16]) {
+        let failure = match self.audio_recording {
+            Some((ref mut writer, _)) => {
+                if writer.sample_rate() != self.machine.devices.sound.sample_rate() {
+                    Some("the sample rate was changed".to_owned())
+                } else {
+                    writer.write_samples(samples).err().map(|error| error.to_string())
+                }
+            },
+            None => { None },
+        };
+        if let Some(reason) = failure {
+            warn!("Stopping the sound recording, {}.", reason);
+            self.stop_audio_recording();
+        }
+    }
+    fn start_video_recording(&mut self, file: &str) {
+        if let Some(ref recording) = self.video_recording {
+            error!("The screen is already being recorded into `{}', use `/record stop' first.", recording.file_path.display());
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        if !self.overwrite_confirmed(&file_path) {
+            return;
+        }
+        let config_items = &self.config_system.config_items;
+        let (bg_color, fg_color) = EmulatorLogicCore::get_screen_colors(&self.config_system);
+        let colors = [bg_color, fg_color];
+        match gif::GifWriter::create(&file_path, video::SCREEN_WIDTH as u16, video::SCREEN_HEIGHT as u16, colors) {
+            Ok(writer) => {
+                self.notify("Recording the screen");
+                info!("Recording the screen into `{}'.", file_path.display());
+                self.video_recording = Some(VideoRecording {
+                    writer,
+                    file_path,
+                    font:       EmulatorLogicCore::get_character_generator_font(&self.config_system),
+                    frame_rate: config_items.video_recording_frame_rate,
+                    max_frames: config_items.video_recording_max_seconds.saturating_mul(machine::FRAME_RATE),
+                    frames:     0,
+                    phase:      machine::FRAME_RATE,
+                    captured:   0,
+                });
+            },
+            Err(error) => {
+                error!("Failed to create `{}': {}.", file_path.display(), error);
+            },
+        }
+    }
+    fn start_input_recording(&mut self, file: &str) {
+        if !self.powered_on {
+            error!("The machine has to be powered on to record its input.");
+            return;
+        }
+        if let Some(ref file_path) = self.input_recording {
+            error!("The input is already being recorded into `{}', use `/record stop' first.", file_path.display());
+            return;
+        }
+        if let Some(ref file_path) = self.input_replay {
+            error!("The input recorded in `{}' is being replayed, use `/record stop' first.", file_path.display());
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        if !self.overwrite_confirmed(&file_path) {
+            return;
+        }
+        self.machine.start_input_recording();
+        self.notify("Recording the input");
+        info!("Recording the input into `{}'.", file_path.display());
+        self.input_recording = Some(file_path);
+    }
+    // Returns false if there was no recording to finish.
+    fn finish_input_recording(&mut self) -> bool {
+        match self.input_recording.take() {
+            Some(file_path) => {
+                if let Some(recording) = self.machine.finish_input_recording() {
+                    let cycles_per_tenth = (machine::CPU_HZ / 10) as u64;
+                    let tenths = recording.length() / cycles_per_tenth;
+                    match fs::write(&file_path, recording.to_bytes()) {
+                        Ok(..) => {
+                            info!("Recorded {}.{} seconds of input into `{}'.", tenths / 10, tenths % 10, file_path.display());
+                        },
+                        Err(error) => {
+                            error!("Failed to write `{}': {}.", file_path.display(), error);
+                        },
+                    }
+                }
+                true
+            },
+            None => {
+                false
+            },
+        }
+    }
+    // The snapshot of the recording is loaded the same way as a saved
+    // state, and the keyboard and the joystick are left out until it ends.
+    fn start_input_replay(&mut self, file: &str) {
+        if !self.powered_on {
+            error!("The machine has to be powered on to replay its input.");
+            return;
+        }
+        if let Some(ref file_path) = self.input_recording {
+            error!("The input is being recorded into `{}', use `/record stop' first.", file_path.display());
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        let bytes = match fs::read(&file_path) {
+            Ok(bytes) => { bytes },
+            Err(error) => {
+                error!("Failed to read `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+        let recording = match replay::InputRecording::parse(&bytes) {
+            Ok(recording) => { recording },
+            Err(error) => {
+                error!("Failed to load `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+        let rom_matches = recording.snapshot().rom_matches(&self.machine);
+        if let Err(error) = self.machine.start_input_replay(recording) {
+            error!("Failed to load `{}': {}.", file_path.display(), error);
+            return;
+        }
+        if !rom_matches {
+            warn!("`{}' was recorded with a different ROM, the replay won't go the same way.", file_path.display());
+        }
+        self.notify("Replaying the input");
+        info!("Replaying the input recorded in `{}'.", file_path.display());
+        self.input_replay = Some(file_path);
+    }
+    // Returns false if nothing was being replayed.
+    fn stop_input_replay(&mut self) -> bool {
+        match self.input_replay.take() {
+            Some(file_path) => {
+                self.machine.stop_input_replay();
+                info!("Stopped replaying `{}'.", file_path.display());
+                true
+            },
+            None => {
+                false
             },
         }
     }
+    fn check_input_replay(&mut self) {
+        if self.input_replay.is_some() && !self.machine.replaying_input() {
+            let file_path = self.input_replay.take().unwrap();
+            self.notify("Replay finished");
+            info!("Finished replaying `{}'.", file_path.display());
+        }
+    }
+    // The recording or the replay of the input only works out for as long
+    // as nothing else reaches the machine.
+    fn end_input_log(&mut self, reason: &str) {
+        if self.input_recording.is_some() {
+            warn!("Stopping the input recording, {}.", reason);
+            self.finish_input_recording();
+        }
+        if self.input_replay.is_some() {
+            warn!("Stopping the replay, {}.", reason);
+            self.stop_input_replay();
+        }
+    }
     fn get_cassette_path(config_system: &proj_config::ConfigSystem) -> Option<path::PathBuf> {
         match &config_system.config_items.cassette_file {
             Some(filename) => {
@@ -206,6 +997,11 @@ impl EmulatorLogicCore {
             },
         }
     }
+    fn get_config_relative_path(config_system: &proj_config::ConfigSystem, filename: &str) -> path::PathBuf {
+        let mut file_path = config_system.config_dir_path.clone();
+        file_path.push(filename);
+        file_path
+    }
     fn send_initial_status(&self) {
         self.status_tx.send(EmulatorStatus::Created).unwrap();
 
@@ -220,6 +1016,7 @@ impl EmulatorLogicCore {
         } else {
             self.status_tx.send(EmulatorStatus::NotPaused).unwrap();
         }
+        self.status_tx.send(EmulatorStatus::Warp(self.warp)).unwrap();
 
         if self.powered_on && !self.paused {
             self.video_cmd_tx.send(VideoCommand::SetFrameDrawing { enabled: true, emulation_paused: false }).unwrap();
@@ -234,10 +1031,239 @@ impl EmulatorLogicCore {
         } else {
             self.status_tx.send(EmulatorStatus::CpuNotHalted).unwrap();
         }
+        self.status_tx.send(EmulatorStatus::CassetteCounter(self.cached_cas_counter)).unwrap();
+        self.status_tx.send(EmulatorStatus::Clock(self.cached_clock)).unwrap();
+        let (running, forced) = self.cached_cas_motor;
+        self.status_tx.send(EmulatorStatus::CassetteMotor { running, forced }).unwrap();
+    }
+    fn cassette_motor_state(&self) -> (bool, bool) {
+        (self.machine.devices.cassette.motor_running(), self.machine.devices.cassette.motor_override().is_some())
+    }
+    // The T-states run since the machine was powered on, which is how long
+    // it has been on in the emulated time, whatever speed it ran at.
+    fn cycles_since_power_on(&self) -> Option<u64> {
+        if self.powered_on {
+            Some(self.machine.cycles() - self.power_on_cycles)
+        } else {
+            None
+        }
+    }
+    // The speed the machine runs at, in percent of the speed of the real one,
+    // or none to run it as fast as it can, like with the turbo key held.
+    pub fn set_speed_limit(&mut self, speed_limit: Option<u32>) {
+        match speed_limit {
+            Some(100) => { },
+            Some(percent) => { info!("Running at {}% of the speed of the real machine.", percent); },
+            None => { info!("Running as fast as possible."); },
+        }
+        self.speed_limit = speed_limit;
+    }
+    // A speed limit set while running takes the place of the one of the
+    // title attached, too.
+    fn change_speed_limit(&mut self, speed_limit: Option<u32>) {
+        self.title_speed_limit = None;
+        self.set_speed_limit(speed_limit);
+        match speed_limit {
+            Some(100) => {
+                self.notify("Speed 100%");
+                info!("Running at the speed of the real machine.");
+            },
+            Some(percent) => { self.notify(&format!("Speed {}%", percent)); },
+            None => { self.notify("Speed unlimited"); },
+        }
+        if self.warp {
+            info!("The warp is on, the speed limit applies once it's off.");
+        }
+    }
+    // The slow motion goes from the speed of the real machine to a half of
+    // it, to a tenth of it, and back.
+    fn next_slow_motion(&self) -> u32 {
+        match self.current_speed_limit() {
+            Some(100) => { 50 },
+            Some(50)  => { 10 },
+            _         => { 100 },
+        }
+    }
+    // The warp runs the machine as fast as it can until it's turned off
+    // again, like the turbo key held down, whatever the speed limit.
+    fn set_warp(&mut self, warp: bool) {
+        self.warp = warp;
+        self.status_tx.send(EmulatorStatus::Warp(warp)).unwrap();
+        if warp {
+            self.notify("Warp on");
+            info!("Warp on, running as fast as possible.");
+        } else {
+            self.notify("Warp off");
+            info!("Warp off.");
+        }
+    }
+    // The speed limit of the title attached takes the place of the one of
+    // the run.
+    fn current_speed_limit(&self) -> Option<u32> {
+        self.title_speed_limit.unwrap_or(self.speed_limit)
+    }
+    // Use the settings of the title a cassette or a /CMD file belongs to, in
+    // place of the ones of the title attached before.
+    fn attach_title(&mut self, media_path: &path::Path, from_cassette: bool) {
+        let title = titles::find(&self.config_system.config_dir_path, media_path);
+        self.detach_title();
+        let title = match title {
+            Some(title) => { title },
+            None => { return; },
+        };
+
+        let mut apply_actions = Vec::new();
+        for (entry_specifier, value) in &title.entries {
+            match self.config_system.override_config_entry_for_title(entry_specifier, &format!("= {}", value)) {
+                Ok(apply_action) => {
+                    if !apply_actions.contains(&apply_action) {
+                        apply_actions.push(apply_action);
+                    }
+                },
+                Err(error) => {
+                    error!("The `{}' entry of the settings of `{}' can't be used: {}.", entry_specifier, title.name, error);
+                },
+            }
+        }
+        for apply_action in apply_actions {
+            self.apply_config_change(apply_action);
+        }
+        self.title_speed_limit = title.speed_limit;
+
+        let file_name = media_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        info!("Using the settings of `{}' for `{}'.", title.name, file_name);
+        self.title = Some((title.name, from_cassette));
+    }
+    fn detach_title(&mut self) {
+        let name = match self.title.take() {
+            Some((name, _)) => { name },
+            None => { return; },
+        };
+        self.title_speed_limit = None;
+
+        match self.config_system.clear_title_overrides() {
+            Ok(apply_actions) => {
+                for apply_action in apply_actions {
+                    self.apply_config_change(apply_action);
+                }
+                info!("Stopped using the settings of `{}'.", name);
+            },
+            Err(error) => {
+                error!("Failed to go back to the settings from before `{}': {}.", name, error);
+            },
+        }
+    }
+    // The media are attached once the logic core starts running.
+    pub fn set_startup_media(&mut self, startup_media: StartupMedia) {
+        self.startup_media = Some(startup_media);
+    }
+    // The cassette given on the command line is only in the drive for the
+    // run, its entries are overridden rather than written into the
+    // configuration file, until another cassette gets inserted.
+    fn change_cassette_entry(&mut self, entry_specifier: &str, invocation_text: &str) -> Result<proj_config::ConfigChangeApplyAction, proj_config::ConfigError> {
+        if self.cassette_for_run {
+            self.config_system.override_config_entry(entry_specifier, invocation_text)
+        } else {
+            self.config_system.change_config_entry(entry_specifier, invocation_text)
+        }
+    }
+    fn insert_cassette(&mut self, format: cassette::Format, file: String, auto_load: bool) {
+        if file.to_lowercase() == "none" {
+            info!("A filename of `{}' is not allowed, since the config system would understand it as a lack of a cassette.", file);
+        } else {
+            match self.change_cassette_entry("cassette_file", format!("= {}", file).as_str()) {
+                Err(error) => {
+                    info!("Failed to set the cassette file in the config system: {}.", error);
+                },
+                Ok(..) => {
+                    let cassette_file_path = EmulatorLogicCore::get_cassette_path(&self.config_system);
+                    if self.machine.devices.cassette.set_cassette_file(cassette_file_path) {
+
+                        match self.change_cassette_entry("cassette_file_format", match format {
+                            cassette::Format::CAS => { "= CAS" },
+                            cassette::Format::CPT => { "= CPT" },
+                            cassette::Format::WAV => { "= WAV" },
+                        }) {
+                            Err(error) => {
+                                info!("Failed to set the cassette file format in the config system: {}.", error);
+                            },
+                            Ok(..) => {
+                                self.machine.devices.cassette.set_cassette_data_format(self.config_system.config_items.cassette_file_format);
+                                match self.change_cassette_entry("cassette_file_offset", "= 0") {
+                                    Err(error) => {
+                                        info!("Failed to set the cassette file offset in the config system: {}.", error);
+                                    },
+                                    Ok(..) => {
+                                        self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset);
+                                        let file_name = path::Path::new(&file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                                        self.notify(&format!("Cassette {} inserted", file_name));
+                                        if let Some(cassette_path) = EmulatorLogicCore::get_cassette_path(&self.config_system) {
+                                            self.attach_title(&cassette_path, true);
+                                        }
+                                        if auto_load {
+                                            self.type_cassette_load_command();
+                                        }
+                                    }
+                                }
+                            },
+                        }
+                    }
+                },
+            }
+        }
+    }
+    fn attach_startup_media(&mut self, startup_media: StartupMedia) {
+        let load_cassette = startup_media.autostart && startup_media.cassette.is_some();
+        if let Some((format, file)) = startup_media.cassette {
+            self.cassette_for_run = true;
+            self.insert_cassette(format, file, false);
+        }
+        if startup_media.cmd_file.is_some() || load_cassette {
+            self.autostart = Some(Autostart {
+                cmd_file: startup_media.cmd_file,
+                load_cassette,
+                memory_size_given: false,
+            });
+        }
+    }
+    // Watch the screen for the BASIC prompt, to start the program of the
+    // startup media once the ROM is ready for it.
+    fn check_autostart(&mut self) {
+        let seconds_on = match self.cycles_since_power_on() {
+            Some(cycles) => { cycles / machine::CPU_HZ as u64 },
+            None => { return; },
+        };
+        let frame = video::VideoFrame::new(&self.machine.memory_system.vid_mem, &self.machine.memory_system.hires);
+        let text = frame.text(video::TextGraphics::Ascii);
+
+        let memory_size_given = self.autostart.as_ref().map(|autostart| autostart.memory_size_given).unwrap_or(true);
+
+        // The question about the memory size stays on the screen above the
+        // prompt once it's answered, so the prompt is looked for first.
+        if text.contains("READY") {
+            if let Some(autostart) = self.autostart.take() {
+                if let Some(file) = autostart.cmd_file {
+                    self.run_cmd_file(&file);
+                }
+                if autostart.load_cassette {
+                    self.type_cassette_load_command();
+                }
+            }
+        } else if !memory_size_given && text.contains("MEMORY SIZE?") {
+            if let Some(autostart) = &mut self.autostart {
+                autostart.memory_size_given = true;
+            }
+            self.type_text("\n");
+        } else if seconds_on >= AUTOSTART_TIMEOUT {
+            self.autostart = None;
+            error!("The machine didn't get to the BASIC prompt in {} seconds, so nothing was started.", AUTOSTART_TIMEOUT);
+        }
     }
     fn power_on(&mut self) {
         self.machine.power_on();
         self.powered_on = true;
+        self.power_on_cycles = self.machine.cycles();
+        self.power_on_time = time::Instant::now();
 
         self.status_tx.send(EmulatorStatus::PoweredOn).unwrap();
         if self.paused {
@@ -249,9 +1275,11 @@ impl EmulatorLogicCore {
     }
     fn power_off<ES: Sink<cassette::CassetteEvent>>(&mut self, cassette_event_sink: &mut ES) {
 
+        self.end_input_log("the machine was powered off");
         self.machine.power_off(cassette_event_sink);
 
         self.powered_on = false;
+        self.frame_advance = 0;
         self.status_tx.send(EmulatorStatus::PoweredOff).unwrap();
 
         self.video_cmd_tx.send(VideoCommand::SetFrameDrawing { enabled: false, emulation_paused: false }).unwrap();
@@ -259,40 +1287,76 @@ impl EmulatorLogicCore {
     }
     fn pause(&mut self) {
         self.paused = true;
+        self.frame_end = false;
         if self.powered_on {
             self.video_cmd_tx.send(VideoCommand::SetFrameDrawing { enabled: false, emulation_paused: true }).unwrap();
         }
         self.status_tx.send(EmulatorStatus::Paused).unwrap();
+        self.notify("Paused");
         info!("Emulation paused.");
     }
     fn unpause(&mut self) {
         self.paused = false;
+        self.frame_advance = 0;
         if self.powered_on {
             self.video_cmd_tx.send(VideoCommand::SetFrameDrawing { enabled: true, emulation_paused: false }).unwrap();
         }
         self.status_tx.send(EmulatorStatus::NotPaused).unwrap();
+        self.notify("Running");
         info!("Emulation unpaused.");
     }
+    // Tell the user about something done in a notification in the window,
+    // besides the messages.
+    fn notify(&self, text: &str) {
+        self.video_cmd_tx.send(VideoCommand::Notify(text.to_owned())).unwrap();
+    }
+    // Whether an action which throws work away can go ahead, the ones set up
+    // to be confirmed have to be asked for again within a few seconds.  The
+    // key tells the actions apart, like by the file they overwrite.  The
+    // commands of the scripts and of the remote clients go ahead right away.
+    fn confirmed(&mut self, action: confirm::Action, key: &str, warning: &str) -> bool {
+        if self.unattended || !self.config_system.config_items.interface_confirm_actions.contains(&action) || self.confirmation.confirmed(key) {
+            return true;
+        }
+        self.notify("Again to confirm");
+        warn!("{}, do it again within {} seconds to go ahead.", warning, confirm::CONFIRM_SECONDS);
+        false
+    }
+    fn overwrite_confirmed(&mut self, file_path: &path::Path) -> bool {
+        !file_path.exists() || self.confirmed(confirm::Action::Overwrite, &format!("overwrite {}", file_path.display()), &format!("The file `{}' already exists and would be overwritten", file_path.display()))
+    }
+    fn cassette_slot(&self) -> CassetteSlot {
+        let config_items = &self.config_system.config_items;
+        let position = self.machine.devices.cassette.counter().map(|(position, _)| position).unwrap_or(0);
+        (config_items.cassette_file.clone(), config_items.cassette_file_format, position)
+    }
     fn handle_command<ES: Sink<cassette::CassetteEvent>>(&mut self, command: EmulatorCommand, cassette_event_sink: &mut ES) {
         match command {
             EmulatorCommand::PowerOn => {
                 if !self.powered_on {
                     self.power_on();
+                    self.notify("Powered on");
                 }
             },
             EmulatorCommand::PowerOff => {
                 if self.powered_on {
                     self.power_off(cassette_event_sink);
+                    self.notify("Powered off");
                 }
             },
             EmulatorCommand::ResetSoft => {
+                self.end_input_log("the machine was reset");
                 self.machine.cpu.reset();
+                self.notify("Reset");
                 info!("System reset performed.");
             },
             EmulatorCommand::ResetHard => {
-                self.power_off(cassette_event_sink);
-                self.power_on();
-                info!("Full reset performed.");
+                if !self.powered_on || self.confirmed(confirm::Action::Reset, "reset", "The full reset throws away the running program") {
+                    self.power_off(cassette_event_sink);
+                    self.power_on();
+                    self.notify("Full reset");
+                    info!("Full reset performed.");
+                }
             },
             EmulatorCommand::Pause => {
                 if !self.paused {
@@ -316,13 +1380,298 @@ impl EmulatorLogicCore {
                 self.status_tx.send(EmulatorStatus::TerminateNotification).unwrap();
             },
             EmulatorCommand::NmiRequest => {
+                self.end_input_log("the reset button was pressed");
                 self.machine.memory_system.nmi_request = true;
             },
-            EmulatorCommand::WipeSystemRom => {
-                self.machine.memory_system.rom_chip.wipe();
+            EmulatorCommand::SetFullscreen(state) => {
+                let fullscreen = state.unwrap_or(!self.config_system.config_items.video_fullscreen);
+                match self.config_system.change_config_entry("video_fullscreen", if fullscreen { "= true" } else { "= false" }) {
+                    Err(error) => {
+                        error!("Failed to update the full-screen mode in the config system: {}.", error);
+                    },
+                    Ok(..) => {
+                        self.video_cmd_tx.send(VideoCommand::SetFullscreen(fullscreen)).unwrap();
+                    },
+                }
             },
-            EmulatorCommand::LoadSystemRom { path, offset } => {
-                self.machine.memory_system.rom_chip.load_from_file(path, offset);
+            EmulatorCommand::SetAlternateCharset(state) => {
+                let vid_mem = &mut self.machine.memory_system.vid_mem;
+                if !vid_mem.alternate_installed {
+                    error!("The character generator has no alternate character set, load a dump of the Japanese or the European one with the `character_generator_file' entry of the config file.");
+                } else {
+                    vid_mem.alternate = state.unwrap_or(!vid_mem.alternate);
+                    info!("The {} is now shown for the characters 0xC0-0xFF.", if vid_mem.alternate { "alternate character set" } else { "block graphics" });
+                }
+            },
+            EmulatorCommand::SetMagnifier(state) => {
+                self.magnifier = state.unwrap_or(!self.magnifier);
+                self.video_cmd_tx.send(VideoCommand::SetMagnifier(self.magnifier)).unwrap();
+            },
+            EmulatorCommand::CycleOverlay => {
+                let level = self.config_system.config_items.video_overlay.next();
+                match self.config_system.change_config_entry("video_overlay", match level {
+                    sdl_video::OverlayLevel::Off   => { "= off" },
+                    sdl_video::OverlayLevel::Speed => { "= speed" },
+                    sdl_video::OverlayLevel::Full  => { "= full" },
+                }) {
+                    Err(error) => {
+                        error!("Failed to update the overlay in the config system: {}.", error);
+                    },
+                    Ok(..) => {
+                        self.video_cmd_tx.send(VideoCommand::SetOverlay(level)).unwrap();
+                    },
+                }
+            },
+            EmulatorCommand::SetScaling(scaling) => {
+                match self.config_system.change_config_entry("video_scaling", match scaling {
+                    sdl_video::Scaling::Integer => { "= integer" },
+                    sdl_video::Scaling::Aspect  => { "= aspect" },
+                    sdl_video::Scaling::Stretch => { "= stretch" },
+                }) {
+                    Err(error) => {
+                        error!("Failed to update the scaling mode in the config system: {}.", error);
+                    },
+                    Ok(..) => {
+                        self.video_cmd_tx.send(VideoCommand::SetScaling(scaling)).unwrap();
+                        info!("Scaling mode changed.");
+                    },
+                }
+            },
+            EmulatorCommand::ShowPicture => {
+                let config_items = &self.config_system.config_items;
+                info!("Picture settings:");
+                info!("");
+                info!("    Brightness:          {}%", config_items.video_brightness);
+                info!("    Contrast:            {}%", config_items.video_contrast);
+                info!("    Scanlines:           {}%", config_items.video_crt_scanlines);
+                info!("");
+            },
+            EmulatorCommand::SetPicture { setting, level, relative } => {
+                let config_items = &self.config_system.config_items;
+                let (entry_specifier, name, current, max) = match setting {
+                    PictureSetting::Brightness => { ("video_brightness",    "brightness", config_items.video_brightness,    sdl_video::MAX_BRIGHTNESS) },
+                    PictureSetting::Contrast   => { ("video_contrast",      "contrast",   config_items.video_contrast,      sdl_video::MAX_CONTRAST) },
+                    PictureSetting::Scanlines  => { ("video_crt_scanlines", "scanlines",  config_items.video_crt_scanlines, 100) },
+                };
+                // Relative changes stop at the ends of the range, absolute
+                // ones out of it are refused by the config system.
+                let level = if relative {
+                    ((current as i64) + (level as i64)).clamp(0, max as i64)
+                } else {
+                    level as i64
+                };
+                match self.config_system.change_config_entry(entry_specifier, format!("= {}", level).as_str()) {
+                    Err(error) => {
+                        error!("Failed to set the {}: {}.", name, error);
+                    },
+                    Ok(..) => {
+                        match setting {
+                            PictureSetting::Brightness | PictureSetting::Contrast => { self.update_screen_textures(); },
+                            PictureSetting::Scanlines => { self.update_crt_effects(); },
+                        }
+                        info!("Picture {} set to {}%.", name, level);
+                    },
+                }
+            },
+            EmulatorCommand::Screenshot(scaled) => {
+                match self.screenshot_path() {
+                    Ok(path) => {
+                        let scaled = scaled.unwrap_or(self.config_system.config_items.video_screenshot_scaled);
+                        self.video_cmd_tx.send(VideoCommand::TakeScreenshot { path, scaled }).unwrap();
+                    },
+                    Err(error) => {
+                        error!("Failed to prepare the screenshot directory: {}.", error);
+                    },
+                }
+            },
+            EmulatorCommand::ScreenText { file } => {
+                let frame = video::VideoFrame::new(&self.machine.memory_system.vid_mem, &self.machine.memory_system.hires);
+                let text = frame.text(self.config_system.config_items.video_text_graphics);
+                match file {
+                    Some(file) => {
+                        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                        match fs::write(&file_path, text) {
+                            Ok(..) => {
+                                info!("Saved the text on the screen into `{}'.", file_path.display());
+                            },
+                            Err(error) => {
+                                error!("Failed to write `{}': {}.", file_path.display(), error);
+                            },
+                        }
+                    },
+                    None => {
+                        self.video_cmd_tx.send(VideoCommand::SetClipboardText(text)).unwrap();
+                    },
+                }
+            },
+            EmulatorCommand::TypeText(text) => {
+                self.type_text(&text);
+            },
+            EmulatorCommand::PasteClipboard => {
+                self.video_cmd_tx.send(VideoCommand::PasteClipboard).unwrap();
+            },
+            EmulatorCommand::PasteText(text) => {
+                self.paste_text(&text);
+            },
+            EmulatorCommand::TypeFile(file) => {
+                self.type_file(&file);
+            },
+            EmulatorCommand::SetJoystick(pressed) => {
+                self.machine.memory_system.joystick.set_pressed(pressed);
+            },
+            EmulatorCommand::SetTurbo(turbo) => {
+                self.turbo = turbo;
+            },
+            EmulatorCommand::SetWarp(state) => {
+                self.set_warp(state.unwrap_or(!self.warp));
+            },
+            EmulatorCommand::SetSpeedLimit(speed_limit) => {
+                self.change_speed_limit(speed_limit);
+            },
+            EmulatorCommand::ShowSpeedLimit => {
+                let speed = match self.current_speed_limit() {
+                    Some(100)     => { "at the speed of the real machine".to_owned() },
+                    Some(percent) => { format!("at {}% of the speed of the real machine", percent) },
+                    None          => { "as fast as possible".to_owned() },
+                };
+                match (&self.title, self.title_speed_limit) {
+                    (Some((name, _)), Some(..)) => { info!("Running {}, the speed limit of the title `{}'.", speed, name); },
+                    _ => { info!("Running {}.", speed); },
+                }
+                if self.warp {
+                    info!("The warp is on, the speed limit applies once it's off.");
+                }
+            },
+            EmulatorCommand::CycleSlowMotion => {
+                let percent = self.next_slow_motion();
+                self.change_speed_limit(Some(percent));
+            },
+            EmulatorCommand::AdvanceFrames(frames) => {
+                // The first press stops the machine where it is, the ones
+                // after that run it a frame at a time.
+                if !self.powered_on {
+                    warn!("The machine is powered off, there's nothing to advance.");
+                } else if !self.paused {
+                    self.pause();
+                } else {
+                    self.frame_advance = self.frame_advance.saturating_add(frames);
+                }
+            },
+            EmulatorCommand::DumpMemory { address, length } => {
+                for line in sdl_debug::dump_memory(&mut self.machine, address, length) {
+                    info!("{}", line);
+                }
+            },
+            EmulatorCommand::OpenFile(file) => {
+                self.open_file(file, cassette_event_sink);
+            },
+            EmulatorCommand::ShowMenu => {
+                self.show_menu();
+            },
+            EmulatorCommand::SaveState { file } => {
+                self.save_state(file.as_deref().unwrap_or(DEFAULT_SNAPSHOT_FILE));
+            },
+            EmulatorCommand::LoadState { file } => {
+                self.load_state(file.as_deref().unwrap_or(DEFAULT_SNAPSHOT_FILE));
+            },
+            EmulatorCommand::SetStatusPane(state) => {
+                self.status_pane = state;
+                if self.status_pane {
+                    self.update_status_pane();
+                }
+            },
+            EmulatorCommand::Acknowledge(id) => {
+                self.status_tx.send(EmulatorStatus::Acknowledged(id)).unwrap();
+            },
+            EmulatorCommand::SetUnattended(state) => {
+                self.unattended = state;
+            },
+            EmulatorCommand::SetKeyProfile(name) => {
+                let profiles = &self.config_system.config_items.keyboard_profiles;
+                match &name {
+                    Some(name) if sdl_keyboard::key_profile(name, profiles).is_none() => {
+                        error!("There's no key profile named `{}', see `/keyboard profile' for a list of them.", name);
+                    },
+                    _ => {
+                        match self.config_system.change_config_entry("keyboard_profile", format!("= {}", name.as_deref().unwrap_or("none")).as_str()) {
+                            Err(error) => {
+                                error!("Failed to update the key profile in the config system: {}.", error);
+                            },
+                            Ok(..) => {
+                                self.update_key_profile();
+                                match name {
+                                    Some(name) => {
+                                        self.notify(&format!("Key profile {}", name));
+                                        info!("Switched to the `{}' key profile.", name);
+                                    },
+                                    None => {
+                                        self.notify("Key profile off");
+                                        info!("Key profile turned off.");
+                                    },
+                                }
+                            },
+                        }
+                    },
+                }
+            },
+            EmulatorCommand::ShowKeyProfiles => {
+                let config_items = &self.config_system.config_items;
+                info!("Key profiles:");
+                info!("");
+                for name in sdl_keyboard::key_profile_names(&config_items.keyboard_profiles) {
+                    let chosen = config_items.keyboard_profile.as_deref() == Some(name.as_str());
+                    info!("    {}{}", name, if chosen { " (in use)" } else { "" });
+                }
+                if config_items.keyboard_profile.is_none() {
+                    info!("");
+                    info!("No key profile is in use.");
+                }
+            },
+            EmulatorCommand::ShowClock => {
+                match self.cycles_since_power_on() {
+                    Some(cycles) => {
+                        info!("Powered on for {} of emulated time ({} T-states), and {} of real time.",
+                              util::clock_text(cycles / machine::CPU_HZ as u64), cycles, util::clock_text(self.power_on_time.elapsed().as_secs()));
+                    },
+                    None => {
+                        info!("The machine is powered off.");
+                    },
+                }
+            },
+            EmulatorCommand::ShowKeyLatency => {
+                let latency = self.machine.devices.keyboard.take_latency();
+                if latency.count > 0 {
+                    info!("Key latency: average {:.2} ms, maximum {:.2} ms, over {} key events.",
+                          latency.average().as_secs_f64() * 1_000.0, latency.max.as_secs_f64() * 1_000.0, latency.count);
+                } else {
+                    info!("No key events since the key latency was last shown.");
+                }
+            },
+            EmulatorCommand::TypeSpecialKey(key) => {
+                let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_ms_per_keypress) / 1_000;
+                for event in keyboard::special_key_events(key, cycles_per_keypress) {
+                    self.machine.devices.keyboard.add_keyboard_event(event);
+                }
+            },
+            EmulatorCommand::SetColors { fg_color, bg_color } => {
+                let mut entries = vec![("video_fg_color", fg_color)];
+                if let Some(bg_color) = bg_color {
+                    entries.push(("video_bg_color", bg_color));
+                }
+                for (entry, (red, green, blue)) in entries {
+                    if let Err(error) = self.config_system.change_config_entry(entry, format!("= #{:02X}{:02X}{:02X}", red, green, blue).as_str()) {
+                        error!("Failed to update the screen colors in the config system: {}.", error);
+                        return;
+                    }
+                }
+                self.update_screen_textures();
+                info!("Screen colors changed.");
+            },
+            EmulatorCommand::WipeSystemRom => {
+                self.machine.memory_system.rom_chip.wipe();
+            },
+            EmulatorCommand::LoadSystemRom { path, offset } => {
+                self.machine.memory_system.rom_chip.load_from_file(path, offset);
             },
             EmulatorCommand::LoadSystemRomDefault => {
                 let rom_choice = EmulatorLogicCore::get_rom_choice(self.selected_rom, &self.config_system);
@@ -342,6 +1691,7 @@ impl EmulatorLogicCore {
                         error!("ROM number {} is invalid, valid options are 1 for Level 1 BASIC, 2 for Level 2 basic, and 3 for the miscellaneous rom.", rom_nr);
                     } else {
                         self.selected_rom = rom_nr;
+                        self.update_cassette_fast_load();
                         let was_powered_on = self.powered_on;
 
                         if was_powered_on {
@@ -358,57 +1708,33 @@ impl EmulatorLogicCore {
                 }
             },
             EmulatorCommand::CassetteCommand(sub_command) => {
+                // The cassette swapped out can be put back with an undo.
+                let swaps_cassette = matches!(sub_command, EmulatorCassetteCommand::Insert { .. } | EmulatorCassetteCommand::Eject);
+                let slot_before = self.cassette_slot();
+
                 match sub_command {
-                    EmulatorCassetteCommand::Insert { format, file } => {
-                        if file.to_lowercase() == "none" {
-                            info!("A filename of `{}' is not allowed, since the config system would understand it as a lack of a cassette.", file);
-                        } else {
-                            match self.config_system.change_config_entry("cassette_file", format!("= {}", file).as_str()) {
-                                Err(error) => {
-                                    info!("Failed to set the cassette file in the config system: {}.", error);
-                                },
-                                Ok(..) => {
-                                    let cassette_file_path = EmulatorLogicCore::get_cassette_path(&self.config_system);
-                                    if self.machine.devices.cassette.set_cassette_file(cassette_file_path) {
-
-                                        match self.config_system.change_config_entry("cassette_file_format", match format {
-                                            cassette::Format::CAS => { "= CAS" },
-                                            cassette::Format::CPT => { "= CPT" },
-                                        }) {
-                                            Err(error) => {
-                                                info!("Failed to set the cassette file format in the config system: {}.", error);
-                                            },
-                                            Ok(..) => {
-                                                self.machine.devices.cassette.set_cassette_data_format(self.config_system.config_items.cassette_file_format);
-                                                match self.config_system.change_config_entry("cassette_file_offset", "= 0") {
-                                                    Err(error) => {
-                                                        info!("Failed to set the cassette file offset in the config system: {}.", error);
-                                                    },
-                                                    Ok(..) => {
-                                                        self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset);
-                                                    }
-                                                }
-                                            },
-                                        }
-                                    }
-                                },
-                            }
-                        }
+                    EmulatorCassetteCommand::Insert { format, file, auto_load } => {
+                        self.cassette_for_run = false;
+                        self.insert_cassette(format, file, auto_load);
                     },
                     EmulatorCassetteCommand::Eject => {
                         match self.config_system.config_items.cassette_file {
 
                             Some(..) => {
-                                match self.config_system.change_config_entry("cassette_file", "= none") {
+                                match self.change_cassette_entry("cassette_file", "= none") {
                                     Err(error) => {
                                         info!("Failed to update the cassette file field in the config system: {}.", error);
                                     },
                                     Ok(..) => {
                                         let cassette_file_path = EmulatorLogicCore::get_cassette_path(&self.config_system);
                                         if self.machine.devices.cassette.set_cassette_file(cassette_file_path) {
+                                            self.notify("Cassette ejected");
                                             info!("Cassette ejected.");
+                                            if matches!(self.title, Some((_, true))) {
+                                                self.detach_title();
+                                            }
 
-                                            match self.config_system.change_config_entry("cassette_file_offset", "= 0") {
+                                            match self.change_cassette_entry("cassette_file_offset", "= 0") {
                                                 Ok(_) => {
                                                     self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset);
                                                 },
@@ -426,7 +1752,7 @@ impl EmulatorLogicCore {
                         }
                     },
                     EmulatorCassetteCommand::Seek { position } => {
-                        match self.config_system.change_config_entry("cassette_file_offset", format!("= {}", position).as_str()) {
+                        match self.change_cassette_entry("cassette_file_offset", format!("= {}", position).as_str()) {
                             Err(error) => {
                                 info!("Failed to set the cassette file offset in the config system: {}.", error);
                             },
@@ -438,20 +1764,274 @@ impl EmulatorLogicCore {
                         }
                     },
                     EmulatorCassetteCommand::Rewind => {
-                        match self.config_system.change_config_entry("cassette_file_offset", "= 0") {
+                        match self.change_cassette_entry("cassette_file_offset", "= 0") {
                             Err(error) => {
                                 info!("Failed to set the cassette file offset in the config system: {}.", error);
                             },
                             Ok(..) => {
                                 if self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset) {
+                                    self.notify("Cassette rewound");
                                     info!("Cassette rewound back to the beginning.");
                                 }
                             },
                         }
                     },
+                    EmulatorCassetteCommand::FastForward => {
+                        match self.machine.devices.cassette.counter() {
+                            Some((_, length)) => {
+                                match self.change_cassette_entry("cassette_file_offset", format!("= {}", length).as_str()) {
+                                    Err(error) => {
+                                        info!("Failed to set the cassette file offset in the config system: {}.", error);
+                                    },
+                                    Ok(..) => {
+                                        if self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset) {
+                                            info!("Cassette wound forward to the end, position {}.", length);
+                                        }
+                                    },
+                                }
+                            },
+                            None => {
+                                info!("The cassette drive is empty.");
+                            },
+                        }
+                    },
                     EmulatorCassetteCommand::Erase => {
                         self.machine.devices.cassette.erase_cassette();
                     },
+                    EmulatorCassetteCommand::Motor { state } => {
+                        self.machine.devices.cassette.set_motor_override(&self.machine.memory_system.cas_rec, state, cassette_event_sink);
+                        match state {
+                            Some(true)  => { info!("The cassette motor is forced on."); },
+                            Some(false) => { info!("The cassette motor is forced off."); },
+                            None        => { info!("The cassette motor is controlled by the machine's relay again."); },
+                        }
+                    },
+                    EmulatorCassetteCommand::Index => {
+                        self.list_cassette_index();
+                    },
+                    EmulatorCassetteCommand::GoTo { recording } => {
+                        let recordings = match self.cassette_recordings() {
+                            Some(recordings) => { recordings },
+                            None => { return; },
+                        };
+                        let selected = match recording.parse::<usize>() {
+                            Ok(index) => {
+                                if index >= 1 { recordings.get(index - 1) } else { None }
+                            },
+                            Err(..) => {
+                                recordings.iter().find(|entry| entry.name().map(|name| name.eq_ignore_ascii_case(&recording)).unwrap_or(false))
+                            },
+                        };
+                        match selected {
+                            Some(entry) => {
+                                let position = entry.offset;
+                                self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Seek { position }), cassette_event_sink);
+                            },
+                            None => {
+                                error!("There's no recording `{}' on the cassette, use `/cassette index' to list them.", recording);
+                            },
+                        }
+                    },
+                    EmulatorCassetteCommand::Library { browse } => {
+                        if browse {
+                            self.status_tx.send(EmulatorStatus::BrowseCassettes(self.cassette_library_path())).unwrap();
+                        } else {
+                            self.list_cassette_library();
+                        }
+                    },
+                    EmulatorCassetteCommand::Undo => {
+                        // Putting the cassette back is a swap of its own, so
+                        // doing it again undoes the undo.
+                        match self.previous_cassette.take() {
+                            Some((Some(file), format, position)) => {
+                                self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Insert { format, file, auto_load: false }), cassette_event_sink);
+                                if position > 0 {
+                                    self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Seek { position }), cassette_event_sink);
+                                }
+                            },
+                            Some((None, _, _)) => {
+                                self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Eject), cassette_event_sink);
+                            },
+                            None => {
+                                info!("There's no cassette change to undo.");
+                            },
+                        }
+                    },
+                    EmulatorCassetteCommand::LibraryInsert { index, auto_load } => {
+                        let library = self.cassette_library();
+                        if index < 1 || index > library.len() {
+                            error!("There's no cassette number {} in the library, use `/cassette library' to list the available ones.", index);
+                        } else {
+                            let (file, format) = library[index - 1].clone();
+                            self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Insert { format, file, auto_load }), cassette_event_sink);
+                        }
+                    },
+                }
+                if swaps_cassette && self.config_system.config_items.cassette_file != slot_before.0 {
+                    self.previous_cassette = Some(slot_before);
+                }
+            },
+            EmulatorCommand::DebuggerCommand(sub_command) => {
+                match sub_command {
+                    EmulatorDebuggerCommand::Window { state } => {
+                        self.debug_window = state.unwrap_or(!self.debug_window);
+                        self.video_cmd_tx.send(VideoCommand::SetDebugWindow(self.debug_window)).unwrap();
+                        if self.debug_window {
+                            self.update_debug_window();
+                        }
+                    },
+                    EmulatorDebuggerCommand::Memory { address } => {
+                        self.debug_memory = address;
+                        if self.debug_window || self.debug_panel {
+                            self.update_debug_window();
+                        }
+                        info!("The debugger window shows the memory starting at 0x{:04X}.", address);
+                    },
+                    EmulatorDebuggerCommand::Panel { state } => {
+                        self.debug_panel = state;
+                        if self.debug_panel {
+                            self.update_debug_window();
+                        }
+                    },
+                }
+            },
+            EmulatorCommand::SoundCommand(sub_command) => {
+                match sub_command {
+                    EmulatorSoundCommand::Statistics => {
+                        self.video_cmd_tx.send(VideoCommand::ReportSoundStatistics).unwrap();
+                    },
+                    EmulatorSoundCommand::Mixer => {
+                        self.show_sound_mixer();
+                    },
+                    EmulatorSoundCommand::Volume { volume } => {
+                        match self.config_system.change_config_entry("audio_volume", format!("= {}", volume).as_str()) {
+                            Err(error) => {
+                                error!("Failed to set the sound volume: {}.", error);
+                            },
+                            Ok(..) => {
+                                self.update_sound();
+                                info!("Sound volume set to {}%.", volume);
+                            },
+                        }
+                    },
+                    EmulatorSoundCommand::Mute { source, muted } => {
+                        let (entry_specifier, name) = match source {
+                            sound::Source::Tape      => { ("audio_mute_tape",      "tape") },
+                            sound::Source::Program   => { ("audio_mute_program",   "program") },
+                            sound::Source::Orchestra => { ("audio_mute_orchestra", "Orchestra-85") },
+                            sound::Source::Ay        => { ("audio_mute_ay",        "AY-3-8910") },
+                        };
+                        match self.config_system.change_config_entry(entry_specifier, if muted { "= true" } else { "= false" }) {
+                            Err(error) => {
+                                error!("Failed to update the sound mixer: {}.", error);
+                            },
+                            Ok(..) => {
+                                self.update_sound_mixer();
+                                info!("The {} sound is now {}.", name, if muted { "muted" } else { "unmuted" });
+                            },
+                        }
+                    },
+                }
+            },
+            EmulatorCommand::RecordCommand(sub_command) => {
+                match sub_command {
+                    EmulatorRecordCommand::Audio { file } => {
+                        self.start_audio_recording(&file);
+                    },
+                    EmulatorRecordCommand::Video { file } => {
+                        self.start_video_recording(&file);
+                    },
+                    EmulatorRecordCommand::Input { file } => {
+                        self.start_input_recording(&file);
+                    },
+                    EmulatorRecordCommand::Replay { file } => {
+                        self.start_input_replay(&file);
+                    },
+                    EmulatorRecordCommand::Stop => {
+                        if self.audio_recording.is_none() && self.video_recording.is_none() && self.input_recording.is_none() && self.input_replay.is_none() {
+                            info!("Nothing is being recorded.");
+                        } else {
+                            self.notify("Recording stopped");
+                        }
+                        if self.finish_audio_recording() {
+                            self.update_sound();
+                        }
+                        if let Some(recording) = self.video_recording.take() {
+                            recording.finish();
+                        }
+                        self.finish_input_recording();
+                        self.stop_input_replay();
+                    },
+                }
+            },
+            EmulatorCommand::TimerCommand(sub_command) => {
+                self.handle_timer_command(sub_command);
+            },
+            EmulatorCommand::ScriptCommand(sub_command) => {
+                self.handle_script_command(sub_command, cassette_event_sink);
+            },
+            EmulatorCommand::DiskCommand(sub_command) => {
+                match sub_command {
+                    EmulatorDiskCommand::Directory { file } => {
+                        self.list_disk_directory(&file);
+                    },
+                    EmulatorDiskCommand::Commit { file } => {
+                        let image_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                        if let Some(mut image) = disk::DiskImage::load_from_file(image_path) {
+                            image.commit_overlay();
+                        }
+                    },
+                    EmulatorDiskCommand::Discard { file } => {
+                        let image_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                        if let Some(mut image) = disk::DiskImage::load_from_file(image_path) {
+                            let overlay_path = image.overlay_path();
+                            if !overlay_path.exists() || self.confirmed(confirm::Action::Discard, &format!("discard {}", overlay_path.display()), &format!("Discarding the overlay throws away the changes made to `{}'", file)) {
+                                image.discard_overlay();
+                            }
+                        }
+                    },
+                    EmulatorDiskCommand::ReadSector { file, track, side, sector } => {
+                        let image_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                        let image = match disk::DiskImage::load_from_file(image_path) {
+                            Some(image) => { image },
+                            None => { return; },
+                        };
+                        match image.read_sector(track, side, sector) {
+                            Some(data) => {
+                                info!("Track {}, side {}, sector {} ({} bytes):", track, side, sector, data.len());
+                                for (row_index, row) in data.chunks(16).enumerate() {
+                                    let hex: Vec<String> = row.iter().map(|byte| format!("{:02X}", byte)).collect();
+                                    let text: String = row.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' }).collect();
+                                    info!("    {:03X}: {:<47}  {}", row_index * 16, hex.join(" "), text);
+                                }
+                            },
+                            None => {
+                                error!("Sector {} of track {} (side {}) doesn't exist in `{}'.", sector, track, side, image.image_path().display());
+                            },
+                        }
+                    },
+                    EmulatorDiskCommand::WriteSector { file, track, side, sector, offset, bytes } => {
+                        let image_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                        let mut image = match disk::DiskImage::load_from_file(image_path) {
+                            Some(image) => { image },
+                            None => { return; },
+                        };
+                        let mut data = match image.read_sector(track, side, sector) {
+                            Some(data) => { data },
+                            None => {
+                                error!("Sector {} of track {} (side {}) doesn't exist in `{}'.", sector, track, side, image.image_path().display());
+                                return;
+                            },
+                        };
+                        if (offset + bytes.len()) > data.len() {
+                            error!("The sector is only {} bytes large, {} bytes at offset {} don't fit.", data.len(), bytes.len(), offset);
+                            return;
+                        }
+                        data[offset..(offset + bytes.len())].copy_from_slice(&bytes);
+                        if image.write_sector(track, side, sector, &data) && image.save() {
+                            info!("Wrote {} byte(s) into track {}, side {}, sector {}.", bytes.len(), track, side, sector);
+                        }
+                    },
                 }
             },
             EmulatorCommand::ConfigCommand(sub_command) => {
@@ -469,6 +2049,19 @@ impl EmulatorLogicCore {
                             info!("{}", &config_entry);
                         }
                     },
+                    EmulatorConfigCommand::Dump => {
+                        let config_lines = match self.config_system.get_effective_config() {
+                            Ok(lines) => { lines },
+                            Err(error) => {
+                                info!("Failed to retrieve the configuration in effect: {}.", error);
+                                return;
+                            },
+                        };
+                        info!("The configuration in effect:");
+                        for config_line in config_lines {
+                            info!("{}", &config_line);
+                        }
+                    },
                     EmulatorConfigCommand::Show { entry_specifier } => {
                         let config_entry = match self.config_system.get_config_entry_current_state(&entry_specifier) {
                             Ok(entry) => { entry },
@@ -482,80 +2075,7 @@ impl EmulatorLogicCore {
                     EmulatorConfigCommand::Change { entry_specifier, invocation_text } => {
                         match self.config_system.change_config_entry(&entry_specifier, &invocation_text) {
                             Ok(apply_action) => {
-                                match apply_action {
-                                    proj_config::ConfigChangeApplyAction::RomChange(which) => {
-                                        if which == self.selected_rom {
-                                            let rom_choice = EmulatorLogicCore::get_rom_choice(self.config_system.config_items.general_default_rom, &self.config_system);
-                                            self.machine.memory_system.load_system_rom(rom_choice);
-                                        } else {
-                                            info!("Configuration updated.");
-                                        }
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeRamSize => {
-                                        self.machine.memory_system.ram_chip.change_size(self.config_system.config_items.general_ram_size as u16);
-                                        info!("Ram size changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::UpdateMsPerKeypress => {
-                                        let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_ms_per_keypress) / 1_000;
-
-                                        self.video_cmd_tx.send(VideoCommand::SetCyclesPerKeypress(cycles_per_keypress)).unwrap();
-                                        info!("Miliseconds per keypress setting updated.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeWindowedResolution => {
-                                        self.video_cmd_tx.send(VideoCommand::SetWindowedResolution(self.config_system.config_items.video_windowed_resolution)).unwrap();
-                                        info!("Windowed mode resolution changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeFullscreenResolution => {
-                                        self.video_cmd_tx.send(VideoCommand::SetFullscreenResolution(self.config_system.config_items.video_fullscreen_resolution, self.config_system.config_items.video_desktop_fullscreen_mode)).unwrap();
-                                        info!("Fullscreen mode resolution changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeColor => {
-                                        self.video_cmd_tx.send(VideoCommand::UpdateTextures { bg_color: self.config_system.config_items.video_bg_color, fg_color: self.config_system.config_items.video_fg_color, cg_num: self.config_system.config_items.video_character_generator }).unwrap();
-                                        info!("Color settings updated.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeHwAccelUsage => {
-                                        self.set_video_mode_with_fallback();
-                                        info!("Hardware acceleration usage setting changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeVsyncUsage => {
-                                        self.set_video_mode_with_fallback();
-                                        info!("Vertical synchronization usage setting changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeCharacterGenerator => {
-                                        self.video_cmd_tx.send(VideoCommand::UpdateTextures { bg_color: self.config_system.config_items.video_bg_color, fg_color: self.config_system.config_items.video_fg_color, cg_num: self.config_system.config_items.video_character_generator }).unwrap();
-                                        info!("Character generator changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::ChangeLowercaseModUsage => {
-                                        self.machine.memory_system.vid_mem.update_lowercase_mod(self.config_system.config_items.video_lowercase_mod);
-                                        if self.config_system.config_items.video_lowercase_mod {
-                                            info!("Lowercase mod enabled. (does not apply to text already in video memory)");
-                                        } else {
-                                            info!("Lowercase mod disabled. (does not apply to text already in video memory)");
-                                        }
-                                    },
-                                    proj_config::ConfigChangeApplyAction::UpdateCassetteFile => {
-                                        let cassette_file_path = EmulatorLogicCore::get_cassette_path(&self.config_system);
-                                        self.machine.devices.cassette.set_cassette_file(cassette_file_path);
-                                        info!("Cassette file changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::UpdateCassetteFileFormat => {
-                                        self.machine.devices.cassette.set_cassette_data_format(self.config_system.config_items.cassette_file_format);
-                                        info!("Cassette file data format changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::UpdateCassetteFileOffset => {
-                                        self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset);
-                                        info!("Cassette file offset changed.");
-                                    },
-                                    proj_config::ConfigChangeApplyAction::UpdateDefaultRomSelection => {
-                                        info!("Default system ROM selection changed to ROM {}.", self.config_system.config_items.general_default_rom);
-                                        if self.config_system.config_items.general_default_rom != self.selected_rom {
-                                            info!("Currently, ROM {} is in use.  To switch to the new default, use the following command: `/machine switch-rom {}'.", self.selected_rom, self.config_system.config_items.general_default_rom);
-                                        }
-                                    },
-                                    proj_config::ConfigChangeApplyAction::AlreadyUpToDate => {
-                                        info!("Nothing to change.");
-                                    },
-                                }
+                                self.apply_config_change(apply_action);
                             },
                             Err(error) => {
                                 error!("Failed to perform the requested configuration change: {}.", error);
@@ -567,37 +2087,983 @@ impl EmulatorLogicCore {
             },
         }
     }
-    fn set_video_mode(&mut self, force_hw_accel_off: bool) -> bool {
+    // Pick up the changes of the configuration file edited by hand while the
+    // emulator runs.  The ones which would change the hardware of the running
+    // machine or the tape in it are only reported, and used once the emulator
+    // is restarted.
+    fn check_config_file(&mut self) {
+        self.config_checked = time::Instant::now();
+        if !self.config_system.config_file_changed() {
+            return;
+        }
+        let changes = match self.config_system.reload_config_file() {
+            Ok(changes) => { changes },
+            Err(error) => {
+                error!("The configuration file was changed, but failed to reload it, keeping the previous settings: {}.", error);
+                return;
+            },
+        };
+        if changes.is_empty() {
+            return;
+        }
+        info!("The configuration file was changed, reloaded it.");
+
+        let mut applied = Vec::new();
+        for (entry_specifier, apply_action) in changes {
+            if apply_action.needs_restart() {
+                warn!("The change of `{}' takes effect once the emulator is restarted.", entry_specifier);
+            } else if !applied.contains(&apply_action) {
+                self.apply_config_change(apply_action);
+                applied.push(apply_action);
+            }
+        }
+    }
+    // Put a changed setting of the configuration file to use.
+    fn apply_config_change(&mut self, apply_action: proj_config::ConfigChangeApplyAction) {
+        match apply_action {
+            proj_config::ConfigChangeApplyAction::RomChange(which) => {
+                if which == self.selected_rom {
+                    let rom_choice = EmulatorLogicCore::get_rom_choice(self.config_system.config_items.machine_default_rom, &self.config_system);
+                    self.machine.memory_system.load_system_rom(rom_choice);
+                } else {
+                    info!("Configuration updated.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateRomDirs => {
+                let rom_choice = EmulatorLogicCore::get_rom_choice(self.selected_rom, &self.config_system);
+                self.machine.memory_system.load_system_rom(rom_choice);
+            },
+            proj_config::ConfigChangeApplyAction::ChangeRamSize => {
+                self.machine.memory_system.ram_chip.change_size(self.config_system.config_items.machine_ram_size as u16);
+                info!("Ram size changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateMsPerKeypress => {
+                let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_ms_per_keypress) / 1_000;
+
+                self.video_cmd_tx.send(VideoCommand::SetCyclesPerKeypress(cycles_per_keypress)).unwrap();
+                info!("Miliseconds per keypress setting updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateKeyMapping => {
+                self.video_cmd_tx.send(VideoCommand::SetKeyMapping(self.config_system.config_items.keyboard_mapping)).unwrap();
+                info!("Key mapping changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateKeyBindings => {
+                self.video_cmd_tx.send(VideoCommand::SetKeyBindings(self.config_system.config_items.keyboard_key_bindings.clone())).unwrap();
+                info!("Key bindings updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateKeyProfile => {
+                self.update_key_profile();
+                info!("Key profile updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdatePasteSettings => {
+                self.update_paste_settings();
+                info!("Paste settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateKeyRepeat => {
+                self.update_key_repeat();
+                info!("Key auto-repeat settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeWindowedResolution => {
+                self.video_cmd_tx.send(VideoCommand::SetWindowedResolution(self.config_system.config_items.video_windowed_resolution)).unwrap();
+                info!("Windowed mode resolution changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeScaling => {
+                self.video_cmd_tx.send(VideoCommand::SetScaling(self.config_system.config_items.video_scaling)).unwrap();
+                info!("Scaling mode changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeCrtEffects => {
+                self.update_crt_effects();
+                info!("CRT effects changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeFullscreenMode => {
+                self.video_cmd_tx.send(VideoCommand::SetFullscreen(self.config_system.config_items.video_fullscreen)).unwrap();
+                info!("Fullscreen mode {}.", if self.config_system.config_items.video_fullscreen { "enabled" } else { "disabled" });
+            },
+            proj_config::ConfigChangeApplyAction::ChangeFullscreenResolution => {
+                self.video_cmd_tx.send(VideoCommand::SetFullscreenResolution(self.config_system.config_items.video_fullscreen_resolution, self.config_system.config_items.video_desktop_fullscreen_mode)).unwrap();
+                info!("Fullscreen mode resolution changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeColor => {
+                self.update_screen_textures();
+                info!("Color settings updated.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeHwAccelUsage => {
+                self.set_video_mode_with_fallback();
+                info!("Hardware acceleration usage setting changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeRenderer => {
+                self.set_video_mode_with_fallback();
+                info!("Renderer changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeVsyncUsage => {
+                self.set_video_mode_with_fallback();
+                info!("Vertical synchronization usage setting changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeFrameSkip => {
+                self.video_cmd_tx.send(VideoCommand::SetFrameSkip(self.config_system.config_items.video_frame_skip)).unwrap();
+                info!("Frame skipping changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeOverlay => {
+                self.video_cmd_tx.send(VideoCommand::SetOverlay(self.config_system.config_items.video_overlay)).unwrap();
+                info!("Status overlay changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeNotifications => {
+                self.video_cmd_tx.send(VideoCommand::SetNotifications(self.config_system.config_items.video_notifications)).unwrap();
+                info!("On-screen notifications {}.", if self.config_system.config_items.video_notifications { "enabled" } else { "disabled" });
+            },
+            proj_config::ConfigChangeApplyAction::ChangeCharacterGenerator => {
+                self.update_screen_textures();
+                info!("Character generator changed.");
+            },
+            proj_config::ConfigChangeApplyAction::ChangeLowercaseModUsage => {
+                self.machine.memory_system.vid_mem.update_lowercase_mod(self.config_system.config_items.video_lowercase_mod);
+                if self.config_system.config_items.video_lowercase_mod {
+                    info!("Lowercase mod enabled. (does not apply to text already in video memory)");
+                } else {
+                    info!("Lowercase mod disabled. (does not apply to text already in video memory)");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::ChangeHiResBoard => {
+                self.machine.memory_system.hires.set_installed(self.config_system.config_items.video_hires_board);
+                if self.config_system.config_items.video_hires_board {
+                    info!("High resolution graphics board installed at ports 00h-05h.");
+                } else {
+                    info!("High resolution graphics board removed.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::ChangeBusContention => {
+                self.machine.memory_system.vid_mem.set_contention(self.config_system.config_items.video_bus_contention);
+                if self.config_system.config_items.video_bus_contention {
+                    info!("Video RAM bus contention emulation enabled.");
+                } else {
+                    info!("Video RAM bus contention emulation disabled.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteFile => {
+                let cassette_file_path = EmulatorLogicCore::get_cassette_path(&self.config_system);
+                self.machine.devices.cassette.set_cassette_file(cassette_file_path);
+                info!("Cassette file changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteFileFormat => {
+                self.machine.devices.cassette.set_cassette_data_format(self.config_system.config_items.cassette_file_format);
+                info!("Cassette file data format changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteFileOffset => {
+                self.machine.devices.cassette.set_cassette_file_offset(self.config_system.config_items.cassette_file_offset);
+                info!("Cassette file offset changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteWavCopy => {
+                self.machine.devices.cassette.set_wav_copy(self.config_system.config_items.cassette_wav_copy);
+                if self.config_system.config_items.cassette_wav_copy {
+                    info!("WAV copies of cassette recordings enabled.");
+                } else {
+                    info!("WAV copies of cassette recordings disabled.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateScreenshotSettings => {
+                info!("Screenshot settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateTextGraphics => {
+                self.video_cmd_tx.send(VideoCommand::SetTextGraphics(self.config_system.config_items.video_text_graphics)).unwrap();
+                info!("Text graphics representation changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateRecordingSettings => {
+                info!("Screen recording settings changed, they apply to the next recording.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteLibraryDir => {
+                info!("Cassette library directory changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteWavDecoding => {
+                if self.update_cassette_wav_decoding() {
+                    info!("WAV decoding parameters changed.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateSoundEnabled => {
+                self.update_sound();
+                if self.config_system.config_items.audio_enabled {
+                    info!("Sound enabled.");
+                } else {
+                    info!("Sound disabled.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateSoundVolume => {
+                self.update_sound();
+                info!("Sound volume changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateSoundOutput => {
+                self.update_sound();
+                info!("Sound output settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateSoundMixer => {
+                self.update_sound_mixer();
+                info!("Sound mixer settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateSoundBoard => {
+                self.update_sound_board();
+                match self.config_system.config_items.audio_ay_port {
+                    Some(port) => { info!("AY-3-8910 sound board installed at ports {:02X}h and {:02X}h.", port, port + 1); },
+                    None       => { info!("AY-3-8910 sound board removed."); },
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateJoystick => {
+                self.update_joystick();
+                match self.config_system.config_items.controller_joystick_port {
+                    Some(port) => { info!("Joystick interface installed at port {:02X}h.", port); },
+                    None       => { info!("Joystick interface removed."); },
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdatePortBridges => {
+                self.update_port_bridges();
+                if self.config_system.config_items.machine_port_bridges.is_empty() {
+                    info!("Port bridges removed.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateControllerBindings => {
+                self.video_cmd_tx.send(VideoCommand::SetControllerBindings(self.config_system.config_items.controller_bindings.clone())).unwrap();
+                info!("Controller bindings updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateCassetteFastLoad => {
+                self.update_cassette_fast_load();
+                if !self.config_system.config_items.cassette_fast_load {
+                    info!("Fast cassette loading disabled.");
+                } else if self.selected_rom == 2 {
+                    info!("Fast cassette loading enabled.");
+                } else {
+                    info!("Fast cassette loading enabled, it will take effect once the Level II ROM is in use.");
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateDefaultRomSelection => {
+                info!("Default system ROM selection changed to ROM {}.", self.config_system.config_items.machine_default_rom);
+                if self.config_system.config_items.machine_default_rom != self.selected_rom {
+                    info!("Currently, ROM {} is in use.  To switch to the new default, use the following command: `/machine switch-rom {}'.", self.selected_rom, self.config_system.config_items.machine_default_rom);
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateStartupScript => {
+                match self.config_system.config_items.paths_startup_script {
+                    Some(ref script) => { info!("The startup script changed to `{}', it runs the next time the emulator starts.", script); },
+                    None => { info!("No startup script will be run from now on."); },
+                }
+            },
+            proj_config::ConfigChangeApplyAction::UpdateAliases => {
+                self.status_tx.send(EmulatorStatus::Aliases(self.config_system.config_items.interface_aliases.clone())).unwrap();
+                info!("Command aliases updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateUiTheme => {
+                let config_items = &self.config_system.config_items;
+                self.status_tx.send(EmulatorStatus::UiTheme(ui_theme::ThemeColors::new(config_items.interface_ui_theme, &config_items.interface_ui_colors))).unwrap();
+                info!("Colors of the curses-based interface updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateLanguage => {
+                self.status_tx.send(EmulatorStatus::Language(self.config_system.config_items.interface_language.clone())).unwrap();
+                info!("Language of the curses-based interface updated.");
+            },
+            proj_config::ConfigChangeApplyAction::UpdateConfirmActions => {
+                info!("Confirmation settings changed.");
+            },
+            proj_config::ConfigChangeApplyAction::AlreadyUpToDate => {
+                info!("Nothing to change.");
+            },
+        }
+    }
+    fn list_disk_directory(&self, file: &str) {
+        let image_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        let image = match disk::DiskImage::load_from_file(image_path) {
+            Some(image) => { image },
+            None => { return; },
+        };
+        let entries = match trsdos::read_directory(&image) {
+            Some(entries) => { entries },
+            None => { return; },
+        };
+
+        info!("Directory of `{}' ({:?}, {} tracks):", image.image_path().display(), image.format(), image.track_count());
+        info!("");
+        info!("    {:<12}  {:>7}  {:<10}  {}", "File", "Size", "Protection", "Attributes");
+        for entry in &entries {
+            let attributes = match (entry.system, entry.invisible) {
+                (true,  true)  => { "system, invisible" },
+                (true,  false) => { "system" },
+                (false, true)  => { "invisible" },
+                (false, false) => { "" },
+            };
+            info!("    {:<12}  {:>7}  {:<10}  {}", entry.full_name(), entry.size, entry.protection_name(), attributes);
+        }
+        info!("");
+        info!("{} file(s) found.", entries.len());
+    }
+    // Open a file dropped onto the emulator window, by what its extension
+    // says it is.  The ones which can be meant in more than one way are put
+    // on the command line, for the user to finish.
+    fn open_file<ES: Sink<cassette::CassetteEvent>>(&mut self, file: String, cassette_event_sink: &mut ES) {
+        if let Some(format) = cassette::Format::from_path(path::Path::new(&file)) {
+            self.handle_command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Insert { format, file, auto_load: true }), cassette_event_sink);
+            return;
+        }
+        let extension = path::Path::new(&file).extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "cmd" => {
+                self.run_cmd_file(&file);
+            },
+            "dsk" | "dmk" | "jv1" | "jv3" | "imd" => {
+                self.list_disk_directory(&file);
+                info!("There are no disk drives on the emulated machine, see `/help disk' for what can be done with the image.");
+            },
+            "bas" | "txt" => {
+                self.status_tx.send(EmulatorStatus::SuggestCommand(format!("machine type {}", file))).unwrap();
+            },
+            _ => {
+                self.status_tx.send(EmulatorStatus::SuggestCommand(format!("memory load ram {} ", file))).unwrap();
+            },
+        }
+    }
+    // Load a /CMD program into the memory and jump to its entry point, like
+    // the DOS does when it's run.
+    fn run_cmd_file(&mut self, file: &str) {
+        if !self.powered_on {
+            error!("The machine has to be powered on to run `{}'.", file);
+            return;
+        }
+        let bytes = match fs::read(file) {
+            Ok(bytes) => { bytes },
+            Err(error) => {
+                error!("Failed to read `{}': {}.", file, error);
+                return;
+            },
+        };
+        let program = match cmd_file::CmdFile::parse(&bytes) {
+            Some(program) => { program },
+            None => {
+                error!("`{}' isn't a valid /CMD file.", file);
+                return;
+            },
+        };
+        // The programs may draw on the screen while they're loaded, but
+        // nothing else outside of the RAM.
+        let memory_end = memory::RAM_BASE as u32 + self.config_system.config_items.machine_ram_size;
+        if program.blocks.iter().any(|(address, data)| *address < memory::VID_BASE || (*address as u32 + data.len() as u32) > memory_end) {
+            error!("`{}' doesn't fit into the memory of the emulated machine.", file);
+            return;
+        }
+        program.load(&mut self.machine.memory_system);
+        self.attach_title(path::Path::new(file), false);
+
+        let (start, end) = program.address_range();
+        match program.entry_point {
+            Some(entry_point) => {
+                self.machine.cpu.regs.pc = entry_point;
+                self.machine.cpu.halted = false;
+                let file_name = path::Path::new(file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                self.notify(&format!("Started {}", file_name));
+                info!("Loaded `{}' into 0x{:04X}-0x{:04X}, and started it at 0x{:04X}.", file, start, end, entry_point);
+            },
+            None => {
+                info!("Loaded `{}' into 0x{:04X}-0x{:04X}, it has no entry point to start it at.", file, start, end);
+            },
+        }
+    }
+    // The menu of the emulator window, with the actions which can be taken
+    // at the moment.
+    fn show_menu(&self) {
+        use sdl_menu::{MenuItem, MenuAction};
+
+        let mut cassettes: Vec<MenuItem> = self.cassette_library().iter().enumerate().map(|(index, (file, _))| {
+            let file_name = path::Path::new(file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            MenuItem::new(&file_name, MenuAction::Command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::LibraryInsert { index: index + 1, auto_load: true })))
+        }).collect();
+        if cassettes.is_empty() {
+            cassettes.push(MenuItem::new("No cassettes in the library", MenuAction::Close));
+        }
+
+        let mut items = vec![MenuItem::new("Load cassette", MenuAction::Submenu(cassettes))];
+        if self.config_system.config_items.cassette_file.is_some() {
+            items.push(MenuItem::new("Rewind cassette", MenuAction::Command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Rewind))));
+            items.push(MenuItem::new("Eject cassette", MenuAction::Command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Eject))));
+        }
+        if self.previous_cassette.is_some() {
+            items.push(MenuItem::new("Undo cassette change", MenuAction::Command(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Undo))));
+        }
+        if self.powered_on {
+            items.push(MenuItem::new(if self.paused { "Continue" } else { "Pause" }, MenuAction::Command(EmulatorCommand::TogglePause)));
+            items.push(MenuItem::new(if self.warp { "Warp off" } else { "Warp" }, MenuAction::Command(EmulatorCommand::SetWarp(None))));
+            let slow_motion = match self.next_slow_motion() {
+                100     => { "Full speed".to_owned() },
+                percent => { format!("Slow motion {}%", percent) },
+            };
+            items.push(MenuItem::new(&slow_motion, MenuAction::Command(EmulatorCommand::CycleSlowMotion)));
+            if self.paused {
+                items.push(MenuItem::new("Advance a frame", MenuAction::Command(EmulatorCommand::AdvanceFrames(1))));
+            }
+            items.push(MenuItem::new("Reset", MenuAction::Command(EmulatorCommand::ResetSoft)));
+            items.push(MenuItem::new("Full reset", MenuAction::Command(EmulatorCommand::ResetHard)));
+            items.push(MenuItem::new("Save state", MenuAction::Command(EmulatorCommand::SaveState { file: None })));
+            if EmulatorLogicCore::get_config_relative_path(&self.config_system, DEFAULT_SNAPSHOT_FILE).is_file() {
+                items.push(MenuItem::new("Load state", MenuAction::Command(EmulatorCommand::LoadState { file: None })));
+            }
+            items.push(MenuItem::new("Power off", MenuAction::Command(EmulatorCommand::PowerOff)));
+        } else {
+            items.push(MenuItem::new("Power on", MenuAction::Command(EmulatorCommand::PowerOn)));
+        }
+        items.push(MenuItem::new("Screenshot", MenuAction::Command(EmulatorCommand::Screenshot(None))));
+        items.push(MenuItem::new(if self.config_system.config_items.video_fullscreen { "Leave full screen" } else { "Full screen" }, MenuAction::Command(EmulatorCommand::SetFullscreen(None))));
+        items.push(MenuItem::new("Quit", MenuAction::Command(EmulatorCommand::Terminate)));
+
+        self.video_cmd_tx.send(VideoCommand::ShowMenu(sdl_menu::Menu::new("Menu", items))).unwrap();
+    }
+    // The snapshots only have the CPU and the memory in them, see the
+    // snapshot module of the core for what's left out.
+    fn save_state(&mut self, file: &str) {
+        if !self.powered_on {
+            error!("The machine has to be powered on to save its state.");
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        if !self.overwrite_confirmed(&file_path) {
+            return;
+        }
+        match fs::write(&file_path, snapshot::Snapshot::take(&mut self.machine).to_bytes()) {
+            Ok(..) => {
+                self.notify("State saved");
+                info!("Saved the state of the machine into `{}'.", file_path.display());
+            },
+            Err(error) => {
+                error!("Failed to write `{}': {}.", file_path.display(), error);
+            },
+        }
+    }
+    fn load_state(&mut self, file: &str) {
+        if !self.powered_on {
+            error!("The machine has to be powered on to load a saved state.");
+            return;
+        }
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        let bytes = match fs::read(&file_path) {
+            Ok(bytes) => { bytes },
+            Err(error) => {
+                error!("Failed to read `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+        let state = match snapshot::Snapshot::parse(&bytes) {
+            Ok(state) => { state },
+            Err(error) => {
+                error!("Failed to load `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+        self.end_input_log("a saved state was loaded");
+        if let Err(error) = state.restore(&mut self.machine) {
+            error!("Failed to load `{}': {}.", file_path.display(), error);
+            return;
+        }
+        if !state.rom_matches(&self.machine) {
+            warn!("`{}' was saved with a different ROM, the program may not run properly.", file_path.display());
+        }
+        self.notify("State loaded");
+        info!("Loaded the state of the machine from `{}'.", file_path.display());
+    }
+    fn type_text(&mut self, text: &str) {
+        let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_ms_per_keypress) / 1_000;
+
+        match keyboard::text_to_key_events(text, cycles_per_keypress) {
+            Some(events) => {
+                for event in events {
+                    self.machine.devices.keyboard.add_keyboard_event(event);
+                }
+            },
+            None => {
+                error!("The text `{}' can't be typed on the keyboard of the emulated machine.", text.trim_end());
+            },
+        }
+    }
+    // Type in text from the clipboard, which may be a whole program listing,
+    // so the characters that can't be typed are skipped instead.
+    fn paste_text(&mut self, text: &str) {
+        let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_paste_ms_per_keypress) / 1_000;
+        let cycles_per_line = (machine::CPU_HZ * self.config_system.config_items.keyboard_paste_ms_per_line) / 1_000;
+        let (events, skipped) = keyboard::pasted_text_to_key_events(text, cycles_per_keypress, cycles_per_line);
+
+        if events.is_empty() {
+            error!("There's nothing in the clipboard that could be typed on the keyboard of the emulated machine.");
+            return;
+        }
+        // Each character is pressed and released, shifted ones press shift too.
+        info!("Pasting {} keystrokes from the clipboard.", events.len() / 2);
+        if skipped > 0 {
+            warn!("Skipped {} character(s) that can't be typed on the keyboard of the emulated machine.", skipped);
+        }
+        for event in events {
+            self.machine.devices.keyboard.add_keyboard_event(event);
+        }
+    }
+    // Type in a script from a file, at the typing rate of the config file,
+    // with the keys which don't type characters written as tokens.
+    fn type_file(&mut self, file: &str) {
+        let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, file);
+        let script = match fs::read_to_string(&file_path) {
+            Ok(script) => { script },
+            Err(error) => {
+                error!("Failed to read `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+        // Each character is a press and a release.
+        let cycles_per_keypress = machine::CPU_HZ / (self.config_system.config_items.keyboard_type_chars_per_second * 2);
+        let cycles_per_line = (machine::CPU_HZ * self.config_system.config_items.keyboard_paste_ms_per_line) / 1_000;
+        let (events, skipped) = match keyboard::script_to_key_events(&script, cycles_per_keypress, cycles_per_line) {
+            Ok(result) => { result },
+            Err(error) => {
+                error!("Failed to type `{}': {}.", file_path.display(), error);
+                return;
+            },
+        };
+
+        info!("Typing {} keystrokes from `{}'.", events.len() / 2, file_path.display());
+        if skipped > 0 {
+            warn!("Skipped {} character(s) that can't be typed on the keyboard of the emulated machine.", skipped);
+        }
+        for event in events {
+            self.machine.devices.keyboard.add_keyboard_event(event);
+        }
+    }
+    // Type in the command for loading the next recording on the tape, for a
+    // SYSTEM tape, the program gets started once the tape stops.
+    fn type_cassette_load_command(&mut self) {
+        let recordings = match self.cassette_recordings() {
+            Some(recordings) => { recordings },
+            None => { return; },
+        };
+        let position = match self.machine.devices.cassette.counter() {
+            Some((position, _)) => { position },
+            None => { 0 },
+        };
+
+        match recordings.iter().find(|recording| recording.end > position) {
+            Some(recording) => {
+                match recording.kind {
+                    tape::RecordingKind::Basic { .. } => {
+                        info!("Loading the {}.", recording.description());
+                        self.cas_autorun_keys = None;
+                        self.type_text("CLOAD\n");
+                    },
+                    tape::RecordingKind::System { ref name, .. } => {
+                        info!("Loading the {}.", recording.description());
+                        self.cas_autorun_keys = Some("/\n".to_owned());
+                        self.type_text(&format!("SYSTEM\n{}\n", name));
+                    },
+                    tape::RecordingKind::Unknown => {
+                        error!("The recording on the tape isn't a BASIC or a SYSTEM program, it can't be loaded automatically.");
+                    },
+                }
+            },
+            None => {
+                error!("There's nothing to load on the rest of the tape.");
+            },
+        }
+    }
+    // The recordings on the inserted cassette, only CAS files are supported.
+    // The contents of the cassette as CAS bytes, along with the position on
+    // the cassette each of them is at.  WAV recordings are decoded first.
+    fn cassette_bytes(&self) -> Option<(Vec<u8>, Vec<usize>)> {
+        let contents = match self.machine.devices.cassette.contents() {
+            Some(contents) => { contents },
+            None => {
+                info!("The cassette drive is empty.");
+                return None;
+            },
+        };
+        match self.config_system.config_items.cassette_file_format {
+            cassette::Format::CAS => {
+                Some((contents.to_vec(), (0..contents.len()).collect()))
+            },
+            cassette::Format::WAV => {
+                let mut progress = Progress::new(&self.status_tx, &self.cancel_flag, "Decoding the WAV cassette");
+                let decoded = self.machine.devices.cassette.decode_wav_with_progress(&mut |done, total| progress.update(done, total));
+                progress.finish();
+                decoded
+            },
+            cassette::Format::CPT => {
+                error!("Only the recordings on CAS and WAV cassettes can be indexed.");
+                None
+            },
+        }
+    }
+    fn handle_timer_command(&mut self, sub_command: EmulatorTimerCommand) {
+        let cycles = self.machine.cycles();
+        match sub_command {
+            EmulatorTimerCommand::Start => {
+                if self.stopwatch.is_some() {
+                    info!("Timer restarted.");
+                } else {
+                    info!("Timer started.");
+                }
+                self.stopwatch = Some(Stopwatch::new(cycles));
+            },
+            EmulatorTimerCommand::Lap => {
+                match self.stopwatch {
+                    Some(ref mut stopwatch) => {
+                        info!("Lap: {}.", timer_text(cycles - stopwatch.lap_cycles, stopwatch.lap.elapsed()));
+                        info!("Total: {}.", timer_text(cycles - stopwatch.started_cycles, stopwatch.started.elapsed()));
+                        stopwatch.lap_cycles = cycles;
+                        stopwatch.lap = time::Instant::now();
+                    },
+                    None => {
+                        error!("The timer isn't running, start it with `/timer start'.");
+                    },
+                }
+            },
+            EmulatorTimerCommand::Stop => {
+                match self.stopwatch.take() {
+                    Some(stopwatch) => {
+                        info!("Timer stopped after {}.", timer_text(cycles - stopwatch.started_cycles, stopwatch.started.elapsed()));
+                    },
+                    None => {
+                        error!("The timer isn't running, start it with `/timer start'.");
+                    },
+                }
+            },
+        }
+    }
+    #[cfg(feature = "scripting")]
+    fn handle_script_command<ES: Sink<cassette::CassetteEvent>>(&mut self, sub_command: EmulatorScriptCommand, cassette_event_sink: &mut ES) {
+        match sub_command {
+            EmulatorScriptCommand::Run { file } => {
+                let file_path = EmulatorLogicCore::get_config_relative_path(&self.config_system, &file);
+                let script = self.script.get_or_insert_with(scripting::Script::new);
+                match script.run(&file_path, &mut self.machine) {
+                    Ok(commands) => {
+                        info!("Ran the script `{}'.", file);
+                        self.script_commands(commands, cassette_event_sink);
+                    },
+                    Err(error) => {
+                        error!("The script `{}' failed: {}.", file, error);
+                    },
+                }
+            },
+            EmulatorScriptCommand::Eval { code } => {
+                let script = self.script.get_or_insert_with(scripting::Script::new);
+                match script.eval(&code, &mut self.machine) {
+                    Ok((value, commands)) => {
+                        if let Some(value) = value {
+                            info!("{}", value);
+                        }
+                        self.script_commands(commands, cassette_event_sink);
+                    },
+                    Err(error) => {
+                        error!("The script failed: {}.", error);
+                    },
+                }
+            },
+            EmulatorScriptCommand::Stop => {
+                if self.script.take().is_some() {
+                    info!("The script was stopped, along with its breakpoints and callbacks.");
+                } else {
+                    error!("There's no script running.");
+                }
+            },
+        }
+    }
+    #[cfg(not(feature = "scripting"))]
+    fn handle_script_command<ES: Sink<cassette::CassetteEvent>>(&mut self, sub_command: EmulatorScriptCommand, _cassette_event_sink: &mut ES) {
+        match sub_command {
+            EmulatorScriptCommand::Run { file } => {
+                error!("Can't run the script `{}', the scripting isn't available, the emulator has to be built with the `scripting' feature for it.", file);
+            },
+            EmulatorScriptCommand::Eval { code } => {
+                error!("Can't run `{}', the scripting isn't available, the emulator has to be built with the `scripting' feature for it.", code);
+            },
+            EmulatorScriptCommand::Stop => {
+                error!("There's no script running.");
+            },
+        }
+    }
+    // Call the callback of the breakpoint of the script at the program
+    // counter, if it has one there.  Whether the callback paused or powered
+    // off the machine, in which case the slice ends right away.  The pause
+    // is looked for among the commands of the callback, since the machine
+    // may already be paused, when advancing a frame at a time.
+    #[cfg(feature = "scripting")]
+    fn script_breakpoint<ES: Sink<cassette::CassetteEvent>>(&mut self, cassette_event_sink: &mut ES) -> bool {
+        let result = match self.script {
+            Some(ref mut script) if !self.machine.cpu.halted && script.breakpoint_at(self.machine.cpu.regs.pc) => {
+                script.breakpoint(&mut self.machine)
+            },
+            _ => { return false; },
+        };
+        let paused = match result {
+            Ok(ref commands) => { commands.iter().any(|command| matches!(command, EmulatorCommand::Pause)) },
+            Err(..) => { false },
+        };
+        let powered_on = self.powered_on;
+        self.script_callback_done(result, cassette_event_sink);
+        paused || (powered_on && !self.powered_on)
+    }
+    #[cfg(not(feature = "scripting"))]
+    fn script_breakpoint<ES: Sink<cassette::CassetteEvent>>(&mut self, _cassette_event_sink: &mut ES) -> bool {
+        false
+    }
+    #[cfg(feature = "scripting")]
+    fn script_frame<ES: Sink<cassette::CassetteEvent>>(&mut self, cassette_event_sink: &mut ES) {
+        let result = match self.script {
+            Some(ref mut script) => { script.frame(&mut self.machine) },
+            None => { return; },
+        };
+        self.script_callback_done(result, cassette_event_sink);
+    }
+    #[cfg(not(feature = "scripting"))]
+    fn script_frame<ES: Sink<cassette::CassetteEvent>>(&mut self, _cassette_event_sink: &mut ES) {
+    }
+    // A callback which fails stops the whole script, rather than failing
+    // again with every frame or pass through the breakpoint.
+    #[cfg(feature = "scripting")]
+    fn script_callback_done<ES: Sink<cassette::CassetteEvent>>(&mut self, result: Result<Vec<EmulatorCommand>, String>, cassette_event_sink: &mut ES) {
+        match result {
+            Ok(commands) => {
+                self.script_commands(commands, cassette_event_sink);
+            },
+            Err(error) => {
+                error!("The script was stopped, its callback failed: {}.", error);
+                self.script = None;
+            },
+        }
+    }
+    #[cfg(feature = "scripting")]
+    fn script_commands<ES: Sink<cassette::CassetteEvent>>(&mut self, commands: Vec<EmulatorCommand>, cassette_event_sink: &mut ES) {
+        let unattended = self.unattended;
+        self.unattended = true;
+        for command in commands {
+            self.handle_command(command, cassette_event_sink);
+        }
+        self.unattended = unattended;
+    }
+    // Find the recordings on the cassette, their positions are translated to
+    // ones on the cassette itself.
+    fn cassette_recordings(&self) -> Option<Vec<tape::Recording>> {
+        let (bytes, positions) = self.cassette_bytes()?;
+        Some(translate_recordings(tape::scan_cas(&bytes), &positions, self.cassette_length()))
+    }
+    fn cassette_length(&self) -> usize {
+        self.machine.devices.cassette.contents().map(|contents| contents.len()).unwrap_or(0)
+    }
+    fn list_cassette_index(&self) {
+        let (bytes, positions) = match self.cassette_bytes() {
+            Some(decoded) => { decoded },
+            None => { return; },
+        };
+        let found = tape::scan_cas(&bytes);
+        let mut bad_blocks = Vec::new();
+        for (index, recording) in found.iter().enumerate() {
+            for block in tape::bad_checksums(&bytes, recording) {
+                bad_blocks.push((index, block));
+            }
+        }
+        let recordings = translate_recordings(found, &positions, self.cassette_length());
+        let position = match self.machine.devices.cassette.counter() {
+            Some((position, _)) => { position },
+            None => { 0 },
+        };
+
+        info!("Recordings on the cassette:");
+        info!("");
+        info!("      {:>3}  {:>8}  {:>8}  {}", "#", "Position", "Length", "Contents");
+        for (index, recording) in recordings.iter().enumerate() {
+            let marker = if position >= recording.offset && position < recording.end { "->" } else { "  " };
+            info!("    {}{:>3}  {:>8}  {:>8}  {}", marker, index + 1, recording.offset, recording.end - recording.offset, recording.description());
+        }
+        info!("");
+        for (index, block) in &bad_blocks {
+            let address = ((bytes.get(block + 3).copied().unwrap_or(0) as u16) << 8) | (bytes.get(block + 2).copied().unwrap_or(0) as u16);
+            warn!("Recording {}: the block loading at {:04X}h (position {}) has a bad checksum.", index + 1, address, positions[*block]);
+        }
+        if !bad_blocks.is_empty() {
+            info!("");
+        }
+        info!("{} recording(s) found, use `/cassette goto <number or name>' to wind the tape to one of them.", recordings.len());
+    }
+    fn cassette_library_path(&self) -> path::PathBuf {
+        match self.config_system.config_items.paths_library_dir {
+            Some(ref dir) => { EmulatorLogicCore::get_config_relative_path(&self.config_system, dir) },
+            None => { self.config_system.config_dir_path.clone() },
+        }
+    }
+    // The cassette files in the library directory, sorted by name:
+    fn cassette_library(&self) -> Vec<(String, cassette::Format)> {
+        let library_path = self.cassette_library_path();
+        let dir_entries = match fs::read_dir(&library_path) {
+            Ok(dir_entries) => { dir_entries },
+            Err(error) => {
+                error!("Failed to read the cassette library directory `{}': {}.", library_path.display(), error);
+                return Vec::new();
+            },
+        };
+
+        let mut library = Vec::new();
+        for dir_entry in dir_entries.flatten() {
+            let file_path = dir_entry.path();
+            let format = match cassette::Format::from_path(&file_path) {
+                Some(format) => { format },
+                None => { continue; },
+            };
+            if let Some(file) = file_path.to_str() {
+                if file_path.is_file() {
+                    library.push((file.to_owned(), format));
+                }
+            }
+        }
+        library.sort_by(|a, b| a.0.cmp(&b.0));
+        library
+    }
+    fn list_cassette_library(&self) {
+        let library = self.cassette_library();
+
+        info!("Cassettes in `{}':", self.cassette_library_path().display());
+        info!("");
+        for (index, (file, format)) in library.iter().enumerate() {
+            let file_name = path::Path::new(file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let contents = file_picker::cassette_contents(path::Path::new(file), *format);
+
+            let format_name = format!("{:?}", format);
+            match contents.split_first() {
+                Some((first, rest)) => {
+                    info!("    {:>3}  {:<20}  {}  {}", index + 1, file_name, format_name, first);
+                    for description in rest {
+                        info!("    {:>3}  {:<20}  {}  {}", "", "", "   ", description);
+                    }
+                },
+                None => {
+                    info!("    {:>3}  {:<20}  {}  {}", index + 1, file_name, format_name, "(empty)");
+                },
+            }
+        }
+        info!("");
+        info!("{} cassette(s) found, use `/cassette library <number>' to insert one of them.", library.len());
+    }
+    fn update_debug_window(&mut self) {
+        let lines = sdl_debug::describe_machine(&mut self.machine, self.debug_memory);
+        if self.debug_panel {
+            self.status_tx.send(EmulatorStatus::DebugState(lines.clone())).unwrap();
+        }
+        if self.debug_window {
+            self.video_cmd_tx.send(VideoCommand::ShowDebugState(lines)).unwrap();
+        }
+        self.debug_updated = time::Instant::now();
+    }
+    // Describe the registers, the speed, the interrupts and the media of the
+    // machine, for the status pane of the curses-based interface.
+    fn update_status_pane(&mut self) {
+        let cpu = &self.machine.cpu;
+        let regs = &cpu.regs;
+        let af = ((regs.a as u16) << 8) | (regs.flags.to_byte() as u16);
+        let int_mode = match cpu.im {
+            cpu::InterruptMode::Mode0         => { "0" },
+            cpu::InterruptMode::Mode1         => { "1" },
+            cpu::InterruptMode::Mode2         => { "2" },
+            cpu::InterruptMode::ModeUndefined => { "?" },
+        };
+        let speed = match self.speed {
+            Some(speed) if self.warp => { format!("{}% (warp)", speed) },
+            Some(speed)              => { format!("{}%", speed) },
+            None                     => { "-".to_owned() },
+        };
+        let mut lines = vec![
+            format!("PC {:04X}  SP {:04X}  IX {:04X}  IY {:04X}  I {:02X}  R {:02X}", regs.pc, regs.sp, regs.ix, regs.iy, regs.i, regs.r),
+            format!("AF {:04X}  BC {:04X}  DE {:04X}  HL {:04X}  FLAGS {}", af, regs.bc, regs.de, regs.hl, sdl_debug::flags_string(&regs.flags)),
+            format!("Speed {}  Interrupts {}, IM {}, IFF2 {}  ROM {}  Clock {}", speed, if cpu.iff1 { "enabled" } else { "disabled" }, int_mode, cpu.iff2 as u8, self.selected_rom,
+                    self.cached_clock.map(util::clock_text).unwrap_or_else(|| "-".to_owned())),
+        ];
+
+        let mut media = match (&self.config_system.config_items.cassette_file, self.cached_cas_counter) {
+            (Some(file), Some((position, length))) => {
+                let format = match self.config_system.config_items.cassette_file_format {
+                    cassette::Format::CAS => { "CAS" },
+                    cassette::Format::CPT => { "CPT" },
+                    cassette::Format::WAV => { "WAV" },
+                };
+                format!("Cassette {} ({}) {}/{}", file, format, position, length)
+            },
+            _ => { "Cassette none".to_owned() },
+        };
+        match self.cached_cas_motor {
+            (true,  false) => { media.push_str(", motor on"); },
+            (true,  true)  => { media.push_str(", motor forced on"); },
+            (false, true)  => { media.push_str(", motor forced off"); },
+            (false, false) => { },
+        }
+        if let Some((_, file_path)) = &self.audio_recording {
+            media.push_str(&format!("  Recording `{}'", file_path.display()));
+        }
+        if let Some(recording) = &self.video_recording {
+            media.push_str(&format!("  Recording `{}'", recording.file_path.display()));
+        }
+        lines.push(media);
+
+        self.status_tx.send(EmulatorStatus::StatusPane(lines)).unwrap();
+        self.status_updated = time::Instant::now();
+    }
+    // Report the speed of the emulation since the last report, along with
+    // the state of the cassette, to the status overlay, and the speed and
+    // the frame rate to the status bar of the user interface.
+    fn update_overlay_status(&mut self) {
+        let elapsed_ns = self.overlay_updated.elapsed().as_nanos();
+        let speed = if self.powered_on && !self.paused && elapsed_ns > 0 {
+            Some(((self.overlay_cycles as u128 * machine::NS_PER_CPU_CYCLE as u128 * 100) / elapsed_ns) as u32)
+        } else {
+            None
+        };
+        self.speed = speed;
+        let (tape_motor, _) = self.cached_cas_motor;
+        self.video_cmd_tx.send(VideoCommand::SetMachineStatus(sdl_video::MachineStatus {
+            speed,
+            warp:         self.warp,
+            tape_counter: self.cached_cas_counter,
+            tape_motor,
+        })).unwrap();
+        if self.cached_performance != (speed, self.frame_rate) {
+            self.cached_performance = (speed, self.frame_rate);
+            self.status_tx.send(EmulatorStatus::Performance { speed, frame_rate: self.frame_rate }).unwrap();
+        }
+        self.overlay_cycles = 0;
+        self.overlay_updated = time::Instant::now();
+    }
+    fn set_video_mode(&mut self, force_fallback: bool) -> bool {
+        let (bg_color, fg_color) = EmulatorLogicCore::get_screen_colors(&self.config_system);
         self.video_cmd_tx.send(VideoCommand::SetVideoMode {
             windowed_res:          self.config_system.config_items.video_windowed_resolution,
             fullscr_res:           self.config_system.config_items.video_fullscreen_resolution,
             desktop_fullscr_mode:  self.config_system.config_items.video_desktop_fullscreen_mode,
-            use_hw_accel:          self.config_system.config_items.video_use_hw_accel && !force_hw_accel_off,
+            use_hw_accel:          self.config_system.config_items.video_use_hw_accel && !force_fallback,
             use_vsync:             self.config_system.config_items.video_use_vsync,
-            bg_color:              self.config_system.config_items.video_bg_color,
-            fg_color:              self.config_system.config_items.video_fg_color,
-            cg_num:                self.config_system.config_items.video_character_generator,
+            renderer:              if force_fallback { sdl_video::Renderer::Sdl } else { self.config_system.config_items.video_renderer },
+            bg_color,
+            fg_color,
+            font:                  EmulatorLogicCore::get_character_generator_font(&self.config_system),
         }).unwrap();
 
-        let status = self.video_status_rx.recv().unwrap();
-        match status {
-            VideoStatus::Created => {
-                self.status_tx.send(EmulatorStatus::VideoThreadCreated).unwrap();
-                panic!("Unexpected creation of the SDL2 front-end thread");
-            },
-            VideoStatus::Destroyed => {
-                self.status_tx.send(EmulatorStatus::VideoThreadDestroyed).unwrap();
-                panic!("Unexpected termination of the SDL2 front-end thread");
-            },
-            VideoStatus::ModeSetStatus(status) => {
-                status
-            },
+        // The frame rate may still come in from before the mode change.
+        loop {
+            let status = self.video_status_rx.recv().unwrap();
+            match status {
+                VideoStatus::Created => {
+                    self.status_tx.send(EmulatorStatus::VideoThreadCreated).unwrap();
+                    panic!("Unexpected creation of the SDL2 front-end thread");
+                },
+                VideoStatus::Destroyed => {
+                    self.status_tx.send(EmulatorStatus::VideoThreadDestroyed).unwrap();
+                    panic!("Unexpected termination of the SDL2 front-end thread");
+                },
+                VideoStatus::ModeSetStatus(status) => {
+                    return status;
+                },
+                VideoStatus::FrameRate(frame_rate) => {
+                    self.frame_rate = Some(frame_rate);
+                },
+            }
         }
     }
     fn set_video_mode_with_fallback(&mut self) {
         let mut status = self.set_video_mode(false);
 
-        if !status && self.config_system.config_items.video_use_hw_accel {
+        if !status && (self.config_system.config_items.video_use_hw_accel ||
+                       self.config_system.config_items.video_renderer == sdl_video::Renderer::OpenGl) {
 
             warn!("Falling back to software rendering.");
             status = self.set_video_mode(true);
@@ -622,14 +3088,39 @@ impl EmulatorLogicCore {
             VideoStatus::ModeSetStatus(..) => {
                 panic!("Received unexpected ModeSetStatus() message from video thread");
             },
+            VideoStatus::FrameRate(..) => {
+                panic!("Received unexpected FrameRate() message from video thread");
+            },
         }
 
         let cycles_per_keypress = (machine::CPU_HZ * self.config_system.config_items.keyboard_ms_per_keypress) / 1_000;
 
         self.video_cmd_tx.send(VideoCommand::SetCyclesPerKeypress(cycles_per_keypress)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetKeyMapping(self.config_system.config_items.keyboard_mapping)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetKeyBindings(self.config_system.config_items.keyboard_key_bindings.clone())).unwrap();
+        self.update_key_profile();
+        self.update_key_repeat();
+        self.video_cmd_tx.send(VideoCommand::SetControllerBindings(self.config_system.config_items.controller_bindings.clone())).unwrap();
+        self.update_sound();
+        self.video_cmd_tx.send(VideoCommand::SetFullscreen(self.config_system.config_items.video_fullscreen)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetScaling(self.config_system.config_items.video_scaling)).unwrap();
+        self.update_crt_effects();
+        self.video_cmd_tx.send(VideoCommand::SetFrameSkip(self.config_system.config_items.video_frame_skip)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetOverlay(self.config_system.config_items.video_overlay)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetNotifications(self.config_system.config_items.video_notifications)).unwrap();
+        self.video_cmd_tx.send(VideoCommand::SetTextGraphics(self.config_system.config_items.video_text_graphics)).unwrap();
         self.set_video_mode_with_fallback();
         self.have_video_thread = true;
     }
+    fn update_crt_effects(&mut self) {
+        let config_items = &self.config_system.config_items;
+        self.video_cmd_tx.send(VideoCommand::SetCrtEffects(sdl_video::CrtEffects {
+            scanlines:   config_items.video_crt_scanlines,
+            mask:        config_items.video_crt_mask,
+            curvature:   config_items.video_crt_curvature,
+            persistence: config_items.video_crt_persistence,
+        })).unwrap();
+    }
     fn stop_video_thread(&mut self) {
 
         let started_with_video_thread = self.have_video_thread;
@@ -662,7 +3153,7 @@ impl EmulatorLogicCore {
             self.status_tx.send(EmulatorStatus::VideoThreadDestroyed).unwrap();
         }
     }
-    pub fn check_for_destroy_status(&self, status: VideoStatus) -> bool {
+    pub fn check_for_destroy_status(&mut self, status: VideoStatus) -> bool {
         match status {
             VideoStatus::Created => {
                 warn!("Received unexpected Created message from video thread.");
@@ -676,6 +3167,10 @@ impl EmulatorLogicCore {
                 warn!("Received unexpected ModeSetStatus() message from video thread.");
                 false
             },
+            VideoStatus::FrameRate(frame_rate) => {
+                self.frame_rate = Some(frame_rate);
+                false
+            },
         }
     }
     fn handle_cas_event(&mut self, event: cassette::CassetteEvent) {
@@ -685,7 +3180,10 @@ impl EmulatorLogicCore {
             cassette::CassetteEvent::RecordingStarted => {
             },
             cassette::CassetteEvent::MotorStopped(pos) => {
-                match self.config_system.change_config_entry("cassette_file_offset", format!("= {}", pos).as_str()) {
+                if let Some(keys) = self.cas_autorun_keys.take() {
+                    self.type_text(&keys);
+                }
+                match self.change_cassette_entry("cassette_file_offset", format!("= {}", pos).as_str()) {
                     Err(error) => {
                         info!("Failed to set the cassette file offset in the config system: {}.", error);
                     },
@@ -714,17 +3212,29 @@ impl EmulatorLogicCore {
                                                 // consistent video frame generation.
         emulated_cycles = 0;
 
+        // The cassette left in the drive is attached from the start.
+        if let Some(cassette_path) = EmulatorLogicCore::get_cassette_path(&self.config_system) {
+            self.attach_title(&cassette_path, true);
+        }
+        if let Some(startup_media) = self.startup_media.take() {
+            self.attach_startup_media(startup_media);
+        }
         while !self.exit_request {
             // Execute as many machine cycles as we should've executed on the
             // last frame.
-            frame_cycles = last_frame_ns / machine::NS_PER_CPU_CYCLE;
-            residual_ns  = last_frame_ns % machine::NS_PER_CPU_CYCLE;
+            // With a speed limit other than the real speed, the slice
+            // stands for a longer or a shorter time of the emulated machine.
+            let percent = self.current_speed_limit().unwrap_or(100) as u64;
+            let slice_ns = last_frame_ns as u64 * percent / 100;
+            frame_cycles = (slice_ns / machine::NS_PER_CPU_CYCLE as u64) as u32;
+            residual_ns  = ((slice_ns % machine::NS_PER_CPU_CYCLE as u64) * 100 / percent) as u32;
 
             for command in cmd_rx.try_iter() {
                 self.handle_command(command, &mut cassette_event_sink);
             }
             if self.have_video_thread {
-                for status in self.video_status_rx.try_iter() {
+                let statuses: Vec<VideoStatus> = self.video_status_rx.try_iter().collect();
+                for status in statuses {
                     let hung_up = self.check_for_destroy_status(status);
                     self.have_video_thread = !hung_up;
                 }
@@ -732,14 +3242,78 @@ impl EmulatorLogicCore {
             for kb_event in kb_rcv.try_iter() {
                 self.machine.devices.keyboard.add_keyboard_event(kb_event);
             }
+            // This slice makes up for the time since the previous one began.
+            let slice_start = frame_begin.unwrap().checked_sub(time::Duration::new(0, last_frame_ns)).unwrap_or(frame_begin.unwrap());
+            self.machine.devices.keyboard.begin_slice(slice_start);
             for cas_event in cassette_event_sink.vec.drain(..) {
                 self.handle_cas_event(cas_event);
             }
-            if self.powered_on && !self.paused {
+            if self.powered_on && !self.paused && (self.turbo || self.warp || self.current_speed_limit().is_none()) {
+                // With the turbo key held, the warp on, or without a speed
+                // limit, the machine runs as fast as it can for the whole
+                // slice, rather than for as long as the previous one took,
+                // which leaves no time to nap.
+                let slice = time::Duration::new(0, machine::NS_PER_FRAME/3);
+                while self.powered_on && !self.paused && frame_begin.unwrap().elapsed() < slice {
+                    for _ in 0..TURBO_STEPS_PER_CHECK {
+                        let mut frame_sink = RecordingSink { sink: &mut video_frame_sink, shared_frame: &self.shared_frame, recording: &mut self.video_recording };
+                        self.overlay_cycles += self.machine.step(&mut cassette_event_sink, &mut frame_sink) as u64;
+                        if self.script_breakpoint(&mut cassette_event_sink) {
+                            break;
+                        }
+                    }
+                }
+                emulated_cycles = 0;
+            } else if self.powered_on && !self.paused {
                 while emulated_cycles < frame_cycles {
-                    emulated_cycles += self.machine.step(&mut cassette_event_sink, &mut video_frame_sink);
+                    let mut frame_sink = RecordingSink { sink: &mut video_frame_sink, shared_frame: &self.shared_frame, recording: &mut self.video_recording };
+                    emulated_cycles += self.machine.step(&mut cassette_event_sink, &mut frame_sink);
+                    if self.script_breakpoint(&mut cassette_event_sink) {
+                        emulated_cycles = emulated_cycles.max(frame_cycles);
+                    }
                 }
                 emulated_cycles -= frame_cycles;
+                self.overlay_cycles += frame_cycles as u64;
+            } else if self.powered_on && self.frame_advance > 0 {
+                // Paused, with frames to advance, each slice runs the machine
+                // through the next whole frame, unless a breakpoint of the
+                // script stops it before that.  When it was paused in the
+                // middle of a frame, the rest of that one is run first.
+                self.frame_advance -= 1;
+                let frames_needed = if self.frame_end { 1 } else { 2 };
+                let mut frames = 0;
+                self.frame_end = true;
+                while frames < frames_needed {
+                    let mut frame_sink = FrameCountingSink { sink: RecordingSink { sink: &mut video_frame_sink, shared_frame: &self.shared_frame, recording: &mut self.video_recording }, frames: &mut frames };
+                    self.machine.step(&mut cassette_event_sink, &mut frame_sink);
+                    if self.script_breakpoint(&mut cassette_event_sink) {
+                        self.frame_advance = 0;
+                        self.frame_end = false;
+                        break;
+                    }
+                }
+                emulated_cycles = 0;
+            }
+            if self.powered_on && !self.paused {
+                self.script_frame(&mut cassette_event_sink);
+            }
+            self.check_input_replay();
+            if self.autostart.is_some() {
+                self.check_autostart();
+            }
+            if self.machine.devices.sound.enabled() {
+                let samples = self.machine.devices.sound.take_samples();
+                if !samples.is_empty() {
+                    self.record_samples(&samples);
+
+                    // The sound would pile up faster than it's played with
+                    // the turbo or the warp, or run short with a slower speed
+                    // limit, so it's left out unless the machine runs at its
+                    // speed.
+                    if self.config_system.config_items.audio_enabled && !self.turbo && !self.warp && self.current_speed_limit() == Some(100) {
+                        video_frame_sink.push(samples);
+                    }
+                }
             }
             if self.have_video_thread && video_frame_sink.hung_up {
                 self.have_video_thread = false;
@@ -756,16 +3330,54 @@ impl EmulatorLogicCore {
                 }
                 self.cached_cpu_halted = self.machine.cpu.halted;
             }
+            if self.cached_cas_counter != self.machine.devices.cassette.counter() {
+                self.cached_cas_counter = self.machine.devices.cassette.counter();
+                self.status_tx.send(EmulatorStatus::CassetteCounter(self.cached_cas_counter)).unwrap();
+            }
+            let clock = self.cycles_since_power_on().map(|cycles| cycles / machine::CPU_HZ as u64);
+            if self.cached_clock != clock {
+                self.cached_clock = clock;
+                self.status_tx.send(EmulatorStatus::Clock(clock)).unwrap();
+            }
+            if self.cached_cas_motor != self.cassette_motor_state() {
+                self.cached_cas_motor = self.cassette_motor_state();
+                let (running, forced) = self.cached_cas_motor;
+                self.status_tx.send(EmulatorStatus::CassetteMotor { running, forced }).unwrap();
+            }
+            if (self.debug_window || self.debug_panel) && self.debug_updated.elapsed() >= sdl_debug::UPDATE_INTERVAL {
+                self.update_debug_window();
+            }
+            if self.status_pane && self.status_updated.elapsed() >= sdl_debug::UPDATE_INTERVAL {
+                self.update_status_pane();
+            }
+            if self.overlay_updated.elapsed() >= sdl_video::OVERLAY_UPDATE_INTERVAL {
+                self.update_overlay_status();
+            }
+            if self.config_checked.elapsed() >= proj_config::WATCH_INTERVAL {
+                self.check_config_file();
+            }
 
             frame_end = Some(time::Instant::now());
             let mut frame_duration = frame_end.unwrap().duration_since(frame_begin.unwrap());
 
-            // If we have time to spare, take a nap.
+            // If we have time to spare, take a nap, which a key cuts short,
+            // so that it's applied right away, with the next slice making up
+            // for the time of the nap that passed.
             let frame_dur_ns = frame_duration.subsec_nanos();
             if frame_duration.as_secs() == 0 &&
                 frame_dur_ns < machine::NS_PER_FRAME/3 {
 
-                thread::sleep(time::Duration::new(0, machine::NS_PER_FRAME/3 - frame_dur_ns));
+                let nap = time::Duration::new(0, machine::NS_PER_FRAME/3 - frame_dur_ns);
+                match kb_rcv.recv_timeout(nap) {
+                    Ok(kb_event) => {
+                        self.machine.devices.keyboard.add_keyboard_event(kb_event);
+                    },
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                    },
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        thread::sleep(nap);
+                    },
+                }
                 frame_end = Some(time::Instant::now());
                 frame_duration = frame_end.unwrap().duration_since(frame_begin.unwrap());
             }
@@ -787,19 +3399,168 @@ impl EmulatorLogicCore {
     }
 }
 
+// Move the positions of recordings found in decoded cassette bytes to where
+// the bytes are on the cassette, anything past the decoded bytes reaches the
+// end of the cassette.
+fn translate_recordings(recordings: Vec<tape::Recording>, positions: &[usize], length: usize) -> Vec<tape::Recording> {
+    let translate = |position: usize| { positions.get(position).copied().unwrap_or(length) };
+
+    recordings.into_iter().map(|recording| {
+        tape::Recording {
+            offset: translate(recording.offset),
+            data:   translate(recording.data),
+            end:    translate(recording.end),
+            kind:   recording.kind,
+        }
+    }).collect()
+}
+
+// A recording of the screen into an animated GIF file.
+//
+// Every `frame_rate'-th of the emulated video frames is drawn into the
+// recording, they're counted in emulated time, so the recording plays back
+// at the speed of the machine, even if the emulator falls behind.
+struct VideoRecording {
+    writer:     gif::GifWriter,
+    file_path:  path::PathBuf,
+    font:       Box<[u8]>,
+    frame_rate: u32,
+    max_frames: u32,  // In emulated frames, 0 for no limit.
+    frames:     u32,  // Emulated frames so far.
+    phase:      u32,
+    captured:   u32,  // Frames drawn into the recording.
+}
+
+impl VideoRecording {
+    // Returns true once the recording reached its length limit.
+    fn add_frame(&mut self, frame: &video::VideoFrame) -> io::Result<bool> {
+        self.frames += 1;
+        self.phase += self.frame_rate;
+        if self.phase >= machine::FRAME_RATE {
+            self.phase -= machine::FRAME_RATE;
+            let time = (self.captured * 100) / self.frame_rate;
+            self.writer.add_frame(&frame.rasterize(&self.font), time)?;
+            self.captured += 1;
+        }
+        Ok(self.max_frames != 0 && self.frames >= self.max_frames)
+    }
+    fn finish(self) {
+        let time = (self.captured * 100) / self.frame_rate;
+        match self.writer.finish(time) {
+            Ok(..) => {
+                info!("Recorded {}.{} seconds of the screen into `{}'.", time / 100, (time % 100) / 10, self.file_path.display());
+            },
+            Err(error) => {
+                error!("Failed to finish writing `{}': {}.", self.file_path.display(), error);
+            },
+        }
+    }
+}
+
+// Puts the video frames into the shared frame, and adds them to the screen
+// recording, if there is one.
+struct RecordingSink<'a, 'b> {
+    sink:         &'a mut MpscSenderSink<'b, VideoCommand>,
+    shared_frame: &'a video::SharedFrame,
+    recording:    &'a mut Option<VideoRecording>,
+}
+
+impl Sink<video::VideoFrame> for RecordingSink<'_, '_> {
+    fn push(&mut self, value: video::VideoFrame) {
+        let result = match self.recording {
+            Some(ref mut recording) => { recording.add_frame(&value) },
+            None => { Ok(false) },
+        };
+        match result {
+            Ok(false) => { },
+            Ok(true) => {
+                info!("The screen recording reached its length limit.");
+                self.recording.take().unwrap().finish();
+            },
+            Err(error) => {
+                warn!("Stopping the screen recording, {}.", error);
+                self.recording.take().unwrap().finish();
+            },
+        }
+        if self.shared_frame.put(value) {
+            self.sink.frame_ready();
+        }
+    }
+}
+
+// Counts the frames passed on, for running the machine a frame at a time.
+struct FrameCountingSink<'a, S: Sink<video::VideoFrame>> {
+    sink:   S,
+    frames: &'a mut u32,
+}
+
+impl<S: Sink<video::VideoFrame>> Sink<video::VideoFrame> for FrameCountingSink<'_, S> {
+    fn push(&mut self, value: video::VideoFrame) {
+        self.sink.push(value);
+        *self.frames += 1;
+    }
+}
+
 impl Drop for EmulatorLogicCore {
     fn drop(&mut self) {
+        self.finish_audio_recording();
+        if let Some(recording) = self.video_recording.take() {
+            recording.finish();
+        }
         self.stop_video_thread();
         self.status_tx.send(EmulatorStatus::Destroyed).unwrap();
     }
 }
 
+// What the window is drawn with, see sdl_video::Renderer.
+enum WindowScreen {
+    Canvas(sdl2::render::Canvas<sdl2::video::Window>),
+    #[cfg(feature = "opengl")]
+    OpenGl(Box<gl_video::GlScreen>),
+}
+
+impl WindowScreen {
+    fn window(&self) -> &sdl2::video::Window {
+        match self {
+            WindowScreen::Canvas(canvas) => { canvas.window() },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => { screen.window() },
+        }
+    }
+    fn window_mut(&mut self) -> &mut sdl2::video::Window {
+        match self {
+            WindowScreen::Canvas(canvas) => { canvas.window_mut() },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => { screen.window_mut() },
+        }
+    }
+    fn clear(&mut self, color: (u8, u8, u8)) {
+        match self {
+            WindowScreen::Canvas(canvas) => {
+                let (red, green, blue) = color;
+                canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+                canvas.clear();
+            },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => { screen.clear(color); },
+        }
+    }
+    fn present(&mut self) {
+        match self {
+            WindowScreen::Canvas(canvas) => { canvas.present(); },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => { screen.present(); },
+        }
+    }
+}
+
 struct SdlWindowState {
-    canvas:          sdl2::render::Canvas<sdl2::video::Window>,
+    screen:          WindowScreen,
     windowed_res:    (u32, u32),
     fullscr_res:     (u32, u32),
     fullscreen_mode: bool,
     fscr_mode_dsktp: bool,
+    scaling:         sdl_video::Scaling,
 }
 
 pub struct EmulatorSdlFrontend {
@@ -813,7 +3574,22 @@ pub struct EmulatorSdlFrontend {
     emu_paused:      bool,
     cur_frame_used:  bool,
     current_frame:   Option<video::VideoFrame>,
+    shared_frame:    video::SharedFrame,
+    dirty_cells:     video::DirtyCells, // Changed since the last frame drawn.
     delayed_command: Option<VideoCommand>,
+    fullscreen:      bool,
+    scaling:         sdl_video::Scaling,
+    crt_effects:     sdl_video::CrtEffects,
+    frame_pacer:     sdl_video::FramePacer,
+    overlay:         sdl_video::Overlay,
+    notifications:   sdl_video::Notifications,
+    screenshot:      Option<(path::PathBuf, bool)>,
+    debug_window:    Option<sdl_debug::DebugWindow>,
+    magnifier:       Option<sdl_magnifier::MagnifierWindow>,
+    text_colors:     ((u8, u8, u8), (u8, u8, u8)),
+    text_font:       Box<[u8]>,
+
+    sdl2_audio:      sdl_audio::SdlAudio,
 
     kb_tx:           mpsc::Sender<keyboard::KeyboardQueueEntry>,
     lc_cmd_tx:       mpsc::Sender<EmulatorCommand>,
@@ -821,7 +3597,7 @@ pub struct EmulatorSdlFrontend {
 }
 
 impl EmulatorSdlFrontend {
-    pub fn new(kb_tx: mpsc::Sender<keyboard::KeyboardQueueEntry>, lc_cmd_tx: mpsc::Sender<EmulatorCommand>, status_tx: mpsc::Sender<VideoStatus>) -> EmulatorSdlFrontend {
+    pub fn new(kb_tx: mpsc::Sender<keyboard::KeyboardQueueEntry>, lc_cmd_tx: mpsc::Sender<EmulatorCommand>, status_tx: mpsc::Sender<VideoStatus>, shared_frame: video::SharedFrame) -> EmulatorSdlFrontend {
 
         let main_ctxt = match sdl2::init() {
             Ok(context) => { context },
@@ -841,6 +3617,22 @@ impl EmulatorSdlFrontend {
                 panic!("Failed to initialize the SDL2 event pump: {}", error);
             },
         };
+        // The emulator works without game controllers, if their support
+        // isn't available.
+        let controller_ctxt = match main_ctxt.game_controller() {
+            Ok(context) => { Some(context) },
+            Err(error) => {
+                warn!("Failed to initialize the SDL2 game controller subsystem, game controllers won't work: {}.", error);
+                None
+            },
+        };
+        let timer_ctxt = match main_ctxt.timer() {
+            Ok(context) => { Some(context) },
+            Err(error) => {
+                warn!("Failed to initialize the SDL2 timer subsystem, the keys will be timed less accurately: {}.", error);
+                None
+            },
+        };
         main_ctxt.mouse().show_cursor(false);
         status_tx.send(VideoStatus::Created).unwrap();
 
@@ -848,12 +3640,26 @@ impl EmulatorSdlFrontend {
             sdl2_main_ctxt:  main_ctxt,
             sdl2_video_ctxt: video_ctxt,
             sdl2_event_pump: event_pump,
-            sdl2_keyboard:   sdl_keyboard::SdlKeyboard::new(0),
+            sdl2_keyboard:   sdl_keyboard::SdlKeyboard::new(0, controller_ctxt, timer_ctxt),
             frame_draw:      false,
             emu_paused:      false,
             cur_frame_used:  false,
             current_frame:   None,
+            shared_frame,
+            dirty_cells:     video::DirtyCells::default(),
             delayed_command: None,
+            fullscreen:      false,
+            scaling:         sdl_video::Scaling::Aspect,
+            crt_effects:     sdl_video::CrtEffects::default(),
+            frame_pacer:     sdl_video::FramePacer::new(sdl_video::FrameSkip::Auto),
+            overlay:         sdl_video::Overlay::new(),
+            notifications:   sdl_video::Notifications::new(),
+            screenshot:      None,
+            debug_window:    None,
+            magnifier:       None,
+            text_colors:     ((0x00, 0x00, 0x00), (0xFF, 0xFF, 0xFF)),
+            text_font:       sdl_video::font_for_cg_num(1).into(),
+            sdl2_audio:      sdl_audio::SdlAudio::new(),
             kb_tx,
             lc_cmd_tx,
             status_tx,
@@ -864,9 +3670,22 @@ impl EmulatorSdlFrontend {
                         fullscr_res:           (u32, u32),
                         desktop_fullscr_mode:  bool,
                         use_hw_accel:          bool,
-                        use_vsync:             bool) -> Option<(SdlWindowState, sdl2::render::TextureCreator<sdl2::video::WindowContext>)> {
+                        use_vsync:             bool,
+                        renderer:              sdl_video::Renderer) -> Option<(SdlWindowState, Option<sdl2::render::TextureCreator<sdl2::video::WindowContext>>)> {
 
         let (width, height) = windowed_res;
+        if renderer == sdl_video::Renderer::OpenGl {
+            let screen = self.create_gl_screen(width, height, use_vsync)?;
+            return Some((SdlWindowState {
+                screen,
+                windowed_res,
+                fullscr_res,
+                fullscreen_mode: false,
+                fscr_mode_dsktp: desktop_fullscr_mode,
+                scaling:         self.scaling,
+            }, None));
+        }
+
         let mut window_builder = self.sdl2_video_ctxt.window("TRS-80 Model I Emulator", width, height);
 
         let window = match window_builder.position_centered().build() {
@@ -896,101 +3715,591 @@ impl EmulatorSdlFrontend {
                 return None;
             },
         };
-        match canvas.set_logical_size(video::SCREEN_WIDTH, video::SCREEN_HEIGHT) {
+        match sdl_video::set_scaling(&mut canvas, self.scaling) {
             Ok(..) => { () },
             Err(error) => {
-                error!("Failed to set the SDL2 renderer's logical size: {}.", error);
+                error!("Failed to set up the scaling of the SDL2 renderer: {}.", error);
                 return None;
             },
         }
 
-        let texture_creator = canvas.texture_creator();
+        let texture_creator = canvas.texture_creator();
+
+        Some((SdlWindowState {
+            screen:          WindowScreen::Canvas(canvas),
+            windowed_res,
+            fullscr_res,
+            fullscreen_mode: false,
+            fscr_mode_dsktp: desktop_fullscr_mode,
+            scaling:         self.scaling,
+        }, Some(texture_creator)))
+    }
+    #[cfg(feature = "opengl")]
+    fn create_gl_screen(&self, width: u32, height: u32, use_vsync: bool) -> Option<WindowScreen> {
+        match gl_video::GlScreen::create(&self.sdl2_video_ctxt, "TRS-80 Model I Emulator", width, height, use_vsync) {
+            Ok(screen) => { Some(WindowScreen::OpenGl(Box::new(screen))) },
+            Err(error) => {
+                error!("Failed to set up the OpenGL renderer: {}.", error);
+                None
+            },
+        }
+    }
+    #[cfg(not(feature = "opengl"))]
+    fn create_gl_screen(&self, _width: u32, _height: u32, _use_vsync: bool) -> Option<WindowScreen> {
+        error!("The OpenGL renderer isn't available, the emulator has to be built with the `opengl' feature for it.");
+        None
+    }
+    fn handle_video_cmd_toplevel(&mut self, wnd_state: &mut SdlWindowState, cmd: VideoCommand, terminate_thread: &mut bool) -> bool
+    {
+        *terminate_thread = false;
+        match cmd {
+            VideoCommand::SetFrameDrawing{ enabled, emulation_paused } => {
+                self.frame_draw = enabled;
+                self.emu_paused = emulation_paused;
+                false
+            },
+            VideoCommand::FrameReady => {
+                self.take_shared_frame();
+                false
+            },
+            VideoCommand::SetCyclesPerKeypress(cycles_per_keypress) => {
+                self.sdl2_keyboard.set_cycles_per_keypress(cycles_per_keypress);
+                false
+            }
+            VideoCommand::SetKeyMapping(mapping) => {
+                self.sdl2_keyboard.set_key_mapping(mapping);
+                false
+            },
+            VideoCommand::SetKeyBindings(bindings) => {
+                self.sdl2_keyboard.set_key_bindings(&bindings);
+                false
+            },
+            VideoCommand::SetKeyProfile(bindings) => {
+                self.sdl2_keyboard.set_key_profile(&bindings);
+                false
+            },
+            VideoCommand::SetKeyRepeat(repeat) => {
+                self.sdl2_keyboard.set_key_repeat(repeat);
+                false
+            },
+            VideoCommand::SetControllerBindings(bindings) => {
+                self.sdl2_keyboard.set_controller_bindings(&bindings);
+                false
+            },
+            VideoCommand::SetSound(settings) => {
+                self.sdl2_audio.set_output(&self.sdl2_main_ctxt, settings);
+                false
+            },
+            VideoCommand::PlaySamples(samples) => {
+                self.sdl2_audio.play(samples);
+                false
+            },
+            VideoCommand::ReportSoundStatistics => {
+                self.sdl2_audio.report_statistics();
+                false
+            },
+            VideoCommand::Terminate => {
+                *terminate_thread = true;
+                true
+            },
+            VideoCommand::UpdateTextures { bg_color, fg_color, font } => {
+                self.delayed_command = Some(VideoCommand::UpdateTextures { bg_color, fg_color, font });
+                true
+            },
+            VideoCommand::SetWindowedResolution((width, height)) => {
+                wnd_state.windowed_res = (width, height);
+                if !wnd_state.fullscreen_mode {
+                    let window = wnd_state.screen.window_mut();
+                    window.set_size(width, height).unwrap();
+                }
+                false
+            },
+            VideoCommand::SetFullscreenResolution((width, height), fscr_mode_dsktp) => {
+                self.handle_fullscr_res_change(wnd_state, width, height, fscr_mode_dsktp);
+                false
+            },
+            VideoCommand::SetFullscreen(fullscreen) => {
+                self.fullscreen = fullscreen;
+                EmulatorSdlFrontend::set_fullscreen_mode(wnd_state, fullscreen);
+                false
+            },
+            VideoCommand::SetScaling(scaling) => {
+                self.scaling = scaling;
+                EmulatorSdlFrontend::set_scaling_mode(wnd_state, scaling);
+                false
+            },
+            VideoCommand::SetCrtEffects(effects) => {
+                self.crt_effects = effects;
+                false
+            },
+            VideoCommand::SetFrameSkip(frame_skip) => {
+                self.frame_pacer.set_frame_skip(frame_skip);
+                false
+            },
+            VideoCommand::SetOverlay(level) => {
+                self.overlay.set_level(level);
+                self.cur_frame_used = false;
+                false
+            },
+            VideoCommand::SetMachineStatus(status) => {
+                self.overlay.set_status(status);
+                false
+            },
+            VideoCommand::SetNotifications(enabled) => {
+                self.notifications.set_enabled(enabled);
+                false
+            },
+            VideoCommand::Notify(text) => {
+                self.notifications.push(&text);
+                self.cur_frame_used = false;
+                false
+            },
+            VideoCommand::SetClipboardText(text) => {
+                self.set_clipboard_text(&text);
+                false
+            },
+            VideoCommand::PasteClipboard => {
+                self.paste_clipboard();
+                false
+            },
+            VideoCommand::SetTextGraphics(..) => {
+                false
+            },
+            VideoCommand::SetDebugWindow(enabled) => {
+                self.set_debug_window(enabled);
+                false
+            },
+            VideoCommand::ShowDebugState(lines) => {
+                self.show_debug_state(&lines);
+                false
+            },
+            VideoCommand::SetMagnifier(enabled) => {
+                self.set_magnifier(enabled);
+                false
+            },
+            VideoCommand::ShowMenu(menu) => {
+                self.show_menu(menu);
+                self.cur_frame_used = false;
+                false
+            },
+            VideoCommand::TakeScreenshot { path, scaled } => {
+                // Draw the current frame again, even if the machine is
+                // paused, so that the screenshot is taken right away.
+                self.screenshot = Some((path, scaled));
+                self.cur_frame_used = false;
+                false
+            },
+            VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer, bg_color, fg_color, font } => {
+
+                self.delayed_command = Some(VideoCommand::SetVideoMode{ windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer, bg_color, fg_color, font });
+                true
+            },
+        }
+    }
+    // The characters changed by the frames which were replaced without
+    // being drawn are added up, so that they're redrawn with the next one.
+    fn take_shared_frame(&mut self) {
+        if let Some(frame) = self.shared_frame.take() {
+            video::add_dirty(&mut self.dirty_cells, &frame.dirty);
+            self.current_frame = Some(frame);
+            self.cur_frame_used = false;
+        }
+    }
+    fn set_clipboard_text(&self, text: &str) {
+        match self.sdl2_video_ctxt.clipboard().set_clipboard_text(text) {
+            Ok(..) => {
+                info!("Copied the text on the screen into the clipboard.");
+            },
+            Err(error) => {
+                error!("Failed to copy the text on the screen into the clipboard: {}.", error);
+            },
+        }
+    }
+    fn paste_clipboard(&self) {
+        match self.sdl2_video_ctxt.clipboard().clipboard_text() {
+            Ok(text) => {
+                self.lc_cmd_tx.send(EmulatorCommand::PasteText(text)).unwrap();
+            },
+            Err(error) => {
+                error!("Failed to read the text in the clipboard: {}.", error);
+            },
+        }
+    }
+    fn handle_sdl_events(&mut self, capture_kbd: bool) {
+
+        let mut fullscreen_toggle = false;
+        let mut keyboard_toggle = false;
+        let mut closed_window = None;
+        let menu_shown = self.sdl2_keyboard.menu().is_some();
+        self.sdl2_keyboard.handle_events(&self.lc_cmd_tx, &mut self.sdl2_event_pump, &mut fullscreen_toggle, &mut keyboard_toggle, &mut closed_window, &self.kb_tx, capture_kbd);
+
+        // The logic core keeps track of the mode, so that it can be saved
+        // into the config file, and sends the switch back.
+        if fullscreen_toggle {
+            self.lc_cmd_tx.send(EmulatorCommand::SetFullscreen(None)).unwrap();
+        }
+
+        if keyboard_toggle {
+            self.sdl2_keyboard.toggle_virtual_keyboard(&self.lc_cmd_tx, &self.kb_tx);
+        }
+        if keyboard_toggle || self.sdl2_keyboard.menu().is_some() != menu_shown {
+            self.update_mouse_cursor();
+        }
+
+        for scancode in self.sdl2_keyboard.take_magnifier_keys() {
+            if let Some(magnifier) = &mut self.magnifier {
+                magnifier.handle_key(scancode);
+            }
+        }
+
+        // Closing the debugger or the magnifier window only closes that, but
+        // with either of them open, closing the emulator window doesn't make
+        // SDL2 quit by itself.
+        if let Some(window_id) = closed_window {
+            if self.debug_window.as_ref().map(|window| window.id()) == Some(window_id) {
+                self.lc_cmd_tx.send(EmulatorCommand::DebuggerCommand(EmulatorDebuggerCommand::Window { state: Some(false) })).unwrap();
+            } else if self.magnifier.as_ref().map(|window| window.id()) == Some(window_id) {
+                self.lc_cmd_tx.send(EmulatorCommand::SetMagnifier(Some(false))).unwrap();
+            } else if self.debug_window.is_some() || self.magnifier.is_some() {
+                self.lc_cmd_tx.send(EmulatorCommand::Terminate).unwrap();
+            }
+        }
+    }
+    // The mouse cursor is only shown with the on-screen keyboard or the
+    // menu.
+    fn update_mouse_cursor(&self) {
+        let shown = self.sdl2_keyboard.virtual_keyboard().shown() || self.sdl2_keyboard.menu().is_some();
+        self.sdl2_main_ctxt.mouse().show_cursor(shown);
+    }
+    fn show_menu(&mut self, menu: sdl_menu::Menu) {
+        self.sdl2_keyboard.set_menu(Some(menu));
+        self.update_mouse_cursor();
+    }
+    // The clicks in the emulator window are placed on the screen, for the
+    // menu or the on-screen keyboard, the ones in the other windows are
+    // ignored.
+    fn handle_clicks(&mut self, wnd_state: &SdlWindowState, capture_kbd: bool) {
+        let window_id = wnd_state.screen.window().id();
+        let menu_shown = self.sdl2_keyboard.menu().is_some();
+
+        for click in self.sdl2_keyboard.take_clicks() {
+            if click.window_id != window_id {
+                continue;
+            }
+            // The SDL2 renderer gives the position in the dots of the
+            // screen by itself, as it's what its logical size is set to.
+            let (x, y) = match &wnd_state.screen {
+                WindowScreen::Canvas(..) => { (click.x, click.y) },
+                #[cfg(feature = "opengl")]
+                WindowScreen::OpenGl(screen) => { screen.screen_position(click.x, click.y, wnd_state.scaling) },
+            };
+            if !self.sdl2_keyboard.click_menu(x, y, click.pressed, &self.lc_cmd_tx) {
+                self.sdl2_keyboard.click_virtual_keyboard(x, y, click.pressed, click.at, capture_kbd, &self.lc_cmd_tx, &self.kb_tx);
+            }
+        }
+        if self.sdl2_keyboard.menu().is_some() != menu_shown {
+            self.update_mouse_cursor();
+        }
+    }
+    fn set_debug_window(&mut self, enabled: bool) {
+        if !enabled {
+            self.debug_window = None;
+        } else if self.debug_window.is_none() {
+            match sdl_debug::DebugWindow::open(&self.sdl2_video_ctxt) {
+                Ok(debug_window) => {
+                    self.debug_window = Some(debug_window);
+                },
+                Err(error) => {
+                    error!("Failed to open the debugger window: {}.", error);
+                    self.lc_cmd_tx.send(EmulatorCommand::DebuggerCommand(EmulatorDebuggerCommand::Window { state: Some(false) })).unwrap();
+                },
+            }
+        }
+    }
+    fn set_magnifier(&mut self, enabled: bool) {
+        if !enabled {
+            self.magnifier = None;
+        } else if self.magnifier.is_none() {
+            match sdl_magnifier::MagnifierWindow::open(&self.sdl2_video_ctxt) {
+                Ok(magnifier) => {
+                    self.magnifier = Some(magnifier);
+                },
+                Err(error) => {
+                    error!("Failed to open the magnifier window: {}.", error);
+                    self.lc_cmd_tx.send(EmulatorCommand::SetMagnifier(Some(false))).unwrap();
+                },
+            }
+        }
+        self.sdl2_keyboard.set_magnifier_window(self.magnifier.as_ref().map(|magnifier| magnifier.id()));
+    }
+    fn draw_magnifier(magnifier: &mut Option<sdl_magnifier::MagnifierWindow>,
+                      lc_cmd_tx: &mpsc::Sender<EmulatorCommand>,
+                      frame:     &video::VideoFrame,
+                      font:      &[u8],
+                      colors:    ((u8, u8, u8), (u8, u8, u8))) {
+
+        let (bg_color, fg_color) = colors;
+        if let Some(window) = magnifier {
+            if let Err(error) = window.show(frame, font, bg_color, fg_color) {
+                error!("Failed to draw the magnifier window, closing it: {}.", error);
+                lc_cmd_tx.send(EmulatorCommand::SetMagnifier(Some(false))).unwrap();
+            }
+        }
+    }
+    fn show_debug_state(&mut self, lines: &[String]) {
+        let (bg_color, fg_color) = self.text_colors;
 
-        Some((SdlWindowState {
-            canvas,
-            windowed_res,
-            fullscr_res,
-            fullscreen_mode: false,
-            fscr_mode_dsktp: desktop_fullscr_mode,
-        }, texture_creator))
+        if let Some(debug_window) = &mut self.debug_window {
+            if let Err(error) = debug_window.show(lines, &self.text_font, bg_color, fg_color) {
+                error!("Failed to draw the debugger window: {}.", error);
+            }
+        }
     }
-    fn handle_video_cmd_toplevel(&mut self, wnd_state: &mut SdlWindowState, cmd: VideoCommand, terminate_thread: &mut bool) -> bool
-    {
-        *terminate_thread = false;
-        match cmd {
-            VideoCommand::SetFrameDrawing{ enabled, emulation_paused } => {
-                self.frame_draw = enabled;
-                self.emu_paused = emulation_paused;
-                false
+    fn render_frame(wnd_state:    &mut SdlWindowState,
+                    txt_creat:    Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
+                    screen_tex:   &mut Option<sdl_video::ScreenTextures>,
+                    frame:        &video::VideoFrame,
+                    dirty_cells:  &mut video::DirtyCells,
+                    fg_color:     (u8, u8, u8),
+                    crt_textures: &mut Option<sdl_video::CrtTextures>,
+                    crt_effects:  sdl_video::CrtEffects,
+                    screenshot:   Option<(path::PathBuf, bool)>,
+                    notifications: &mut sdl_video::Notifications) {
+
+        // The window has to be read back before the frame is presented (by
+        // the caller), its contents are undefined afterwards.
+        let scaling = wnd_state.scaling;
+        let dirty_cells = std::mem::take(dirty_cells);
+        let capture = match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                let screen_tex = screen_tex.as_mut().expect("The SDL2 renderer has no screen textures");
+                screen_tex.add_dirty(&dirty_cells);
+                let mut rendered = false;
+                if crt_effects.enabled() {
+                    if let Some(textures) = crt_textures {
+                        match sdl_video::render_crt(canvas, screen_tex, frame, fg_color, scaling, textures, crt_effects) {
+                            Ok(..) => { rendered = true; },
+                            Err(error) => {
+                                error!("Failed to apply the CRT effects, turning them off: {}.", error);
+                                *crt_textures = None;
+                            },
+                        }
+                    }
+                }
+                if !rendered {
+                    sdl_video::render(canvas, screen_tex, frame, fg_color, scaling);
+                }
+                screenshot.map(|(path, scaled)| {
+                    (path, if scaled {
+                        sdl_video::capture_window(canvas, scaling)
+                    } else {
+                        match txt_creat {
+                            Some(txt_creat) => { sdl_video::capture_native(canvas, txt_creat, screen_tex, frame, fg_color) },
+                            None => { Err("no textures to draw the screen with".to_owned()) },
+                        }
+                    })
+                })
             },
-            VideoCommand::DrawFrame(frame) => {
-                self.current_frame = Some(frame);
-                self.cur_frame_used = false;
-                false
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => {
+                screen.render(frame, scaling, crt_effects);
+                screenshot.map(|(path, scaled)| {
+                    (path, if scaled { screen.capture_window() } else { screen.capture_native(frame) })
+                })
             },
-            VideoCommand::SetCyclesPerKeypress(cycles_per_keypress) => {
-                self.sdl2_keyboard.set_cycles_per_keypress(cycles_per_keypress);
-                false
+        };
+        if let Some((path, capture)) = capture {
+            match capture.and_then(|(width, height, pixels)| png::write_rgb(&path, width, height, &pixels).map_err(|error| error.to_string())) {
+                Ok(..) => {
+                    info!("Screenshot saved into `{}'.", path.display());
+                    notifications.push(&format!("Screenshot saved into {}", path.file_name().unwrap_or_default().to_string_lossy()));
+                },
+                Err(error) => {
+                    error!("Failed to save the screenshot `{}': {}.", path.display(), error);
+                },
             }
-            VideoCommand::Terminate => {
-                *terminate_thread = true;
-                true
+        }
+    }
+    // The overlay is drawn after the frame has been rendered, so it doesn't
+    // show up in the screenshots.
+    fn draw_overlay(wnd_state: &mut SdlWindowState,
+                    txt_creat: Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
+                    overlay:   &mut sdl_video::Overlay,
+                    font:      &[u8],
+                    colors:    ((u8, u8, u8), (u8, u8, u8))) {
+
+        let lines = overlay.lines();
+        if lines.is_empty() {
+            return;
+        }
+        let (bg_color, fg_color) = colors;
+        let result = match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                match txt_creat {
+                    Some(txt_creat) => { sdl_video::render_overlay(canvas, txt_creat, &lines, font, bg_color, fg_color) },
+                    None => { Err("no textures to draw the overlay with".to_owned()) },
+                }
             },
-            VideoCommand::UpdateTextures { bg_color, fg_color, cg_num } => {
-                self.delayed_command = Some(VideoCommand::UpdateTextures { bg_color, fg_color, cg_num });
-                true
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => {
+                screen.render_overlay(&lines, wnd_state.scaling);
+                Ok(())
             },
-            VideoCommand::SetWindowedResolution((width, height)) => {
-                wnd_state.windowed_res = (width, height);
-                if !wnd_state.fullscreen_mode {
-                    let window = wnd_state.canvas.window_mut();
-                    window.set_size(width, height).unwrap();
+        };
+        if let Err(error) = result {
+            error!("Failed to draw the status overlay, turning it off: {}.", error);
+            overlay.set_level(sdl_video::OverlayLevel::Off);
+        }
+    }
+    // The on-screen keyboard is drawn over the bottom of the screen, after
+    // the overlay, for the same reason.
+    fn draw_virtual_keyboard(wnd_state: &mut SdlWindowState,
+                             txt_creat: Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
+                             keyboard:  &sdl_keyboard::SdlKeyboard,
+                             font:      &[u8],
+                             colors:    ((u8, u8, u8), (u8, u8, u8))) {
+
+        let virtual_keyboard = keyboard.virtual_keyboard();
+        if !virtual_keyboard.shown() {
+            return;
+        }
+        let (width, height, bitmap) = virtual_keyboard.rasterize(font);
+        let (x, y, area_width, area_height) = virtual_keyboard.area();
+        let (bg_color, fg_color) = colors;
+        let result = match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                match txt_creat {
+                    Some(txt_creat) => {
+                        let area = sdl2::rect::Rect::new(x, y, area_width, area_height);
+                        sdl_video::render_bitmap(canvas, txt_creat, &bitmap, width, height, area, bg_color, fg_color)
+                    },
+                    None => { Err("no textures to draw the keyboard with".to_owned()) },
                 }
-                false
             },
-            VideoCommand::SetFullscreenResolution((width, height), fscr_mode_dsktp) => {
-                self.handle_fullscr_res_change(wnd_state, width, height, fscr_mode_dsktp);
-                false
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => {
+                screen.render_bitmap_at(&bitmap, width, height, (x, y, area_width, area_height), wnd_state.scaling);
+                Ok(())
             },
-            VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, bg_color, fg_color, cg_num } => {
+        };
+        if let Err(error) = result {
+            error!("Failed to draw the on-screen keyboard: {}.", error);
+        }
+    }
+    // The menu is drawn over everything else, in the middle of the screen.
+    fn draw_menu(wnd_state: &mut SdlWindowState,
+                 txt_creat: Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
+                 keyboard:  &sdl_keyboard::SdlKeyboard,
+                 font:      &[u8],
+                 colors:    ((u8, u8, u8), (u8, u8, u8))) {
 
-                self.delayed_command = Some(VideoCommand::SetVideoMode{ windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, bg_color, fg_color, cg_num });
-                true
+        let menu = match keyboard.menu() {
+            Some(menu) => { menu },
+            None => { return; },
+        };
+        let (width, height, bitmap) = menu.rasterize(font);
+        let (x, y, area_width, area_height) = menu.area();
+        let (bg_color, fg_color) = colors;
+        let result = match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                match txt_creat {
+                    Some(txt_creat) => {
+                        let area = sdl2::rect::Rect::new(x, y, area_width, area_height);
+                        sdl_video::render_bitmap(canvas, txt_creat, &bitmap, width, height, area, bg_color, fg_color)
+                    },
+                    None => { Err("no textures to draw the menu with".to_owned()) },
+                }
+            },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => {
+                screen.render_bitmap_at(&bitmap, width, height, (x, y, area_width, area_height), wnd_state.scaling);
+                Ok(())
             },
+        };
+        if let Err(error) = result {
+            error!("Failed to draw the menu: {}.", error);
         }
     }
-    fn handle_sdl_events(&mut self, wnd_state: &mut SdlWindowState, capture_kbd: bool) {
-
-        let mut fullscreen_toggle = false;
-        self.sdl2_keyboard.handle_events(&self.lc_cmd_tx, &mut self.sdl2_event_pump, &mut fullscreen_toggle, &self.kb_tx, capture_kbd);
-
-        if fullscreen_toggle {
-            let window = wnd_state.canvas.window_mut();
-
-            if !wnd_state.fullscreen_mode {
+    // The notifications go over the bottom of the screen, but under the
+    // menu.
+    fn draw_notifications(wnd_state:     &mut SdlWindowState,
+                          txt_creat:     Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
+                          notifications: &mut sdl_video::Notifications,
+                          font:          &[u8],
+                          colors:        ((u8, u8, u8), (u8, u8, u8))) {
 
-                if !wnd_state.fscr_mode_dsktp {
-                    let (width, height) = wnd_state.fullscr_res;
-                    window.set_size(width, height).unwrap();
-                    window.set_fullscreen(sdl2::video::FullscreenType::True).unwrap();
-                } else {
-                    window.set_fullscreen(sdl2::video::FullscreenType::Desktop).unwrap();
+        let lines = notifications.lines();
+        if lines.is_empty() {
+            return;
+        }
+        let (columns, rows) = sdl_video::overlay_size(&lines);
+        let (width, height) = (columns * video::GLYPH_WIDTH, rows * video::GLYPH_HEIGHT);
+        let bitmap = sdl_video::rasterize_text(&lines, columns, rows, font);
+        let (x, y, area_width, area_height) = sdl_video::notifications_area(&lines);
+        let (bg_color, fg_color) = colors;
+        let result = match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                match txt_creat {
+                    Some(txt_creat) => {
+                        let area = sdl2::rect::Rect::new(x, y, area_width, area_height);
+                        sdl_video::render_bitmap(canvas, txt_creat, &bitmap, width, height, area, bg_color, fg_color)
+                    },
+                    None => { Err("no textures to draw the notifications with".to_owned()) },
+                }
+            },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => {
+                screen.render_bitmap_at(&bitmap, width, height, (x, y, area_width, area_height), wnd_state.scaling);
+                Ok(())
+            },
+        };
+        if let Err(error) = result {
+            error!("Failed to draw the notifications, turning them off: {}.", error);
+            notifications.set_enabled(false);
+        }
+    }
+    fn set_scaling_mode(wnd_state: &mut SdlWindowState, scaling: sdl_video::Scaling) {
+        // The OpenGL renderer scales the screen up as it draws it.
+        match &mut wnd_state.screen {
+            WindowScreen::Canvas(canvas) => {
+                if let Err(error) = sdl_video::set_scaling(canvas, scaling) {
+                    error!("Failed to change the scaling of the SDL2 renderer: {}.", error);
                 }
-                wnd_state.fullscreen_mode = true;
+            },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(..) => { },
+        }
+        wnd_state.scaling = scaling;
+    }
+    fn set_fullscreen_mode(wnd_state: &mut SdlWindowState, fullscreen: bool) {
+        if fullscreen == wnd_state.fullscreen_mode {
+            return;
+        }
+        let window = wnd_state.screen.window_mut();
 
-            } else {
-                let (width, height) = wnd_state.windowed_res;
-                window.set_fullscreen(sdl2::video::FullscreenType::Off).unwrap();
-                window.set_size(width, height).unwrap();
-                window.set_position(sdl2::video::WindowPos::Centered, sdl2::video::WindowPos::Centered);
+        if fullscreen {
 
-                wnd_state.fullscreen_mode = false;
+            if !wnd_state.fscr_mode_dsktp {
+                let (width, height) = wnd_state.fullscr_res;
+                window.set_size(width, height).unwrap();
+                window.set_fullscreen(sdl2::video::FullscreenType::True).unwrap();
+            } else {
+                window.set_fullscreen(sdl2::video::FullscreenType::Desktop).unwrap();
             }
+            wnd_state.fullscreen_mode = true;
+
+        } else {
+            let (width, height) = wnd_state.windowed_res;
+            window.set_fullscreen(sdl2::video::FullscreenType::Off).unwrap();
+            window.set_size(width, height).unwrap();
+            window.set_position(sdl2::video::WindowPos::Centered, sdl2::video::WindowPos::Centered);
+
+            wnd_state.fullscreen_mode = false;
         }
     }
     fn handle_fullscr_res_change(&mut self, wnd_state: &mut SdlWindowState, width: u32, height: u32, fscr_mode_dsktp: bool) {
 
-        let window = wnd_state.canvas.window_mut();
+        let window = wnd_state.screen.window_mut();
         if wnd_state.fullscreen_mode {
 
             if wnd_state.fscr_mode_dsktp != fscr_mode_dsktp {
@@ -1009,7 +4318,7 @@ impl EmulatorSdlFrontend {
 
             } else if !wnd_state.fscr_mode_dsktp {
 
-                let window = wnd_state.canvas.window_mut();
+                let window = wnd_state.screen.window_mut();
                 window.set_size(width, height).unwrap();
             }
         }
@@ -1019,16 +4328,43 @@ impl EmulatorSdlFrontend {
     fn run_with_textures(&mut self,
                          cmd_rx:    &mpsc::Receiver<VideoCommand>,
                          wnd_state: &mut SdlWindowState,
-                         txt_creat: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+                         txt_creat: Option<&sdl2::render::TextureCreator<sdl2::video::WindowContext>>,
                          bg_color:  (u8, u8, u8),
                          fg_color:  (u8, u8, u8),
-                         cg_num:    u32) -> bool {
+                         font:      &[u8]) -> bool {
 
-        let (narrow_glyphs, wide_glyphs) = sdl_video::generate_glyph_textures(bg_color, fg_color, cg_num, txt_creat);
+        // Only the SDL2 renderer draws the screen out of textures, the OpenGL
+        // one keeps the glyphs itself.
+        let (mut screen_textures, mut crt_textures) = match txt_creat {
+            Some(txt_creat) => {
+                let screen_textures = sdl_video::ScreenTextures::new(bg_color, fg_color, font, txt_creat);
+                let crt_textures = match sdl_video::generate_crt_textures(txt_creat) {
+                    Ok(textures) => { Some(textures) },
+                    Err(error) => {
+                        if self.crt_effects.enabled() {
+                            warn!("The CRT effects aren't available with this renderer: {}.", error);
+                        }
+                        None
+                    },
+                };
+                (Some(screen_textures), crt_textures)
+            },
+            None => {
+                (None, None)
+            },
+        };
+        match &mut wnd_state.screen {
+            WindowScreen::Canvas(..) => { },
+            #[cfg(feature = "opengl")]
+            WindowScreen::OpenGl(screen) => { screen.set_glyphs(bg_color, fg_color, font); },
+        }
+        self.text_colors = (bg_color, fg_color);
+        self.text_font = font.into();
         let mut sticky_clear = false;
 
         loop {
-            self.handle_sdl_events(wnd_state, self.frame_draw);
+            self.handle_sdl_events(self.frame_draw);
+            self.handle_clicks(wnd_state, self.frame_draw);
             if self.frame_draw {
                 for cmd in cmd_rx.try_iter() {
 
@@ -1043,7 +4379,17 @@ impl EmulatorSdlFrontend {
                 }
                 while self.frame_draw && match self.current_frame { Some(..) => { self.cur_frame_used }, None => { true } } {
 
-                    let cmd = cmd_rx.recv().unwrap();
+                    let cmd = match cmd_rx.recv_timeout(EVENT_POLL_INTERVAL) {
+                        Ok(cmd) => { cmd },
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            self.handle_sdl_events(self.frame_draw);
+                            self.handle_clicks(wnd_state, self.frame_draw);
+                            continue;
+                        },
+                        Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            panic!("Video command transmitter disconnected.");
+                        },
+                    };
 
                     let mut terminate_thread = false;
                     let exit_func = self.handle_video_cmd_toplevel(wnd_state, cmd, &mut terminate_thread);
@@ -1056,16 +4402,31 @@ impl EmulatorSdlFrontend {
                 }
                 if self.frame_draw {
 
+                    // The frame is drawn as soon as it arrives, and with
+                    // vertical synchronization, presenting it waits for the
+                    // display, so it's the display that paces the drawing.
                     match &self.current_frame {
                         Some(frame) => {
-                            sdl_video::render(&mut wnd_state.canvas, &narrow_glyphs, &wide_glyphs, frame);
+                            if self.frame_pacer.draw_next() || self.screenshot.is_some() {
+                                let draw_begin = time::Instant::now();
+                                EmulatorSdlFrontend::render_frame(wnd_state, txt_creat, &mut screen_textures, frame, &mut self.dirty_cells, fg_color, &mut crt_textures, self.crt_effects, self.screenshot.take(), &mut self.notifications);
+                                if let Some(frame_rate) = self.overlay.frame_drawn() {
+                                    self.status_tx.send(VideoStatus::FrameRate(frame_rate)).unwrap();
+                                }
+                                EmulatorSdlFrontend::draw_overlay(wnd_state, txt_creat, &mut self.overlay, &self.text_font, self.text_colors);
+                                EmulatorSdlFrontend::draw_virtual_keyboard(wnd_state, txt_creat, &self.sdl2_keyboard, &self.text_font, self.text_colors);
+                                EmulatorSdlFrontend::draw_notifications(wnd_state, txt_creat, &mut self.notifications, &self.text_font, self.text_colors);
+                                EmulatorSdlFrontend::draw_menu(wnd_state, txt_creat, &self.sdl2_keyboard, &self.text_font, self.text_colors);
+                                self.frame_pacer.frame_drawn(draw_begin.elapsed());
+                                wnd_state.screen.present();
+                                EmulatorSdlFrontend::draw_magnifier(&mut self.magnifier, &self.lc_cmd_tx, frame, &self.text_font, self.text_colors);
+                            }
                         },
                         None => {
                             // This point should be impossible to reach.
                             //
-                            let (bg_red, bg_green, bg_blue) = bg_color;
-                            wnd_state.canvas.set_draw_color(sdl2::pixels::Color::RGB(bg_red, bg_green, bg_blue));
-                            wnd_state.canvas.clear();
+                            wnd_state.screen.clear(bg_color);
+                            wnd_state.screen.present();
                         },
                     }
                     self.cur_frame_used = true;
@@ -1114,11 +4475,13 @@ impl EmulatorSdlFrontend {
                     None => { },
                 }
 
-                // Was the machine powered down?  Clear the screen.
+                // Was the machine powered down?  Clear the screen, but for
+                // the menu, which can power it on again.
                 if !self.emu_paused || sticky_clear {
-                    let (bg_red, bg_green, bg_blue) = bg_color;
-                    wnd_state.canvas.set_draw_color(sdl2::pixels::Color::RGB(bg_red, bg_green, bg_blue));
-                    wnd_state.canvas.clear();
+                    wnd_state.screen.clear(bg_color);
+                    EmulatorSdlFrontend::draw_notifications(wnd_state, txt_creat, &mut self.notifications, &self.text_font, self.text_colors);
+                    EmulatorSdlFrontend::draw_menu(wnd_state, txt_creat, &self.sdl2_keyboard, &self.text_font, self.text_colors);
+                    wnd_state.screen.present();
                     sticky_clear = true;
 
                 } else {
@@ -1126,16 +4489,20 @@ impl EmulatorSdlFrontend {
                     // Otherwise, draw the previous frame, if any.
                     match &self.current_frame {
                         Some(frame) => {
-                            sdl_video::render(&mut wnd_state.canvas, &narrow_glyphs, &wide_glyphs, frame);
+                            EmulatorSdlFrontend::render_frame(wnd_state, txt_creat, &mut screen_textures, frame, &mut self.dirty_cells, fg_color, &mut crt_textures, self.crt_effects, self.screenshot.take(), &mut self.notifications);
+                            EmulatorSdlFrontend::draw_overlay(wnd_state, txt_creat, &mut self.overlay, &self.text_font, self.text_colors);
+                            EmulatorSdlFrontend::draw_virtual_keyboard(wnd_state, txt_creat, &self.sdl2_keyboard, &self.text_font, self.text_colors);
+                            EmulatorSdlFrontend::draw_notifications(wnd_state, txt_creat, &mut self.notifications, &self.text_font, self.text_colors);
+                            EmulatorSdlFrontend::draw_menu(wnd_state, txt_creat, &self.sdl2_keyboard, &self.text_font, self.text_colors);
+                            wnd_state.screen.present();
+                            EmulatorSdlFrontend::draw_magnifier(&mut self.magnifier, &self.lc_cmd_tx, frame, &self.text_font, self.text_colors);
                         },
                         None => {
-                            let (bg_red, bg_green, bg_blue) = bg_color;
-                            wnd_state.canvas.set_draw_color(sdl2::pixels::Color::RGB(bg_red, bg_green, bg_blue));
-                            wnd_state.canvas.clear();
+                            wnd_state.screen.clear(bg_color);
+                            wnd_state.screen.present();
                         },
                     }
                 }
-                wnd_state.canvas.present();
             }
         }
     }
@@ -1145,11 +4512,13 @@ impl EmulatorSdlFrontend {
                    fullscr_res:           (u32, u32),
                    desktop_fullscr_mode:  bool,
                    use_hw_accel:          bool,
-                   use_vsync:             bool) -> bool {
+                   use_vsync:             bool,
+                   renderer:              sdl_video::Renderer) -> bool {
 
-        let (mut wnd_state, txt_creat) = match self.create_draw_ctxt(windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync) {
-            Some((ctxt, creat)) => {
+        let (mut wnd_state, txt_creat) = match self.create_draw_ctxt(windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer) {
+            Some((mut ctxt, creat)) => {
                 self.status_tx.send(VideoStatus::ModeSetStatus(true)).unwrap();
+                EmulatorSdlFrontend::set_fullscreen_mode(&mut ctxt, self.fullscreen);
                 (ctxt, creat)
             },
             None => {
@@ -1158,7 +4527,8 @@ impl EmulatorSdlFrontend {
             },
         };
         loop {
-            self.handle_sdl_events(&mut wnd_state, self.frame_draw);
+            self.handle_sdl_events(self.frame_draw);
+            self.handle_clicks(&wnd_state, self.frame_draw);
 
             let mut delayed_command: Option<VideoCommand> = None;
             std::mem::swap(&mut delayed_command, &mut self.delayed_command);
@@ -1168,34 +4538,106 @@ impl EmulatorSdlFrontend {
                     self.frame_draw = enabled;
                     self.emu_paused = emulation_paused;
                 },
-                VideoCommand::DrawFrame(frame) => {
-                    self.current_frame = Some(frame);
-                    self.cur_frame_used = false;
+                VideoCommand::FrameReady => {
+                    self.take_shared_frame();
                 },
                 VideoCommand::SetCyclesPerKeypress(cycles_per_keypress) => {
                     self.sdl2_keyboard.set_cycles_per_keypress(cycles_per_keypress);
                 }
+                VideoCommand::SetKeyMapping(mapping) => {
+                    self.sdl2_keyboard.set_key_mapping(mapping);
+                },
+                VideoCommand::SetKeyBindings(bindings) => {
+                    self.sdl2_keyboard.set_key_bindings(&bindings);
+                },
+                VideoCommand::SetKeyProfile(bindings) => {
+                    self.sdl2_keyboard.set_key_profile(&bindings);
+                },
+                VideoCommand::SetKeyRepeat(repeat) => {
+                    self.sdl2_keyboard.set_key_repeat(repeat);
+                },
+                VideoCommand::SetControllerBindings(bindings) => {
+                    self.sdl2_keyboard.set_controller_bindings(&bindings);
+                },
+                VideoCommand::SetSound(settings) => {
+                    self.sdl2_audio.set_output(&self.sdl2_main_ctxt, settings);
+                },
+                VideoCommand::PlaySamples(samples) => {
+                    self.sdl2_audio.play(samples);
+                },
+                VideoCommand::ReportSoundStatistics => {
+                    self.sdl2_audio.report_statistics();
+                },
                 VideoCommand::Terminate => {
                     return false;
                 },
-                VideoCommand::UpdateTextures { bg_color, fg_color, cg_num } => {
-                    if !self.run_with_textures(cmd_rx, &mut wnd_state, &txt_creat, bg_color, fg_color, cg_num) {
+                VideoCommand::UpdateTextures { bg_color, fg_color, font } => {
+                    if !self.run_with_textures(cmd_rx, &mut wnd_state, txt_creat.as_ref(), bg_color, fg_color, &font) {
                         return false;
                     }
                 },
                 VideoCommand::SetWindowedResolution((width, height)) => {
                     wnd_state.windowed_res = (width, height);
                     if !wnd_state.fullscreen_mode {
-                        let window = wnd_state.canvas.window_mut();
+                        let window = wnd_state.screen.window_mut();
                         window.set_size(width, height).unwrap();
                     }
                 },
                 VideoCommand::SetFullscreenResolution((width, height), fscr_mode_dsktp) => {
                     self.handle_fullscr_res_change(&mut wnd_state, width, height, fscr_mode_dsktp);
                 },
-                VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, bg_color, fg_color, cg_num } => {
+                VideoCommand::SetFullscreen(fullscreen) => {
+                    self.fullscreen = fullscreen;
+                    EmulatorSdlFrontend::set_fullscreen_mode(&mut wnd_state, fullscreen);
+                },
+                VideoCommand::SetScaling(scaling) => {
+                    self.scaling = scaling;
+                    EmulatorSdlFrontend::set_scaling_mode(&mut wnd_state, scaling);
+                },
+                VideoCommand::SetCrtEffects(effects) => {
+                    self.crt_effects = effects;
+                },
+                VideoCommand::SetFrameSkip(frame_skip) => {
+                    self.frame_pacer.set_frame_skip(frame_skip);
+                },
+                VideoCommand::SetOverlay(level) => {
+                    self.overlay.set_level(level);
+                },
+                VideoCommand::SetMachineStatus(status) => {
+                    self.overlay.set_status(status);
+                },
+                VideoCommand::SetNotifications(enabled) => {
+                    self.notifications.set_enabled(enabled);
+                },
+                VideoCommand::Notify(text) => {
+                    self.notifications.push(&text);
+                },
+                VideoCommand::SetClipboardText(text) => {
+                    self.set_clipboard_text(&text);
+                },
+                VideoCommand::PasteClipboard => {
+                    self.paste_clipboard();
+                },
+                VideoCommand::SetTextGraphics(..) => {
+                },
+                VideoCommand::SetDebugWindow(enabled) => {
+                    self.set_debug_window(enabled);
+                },
+                VideoCommand::ShowDebugState(lines) => {
+                    self.show_debug_state(&lines);
+                },
+                VideoCommand::SetMagnifier(enabled) => {
+                    self.set_magnifier(enabled);
+                },
+                VideoCommand::ShowMenu(menu) => {
+                    self.show_menu(menu);
+                },
+                VideoCommand::TakeScreenshot { path, scaled } => {
+                    self.screenshot = Some((path, scaled));
+                },
+                VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer, bg_color, fg_color, font } => {
 
-                    self.delayed_command = Some(VideoCommand::SetVideoMode{ windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, bg_color, fg_color, cg_num });
+                    self.delayed_command = Some(VideoCommand::SetVideoMode{ windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer, bg_color, fg_color, font });
                     return true;
                 },
             }
@@ -1212,13 +4654,36 @@ impl EmulatorSdlFrontend {
                     self.frame_draw = enabled;
                     self.emu_paused = emulation_paused;
                 },
-                VideoCommand::DrawFrame(frame) => {
-                    self.current_frame = Some(frame);
-                    self.cur_frame_used = false;
+                VideoCommand::FrameReady => {
+                    self.take_shared_frame();
                 },
                 VideoCommand::SetCyclesPerKeypress(cycles_per_keypress) => {
                     self.sdl2_keyboard.set_cycles_per_keypress(cycles_per_keypress);
                 }
+                VideoCommand::SetKeyMapping(mapping) => {
+                    self.sdl2_keyboard.set_key_mapping(mapping);
+                },
+                VideoCommand::SetKeyBindings(bindings) => {
+                    self.sdl2_keyboard.set_key_bindings(&bindings);
+                },
+                VideoCommand::SetKeyProfile(bindings) => {
+                    self.sdl2_keyboard.set_key_profile(&bindings);
+                },
+                VideoCommand::SetKeyRepeat(repeat) => {
+                    self.sdl2_keyboard.set_key_repeat(repeat);
+                },
+                VideoCommand::SetControllerBindings(bindings) => {
+                    self.sdl2_keyboard.set_controller_bindings(&bindings);
+                },
+                VideoCommand::SetSound(settings) => {
+                    self.sdl2_audio.set_output(&self.sdl2_main_ctxt, settings);
+                },
+                VideoCommand::PlaySamples(samples) => {
+                    self.sdl2_audio.play(samples);
+                },
+                VideoCommand::ReportSoundStatistics => {
+                    self.sdl2_audio.report_statistics();
+                },
                 VideoCommand::Terminate => {
                     return;
                 },
@@ -1228,10 +4693,57 @@ impl EmulatorSdlFrontend {
                 },
                 VideoCommand::SetFullscreenResolution(..) => {
                 },
-                VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, bg_color, fg_color, cg_num } => {
+                VideoCommand::SetFullscreen(fullscreen) => {
+                    self.fullscreen = fullscreen;
+                },
+                VideoCommand::SetScaling(scaling) => {
+                    self.scaling = scaling;
+                },
+                VideoCommand::SetCrtEffects(effects) => {
+                    self.crt_effects = effects;
+                },
+                VideoCommand::SetFrameSkip(frame_skip) => {
+                    self.frame_pacer.set_frame_skip(frame_skip);
+                },
+                VideoCommand::SetOverlay(level) => {
+                    self.overlay.set_level(level);
+                },
+                VideoCommand::SetMachineStatus(status) => {
+                    self.overlay.set_status(status);
+                },
+                VideoCommand::SetNotifications(enabled) => {
+                    self.notifications.set_enabled(enabled);
+                },
+                VideoCommand::Notify(text) => {
+                    self.notifications.push(&text);
+                },
+                VideoCommand::SetClipboardText(text) => {
+                    self.set_clipboard_text(&text);
+                },
+                VideoCommand::PasteClipboard => {
+                    self.paste_clipboard();
+                },
+                VideoCommand::SetTextGraphics(..) => {
+                },
+                VideoCommand::SetDebugWindow(enabled) => {
+                    self.set_debug_window(enabled);
+                },
+                VideoCommand::ShowDebugState(lines) => {
+                    self.show_debug_state(&lines);
+                },
+                VideoCommand::SetMagnifier(enabled) => {
+                    self.set_magnifier(enabled);
+                },
+                VideoCommand::ShowMenu(menu) => {
+                    self.show_menu(menu);
+                },
+                VideoCommand::TakeScreenshot { .. } => {
+                    error!("Can't take a screenshot, the emulator window isn't open.");
+                },
+                VideoCommand::SetVideoMode { windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer, bg_color, fg_color, font } => {
 
-                    self.delayed_command = Some(VideoCommand::UpdateTextures { bg_color, fg_color, cg_num });
-                    if !self.run_in_mode(cmd_rx, windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync) {
+                    self.delayed_command = Some(VideoCommand::UpdateTextures { bg_color, fg_color, font });
+                    if !self.run_in_mode(cmd_rx, windowed_res, fullscr_res, desktop_fullscr_mode, use_hw_accel, use_vsync, renderer) {
                         return;
                     }
                 },
@@ -1275,13 +4787,30 @@ impl<'a, T> MpscSenderSink<'a, T> {
 }
 
 
-impl Sink<video::VideoFrame> for MpscSenderSink<'_, VideoCommand> {
+impl MpscSenderSink<'_, VideoCommand> {
 
-    fn push(&mut self, value: video::VideoFrame) {
+    // Tell the front-end that there's a frame for it in the shared frame.
+    fn frame_ready(&mut self) {
+
+        if !self.hung_up {
+
+            match self.sender.send(VideoCommand::FrameReady) {
+                Ok(..) => { },
+                Err(..) => {
+                    self.hung_up = true;
+                },
+            }
+        }
+    }
+}
+
+impl Sink<Vec<i16>> for MpscSenderSink<'_, VideoCommand> {
+
+    fn push(&mut self, value: Vec<i16>) {
 
         if !self.hung_up {
 
-            match self.sender.send(VideoCommand::DrawFrame(value)) {
+            match self.sender.send(VideoCommand::PlaySamples(value)) {
                 Ok(..) => { },
                 Err(..) => {
                     self.hung_up = true;
@@ -1299,3 +4828,209 @@ impl Drop for EmulatorSdlFrontend {
         }
     }
 }
+
+// The front-end of the terminal-only mode, which doesn't open a window, and
+// sends the text on the screen into the curses-based user interface instead,
+// one string per row, laid out into 64 columns.  The sound is still played
+// through SDL2, if it's available.
+pub struct EmulatorTerminalFrontend {
+    sdl2_main_ctxt:  Option<sdl2::Sdl>,
+    sdl2_audio:      sdl_audio::SdlAudio,
+
+    frame_draw:      bool,
+    current_frame:   Option<video::VideoFrame>,
+    shared_frame:    video::SharedFrame,
+    text_graphics:   video::TextGraphics,
+    shown_rows:      Vec<String>,
+    bg_color:        (u8, u8, u8),
+    fg_color:        (u8, u8, u8),
+    font:            Box<[u8]>,
+
+    // In the headless mode, there's no one to send the screen to, and SDL2
+    // isn't used, so there's no sound either.
+    screen_tx:       Option<mpsc::Sender<Vec<String>>>,
+    status_tx:       mpsc::Sender<VideoStatus>,
+}
+
+impl EmulatorTerminalFrontend {
+    pub fn new(screen_tx: Option<mpsc::Sender<Vec<String>>>, status_tx: mpsc::Sender<VideoStatus>, shared_frame: video::SharedFrame) -> EmulatorTerminalFrontend {
+
+        let main_ctxt = if screen_tx.is_none() {
+            None
+        } else {
+            match sdl2::init() {
+                Ok(context) => { Some(context) },
+                Err(error) => {
+                    warn!("Failed to initialize SDL2, there will be no sound: {}.", error);
+                    None
+                },
+            }
+        };
+        status_tx.send(VideoStatus::Created).unwrap();
+
+        EmulatorTerminalFrontend {
+            sdl2_main_ctxt:  main_ctxt,
+            sdl2_audio:      sdl_audio::SdlAudio::new(),
+            frame_draw:      false,
+            current_frame:   None,
+            shared_frame,
+            text_graphics:   video::TextGraphics::Unicode,
+            shown_rows:      Vec::new(),
+            bg_color:        (0, 0, 0),
+            fg_color:        (0, 0, 0),
+            font:            Box::new([]),
+            screen_tx,
+            status_tx,
+        }
+    }
+    // Send the rows of the screen to the user interface, if they changed.
+    fn show_screen(&mut self) {
+        let rows: Vec<String> = match &self.current_frame {
+            Some(frame) => {
+                frame.text(self.text_graphics).lines().map(|line| {
+                    if frame.modesel {
+                        // Each of the 32 columns takes up two.
+                        line.chars().flat_map(|character| {
+                            [character, if character.is_ascii() { ' ' } else { character }]
+                        }).collect()
+                    } else {
+                        line.to_owned()
+                    }
+                }).collect()
+            },
+            None => { Vec::new() },
+        };
+        if rows != self.shown_rows {
+            self.shown_rows = rows.clone();
+            if let Some(screen_tx) = &self.screen_tx {
+                screen_tx.send(rows).unwrap();
+            }
+        }
+    }
+    fn take_screenshot(&self, path: &path::Path) {
+        let frame = match &self.current_frame {
+            Some(frame) => { frame },
+            None => {
+                error!("Can't take a screenshot, there's nothing on the screen.");
+                return;
+            },
+        };
+        let (width, height, pixels) = sdl_video::rasterize_rgb(frame, &self.font, self.bg_color, self.fg_color);
+        match png::write_rgb(path, width, height, &pixels) {
+            Ok(..) => {
+                info!("Screenshot saved into `{}'.", path.display());
+            },
+            Err(error) => {
+                error!("Failed to save the screenshot `{}': {}.", path.display(), error);
+            },
+        }
+    }
+    pub fn run(&mut self, cmd_rx: &mpsc::Receiver<VideoCommand>) {
+
+        loop {
+            match cmd_rx.recv().unwrap() {
+                VideoCommand::SetFrameDrawing{ enabled, emulation_paused } => {
+                    self.frame_draw = enabled;
+
+                    // Was the machine powered down?  Clear the screen.
+                    if !enabled && !emulation_paused {
+                        self.current_frame = None;
+                        self.show_screen();
+                    }
+                },
+                VideoCommand::FrameReady => {
+                    // The frame is taken out even if it isn't shown, so
+                    // that the logic core keeps telling about new ones.
+                    let frame = self.shared_frame.take();
+                    if self.frame_draw && frame.is_some() {
+                        self.current_frame = frame;
+                        self.show_screen();
+                    }
+                },
+                VideoCommand::SetTextGraphics(text_graphics) => {
+                    self.text_graphics = text_graphics;
+                    self.show_screen();
+                },
+                VideoCommand::SetVideoMode { bg_color, fg_color, font, .. } => {
+                    self.bg_color = bg_color;
+                    self.fg_color = fg_color;
+                    self.font = font;
+                    self.status_tx.send(VideoStatus::ModeSetStatus(true)).unwrap();
+                },
+                VideoCommand::UpdateTextures { bg_color, fg_color, font } => {
+                    self.bg_color = bg_color;
+                    self.fg_color = fg_color;
+                    self.font = font;
+                },
+                VideoCommand::SetSound(settings) => {
+                    match &self.sdl2_main_ctxt {
+                        Some(main_ctxt) => {
+                            self.sdl2_audio.set_output(main_ctxt, settings);
+                        },
+                        None => {
+                            if settings.enabled && self.screen_tx.is_some() {
+                                error!("Can't play the sound, SDL2 isn't available.");
+                            }
+                        },
+                    }
+                },
+                VideoCommand::PlaySamples(samples) => {
+                    self.sdl2_audio.play(samples);
+                },
+                VideoCommand::ReportSoundStatistics => {
+                    self.sdl2_audio.report_statistics();
+                },
+                VideoCommand::TakeScreenshot { path, .. } => {
+                    self.take_screenshot(&path);
+                },
+                VideoCommand::SetClipboardText(..) => {
+                    error!("The clipboard isn't available in the terminal, use `/video text <file>' instead.");
+                },
+                VideoCommand::PasteClipboard => {
+                    error!("The clipboard isn't available in the terminal, paste the text into the terminal instead.");
+                },
+                VideoCommand::SetDebugWindow(enabled) => {
+                    if enabled {
+                        error!("The debugger window isn't available in the terminal-only mode.");
+                    }
+                },
+                VideoCommand::SetMagnifier(enabled) => {
+                    if enabled {
+                        error!("The magnifier window isn't available in the terminal-only mode.");
+                    }
+                },
+                VideoCommand::Terminate => {
+                    return;
+                },
+                VideoCommand::SetCyclesPerKeypress(..) |
+                VideoCommand::SetKeyMapping(..) |
+                VideoCommand::SetKeyBindings(..) |
+                VideoCommand::SetKeyProfile(..) |
+                VideoCommand::SetKeyRepeat(..) |
+                VideoCommand::SetControllerBindings(..) |
+                VideoCommand::SetWindowedResolution(..) |
+                VideoCommand::SetFullscreenResolution(..) |
+                VideoCommand::SetFullscreen(..) |
+                VideoCommand::SetScaling(..) |
+                VideoCommand::SetCrtEffects(..) |
+                VideoCommand::SetFrameSkip(..) |
+                VideoCommand::SetOverlay(..) |
+                VideoCommand::SetMachineStatus(..) |
+                VideoCommand::SetNotifications(..) |
+                VideoCommand::Notify(..) |
+                VideoCommand::ShowDebugState(..) |
+                VideoCommand::ShowMenu(..) => {
+                },
+            }
+        }
+    }
+}
+
+impl Drop for EmulatorTerminalFrontend {
+    fn drop(&mut self) {
+        match self.status_tx.send(VideoStatus::Destroyed) {
+            Ok(..) => { },
+            Err(..) => { }, // Ignore error to prevent double panic.
+        }
+    }
+}
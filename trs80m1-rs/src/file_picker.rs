@@ -0,0 +1,230 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::fs;
+use std::path;
+
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::tape;
+
+
+// The file picker of the curses-based interface, for choosing the files by
+// going through the directories instead of typing their paths.  This keeps
+// the directory being looked at and the entry chosen in it, the interface
+// draws it in place of the messages and passes it the keys.
+//
+// Only the directories and the files which can be used for the purpose the
+// picker was opened for are listed, the hidden ones are left out.  Next to
+// the cassettes, the programs detected on them are shown.
+//
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Purpose {
+    Cassette,
+    Disk,
+    Rom,
+    Script,
+}
+
+pub const PURPOSES: [(&str, Purpose); 4] = [
+    ("cassette", Purpose::Cassette),
+    ("disk",     Purpose::Disk),
+    ("rom",      Purpose::Rom),
+    ("script",   Purpose::Script),
+];
+
+const DISK_EXTENSIONS: [&str; 5] = ["dsk", "dmk", "jv1", "jv3", "imd"];
+const ROM_EXTENSIONS:  [&str; 3] = ["rom", "bin", "hex"];
+
+impl Purpose {
+    pub fn from_name(name: &str) -> Option<Purpose> {
+        let name = name.to_lowercase();
+        PURPOSES.iter().find(|(purpose_name, _)| *purpose_name == name).map(|(_, purpose)| *purpose)
+    }
+    pub fn title(&self) -> &'static str {
+        match *self {
+            Purpose::Cassette => { "Choose a cassette" },
+            Purpose::Disk     => { "Choose a disk image" },
+            Purpose::Rom      => { "Choose a ROM image" },
+            Purpose::Script   => { "Choose a script to run" },
+        }
+    }
+    fn can_use(&self, file: &path::Path) -> bool {
+        let extension = file.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match *self {
+            Purpose::Cassette => { cassette::Format::from_path(file).is_some() },
+            Purpose::Disk     => { DISK_EXTENSIONS.contains(&extension.as_str()) },
+            Purpose::Rom      => { ROM_EXTENSIONS.contains(&extension.as_str()) },
+            Purpose::Script   => { true },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name:     String,
+    pub is_dir:   bool,
+    pub contents: Option<String>, // What's detected on a cassette.
+}
+
+// What's recorded on a cassette, one line per recording.  Only the CAS
+// cassettes are looked into, decoding the WAV ones would take too long.
+pub fn cassette_contents(file: &path::Path, format: cassette::Format) -> Vec<String> {
+    match format {
+        cassette::Format::CAS => {
+            match fs::read(file) {
+                Ok(buffer) => {
+                    tape::scan_cas(&buffer).iter().map(|recording| recording.description()).collect()
+                },
+                Err(error) => {
+                    vec![format!("(couldn't be read: {})", error)]
+                },
+            }
+        },
+        _ => { vec!["(contents not detected)".to_owned()] },
+    }
+}
+
+pub struct FilePicker {
+    pub purpose:  Purpose,
+    pub dir:      path::PathBuf,
+    pub entries:  Vec<Entry>,
+    pub selected: usize,
+    pub error:    Option<String>,     // Why the directory couldn't be read.
+    scroll:       usize,
+}
+
+impl FilePicker {
+    pub fn new(purpose: Purpose, dir: path::PathBuf) -> FilePicker {
+        let mut picker = FilePicker {
+            purpose,
+            dir:      dir.canonicalize().unwrap_or(dir),
+            entries:  Vec::new(),
+            selected: 0,
+            error:    None,
+            scroll:   0,
+        };
+        picker.read_dir();
+        picker
+    }
+    fn read_dir(&mut self) {
+        self.entries.clear();
+        self.selected = 0;
+        self.scroll = 0;
+        self.error = None;
+
+        if self.dir.parent().is_some() {
+            self.entries.push(Entry { name: "..".to_owned(), is_dir: true, contents: None });
+        }
+        let dir_entries = match fs::read_dir(&self.dir) {
+            Ok(dir_entries) => { dir_entries },
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            },
+        };
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        for dir_entry in dir_entries.flatten() {
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let entry_path = dir_entry.path();
+            if entry_path.is_dir() {
+                dirs.push(Entry { name, is_dir: true, contents: None });
+            } else if self.purpose.can_use(&entry_path) {
+                let contents = match (self.purpose, cassette::Format::from_path(&entry_path)) {
+                    (Purpose::Cassette, Some(format)) => {
+                        let contents = cassette_contents(&entry_path, format);
+                        Some(if contents.is_empty() { "(empty)".to_owned() } else { contents.join("; ") })
+                    },
+                    _ => { None },
+                };
+                files.push(Entry { name, is_dir: false, contents });
+            }
+        }
+        dirs.sort_by_key(|entry| entry.name.to_lowercase());
+        files.sort_by_key(|entry| entry.name.to_lowercase());
+        self.entries.extend(dirs);
+        self.entries.extend(files);
+    }
+    pub fn move_by(&mut self, rows: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let last = self.entries.len() - 1;
+        self.selected = self.selected.saturating_add_signed(rows).min(last);
+    }
+    pub fn move_to_end(&mut self) {
+        self.selected = self.entries.len().saturating_sub(1);
+    }
+    pub fn move_to_start(&mut self) {
+        self.selected = 0;
+    }
+    // Go to the next entry starting with the typed character, like in the
+    // file managers.
+    pub fn jump_to(&mut self, ch: char) -> bool {
+        let ch = ch.to_lowercase().to_string();
+        let count = self.entries.len();
+        for offset in 1..=count {
+            let index = (self.selected + offset) % count;
+            if self.entries[index].name.to_lowercase().starts_with(&ch) {
+                self.selected = index;
+                return true;
+            }
+        }
+        false
+    }
+    pub fn go_up(&mut self) {
+        if let Some(parent) = self.dir.parent() {
+            let dir_name = self.dir.file_name().map(|name| name.to_string_lossy().into_owned());
+            self.dir = parent.to_owned();
+            self.read_dir();
+
+            // Land on the directory just left.
+            if let Some(dir_name) = dir_name {
+                if let Some(index) = self.entries.iter().position(|entry| entry.is_dir && entry.name == dir_name) {
+                    self.selected = index;
+                }
+            }
+        }
+    }
+    // Enter the chosen directory, or give the chosen file.
+    pub fn choose(&mut self) -> Option<path::PathBuf> {
+        let entry = self.entries.get(self.selected)?.clone();
+        if entry.name == ".." {
+            self.go_up();
+            None
+        } else if entry.is_dir {
+            self.dir.push(&entry.name);
+            self.read_dir();
+            None
+        } else {
+            Some(self.dir.join(&entry.name))
+        }
+    }
+    // The first entry to show in the given number of rows, so that the chosen
+    // one is among them.
+    pub fn first_shown(&mut self, rows: usize) -> usize {
+        let rows = rows.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + rows {
+            self.scroll = self.selected + 1 - rows;
+        }
+        self.scroll
+    }
+}
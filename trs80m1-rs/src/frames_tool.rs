@@ -0,0 +1,253 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, error};
+
+use std::fs;
+use std::path;
+
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::fonts;
+use trs80m1_rs_core::keyboard;
+use trs80m1_rs_core::replay;
+use trs80m1_rs_core::util::Sink;
+use trs80m1_rs_core::video;
+
+use crate::tool_machine;
+
+
+// The `frames' command line mode, which runs the emulated machine without
+// showing it, for automated tests checking that the boot screens of ROMs
+// and the pictures drawn by programs stay the same:
+//
+//     trs80m1-rs frames record [options] <hash-file> <rom> <frames>
+//     trs80m1-rs frames check [options] <hash-file> <rom>
+//
+// The machine is powered on with the given ROM image and run for the given
+// number of video frames, and the hash of the picture of each frame is
+// written into the hash file, one line per frame.  Checking runs it again
+// for as many frames as there are in the hash file, and fails at the first
+// one whose picture is different.
+//
+// Both take the same options, which have to match for the machine to do
+// the same thing:
+//
+//     --cas <cas-file>       Insert the cassette, it plays when the motor runs.
+//     --type <frame>:<text>  Type in the text once the frame is reached, with
+//                            `\n' pressing Enter.  Can be given repeatedly.
+//     --input <inp-file>     Replay an input recording made with `/record
+//                            input', from its snapshot, which is where the
+//                            frames start.  Its RAM has to be 48K.
+//
+// The machine is set up the same way each time, see tool_machine.rs.
+//
+const HASH_FILE_HEADER: &str = "# trs80m1-rs frame hashes";
+
+pub fn print_usage(progname: &str) {
+    println!("Usage: {} frames record [--cas <cas-file>] [--type <frame>:<text>]... [--input <inp-file>] <hash-file> <rom> <frames>", progname);
+    println!("       {} frames check [--cas <cas-file>] [--type <frame>:<text>]... [--input <inp-file>] <hash-file> <rom>", progname);
+}
+
+struct RunOptions {
+    cassette: Option<(path::PathBuf, cassette::Format)>,
+    typed:    Vec<(usize, String)>, // The frame to type the text at.
+    input:    Option<path::PathBuf>,
+}
+
+// Hashes the pictures of the frames as the machine produces them.
+struct FrameHasher {
+    hashes: Vec<u64>,
+}
+
+impl Sink<video::VideoFrame> for FrameHasher {
+    fn push(&mut self, value: video::VideoFrame) {
+        self.hashes.push(value.picture_hash(&fonts::FONT_CG0));
+    }
+}
+
+// Returns the exit code of the program.
+pub fn run(progname: &str, args_in: &[String]) -> i32 {
+    let (options, args) = match parse_options(args_in) {
+        Some(parsed) => { parsed },
+        None => {
+            print_usage(progname);
+            return 1;
+        },
+    };
+
+    let subcommand = match args.first() {
+        Some(subcommand) => { subcommand.to_lowercase() },
+        None => {
+            print_usage(progname);
+            return 1;
+        },
+    };
+    let arg_count = if subcommand == "record" { 4 } else { 3 };
+    if args.len() != arg_count {
+        print_usage(progname);
+        return 1;
+    }
+
+    let success = if subcommand == "record" {
+        match args[3].parse::<usize>() {
+            Ok(frames) => { record(&args[1], &args[2], frames, &options) },
+            Err(..) => {
+                error!("Invalid number of frames `{}'.", args[3]);
+                false
+            },
+        }
+    } else if subcommand == "check" {
+        check(&args[1], &args[2], &options)
+    } else {
+        eprintln!("{}: Unknown frames subcommand `{}'.", progname, subcommand);
+        print_usage(progname);
+        false
+    };
+    if success { 0 } else { 1 }
+}
+
+// Separate the options from the rest of the arguments.
+fn parse_options(args_in: &[String]) -> Option<(RunOptions, Vec<String>)> {
+    let mut options = RunOptions { cassette: None, typed: Vec::new(), input: None };
+    let mut args = Vec::new();
+    let mut args_iter = args_in.iter();
+
+    while let Some(arg) = args_iter.next() {
+        if arg == "--cas" {
+            let cas_path = path::PathBuf::from(args_iter.next()?);
+            match cassette::Format::from_path(&cas_path) {
+                Some(format) => { options.cassette = Some((cas_path, format)); },
+                None => {
+                    error!("Can't tell the format of the cassette file `{}' from its extension.", cas_path.display());
+                    return None;
+                },
+            }
+        } else if arg == "--type" {
+            let typed = args_iter.next()?;
+            let (frame, text) = typed.split_once(':')?;
+            let text = text.replace("\\n", "\n");
+            if keyboard::text_to_key_events(&text, 0).is_none() {
+                error!("The text `{}' can't be typed on the keyboard of the emulated machine.", text.trim_end());
+                return None;
+            }
+            options.typed.push((frame.parse::<usize>().ok()?, text));
+        } else if arg == "--input" {
+            options.input = Some(path::PathBuf::from(args_iter.next()?));
+        } else {
+            args.push(arg.clone());
+        }
+    }
+    Some((options, args))
+}
+
+// Run the machine for the given number of frames, returning the hashes of
+// their pictures.
+fn run_machine(rom_path: &str, frames: usize, options: &RunOptions) -> Option<Vec<u64>> {
+    let mut machine = tool_machine::power_on(rom_path, options.cassette.clone())?;
+    let mut hasher = FrameHasher { hashes: Vec::with_capacity(frames) };
+
+    if let Some(input_path) = &options.input {
+        let replayed = fs::read(input_path).map_err(|error| error.to_string())
+                                           .and_then(|bytes| replay::InputRecording::parse(&bytes))
+                                           .and_then(|recording| machine.start_input_replay(recording));
+        if let Err(error) = replayed {
+            error!("Failed to replay `{}': {}.", input_path.display(), error);
+            return None;
+        }
+    }
+    for frame in 0..frames {
+        for (_, text) in options.typed.iter().filter(|(at_frame, _)| *at_frame == frame) {
+            tool_machine::type_text(&mut machine, text);
+        }
+        while hasher.hashes.len() <= frame {
+            machine.step(&mut tool_machine::IgnoredEvents, &mut hasher);
+        }
+    }
+    hasher.hashes.truncate(frames);
+    Some(hasher.hashes)
+}
+
+fn record(hash_path: &str, rom_path: &str, frames: usize, options: &RunOptions) -> bool {
+    let hashes = match run_machine(rom_path, frames, options) {
+        Some(hashes) => { hashes },
+        None => { return false; },
+    };
+    let mut contents = format!("{}\n", HASH_FILE_HEADER);
+    for (frame, hash) in hashes.iter().enumerate() {
+        contents.push_str(&format!("{} {:016X}\n", frame, hash));
+    }
+    match fs::write(hash_path, &contents) {
+        Ok(..) => {
+            info!("Recorded the hashes of {} frames into `{}'.", frames, hash_path);
+            true
+        },
+        Err(error) => {
+            error!("Failed to write `{}': {}.", hash_path, error);
+            false
+        },
+    }
+}
+
+// The hashes in a hash file, one for each frame, in order.
+fn read_hash_file(hash_path: &str) -> Option<Vec<u64>> {
+    let contents = match fs::read_to_string(hash_path) {
+        Ok(contents) => { contents },
+        Err(error) => {
+            error!("Failed to read `{}': {}.", hash_path, error);
+            return None;
+        },
+    };
+    let mut hashes = Vec::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let frame = fields.next().and_then(|field| field.parse::<usize>().ok());
+        let hash = fields.next().and_then(|field| u64::from_str_radix(field, 16).ok());
+        match (frame, hash, fields.next()) {
+            (Some(frame), Some(hash), None) if frame == hashes.len() => {
+                hashes.push(hash);
+            },
+            _ => {
+                error!("Line {} of the hash file `{}' is malformed.", line_index + 1, hash_path);
+                return None;
+            },
+        }
+    }
+    Some(hashes)
+}
+
+fn check(hash_path: &str, rom_path: &str, options: &RunOptions) -> bool {
+    let expected = match read_hash_file(hash_path) {
+        Some(hashes) => { hashes },
+        None => { return false; },
+    };
+    let hashes = match run_machine(rom_path, expected.len(), options) {
+        Some(hashes) => { hashes },
+        None => { return false; },
+    };
+    match hashes.iter().zip(expected.iter()).position(|(hash, expected)| hash != expected) {
+        Some(frame) => {
+            error!("Frame {} differs from the one in `{}', its hash is {:016X} instead of {:016X}.", frame, hash_path, hashes[frame], expected[frame]);
+            false
+        },
+        None => {
+            info!("All {} frames match the ones in `{}'.", expected.len(), hash_path);
+            true
+        },
+    }
+}
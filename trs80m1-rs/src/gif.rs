@@ -0,0 +1,219 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+
+
+// A minimal writer of animated GIF images, just enough to record the screen,
+// which only ever shows two colors.
+//
+// The file starts with a header and a global color table of the two colors,
+// followed by an application extension which makes the animation loop.  Each
+// frame is a graphic control extension with the time the frame is shown for,
+// in hundredths of a second, followed by the image itself, whose pixels are
+// indices into the color table, compressed with a variant of LZW and split
+// into sub-blocks of up to 255 bytes.
+//
+// Consecutive frames which are the same are merged into a single one which
+// is shown for longer, so still parts of a recording take up almost no room.
+//
+const GIF_SIGNATURE:   &[u8] = b"GIF89a";
+const NETSCAPE_LOOP:   &[u8] = b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00";
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xF9;
+const IMAGE_SEPARATOR:  u8 = 0x2C;
+const TRAILER:          u8 = 0x3B;
+
+const GLOBAL_COLOR_TABLE: u8 = 0x80; // With 2 entries.
+
+// Players don't show frames for less than 2 hundredths of a second.
+pub const MAX_FRAME_RATE: u32 = 50;
+
+// The smallest code size GIF allows, enough for the 2 colors.
+const MIN_CODE_SIZE:    u32 = 2;
+const MAX_CODE_SIZE:    u32 = 12;
+const CLEAR_CODE:       u16 = 1 << MIN_CODE_SIZE;
+const END_CODE:         u16 = CLEAR_CODE + 1;
+const MAX_CODES:        u16 = 1 << MAX_CODE_SIZE;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bytes:       Vec::new(),
+            accumulator: 0,
+            bits:        0,
+        }
+    }
+    // Codes are packed starting from the least significant bit.
+    fn write(&mut self, code: u16, code_size: u32) {
+        self.accumulator |= (code as u32) << self.bits;
+        self.bits += code_size;
+        while self.bits >= 8 {
+            self.bytes.push(self.accumulator as u8);
+            self.accumulator >>= 8;
+            self.bits -= 8;
+        }
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.accumulator as u8);
+        }
+        self.bytes
+    }
+}
+
+// Compress the pixels, each of which is either 0 or 1.
+//
+// Since there are only two colors, each string in the table can only be
+// followed by one of two pixels, so the code of a string followed by a pixel
+// is kept at `2 * code + pixel' of a flat table, 0 meaning that it's not in
+// the table yet (no string can get the code 0, that's the first color).
+fn compress(pixels: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    let mut table = vec![0u16; (MAX_CODES as usize) * 2];
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = END_CODE + 1;
+    let mut prefix: Option<u16> = None;
+
+    writer.write(CLEAR_CODE, code_size);
+    for pixel in pixels.iter().map(|pixel| (*pixel & 1) as u16) {
+        let code = match prefix {
+            Some(code) => { code },
+            None => {
+                prefix = Some(pixel);
+                continue;
+            },
+        };
+        let entry = ((code as usize) * 2) + (pixel as usize);
+        if table[entry] != 0 {
+            prefix = Some(table[entry]);
+            continue;
+        }
+        writer.write(code, code_size);
+
+        // Once the table is full, it's started over.
+        if next_code < MAX_CODES {
+            table[entry] = next_code;
+            next_code += 1;
+            if next_code > (1 << code_size) && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        } else {
+            writer.write(CLEAR_CODE, code_size);
+            table.iter_mut().for_each(|code| *code = 0);
+            code_size = MIN_CODE_SIZE + 1;
+            next_code = END_CODE + 1;
+        }
+        prefix = Some(pixel);
+    }
+    if let Some(code) = prefix {
+        writer.write(code, code_size);
+    }
+    writer.write(END_CODE, code_size);
+    writer.finish()
+}
+
+pub struct GifWriter {
+    file:    io::BufWriter<fs::File>,
+    width:   u16,
+    height:  u16,
+    pending: Option<(Vec<u8>, u32)>, // The last frame and when it started.
+    frames:  u32,
+}
+
+impl GifWriter {
+    // Create an animation of `width' * `height' pixels, in the given
+    // background and foreground colors.
+    pub fn create<P: AsRef<path::Path>>(path: P, width: u16, height: u16, colors: [(u8, u8, u8); 2]) -> io::Result<GifWriter> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+
+        file.write_all(GIF_SIGNATURE)?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        file.write_all(&[GLOBAL_COLOR_TABLE, 0, 0])?; // Background color and aspect ratio.
+        for (red, green, blue) in colors.iter() {
+            file.write_all(&[*red, *green, *blue])?;
+        }
+        file.write_all(NETSCAPE_LOOP)?;
+
+        Ok(GifWriter {
+            file,
+            width,
+            height,
+            pending: None,
+            frames:  0,
+        })
+    }
+    fn write_frame(&mut self, pixels: &[u8], delay: u32) -> io::Result<()> {
+        let delay = delay.clamp(1, u16::MAX as u32) as u16;
+
+        self.file.write_all(&[EXTENSION_INTRODUCER, GRAPHIC_CONTROL_LABEL, 4, 0])?;
+        self.file.write_all(&delay.to_le_bytes())?;
+        self.file.write_all(&[0, 0])?; // No transparency, end of the extension.
+
+        self.file.write_all(&[IMAGE_SEPARATOR, 0, 0, 0, 0])?;
+        self.file.write_all(&self.width.to_le_bytes())?;
+        self.file.write_all(&self.height.to_le_bytes())?;
+        self.file.write_all(&[0, MIN_CODE_SIZE as u8])?;
+        for block in compress(pixels).chunks(255) {
+            self.file.write_all(&[block.len() as u8])?;
+            self.file.write_all(block)?;
+        }
+        self.file.write_all(&[0])?;
+        self.frames += 1;
+        Ok(())
+    }
+    // Add a frame of `width' * `height' pixels, 0 for the background and 1
+    // for the foreground, which starts at the given time, in hundredths of
+    // a second since the start of the recording.
+    pub fn add_frame(&mut self, pixels: &[u8], time: u32) -> io::Result<()> {
+        if pixels.len() != (self.width as usize) * (self.height as usize) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("the frame has {} pixels rather than {} for {}x{}", pixels.len(), (self.width as usize) * (self.height as usize), self.width, self.height)));
+        }
+
+        match self.pending.take() {
+            Some((last_pixels, last_time)) => {
+                if last_pixels == pixels {
+                    self.pending = Some((last_pixels, last_time));
+                    return Ok(());
+                }
+                self.write_frame(&last_pixels, time.saturating_sub(last_time))?;
+            },
+            None => { },
+        }
+        self.pending = Some((pixels.to_vec(), time));
+        Ok(())
+    }
+    // Finish the animation at the given time, returns the number of frames
+    // written.
+    pub fn finish(mut self, time: u32) -> io::Result<u32> {
+        if let Some((last_pixels, last_time)) = self.pending.take() {
+            self.write_frame(&last_pixels, time.saturating_sub(last_time))?;
+        }
+        self.file.write_all(&[TRAILER])?;
+        self.file.flush()?;
+        Ok(self.frames)
+    }
+}
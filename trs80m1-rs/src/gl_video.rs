@@ -0,0 +1,764 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::warn;
+use sdl2;
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+use trs80m1_rs_core::fonts;
+use trs80m1_rs_core::hires::{self, HIRES_WIDTH, HIRES_HEIGHT};
+use trs80m1_rs_core::video::{self, VideoFrame, SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::sdl_video::{self, CrtEffects, Scaling, MAX_CURVATURE, MAX_PERSISTENCE};
+
+
+// A renderer which draws the screen with OpenGL directly, instead of going
+// through the SDL2 renderer:
+//
+// The video memory is uploaded into a 64x16 texture for each frame, and the
+// first shader looks each dot of the screen up in it and in a texture with
+// all the glyphs, drawing the screen at its native resolution into a texture
+// of its own.  The bitmap of the graphics board, when it's shown, is uploaded
+// into a 384x192 texture, and mixed in by the same shader, as are the
+// glitches of the bus contention, from a 64x192 texture holding the character
+// shown instead at each column of each scanline.  The afterglow of the CRT effects is done by blending the new
+// frame into what's already there.  The second shader then scales that
+// texture up to the window, applying the rest of the CRT effects on the way.
+//
+// Only OpenGL 2.1 with framebuffer objects is needed, the functions are
+// looked up through SDL2, so there's no need for any other library.
+//
+const GL_TEXTURE_2D:              u32 = 0x0DE1;
+const GL_TEXTURE_MAG_FILTER:      u32 = 0x2800;
+const GL_TEXTURE_MIN_FILTER:      u32 = 0x2801;
+const GL_TEXTURE_WRAP_S:          u32 = 0x2802;
+const GL_TEXTURE_WRAP_T:          u32 = 0x2803;
+const GL_NEAREST:                 i32 = 0x2600;
+const GL_CLAMP_TO_EDGE:           i32 = 0x812F;
+const GL_LUMINANCE:               u32 = 0x1909;
+const GL_LUMINANCE_ALPHA:         u32 = 0x190A;
+const GL_RGB:                     u32 = 0x1907;
+const GL_RGBA:                    u32 = 0x1908;
+const GL_UNSIGNED_BYTE:           u32 = 0x1401;
+const GL_FLOAT:                   u32 = 0x1406;
+const GL_UNPACK_ALIGNMENT:        u32 = 0x0CF5;
+const GL_PACK_ALIGNMENT:          u32 = 0x0D05;
+const GL_COLOR_BUFFER_BIT:        u32 = 0x4000;
+const GL_TRIANGLE_STRIP:          u32 = 0x0005;
+const GL_FRAGMENT_SHADER:         u32 = 0x8B30;
+const GL_VERTEX_SHADER:           u32 = 0x8B31;
+const GL_COMPILE_STATUS:          u32 = 0x8B81;
+const GL_LINK_STATUS:             u32 = 0x8B82;
+const GL_INFO_LOG_LENGTH:         u32 = 0x8B84;
+const GL_TEXTURE0:                u32 = 0x84C0;
+const GL_FRAMEBUFFER:             u32 = 0x8D40;
+const GL_COLOR_ATTACHMENT0:       u32 = 0x8CE0;
+const GL_FRAMEBUFFER_COMPLETE:    u32 = 0x8CD5;
+const GL_BLEND:                   u32 = 0x0BE2;
+const GL_CONSTANT_ALPHA:          u32 = 0x8003;
+const GL_ONE_MINUS_CONSTANT_ALPHA: u32 = 0x8004;
+
+// The glyphs are kept in a texture of 16 glyphs by 16 glyphs, in the order
+// of fonts::glyph_index(), the 128 of the character generator followed by
+// the 64 block graphics characters and the alternate character set.
+const ATLAS_COLUMNS:              u32 = 16;
+const ATLAS_ROWS:                 u32 = fonts::GLYPH_TOTAL / ATLAS_COLUMNS;
+const ATLAS_WIDTH:                u32 = ATLAS_COLUMNS * video::GLYPH_WIDTH;
+const ATLAS_HEIGHT:               u32 = ATLAS_ROWS * video::GLYPH_HEIGHT;
+
+const CORNER_ATTRIBUTE:           u32 = 0;
+
+// The two triangles which cover the whole viewport:
+static CORNERS: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+const VERTEX_SHADER: &str = "
+#version 120
+attribute vec2 corner;
+varying vec2 position;
+
+void main() {
+    // The position on the screen, from the top left corner.
+    position = vec2(corner.x + 1.0, 1.0 - corner.y) * 0.5;
+    gl_Position = vec4(corner, 0.0, 1.0);
+}
+";
+
+const SCREEN_SHADER: &str = "
+#version 120
+uniform sampler2D video_memory;
+uniform sampler2D glyphs;
+uniform float wide;
+uniform float alternate;
+uniform sampler2D hires;
+uniform float graphics;
+uniform sampler2D glitches;
+uniform float glitched;
+varying vec2 position;
+
+void main() {
+    vec2 pixel = floor(position * vec2(512.0, 384.0));
+    float row = floor(pixel.y / 24.0);
+    float glyph_y = floor(mod(pixel.y, 24.0) / 2.0);
+    float column;
+    float glyph_x;
+    if (wide > 0.5) {
+        column = floor(pixel.x / 16.0) * 2.0;
+        glyph_x = floor(mod(pixel.x, 16.0) / 2.0);
+    } else {
+        column = floor(pixel.x / 8.0);
+        glyph_x = mod(pixel.x, 8.0);
+    }
+    vec4 code_texel = texture2D(video_memory, vec2((column + 0.5) / 64.0, (row + 0.5) / 16.0));
+    if (glitched > 0.5) {
+        vec4 glitch = texture2D(glitches, vec2((column + 0.5) / 64.0, (row * 12.0 + glyph_y + 0.5) / 192.0));
+        if (glitch.a > 0.5) {
+            code_texel = glitch;
+        }
+    }
+    float code = floor(code_texel.r * 255.0 + 0.5);
+    float glyph = code < 128.0 ? code : 128.0 + mod(code, 64.0);
+    if (code >= 192.0 && alternate > 0.5) {
+        glyph += 64.0;
+    }
+    vec2 texel = vec2(mod(glyph, 16.0) * 8.0 + glyph_x, floor(glyph / 16.0) * 12.0 + glyph_y);
+    float lit = texture2D(glyphs, (texel + 0.5) / vec2(128.0, 192.0)).r;
+    if (graphics > 0.5) {
+        vec2 hires_pixel = floor(pixel * vec2(384.0 / 512.0, 192.0 / 384.0));
+        lit = max(lit, texture2D(hires, (hires_pixel + 0.5) / vec2(384.0, 192.0)).r);
+    }
+    gl_FragColor = vec4(lit, lit, lit, 1.0);
+}
+";
+
+const OUTPUT_SHADER: &str = "
+#version 120
+uniform sampler2D screen;
+uniform vec3 bg_color;
+uniform vec3 fg_color;
+uniform float scanlines;
+uniform float mask;
+uniform float curvature;
+varying vec2 position;
+
+void main() {
+    vec2 point = position;
+
+    // The top and bottom are bent by shrinking the columns, and the sides
+    // by shrinking the rows, away from the middle.
+    if (curvature > 0.0) {
+        float offset_x = point.x * 2.0 - 1.0;
+        point.y = 0.5 + (point.y - 0.5) / (1.0 - curvature * offset_x * offset_x);
+        float offset_y = point.y * 2.0 - 1.0;
+        point.x = 0.5 + (point.x - 0.5) / (1.0 - curvature * offset_y * offset_y);
+        if (point.x < 0.0 || point.x > 1.0 || point.y < 0.0 || point.y > 1.0) {
+            gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+    }
+    vec2 pixel = min(floor(point * vec2(512.0, 384.0)), vec2(511.0, 383.0));
+    float lit = texture2D(screen, vec2((pixel.x + 0.5) / 512.0, 1.0 - (pixel.y + 0.5) / 384.0)).r;
+    vec3 color = mix(bg_color, fg_color, lit);
+
+    if (mod(pixel.y, 2.0) == 1.0) {
+        color *= 1.0 - scanlines;
+    }
+    float phosphor = mod(pixel.x, 3.0);
+    color *= vec3(phosphor == 0.0 ? 1.0 : 1.0 - mask,
+                  phosphor == 1.0 ? 1.0 : 1.0 - mask,
+                  phosphor == 2.0 ? 1.0 : 1.0 - mask);
+    gl_FragColor = vec4(color, 1.0);
+}
+";
+
+const OVERLAY_SHADER: &str = "
+#version 120
+uniform sampler2D text;
+uniform vec3 bg_color;
+uniform vec3 fg_color;
+varying vec2 position;
+
+void main() {
+    gl_FragColor = vec4(mix(bg_color, fg_color, texture2D(text, position).r), 1.0);
+}
+";
+
+// The OpenGL functions, looked up by name when the renderer is created:
+macro_rules! gl_functions {
+    ($($field:ident: $name:literal => fn($($arg:ty),*) $(-> $ret:ty)?;)*) => {
+        struct Gl {
+            $($field: unsafe extern "system" fn($($arg),*) $(-> $ret)?,)*
+        }
+
+        impl Gl {
+            fn load(video_ctxt: &sdl2::VideoSubsystem) -> Result<Gl, String> {
+                Ok(Gl {
+                    $($field: {
+                        let address = video_ctxt.gl_get_proc_address($name);
+                        if address.is_null() {
+                            return Err(format!("the OpenGL function `{}' is missing", $name));
+                        }
+                        // The address comes from the OpenGL driver itself,
+                        // and has the signature given in the specification.
+                        unsafe { std::mem::transmute::<*const (), unsafe extern "system" fn($($arg),*) $(-> $ret)?>(address) }
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+gl_functions! {
+    viewport:                   "glViewport"                => fn(i32, i32, i32, i32);
+    clear_color:                "glClearColor"              => fn(f32, f32, f32, f32);
+    clear:                      "glClear"                   => fn(u32);
+    enable:                     "glEnable"                  => fn(u32);
+    disable:                    "glDisable"                 => fn(u32);
+    blend_func:                 "glBlendFunc"               => fn(u32, u32);
+    blend_color:                "glBlendColor"              => fn(f32, f32, f32, f32);
+    pixel_storei:               "glPixelStorei"             => fn(u32, i32);
+    read_pixels:                "glReadPixels"              => fn(i32, i32, i32, i32, u32, u32, *mut c_void);
+    gen_textures:               "glGenTextures"             => fn(i32, *mut u32);
+    delete_textures:            "glDeleteTextures"          => fn(i32, *const u32);
+    bind_texture:               "glBindTexture"             => fn(u32, u32);
+    active_texture:             "glActiveTexture"           => fn(u32);
+    tex_parameteri:             "glTexParameteri"           => fn(u32, u32, i32);
+    tex_image_2d:               "glTexImage2D"              => fn(u32, i32, i32, i32, i32, i32, u32, u32, *const c_void);
+    tex_sub_image_2d:           "glTexSubImage2D"           => fn(u32, i32, i32, i32, i32, i32, u32, u32, *const c_void);
+    create_shader:              "glCreateShader"            => fn(u32) -> u32;
+    delete_shader:              "glDeleteShader"            => fn(u32);
+    shader_source:              "glShaderSource"            => fn(u32, i32, *const *const c_char, *const i32);
+    compile_shader:             "glCompileShader"           => fn(u32);
+    get_shaderiv:               "glGetShaderiv"             => fn(u32, u32, *mut i32);
+    get_shader_info_log:        "glGetShaderInfoLog"        => fn(u32, i32, *mut i32, *mut c_char);
+    create_program:             "glCreateProgram"           => fn() -> u32;
+    delete_program:             "glDeleteProgram"           => fn(u32);
+    attach_shader:              "glAttachShader"            => fn(u32, u32);
+    bind_attrib_location:       "glBindAttribLocation"      => fn(u32, u32, *const c_char);
+    link_program:               "glLinkProgram"             => fn(u32);
+    get_programiv:              "glGetProgramiv"            => fn(u32, u32, *mut i32);
+    get_program_info_log:       "glGetProgramInfoLog"       => fn(u32, i32, *mut i32, *mut c_char);
+    use_program:                "glUseProgram"              => fn(u32);
+    get_uniform_location:       "glGetUniformLocation"      => fn(u32, *const c_char) -> i32;
+    uniform1i:                  "glUniform1i"               => fn(i32, i32);
+    uniform1f:                  "glUniform1f"               => fn(i32, f32);
+    uniform3f:                  "glUniform3f"               => fn(i32, f32, f32, f32);
+    enable_vertex_attrib_array: "glEnableVertexAttribArray" => fn(u32);
+    vertex_attrib_pointer:      "glVertexAttribPointer"     => fn(u32, i32, u32, u8, i32, *const c_void);
+    draw_arrays:                "glDrawArrays"              => fn(u32, i32, i32);
+    gen_framebuffers:           "glGenFramebuffers"         => fn(i32, *mut u32);
+    delete_framebuffers:        "glDeleteFramebuffers"      => fn(i32, *const u32);
+    bind_framebuffer:           "glBindFramebuffer"         => fn(u32, u32);
+    framebuffer_texture_2d:     "glFramebufferTexture2D"    => fn(u32, u32, u32, u32, i32);
+    check_framebuffer_status:   "glCheckFramebufferStatus"  => fn(u32) -> u32;
+}
+
+struct ScreenProgram {
+    program:      u32,
+    video_memory: i32,
+    glyphs:       i32,
+    wide:         i32,
+    alternate:    i32,
+    hires:        i32,
+    graphics:     i32,
+    glitches:     i32,
+    glitched:     i32,
+}
+
+struct OutputProgram {
+    program:      u32,
+    screen:       i32,
+    bg_color:     i32,
+    fg_color:     i32,
+    scanlines:    i32,
+    mask:         i32,
+    curvature:    i32,
+}
+
+struct OverlayProgram {
+    program:      u32,
+    text:         i32,
+    bg_color:     i32,
+    fg_color:     i32,
+}
+
+pub struct GlScreen {
+    gl:             Gl,
+    screen_program: ScreenProgram,
+    output_program: OutputProgram,
+    overlay_program: OverlayProgram,
+    video_memory:   u32,
+    glyphs:         u32,
+    screen:         u32,
+    overlay:        u32,
+    hires:          u32,
+    glitches:       u32,
+    framebuffer:    u32,
+    glow_valid:     bool,    // Whether `screen' holds the previous frame.
+
+    bg_color:       (u8, u8, u8),
+    fg_color:       (u8, u8, u8),
+    font:           Box<[u8]>,
+
+    // The context has to go away before the window does.
+    context:        sdl2::video::GLContext,
+    window:         sdl2::video::Window,
+}
+
+fn color_components((red, green, blue): (u8, u8, u8)) -> (f32, f32, f32) {
+    ((red as f32) / 255.0, (green as f32) / 255.0, (blue as f32) / 255.0)
+}
+
+impl GlScreen {
+    // Create a window with an OpenGL context, and set up the shaders and
+    // textures in it.
+    pub fn create(video_ctxt: &sdl2::VideoSubsystem, title: &str, width: u32, height: u32, use_vsync: bool) -> Result<GlScreen, String> {
+        let gl_attr = video_ctxt.gl_attr();
+        gl_attr.set_context_version(2, 1);
+        gl_attr.set_double_buffer(true);
+
+        let window = video_ctxt.window(title, width, height).position_centered().opengl().build().map_err(|error| error.to_string())?;
+        let context = window.gl_create_context()?;
+        window.gl_make_current(&context)?;
+        let swap_interval = if use_vsync { sdl2::video::SwapInterval::VSync } else { sdl2::video::SwapInterval::Immediate };
+        if let Err(error) = video_ctxt.gl_set_swap_interval(swap_interval) {
+            warn!("Failed to {} vertical synchronization for OpenGL: {}.", if use_vsync { "enable" } else { "disable" }, error);
+        }
+
+        let gl = Gl::load(video_ctxt)?;
+        let screen_program = {
+            let program = link_program(&gl, SCREEN_SHADER)?;
+            ScreenProgram {
+                program,
+                video_memory: uniform_location(&gl, program, "video_memory"),
+                glyphs:       uniform_location(&gl, program, "glyphs"),
+                wide:         uniform_location(&gl, program, "wide"),
+                alternate:    uniform_location(&gl, program, "alternate"),
+                hires:        uniform_location(&gl, program, "hires"),
+                graphics:     uniform_location(&gl, program, "graphics"),
+                glitches:     uniform_location(&gl, program, "glitches"),
+                glitched:     uniform_location(&gl, program, "glitched"),
+            }
+        };
+        let output_program = {
+            let program = link_program(&gl, OUTPUT_SHADER)?;
+            OutputProgram {
+                program,
+                screen:    uniform_location(&gl, program, "screen"),
+                bg_color:  uniform_location(&gl, program, "bg_color"),
+                fg_color:  uniform_location(&gl, program, "fg_color"),
+                scanlines: uniform_location(&gl, program, "scanlines"),
+                mask:      uniform_location(&gl, program, "mask"),
+                curvature: uniform_location(&gl, program, "curvature"),
+            }
+        };
+        let overlay_program = {
+            let program = link_program(&gl, OVERLAY_SHADER)?;
+            OverlayProgram {
+                program,
+                text:      uniform_location(&gl, program, "text"),
+                bg_color:  uniform_location(&gl, program, "bg_color"),
+                fg_color:  uniform_location(&gl, program, "fg_color"),
+            }
+        };
+
+        let mut textures = [0u32; 6];
+        let mut framebuffer = 0u32;
+        let status = unsafe {
+            (gl.gen_textures)(textures.len() as i32, textures.as_mut_ptr());
+            (gl.pixel_storei)(GL_UNPACK_ALIGNMENT, 1);
+            (gl.pixel_storei)(GL_PACK_ALIGNMENT, 1);
+
+            let sizes = [(video::SCREEN_COLS, video::SCREEN_ROWS, GL_LUMINANCE),
+                         (ATLAS_WIDTH, ATLAS_HEIGHT, GL_LUMINANCE),
+                         (SCREEN_WIDTH, SCREEN_HEIGHT, GL_RGBA),
+                         (1, 1, GL_LUMINANCE),
+                         (HIRES_WIDTH, HIRES_HEIGHT, GL_LUMINANCE),
+                         (video::SCREEN_COLS, video::DISPLAY_LINES, GL_LUMINANCE_ALPHA)];
+            for (texture, (texture_width, texture_height, format)) in textures.iter().zip(sizes.iter()) {
+                (gl.bind_texture)(GL_TEXTURE_2D, *texture);
+                (gl.tex_parameteri)(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST);
+                (gl.tex_parameteri)(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST);
+                (gl.tex_parameteri)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+                (gl.tex_parameteri)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+                (gl.tex_image_2d)(GL_TEXTURE_2D, 0, *format as i32, *texture_width as i32, *texture_height as i32, 0, *format, GL_UNSIGNED_BYTE, ptr::null());
+            }
+
+            (gl.gen_framebuffers)(1, &mut framebuffer);
+            (gl.bind_framebuffer)(GL_FRAMEBUFFER, framebuffer);
+            (gl.framebuffer_texture_2d)(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, textures[2], 0);
+            let status = (gl.check_framebuffer_status)(GL_FRAMEBUFFER);
+            (gl.bind_framebuffer)(GL_FRAMEBUFFER, 0);
+
+            (gl.enable_vertex_attrib_array)(CORNER_ATTRIBUTE);
+            (gl.vertex_attrib_pointer)(CORNER_ATTRIBUTE, 2, GL_FLOAT, 0, 0, CORNERS.as_ptr() as *const c_void);
+            status
+        };
+
+        let screen = GlScreen {
+            gl,
+            screen_program,
+            output_program,
+            overlay_program,
+            video_memory:   textures[0],
+            glyphs:         textures[1],
+            screen:         textures[2],
+            overlay:        textures[3],
+            hires:          textures[4],
+            glitches:       textures[5],
+            framebuffer,
+            glow_valid:     false,
+            bg_color:       (0, 0, 0),
+            fg_color:       (0, 0, 0),
+            font:           Box::new([]),
+            context,
+            window,
+        };
+        if status != GL_FRAMEBUFFER_COMPLETE {
+            return Err(format!("the screen can't be rendered into a texture (framebuffer status 0x{:04X})", status));
+        }
+        Ok(screen)
+    }
+    pub fn window(&self) -> &sdl2::video::Window {
+        &self.window
+    }
+    pub fn window_mut(&mut self) -> &mut sdl2::video::Window {
+        &mut self.window
+    }
+    // Set the colors of the screen and the font of the character generator.
+    pub fn set_glyphs(&mut self, bg_color: (u8, u8, u8), fg_color: (u8, u8, u8), font: &[u8]) {
+        let mut atlas = vec![0u8; (ATLAS_WIDTH * ATLAS_HEIGHT) as usize];
+
+        for glyph in 0..fonts::GLYPH_TOTAL {
+            let rows = fonts::glyph_rows(font, glyph);
+            let left = (glyph % ATLAS_COLUMNS) * video::GLYPH_WIDTH;
+            let top = (glyph / ATLAS_COLUMNS) * video::GLYPH_HEIGHT;
+            for (y, dots) in rows.iter().enumerate() {
+                for x in 0..video::GLYPH_WIDTH {
+                    if (dots & (1 << x)) != 0 {
+                        atlas[(((top + (y as u32)) * ATLAS_WIDTH) + left + x) as usize] = 255;
+                    }
+                }
+            }
+        }
+        unsafe {
+            (self.gl.bind_texture)(GL_TEXTURE_2D, self.glyphs);
+            (self.gl.tex_sub_image_2d)(GL_TEXTURE_2D, 0, 0, 0, ATLAS_WIDTH as i32, ATLAS_HEIGHT as i32, GL_LUMINANCE, GL_UNSIGNED_BYTE, atlas.as_ptr() as *const c_void);
+        }
+        self.bg_color = bg_color;
+        self.fg_color = fg_color;
+        self.font = font.into();
+    }
+    // Fill the window with the given color.
+    pub fn clear(&mut self, color: (u8, u8, u8)) {
+        let (red, green, blue) = color_components(color);
+        let (width, height) = self.window.drawable_size();
+        unsafe {
+            (self.gl.viewport)(0, 0, width as i32, height as i32);
+            (self.gl.clear_color)(red, green, blue, 1.0);
+            (self.gl.clear)(GL_COLOR_BUFFER_BIT);
+        }
+    }
+    // The area of the window the screen is scaled up into, from the bottom
+    // left corner, the way OpenGL counts.
+    fn output_area(&self, scaling: Scaling) -> (u32, u32, u32, u32) {
+        let (width, height) = self.window.drawable_size();
+        let (output_width, output_height) = match scaling {
+            Scaling::Stretch => {
+                (width, height)
+            },
+            Scaling::Integer if width >= SCREEN_WIDTH && height >= SCREEN_HEIGHT => {
+                let scale = (width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT);
+                (SCREEN_WIDTH * scale, SCREEN_HEIGHT * scale)
+            },
+            Scaling::Integer | Scaling::Aspect => {
+                if (width * SCREEN_HEIGHT) > (height * SCREEN_WIDTH) {
+                    ((height * SCREEN_WIDTH) / SCREEN_HEIGHT, height)
+                } else {
+                    (width, (width * SCREEN_HEIGHT) / SCREEN_WIDTH)
+                }
+            },
+        };
+        ((width - output_width) / 2, (height - output_height) / 2, output_width, output_height)
+    }
+    // Render the screen contents, the caller presents them.
+    pub fn render(&mut self, frame: &VideoFrame, scaling: Scaling, effects: CrtEffects) {
+        let gl = &self.gl;
+
+        // The new frame is mixed into the afterglow of the previous ones,
+        // with the transparency of the part that fades away.
+        let fade = if effects.persistence > 0 && self.glow_valid {
+            1.0 - (effects.persistence.min(MAX_PERSISTENCE) as f32) / 100.0
+        } else {
+            1.0
+        };
+        self.glow_valid = effects.persistence > 0;
+
+        let (x, y, width, height) = self.output_area(scaling);
+        let curvature = MAX_CURVATURE * (effects.curvature.min(100) as f32) / 100.0;
+        let (bg_red, bg_green, bg_blue) = color_components(self.bg_color);
+        let (fg_red, fg_green, fg_blue) = color_components(self.fg_color);
+
+        unsafe {
+            (gl.active_texture)(GL_TEXTURE0);
+            (gl.bind_texture)(GL_TEXTURE_2D, self.video_memory);
+            (gl.tex_sub_image_2d)(GL_TEXTURE_2D, 0, 0, 0, video::SCREEN_COLS as i32, video::SCREEN_ROWS as i32, GL_LUMINANCE, GL_UNSIGNED_BYTE, frame.memory.as_ptr() as *const c_void);
+            (gl.active_texture)(GL_TEXTURE0 + 1);
+            (gl.bind_texture)(GL_TEXTURE_2D, self.glyphs);
+            if let Some(hires) = frame.hires_bitmap() {
+                let bitmap = hires_pixels(hires);
+                (gl.active_texture)(GL_TEXTURE0 + 2);
+                (gl.bind_texture)(GL_TEXTURE_2D, self.hires);
+                (gl.tex_sub_image_2d)(GL_TEXTURE_2D, 0, 0, 0, HIRES_WIDTH as i32, HIRES_HEIGHT as i32, GL_LUMINANCE, GL_UNSIGNED_BYTE, bitmap.as_ptr() as *const c_void);
+            }
+            if !frame.glitches.is_empty() {
+                let glitches = glitch_texels(frame);
+                (gl.active_texture)(GL_TEXTURE0 + 3);
+                (gl.bind_texture)(GL_TEXTURE_2D, self.glitches);
+                (gl.tex_sub_image_2d)(GL_TEXTURE_2D, 0, 0, 0, video::SCREEN_COLS as i32, video::DISPLAY_LINES as i32, GL_LUMINANCE_ALPHA, GL_UNSIGNED_BYTE, glitches.as_ptr() as *const c_void);
+            }
+
+            (gl.bind_framebuffer)(GL_FRAMEBUFFER, self.framebuffer);
+            (gl.viewport)(0, 0, SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+            if fade < 1.0 {
+                (gl.enable)(GL_BLEND);
+                (gl.blend_color)(0.0, 0.0, 0.0, fade);
+                (gl.blend_func)(GL_CONSTANT_ALPHA, GL_ONE_MINUS_CONSTANT_ALPHA);
+            }
+            (gl.use_program)(self.screen_program.program);
+            (gl.uniform1i)(self.screen_program.video_memory, 0);
+            (gl.uniform1i)(self.screen_program.glyphs, 1);
+            (gl.uniform1f)(self.screen_program.wide, if frame.modesel { 1.0 } else { 0.0 });
+            (gl.uniform1f)(self.screen_program.alternate, if frame.alternate { 1.0 } else { 0.0 });
+            (gl.uniform1i)(self.screen_program.hires, 2);
+            (gl.uniform1f)(self.screen_program.graphics, if frame.hires_bitmap().is_some() { 1.0 } else { 0.0 });
+            (gl.uniform1i)(self.screen_program.glitches, 3);
+            (gl.uniform1f)(self.screen_program.glitched, if frame.glitches.is_empty() { 0.0 } else { 1.0 });
+            (gl.draw_arrays)(GL_TRIANGLE_STRIP, 0, 4);
+            (gl.disable)(GL_BLEND);
+            (gl.bind_framebuffer)(GL_FRAMEBUFFER, 0);
+
+            let (window_width, window_height) = self.window.drawable_size();
+            (gl.viewport)(0, 0, window_width as i32, window_height as i32);
+            (gl.clear_color)(bg_red, bg_green, bg_blue, 1.0);
+            (gl.clear)(GL_COLOR_BUFFER_BIT);
+
+            (gl.viewport)(x as i32, y as i32, width as i32, height as i32);
+            (gl.active_texture)(GL_TEXTURE0);
+            (gl.bind_texture)(GL_TEXTURE_2D, self.screen);
+            (gl.use_program)(self.output_program.program);
+            (gl.uniform1i)(self.output_program.screen, 0);
+            (gl.uniform3f)(self.output_program.bg_color, bg_red, bg_green, bg_blue);
+            (gl.uniform3f)(self.output_program.fg_color, fg_red, fg_green, fg_blue);
+            (gl.uniform1f)(self.output_program.scanlines, (effects.scanlines.min(100) as f32) / 100.0);
+            (gl.uniform1f)(self.output_program.mask, (effects.mask.min(100) as f32) / 100.0);
+            (gl.uniform1f)(self.output_program.curvature, curvature);
+            (gl.draw_arrays)(GL_TRIANGLE_STRIP, 0, 4);
+        }
+    }
+    // Draw the lines of the status overlay into the top left corner of the
+    // screen, over what's already been rendered, the caller presents it.
+    pub fn render_overlay(&mut self, lines: &[String], scaling: Scaling) {
+        let (columns, rows) = sdl_video::overlay_size(lines);
+        let (text_width, text_height) = (columns * video::GLYPH_WIDTH, rows * video::GLYPH_HEIGHT);
+        if text_width == 0 || text_height == 0 {
+            return;
+        }
+        let text = sdl_video::rasterize_text(lines, columns, rows, &self.font);
+
+        // The overlay is scaled up along with the screen, and placed the
+        // same distance from its top left corner as in the SDL renderer.
+        let (x, y, _width, height) = self.output_area(scaling);
+        let scale = (height as f32) / (SCREEN_HEIGHT as f32);
+        let overlay_width = ((text_width as f32) * scale) as i32;
+        let overlay_height = ((text_height as f32) * scale) as i32;
+        let overlay_x = (x as i32) + (((sdl_video::OVERLAY_MARGIN as f32) * scale) as i32);
+        let overlay_y = ((y + height) as i32) - (((sdl_video::OVERLAY_MARGIN as f32) * scale) as i32) - overlay_height;
+        self.render_bitmap(&text, text_width, text_height, (overlay_x, overlay_y, overlay_width, overlay_height));
+    }
+    // Draw a bitmap of `width' by `height' dots, 1 for the lit ones and 0
+    // for the rest, stretched over the given area of the screen, in its
+    // dots, over what's already been rendered, the caller presents it.
+    pub fn render_bitmap_at(&mut self, bitmap: &[u8], width: u32, height: u32, area: (i32, i32, u32, u32), scaling: Scaling) {
+        let (x, y, output_width, output_height) = self.output_area(scaling);
+        let scale_x = (output_width as f32) / (SCREEN_WIDTH as f32);
+        let scale_y = (output_height as f32) / (SCREEN_HEIGHT as f32);
+        let (area_x, area_y, area_width, area_height) = area;
+
+        // The rows of the window are counted from the bottom up.
+        let viewport_x = (x as i32) + (((area_x as f32) * scale_x) as i32);
+        let viewport_y = (y as i32) + ((((SCREEN_HEIGHT as i32) - area_y - (area_height as i32)) as f32 * scale_y) as i32);
+        let viewport_width = ((area_width as f32) * scale_x) as i32;
+        let viewport_height = ((area_height as f32) * scale_y) as i32;
+        self.render_bitmap(bitmap, width, height, (viewport_x, viewport_y, viewport_width, viewport_height));
+    }
+    // The position of a point of the window, as the mouse events give it,
+    // in the dots of the screen, which is off the screen for the points
+    // around it.
+    pub fn screen_position(&self, x: i32, y: i32, scaling: Scaling) -> (i32, i32) {
+        let (window_width, window_height) = self.window.size();
+        let (drawable_width, drawable_height) = self.window.drawable_size();
+        let (output_x, output_y, output_width, output_height) = self.output_area(scaling);
+
+        // With high DPI displays, the window can have more pixels than its
+        // size in the coordinates of the mouse.
+        let x = (x as f32) * (drawable_width as f32) / (window_width.max(1) as f32);
+        let y = (y as f32) * (drawable_height as f32) / (window_height.max(1) as f32);
+        let screen_x = (x - (output_x as f32)) * (SCREEN_WIDTH as f32) / (output_width.max(1) as f32);
+        let screen_y = (y - (output_y as f32)) * (SCREEN_HEIGHT as f32) / (output_height.max(1) as f32);
+        (screen_x.floor() as i32, screen_y.floor() as i32)
+    }
+    // Draw a bitmap into the given viewport, in the pixels of the window
+    // counted from its bottom left corner.
+    fn render_bitmap(&mut self, bitmap: &[u8], width: u32, height: u32, viewport: (i32, i32, i32, i32)) {
+        let text: Vec<u8> = bitmap.iter().map(|lit| lit * 255).collect();
+        let (viewport_x, viewport_y, viewport_width, viewport_height) = viewport;
+        let (bg_red, bg_green, bg_blue) = color_components(self.bg_color);
+        let (fg_red, fg_green, fg_blue) = color_components(self.fg_color);
+
+        let gl = &self.gl;
+        unsafe {
+            (gl.active_texture)(GL_TEXTURE0);
+            (gl.bind_texture)(GL_TEXTURE_2D, self.overlay);
+            (gl.tex_image_2d)(GL_TEXTURE_2D, 0, GL_LUMINANCE as i32, width as i32, height as i32, 0, GL_LUMINANCE, GL_UNSIGNED_BYTE, text.as_ptr() as *const c_void);
+
+            (gl.viewport)(viewport_x, viewport_y, viewport_width, viewport_height);
+            (gl.use_program)(self.overlay_program.program);
+            (gl.uniform1i)(self.overlay_program.text, 0);
+            (gl.uniform3f)(self.overlay_program.bg_color, bg_red, bg_green, bg_blue);
+            (gl.uniform3f)(self.overlay_program.fg_color, fg_red, fg_green, fg_blue);
+            (gl.draw_arrays)(GL_TRIANGLE_STRIP, 0, 4);
+        }
+    }
+    pub fn present(&mut self) {
+        self.window.gl_swap_window();
+    }
+    // Read back the picture rendered into the window, at the resolution of
+    // the window, as rows of RGB pixels.
+    pub fn capture_window(&mut self) -> Result<(u32, u32, Vec<u8>), String> {
+        let (width, height) = self.window.drawable_size();
+        let row_size = (width * 3) as usize;
+        let mut pixels = vec![0u8; row_size * (height as usize)];
+
+        unsafe {
+            (self.gl.read_pixels)(0, 0, width as i32, height as i32, GL_RGB, GL_UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+        }
+
+        // OpenGL reads the rows from the bottom up.
+        let flipped = pixels.chunks(row_size).rev().flatten().copied().collect();
+        Ok((width, height, flipped))
+    }
+    // The screen contents at their native resolution, without any of the
+    // CRT effects, as rows of RGB pixels.
+    pub fn capture_native(&self, frame: &VideoFrame) -> Result<(u32, u32, Vec<u8>), String> {
+        Ok(sdl_video::rasterize_rgb(frame, &self.font, self.bg_color, self.fg_color))
+    }
+}
+
+impl Drop for GlScreen {
+    fn drop(&mut self) {
+        let textures = [self.video_memory, self.glyphs, self.screen, self.overlay, self.hires, self.glitches];
+        if self.window.gl_make_current(&self.context).is_ok() {
+            unsafe {
+                (self.gl.delete_framebuffers)(1, &self.framebuffer);
+                (self.gl.delete_textures)(textures.len() as i32, textures.as_ptr());
+                (self.gl.delete_program)(self.screen_program.program);
+                (self.gl.delete_program)(self.output_program.program);
+                (self.gl.delete_program)(self.overlay_program.program);
+            }
+        }
+    }
+}
+
+// The bitmap of the graphics board, one byte for each pixel.
+fn hires_pixels(hires: &hires::HiResFrame) -> Vec<u8> {
+    (0..HIRES_HEIGHT).flat_map(|y| (0..HIRES_WIDTH).map(move |x| if hires.pixel_at(x, y) { 255 } else { 0 })).collect()
+}
+
+// The character shown at each column of each scanline by the glitches, with
+// the columns left alone being transparent.
+fn glitch_texels(frame: &VideoFrame) -> Vec<u8> {
+    let mut texels = vec![0u8; (video::SCREEN_COLS * video::DISPLAY_LINES * 2) as usize];
+    for glitch in frame.glitches.iter() {
+        let index = (((glitch.line * video::SCREEN_COLS) + glitch.column) * 2) as usize;
+        texels[index] = glitch.value;
+        texels[index + 1] = 255;
+    }
+    texels
+}
+
+fn uniform_location(gl: &Gl, program: u32, name: &str) -> i32 {
+    let name = CString::new(name).unwrap();
+    unsafe { (gl.get_uniform_location)(program, name.as_ptr()) }
+}
+
+fn compile_shader(gl: &Gl, kind: u32, source: &str) -> Result<u32, String> {
+    let source = CString::new(source).unwrap();
+    unsafe {
+        let shader = (gl.create_shader)(kind);
+        (gl.shader_source)(shader, 1, &source.as_ptr(), ptr::null());
+        (gl.compile_shader)(shader);
+
+        let mut status = 0;
+        (gl.get_shaderiv)(shader, GL_COMPILE_STATUS, &mut status);
+        if status == 0 {
+            let mut log_length = 0;
+            (gl.get_shaderiv)(shader, GL_INFO_LOG_LENGTH, &mut log_length);
+            let mut log = vec![0u8; log_length.max(1) as usize];
+            (gl.get_shader_info_log)(shader, log.len() as i32, ptr::null_mut(), log.as_mut_ptr() as *mut c_char);
+            (gl.delete_shader)(shader);
+            return Err(format!("failed to compile a shader: {}", String::from_utf8_lossy(&log).trim_end_matches('\0').trim()));
+        }
+        Ok(shader)
+    }
+}
+
+// Link the given fragment shader with the vertex shader shared by both of
+// the programs.
+fn link_program(gl: &Gl, fragment_source: &str) -> Result<u32, String> {
+    let vertex_shader = compile_shader(gl, GL_VERTEX_SHADER, VERTEX_SHADER)?;
+    let fragment_shader = match compile_shader(gl, GL_FRAGMENT_SHADER, fragment_source) {
+        Ok(shader) => { shader },
+        Err(error) => {
+            unsafe { (gl.delete_shader)(vertex_shader); }
+            return Err(error);
+        },
+    };
+    let corner = CString::new("corner").unwrap();
+    unsafe {
+        let program = (gl.create_program)();
+        (gl.attach_shader)(program, vertex_shader);
+        (gl.attach_shader)(program, fragment_shader);
+        (gl.bind_attrib_location)(program, CORNER_ATTRIBUTE, corner.as_ptr());
+        (gl.link_program)(program);
+
+        // The shaders stay around for as long as the program does.
+        (gl.delete_shader)(vertex_shader);
+        (gl.delete_shader)(fragment_shader);
+
+        let mut status = 0;
+        (gl.get_programiv)(program, GL_LINK_STATUS, &mut status);
+        if status == 0 {
+            let mut log_length = 0;
+            (gl.get_programiv)(program, GL_INFO_LOG_LENGTH, &mut log_length);
+            let mut log = vec![0u8; log_length.max(1) as usize];
+            (gl.get_program_info_log)(program, log.len() as i32, ptr::null_mut(), log.as_mut_ptr() as *mut c_char);
+            (gl.delete_program)(program);
+            return Err(format!("failed to link a shader program: {}", String::from_utf8_lossy(&log).trim_end_matches('\0').trim()));
+        }
+        Ok(program)
+    }
+}
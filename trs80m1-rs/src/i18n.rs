@@ -0,0 +1,169 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::sync::RwLock;
+
+
+// Translations of the texts of the curses-based interface.  The English
+// texts are the keys, and the translations are kept in gettext-style
+// catalogs in the `po' directory, one per language, built into the program,
+// so that they can be edited with the usual tools for them.  A text with no
+// translation in the catalog is shown in English.
+//
+// The texts with arguments mark them with `{}', taken in order, or with
+// `{0}', `{1}' and so on, for the translations which need them in another
+// order.
+//
+// The messages logged by the emulator itself are left in English, so that
+// they can be searched for when reporting problems.
+//
+pub const LANGUAGES: [(&str, &str, &str); 2] = [
+    ("en", "English",    ""),
+    ("sk", "Slovenčina", include_str!("../po/sk.po")),
+];
+
+lazy_static! {
+    static ref CATALOG: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+// Whether the language can be used for the `language' entry of the
+// configuration file, which can also be `auto'.
+pub fn is_language(name: &str) -> bool {
+    name == "auto" || LANGUAGES.iter().any(|(code, _, _)| *code == name)
+}
+
+// Switch to the given language, or with `auto', to the one of the
+// environment, like the gettext-based programs do.
+pub fn set_language(name: &str) {
+    let code = if name == "auto" { environment_language() } else { name.to_owned() };
+    let catalog = LANGUAGES.iter().find(|(language_code, _, _)| *language_code == code).map(|(_, _, catalog)| parse_catalog(catalog)).unwrap_or_default();
+
+    *CATALOG.write().unwrap() = catalog;
+}
+
+fn environment_language() -> String {
+    for variable in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = env::var(variable) {
+            if !value.is_empty() {
+                // Like `sk_SK.UTF-8', only the language is looked at.
+                let code = value.split(['_', '.', '@']).next().unwrap_or("");
+                return code.to_lowercase();
+            }
+        }
+    }
+    "en".to_owned()
+}
+
+// The text in the current language.
+pub fn tr(text: &str) -> String {
+    match CATALOG.read().unwrap().get(text) {
+        Some(translation) => { translation.clone() },
+        None => { text.to_owned() },
+    }
+}
+
+// The text in the current language, with the arguments put in.
+pub fn tr_format(text: &str, args: &[&dyn fmt::Display]) -> String {
+    let template = tr(text);
+    let mut result = String::new();
+    let mut next_arg = 0;
+    let mut rest = template.as_str();
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let index_end = after.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(after.len());
+
+        if after[index_end..].starts_with('}') {
+            let index = if index_end == 0 {
+                next_arg += 1;
+                next_arg - 1
+            } else {
+                after[..index_end].parse::<usize>().unwrap_or(usize::MAX)
+            };
+            match args.get(index) {
+                Some(arg) => { result.push_str(&arg.to_string()); },
+                None => { result.push_str(&rest[start..start + index_end + 2]); },
+            }
+            rest = &after[index_end + 1..];
+        } else {
+            result.push('{');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// Read the `msgid' and `msgstr' pairs of a catalog, the untranslated ones
+// and the header are left out.
+fn parse_catalog(catalog: &str) -> HashMap<String, String> {
+    let mut translations = HashMap::new();
+    let mut msgid: Option<String> = None;
+    let mut msgstr: Option<String> = None;
+    let mut in_msgstr = false;
+
+    let mut finish = |msgid: &mut Option<String>, msgstr: &mut Option<String>| {
+        if let (Some(id), Some(text)) = (msgid.take(), msgstr.take()) {
+            if !id.is_empty() && !text.is_empty() {
+                translations.insert(id, text);
+            }
+        }
+    };
+    for line in catalog.lines() {
+        let line = line.trim();
+        if let Some(quoted) = line.strip_prefix("msgid ") {
+            finish(&mut msgid, &mut msgstr);
+            msgid = Some(unquote(quoted));
+            in_msgstr = false;
+        } else if let Some(quoted) = line.strip_prefix("msgstr ") {
+            msgstr = Some(unquote(quoted));
+            in_msgstr = true;
+        } else if line.starts_with('"') {
+            // A continuation of the text above.
+            let target = if in_msgstr { &mut msgstr } else { &mut msgid };
+            if let Some(text) = target {
+                text.push_str(&unquote(line));
+            }
+        }
+    }
+    finish(&mut msgid, &mut msgstr);
+    translations
+}
+
+fn unquote(quoted: &str) -> String {
+    let quoted = quoted.trim();
+    let inner = quoted.strip_prefix('"').unwrap_or(quoted);
+    let inner = inner.strip_suffix('"').unwrap_or(inner);
+    let mut text = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n') => { text.push('\n'); },
+                Some('t') => { text.push('\t'); },
+                Some(other) => { text.push(other); },
+                None => { },
+            }
+        } else {
+            text.push(ch);
+        }
+    }
+    text
+}
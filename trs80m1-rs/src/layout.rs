@@ -0,0 +1,150 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+
+// The panes of the curses-based interface, stacked from the top down: the
+// screen of the machine in the terminal-only mode, the status pane and the
+// debugger pane while they're shown, the messages, which get the rows left
+// over, and the command line at the bottom.
+//
+// One of them has the keyboard focus, which F6 moves on to the next one.
+// The status and the debugger panes can be made taller or shorter while
+// they have it, and the arrow keys scroll the pane with the focus.
+//
+pub const DEFAULT_STATUS_ROWS:   usize = 4;
+pub const DEFAULT_DEBUGGER_ROWS: usize = 10;
+
+// The rows the messages keep when the other panes grow.
+pub const MIN_MESSAGE_ROWS:      usize = 3;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Pane {
+    Machine,        // The screen of the machine, in the terminal-only mode.
+    Status,
+    Debugger,
+    Messages,
+    Prompt,
+}
+
+impl Pane {
+    // The name shown in the bottom strip while the pane has the focus.
+    pub fn name(self) -> &'static str {
+        match self {
+            Pane::Machine  => { "machine screen" },
+            Pane::Status   => { "status pane" },
+            Pane::Debugger => { "debugger pane" },
+            Pane::Messages => { "messages" },
+            Pane::Prompt   => { "command line" },
+        }
+    }
+}
+
+pub struct Layout {
+    focus:           Pane,
+    status_rows:     usize,
+    status_scroll:   usize,
+    debugger_rows:   usize,
+    debugger_scroll: usize,
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout {
+            focus:           Pane::Prompt,
+            status_rows:     DEFAULT_STATUS_ROWS,
+            status_scroll:   0,
+            debugger_rows:   DEFAULT_DEBUGGER_ROWS,
+            debugger_scroll: 0,
+        }
+    }
+    pub fn focus(&self) -> Pane {
+        self.focus
+    }
+    pub fn set_focus(&mut self, pane: Pane) {
+        self.focus = pane;
+    }
+    // Move the focus on to the next of the shown panes, given in the order
+    // they're stacked in, back to the first one after the last.
+    pub fn focus_next(&mut self, shown: &[Pane]) {
+        let next = match shown.iter().position(|pane| *pane == self.focus) {
+            Some(index) => { (index + 1) % shown.len() },
+            None => { 0 },
+        };
+        self.focus = shown.get(next).copied().unwrap_or(Pane::Prompt);
+    }
+    // The rows of the pane, the ones with a size of their own, the rest of
+    // them get none.
+    pub fn rows(&self, pane: Pane) -> usize {
+        match pane {
+            Pane::Status   => { self.status_rows },
+            Pane::Debugger => { self.debugger_rows },
+            _              => { 0 },
+        }
+    }
+    // Make the pane taller by a row if there are any spare ones, or shorter
+    // down to a single row.  Whether the size changed.
+    pub fn resize(&mut self, pane: Pane, grow: bool, spare_rows: usize) -> bool {
+        let rows = match pane {
+            Pane::Status   => { &mut self.status_rows },
+            Pane::Debugger => { &mut self.debugger_rows },
+            _              => { return false; },
+        };
+        if grow && spare_rows > 0 {
+            *rows += 1;
+            true
+        } else if !grow && *rows > 1 {
+            *rows -= 1;
+            true
+        } else {
+            false
+        }
+    }
+    // Shrink the shown panes to fit into the given rows, the debugger pane
+    // first, when the screen gets smaller.
+    pub fn fit(&mut self, available_rows: usize, status_shown: bool, debugger_shown: bool) {
+        let status_rows = if status_shown { self.status_rows } else { 0 };
+        let debugger_rows = if debugger_shown { self.debugger_rows } else { 0 };
+        let mut excess = (status_rows + debugger_rows).saturating_sub(available_rows);
+
+        if debugger_shown && excess > 0 {
+            let shrink = excess.min(self.debugger_rows - 1);
+            self.debugger_rows -= shrink;
+            excess -= shrink;
+        }
+        if status_shown && excess > 0 {
+            let shrink = excess.min(self.status_rows - 1);
+            self.status_rows -= shrink;
+        }
+    }
+    // The first of the lines of the pane which is shown at its top.
+    pub fn scroll(&self, pane: Pane) -> usize {
+        match pane {
+            Pane::Status   => { self.status_scroll },
+            Pane::Debugger => { self.debugger_scroll },
+            _              => { 0 },
+        }
+    }
+    // Scroll the pane by the given number of rows, up for the negative
+    // ones, no further than to show its last line at the bottom.
+    pub fn scroll_by(&mut self, pane: Pane, rows: isize, content_rows: usize) {
+        let max_scroll = content_rows.saturating_sub(self.rows(pane));
+        let scroll = match pane {
+            Pane::Status   => { &mut self.status_scroll },
+            Pane::Debugger => { &mut self.debugger_scroll },
+            _              => { return; },
+        };
+        *scroll = scroll.saturating_add_signed(rows).min(max_scroll);
+    }
+}
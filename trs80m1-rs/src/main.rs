@@ -19,14 +19,45 @@ extern crate pancurses;
 #[macro_use]
 extern crate lazy_static;
 extern crate log;
+extern crate miniz_oxide;
 extern crate sdl2;
 extern crate trs80m1_rs_core;
 
+mod aliases;
+mod batch_tool;
+mod cas_tool;
+mod commands;
+mod completion;
+mod confirm;
+mod disk_tool;
+#[cfg(feature = "egui")]
+mod egui_ui;
 mod emulator;
+mod file_picker;
+mod frames_tool;
+mod gif;
+#[cfg(feature = "opengl")]
+mod gl_video;
+mod i18n;
+mod layout;
+mod png;
 mod proj_config;
+mod remote_control;
+#[cfg(feature = "scripting")]
+mod scripting;
 mod user_interface;
 mod sdl_keyboard;
+mod sdl_controller;
+mod sdl_audio;
 mod sdl_video;
+mod sdl_debug;
+mod sdl_magnifier;
+mod sdl_menu;
+mod sdl_vkeyboard;
+mod titles;
+mod tool_machine;
+mod transcript;
+mod ui_theme;
 mod util;
 
 use backtrace::Backtrace;
@@ -41,6 +72,9 @@ use std::path;
 use std::process;
 use std::thread;
 
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::video;
+
 
 lazy_static! {
 
@@ -68,6 +102,11 @@ lazy_static! {
 fn print_usage(progname: &str, opts: getopts::Options) {
     let brief = format!("Usage: {} [options]", progname);
     print!("{}", opts.usage(&brief));
+    println!();
+    disk_tool::print_usage(progname);
+    cas_tool::print_usage(progname);
+    frames_tool::print_usage(progname);
+    batch_tool::print_usage(progname);
 }
 
 // Figure out the name of the executable:
@@ -98,6 +137,17 @@ fn entry_point() {
     let (video_stat_tx, video_stat_rx) = mpsc::channel();
     let emu_cmd_tx2 = emu_cmd_tx.clone();
 
+    // The video frames aren't sent over the video channel, only the newest
+    // one is kept, for the front-end to take when it's ready to draw it.
+    //
+    let shared_frame = video::SharedFrame::new();
+    let frontend_frame = shared_frame.clone();
+
+    // The user interface can cancel the long operations of the logic core.
+    //
+    let cancel_flag = emulator::CancelFlag::new();
+    let core_cancel_flag = cancel_flag.clone();
+
     // Keyboard interface.
     //
     let (kbd_codes_tx, kbd_codes_rx)  = mpsc::channel();
@@ -105,11 +155,45 @@ fn entry_point() {
     let args: Vec<String> = env::args().collect();
     let progname = get_progname(args[0].as_ref());
 
+    // The disk image manipulation mode doesn't start the emulator.
+    if args.len() > 1 && args[1] == "disk" {
+        process::exit(disk_tool::run(&progname, &args[2..]));
+    }
+    // Neither does the BASIC program conversion mode.
+    if args.len() > 1 && args[1] == "cas" {
+        process::exit(cas_tool::run(&progname, &args[2..]));
+    }
+    // Nor does the frame hashing mode, which runs the machine without
+    // showing it.
+    if args.len() > 1 && args[1] == "frames" {
+        process::exit(frames_tool::run(&progname, &args[2..]));
+    }
+    // And the batch mode, which runs it following a script.
+    if args.len() > 1 && args[1] == "batch" {
+        process::exit(batch_tool::run(&progname, &args[2..]));
+    }
+
     let mut options = getopts::Options::new();
     options.optopt("c", "cfg-dir", "Override the default config directory.", "PATH");
     options.optflag("1", "", "Use the level 1 BASIC rom.");
     options.optflag("2", "", "Use the level 2 BASIC rom.");
     options.optflag("3", "", "Use the miscellaneous rom.");
+    options.optopt("", "ram", "Use this much RAM, like 16K.", "SIZE");
+    options.optopt("", "speed", "Run at a percentage of the speed of the real machine, or `unlimited'.", "PERCENT");
+    options.optopt("", "scale", "Make the window this many times the native resolution.", "FACTOR");
+    options.optflag("", "fullscreen", "Start in the full-screen mode.");
+    options.optflag("", "windowed", "Start in a window.");
+    options.optflag("t", "terminal", "Show the screen in the terminal, without opening a window.");
+    options.optflag("", "headless", "Run without a terminal interface or a window, reading commands from the standard input.");
+    #[cfg(feature = "egui")]
+    options.optflag("g", "gui", "Use the graphical user interface instead of the curses-based one.");
+    options.optopt("", "remote", "Accept commands on a local TCP port, a host:port address or a Unix socket.", "ADDRESS");
+    options.optflag("", "remote-public", "Allow a --remote address which other computers can connect to.");
+    options.optopt("s", "script", "Run the commands in a file at startup.", "FILE");
+    options.optopt("", "cassette", "Insert a cassette for this run, leaving the configuration file as it is.", "FILE");
+    options.optopt("", "cmd", "Run a /CMD program once the machine gets to the BASIC prompt.", "FILE");
+    options.optflag("", "autostart", "Load the program on the cassette once the machine gets to the BASIC prompt.");
+    options.optflag("", "dump-config", "Print the configuration in effect, with the options given, and exit.");
     options.optflag("h", "help", "Show this help listing.");
 
     let matches = match options.parse(&args[1..]) {
@@ -135,7 +219,18 @@ fn entry_point() {
     let rom1_selected = matches.opt_present("1");
     let rom2_selected = matches.opt_present("2");
     let rom3_selected = matches.opt_present("3");
-
+    let terminal_only = matches.opt_present("t");
+    let headless = matches.opt_present("headless");
+    #[cfg(feature = "egui")]
+    let gui = matches.opt_present("g");
+    #[cfg(not(feature = "egui"))]
+    let gui = false;
+
+    if gui && (headless || terminal_only) {
+        eprintln!("The graphical user interface can't be used in the terminal-only or the headless mode.");
+        user_interface::UserInterface::enter_key_to_close_on_windows();
+        process::exit(1);
+    }
     if (rom1_selected && rom2_selected) ||
        (rom1_selected && rom3_selected) ||
        (rom2_selected && rom3_selected) {
@@ -144,16 +239,67 @@ fn entry_point() {
         user_interface::UserInterface::enter_key_to_close_on_windows();
         process::exit(1);
     }
+    let startup_media = match get_startup_media(&matches) {
+        Ok(startup_media) => { startup_media },
+        Err(error) => {
+            eprintln!("{}", error);
+            user_interface::UserInterface::enter_key_to_close_on_windows();
+            process::exit(1);
+        },
+    };
+
+    let speed_limit = match get_speed_limit(&matches) {
+        Ok(speed_limit) => { speed_limit },
+        Err(error) => {
+            eprintln!("{}", error);
+            user_interface::UserInterface::enter_key_to_close_on_windows();
+            process::exit(1);
+        },
+    };
+
+    // The configuration dump goes to the standard output by itself, so that
+    // it can be saved, with only the problems found while loading it shown.
+    let dump_config = matches.opt_present("dump-config");
+    if dump_config {
+        MSG_LOGGER.set_stdouterr_echo(false);
+    }
 
-    let config_system = match proj_config::ConfigSystem::new(&config_dir) {
+    let mut config_system = match proj_config::ConfigSystem::new(&config_dir) {
         Some(system) => { system },
         None => {
+            if dump_config {
+                print_logged_problems();
+            }
             eprintln!("Failed to initialize the emulator.");
             user_interface::UserInterface::enter_key_to_close_on_windows();
             process::exit(1);
         }
     };
 
+    // Like the ROM choice, the machine settings given on the command line
+    // are only used for this run, the configuration file stays as it is.
+    if let Err(error) = override_config_entries(&matches, &mut config_system) {
+        eprintln!("{}", error);
+        user_interface::UserInterface::enter_key_to_close_on_windows();
+        process::exit(1);
+    }
+
+    if dump_config {
+        print_logged_problems();
+        match config_system.get_effective_config() {
+            Ok(lines) => {
+                for line in lines {
+                    println!("{}", line);
+                }
+                process::exit(0);
+            },
+            Err(error) => {
+                eprintln!("Failed to retrieve the configuration in effect: {}.", error);
+                process::exit(1);
+            },
+        }
+    }
+
     let selected_rom = if rom1_selected {
         1
     } else if rom2_selected {
@@ -161,33 +307,220 @@ fn entry_point() {
     } else if rom3_selected {
         3
     } else {
-        config_system.config_items.general_default_rom
+        config_system.config_items.machine_default_rom
     };
-
-    info!("Switching to the curses-based user interface.");
-    MSG_LOGGER.set_stdouterr_echo(false);
-    let mut user_interface = match user_interface::UserInterface::new() {
-        Some(user_interface) => {
-            user_interface
+    // The script given on the command line takes the place of the one from
+    // the configuration file.
+    let startup_script = match matches.opt_str("s") {
+        Some(file) => {
+            Some((file.as_ref() as &path::Path).to_owned())
         },
         None => {
-            eprintln!("Starting the curses-based user interface failed.");
-            user_interface::UserInterface::enter_key_to_close_on_windows();
-            process::exit(1);
+            config_system.config_items.paths_startup_script.as_ref().map(|file| config_dir.join(file))
         },
     };
 
+    let aliases = config_system.config_items.interface_aliases.clone();
+
+    i18n::set_language(&config_system.config_items.interface_language);
+
+    if !headless && !gui {
+        info!("Switching to the curses-based user interface.");
+    }
+    MSG_LOGGER.set_stdouterr_echo(false);
+    // In the terminal-only mode, the screen of the machine is shown in the
+    // curses-based interface, which also takes over the keyboard.
+    //
+    // The graphical interface has to run on the main thread, so the user
+    // interface is started on a thread of its own for it, later on.
+    let (machine_screen_tx, machine_screen_rx) = mpsc::channel();
+    let user_interface = if gui {
+        None
+    } else {
+        let console = if headless {
+            user_interface::Console::Headless(user_interface::UserInterface::stdin_lines())
+        } else {
+            user_interface::Console::Curses
+        };
+        match user_interface::UserInterface::new(config_dir.clone(), if terminal_only && !headless { Some(machine_screen_rx) } else { None }, console) {
+            Some(mut user_interface) => {
+                let config_items = &config_system.config_items;
+                user_interface.set_theme(ui_theme::ThemeColors::new(config_items.interface_ui_theme, &config_items.interface_ui_colors));
+                user_interface.set_cancel_flag(cancel_flag);
+                Some(user_interface)
+            },
+            None => {
+                eprintln!("Starting the curses-based user interface failed.");
+                user_interface::UserInterface::enter_key_to_close_on_windows();
+                process::exit(1);
+            },
+        }
+    };
+
     thread::Builder::new().name("logic_core".to_owned()).spawn(move || {
-        let mut logic_core = emulator::EmulatorLogicCore::new(emu_stat_tx, video_cmd_tx, video_stat_rx, config_system, selected_rom);
+        let mut logic_core = emulator::EmulatorLogicCore::new(emu_stat_tx, video_cmd_tx, video_stat_rx, shared_frame, config_system, selected_rom, core_cancel_flag);
+        logic_core.set_speed_limit(speed_limit);
+        logic_core.set_startup_media(startup_media);
         logic_core.run(&emu_cmd_rx, &kbd_codes_rx);
     }).unwrap();
 
-    thread::Builder::new().name("sdl2_frontend".to_owned()).spawn(move || {
-        let mut sdl_frontend = emulator::EmulatorSdlFrontend::new(kbd_codes_tx, emu_cmd_tx2, video_stat_tx);
-        sdl_frontend.run(&video_cmd_rx);
-    }).unwrap();
+    if headless || terminal_only {
+        // The headless mode keeps the screen only for the screenshots.
+        let machine_screen_tx = if headless { None } else { Some(machine_screen_tx) };
+        thread::Builder::new().name("terminal_frontend".to_owned()).spawn(move || {
+            let mut terminal_frontend = emulator::EmulatorTerminalFrontend::new(machine_screen_tx, video_stat_tx, frontend_frame);
+            terminal_frontend.run(&video_cmd_rx);
+        }).unwrap();
+    } else {
+        thread::Builder::new().name("sdl2_frontend".to_owned()).spawn(move || {
+            let mut sdl_frontend = emulator::EmulatorSdlFrontend::new(kbd_codes_tx, emu_cmd_tx2, video_stat_tx, frontend_frame);
+            sdl_frontend.run(&video_cmd_rx);
+        }).unwrap();
+    }
+
+    let remote = matches.opt_str("remote").map(|address| (address, matches.opt_present("remote-public")));
+
+    #[cfg(feature = "egui")]
+    if gui {
+        let (gui_command_tx, gui_command_rx) = mpsc::channel();
+        let (gui_event_tx, gui_event_rx) = mpsc::channel();
+        let gui_emu_cmd_tx = emu_cmd_tx.clone();
+        let ui_config_dir = config_dir.clone();
+
+        let ui_thread = thread::Builder::new().name("user_interface".to_owned()).spawn(move || {
+            let console = user_interface::Console::Gui(gui_command_rx, gui_event_tx);
+            let user_interface = user_interface::UserInterface::new(ui_config_dir.clone(), None, console).expect("the user interface of the graphical one always starts");
+            run_user_interface(user_interface, aliases, remote, &ui_config_dir, startup_script, &emu_cmd_tx, &emu_stat_rx);
+        }).unwrap();
+
+        if let Err(error) = egui_ui::run(config_dir, gui_command_tx.clone(), gui_event_rx, gui_emu_cmd_tx) {
+            eprintln!("Failed to open the graphical user interface: {}", error);
+            let _ = gui_command_tx.send("exit".to_owned());
+        }
+        ui_thread.join().unwrap();
+        return;
+    }
+    if let Some(user_interface) = user_interface {
+        run_user_interface(user_interface, aliases, remote, &config_dir, startup_script, &emu_cmd_tx, &emu_stat_rx);
+    }
+}
+
+// The speed limit given on the command line, in percent, none if unlimited.
+fn get_speed_limit(matches: &getopts::Matches) -> Result<Option<u32>, String> {
+    match matches.opt_str("speed") {
+        Some(text) => {
+            match util::parse_speed_limit(&text) {
+                Some(speed_limit) => { Ok(speed_limit) },
+                None => { Err(format!("The speed `{}' isn't a percentage from 10 to 1000, or `unlimited'.", text)) },
+            }
+        },
+        None => { Ok(Some(100)) },
+    }
+}
+
+// Override the entries of the configuration file with the settings given on
+// the command line, which go through the same checks as the entries.
+// Show the warnings and the errors logged while the messages weren't echoed.
+fn print_logged_problems() {
+    for message in MSG_LOGGER.collect_messages().unwrap_or_default() {
+        if message.level < log::Level::Info {
+            eprintln!("{}", message.text);
+        }
+    }
+}
+
+fn override_config_entries(matches: &getopts::Matches, config_system: &mut proj_config::ConfigSystem) -> Result<(), String> {
+    let mut overrides = Vec::new();
+
+    if let Some(size) = matches.opt_str("ram") {
+        overrides.push(("machine_ram_size", size));
+    }
+    if let Some(text) = matches.opt_str("scale") {
+        match text.parse::<u32>() {
+            Ok(factor) if factor >= 1 => {
+                overrides.push(("video_windowed_resolution", format!("{}x{}", video::SCREEN_WIDTH * factor, video::SCREEN_HEIGHT * factor)));
+            },
+            _ => {
+                return Err(format!("The scale `{}' isn't a whole number of times the native resolution.", text));
+            },
+        }
+    }
+    match (matches.opt_present("fullscreen"), matches.opt_present("windowed")) {
+        (true, true) => {
+            return Err("The --fullscreen and --windowed options can't be used together.".to_owned());
+        },
+        (true, false) => { overrides.push(("video_fullscreen", "true".to_owned())); },
+        (false, true) => { overrides.push(("video_fullscreen", "false".to_owned())); },
+        (false, false) => { },
+    }
+
+    for (entry_specifier, value) in overrides {
+        if let Err(error) = config_system.override_config_entry(entry_specifier, &format!("= {}", value)) {
+            return Err(format!("Can't use the setting given on the command line: {}.", error));
+        }
+    }
+    Ok(())
+}
+
+// The media given on the command line.  The file names are made absolute,
+// since the ones of the configuration file are relative to the configuration
+// directory instead of the working one.
+fn get_startup_media(matches: &getopts::Matches) -> Result<emulator::StartupMedia, String> {
+    let absolute = |file: String| -> String {
+        match env::current_dir() {
+            Ok(dir) => { dir.join(file).to_string_lossy().into_owned() },
+            Err(..) => { file },
+        }
+    };
+    let cassette = match matches.opt_str("cassette") {
+        Some(file) => {
+            let format = match cassette::Format::from_path(file.as_ref()) {
+                Some(format) => { format },
+                None => {
+                    return Err(format!("Can't tell the format of the cassette file `{}' from its extension.", file));
+                },
+            };
+            // The cassette drive would create a missing file.
+            if !(file.as_ref() as &path::Path).is_file() {
+                return Err(format!("The cassette file `{}' doesn't exist.", file));
+            }
+            Some((format, absolute(file)))
+        },
+        None => { None },
+    };
+    let cmd_file = matches.opt_str("cmd").map(absolute);
+    let autostart = matches.opt_present("autostart");
+
+    if autostart && cassette.is_none() {
+        return Err("The --autostart option loads the program on the cassette, so it needs one given with --cassette.".to_owned());
+    }
+    if autostart && cmd_file.is_some() {
+        return Err("The --autostart option can't be combined with --cmd, which starts a program of its own.".to_owned());
+    }
+    Ok(emulator::StartupMedia { cassette, cmd_file, autostart })
+}
 
-    user_interface.run(&emu_cmd_tx, &emu_stat_rx, &MSG_LOGGER);
+// Set up the aliases, the remote control and the startup script, then run
+// the user interface until the emulator terminates.  The remote control comes
+// with whether it may be reached from other computers, and keeps its token in
+// the configuration directory.
+fn run_user_interface(mut user_interface: user_interface::UserInterface, aliases: Vec<aliases::Alias>, remote: Option<(String, bool)>, config_dir: &path::Path, startup_script: Option<path::PathBuf>, emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>, emu_stat_rx: &mpsc::Receiver<emulator::EmulatorStatus>) {
+    user_interface.set_aliases(aliases);
+    if let Some((address, public)) = remote {
+        match remote_control::RemoteControl::new(&address, public, config_dir) {
+            Ok(remote_control) => {
+                info!("Accepting remote commands on `{}'.", address);
+                user_interface.set_remote_control(remote_control);
+            },
+            Err(error) => {
+                error!("Failed to listen for remote commands on `{}': {}.", address, error);
+            },
+        }
+    }
+    if let Some(script_path) = startup_script {
+        user_interface.run_script(&script_path);
+    }
+    user_interface.run(emu_cmd_tx, emu_stat_rx, &MSG_LOGGER);
 }
 
 fn main() {
@@ -0,0 +1,77 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use miniz_oxide;
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+
+use crate::util;
+
+
+// A minimal writer of PNG images, just enough to save screenshots, which are
+// stored as 8-bit RGB images without interlacing.
+//
+// The image data is a sequence of rows, each prefixed by a filter type byte,
+// all compressed into a zlib stream.  Every chunk of the file is made up of
+// its length, its type, the data and a CRC-32 of the type and the data.
+//
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+const COLOR_TYPE_RGB: u8 = 2;
+const FILTER_NONE:    u8 = 0;
+
+const COMPRESSION_LEVEL: u8 = 6;
+
+fn write_chunk<W: Write>(writer: &mut W, chunk_type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(chunk_type)?;
+    writer.write_all(data)?;
+    writer.write_all(&util::crc32(&[chunk_type, data]).to_be_bytes())
+}
+
+// Save an image made up of `width' * `height' pixels of 3 bytes each (red,
+// green and blue), stored row by row, into a PNG file.
+pub fn write_rgb<P: AsRef<path::Path>>(path: P, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let row_size = (width as usize) * 3;
+    if pixels.len() != row_size * (height as usize) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("the image has {} bytes of pixels rather than {} for {}x{}", pixels.len(), row_size * (height as usize), width, height)));
+    }
+
+    let mut header: Vec<u8> = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.push(8);                  // Bits per color channel.
+    header.push(COLOR_TYPE_RGB);
+    header.push(0);                  // Compression method, always deflate.
+    header.push(0);                  // Filter method, always adaptive.
+    header.push(0);                  // No interlacing.
+
+    let mut rows: Vec<u8> = Vec::with_capacity((row_size + 1) * (height as usize));
+    for row in pixels.chunks(row_size) {
+        rows.push(FILTER_NONE);
+        rows.extend_from_slice(row);
+    }
+    let image_data = miniz_oxide::deflate::compress_to_vec_zlib(&rows, COMPRESSION_LEVEL);
+
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    file.write_all(&PNG_SIGNATURE)?;
+    write_chunk(&mut file, b"IHDR", &header)?;
+    write_chunk(&mut file, b"IDAT", &image_data)?;
+    write_chunk(&mut file, b"IEND", &[])?;
+    file.flush()
+}
@@ -15,46 +15,144 @@
 
 use home;
 use log::{info, warn, error};
+use serde::Deserialize;
 
 use std::env;
 use std::fmt;
 use std::path;
 use std::fs;
 use std::io;
+use std::mem;
 use std::num;
+use std::ops;
+use std::time;
 use std::io::prelude::*;
 
-use trs80m1_rs_core::cassette; // For cassette::Format.
+use crate::aliases;
+use crate::confirm;
+use crate::gif;
+use crate::util;
 
+use trs80m1_rs_core::bridge;   // For bridge::BridgeSpec.
+use trs80m1_rs_core::cassette; // For cassette::Format.
+use trs80m1_rs_core::hires;    // For the IO ports of the graphics board.
+use trs80m1_rs_core::memory;   // For the IO ports already in use, and the rom size.
+use trs80m1_rs_core::psg;      // For psg::DEFAULT_PORT.
+use trs80m1_rs_core::sound;    // For sound::Resampling.
+use trs80m1_rs_core::video;    // For video::TextGraphics.
+use crate::sdl_keyboard;       // For sdl_keyboard::KeyBinding.
+use crate::sdl_controller;     // For sdl_controller::ControllerBinding.
+use crate::sdl_video;          // For sdl_video::Scaling.
+use crate::ui_theme;           // For ui_theme::Theme.
+use crate::i18n;               // For i18n::is_language.
+
+
+// The configuration file is written in TOML, with a section for each part of
+// the emulator:
+//
+// The file is kept as a toml_edit document, so that its comments and layout
+// are kept when an entry gets updated, and the values are read from the typed
+// ConfigDocument which serde fills in from it.  Each entry then goes through
+// the handler of its section, which checks its value and puts it into the
+// ConfigItems, the same way as the values given with `/config change'.  A new
+// entry therefore needs both a field in the struct of its section, and a
+// handler in the section.
+//
 
 // Names for determining where to find the configuration folder and files:
 const WINDOWS_DEV_NAME:      &'static str = "DusXMT";
 const WINDOWS_PROJ_NAME:     &'static str = "trs80m1-rs";
 const UNIX_HIDDEN_DIR_NAME:  &'static str = ".trs80m1-rs";
-const CONFIG_FILE_NAME:      &'static str = "config.ini";
+const CONFIG_FILE_NAME:      &'static str = "config.toml";
+
+// The configuration file of the older versions, carried over into the new one
+// when that gets created:
+const LEGACY_CONFIG_FILE_NAME: &'static str = "config.ini";
+
+// The version of the layout of the configuration file, written at its top.
+// When a section or an entry gets renamed or moved, the version goes up and
+// a migration saying what changed is added to CONFIG_MIGRATIONS, so that the
+// files of the older versions are brought up to date when they're loaded.
+const CONFIG_FILE_VERSION: u32 = 1;
+
+// The changes of the layout made by a version of the configuration file.  The
+// entries are given by their specifiers, like `video_fullscreen', with the
+// names of the sections from before the renaming.  The new sections and
+// entries need no migration, they're added with their defaults like to any
+// file they're missing from.
+struct ConfigMigration {
+    version:          u32,
+    renamed_sections: &'static [(&'static str, &'static str)],
+    renamed_entries:  &'static [(&'static str, &'static str)],
+}
+
+const CONFIG_MIGRATIONS: &[ConfigMigration] = &[
+    // The files written before the version was, laid out the same way.
+    ConfigMigration {
+        version:          1,
+        renamed_sections: &[],
+        renamed_entries:  &[],
+    },
+];
+
+// How often the emulator looks at whether the configuration file was edited.
+pub const WATCH_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+// Typing faster than this makes the ROM miss some of the keys.
+pub const MAX_TYPE_CHARS_PER_SECOND: u32 = 100;
+
+// The fastest auto-repeat of the PC keyboards.
+pub const MAX_REPEAT_PER_SECOND: u32 = 30;
 
 // Configuration items.
 //
 // This is data that represents what's in the configuration file, accessible to
 // all parts of the program via the configuration system.
 //
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConfigItems {
 
-    // [General] Entries:
-    pub general_level_1_rom:             Option<String>,
-    pub general_level_2_rom:             Option<String>,
-    pub general_misc_rom:                Option<String>,
+    // [machine] Entries:
+    pub machine_default_rom:             u32,
+    pub machine_ram_size:                u32,
+    pub machine_port_bridges:            Vec<bridge::BridgeSpec>,
 
-    pub general_default_rom:             u32,
-    pub general_ram_size:                u32,
 
+    // [paths] Entries:
+    pub paths_level_1_rom:               Option<String>,
+    pub paths_level_2_rom:               Option<String>,
+    pub paths_misc_rom:                  Option<String>,
+    pub paths_rom_dirs:                  Vec<String>,
+
+    pub paths_startup_script:            Option<String>,
+    pub paths_screenshot_dir:            Option<String>,
+    pub paths_library_dir:               Option<String>,
 
-    // [Keyboard] Entries:
-    pub keyboard_ms_per_keypress:        u32,
 
+    // [interface] Entries:
+    pub interface_aliases:               Vec<aliases::Alias>,
+    pub interface_ui_theme:              ui_theme::Theme,
+    pub interface_ui_colors:             Vec<ui_theme::ColorSetting>,
+    pub interface_language:              String,
+    pub interface_confirm_actions:       Vec<confirm::Action>,
 
-    // [Video] Entries:
+
+    // [keyboard] Entries:
+    pub keyboard_ms_per_keypress:        u32,
+    pub keyboard_mapping:                sdl_keyboard::KeyMapping,
+    pub keyboard_key_bindings:           Vec<sdl_keyboard::KeyBinding>,
+    pub keyboard_profiles:               Vec<sdl_keyboard::KeyProfile>,
+    pub keyboard_profile:                Option<String>,
+    pub keyboard_paste_ms_per_keypress:  u32,
+    pub keyboard_paste_ms_per_line:      u32,
+    pub keyboard_type_chars_per_second:  u32,
+    pub keyboard_paste_wait_for_scan:    bool,
+    pub keyboard_auto_repeat:            bool,
+    pub keyboard_repeat_delay_ms:        u32,
+    pub keyboard_repeat_per_second:      u32,
+
+
+    // [video] Entries:
     pub video_windowed_resolution:       (u32, u32),
     pub video_fullscreen_resolution:     (u32, u32),
 
@@ -62,17 +160,62 @@ pub struct ConfigItems {
     pub video_fg_color:                  (u8, u8, u8),
 
     pub video_desktop_fullscreen_mode:   bool,
+    pub video_fullscreen:                bool,
+    pub video_scaling:                   sdl_video::Scaling,
+    pub video_crt_scanlines:             u32,
+    pub video_crt_mask:                  u32,
+    pub video_crt_curvature:             u32,
+    pub video_crt_persistence:           u32,
+    pub video_brightness:                u32,
+    pub video_contrast:                  u32,
+    pub video_screenshot_scaled:         bool,
+    pub video_recording_frame_rate:      u32,
+    pub video_recording_max_seconds:     u32,
+    pub video_text_graphics:             video::TextGraphics,
     pub video_use_hw_accel:              bool,
+    pub video_renderer:                  sdl_video::Renderer,
     pub video_use_vsync:                 bool,
+    pub video_frame_skip:                sdl_video::FrameSkip,
+    pub video_overlay:                   sdl_video::OverlayLevel,
+    pub video_notifications:             bool,
 
     pub video_character_generator:       u32,
+    pub video_character_generator_file:  Option<String>,
     pub video_lowercase_mod:             bool,
+    pub video_hires_board:               bool,
+    pub video_bus_contention:            bool,
+
+
+    // [audio] Entries:
+    pub audio_enabled:                   bool,
+    pub audio_volume:                    u32,
+    pub audio_sample_rate:               u32,
+    pub audio_buffer_size:               u32,
+    pub audio_latency:                   u32,
+    pub audio_resampling:                sound::Resampling,
+    pub audio_mute_tape:                 bool,
+    pub audio_mute_program:              bool,
+    pub audio_mute_orchestra:            bool,
+    pub audio_mute_ay:                   bool,
+    pub audio_ay_port:                   Option<u8>,
 
 
-    // [Cassette] Entries:
+    // [cassette] Entries:
     pub cassette_file:                   Option<String>,
     pub cassette_file_format:            cassette::Format,
     pub cassette_file_offset:            usize,
+    pub cassette_wav_copy:               bool,
+    pub cassette_fast_load:              bool,
+    pub cassette_wav_threshold:          u32,
+    pub cassette_wav_noise_floor:        u32,
+    pub cassette_wav_dc_filter:          bool,
+    pub cassette_wav_channel:            cassette::WavChannel,
+    pub cassette_wav_speed:              u32,
+
+
+    // [controller] Entries:
+    pub controller_joystick_port:        Option<u8>,
+    pub controller_bindings:             Vec<sdl_controller::ControllerBinding>,
 }
 
 impl ConfigItems {
@@ -84,14 +227,37 @@ impl ConfigItems {
     fn new_uninitialized() -> ConfigItems {
 
         ConfigItems {
-            general_level_1_rom:             None,
-            general_level_2_rom:             None,
-            general_misc_rom:                None,
+            machine_default_rom:             0,
+            machine_ram_size:                0,
+            machine_port_bridges:            Vec::new(),
 
-            general_default_rom:             0,
-            general_ram_size:                0,
+            paths_level_1_rom:               None,
+            paths_level_2_rom:               None,
+            paths_misc_rom:                  None,
+            paths_rom_dirs:                  Vec::new(),
+
+            paths_startup_script:            None,
+            paths_screenshot_dir:            None,
+            paths_library_dir:               None,
+
+            interface_aliases:               Vec::new(),
+            interface_ui_theme:              ui_theme::Theme::Dark,
+            interface_ui_colors:             Vec::new(),
+            interface_language:              String::new(),
+            interface_confirm_actions:       Vec::new(),
 
             keyboard_ms_per_keypress:        0,
+            keyboard_mapping:                sdl_keyboard::KeyMapping::Positional,
+            keyboard_key_bindings:           Vec::new(),
+            keyboard_profiles:               Vec::new(),
+            keyboard_profile:                None,
+            keyboard_paste_ms_per_keypress:  0,
+            keyboard_paste_ms_per_line:      0,
+            keyboard_type_chars_per_second:  0,
+            keyboard_paste_wait_for_scan:    false,
+            keyboard_auto_repeat:            false,
+            keyboard_repeat_delay_ms:        0,
+            keyboard_repeat_per_second:      0,
 
             video_windowed_resolution:       (0, 0),
             video_fullscreen_resolution:     (0, 0),
@@ -100,15 +266,56 @@ impl ConfigItems {
             video_fg_color:                  (0, 0, 0),
 
             video_desktop_fullscreen_mode:   false,
+            video_fullscreen:                false,
+            video_scaling:                   sdl_video::Scaling::Aspect,
+            video_crt_scanlines:             0,
+            video_crt_mask:                  0,
+            video_crt_curvature:             0,
+            video_crt_persistence:           0,
+            video_brightness:                100,
+            video_contrast:                  100,
+            video_screenshot_scaled:         false,
+            video_recording_frame_rate:      10,
+            video_recording_max_seconds:     60,
+            video_text_graphics:             video::TextGraphics::Unicode,
             video_use_hw_accel:              false,
+            video_renderer:                  sdl_video::Renderer::Sdl,
             video_use_vsync:                 false,
+            video_frame_skip:                sdl_video::FrameSkip::Auto,
+            video_overlay:                   sdl_video::OverlayLevel::Off,
+            video_notifications:             true,
 
             video_character_generator:       0,
+            video_character_generator_file:  None,
             video_lowercase_mod:             false,
+            video_hires_board:               false,
+            video_bus_contention:            false,
+
+            audio_enabled:                   false,
+            audio_volume:                    0,
+            audio_sample_rate:               0,
+            audio_buffer_size:               0,
+            audio_latency:                   0,
+            audio_resampling:                sound::Resampling::High,
+            audio_mute_tape:                 false,
+            audio_mute_program:              false,
+            audio_mute_orchestra:            false,
+            audio_mute_ay:                   false,
+            audio_ay_port:                   Some(psg::DEFAULT_PORT),
 
             cassette_file:                   None,
             cassette_file_format:            cassette::Format::CAS,
             cassette_file_offset:            0,
+            cassette_wav_copy:               false,
+            cassette_fast_load:              false,
+            cassette_wav_threshold:          0,
+            cassette_wav_noise_floor:        0,
+            cassette_wav_dc_filter:          false,
+            cassette_wav_channel:            cassette::WavChannel::Left,
+            cassette_wav_speed:              0,
+
+            controller_joystick_port:        None,
+            controller_bindings:             Vec::new(),
         }
     }
 }
@@ -124,15 +331,16 @@ impl ConfigItems {
 //
 #[derive(Debug)]
 pub enum ConfigInfoSource {
-    ConfigFile { line_number: usize, line_text: String },
+    ConfigFile { line_number: usize, line_text: String, argument: String },
     ExternalSource { section_name: String, entry_name: String, invocation_text: String },
 }
 
 impl ConfigInfoSource {
-    fn from_config_file(line_number: usize, line_text: &str) -> ConfigInfoSource {
+    fn from_config_file(line_number: usize, line_text: &str, argument: &str) -> ConfigInfoSource {
         ConfigInfoSource::ConfigFile {
             line_number,
             line_text:   line_text.to_owned(),
+            argument:    argument.to_owned(),
         }
     }
     fn from_external_source(section_name: &str, entry_name: &str, invocation_text: &str) -> ConfigInfoSource {
@@ -144,7 +352,7 @@ impl ConfigInfoSource {
     }
     fn error_prefix(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ConfigInfoSource::ConfigFile { line_number, ref line_text } => {
+            ConfigInfoSource::ConfigFile { line_number, ref line_text, .. } => {
                 write!(f, "error on line {}, `{}', ", line_number + 1, line_text)
             },
             ConfigInfoSource::ExternalSource { ref section_name, ref entry_name, ref invocation_text } => {
@@ -154,8 +362,8 @@ impl ConfigInfoSource {
     }
     fn argument_text(&self) -> String {
         match *self {
-            ConfigInfoSource::ConfigFile { ref line_text, .. } => {
-                retrieve_entry_assignee(line_text)
+            ConfigInfoSource::ConfigFile { ref argument, .. } => {
+                argument.clone()
             },
             ConfigInfoSource::ExternalSource { ref invocation_text, .. } => {
                 retrieve_entry_assignee(invocation_text)
@@ -167,26 +375,49 @@ impl ConfigInfoSource {
 // Error structure used within the module:
 #[derive(Debug)]
 pub enum ConfigError {
-    RedundantSection(String, usize, usize),
-    RedundantEntry(String, String, usize, usize),
     EntryIntParsingError(ConfigInfoSource, num::ParseIntError),
-    TextAfterConfigSectionClosingBracket(ConfigInfoSource),
-    NonAlphaCharactersInConfigSectionName(ConfigInfoSource),
-    ClosingBracketMissingInConfigSectionHeader(ConfigInfoSource),
-    EntryNameBeginsWithNonAlpha(ConfigInfoSource),
-    EntryNameContainsSpaces(ConfigInfoSource),
-    EntryNameContainsNonAlnumChars(ConfigInfoSource),
-    EntryContainsSeveralEqualsSigns(ConfigInfoSource),
-    EntryEqualsSignMissing(ConfigInfoSource),
-    EntryArgumentMissing(ConfigInfoSource),
+    TomlSyntaxError(Option<ConfigInfoSource>, String),
     InvalidResolutionSpecifier(ConfigInfoSource),
     InvalidColorSpecifier(ConfigInfoSource),
     InvalidBoolSpecifier(ConfigInfoSource),
     InvalidCassetteFormatSpecifier(ConfigInfoSource),
+    InvalidWavChannelSpecifier(ConfigInfoSource),
     InvalidRamSpecifier(ConfigInfoSource),
+    InvalidRomSpecifier(ConfigInfoSource),
     TooMuchRamRequested(ConfigInfoSource, u32),
     DefaultRomOutOfRange(ConfigInfoSource, u32),
     CharacterGeneratorOutOfRange(ConfigInfoSource, u32),
+    VolumeOutOfRange(ConfigInfoSource, u32),
+    WavThresholdOutOfRange(ConfigInfoSource, u32),
+    WavNoiseFloorOutOfRange(ConfigInfoSource, u32),
+    WavSpeedOutOfRange(ConfigInfoSource, u32),
+    SampleRateOutOfRange(ConfigInfoSource, u32),
+    BufferSizeOutOfRange(ConfigInfoSource, u32),
+    LatencyOutOfRange(ConfigInfoSource, u32),
+    InvalidResamplingSpecifier(ConfigInfoSource),
+    InvalidScalingSpecifier(ConfigInfoSource),
+    InvalidRendererSpecifier(ConfigInfoSource),
+    InvalidFrameSkipSpecifier(ConfigInfoSource),
+    InvalidOverlaySpecifier(ConfigInfoSource),
+    InvalidTextGraphicsSpecifier(ConfigInfoSource),
+    CrtIntensityOutOfRange(ConfigInfoSource, u32, u32),
+    PictureLevelOutOfRange(ConfigInfoSource, u32, u32),
+    RecordingFrameRateOutOfRange(ConfigInfoSource, u32),
+    TypingRateOutOfRange(ConfigInfoSource, u32),
+    RepeatRateOutOfRange(ConfigInfoSource, u32),
+    InvalidAyPort(ConfigInfoSource),
+    InvalidKeyMappingSpecifier(ConfigInfoSource),
+    InvalidKeyBindingsSpecifier(ConfigInfoSource),
+    InvalidKeyProfilesSpecifier(ConfigInfoSource),
+    InvalidAliasesSpecifier(ConfigInfoSource),
+    InvalidThemeSpecifier(ConfigInfoSource),
+    InvalidUiColorsSpecifier(ConfigInfoSource),
+    InvalidLanguageSpecifier(ConfigInfoSource),
+    InvalidConfirmActionsSpecifier(ConfigInfoSource),
+    InvalidKeyProfileName(ConfigInfoSource),
+    InvalidJoystickPort(ConfigInfoSource),
+    InvalidPortBridgesSpecifier(ConfigInfoSource),
+    InvalidControllerBindingsSpecifier(ConfigInfoSource),
     EntrySpecNoSectionNameSpecified(String),
     EntrySpecNoEntryNameSpecified(String),
     EntrySpecNoSuchConfigEntry(String),
@@ -196,51 +427,15 @@ pub enum ConfigError {
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ConfigError::RedundantSection(ref section_name, first, second) => {
-                write!(f, "section `[{}]' is present in the configuration file more than once, on line {} and line {}", section_name, first + 1, second + 1)
-            },
-            ConfigError::RedundantEntry(ref section_name, ref entry_name, first, second) => {
-                write!(f, "entry `{}' is present more than once in the `[{}]' section of the configuration file, on line {} and line {}", entry_name, section_name, first + 1, second + 1)
-            },
             ConfigError::EntryIntParsingError(ref info_source, ref inner_error) => {
                 info_source.error_prefix(f)?;
                 write!(f, "failed to parse the entry argument: {}", inner_error)
             },
-            ConfigError::TextAfterConfigSectionClosingBracket(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "there is text present after the config section header's closing bracket")
-            },
-            ConfigError::NonAlphaCharactersInConfigSectionName(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the config section name contains non-alphabetical characters")
-            },
-            ConfigError::ClosingBracketMissingInConfigSectionHeader(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the closing bracket is missing in the config section header")
-            },
-            ConfigError::EntryNameBeginsWithNonAlpha(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry's name begins with a non-alphabetical character")
-            },
-            ConfigError::EntryNameContainsSpaces(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry's name contains spaces")
-            },
-            ConfigError::EntryNameContainsNonAlnumChars(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry's name contains non-alphanumerical characters")
-            },
-            ConfigError::EntryContainsSeveralEqualsSigns(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry contains several equals signs")
-            },
-            ConfigError::EntryEqualsSignMissing(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry doesn't contain an equals sign")
-            },
-            ConfigError::EntryArgumentMissing(ref info_source) => {
-                info_source.error_prefix(f)?;
-                write!(f, "the entry doesn't contain an argument")
+            ConfigError::TomlSyntaxError(ref info_source, ref message) => {
+                if let Some(info_source) = info_source {
+                    info_source.error_prefix(f)?;
+                }
+                write!(f, "{}", message)
             },
             ConfigError::InvalidResolutionSpecifier(ref info_source) => {
                 info_source.error_prefix(f)?;
@@ -256,12 +451,20 @@ impl fmt::Display for ConfigError {
             },
             ConfigError::InvalidCassetteFormatSpecifier(ref info_source) => {
                 info_source.error_prefix(f)?;
-                write!(f, "invalid cassette format specification, please use either CAS or CPT")
+                write!(f, "invalid cassette format specification, please use either CAS, CPT or WAV")
+            },
+            ConfigError::InvalidWavChannelSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid channel specification, please use either left, right or mix")
             },
             ConfigError::InvalidRamSpecifier(ref info_source) => {
                 info_source.error_prefix(f)?;
                 write!(f, "invalid ram specification")
             },
+            ConfigError::InvalidRomSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid rom specification, please use a file name, or the files of the parts joined with ` + ', each optionally followed by `@' and its offset within the 12K of the rom")
+            },
             ConfigError::TooMuchRamRequested(ref info_source, ram_requested) => {
                 info_source.error_prefix(f)?;
                 if (ram_requested % 1024) == 0 {
@@ -278,6 +481,131 @@ impl fmt::Display for ConfigError {
                 info_source.error_prefix(f)?;
                 write!(f, "the specified character generator selection of {} is out of range, please choose from 1 to 3", selection)
             },
+            ConfigError::VolumeOutOfRange(ref info_source, volume) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified volume of {} is out of range, please choose from 0 to 100", volume)
+            },
+            ConfigError::WavThresholdOutOfRange(ref info_source, threshold) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified pulse threshold of {}% is out of range, please choose from 1 to 100", threshold)
+            },
+            ConfigError::WavNoiseFloorOutOfRange(ref info_source, noise_floor) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified noise floor of {} is out of range, please choose from 0 to 127", noise_floor)
+            },
+            ConfigError::WavSpeedOutOfRange(ref info_source, speed) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified playback speed of {}% is out of range, please choose from 50 to 200", speed)
+            },
+            ConfigError::SampleRateOutOfRange(ref info_source, sample_rate) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified sample rate of {} Hz is out of range, please choose from 8000 to 96000", sample_rate)
+            },
+            ConfigError::BufferSizeOutOfRange(ref info_source, buffer_size) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified buffer size of {} samples is not supported, please choose a power of two from 64 to 8192", buffer_size)
+            },
+            ConfigError::LatencyOutOfRange(ref info_source, latency) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified latency of {} ms is out of range, please choose from 10 to 1000", latency)
+            },
+            ConfigError::InvalidResamplingSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid resampling quality specification, please use either low or high")
+            },
+            ConfigError::InvalidScalingSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid scaling mode specification, please use either integer, aspect or stretch")
+            },
+            ConfigError::InvalidRendererSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid renderer specification, please use either sdl or opengl")
+            },
+            ConfigError::InvalidFrameSkipSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid frame skip specification, please use either auto or a number from 0 to {}", sdl_video::MAX_FRAME_SKIP)
+            },
+            ConfigError::InvalidOverlaySpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid overlay specification, please use either off, speed or full")
+            },
+            ConfigError::InvalidTextGraphicsSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid text graphics specification, please use either unicode or ascii")
+            },
+            ConfigError::CrtIntensityOutOfRange(ref info_source, intensity, max) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the effect intensity of {}% is out of range, please choose from 0 to {}", intensity, max)
+            },
+            ConfigError::PictureLevelOutOfRange(ref info_source, level, max) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the level of {}% is out of range, please choose from 0 to {}", level, max)
+            },
+            ConfigError::RecordingFrameRateOutOfRange(ref info_source, frame_rate) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified frame rate of {} frames per second is out of range, please choose from 1 to {}", frame_rate, gif::MAX_FRAME_RATE)
+            },
+            ConfigError::TypingRateOutOfRange(ref info_source, rate) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified typing rate of {} characters per second is out of range, please choose from 1 to {}", rate, MAX_TYPE_CHARS_PER_SECOND)
+            },
+            ConfigError::RepeatRateOutOfRange(ref info_source, rate) => {
+                info_source.error_prefix(f)?;
+                write!(f, "the specified auto-repeat rate of {} keys per second is out of range, please choose from 1 to {}", rate, MAX_REPEAT_PER_SECOND)
+            },
+            ConfigError::InvalidAyPort(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid AY-3-8910 board port, please use either none, or a port from 00h to FDh whose pair doesn't overlap with B5h, B9h or FFh")
+            },
+            ConfigError::InvalidKeyMappingSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid key mapping specification, please use either positional or symbolic")
+            },
+            ConfigError::InvalidKeyBindingsSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid key bindings specification, please use either none, or a comma-separated list of <key>:<machine key> pairs")
+            },
+            ConfigError::InvalidKeyProfilesSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid key profiles specification, please use either none, or a list of <name>(<key bindings>) profiles")
+            },
+            ConfigError::InvalidAliasesSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid aliases specification, please use either none, or a list of <name> \"<commands>\" aliases")
+            },
+            ConfigError::InvalidThemeSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid theme specification, please use either dark, light, colorblind or monochrome")
+            },
+            ConfigError::InvalidUiColorsSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid colors specification, please use either none, or a list of <part>=<foreground>/<background> settings")
+            },
+            ConfigError::InvalidLanguageSpecifier(ref info_source) => {
+                let languages: Vec<&str> = i18n::LANGUAGES.iter().map(|(code, _, _)| *code).collect();
+                info_source.error_prefix(f)?;
+                write!(f, "invalid language specification, please use either auto, or one of {}", languages.join(", "))
+            },
+            ConfigError::InvalidConfirmActionsSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid actions specification, please use either none, or a list of reset, discard and overwrite")
+            },
+            ConfigError::InvalidKeyProfileName(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid key profile name, please use either none, or a name made of letters, digits, `_' and `-'")
+            },
+            ConfigError::InvalidJoystickPort(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid joystick interface port, please use either none, or a port from 00h to FEh other than B5h and B9h")
+            },
+            ConfigError::InvalidPortBridgesSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid port bridges, please use either none, or a comma-separated list of bridges like `F0h > file', `F1h < file' or `F2h <> tcp:host:port', on ports from 00h to FEh other than B5h, B9h and the ones of the joystick interface, the graphics board, the AY-3-8910 board and the character set switch of a character generator file, with at most one bridge reading and one writing each port")
+            },
+            ConfigError::InvalidControllerBindingsSpecifier(ref info_source) => {
+                info_source.error_prefix(f)?;
+                write!(f, "invalid controller bindings specification, please use either none, or a comma-separated list of <control>:<target> pairs")
+            },
             ConfigError::EntrySpecNoSectionNameSpecified(ref entry_specifier) => {
                 write!(f, "invalid entry specifier `{}': no section name specified", entry_specifier)
             },
@@ -305,25 +633,81 @@ impl From<io::Error> for ConfigError {
 // The following enum describes the actions that need to be taken in order for
 // the new value of an entry after doing a configuration change to be utilized.
 //
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ConfigChangeApplyAction {
     RomChange(u32),
+    UpdateRomDirs,
     ChangeRamSize,
     UpdateMsPerKeypress,
+    UpdateKeyMapping,
+    UpdateKeyBindings,
+    UpdateKeyProfile,
+    UpdatePasteSettings,
+    UpdateKeyRepeat,
     ChangeWindowedResolution,
     ChangeFullscreenResolution,
+    ChangeFullscreenMode,
+    ChangeScaling,
+    ChangeCrtEffects,
+    UpdateScreenshotSettings,
+    UpdateRecordingSettings,
+    UpdateTextGraphics,
     ChangeColor,
     ChangeHwAccelUsage,
+    ChangeRenderer,
     ChangeVsyncUsage,
+    ChangeFrameSkip,
+    ChangeOverlay,
+    ChangeNotifications,
     ChangeCharacterGenerator,
     ChangeLowercaseModUsage,
+    ChangeHiResBoard,
+    ChangeBusContention,
     UpdateCassetteFile,
     UpdateCassetteFileFormat,
     UpdateCassetteFileOffset,
+    UpdateCassetteWavCopy,
+    UpdateCassetteFastLoad,
+    UpdateCassetteLibraryDir,
+    UpdateCassetteWavDecoding,
+    UpdateSoundEnabled,
+    UpdateSoundVolume,
+    UpdateSoundOutput,
+    UpdateSoundMixer,
+    UpdateSoundBoard,
+    UpdateJoystick,
+    UpdatePortBridges,
+    UpdateControllerBindings,
     UpdateDefaultRomSelection,
+    UpdateStartupScript,
+    UpdateAliases,
+    UpdateUiTheme,
+    UpdateLanguage,
+    UpdateConfirmActions,
     AlreadyUpToDate,
 }
 
+impl ConfigChangeApplyAction {
+    // Whether a change made by editing the configuration file has to wait for
+    // a restart of the emulator, since it would swap the hardware or the tape
+    // of the machine under the running program.  The `/config change'
+    // command applies all of them right away.
+    pub fn needs_restart(&self) -> bool {
+        matches!(*self, ConfigChangeApplyAction::RomChange(..) |
+                        ConfigChangeApplyAction::UpdateRomDirs |
+                        ConfigChangeApplyAction::ChangeRamSize |
+                        ConfigChangeApplyAction::ChangeLowercaseModUsage |
+                        ConfigChangeApplyAction::ChangeHiResBoard |
+                        ConfigChangeApplyAction::ChangeBusContention |
+                        ConfigChangeApplyAction::UpdateCassetteFile |
+                        ConfigChangeApplyAction::UpdateCassetteFileFormat |
+                        ConfigChangeApplyAction::UpdateCassetteFileOffset |
+                        ConfigChangeApplyAction::UpdateSoundBoard |
+                        ConfigChangeApplyAction::UpdateJoystick |
+                        ConfigChangeApplyAction::UpdatePortBridges)
+    }
+}
+
 // A configuration entry handler:
 struct ConfigEntry {
 
@@ -350,13 +734,368 @@ struct ConfigSection {
     obsolete_entries:  Box<[String]>,
 }
 
+// The layout of the configuration file, as seen by the TOML parser.
+//
+// Each of the sections has a struct of its own, with the entries typed the way
+// they're written in the file.  The entries which this version doesn't know,
+// like the ones of a newer version of the layout, are left alone in the file.
+//
+type ConfigValue<T> = Option<toml::Spanned<T>>;
+
+// An entry which is written as text, or as a plain number, like the amount of
+// memory in bytes, or the number of frames to leave out.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(u64),
+    Text(String),
+}
+
+impl fmt::Display for NumberOrText {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NumberOrText::Number(number) => { write!(f, "{}", number) },
+            NumberOrText::Text(ref text) => { write!(f, "{}", text) },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigDocument {
+    // The version is read by file_version() before the file is brought up to
+    // date, this only checks that it's a whole number.
+    #[serde(rename = "version")]
+    _version:   ConfigValue<u32>,
+    #[serde(default)]
+    machine:    MachineEntries,
+    #[serde(default)]
+    paths:      PathsEntries,
+    #[serde(default)]
+    interface:  InterfaceEntries,
+    #[serde(default)]
+    keyboard:   KeyboardEntries,
+    #[serde(default)]
+    video:      VideoEntries,
+    #[serde(default)]
+    audio:      AudioEntries,
+    #[serde(default)]
+    cassette:   CassetteEntries,
+    #[serde(default)]
+    controller: ControllerEntries,
+}
+
+#[derive(Deserialize, Default)]
+struct MachineEntries {
+    default_rom:  ConfigValue<u32>,
+    ram_size:     ConfigValue<NumberOrText>,
+    port_bridges: ConfigValue<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct PathsEntries {
+    level_1_rom:    ConfigValue<String>,
+    level_2_rom:    ConfigValue<String>,
+    misc_rom:       ConfigValue<String>,
+    rom_dirs:       ConfigValue<String>,
+    startup_script: ConfigValue<String>,
+    screenshot_dir: ConfigValue<String>,
+    library_dir:    ConfigValue<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct InterfaceEntries {
+    aliases:         ConfigValue<String>,
+    ui_theme:        ConfigValue<String>,
+    ui_colors:       ConfigValue<String>,
+    language:        ConfigValue<String>,
+    confirm_actions: ConfigValue<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct KeyboardEntries {
+    ms_per_keypress:       ConfigValue<u32>,
+    mapping:               ConfigValue<String>,
+    key_bindings:          ConfigValue<String>,
+    profiles:              ConfigValue<String>,
+    profile:               ConfigValue<String>,
+    paste_ms_per_keypress: ConfigValue<u32>,
+    paste_ms_per_line:     ConfigValue<u32>,
+    type_chars_per_second: ConfigValue<u32>,
+    paste_wait_for_scan:   ConfigValue<bool>,
+    auto_repeat:           ConfigValue<bool>,
+    repeat_delay_ms:       ConfigValue<u32>,
+    repeat_per_second:     ConfigValue<u32>,
+}
+
+#[derive(Deserialize, Default)]
+struct VideoEntries {
+    windowed_resolution:      ConfigValue<String>,
+    fullscreen_resolution:    ConfigValue<String>,
+    bg_color:                 ConfigValue<String>,
+    fg_color:                 ConfigValue<String>,
+    desktop_fullscreen_mode:  ConfigValue<bool>,
+    fullscreen:               ConfigValue<bool>,
+    screenshot_scaled:        ConfigValue<bool>,
+    recording_frame_rate:     ConfigValue<u32>,
+    recording_max_seconds:    ConfigValue<u32>,
+    text_graphics:            ConfigValue<String>,
+    scaling:                  ConfigValue<String>,
+    crt_scanlines:            ConfigValue<u32>,
+    crt_mask:                 ConfigValue<u32>,
+    crt_curvature:            ConfigValue<u32>,
+    crt_persistence:          ConfigValue<u32>,
+    brightness:               ConfigValue<u32>,
+    contrast:                 ConfigValue<u32>,
+    use_hw_accel:             ConfigValue<bool>,
+    renderer:                 ConfigValue<String>,
+    use_vsync:                ConfigValue<bool>,
+    frame_skip:               ConfigValue<NumberOrText>,
+    overlay:                  ConfigValue<String>,
+    notifications:            ConfigValue<bool>,
+    character_generator:      ConfigValue<u32>,
+    character_generator_file: ConfigValue<String>,
+    lowercase_mod:            ConfigValue<bool>,
+    hires_board:              ConfigValue<bool>,
+    bus_contention:           ConfigValue<bool>,
+}
+
+#[derive(Deserialize, Default)]
+struct AudioEntries {
+    enabled:        ConfigValue<bool>,
+    volume:         ConfigValue<u32>,
+    sample_rate:    ConfigValue<u32>,
+    buffer_size:    ConfigValue<u32>,
+    latency:        ConfigValue<u32>,
+    resampling:     ConfigValue<String>,
+    mute_tape:      ConfigValue<bool>,
+    mute_program:   ConfigValue<bool>,
+    mute_orchestra: ConfigValue<bool>,
+    mute_ay:        ConfigValue<bool>,
+    ay_port:        ConfigValue<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct CassetteEntries {
+    file:            ConfigValue<String>,
+    file_format:     ConfigValue<String>,
+    file_offset:     ConfigValue<u64>,
+    wav_copy:        ConfigValue<bool>,
+    fast_load:       ConfigValue<bool>,
+    wav_threshold:   ConfigValue<u32>,
+    wav_noise_floor: ConfigValue<u32>,
+    wav_dc_filter:   ConfigValue<bool>,
+    wav_channel:     ConfigValue<String>,
+    wav_speed:       ConfigValue<u32>,
+}
+
+#[derive(Deserialize, Default)]
+struct ControllerEntries {
+    joystick_port: ConfigValue<String>,
+    bindings:      ConfigValue<String>,
+}
+
+// The value of an entry as the argument text the handlers take, along with
+// where it's written in the file:
+fn entry_argument<T: fmt::Display>(value: &ConfigValue<T>) -> Option<(String, ops::Range<usize>)> {
+    value.as_ref().map(|value| (value.get_ref().to_string(), value.span()))
+}
+
+impl ConfigDocument {
+    fn entry_argument(&self, section_name: &str, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match section_name {
+            "machine"    => { self.machine.entry_argument(entry_name) },
+            "paths"      => { self.paths.entry_argument(entry_name) },
+            "interface"  => { self.interface.entry_argument(entry_name) },
+            "keyboard"   => { self.keyboard.entry_argument(entry_name) },
+            "video"      => { self.video.entry_argument(entry_name) },
+            "audio"      => { self.audio.entry_argument(entry_name) },
+            "cassette"   => { self.cassette.entry_argument(entry_name) },
+            "controller" => { self.controller.entry_argument(entry_name) },
+            _            => { None },
+        }
+    }
+}
+
+impl MachineEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "default_rom"  => { entry_argument(&self.default_rom) },
+            "ram_size"     => { entry_argument(&self.ram_size) },
+            "port_bridges" => { entry_argument(&self.port_bridges) },
+            _              => { None },
+        }
+    }
+}
+
+impl PathsEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "level_1_rom"    => { entry_argument(&self.level_1_rom) },
+            "level_2_rom"    => { entry_argument(&self.level_2_rom) },
+            "misc_rom"       => { entry_argument(&self.misc_rom) },
+            "rom_dirs"       => { entry_argument(&self.rom_dirs) },
+            "startup_script" => { entry_argument(&self.startup_script) },
+            "screenshot_dir" => { entry_argument(&self.screenshot_dir) },
+            "library_dir"    => { entry_argument(&self.library_dir) },
+            _                => { None },
+        }
+    }
+}
+
+impl InterfaceEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "aliases"         => { entry_argument(&self.aliases) },
+            "ui_theme"        => { entry_argument(&self.ui_theme) },
+            "ui_colors"       => { entry_argument(&self.ui_colors) },
+            "language"        => { entry_argument(&self.language) },
+            "confirm_actions" => { entry_argument(&self.confirm_actions) },
+            _                 => { None },
+        }
+    }
+}
+
+impl KeyboardEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "ms_per_keypress"       => { entry_argument(&self.ms_per_keypress) },
+            "mapping"               => { entry_argument(&self.mapping) },
+            "key_bindings"          => { entry_argument(&self.key_bindings) },
+            "profiles"              => { entry_argument(&self.profiles) },
+            "profile"               => { entry_argument(&self.profile) },
+            "paste_ms_per_keypress" => { entry_argument(&self.paste_ms_per_keypress) },
+            "paste_ms_per_line"     => { entry_argument(&self.paste_ms_per_line) },
+            "type_chars_per_second" => { entry_argument(&self.type_chars_per_second) },
+            "paste_wait_for_scan"   => { entry_argument(&self.paste_wait_for_scan) },
+            "auto_repeat"           => { entry_argument(&self.auto_repeat) },
+            "repeat_delay_ms"       => { entry_argument(&self.repeat_delay_ms) },
+            "repeat_per_second"     => { entry_argument(&self.repeat_per_second) },
+            _                       => { None },
+        }
+    }
+}
+
+impl VideoEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "windowed_resolution"      => { entry_argument(&self.windowed_resolution) },
+            "fullscreen_resolution"    => { entry_argument(&self.fullscreen_resolution) },
+            "bg_color"                 => { entry_argument(&self.bg_color) },
+            "fg_color"                 => { entry_argument(&self.fg_color) },
+            "desktop_fullscreen_mode"  => { entry_argument(&self.desktop_fullscreen_mode) },
+            "fullscreen"               => { entry_argument(&self.fullscreen) },
+            "screenshot_scaled"        => { entry_argument(&self.screenshot_scaled) },
+            "recording_frame_rate"     => { entry_argument(&self.recording_frame_rate) },
+            "recording_max_seconds"    => { entry_argument(&self.recording_max_seconds) },
+            "text_graphics"            => { entry_argument(&self.text_graphics) },
+            "scaling"                  => { entry_argument(&self.scaling) },
+            "crt_scanlines"            => { entry_argument(&self.crt_scanlines) },
+            "crt_mask"                 => { entry_argument(&self.crt_mask) },
+            "crt_curvature"            => { entry_argument(&self.crt_curvature) },
+            "crt_persistence"          => { entry_argument(&self.crt_persistence) },
+            "brightness"               => { entry_argument(&self.brightness) },
+            "contrast"                 => { entry_argument(&self.contrast) },
+            "use_hw_accel"             => { entry_argument(&self.use_hw_accel) },
+            "renderer"                 => { entry_argument(&self.renderer) },
+            "use_vsync"                => { entry_argument(&self.use_vsync) },
+            "frame_skip"               => { entry_argument(&self.frame_skip) },
+            "overlay"                  => { entry_argument(&self.overlay) },
+            "notifications"            => { entry_argument(&self.notifications) },
+            "character_generator"      => { entry_argument(&self.character_generator) },
+            "character_generator_file" => { entry_argument(&self.character_generator_file) },
+            "lowercase_mod"            => { entry_argument(&self.lowercase_mod) },
+            "hires_board"              => { entry_argument(&self.hires_board) },
+            "bus_contention"           => { entry_argument(&self.bus_contention) },
+            _                          => { None },
+        }
+    }
+}
+
+impl AudioEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "enabled"        => { entry_argument(&self.enabled) },
+            "volume"         => { entry_argument(&self.volume) },
+            "sample_rate"    => { entry_argument(&self.sample_rate) },
+            "buffer_size"    => { entry_argument(&self.buffer_size) },
+            "latency"        => { entry_argument(&self.latency) },
+            "resampling"     => { entry_argument(&self.resampling) },
+            "mute_tape"      => { entry_argument(&self.mute_tape) },
+            "mute_program"   => { entry_argument(&self.mute_program) },
+            "mute_orchestra" => { entry_argument(&self.mute_orchestra) },
+            "mute_ay"        => { entry_argument(&self.mute_ay) },
+            "ay_port"        => { entry_argument(&self.ay_port) },
+            _                => { None },
+        }
+    }
+}
+
+impl CassetteEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "file"            => { entry_argument(&self.file) },
+            "file_format"     => { entry_argument(&self.file_format) },
+            "file_offset"     => { entry_argument(&self.file_offset) },
+            "wav_copy"        => { entry_argument(&self.wav_copy) },
+            "fast_load"       => { entry_argument(&self.fast_load) },
+            "wav_threshold"   => { entry_argument(&self.wav_threshold) },
+            "wav_noise_floor" => { entry_argument(&self.wav_noise_floor) },
+            "wav_dc_filter"   => { entry_argument(&self.wav_dc_filter) },
+            "wav_channel"     => { entry_argument(&self.wav_channel) },
+            "wav_speed"       => { entry_argument(&self.wav_speed) },
+            _                 => { None },
+        }
+    }
+}
+
+impl ControllerEntries {
+    fn entry_argument(&self, entry_name: &str) -> Option<(String, ops::Range<usize>)> {
+        match entry_name {
+            "joystick_port" => { entry_argument(&self.joystick_port) },
+            "bindings"      => { entry_argument(&self.bindings) },
+            _               => { None },
+        }
+    }
+}
+
+// The configuration file parsed into the typed ConfigDocument, along with the
+// text it was parsed from, for the lines of the entries in the errors.
+struct ParsedConfig {
+    file_text: String,
+    document:  ConfigDocument,
+}
+
+impl ParsedConfig {
+    // The entry, as the source of the value given to its handler:
+    fn info_source(&self, section_name: &str, entry_name: &str) -> Option<ConfigInfoSource> {
+        self.document.entry_argument(section_name, entry_name).map(|(argument, span)| {
+            let (line_number, line_text) = file_line(&self.file_text, span.start);
+            ConfigInfoSource::from_config_file(line_number, line_text, &argument)
+        })
+    }
+}
+
+// An entry overridden on top of the configuration file:
+#[derive(Clone)]
+struct ConfigOverride {
+    entry_specifier: String,
+    invocation_text: String,
+    for_title:       bool,      // Rather than for the whole run.
+}
+
 // The configuration system structure:
 pub struct ConfigSystem {
     pub config_dir_path:  path::PathBuf,
     config_file_path:     path::PathBuf,
 
     pub config_items:     ConfigItems,
-    conf_file_lines:      Vec<String>,
+    conf_document:        toml_edit::DocumentMut,
+    conf_file_modified:   Option<time::SystemTime>,
+
+    // The entries overridden for this run, like from the command line, or
+    // for the title attached, in the order they apply in:
+    overrides:            Vec<ConfigOverride>,
 
     config_sections:      Box<[ConfigSection]>,
 }
@@ -369,10 +1108,11 @@ impl ConfigSystem {
             let mut config_file_path = config_dir.to_owned();
             config_file_path.push(CONFIG_FILE_NAME);
             let config_file_path = config_file_path;
+            let newly_created = !config_file_path.exists();
 
-            let conf_file_lines = match load_config_file(&config_file_path) {
-                Ok(lines) => {
-                    lines
+            let conf_document = match load_config_file(&config_file_path).and_then(|file_text| parse_config_text(&file_text)) {
+                Ok(document) => {
+                    document
                 },
                 Err(error) => {
                     error!("Failed to load the configuration file `{}': {}.", config_file_path.display(), error);
@@ -380,40 +1120,49 @@ impl ConfigSystem {
                     return None;
                 },
             };
+            let conf_file_modified = file_modified(&config_file_path);
             let mut new_system = ConfigSystem {
                 config_dir_path:  config_dir.to_owned(),
                 config_file_path,
 
                 config_items:     ConfigItems::new_uninitialized(),
-                conf_file_lines,
+                conf_document,
+                conf_file_modified,
+
+                overrides:        Vec::new(),
 
                 config_sections:  new_config_sections(),
             };
 
-            match new_system.sanity_check() {
+            let renamed_values = match new_system.upgrade_config_file(newly_created) {
+                Ok(renamed_values) => { renamed_values },
+                Err(error) => {
+                    error!("Failed to upgrade the configuration file: {}.", error);
+
+                    return None;
+                },
+            };
+
+            match new_system.reload_all_sections() {
                 Ok(()) => {
-                    match new_system.reload_all_sections() {
+                    info!("Configuration file parsed successfully.");
+
+                    if newly_created {
+                        new_system.convert_legacy_config_file();
+                    }
+                    new_system.carry_over_renamed_values(renamed_values);
+
+                    match new_system.write_config_file() {
                         Ok(()) => {
-                            info!("Configuration file parsed successfully.");
-
-                            match new_system.write_config_file() {
-                                Ok(()) => {
-                                    info!("Configuration file updated successfully.");
-                                },
-                                Err(error) => {
-                                    error!("Failed to update the configuration file: {}.", error);
-                                },
-                            }
-
-                            let _ = new_system.check_obsolete_entries(&new_system.config_file_path);
-                            Some(new_system)
+                            info!("Configuration file updated successfully.");
                         },
                         Err(error) => {
-                            error!("Failed to parse the configuration file: {}.", error);
-
-                            None
+                            error!("Failed to update the configuration file: {}.", error);
                         },
                     }
+
+                    new_system.check_obsolete_entries(&new_system.config_file_path);
+                    Some(new_system)
                 },
                 Err(error) => {
                     error!("Failed to parse the configuration file: {}.", error);
@@ -425,248 +1174,293 @@ impl ConfigSystem {
             None
         }
     }
-    fn sanity_check(&self) -> Result<(), ConfigError> {
-        for line_iter in 0..self.conf_file_lines.len() {
-            if self.conf_file_lines[line_iter].len() != 0 {
-                let mut chars = self.conf_file_lines[line_iter].chars();
-                let first_char = chars.next();
-
-                match first_char {
-                    Some(first_char_ch) => {
-                        match first_char_ch {
-                            // If it's a comment, it's okay.
-                            ';' => { },
-                            '#' => { },
-
-                            // If it's a section header, it must have a closing
-                            // bracket, and no space after it.
-                            '[' => {
-                                let mut found_closing_bracket = false;
-                                for current_char_ch in chars {
-                                    if found_closing_bracket {
-                                        return Err(ConfigError::TextAfterConfigSectionClosingBracket(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                    } else if current_char_ch == ']' {
-                                        found_closing_bracket = true;
-                                    } else if !current_char_ch.is_alphabetic() && !current_char_ch.is_whitespace() {
-                                        return Err(ConfigError::NonAlphaCharactersInConfigSectionName(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                    }
-                                }
-                                if !found_closing_bracket {
-                                    return Err(ConfigError::ClosingBracketMissingInConfigSectionHeader(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                }
-                            },
+    // Parse the file into the typed ConfigDocument, which the values of the
+    // entries are taken from.
+    fn parse_document(&self) -> Result<ParsedConfig, ConfigError> {
+        let file_text = self.conf_document.to_string();
+
+        match toml::from_str::<ConfigDocument>(&file_text) {
+            Ok(document) => {
+                Ok(ParsedConfig { file_text, document })
+            },
+            Err(error) => {
+                Err(ConfigError::TomlSyntaxError(toml_error_source(&file_text, error.span()), error.message().trim().replace('\n', ", ")))
+            },
+        }
+    }
+    // The version of the layout of the file.  The files without one are of
+    // version 0, and the ones with a version which isn't a number are left to
+    // parse_document().
+    fn file_version(&self) -> u32 {
+        match self.conf_document.get("version") {
+            Some(version) => {
+                version.as_integer().filter(|version| (0..=(u32::MAX as i64)).contains(version)).map(|version| version as u32).unwrap_or(CONFIG_FILE_VERSION)
+            },
+            None => {
+                0
+            },
+        }
+    }
+    // Bring a configuration file of an older version up to date, after saving
+    // a copy of it next to it.  Returns the values of the renamed entries,
+    // under their new specifiers, to be set once the file is loaded.
+    fn upgrade_config_file(&mut self, newly_created: bool) -> Result<Vec<(String, String)>, ConfigError> {
+        let version = self.file_version();
+
+        if newly_created {
+            set_file_version(&mut self.conf_document);
+            return Ok(Vec::new());
+        } else if version > CONFIG_FILE_VERSION {
+            warn!("The configuration file `{}' is of version {}, written by a newer version of the emulator, which knows the versions up to {}; the settings it doesn't know are left alone.", self.config_file_path.display(), version, CONFIG_FILE_VERSION);
+            return Ok(Vec::new());
+        } else if version == CONFIG_FILE_VERSION {
+            return Ok(Vec::new());
+        }
 
-                            // If it's an entry, it must contain one word,
-                            // followed by a single equals sign, followed by
-                            // some argument text.
-                            _ => {
-                                let mut found_equals = false;
-                                let mut found_argument = false;
-                                let mut found_space_before_equals = false;
+        let mut backup_file_path = self.config_dir_path.clone();
+        backup_file_path.push(format!("{}.v{}.bak", CONFIG_FILE_NAME, version));
+        fs::copy(&self.config_file_path, &backup_file_path)?;
 
-                                if !first_char_ch.is_alphabetic() {
-                                    return Err(ConfigError::EntryNameBeginsWithNonAlpha(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                }
-                                for current_char_ch in chars {
-                                    if !found_equals {
-                                        if current_char_ch.is_whitespace() {
-                                            found_space_before_equals = true;
-                                        } else if current_char_ch.is_alphanumeric() || current_char_ch == '_' {
-                                            if found_space_before_equals {
-                                                return Err(ConfigError::EntryNameContainsSpaces(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                            }
-                                        } else if current_char_ch == '=' {
-                                            found_equals = true;
-                                        } else {
-                                            return Err(ConfigError::EntryNameContainsNonAlnumChars(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                        }
-                                    } else {
-                                        if current_char_ch == '=' {
-                                            return Err(ConfigError::EntryContainsSeveralEqualsSigns(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                        } else if !found_argument && !current_char_ch.is_whitespace() {
-                                            found_argument = true;
-                                        }
-                                    }
-                                }
-                                if !found_equals {
-                                    return Err(ConfigError::EntryEqualsSignMissing(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                }
-                                if !found_argument {
-                                    return Err(ConfigError::EntryArgumentMissing(ConfigInfoSource::from_config_file(line_iter, &self.conf_file_lines[line_iter])));
-                                }
-                            },
-                        }
-                    },
-                    None => { },
+        let mut renamed_values = Vec::new();
+        for migration in CONFIG_MIGRATIONS.iter().filter(|migration| migration.version > version) {
+            for &(old_specifier, new_specifier) in migration.renamed_entries {
+                let (section_name, entry_name) = ConfigSystem::parse_entry_specifier(old_specifier)?;
+
+                if let Some(value) = self.conf_document.get_mut(&section_name).and_then(|section| section.as_table_like_mut()).and_then(|section| section.remove(&entry_name)) {
+                    renamed_values.push((new_specifier.to_owned(), item_argument(&value)));
+                }
+            }
+            for &(old_name, new_name) in migration.renamed_sections {
+                if let Some(section) = self.conf_document.remove(old_name) {
+                    self.conf_document.insert(new_name, section);
                 }
             }
         }
+        set_file_version(&mut self.conf_document);
+        info!("Upgraded the configuration file from version {} to version {}, the previous one was saved as `{}'.", version, CONFIG_FILE_VERSION, backup_file_path.display());
 
-        Ok(())
+        Ok(renamed_values)
     }
-    fn reload_all_sections(&mut self) -> Result<(), ConfigError> {
-        for section_iter in 0..self.config_sections.len() {
-
-            // Find the section's location:
-            let start_index;
-            let mut end_index;
-
-            match self.find_section(&self.config_sections[section_iter].section_name)? {
-                Some((found_start_index, found_end_index)) => {
-                    start_index = found_start_index;
-                    end_index   = found_end_index;
+    // Set the entries renamed by upgrade_config_file() to their old values.
+    fn carry_over_renamed_values(&mut self, renamed_values: Vec<(String, String)>) {
+        for (entry_specifier, value) in renamed_values {
+            match self.change_config_entry(&entry_specifier, &format!("= {}", value)) {
+                Ok(..) => { },
+                Err(error) => {
+                    warn!("Couldn't carry the value `{}' over into the `{}' entry: {}.", value, entry_specifier, error);
                 },
-                None => {
-                    // The section isn't in the configuration file, add it:
-                    if self.conf_file_lines.len() != 0 {
-                        self.conf_file_lines.push("".to_owned());
-                    }
-                    let loc = self.conf_file_lines.len();
-                    self.conf_file_lines.push(format!("[{}]", self.config_sections[section_iter].section_name));
-
-                    start_index = loc;
-                    end_index   = loc;
-                }
             }
+        }
+    }
+    fn reload_all_sections(&mut self) -> Result<(), ConfigError> {
+        // The values are checked before the missing entries are added, so
+        // that the errors give the lines of the file as it's written.
+        let parsed_config = self.parse_document()?;
+        self.add_missing_entries();
+        let completed_config = self.parse_document()?;
 
-            // Find all of its entries and reload them:
+        for section_iter in 0..self.config_sections.len() {
             for entry_iter in 0..self.config_sections[section_iter].entries.len() {
-                // Find the entry's location:
-                let entry_loc = match self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, start_index, end_index)? {
-                    Some(loc) => { loc },
-                    None => {
-                        // The entry doesn't exist yet, add it:
-                        let mut src_line_iter  = 0;
-                        let mut dest_line_iter = end_index + 1;
-                        while src_line_iter < self.config_sections[section_iter].entries[entry_iter].default_text.len() {
-                            self.conf_file_lines.insert(dest_line_iter, self.config_sections[section_iter].entries[entry_iter].default_text[src_line_iter].to_owned());
-                            src_line_iter += 1;
-                            dest_line_iter += 1;
-                        }
-                        end_index = dest_line_iter - 1;
-                        self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, start_index, end_index)?.expect(format!(".expect() call: Unable to find the freshly added `{}' entry in the `[{}]' section", self.config_sections[section_iter].entries[entry_iter].entry_name, self.config_sections[section_iter].section_name).as_str())
-                    },
-                };
-                (self.config_sections[section_iter].entries[entry_iter].parse_entry)(ConfigInfoSource::from_config_file(entry_loc, &self.conf_file_lines[entry_loc]), &mut self.config_items)?;
+                // The port bridges are checked against the ports of the
+                // devices, which are set up in the later sections, so
+                // they're parsed after all of them.
+                if self.config_sections[section_iter].entries[entry_iter].apply_action == ConfigChangeApplyAction::UpdatePortBridges {
+                    continue;
+                }
+                let section_name = &self.config_sections[section_iter].section_name;
+                let entry_name = &self.config_sections[section_iter].entries[entry_iter].entry_name;
+                let info_source = parsed_config.info_source(section_name, entry_name).unwrap_or_else(|| ConfigSystem::entry_info_source(&completed_config, section_name, entry_name));
+                (self.config_sections[section_iter].entries[entry_iter].parse_entry)(info_source, &mut self.config_items)?;
             }
         }
+        let info_source = parsed_config.info_source("machine", "port_bridges").unwrap_or_else(|| ConfigSystem::entry_info_source(&completed_config, "machine", "port_bridges"));
+        parse_entry_machine_port_bridges(info_source, &mut self.config_items)?;
 
         Ok(())
     }
-    fn find_section(&self, section_name: &str) -> Result<Option<(usize, usize)>, ConfigError> {
-        let mut already_found  = false;
-        let mut in_the_section = false;
-
-        let mut start_index: usize = 0;
-        let mut end_index:   usize = 0;
-
-        let compare_str = format!("[{}]", section_name.to_uppercase());
-
-        for line_iter in 0..self.conf_file_lines.len() {
-            if self.conf_file_lines[line_iter].to_uppercase() == compare_str {
-                if !already_found {
-                    already_found = true;
-                    in_the_section = true;
-                    start_index = line_iter;
-                } else {
-                    return Err(ConfigError::RedundantSection(section_name.to_owned(), start_index, line_iter));
-                }
-            } else if in_the_section {
-                if let Some(character) = self.conf_file_lines[line_iter].chars().next() {
-                    if character == '[' {
-                        // Is there a blank line before the next section starts?
-                        if (line_iter - 1) > start_index && self.conf_file_lines[line_iter - 1] == "" {
-                            end_index = line_iter - 2;
-                        } else {
-                            end_index = line_iter - 1;
-                        }
-                        in_the_section = false;
-                    }
+    // The entry as its handler reads it, from the configuration file, which
+    // has all of them once it's loaded.
+    fn entry_info_source(parsed_config: &ParsedConfig, section_name: &str, entry_name: &str) -> ConfigInfoSource {
+        match parsed_config.info_source(section_name, entry_name) {
+            Some(info_source) => { info_source },
+            None => {
+                panic!("ConfigSystem::entry_info_source(): Entry {} of Section {} is missing in the configuration file document, this is a bug", entry_name, section_name);
+            },
+        }
+    }
+    // Add the sections and the entries missing from the file, with their
+    // default values, and the comments which describe them.
+    fn add_missing_entries(&mut self) {
+        for section in self.config_sections.iter() {
+            let section_item = self.conf_document.entry(&section.section_name).or_insert_with(|| {
+                let mut table = toml_edit::Table::new();
+                table.decor_mut().set_prefix("\n");
+                toml_edit::Item::Table(table)
+            });
+
+            // The entries of a section written as an inline table are put on
+            // lines of their own, so that they can have their comments.
+            if section_item.is_inline_table() {
+                *section_item = match mem::take(section_item).into_table() {
+                    Ok(table) => { toml_edit::Item::Table(table) },
+                    Err(item) => { item },
+                };
+            }
+            // The sections which aren't tables are rejected by the parser.
+            let table = match section_item.as_table_mut() {
+                Some(table) => { table },
+                None        => { continue; },
+            };
+            for entry in section.entries.iter() {
+                if !table.contains_key(&entry.entry_name) {
+                    let (key, item) = default_entry(entry);
+                    table.insert_formatted(&key, item);
                 }
             }
         }
-        // If we haven't encountered the end of the section, that means that
-        // the section extends to the end of the file.
-        if in_the_section {
-            end_index = self.conf_file_lines.len() - 1;
+    }
+    fn check_obsolete_entries(&self, config_file_path: &path::Path) {
+        for section in self.config_sections.iter() {
+            let table = match self.conf_document.get(&section.section_name).and_then(|section_item| section_item.as_table_like()) {
+                Some(table) => { table },
+                None        => { continue; },
+            };
+            for entry_name in section.obsolete_entries.iter() {
+                if table.contains_key(entry_name) {
+                    warn!("The entry `{}' of the `[{}]' section of `{}' is from an older version of the emulator and is no longer used, it can be safely removed from the configuration file.", entry_name, section.section_name, config_file_path.display());
+                }
+            }
         }
+    }
+    fn write_config_file(&mut self) -> Result<(), ConfigError> {
 
-        if already_found {
-            Ok(Some((start_index, end_index)))
-        } else {
-            Ok(None)
+        // Use CR/LF on Windows, and plain LF everywhere else:
+        let eol_mark = match cfg!(target_os = "windows") {
+            true  => { "\r\n" },
+            false => { "\n" },
+        };
+
+        let mut out_file = fs::File::create(&self.config_file_path)?;
+        out_file.write_all(self.conf_document.to_string().replace('\n', eol_mark).as_bytes())?;
+        self.conf_file_modified = file_modified(&self.config_file_path);
+
+        Ok(())
+    }
+    // Whether the configuration file was changed by something else than the
+    // configuration system since it was last read or written.
+    pub fn config_file_changed(&self) -> bool {
+        match file_modified(&self.config_file_path) {
+            Some(modified) => { Some(modified) != self.conf_file_modified },
+            None           => { false },
         }
     }
-    fn find_entry(&self, section_name: &str, entry_name: &str, section_start: usize, section_end: usize) -> Result<Option<usize>, ConfigError> {
-        let compare_str = entry_name.to_uppercase();
+    // Read the configuration file again after it was edited, and return the
+    // specifiers of the entries which changed, along with the actions which
+    // apply them.  If the new file can't be used, the previous settings are
+    // kept.
+    pub fn reload_config_file(&mut self) -> Result<Vec<(String, ConfigChangeApplyAction)>, ConfigError> {
+        self.conf_file_modified = file_modified(&self.config_file_path);
+
+        let previous_state = self.get_config_entry_current_state_all()?;
+        let previous_document = mem::replace(&mut self.conf_document, load_config_file(&self.config_file_path).and_then(|file_text| parse_config_text(&file_text))?);
+        let previous_items = mem::replace(&mut self.config_items, ConfigItems::new_uninitialized());
+        let loaded_text = self.conf_document.to_string();
+
+        let current_state = match self.reload_all_sections().and_then(|()| self.apply_overrides()).and_then(|()| self.get_config_entry_current_state_all()) {
+            Ok(state) => { state },
+            Err(error) => {
+                self.conf_document = previous_document;
+                self.config_items = previous_items;
+                return Err(error);
+            },
+        };
 
-        let mut already_found = false;
-        let mut found_loc: usize = 0;
+        // Put back the entries which were removed from the file:
+        if self.conf_document.to_string() != loaded_text {
+            self.write_config_file()?;
+        }
 
-        let mut line_iter = section_start;
-        while line_iter <= section_end {
-            let mut first_word = String::new();
-            for character in self.conf_file_lines[line_iter].chars() {
-                if character.is_whitespace() || character == '=' {
-                    break;
+        let mut changes = Vec::new();
+        let mut state_iter = 0;
+        for section in self.config_sections.iter() {
+            for entry in section.entries.iter() {
+                let entry_specifier = format!("{}_{}", section.section_name, entry.entry_name);
+                if previous_state[state_iter] != current_state[state_iter] && !self.is_overridden(&entry_specifier) {
+                    changes.push((entry_specifier, entry.apply_action));
                 }
-                first_word.push(character);
+                state_iter += 1;
             }
-            if first_word.to_uppercase() == compare_str {
-                if !already_found {
-                    already_found = true;
-                    found_loc = line_iter
-                } else {
-                    return Err(ConfigError::RedundantEntry(section_name.to_owned(), entry_name.to_owned(), found_loc, line_iter));
+        }
+        Ok(changes)
+    }
+    // Override an entry for this run only, on top of the configuration file,
+    // without writing it into the file.  The override outlasts the reloads of
+    // the file, until the entry is changed with change_config_entry().
+    pub fn override_config_entry(&mut self, entry_specifier: &str, invocation_text: &str) -> Result<ConfigChangeApplyAction, ConfigError> {
+        self.add_override(entry_specifier, invocation_text, false)
+    }
+    // The same for the settings of a title, which are taken back once the
+    // title is detached, with clear_title_overrides().
+    pub fn override_config_entry_for_title(&mut self, entry_specifier: &str, invocation_text: &str) -> Result<ConfigChangeApplyAction, ConfigError> {
+        self.add_override(entry_specifier, invocation_text, true)
+    }
+    // Go back to the settings from before the title overrides, and return
+    // the actions which apply them.
+    pub fn clear_title_overrides(&mut self) -> Result<Vec<ConfigChangeApplyAction>, ConfigError> {
+        let mut apply_actions = Vec::new();
+        for config_override in self.overrides.iter().filter(|config_override| config_override.for_title) {
+            if let Some(apply_action) = self.entry_apply_action(&config_override.entry_specifier) {
+                if !apply_actions.contains(&apply_action) {
+                    apply_actions.push(apply_action);
                 }
             }
-
-            line_iter += 1;
         }
+        self.overrides.retain(|config_override| !config_override.for_title);
 
-        if already_found {
-            Ok(Some(found_loc))
-        } else {
-            Ok(None)
-        }
+        self.reload_all_sections()?;
+        self.apply_overrides()?;
+        Ok(apply_actions)
     }
-    fn check_obsolete_entries(&self, config_file_path: &path::Path) -> Result<(), ConfigError> {
-
-        for section_iter in 0..self.config_sections.len() {
-
-            match self.find_section(&self.config_sections[section_iter].section_name)? {
-                Some((start_index, end_index)) => {
-                    for entry_iter in 0..self.config_sections[section_iter].obsolete_entries.len() {
+    fn add_override(&mut self, entry_specifier: &str, invocation_text: &str, for_title: bool) -> Result<ConfigChangeApplyAction, ConfigError> {
+        let (requested_section, requested_entry) = ConfigSystem::parse_entry_specifier(entry_specifier)?;
 
-                        match self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].obsolete_entries[entry_iter], start_index, end_index)? {
-                            Some(loc) => {
-                                warn!("The entry `{}' of the `[{}]' section at line {} of `{}' is from an older version of the emulator and is no longer used, it can be safely removed from the configuration file.", self.config_sections[section_iter].obsolete_entries[entry_iter], self.config_sections[section_iter].section_name, loc + 1, config_file_path.display());
-                            },
-                            None => { },
-                        }
-                    }
-                },
-                None => { }
-            };
+        for section in self.config_sections.iter() {
+            for entry in section.entries.iter() {
+                if section.section_name.to_lowercase() == requested_section && entry.entry_name.to_lowercase() == requested_entry {
+                    (entry.parse_entry)(ConfigInfoSource::from_external_source(&section.section_name, &entry.entry_name, invocation_text), &mut self.config_items)?;
+
+                    let entry_specifier = format!("{}_{}", requested_section, requested_entry);
+                    self.overrides.retain(|config_override| config_override.entry_specifier != entry_specifier || config_override.for_title != for_title);
+                    self.overrides.push(ConfigOverride {
+                        entry_specifier,
+                        invocation_text: invocation_text.to_owned(),
+                        for_title,
+                    });
+                    return Ok(entry.apply_action);
+                }
+            }
         }
-        Ok(())
+        Err(ConfigError::EntrySpecNoSuchConfigEntry(entry_specifier.to_owned()))
     }
-    fn write_config_file(&self) -> Result<(), ConfigError> {
-
-        // Use CR/LF on Windows, and plain LF everywhere else:
-        let eol_mark = match cfg!(target_os = "windows") {
-            true  => { "\r\n" },
-            false => { "\n" },
-        };
-
-        let mut out_file = fs::File::create(&self.config_file_path)?;
-        for line in &self.conf_file_lines {
-            out_file.write_all(line.as_bytes())?;
-            out_file.write_all(eol_mark.as_bytes())?;
+    fn apply_overrides(&mut self) -> Result<(), ConfigError> {
+        for config_override in self.overrides.clone() {
+            self.add_override(&config_override.entry_specifier, &config_override.invocation_text, config_override.for_title)?;
         }
-
         Ok(())
     }
+    fn is_overridden(&self, entry_specifier: &str) -> bool {
+        self.overrides.iter().any(|config_override| config_override.entry_specifier == entry_specifier)
+    }
+    fn entry_apply_action(&self, entry_specifier: &str) -> Option<ConfigChangeApplyAction> {
+        for section in self.config_sections.iter() {
+            for entry in section.entries.iter() {
+                if format!("{}_{}", section.section_name, entry.entry_name) == entry_specifier {
+                    return Some(entry.apply_action);
+                }
+            }
+        }
+        None
+    }
     fn parse_entry_specifier(entry_specifier: &str) -> Result<(String, String), ConfigError> {
         let mut section_acc = String::new();
         let mut entry_acc = String::new();
@@ -699,29 +1493,18 @@ impl ConfigSystem {
     }
     pub fn get_config_entry_current_state(&self, entry_specifier: &str) -> Result<String, ConfigError> {
         let (requested_section, requested_entry) = ConfigSystem::parse_entry_specifier(entry_specifier)?;
+        let parsed_config = self.parse_document()?;
 
         for section_iter in 0..self.config_sections.len() {
             let section_name_lc = self.config_sections[section_iter].section_name.to_lowercase();
 
             if section_name_lc == requested_section {
-                let (start_index, end_index) = match self.find_section(&self.config_sections[section_iter].section_name)? {
-                    Some((found_start_index, found_end_index)) => { (found_start_index, found_end_index) },
-                    None => {
-                        panic!("ConfigSystem::get_config_entry_current_state(): Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].section_name);
-                    }
-                };
                 for entry_iter in 0..self.config_sections[section_iter].entries.len() {
                     let entry_name_lc = self.config_sections[section_iter].entries[entry_iter].entry_name.to_lowercase();
 
                     if entry_name_lc == requested_entry {
-                        let entry_loc = match self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, start_index, end_index)? {
-                            Some(loc) => { loc },
-                            None => {
-                                panic!("ConfigSystem::get_config_entry_current_state(): Entry {} of Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].entries[entry_iter].entry_name, self.config_sections[section_iter].section_name);
-                            },
-                        };
-                        let assignee = retrieve_entry_assignee(&self.conf_file_lines[entry_loc]);
-                        return Ok(format!("{}_{} = {}", section_name_lc, entry_name_lc, assignee));
+                        let info_source = ConfigSystem::entry_info_source(&parsed_config, &self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name);
+                        return Ok(format!("{}_{} = {}", section_name_lc, entry_name_lc, info_source.argument_text()));
                     }
                 }
             }
@@ -729,33 +1512,69 @@ impl ConfigSystem {
         Err(ConfigError::EntrySpecNoSuchConfigEntry(entry_specifier.to_owned()))
     }
     pub fn get_config_entry_current_state_all(&self) -> Result<Vec<String>, ConfigError> {
+        let parsed_config = self.parse_document()?;
         let mut entry_state_collection = Vec::new();
 
         for section_iter in 0..self.config_sections.len() {
             let section_name_lc = self.config_sections[section_iter].section_name.to_lowercase();
 
-            let (start_index, end_index) = match self.find_section(&self.config_sections[section_iter].section_name)? {
-                Some((found_start_index, found_end_index)) => { (found_start_index, found_end_index) },
-                None => {
-                    panic!("ConfigSystem::get_config_entry_current_state_all(): Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].section_name);
-                }
-            };
             for entry_iter in 0..self.config_sections[section_iter].entries.len() {
                 let entry_name_lc = self.config_sections[section_iter].entries[entry_iter].entry_name.to_lowercase();
 
-                let entry_loc = match self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, start_index, end_index)? {
-                    Some(loc) => { loc },
+                let info_source = ConfigSystem::entry_info_source(&parsed_config, &self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name);
+                entry_state_collection.push(format!("{}_{} = {}", section_name_lc, entry_name_lc, info_source.argument_text()));
+            }
+        }
+
+        Ok(entry_state_collection)
+    }
+    // The value of an entry as it's written in the configuration file, which
+    // has all of them once it's loaded.
+    fn entry_value(&self, section_name: &str, entry_name: &str) -> &toml_edit::Value {
+        match self.conf_document.get(section_name).and_then(|section| section.get(entry_name)).and_then(|entry| entry.as_value()) {
+            Some(value) => { value },
+            None => {
+                panic!("ConfigSystem::entry_value(): Entry {} of Section {} is missing in the configuration file document, this is a bug", entry_name, section_name);
+            },
+        }
+    }
+    // The configuration in effect, in the syntax of the configuration file:
+    // the entries as they're written in the file, apart from the overridden
+    // ones, which are followed by a comment saying where they come from.
+    pub fn get_effective_config(&self) -> Result<Vec<String>, ConfigError> {
+        let parsed_config = self.parse_document()?;
+        let mut config_items = self.config_items.clone();
+        let mut lines = vec![format!("version = {}", CONFIG_FILE_VERSION)];
+
+        for section in self.config_sections.iter() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section.section_name));
+
+            for entry in section.entries.iter() {
+                let entry_specifier = format!("{}_{}", section.section_name.to_lowercase(), entry.entry_name.to_lowercase());
+                let config_override = self.overrides.iter().rev().find(|config_override| config_override.entry_specifier == entry_specifier);
+                let entry_value = self.entry_value(&section.section_name, &entry.entry_name);
+
+                // The handler gives a new line only if the value in effect
+                // isn't the one in the file.
+                match (entry.update_line)(ConfigSystem::entry_info_source(&parsed_config, &section.section_name, &entry.entry_name), &mut config_items) {
+                    Some(updated_line) => {
+                        let origin = match config_override {
+                            Some(ConfigOverride { for_title: true, .. }) => { "from the settings of the title" },
+                            _                                            => { "from the command line" },
+                        };
+                        lines.push(format!("{} = {}    # {}", entry.entry_name, toml_entry_value(&updated_line, entry_value), origin));
+                    },
                     None => {
-                        panic!("ConfigSystem::get_config_entry_current_state_all(): Entry {} of Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].entries[entry_iter].entry_name, self.config_sections[section_iter].section_name);
+                        lines.push(format!("{} = {}", entry.entry_name, entry_value.clone().decorated("", "")));
                     },
-                };
-                let assignee = retrieve_entry_assignee(&self.conf_file_lines[entry_loc]);
-
-                entry_state_collection.push(format!("{}_{} = {}", section_name_lc, entry_name_lc, assignee));
+                }
             }
         }
 
-        Ok(entry_state_collection)
+        Ok(lines)
     }
     pub fn change_config_entry(&mut self, entry_specifier: &str, invocation_text: &str) -> Result<ConfigChangeApplyAction, ConfigError> {
         let (requested_section, requested_entry) = ConfigSystem::parse_entry_specifier(entry_specifier)?;
@@ -764,29 +1583,37 @@ impl ConfigSystem {
             let section_name_lc = self.config_sections[section_iter].section_name.to_lowercase();
 
             if section_name_lc == requested_section {
-                let (start_index, end_index) = match self.find_section(&self.config_sections[section_iter].section_name)? {
-                    Some((found_start_index, found_end_index)) => { (found_start_index, found_end_index) },
-                    None => {
-                        panic!("ConfigSystem::get_config_entry_current_state(): Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].section_name);
-                    }
-                };
                 for entry_iter in 0..self.config_sections[section_iter].entries.len() {
                     let entry_name_lc = self.config_sections[section_iter].entries[entry_iter].entry_name.to_lowercase();
 
                     if entry_name_lc == requested_entry {
-                        let entry_loc = match self.find_entry(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, start_index, end_index)? {
-                            Some(loc) => { loc },
-                            None => {
-                                panic!("ConfigSystem::get_config_entry_current_state(): Entry {} of Section {} is missing in the configuration file text buffer, this is a bug", self.config_sections[section_iter].entries[entry_iter].entry_name, self.config_sections[section_iter].section_name);
-                            },
-                        };
                         (self.config_sections[section_iter].entries[entry_iter].parse_entry)(ConfigInfoSource::from_external_source(&self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name, invocation_text), &mut self.config_items)?;
-                        match (self.config_sections[section_iter].entries[entry_iter].update_line)(ConfigInfoSource::from_config_file(entry_loc, &self.conf_file_lines[entry_loc]), &mut self.config_items) {
+
+                        // A change of an overridden entry ends the override,
+                        // which may have been different from the file.
+                        let entry_specifier = format!("{}_{}", section_name_lc, entry_name_lc);
+                        let was_overridden = self.is_overridden(&entry_specifier);
+                        self.overrides.retain(|config_override| config_override.entry_specifier != entry_specifier);
+
+                        let parsed_config = self.parse_document()?;
+                        let info_source = ConfigSystem::entry_info_source(&parsed_config, &self.config_sections[section_iter].section_name, &self.config_sections[section_iter].entries[entry_iter].entry_name);
+                        match (self.config_sections[section_iter].entries[entry_iter].update_line)(info_source, &mut self.config_items) {
                             Some(updated_line) => {
-                                self.conf_file_lines[entry_loc] = updated_line;
+                                let section_name = self.config_sections[section_iter].section_name.clone();
+                                let entry_name = self.config_sections[section_iter].entries[entry_iter].entry_name.clone();
+                                let new_value = toml_entry_value(&updated_line, self.entry_value(&section_name, &entry_name));
+
+                                // The value is replaced in place, keeping the
+                                // comments around it.
+                                if let Some(value) = self.conf_document.get_mut(&section_name).and_then(|section| section.get_mut(&entry_name)).and_then(|entry| entry.as_value_mut()) {
+                                    replace_value(value, new_value);
+                                }
                                 self.write_config_file()?;
                                 return Ok(self.config_sections[section_iter].entries[entry_iter].apply_action);
                             },
+                            None if was_overridden => {
+                                return Ok(self.config_sections[section_iter].entries[entry_iter].apply_action);
+                            },
                             None => {
                                 return Ok(ConfigChangeApplyAction::AlreadyUpToDate);
                             },
@@ -797,6 +1624,45 @@ impl ConfigSystem {
         }
         Err(ConfigError::EntrySpecNoSuchConfigEntry(entry_specifier.to_owned()))
     }
+    // Carry the entries of the configuration file of the older versions over
+    // into the freshly created one, the ones of the general section into the
+    // sections they were moved to.
+    fn convert_legacy_config_file(&mut self) {
+        let mut legacy_file_path = self.config_dir_path.clone();
+        legacy_file_path.push(LEGACY_CONFIG_FILE_NAME);
+
+        if !legacy_file_path.exists() {
+            return;
+        }
+        let legacy_file_text = match load_config_file(&legacy_file_path) {
+            Ok(file_text) => { file_text },
+            Err(error) => {
+                error!("Failed to load the old configuration file `{}': {}.", legacy_file_path.display(), error);
+                return;
+            },
+        };
+
+        let mut section_name = String::new();
+        for (line_number, line) in legacy_file_text.lines().map(|line| line.trim()).enumerate() {
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            } else if line.starts_with('[') {
+                section_name = line.trim_matches(|character| character == '[' || character == ']').trim().to_lowercase();
+                continue;
+            }
+
+            let entry_name = line.split('=').next().unwrap_or("").trim().to_lowercase();
+            let entry_specifier = format!("{}_{}", legacy_entry_section(&section_name, &entry_name), entry_name);
+
+            match self.change_config_entry(&entry_specifier, &format!("= {}", retrieve_entry_assignee(line))) {
+                Ok(..) => { },
+                Err(error) => {
+                    warn!("Couldn't carry over line {} of `{}': {}.", line_number + 1, legacy_file_path.display(), error);
+                },
+            }
+        }
+        info!("Carried the settings of `{}' over into `{}', the old file is no longer used and can be removed.", legacy_file_path.display(), self.config_file_path.display());
+    }
 }
 
 // Find the %AppData% directory on Windows:
@@ -872,9 +1738,14 @@ fn check_config_dir<P: AsRef<path::Path>>(config_dir_in: P) -> bool {
     }
 }
 
-// Load the configuration file into a vector of strings representing lines:
+// The time the configuration file was last modified at, if it can be found:
+fn file_modified(file_path: &path::Path) -> Option<time::SystemTime> {
+    fs::metadata(file_path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Load the text of the configuration file:
 fn load_config_file<P: AsRef<path::Path>>(config_file_path_in: P)
-                   -> Result<Vec<String>, ConfigError> {
+                   -> Result<String, ConfigError> {
 
     let config_file_path = config_file_path_in.as_ref() as &path::Path;
     if config_file_path.exists() {
@@ -883,46 +1754,86 @@ fn load_config_file<P: AsRef<path::Path>>(config_file_path_in: P)
         let mut buffer = String::new();
         config_file.read_to_string(&mut buffer)?;
 
-        // Split it into lines:
-        let mut current_line = String::new();
-        let mut line_collection: Vec<String> = Vec::new();
-
-        for current_char in buffer.chars() {
-            // For line-splitting, it is assumed that either an LF or CR/LF is
-            // the line separator.  The code ignores any CR, so both of the
-            // above cases end up with LF as the line separator.
-
-            if current_char == '\n' {
-                line_collection.push(current_line.trim().to_owned());
-                current_line = String::new();
-            } else if current_char == '\r' {
-                // Nothing.
-            } else {
-                current_line.push(current_char);
-            }
-        }
-        if !current_line.is_empty() {
-            line_collection.push(current_line.trim().to_owned());
-        }
-
+        // It is assumed that either an LF or CR/LF is the line separator.  The
+        // code ignores any CR, so both of the above cases end up with LF as
+        // the line separator.
         info!("Successfully loaded the configuration file `{}'.", config_file_path.display());
-        Ok(line_collection)
+        Ok(buffer.replace('\r', ""))
     } else {
         // Nothing to load:
         fs::File::create(config_file_path)?;
         info!("Created new configuration file `{}'.", config_file_path.display());
-        Ok(Vec::new())
+        Ok(String::new())
+    }
+}
+
+// Parse the text of the configuration file as a TOML document, which keeps its
+// comments and layout:
+fn parse_config_text(file_text: &str) -> Result<toml_edit::DocumentMut, ConfigError> {
+    match file_text.parse::<toml_edit::DocumentMut>() {
+        Ok(document) => {
+            Ok(document)
+        },
+        Err(error) => {
+            Err(ConfigError::TomlSyntaxError(toml_error_source(file_text, error.span()), error.message().trim().replace('\n', ", ")))
+        },
+    }
+}
+
+// The line of the file an error of the TOML parser is on, and its number:
+fn file_line(file_text: &str, offset: usize) -> (usize, &str) {
+    let line_number = file_text[..offset.min(file_text.len())].matches('\n').count();
+
+    (line_number, file_text.lines().nth(line_number).unwrap_or("").trim())
+}
+fn toml_error_source(file_text: &str, span: Option<ops::Range<usize>>) -> Option<ConfigInfoSource> {
+    span.map(|span| {
+        let (line_number, line_text) = file_line(file_text, span.start);
+        ConfigInfoSource::from_config_file(line_number, line_text, "")
+    })
+}
+
+// Write the version of the layout at the top of the file, with the comment
+// which describes it when it isn't there yet:
+fn set_file_version(document: &mut toml_edit::DocumentMut) {
+    if let Some(version) = document.get_mut("version").and_then(|version| version.as_value_mut()) {
+        replace_value(version, toml_edit::Value::from(CONFIG_FILE_VERSION as i64));
+    } else if !document.contains_key("version") {
+        let comment = "# The version of the layout of this file, used to bring it up to date\n\
+                       # when a newer version of the emulator changes it.  Leave it as it is.\n";
+        let key = toml_edit::Key::new("version").with_leaf_decor(toml_edit::Decor::new(comment, " "));
+        document.insert_formatted(&key, toml_edit::value(CONFIG_FILE_VERSION as i64));
+
+        // With a blank line between it and the first section:
+        if let Some(first_section) = document.iter_mut().filter_map(|(_, item)| item.as_table_mut()).min_by_key(|table| table.position()) {
+            let prefix = first_section.decor().prefix().and_then(|prefix| prefix.as_str()).unwrap_or("").to_owned();
+            if !prefix.starts_with('\n') {
+                first_section.decor_mut().set_prefix(format!("\n{}", prefix));
+            }
+        }
     }
 }
 
+// The key and the value of an entry added to the file, parsed from its default
+// text, so that the key carries the comments written above it:
+fn default_entry(entry: &ConfigEntry) -> (toml_edit::Key, toml_edit::Item) {
+    let mut document = entry.default_text.join("\n").parse::<toml_edit::DocumentMut>().expect(format!(".expect() call: The default text of the `{}' entry isn't valid TOML", entry.entry_name).as_str());
+
+    document.remove_entry(&entry.entry_name).expect(format!(".expect() call: The default text of the `{}' entry doesn't set it", entry.entry_name).as_str())
+}
+
 // Configuration sections:
 fn new_config_sections() -> Box<[ConfigSection]> {
     let mut sections: Vec<ConfigSection> = Vec::new();
 
-    sections.push(new_general_section());
+    sections.push(new_machine_section());
+    sections.push(new_paths_section());
+    sections.push(new_interface_section());
     sections.push(new_keyboard_section());
     sections.push(new_video_section());
+    sections.push(new_audio_section());
     sections.push(new_cassette_section());
+    sections.push(new_controller_section());
 
     sections.into_boxed_slice()
 }
@@ -964,6 +1875,139 @@ fn retrieve_entry_assignee(entry_string: &str) -> String {
     argument
 }
 
+// The value of an entry as the argument text the handlers take, for the ones
+// which are no longer in the ConfigDocument, like the renamed entries: strings
+// without their quotes, other values the way they're written in TOML.
+fn item_argument(item: &toml_edit::Item) -> String {
+    match (item.as_str(), item.as_value()) {
+        (Some(text), _)     => { text.to_owned() },
+        (None, Some(value)) => { value.clone().decorated("", "").to_string() },
+        (None, None)        => { String::new() },
+    }
+}
+
+// The value of an entry line of the handlers, `name = argument', in TOML, of
+// the type of the value it replaces: numbers and booleans are kept as such,
+// anything else is written as a string.
+fn toml_entry_value(entry_string: &str, previous_value: &toml_edit::Value) -> toml_edit::Value {
+    let argument = retrieve_entry_assignee(entry_string);
+
+    if previous_value.is_integer() {
+        if let Ok(number) = argument.parse::<i64>() {
+            return toml_edit::Value::from(number);
+        }
+    } else if previous_value.is_bool() {
+        if let Some(flag) = parse_bool_argument(&argument) {
+            return toml_edit::Value::from(flag);
+        }
+    }
+    toml_edit::Value::from(argument)
+}
+
+// Replace a value of the file, keeping the spacing and the comment around it:
+fn replace_value(value: &mut toml_edit::Value, new_value: toml_edit::Value) {
+    let decor = value.decor().clone();
+
+    *value = new_value;
+    *value.decor_mut() = decor;
+}
+
+// The section an entry of the `config.ini' file of the older versions is in
+// now.
+fn legacy_entry_section<'a>(section_name: &'a str, entry_name: &str) -> &'a str {
+    match (section_name, entry_name) {
+        ("general", "default_rom") | ("general", "ram_size") => {
+            "machine"
+        },
+        ("general", "aliases") | ("general", "ui_theme") | ("general", "ui_colors") | ("general", "language") | ("general", "confirm_actions") => {
+            "interface"
+        },
+        ("general", _) | ("video", "screenshot_dir") | ("cassette", "library_dir") => {
+            "paths"
+        },
+        ("sound", _) => {
+            "audio"
+        },
+        (section_name, _) => {
+            section_name
+        },
+    }
+}
+
+// Example of a valid port bridges argument: `F0h > printer.txt, F1h <>
+// tcp:localhost:5000'.
+pub fn parse_port_bridges_argument(entry_argument: &str, device_ports: &[u16]) -> Option<Vec<bridge::BridgeSpec>> {
+    let mut specs: Vec<bridge::BridgeSpec> = Vec::new();
+    if entry_argument.trim().to_lowercase() == "none" {
+        return Some(specs);
+    }
+
+    for item in entry_argument.split(',') {
+        let item = item.trim();
+        let port_text = item.split(|character: char| character.is_whitespace() || character == '<' || character == '>').next().unwrap_or("");
+        let port = match util::parse_u32_from_str(port_text) {
+            Some(port) if port < (memory::CAS_MODESEL_BASE as u32) => { port as u16 },
+            _ => { return None; },
+        };
+        if port == memory::ORCH_BASE || port == (memory::ORCH_BASE + (memory::ORCH_SIZE - 1)) || device_ports.contains(&port) {
+            return None;
+        }
+
+        let rest = item[port_text.len()..].trim_start();
+        let (direction, target_text) = if let Some(target_text) = rest.strip_prefix("<>") {
+            (bridge::BridgeDirection::Both, target_text.trim())
+        } else if let Some(target_text) = rest.strip_prefix('<') {
+            (bridge::BridgeDirection::Read, target_text.trim())
+        } else if let Some(target_text) = rest.strip_prefix('>') {
+            (bridge::BridgeDirection::Write, target_text.trim())
+        } else {
+            return None;
+        };
+        let target = match target_text.strip_prefix("tcp:") {
+            Some(address) => {
+                match address.rsplit_once(':') {
+                    Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                        bridge::BridgeTarget::Tcp(address.to_owned())
+                    },
+                    _ => { return None; },
+                }
+            },
+            None if !target_text.is_empty() => { bridge::BridgeTarget::File(path::PathBuf::from(target_text)) },
+            None => { return None; },
+        };
+
+        if specs.iter().any(|spec| (spec.port as u16) == port && spec.direction.overlaps(direction)) {
+            return None;
+        }
+        specs.push(bridge::BridgeSpec { port: port as u8, direction, target });
+    }
+    Some(specs)
+}
+
+// The parts of a rom entry, joined with ` + ', as the file names, and the
+// offsets given after `@'.  Returns None if an offset isn't within the rom.
+pub fn parse_rom_parts(entry_argument: &str) -> Option<Vec<(String, Option<u16>)>> {
+    let mut parts = Vec::new();
+
+    for part in entry_argument.split('+') {
+        let part = part.trim();
+        let (file_name, offset) = match part.rsplit_once('@') {
+            Some((file_name, offset_text)) => {
+                match util::parse_u32_from_str(offset_text) {
+                    Some(offset) if offset < memory::ROM_SIZE as u32 => { (file_name.trim(), Some(offset as u16)) },
+                    _ => { return None; },
+                }
+            },
+            None => { (part, None) },
+        };
+        if file_name.is_empty() {
+            return None;
+        }
+        parts.push((file_name.to_owned(), offset));
+    }
+    Some(parts)
+}
+
 // Example of a valid resolution argument: `1024x768'.
 fn parse_resolution_argument(entry_argument: &str) -> Option<(u32, u32)> {
 
@@ -1002,7 +2046,7 @@ fn parse_resolution_argument(entry_argument: &str) -> Option<(u32, u32)> {
 }
 
 // Example of a valid color argument: `#00FF00'.
-fn parse_color_argument(entry_argument: &str) -> Option<(u8, u8, u8)> {
+pub fn parse_color_argument(entry_argument: &str) -> Option<(u8, u8, u8)> {
 
     let mut chars = entry_argument.chars();
 
@@ -1104,1094 +2148,4544 @@ fn parse_bool_argument(entry_argument: &str) -> Option<bool> {
     }
 }
 
-// The general section and entries:
-fn update_line_general_level_1_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.general_level_1_rom.clone();
+// The machine section and entries:
+fn update_line_machine_default_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.machine_default_rom;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_general_level_1_rom(info_source, config_items) {
+    let failed_read = match parse_entry_machine_default_rom(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.general_level_1_rom != new_val {
-        config_items.general_level_1_rom = new_val.clone();
-        match new_val {
-            Some(value) => {
-                Some(format!("level_1_rom = {}", value))
-            },
-            None => {
-                Some("level_1_rom = none".to_owned())
-            },
-        }
+    if failed_read || config_items.machine_default_rom != new_val {
+        config_items.machine_default_rom = new_val;
+        Some(format!("default_rom = {}", new_val))
     } else {
         None
     }
 }
-fn update_line_general_level_2_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.general_level_2_rom.clone();
+fn parse_entry_machine_default_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument >= 1 && argument <= 3 {
+        config_items.machine_default_rom = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::DefaultRomOutOfRange(info_source, argument))
+    }
+}
+
+fn update_line_machine_ram_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.machine_ram_size;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_general_level_2_rom(info_source, config_items) {
+    let failed_read = match parse_entry_machine_ram_size(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.general_level_2_rom != new_val {
-        config_items.general_level_2_rom = new_val.clone();
-        match new_val {
-            Some(value) => {
-                Some(format!("level_2_rom = {}", value))
-            },
-            None => {
-                Some("level_2_rom = none".to_owned())
-            },
+    if failed_read || config_items.machine_ram_size != new_val {
+        config_items.machine_ram_size = new_val;
+        if (new_val % 1024) == 0 {
+            Some(format!("ram_size = {}K", new_val / 1024))
+        } else {
+            Some(format!("ram_size = {}", new_val))
         }
     } else {
         None
     }
 }
-fn update_line_general_misc_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.general_misc_rom.clone();
+fn parse_entry_machine_ram_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let mut new_ram_size = 0;
+    let mut found_suffix = false;
+
+    for character in info_source.argument_text().chars() {
+        if !found_suffix {
+            match character.to_digit(10) {
+                Some(digit) => {
+                    new_ram_size = (new_ram_size * 10) + digit;
+                },
+                None => {
+                    if character == 'k' || character == 'K' {
+                        new_ram_size *= 1024;
+                        found_suffix = true;
+                    } else {
+                        return Err(ConfigError::InvalidRamSpecifier(info_source));
+                    }
+                },
+            }
+        } else {
+            return Err(ConfigError::InvalidRamSpecifier(info_source));
+        }
+    }
+    if new_ram_size <= 48 * 1024 {
+        config_items.machine_ram_size = new_ram_size;
+        Ok(())
+    } else {
+        Err(ConfigError::TooMuchRamRequested(info_source, new_ram_size))
+    }
+}
+fn update_line_machine_port_bridges(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.machine_port_bridges.clone();
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_general_misc_rom(info_source, config_items) {
+    let failed_read = match parse_entry_machine_port_bridges(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.general_misc_rom != new_val {
-        config_items.general_misc_rom = new_val.clone();
-        match new_val {
-            Some(value) => {
-                Some(format!("misc_rom = {}", value))
-            },
-            None => {
-                Some("misc_rom = none".to_owned())
-            },
+    if failed_read || config_items.machine_port_bridges != new_val {
+        config_items.machine_port_bridges = new_val.clone();
+        if new_val.is_empty() {
+            Some("port_bridges = none".to_owned())
+        } else {
+            Some(format!("port_bridges = {}", new_val.iter().map(|spec| spec.to_string()).collect::<Vec<String>>().join(", ")))
         }
     } else {
         None
     }
 }
-
-fn parse_entry_general_level_1_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = info_source.argument_text();
-
-    if argument.to_uppercase() == "NONE" {
-        config_items.general_level_1_rom = None;
-    } else {
-        config_items.general_level_1_rom = Some(argument);
+// The IO ports of the emulated devices which are set up, which the port
+// bridges can't be put on.
+fn device_ports(config_items: &ConfigItems) -> Vec<u16> {
+    let mut ports = Vec::new();
+    if let Some(port) = config_items.controller_joystick_port {
+        ports.push(port as u16);
     }
-
-    Ok(())
-}
-fn parse_entry_general_level_2_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = info_source.argument_text();
-
-    if argument.to_uppercase() == "NONE" {
-        config_items.general_level_2_rom = None;
-    } else {
-        config_items.general_level_2_rom = Some(argument);
+    if config_items.video_hires_board {
+        ports.extend(hires::HIRES_BASE..(hires::HIRES_BASE + hires::HIRES_SIZE));
     }
-
-    Ok(())
+    if config_items.video_character_generator_file.is_some() {
+        ports.push(memory::ALTCHR_BASE); // In case it has an alternate set.
+    }
+    if let Some(port) = config_items.audio_ay_port {
+        ports.extend([port as u16, (port as u16) + 1]);
+    }
+    ports
+}
+fn parse_entry_machine_port_bridges(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_port_bridges_argument(&info_source.argument_text(), &device_ports(config_items)) {
+        Some(specs) => {
+            config_items.machine_port_bridges = specs;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidPortBridgesSpecifier(info_source))
+        },
+    }
+}
+
+fn new_handler_machine_default_rom() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Which of the three rom files of the `[paths]' section to use (1 to 3); this".to_owned());
+    default_text.push("# can be overridden on program startup, using the -1, -2 and -3 command-line".to_owned());
+    default_text.push("# arguments.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("default_rom = 2".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "default_rom".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateDefaultRomSelection,
+        update_line:  update_line_machine_default_rom,
+        parse_entry:  parse_entry_machine_default_rom,
+    }
+}
+fn new_handler_machine_ram_size() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The amount of memory the machine has installed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Common values are 4K, 16K, 32K and 48K.  You can have at most 48K installed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# You can specify the amount of memory either in bytes (without a suffix), or".to_owned());
+    default_text.push("# in kilobytes, by appending the K suffix to the number.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("ram_size = \"16K\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "ram_size".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeRamSize,
+        update_line:  update_line_machine_ram_size,
+        parse_entry:  parse_entry_machine_ram_size,
+    }
+}
+
+fn new_handler_machine_port_bridges() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Simple peripherals connecting IO ports of the machine to files or to TCP".to_owned());
+    default_text.push("# connections of the host, as a comma-separated list, or none.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Each bridge is written as the port, the direction and the target, like".to_owned());
+    default_text.push("# \"F0h > printer.txt, F1h < tcp:localhost:5000\".  With `>', the bytes the".to_owned());
+    default_text.push("# machine writes to the port are appended to the file, or sent through the".to_owned());
+    default_text.push("# connection.  With `<', each read of the port gives the next byte of the".to_owned());
+    default_text.push("# file, or the next byte which came through the connection, and 0xFF when".to_owned());
+    default_text.push("# there's nothing more to read.  With `<>', the bridge does both.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The files which aren't full paths are taken as located in the".to_owned());
+    default_text.push("# configuration directory.  The ports used by the emulated devices, the".to_owned());
+    default_text.push("# joystick interface, the graphics board, the sound board and the character".to_owned());
+    default_text.push("# set switch of a character generator file, can't have bridges on them.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("port_bridges = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "port_bridges".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdatePortBridges,
+        update_line:  update_line_machine_port_bridges,
+        parse_entry:  parse_entry_machine_port_bridges,
+    }
+}
+
+fn new_machine_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
+
+    entries.push(new_handler_machine_default_rom());
+    entries.push(new_handler_machine_ram_size());
+    entries.push(new_handler_machine_port_bridges());
+
+    let obsolete_entries: Vec<String> = Vec::new();
+
+    ConfigSection {
+        section_name:     "machine".to_owned(),
+        entries:          entries.into_boxed_slice(),
+        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    }
+}
+
+// The paths section and entries:
+fn update_line_paths_level_1_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_level_1_rom.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_paths_level_1_rom(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.paths_level_1_rom != new_val {
+        config_items.paths_level_1_rom = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("level_1_rom = {}", value))
+            },
+            None => {
+                Some("level_1_rom = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn update_line_paths_level_2_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_level_2_rom.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_paths_level_2_rom(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.paths_level_2_rom != new_val {
+        config_items.paths_level_2_rom = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("level_2_rom = {}", value))
+            },
+            None => {
+                Some("level_2_rom = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn update_line_paths_misc_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_misc_rom.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_paths_misc_rom(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.paths_misc_rom != new_val {
+        config_items.paths_misc_rom = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("misc_rom = {}", value))
+            },
+            None => {
+                Some("misc_rom = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+
+fn parse_entry_paths_level_1_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_level_1_rom = None;
+    } else if parse_rom_parts(&argument).is_some() {
+        config_items.paths_level_1_rom = Some(argument);
+    } else {
+        return Err(ConfigError::InvalidRomSpecifier(info_source));
+    }
+
+    Ok(())
+}
+fn parse_entry_paths_level_2_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_level_2_rom = None;
+    } else if parse_rom_parts(&argument).is_some() {
+        config_items.paths_level_2_rom = Some(argument);
+    } else {
+        return Err(ConfigError::InvalidRomSpecifier(info_source));
+    }
+
+    Ok(())
 }
-fn parse_entry_general_misc_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+fn parse_entry_paths_misc_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
     let argument = info_source.argument_text();
 
     if argument.to_uppercase() == "NONE" {
-        config_items.general_misc_rom = None;
+        config_items.paths_misc_rom = None;
+    } else if parse_rom_parts(&argument).is_some() {
+        config_items.paths_misc_rom = Some(argument);
     } else {
-        config_items.general_misc_rom = Some(argument);
+        return Err(ConfigError::InvalidRomSpecifier(info_source));
     }
 
     Ok(())
 }
 
-fn update_line_general_default_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.general_default_rom;
+fn new_handler_paths_level_1_rom() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The name of the system ROM image files (name, path, or the keyword `none').".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# You only need to provide one of these rom files.  The three fields exist".to_owned());
+    default_text.push("# solely for the sake of convenience, when you want to have different roms".to_owned());
+    default_text.push("# and to be able to switch between them without having to keep changing".to_owned());
+    default_text.push("# the names here in the configuration file.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If you specify a name, the program will look for the rom file in the".to_owned());
+    default_text.push("# configuration directory, which is where this file resides, and then in".to_owned());
+    default_text.push("# the directories of the `rom_dirs' entry.  If you want to store the rom(s)".to_owned());
+    default_text.push("# somewhere else, specify a full path to the rom files.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# A rom can also be put together from the dumps of its chips, by joining".to_owned());
+    default_text.push("# their files with ` + ', like \"rom-a.bin + rom-b.bin + rom-c.bin\".  Each".to_owned());
+    default_text.push("# part goes right after the previous one, unless it's followed by `@' and".to_owned());
+    default_text.push("# the offset it's loaded at, like `rom-c.bin@0x2000'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("level_1_rom = \"none\"".to_owned());
+
+    ConfigEntry {
+        entry_name:   "level_1_rom".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::RomChange(1),
+        update_line:  update_line_paths_level_1_rom,
+        parse_entry:  parse_entry_paths_level_1_rom,
+    }
+}
+fn new_handler_paths_level_2_rom() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("level_2_rom = \"none\"".to_owned());
+
+    ConfigEntry {
+        entry_name:   "level_2_rom".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::RomChange(2),
+        update_line:  update_line_paths_level_2_rom,
+        parse_entry:  parse_entry_paths_level_2_rom,
+    }
+}
+fn new_handler_paths_misc_rom() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("misc_rom = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "misc_rom".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::RomChange(3),
+        update_line:  update_line_paths_misc_rom,
+        parse_entry:  parse_entry_paths_misc_rom,
+    }
+}
+
+fn update_line_paths_rom_dirs(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_rom_dirs.clone();
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_general_default_rom(info_source, config_items) {
+    let failed_read = match parse_entry_paths_rom_dirs(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.general_default_rom != new_val {
-        config_items.general_default_rom = new_val;
-        Some(format!("default_rom = {}", new_val))
+    if failed_read || config_items.paths_rom_dirs != new_val {
+        config_items.paths_rom_dirs = new_val.clone();
+        if new_val.is_empty() {
+            Some("rom_dirs = none".to_owned())
+        } else {
+            Some(format!("rom_dirs = {}", new_val.join("; ")))
+        }
     } else {
         None
     }
 }
-fn parse_entry_general_default_rom(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = match info_source.argument_text().parse::<u32>() {
-        Ok(result) => { result },
-        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
-    };
+fn parse_entry_paths_rom_dirs(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
 
-    if argument >= 1 && argument <= 3 {
-        config_items.general_default_rom = argument;
-        Ok(())
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_rom_dirs = Vec::new();
     } else {
-        Err(ConfigError::DefaultRomOutOfRange(info_source, argument))
+        config_items.paths_rom_dirs = argument.split(';').map(|dir| dir.trim().to_owned()).filter(|dir| !dir.is_empty()).collect();
+    }
+
+    Ok(())
+}
+fn new_handler_paths_rom_dirs() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The directories to look for the rom files in, after the configuration".to_owned());
+    default_text.push("# directory, separated by semicolons, or the keyword `none'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The directories which aren't full paths are taken as located in the".to_owned());
+    default_text.push("# configuration directory, like \"roms; /usr/share/trs80/roms\".".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("rom_dirs = \"none\"".to_owned());
+
+    ConfigEntry {
+        entry_name:   "rom_dirs".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateRomDirs,
+        update_line:  update_line_paths_rom_dirs,
+        parse_entry:  parse_entry_paths_rom_dirs,
     }
 }
 
-fn update_line_general_ram_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.general_ram_size;
+fn update_line_paths_startup_script(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_startup_script.clone();
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_general_ram_size(info_source, config_items) {
+    let failed_read = match parse_entry_paths_startup_script(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.general_ram_size != new_val {
-        config_items.general_ram_size = new_val;
-        if (new_val % 1024) == 0 {
-            Some(format!("ram_size = {}K", new_val / 1024))
-        } else {
-            Some(format!("ram_size = {}", new_val))
+    if failed_read || config_items.paths_startup_script != new_val {
+        config_items.paths_startup_script = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("startup_script = {}", value))
+            },
+            None => {
+                Some("startup_script = none".to_owned())
+            },
         }
     } else {
         None
     }
 }
-fn parse_entry_general_ram_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let mut new_ram_size = 0;
-    let mut found_suffix = false;
+fn parse_entry_paths_startup_script(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
 
-    for character in info_source.argument_text().chars() {
-        if !found_suffix {
-            match character.to_digit(10) {
-                Some(digit) => {
-                    new_ram_size = (new_ram_size * 10) + digit;
-                },
-                None => {
-                    if character == 'k' || character == 'K' {
-                        new_ram_size *= 1024;
-                        found_suffix = true;
-                    } else {
-                        return Err(ConfigError::InvalidRamSpecifier(info_source));
-                    }
-                },
-            }
-        } else {
-            return Err(ConfigError::InvalidRamSpecifier(info_source));
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_startup_script = None;
+    } else {
+        config_items.paths_startup_script = Some(argument);
+    }
+
+    Ok(())
+}
+fn new_handler_paths_startup_script() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# A script of commands of the curses-based interface to run when the".to_owned());
+    default_text.push("# emulator starts (name, path, or the keyword `none'), one per line, like".to_owned());
+    default_text.push("# `/cassette insert game.cas' or `/machine type-text CLOAD\\n'.  The slash in".to_owned());
+    default_text.push("# front of the commands can be left out, and lines starting with `#' are".to_owned());
+    default_text.push("# comments.  Names are looked up in the configuration directory.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# This can be overridden on program startup, using the --script argument.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("startup_script = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "startup_script".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateStartupScript,
+        update_line:  update_line_paths_startup_script,
+        parse_entry:  parse_entry_paths_startup_script,
+    }
+}
+
+fn update_line_paths_screenshot_dir(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_screenshot_dir.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_paths_screenshot_dir(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.paths_screenshot_dir != new_val {
+        config_items.paths_screenshot_dir = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("screenshot_dir = {}", value))
+            },
+            None => {
+                Some("screenshot_dir = none".to_owned())
+            },
         }
+    } else {
+        None
     }
-    if new_ram_size <= 48 * 1024 {
-        config_items.general_ram_size = new_ram_size;
-        Ok(())
+}
+fn parse_entry_paths_screenshot_dir(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_screenshot_dir = None;
     } else {
-        Err(ConfigError::TooMuchRamRequested(info_source, new_ram_size))
+        config_items.paths_screenshot_dir = Some(argument);
+    }
+
+    Ok(())
+}
+fn new_handler_paths_screenshot_dir() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The directory to save screenshots into, taken with F12 or `/video screenshot'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# You can either specify a full path, a simple directory name if you want the".to_owned());
+    default_text.push("# directory to be located in the configuration directory, or the keyword".to_owned());
+    default_text.push("# `none' to use the configuration directory itself.  The screenshots are PNG".to_owned());
+    default_text.push("# files named after the date and time (in UTC) they were taken at.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("screenshot_dir = \"screenshots\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "screenshot_dir".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateScreenshotSettings,
+        update_line:  update_line_paths_screenshot_dir,
+        parse_entry:  parse_entry_paths_screenshot_dir,
+    }
+}
+
+fn update_line_paths_library_dir(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.paths_library_dir.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_paths_library_dir(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.paths_library_dir != new_val {
+        config_items.paths_library_dir = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("library_dir = {}", value))
+            },
+            None => {
+                Some("library_dir = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_paths_library_dir(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.paths_library_dir = None;
+    } else {
+        config_items.paths_library_dir = Some(argument);
+    }
+
+    Ok(())
+}
+fn new_handler_paths_library_dir() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The directory holding your collection of cassettes.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The CAS, CPT and WAV files in this directory can be listed and inserted".to_owned());
+    default_text.push("# using the `/cassette library' command.  You can either specify a full path,".to_owned());
+    default_text.push("# a simple directory name if you want the directory to be located in the".to_owned());
+    default_text.push("# configuration directory, or the keyword `none' to use the configuration".to_owned());
+    default_text.push("# directory itself.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("library_dir = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "library_dir".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteLibraryDir,
+        update_line:  update_line_paths_library_dir,
+        parse_entry:  parse_entry_paths_library_dir,
+    }
+}
+
+fn new_paths_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
+
+    entries.push(new_handler_paths_level_1_rom());
+    entries.push(new_handler_paths_level_2_rom());
+    entries.push(new_handler_paths_misc_rom());
+    entries.push(new_handler_paths_rom_dirs());
+    entries.push(new_handler_paths_startup_script());
+    entries.push(new_handler_paths_screenshot_dir());
+    entries.push(new_handler_paths_library_dir());
+
+    let obsolete_entries: Vec<String> = Vec::new();
+
+    ConfigSection {
+        section_name:     "paths".to_owned(),
+        entries:          entries.into_boxed_slice(),
+        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    }
+}
+
+// The interface section and entries:
+fn update_line_interface_aliases(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.interface_aliases.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_interface_aliases(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.interface_aliases != new_val {
+        let line = format!("aliases = {}", aliases::aliases_text(&new_val));
+        config_items.interface_aliases = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+fn parse_entry_interface_aliases(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match aliases::parse_aliases(&info_source.argument_text()) {
+        Some(aliases) => { aliases },
+        None => { return Err(ConfigError::InvalidAliasesSpecifier(info_source)); },
+    };
+
+    config_items.interface_aliases = argument;
+    Ok(())
+}
+fn new_handler_interface_aliases() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Command aliases, names which stand for one or more commands of the".to_owned());
+    default_text.push("# curses-based interface, as a list of `<name> \"<commands>\"' aliases, or".to_owned());
+    default_text.push("# `none'.  The commands are separated by semicolons, and can include".to_owned());
+    default_text.push("# `wait <seconds>', like in the startup script.  Inside of the quotes, `\\\"'".to_owned());
+    default_text.push("# stands for a quote and `\\\\' for a backslash.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The words typed after the name of an alias are added to the end of its".to_owned());
+    default_text.push("# last command, and the aliases can use each other.  An alias named like".to_owned());
+    default_text.push("# one of the commands of the emulator is never used.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The list is best written in single quotes, which keep the quotes and the".to_owned());
+    default_text.push("# backslashes inside of it as they are.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Example: aliases = 'l2 \"cassette load games/defense.cas; machine type-text SYSTEM\\n\" t \"machine type-text\"'".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("aliases = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "aliases".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateAliases,
+        update_line:  update_line_interface_aliases,
+        parse_entry:  parse_entry_interface_aliases,
+    }
+}
+
+fn update_line_interface_ui_theme(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.interface_ui_theme;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_interface_ui_theme(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.interface_ui_theme != new_val {
+        config_items.interface_ui_theme = new_val;
+        Some(format!("ui_theme = {}", new_val.name()))
+    } else {
+        None
+    }
+}
+fn parse_entry_interface_ui_theme(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match ui_theme::Theme::from_name(&info_source.argument_text()) {
+        Some(theme) => {
+            config_items.interface_ui_theme = theme;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidThemeSpecifier(info_source))
+        },
+    }
+}
+fn new_handler_interface_ui_theme() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The color theme of the curses-based interface (dark, light, colorblind".to_owned());
+    default_text.push("# or monochrome).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The light theme keeps the background of the terminal, for terminals with".to_owned());
+    default_text.push("# a white one, the colorblind theme tells the states and the warnings apart".to_owned());
+    default_text.push("# with blue and yellow instead of green and red, and the monochrome theme".to_owned());
+    default_text.push("# sticks to the black and white of the terminal.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("ui_theme = \"dark\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "ui_theme".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateUiTheme,
+        update_line:  update_line_interface_ui_theme,
+        parse_entry:  parse_entry_interface_ui_theme,
+    }
+}
+
+fn update_line_interface_ui_colors(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.interface_ui_colors.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_interface_ui_colors(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.interface_ui_colors != new_val {
+        let line = format!("ui_colors = {}", ui_theme::color_settings_text(&new_val));
+        config_items.interface_ui_colors = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+fn parse_entry_interface_ui_colors(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match ui_theme::parse_color_settings(&info_source.argument_text()) {
+        Some(settings) => { settings },
+        None => { return Err(ConfigError::InvalidUiColorsSpecifier(info_source)); },
+    };
+
+    config_items.interface_ui_colors = argument;
+    Ok(())
+}
+fn new_handler_interface_ui_colors() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Colors of the curses-based interface to use instead of the ones of the".to_owned());
+    default_text.push("# theme, as a list of `<part>=<foreground>/<background>' settings, or".to_owned());
+    default_text.push("# `none'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The parts are status, status-on, status-off and status-accent for the".to_owned());
+    default_text.push("# status strips, message, warning and error for the messages of the".to_owned());
+    default_text.push("# emulator, machine for the text printed by the machine, and prompt.  The".to_owned());
+    default_text.push("# colors are black, red, green, yellow, blue, magenta, cyan, white, and".to_owned());
+    default_text.push("# default, the color the terminal uses on its own.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Example: ui_colors = \"warning=yellow/default error=white/red\"".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("ui_colors = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "ui_colors".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateUiTheme,
+        update_line:  update_line_interface_ui_colors,
+        parse_entry:  parse_entry_interface_ui_colors,
+    }
+}
+
+fn update_line_interface_language(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.interface_language.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_interface_language(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.interface_language != new_val {
+        let line = format!("language = {}", new_val);
+        config_items.interface_language = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+fn parse_entry_interface_language(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text().to_lowercase();
+
+    if i18n::is_language(&argument) {
+        config_items.interface_language = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidLanguageSpecifier(info_source))
+    }
+}
+fn new_handler_interface_language() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The language of the curses-based interface, either auto, which takes it".to_owned());
+    default_text.push("# from the environment (LC_ALL, LC_MESSAGES or LANG), or one of en".to_owned());
+    default_text.push("# (English) and sk (Slovak).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The texts which haven't been translated yet, and the messages logged by".to_owned());
+    default_text.push("# the emulator, are shown in English.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("language = \"auto\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "language".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateLanguage,
+        update_line:  update_line_interface_language,
+        parse_entry:  parse_entry_interface_language,
+    }
+}
+
+fn update_line_interface_confirm_actions(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.interface_confirm_actions.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_interface_confirm_actions(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.interface_confirm_actions != new_val {
+        let line = format!("confirm_actions = {}", confirm::actions_text(&new_val));
+        config_items.interface_confirm_actions = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+fn parse_entry_interface_confirm_actions(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match confirm::parse_actions(&info_source.argument_text()) {
+        Some(actions) => { actions },
+        None => { return Err(ConfigError::InvalidConfirmActionsSpecifier(info_source)); },
+    };
+
+    config_items.interface_confirm_actions = argument;
+    Ok(())
+}
+fn new_handler_interface_confirm_actions() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The actions which throw work away, and have to be asked for twice within".to_owned());
+    default_text.push("# five seconds to be carried out, as a list of them, or `none'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The actions are reset, the full reset of the machine (F5), discard,".to_owned());
+    default_text.push("# throwing away the changes kept in the overlay of a disk image, and".to_owned());
+    default_text.push("# overwrite, saving the state or a recording over an existing file.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The commands of the scripts, of the standard input in the headless mode,".to_owned());
+    default_text.push("# and of the remote control clients are carried out without asking twice.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("confirm_actions = \"reset discard overwrite\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "confirm_actions".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateConfirmActions,
+        update_line:  update_line_interface_confirm_actions,
+        parse_entry:  parse_entry_interface_confirm_actions,
+    }
+}
+
+fn new_interface_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
+
+    entries.push(new_handler_interface_aliases());
+    entries.push(new_handler_interface_ui_theme());
+    entries.push(new_handler_interface_ui_colors());
+    entries.push(new_handler_interface_language());
+    entries.push(new_handler_interface_confirm_actions());
+
+    let obsolete_entries: Vec<String> = Vec::new();
+
+    ConfigSection {
+        section_name:     "interface".to_owned(),
+        entries:          entries.into_boxed_slice(),
+        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    }
+}
+
+// The keyboard section and entries:
+fn update_line_keyboard_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_ms_per_keypress;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_ms_per_keypress(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_ms_per_keypress != new_val {
+        config_items.keyboard_ms_per_keypress = new_val;
+        Some(format!("ms_per_keypress = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    config_items.keyboard_ms_per_keypress = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_ms_per_keypress() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# The minimum time it takes to press down or release a key, in miliseconds.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The purpose of this is to make sure that the input routine can catch the".to_owned());
+    default_text.push("# keyboard updates, since there's no dedicated circuitry for this in the".to_owned());
+    default_text.push("# machine, just the CPU probing the keyboard matrix.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# A value between 5 to 50 is recommended.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("ms_per_keypress = 20".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "ms_per_keypress".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateMsPerKeypress,
+        update_line:  update_line_keyboard_ms_per_keypress,
+        parse_entry:  parse_entry_keyboard_ms_per_keypress,
+    }
+}
+
+fn update_line_keyboard_mapping(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_mapping;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_mapping(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_mapping != new_val {
+        config_items.keyboard_mapping = new_val;
+        match new_val {
+            sdl_keyboard::KeyMapping::Positional => {
+                Some("mapping = positional".to_owned())
+            },
+            sdl_keyboard::KeyMapping::Symbolic => {
+                Some("mapping = symbolic".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_mapping(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "positional" {
+        config_items.keyboard_mapping = sdl_keyboard::KeyMapping::Positional;
+        Ok(())
+    } else if compare_str == "symbolic" {
+        config_items.keyboard_mapping = sdl_keyboard::KeyMapping::Symbolic;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidKeyMappingSpecifier(info_source))
+    }
+}
+
+fn new_handler_keyboard_mapping() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# How the keys are mapped to the keys of the emulated machine (positional".to_owned());
+    default_text.push("# or symbolic).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With positional, the keys are mapped by where they are on the keyboard,".to_owned());
+    default_text.push("# which puts the punctuation of the machine where it is on the US layout.".to_owned());
+    default_text.push("# With symbolic, the characters typed are typed into the machine as they".to_owned());
+    default_text.push("# are, shift included, so the punctuation comes out right with any layout".to_owned());
+    default_text.push("# (AZERTY, QWERTZ, ...), and the characters the machine doesn't have are".to_owned());
+    default_text.push("# skipped.  Only ENTER, BREAK, CLEAR and the arrows are mapped by where they".to_owned());
+    default_text.push("# are, the keys can't be held down for longer, and shift can't be used".to_owned());
+    default_text.push("# together with the arrows.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("mapping = \"positional\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "mapping".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyMapping,
+        update_line:  update_line_keyboard_mapping,
+        parse_entry:  parse_entry_keyboard_mapping,
+    }
+}
+
+fn update_line_keyboard_key_bindings(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_key_bindings.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_key_bindings(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_key_bindings != new_val {
+        let line = format!("key_bindings = {}", sdl_keyboard::key_bindings_text(&new_val));
+        config_items.keyboard_key_bindings = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_key_bindings(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match sdl_keyboard::parse_key_bindings(&info_source.argument_text()) {
+        Some(bindings) => { bindings },
+        None => { return Err(ConfigError::InvalidKeyBindingsSpecifier(info_source)); },
+    };
+
+    config_items.keyboard_key_bindings = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_key_bindings() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Changes to where the keys of the emulated machine are on the keyboard,".to_owned());
+    default_text.push("# as a comma-separated list of `<key>:<machine key>' pairs, or `none' to".to_owned());
+    default_text.push("# keep the default layout.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The keys are named as in the Scancode enum of SDL2, for example `A',".to_owned());
+    default_text.push("# `Num1', `Kp1', `F1', `Escape', `Tab', `Grave', `LeftBracket', `Return',".to_owned());
+    default_text.push("# `Insert', `Home', `LShift' or `RCtrl'.  The keys of the machine are `a'".to_owned());
+    default_text.push("# to `z', `0' to `9', `at', `colon', `semicolon', `comma', `minus', `period',".to_owned());
+    default_text.push("# `slash', `enter', `clear', `break', `up', `down', `left', `right', `space'".to_owned());
+    default_text.push("# and `shift', the joystick is `joy_up', `joy_down', `joy_left', `joy_right'".to_owned());
+    default_text.push("# and `joy_fire', `turbo' runs the emulation at full speed while it's held,".to_owned());
+    default_text.push("# `warp' runs it at full speed until it's pressed again, `slow_motion' goes".to_owned());
+    default_text.push("# through the speeds of 100%, 50% and 10%, `frame_advance' pauses the".to_owned());
+    default_text.push("# emulation, and then runs it for a video frame each time, or `none' makes".to_owned());
+    default_text.push("# the key do nothing.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# By default, the keys are where they are on the machine, as far as the".to_owned());
+    default_text.push("# keyboard allows, with `at' on LeftBracket, `colon' on Minus, `minus' on".to_owned());
+    default_text.push("# Equals, `break' on F1 and Insert, `clear' on F2 and Delete, `left' on".to_owned());
+    default_text.push("# Left and Backspace, the number pad typing the digits, `turbo' on F10,".to_owned());
+    default_text.push("# `warp' on ScrollLock and `frame_advance' on Pause.".to_owned());
+    default_text.push("# F3 to F9, F11 and F12 control the emulator, and can't be bound.  With the".to_owned());
+    default_text.push("# symbolic mapping, the keys typing characters are only bound if they're".to_owned());
+    default_text.push("# listed here.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# For example, to have BREAK on Escape and CLEAR on Home instead:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#   key_bindings = \"Escape:break, F1:none, Insert:none, Home:clear, F2:none, Delete:none\"".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("key_bindings = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "key_bindings".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyBindings,
+        update_line:  update_line_keyboard_key_bindings,
+        parse_entry:  parse_entry_keyboard_key_bindings,
+    }
+}
+
+fn update_line_keyboard_profiles(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_profiles.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_profiles(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_profiles != new_val {
+        let line = format!("profiles = {}", sdl_keyboard::key_profiles_text(&new_val));
+        config_items.keyboard_profiles = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_profiles(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match sdl_keyboard::parse_key_profiles(&info_source.argument_text()) {
+        Some(profiles) => { profiles },
+        None => { return Err(ConfigError::InvalidKeyProfilesSpecifier(info_source)); },
+    };
+
+    config_items.keyboard_profiles = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_profiles() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Key profiles, named sets of key bindings for playing games, which go on".to_owned());
+    default_text.push("# top of the key bindings while they're chosen, as a list of".to_owned());
+    default_text.push("# `<name>(<key bindings>)' profiles, or `none'.  The key bindings are".to_owned());
+    default_text.push("# written like in the `key_bindings' entry.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The emulator comes with the `numpad' profile, which makes the number pad".to_owned());
+    default_text.push("# the arrows, with 5 and 0 as the space bar, the `wasd' one, which makes".to_owned());
+    default_text.push("# W, A, S and D the arrows, the `invaders' one, which adds Z and X for".to_owned());
+    default_text.push("# moving left and right and fires with Up and the Ctrl and Alt keys, and".to_owned());
+    default_text.push("# the `joystick' one, which makes the number pad the joystick.  A profile".to_owned());
+    default_text.push("# listed here with the same name replaces the one of the emulator.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# For example, for a game moving with Q, A, O and P, and firing with M:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#   profiles = \"qaop(Up:q, Down:a, Left:o, Right:p, Space:m)\"".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("profiles = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "profiles".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyProfile,
+        update_line:  update_line_keyboard_profiles,
+        parse_entry:  parse_entry_keyboard_profiles,
+    }
+}
+
+fn update_line_keyboard_profile(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_profile.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_profile(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_profile != new_val {
+        let line = format!("profile = {}", new_val.as_deref().unwrap_or("none"));
+        config_items.keyboard_profile = new_val;
+        Some(line)
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_profile(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text().trim().to_lowercase();
+
+    if argument == "none" {
+        config_items.keyboard_profile = None;
+        Ok(())
+    } else if sdl_keyboard::is_valid_profile_name(&argument) {
+        config_items.keyboard_profile = Some(argument);
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidKeyProfileName(info_source))
+    }
+}
+
+fn new_handler_keyboard_profile() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# The key profile in use, or `none', which is also switched with the".to_owned());
+    default_text.push("# `/keyboard profile' command.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("profile = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "profile".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyProfile,
+        update_line:  update_line_keyboard_profile,
+        parse_entry:  parse_entry_keyboard_profile,
+    }
+}
+
+fn update_line_keyboard_paste_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_paste_ms_per_keypress;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_paste_ms_per_keypress(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_paste_ms_per_keypress != new_val {
+        config_items.keyboard_paste_ms_per_keypress = new_val;
+        Some(format!("paste_ms_per_keypress = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_paste_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    config_items.keyboard_paste_ms_per_keypress = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_paste_ms_per_keypress() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# The time it takes to press down or release a key when pasting text from".to_owned());
+    default_text.push("# the clipboard with F7 or `/machine paste', in miliseconds.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Programs which take longer to read the keyboard than the ROM does may need".to_owned());
+    default_text.push("# more than the 20 miliseconds to catch all of the keys.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("paste_ms_per_keypress = 20".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "paste_ms_per_keypress".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdatePasteSettings,
+        update_line:  update_line_keyboard_paste_ms_per_keypress,
+        parse_entry:  parse_entry_keyboard_paste_ms_per_keypress,
+    }
+}
+
+fn update_line_keyboard_paste_ms_per_line(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_paste_ms_per_line;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_paste_ms_per_line(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_paste_ms_per_line != new_val {
+        config_items.keyboard_paste_ms_per_line = new_val;
+        Some(format!("paste_ms_per_line = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_paste_ms_per_line(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    config_items.keyboard_paste_ms_per_line = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_paste_ms_per_line() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# The pause after each line of the pasted text, in miliseconds, for the".to_owned());
+    default_text.push("# program to take in the line after ENTER is pressed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# BASIC needs some time to store a line of a program, raise this if the".to_owned());
+    default_text.push("# start of some of the lines goes missing when pasting a listing.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("paste_ms_per_line = 250".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "paste_ms_per_line".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdatePasteSettings,
+        update_line:  update_line_keyboard_paste_ms_per_line,
+        parse_entry:  parse_entry_keyboard_paste_ms_per_line,
+    }
+}
+
+fn update_line_keyboard_type_chars_per_second(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_type_chars_per_second;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_type_chars_per_second(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_type_chars_per_second != new_val {
+        config_items.keyboard_type_chars_per_second = new_val;
+        Some(format!("type_chars_per_second = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_type_chars_per_second(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if (1..=MAX_TYPE_CHARS_PER_SECOND).contains(&argument) {
+        config_items.keyboard_type_chars_per_second = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::TypingRateOutOfRange(info_source, argument))
+    }
+}
+
+fn new_handler_keyboard_type_chars_per_second() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# How many characters a second `/machine type' types in from a file, from".to_owned());
+    default_text.push("# 1 to 100.  The lines are followed by the pause of `paste_ms_per_line'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("type_chars_per_second = 10".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "type_chars_per_second".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdatePasteSettings,
+        update_line:  update_line_keyboard_type_chars_per_second,
+        parse_entry:  parse_entry_keyboard_type_chars_per_second,
+    }
+}
+
+fn update_line_keyboard_paste_wait_for_scan(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_paste_wait_for_scan;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_paste_wait_for_scan(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_paste_wait_for_scan != new_val {
+        config_items.keyboard_paste_wait_for_scan = new_val;
+        Some(format!("paste_wait_for_scan = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_paste_wait_for_scan(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.keyboard_paste_wait_for_scan = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn new_handler_keyboard_paste_wait_for_scan() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Whether the pasted and typed text waits for the program to read each key".to_owned());
+    default_text.push("# from the keyboard before going on with the next one, so that no keys are".to_owned());
+    default_text.push("# lost while the program is busy, like when BASIC executes a line.  The".to_owned());
+    default_text.push("# delays above then count from when the key was read.  A program which".to_owned());
+    default_text.push("# doesn't read the keyboard at all holds the typing up for 5 seconds a key.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("paste_wait_for_scan = true".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "paste_wait_for_scan".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdatePasteSettings,
+        update_line:  update_line_keyboard_paste_wait_for_scan,
+        parse_entry:  parse_entry_keyboard_paste_wait_for_scan,
+    }
+}
+
+fn update_line_keyboard_auto_repeat(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_auto_repeat;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_auto_repeat(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_auto_repeat != new_val {
+        config_items.keyboard_auto_repeat = new_val;
+        Some(format!("auto_repeat = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_auto_repeat(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.keyboard_auto_repeat = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn new_handler_keyboard_auto_repeat() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Whether the keys of the keyboard repeat while they're held down.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The Model I itself doesn't repeat the keys, which is what its programs".to_owned());
+    default_text.push("# expect, so this is off by default.  When it's on, the last key pressed".to_owned());
+    default_text.push("# is released and pressed again, like the keys of a PC repeat, after".to_owned());
+    default_text.push("# `repeat_delay_ms', `repeat_per_second' times a second.  SHIFT and the".to_owned());
+    default_text.push("# game controllers aren't repeated.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("auto_repeat = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "auto_repeat".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyRepeat,
+        update_line:  update_line_keyboard_auto_repeat,
+        parse_entry:  parse_entry_keyboard_auto_repeat,
+    }
+}
+
+fn update_line_keyboard_repeat_delay_ms(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_repeat_delay_ms;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_repeat_delay_ms(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_repeat_delay_ms != new_val {
+        config_items.keyboard_repeat_delay_ms = new_val;
+        Some(format!("repeat_delay_ms = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_repeat_delay_ms(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    config_items.keyboard_repeat_delay_ms = argument;
+    Ok(())
+}
+
+fn new_handler_keyboard_repeat_delay_ms() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# How long a key has to be held down before it starts repeating, in".to_owned());
+    default_text.push("# miliseconds, with the auto-repeat on.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("repeat_delay_ms = 500".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "repeat_delay_ms".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyRepeat,
+        update_line:  update_line_keyboard_repeat_delay_ms,
+        parse_entry:  parse_entry_keyboard_repeat_delay_ms,
+    }
+}
+
+fn update_line_keyboard_repeat_per_second(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.keyboard_repeat_per_second;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_keyboard_repeat_per_second(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.keyboard_repeat_per_second != new_val {
+        config_items.keyboard_repeat_per_second = new_val;
+        Some(format!("repeat_per_second = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_keyboard_repeat_per_second(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if (1..=MAX_REPEAT_PER_SECOND).contains(&argument) {
+        config_items.keyboard_repeat_per_second = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::RepeatRateOutOfRange(info_source, argument))
+    }
+}
+
+fn new_handler_keyboard_repeat_per_second() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# How many times a second a key held down repeats, from 1 to 30, with the".to_owned());
+    default_text.push("# auto-repeat on.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("repeat_per_second = 10".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "repeat_per_second".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateKeyRepeat,
+        update_line:  update_line_keyboard_repeat_per_second,
+        parse_entry:  parse_entry_keyboard_repeat_per_second,
+    }
+}
+
+fn new_keyboard_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
+    entries.push(new_handler_keyboard_ms_per_keypress());
+    entries.push(new_handler_keyboard_mapping());
+    entries.push(new_handler_keyboard_key_bindings());
+    entries.push(new_handler_keyboard_profiles());
+    entries.push(new_handler_keyboard_profile());
+    entries.push(new_handler_keyboard_paste_ms_per_keypress());
+    entries.push(new_handler_keyboard_paste_ms_per_line());
+    entries.push(new_handler_keyboard_type_chars_per_second());
+    entries.push(new_handler_keyboard_paste_wait_for_scan());
+    entries.push(new_handler_keyboard_auto_repeat());
+    entries.push(new_handler_keyboard_repeat_delay_ms());
+    entries.push(new_handler_keyboard_repeat_per_second());
+
+    let obsolete_entries: Vec<String> = Vec::new();
+
+    ConfigSection {
+        section_name:     "keyboard".to_owned(),
+        entries:          entries.into_boxed_slice(),
+        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    }
+}
+
+// The video section and entries:
+fn update_line_video_windowed_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_windowed_resolution;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_windowed_resolution(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_windowed_resolution != new_val {
+        config_items.video_windowed_resolution = new_val;
+        let (width, height) = new_val;
+        Some(format!("windowed_resolution = {}x{}", width, height))
+    } else {
+        None
+    }
+}
+fn update_line_video_fullscreen_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_fullscreen_resolution;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_fullscreen_resolution(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_fullscreen_resolution != new_val {
+        config_items.video_fullscreen_resolution = new_val;
+        let (width, height) = new_val;
+        Some(format!("fullscreen_resolution = {}x{}", width, height))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_windowed_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_resolution_argument(info_source.argument_text().as_str()) {
+        Some(resolution) => {
+            config_items.video_windowed_resolution = resolution;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidResolutionSpecifier(info_source))
+        }
+    }
+}
+fn parse_entry_video_fullscreen_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_resolution_argument(info_source.argument_text().as_str()) {
+        Some(resolution) => {
+            config_items.video_fullscreen_resolution = resolution;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidResolutionSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_bg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_bg_color;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_bg_color(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_bg_color != new_val {
+        config_items.video_bg_color = new_val;
+        let (red, green, blue) = new_val;
+        Some(format!("bg_color = #{:02X}{:02X}{:02X}", red, green, blue))
+    } else {
+        None
+    }
+}
+fn update_line_video_fg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_fg_color;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_fg_color(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_fg_color != new_val {
+        config_items.video_fg_color = new_val;
+        let (red, green, blue) = new_val;
+        Some(format!("fg_color = #{:02X}{:02X}{:02X}", red, green, blue))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_bg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_color_argument(info_source.argument_text().as_str()) {
+        Some(color) => {
+            config_items.video_bg_color = color;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidColorSpecifier(info_source))
+        }
+    }
+}
+fn parse_entry_video_fg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_color_argument(info_source.argument_text().as_str()) {
+        Some(color) => {
+            config_items.video_fg_color = color;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidColorSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_desktop_fullscreen_mode(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_desktop_fullscreen_mode;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_desktop_fullscreen_mode(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_desktop_fullscreen_mode != new_val {
+        config_items.video_desktop_fullscreen_mode = new_val;
+        Some(format!("desktop_fullscreen_mode = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_desktop_fullscreen_mode(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_desktop_fullscreen_mode = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn update_line_video_fullscreen(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_fullscreen;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_fullscreen(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_fullscreen != new_val {
+        config_items.video_fullscreen = new_val;
+        Some(format!("fullscreen = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_fullscreen(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_fullscreen = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn update_line_video_screenshot_scaled(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_screenshot_scaled;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_screenshot_scaled(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_screenshot_scaled != new_val {
+        config_items.video_screenshot_scaled = new_val;
+        Some(format!("screenshot_scaled = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_screenshot_scaled(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_screenshot_scaled = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn update_line_video_recording_frame_rate(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_recording_frame_rate;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_recording_frame_rate(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_recording_frame_rate != new_val {
+        config_items.video_recording_frame_rate = new_val;
+        Some(format!("recording_frame_rate = {}", new_val))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_recording_frame_rate(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument >= 1 && argument <= gif::MAX_FRAME_RATE {
+        config_items.video_recording_frame_rate = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::RecordingFrameRateOutOfRange(info_source, argument))
+    }
+}
+fn update_line_video_recording_max_seconds(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_recording_max_seconds;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_recording_max_seconds(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_recording_max_seconds != new_val {
+        config_items.video_recording_max_seconds = new_val;
+        Some(format!("recording_max_seconds = {}", new_val))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_recording_max_seconds(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match info_source.argument_text().parse::<u32>() {
+        Ok(result) => {
+            config_items.video_recording_max_seconds = result;
+            Ok(())
+        },
+        Err(error) => {
+            Err(ConfigError::EntryIntParsingError(info_source, error))
+        },
+    }
+}
+fn update_line_video_scaling(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_scaling;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_scaling(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_scaling != new_val {
+        config_items.video_scaling = new_val;
+        match new_val {
+            sdl_video::Scaling::Integer => {
+                Some("scaling = integer".to_owned())
+            },
+            sdl_video::Scaling::Aspect => {
+                Some("scaling = aspect".to_owned())
+            },
+            sdl_video::Scaling::Stretch => {
+                Some("scaling = stretch".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_scaling(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "integer" {
+        config_items.video_scaling = sdl_video::Scaling::Integer;
+        Ok(())
+    } else if compare_str == "aspect" {
+        config_items.video_scaling = sdl_video::Scaling::Aspect;
+        Ok(())
+    } else if compare_str == "stretch" {
+        config_items.video_scaling = sdl_video::Scaling::Stretch;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidScalingSpecifier(info_source))
+    }
+}
+fn update_line_video_text_graphics(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_text_graphics;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_text_graphics(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_text_graphics != new_val {
+        config_items.video_text_graphics = new_val;
+        match new_val {
+            video::TextGraphics::Unicode => {
+                Some("text_graphics = unicode".to_owned())
+            },
+            video::TextGraphics::Ascii => {
+                Some("text_graphics = ascii".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_text_graphics(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "unicode" {
+        config_items.video_text_graphics = video::TextGraphics::Unicode;
+        Ok(())
+    } else if compare_str == "ascii" {
+        config_items.video_text_graphics = video::TextGraphics::Ascii;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidTextGraphicsSpecifier(info_source))
+    }
+}
+
+fn update_line_video_crt_scanlines(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_crt_scanlines;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_crt_scanlines(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_crt_scanlines != new_val {
+        config_items.video_crt_scanlines = new_val;
+        Some(format!("crt_scanlines = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn update_line_video_crt_mask(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_crt_mask;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_crt_mask(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_crt_mask != new_val {
+        config_items.video_crt_mask = new_val;
+        Some(format!("crt_mask = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn update_line_video_crt_curvature(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_crt_curvature;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_crt_curvature(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_crt_curvature != new_val {
+        config_items.video_crt_curvature = new_val;
+        Some(format!("crt_curvature = {}", new_val))
+    } else {
+        None
+    }
+}
+fn update_line_video_crt_persistence(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_crt_persistence;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_crt_persistence(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_crt_persistence != new_val {
+        config_items.video_crt_persistence = new_val;
+        Some(format!("crt_persistence = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_video_crt_scanlines(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= 100 {
+        config_items.video_crt_scanlines = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::CrtIntensityOutOfRange(info_source, argument, 100))
+    }
+}
+
+fn parse_entry_video_crt_mask(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= 100 {
+        config_items.video_crt_mask = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::CrtIntensityOutOfRange(info_source, argument, 100))
+    }
+}
+
+fn parse_entry_video_crt_curvature(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= 100 {
+        config_items.video_crt_curvature = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::CrtIntensityOutOfRange(info_source, argument, 100))
+    }
+}
+fn parse_entry_video_crt_persistence(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= sdl_video::MAX_PERSISTENCE {
+        config_items.video_crt_persistence = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::CrtIntensityOutOfRange(info_source, argument, sdl_video::MAX_PERSISTENCE))
+    }
+}
+fn update_line_video_brightness(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_brightness;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_brightness(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_brightness != new_val {
+        config_items.video_brightness = new_val;
+        Some(format!("brightness = {}", new_val))
+    } else {
+        None
+    }
+}
+fn update_line_video_contrast(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_contrast;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_contrast(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_contrast != new_val {
+        config_items.video_contrast = new_val;
+        Some(format!("contrast = {}", new_val))
+    } else {
+        None
+    }
+}
+
+fn parse_entry_video_brightness(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= sdl_video::MAX_BRIGHTNESS {
+        config_items.video_brightness = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::PictureLevelOutOfRange(info_source, argument, sdl_video::MAX_BRIGHTNESS))
+    }
+}
+fn parse_entry_video_contrast(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= sdl_video::MAX_CONTRAST {
+        config_items.video_contrast = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::PictureLevelOutOfRange(info_source, argument, sdl_video::MAX_CONTRAST))
+    }
+}
+
+fn update_line_video_renderer(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_renderer;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_renderer(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_renderer != new_val {
+        config_items.video_renderer = new_val;
+        match new_val {
+            sdl_video::Renderer::Sdl => {
+                Some("renderer = sdl".to_owned())
+            },
+            sdl_video::Renderer::OpenGl => {
+                Some("renderer = opengl".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_renderer(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "sdl" {
+        config_items.video_renderer = sdl_video::Renderer::Sdl;
+        Ok(())
+    } else if compare_str == "opengl" {
+        config_items.video_renderer = sdl_video::Renderer::OpenGl;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidRendererSpecifier(info_source))
+    }
+}
+
+fn update_line_video_use_hw_accel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_use_hw_accel;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_use_hw_accel(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_use_hw_accel != new_val {
+        config_items.video_use_hw_accel = new_val;
+        Some(format!("use_hw_accel = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_use_hw_accel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_use_hw_accel = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_frame_skip(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_frame_skip;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_frame_skip(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_frame_skip != new_val {
+        config_items.video_frame_skip = new_val;
+        match new_val {
+            sdl_video::FrameSkip::Auto => {
+                Some("frame_skip = auto".to_owned())
+            },
+            sdl_video::FrameSkip::Fixed(skip) => {
+                Some(format!("frame_skip = {}", skip))
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_frame_skip(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_lowercase() == "auto" {
+        config_items.video_frame_skip = sdl_video::FrameSkip::Auto;
+        Ok(())
+    } else {
+        match argument.parse::<u32>() {
+            Ok(skip) if skip <= sdl_video::MAX_FRAME_SKIP => {
+                config_items.video_frame_skip = sdl_video::FrameSkip::Fixed(skip);
+                Ok(())
+            },
+            _ => {
+                Err(ConfigError::InvalidFrameSkipSpecifier(info_source))
+            },
+        }
+    }
+}
+
+fn update_line_video_overlay(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_overlay;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_overlay(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_overlay != new_val {
+        config_items.video_overlay = new_val;
+        match new_val {
+            sdl_video::OverlayLevel::Off => {
+                Some("overlay = off".to_owned())
+            },
+            sdl_video::OverlayLevel::Speed => {
+                Some("overlay = speed".to_owned())
+            },
+            sdl_video::OverlayLevel::Full => {
+                Some("overlay = full".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_overlay(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "off" {
+        config_items.video_overlay = sdl_video::OverlayLevel::Off;
+        Ok(())
+    } else if compare_str == "speed" {
+        config_items.video_overlay = sdl_video::OverlayLevel::Speed;
+        Ok(())
+    } else if compare_str == "full" {
+        config_items.video_overlay = sdl_video::OverlayLevel::Full;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidOverlaySpecifier(info_source))
+    }
+}
+
+fn update_line_video_notifications(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_notifications;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_notifications(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_notifications != new_val {
+        config_items.video_notifications = new_val;
+        Some(format!("notifications = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_notifications(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_notifications = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_use_vsync(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_use_vsync;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_use_vsync(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_use_vsync != new_val {
+        config_items.video_use_vsync = new_val;
+        Some(format!("use_vsync = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_use_vsync(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_use_vsync = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_character_generator(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_character_generator;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_character_generator(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_character_generator != new_val {
+        config_items.video_character_generator = new_val;
+        Some(format!("character_generator = {}", new_val))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_character_generator(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument >= 1 && argument <= 3 {
+        config_items.video_character_generator = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::CharacterGeneratorOutOfRange(info_source, argument))
+    }
+}
+fn update_line_video_character_generator_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_character_generator_file.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_character_generator_file(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_character_generator_file != new_val {
+        config_items.video_character_generator_file = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("character_generator_file = {}", value))
+            },
+            None => {
+                Some("character_generator_file = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn parse_entry_video_character_generator_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.video_character_generator_file = None;
+    } else {
+        config_items.video_character_generator_file = Some(argument);
+    }
+
+    Ok(())
+}
+
+fn update_line_video_lowercase_mod(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_lowercase_mod;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_lowercase_mod(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_lowercase_mod != new_val {
+        config_items.video_lowercase_mod = new_val;
+        Some(format!("lowercase_mod = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_lowercase_mod(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_lowercase_mod = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_hires_board(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_hires_board;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_hires_board(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_hires_board != new_val {
+        config_items.video_hires_board = new_val;
+        Some(format!("hires_board = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_hires_board(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_hires_board = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+fn update_line_video_bus_contention(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.video_bus_contention;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_video_bus_contention(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.video_bus_contention != new_val {
+        config_items.video_bus_contention = new_val;
+        Some(format!("bus_contention = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn parse_entry_video_bus_contention(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.video_bus_contention = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+
+
+fn new_handler_video_windowed_resolution() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The screen resolution, as WIDTHxHEIGHT, in windowed and full-screen mode.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The native resolution of the emulator is 512x384 (4:3 aspect ratio),".to_owned());
+    default_text.push("# recommended are multiples of this resolution, like 1024x768.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# I'd advise against 648x480, as it looks quite crummy because of the scaling.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The fullscreen resolution is only taken into account if the true fullscreen".to_owned());
+    default_text.push("# mode is selected.  In the desktop fullscreen mode, the emulator adapts to".to_owned());
+    default_text.push("# your current screen resolution.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("windowed_resolution = \"512x384\"".to_owned());
+
+    ConfigEntry {
+        entry_name:   "windowed_resolution".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeWindowedResolution,
+        update_line:  update_line_video_windowed_resolution,
+        parse_entry:  parse_entry_video_windowed_resolution,
+    }
+}
+fn new_handler_video_fullscreen_resolution() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+    default_text.push("fullscreen_resolution = \"1024x768\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "fullscreen_resolution".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeFullscreenResolution,
+        update_line:  update_line_video_fullscreen_resolution,
+        parse_entry:  parse_entry_video_fullscreen_resolution,
+    }
+}
+fn new_handler_video_bg_color() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The colors to use for the screen background and foreground, specified using".to_owned());
+    default_text.push("# the hex (#RRGGBB) format.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# By default, the background is black, #000000, and the foreground is green,".to_owned());
+    default_text.push("# #00FF00; other common choices for the foreground are amber, #FFBF00, and".to_owned());
+    default_text.push("# gray, #A8A8A8.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("bg_color = \"#000000\"".to_owned());
+
+    ConfigEntry {
+        entry_name:   "bg_color".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeColor,
+        update_line:  update_line_video_bg_color,
+        parse_entry:  parse_entry_video_bg_color,
+    }
+}
+fn new_handler_video_fg_color() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("fg_color = \"#00FF00\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "fg_color".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeColor,
+        update_line:  update_line_video_fg_color,
+        parse_entry:  parse_entry_video_fg_color,
+    }
+}
+fn new_handler_video_desktop_fullscreen_mode() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Use the desktop fullscreen mode (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If set to true, the emulator doesn't change the resolution of your screen".to_owned());
+    default_text.push("# when going into full-screen mode, and instead acts as a borderless window".to_owned());
+    default_text.push("# that takes up the whole screen.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("desktop_fullscreen_mode = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "desktop_fullscreen_mode".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeFullscreenResolution,
+        update_line:  update_line_video_desktop_fullscreen_mode,
+        parse_entry:  parse_entry_video_desktop_fullscreen_mode,
+    }
+}
+fn new_handler_video_fullscreen() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Whether the emulator window is in the full-screen mode (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# This is updated when switching between the windowed and full-screen modes".to_owned());
+    default_text.push("# with F11 or `/video fullscreen', so that the emulator starts up in the mode".to_owned());
+    default_text.push("# it was last left in.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("fullscreen = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "fullscreen".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeFullscreenMode,
+        update_line:  update_line_video_fullscreen,
+        parse_entry:  parse_entry_video_fullscreen,
+    }
+}
+fn new_handler_video_screenshot_scaled() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Whether screenshots are taken of the emulator window as it is, scaled up".to_owned());
+    default_text.push("# and with the CRT effects (true), or of the screen at its native resolution".to_owned());
+    default_text.push("# of 512x384, without the effects (false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("screenshot_scaled = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "screenshot_scaled".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateScreenshotSettings,
+        update_line:  update_line_video_screenshot_scaled,
+        parse_entry:  parse_entry_video_screenshot_scaled,
+    }
+}
+fn new_handler_video_recording_frame_rate() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The number of frames per second of screen recordings, made with".to_owned());
+    default_text.push("# `/record video', from 1 to 50.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The machine shows 60 frames per second, but GIF animations can't be played".to_owned());
+    default_text.push("# back any faster than 50.  Frames that are the same as the one before them".to_owned());
+    default_text.push("# don't make the recording any larger.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("recording_frame_rate = 10".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "recording_frame_rate".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateRecordingSettings,
+        update_line:  update_line_video_recording_frame_rate,
+        parse_entry:  parse_entry_video_recording_frame_rate,
+    }
+}
+fn new_handler_video_recording_max_seconds() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The length at which screen recordings stop by themselves, in seconds of".to_owned());
+    default_text.push("# the emulated machine's time, or 0 for recordings that only stop with".to_owned());
+    default_text.push("# `/record stop'.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("recording_max_seconds = 60".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "recording_max_seconds".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateRecordingSettings,
+        update_line:  update_line_video_recording_max_seconds,
+        parse_entry:  parse_entry_video_recording_max_seconds,
+    }
+}
+fn new_handler_video_text_graphics() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How the block graphics characters are written out when the text on the".to_owned());
+    default_text.push("# screen is copied with `/video text' (unicode or ascii).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With unicode, they're written out as the block sextant characters of".to_owned());
+    default_text.push("# Unicode, which look just like them, but which not all fonts have.  With".to_owned());
+    default_text.push("# ascii, they're written out as `#' if any of their blocks is lit, and as".to_owned());
+    default_text.push("# spaces otherwise.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("text_graphics = \"unicode\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "text_graphics".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateTextGraphics,
+        update_line:  update_line_video_text_graphics,
+        parse_entry:  parse_entry_video_text_graphics,
+    }
+}
+fn new_handler_video_scaling() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How the screen is scaled up to the size of the window (integer, aspect".to_owned());
+    default_text.push("# or stretch).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With integer, the screen is scaled by a whole number of times, so that all".to_owned());
+    default_text.push("# the dots of the characters come out the same size.  With aspect, it's".to_owned());
+    default_text.push("# scaled as large as fits, keeping its 4:3 aspect ratio.  With stretch, it".to_owned());
+    default_text.push("# fills the whole window, whatever the shape of the window is.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("scaling = \"aspect\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "scaling".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeScaling,
+        update_line:  update_line_video_scaling,
+        parse_entry:  parse_entry_video_scaling,
+    }
+}
+fn new_handler_video_crt_scanlines() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How dark the gaps between the scanlines are, in percent (0 to 100, 0".to_owned());
+    default_text.push("# turns the effect off).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("crt_scanlines = 0".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "crt_scanlines".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCrtEffects,
+        update_line:  update_line_video_crt_scanlines,
+        parse_entry:  parse_entry_video_crt_scanlines,
+    }
+}
+fn new_handler_video_crt_mask() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The strength of the red, green and blue phosphor stripes, in percent (0 to".to_owned());
+    default_text.push("# 100, 0 turns the effect off).  Works best with a white foreground color.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("crt_mask = 0".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "crt_mask".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCrtEffects,
+        update_line:  update_line_video_crt_mask,
+        parse_entry:  parse_entry_video_crt_mask,
+    }
+}
+fn new_handler_video_crt_curvature() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How much the picture bulges, like the glass of a CRT tube, in percent".to_owned());
+    default_text.push("# (0 to 100, 0 turns the effect off).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("crt_curvature = 0".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "crt_curvature".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCrtEffects,
+        update_line:  update_line_video_crt_curvature,
+        parse_entry:  parse_entry_video_crt_curvature,
+    }
+}
+fn new_handler_video_crt_persistence() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How much of the brightness of the picture is kept from one frame to the".to_owned());
+    default_text.push("# next, in percent (0 to 95, 0 turns the effect off), imitating the".to_owned());
+    default_text.push("# afterglow of the phosphors of the monitor.  Some programs flicker objects".to_owned());
+    default_text.push("# on alternate frames, relying on the afterglow to make them visible.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("crt_persistence = 0".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "crt_persistence".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCrtEffects,
+        update_line:  update_line_video_crt_persistence,
+        parse_entry:  parse_entry_video_crt_persistence,
+    }
+}
+fn new_handler_video_brightness() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The brightness of the picture, in percent (0 to 200, 100 leaves the colors".to_owned());
+    default_text.push("# as they are).  Like the brightness knob of a monitor, it moves the level of".to_owned());
+    default_text.push("# black, so values above 100 make the background glow.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("brightness = 100".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "brightness".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeColor,
+        update_line:  update_line_video_brightness,
+        parse_entry:  parse_entry_video_brightness,
+    }
+}
+fn new_handler_video_contrast() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The contrast of the picture, in percent (0 to 200, 100 leaves the colors as".to_owned());
+    default_text.push("# they are).  Like the contrast knob of a monitor, it sets how strong the".to_owned());
+    default_text.push("# signal is, making the lit dots brighter or dimmer.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("contrast = 100".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "contrast".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeColor,
+        update_line:  update_line_video_contrast,
+        parse_entry:  parse_entry_video_contrast,
+    }
+}
+fn new_handler_video_use_hw_accel() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Use hardware video acceleration (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With video acceleration enabled, the emulator will use your graphics card".to_owned());
+    default_text.push("# to render the screen directly.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# This is mainly useful when not using the emulator's native resolution, as it".to_owned());
+    default_text.push("# allows the GPU to stretch the image, instead of having the CPU stretch it.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("use_hw_accel = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "use_hw_accel".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeHwAccelUsage,
+        update_line:  update_line_video_use_hw_accel,
+        parse_entry:  parse_entry_video_use_hw_accel,
+    }
+}
+fn new_handler_video_renderer() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# What draws the screen into the emulator window (sdl or opengl).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With sdl, the screen is drawn by the renderer of SDL2, which can be".to_owned());
+    default_text.push("# hardware accelerated or not, as set with `use_hw_accel'.  With opengl, it's".to_owned());
+    default_text.push("# drawn by OpenGL directly, with shaders, which takes the least work from the".to_owned());
+    default_text.push("# CPU, especially with the CRT effects, but needs a graphics card with".to_owned());
+    default_text.push("# OpenGL 2.1 support, and the emulator has to be built with the `opengl'".to_owned());
+    default_text.push("# feature.  If the OpenGL renderer can't be used, the SDL2 one is used".to_owned());
+    default_text.push("# instead.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("renderer = \"sdl\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "renderer".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeRenderer,
+        update_line:  update_line_video_renderer,
+        parse_entry:  parse_entry_video_renderer,
+    }
+}
+fn new_handler_video_use_vsync() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Use vertical synchronization (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Vith vsync enabled, the screen contents are updated in sync with the screen's".to_owned());
+    default_text.push("# refresh rate, so that the picture doesn't tear, and the frames are shown at".to_owned());
+    default_text.push("# an even pace.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("use_vsync = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "use_vsync".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeVsyncUsage,
+        update_line:  update_line_video_use_vsync,
+        parse_entry:  parse_entry_video_use_vsync,
+    }
+}
+fn new_handler_video_frame_skip() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# How many frames to leave out between the drawn ones (auto, or 0 to 9).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Drawing fewer frames takes some of the load off of slow computers, so that".to_owned());
+    default_text.push("# the emulation itself can keep up.  With auto, frames are only left out when".to_owned());
+    default_text.push("# drawing them can't keep up with the emulated machine.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("frame_skip = \"auto\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "frame_skip".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeFrameSkip,
+        update_line:  update_line_video_frame_skip,
+        parse_entry:  parse_entry_video_frame_skip,
+    }
+}
+fn new_handler_video_overlay() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# What the status overlay in the corner of the window shows (off, speed or".to_owned());
+    default_text.push("# full).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With speed, the overlay shows the speed of the emulation and the frame rate,".to_owned());
+    default_text.push("# and with full, also the tape counter and the state of the cassette motor.".to_owned());
+    default_text.push("# The F3 key cycles through these.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("overlay = \"off\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "overlay".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeOverlay,
+        update_line:  update_line_video_overlay,
+        parse_entry:  parse_entry_video_overlay,
+    }
+}
+fn new_handler_video_notifications() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Show notifications at the bottom of the window for a few seconds, about".to_owned());
+    default_text.push("# things like inserting a cassette, saving the state of the machine or".to_owned());
+    default_text.push("# taking a screenshot (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("notifications = true".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "notifications".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeNotifications,
+        update_line:  update_line_video_notifications,
+        parse_entry:  parse_entry_video_notifications,
+    }
+}
+fn new_handler_video_character_generator() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Character generator to use (1 to 3).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# There are three variants of the character generator commonly found in".to_owned());
+    default_text.push("# a TRS-80 Model I, available for you to choose:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     1 - A very old version of the Model I font, found in only a few machines,".to_owned());
+    default_text.push("#         that has standard ASCII [ \\ ] ^ instead of directional arrows.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#         Level II basic puts odd symbols from positions 0-31 onto the screen".to_owned());
+    default_text.push("#         if you enable the lowercase mod.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     2 - This is the standard Model I character generator found in machines".to_owned());
+    default_text.push("#         without the Radio Shack lowercase modification, including the".to_owned());
+    default_text.push("#         arrows.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#         Just like with the previous character generator, Level II basic".to_owned());
+    default_text.push("#         puts odd symbols onto the screen if you enable the lowercase mod.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     3 - This is the replacement character generator you got with the".to_owned());
+    default_text.push("#         Radio Shack lowercase mod.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#         Positions 0-31 are a copy of the uppercase letters, to work around".to_owned());
+    default_text.push("#         a bug in the Level II ROM.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#         All characters without descenders are moved up one row.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("character_generator = 2".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "character_generator".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCharacterGenerator,
+        update_line:  update_line_video_character_generator,
+        parse_entry:  parse_entry_video_character_generator,
+    }
+}
+fn new_handler_video_character_generator_file() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# A custom character generator font to use instead of the above ones (name,".to_owned());
+    default_text.push("# path, or the keyword `none'), for clone machines and modified character".to_owned());
+    default_text.push("# sets.  Names are looked up in the configuration directory.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The file is either a dump of the character generator ROM, 1024 bytes long,".to_owned());
+    default_text.push("# with 8 rows for each of the 128 characters, or a font bitmap, 1536 bytes".to_owned());
+    default_text.push("# long, with 12 rows for each character, so that there's room for the".to_owned());
+    default_text.push("# descenders of lowercase letters.  Each row is a byte, the top row comes".to_owned());
+    default_text.push("# first, and the lowest bit of a row is its leftmost dot.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The character generators of the Japanese and the European machines have".to_owned());
+    default_text.push("# an alternate character set, with katakana or accented letters.  A dump of".to_owned());
+    default_text.push("# one of them is 2048 bytes long, the alternate set being its last 64".to_owned());
+    default_text.push("# characters, and a font bitmap with it is 2304 bytes long.  The set is".to_owned());
+    default_text.push("# shown instead of the block graphics characters 0xC0-0xFF while bit 3 of".to_owned());
+    default_text.push("# the IO port ECh is set, like in the Model III, or with `/video charset'.".to_owned());
+    default_text.push("# It needs the lowercase mod to be shown.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If the file can't be loaded, the character generator selected above is".to_owned());
+    default_text.push("# used instead.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("character_generator_file = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "character_generator_file".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeCharacterGenerator,
+        update_line:  update_line_video_character_generator_file,
+        parse_entry:  parse_entry_video_character_generator_file,
+    }
+}
+fn new_handler_video_lowercase_mod() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Use the lowercase mod (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The original TRS-80 Model I machines lacked the ability to display lowercase".to_owned());
+    default_text.push("# characters, but this could be remedied by a modification.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# It is advised to use character generator 3 with this modification enabled,".to_owned());
+    default_text.push("# as without it, Level II basic puts odd symbols onto the screen instead of".to_owned());
+    default_text.push("# the regular uppercase letters.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("lowercase_mod = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "lowercase_mod".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeLowercaseModUsage,
+        update_line:  update_line_video_lowercase_mod,
+        parse_entry:  parse_entry_video_lowercase_mod,
+    }
+}
+fn new_handler_video_hires_board() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Install the high resolution graphics board (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The board is the HRG1B, which adds a bitmap of 384 by 192 pixels shown over".to_owned());
+    default_text.push("# the text screen.  Programs access it through the IO ports 00h-05h: writing".to_owned());
+    default_text.push("# 00h or 01h turns the bitmap off or on, 02h and 03h take the low and high".to_owned());
+    default_text.push("# byte of the address, 04h reads the byte at it and 05h writes it.  Bits 0-9".to_owned());
+    default_text.push("# of the address select the character the byte is under, bits 10-13 the row".to_owned());
+    default_text.push("# of the character, and the 6 pixels of the row are in bits 0-5 of the byte.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("hires_board = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "hires_board".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeHiResBoard,
+        update_line:  update_line_video_hires_board,
+        parse_entry:  parse_entry_video_hires_board,
+    }
+}
+fn new_handler_video_bus_contention() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Emulate the video RAM bus contention (true or false).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# When the CPU of a Model I accesses the video RAM while the screen is being".to_owned());
+    default_text.push("# drawn, the character being drawn at that moment is replaced by the byte the".to_owned());
+    default_text.push("# CPU accessed, for a single scanline, which shows up as short flickering".to_owned());
+    default_text.push("# dashes on the screen.  Some programs time their screen updates to avoid".to_owned());
+    default_text.push("# them, this option shows whether they do.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("bus_contention = false".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "bus_contention".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::ChangeBusContention,
+        update_line:  update_line_video_bus_contention,
+        parse_entry:  parse_entry_video_bus_contention,
+    }
+}
+
+fn new_video_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
+
+    entries.push(new_handler_video_windowed_resolution());
+    entries.push(new_handler_video_fullscreen_resolution());
+    entries.push(new_handler_video_bg_color());
+    entries.push(new_handler_video_fg_color());
+    entries.push(new_handler_video_desktop_fullscreen_mode());
+    entries.push(new_handler_video_fullscreen());
+    entries.push(new_handler_video_scaling());
+    entries.push(new_handler_video_crt_scanlines());
+    entries.push(new_handler_video_crt_mask());
+    entries.push(new_handler_video_crt_curvature());
+    entries.push(new_handler_video_crt_persistence());
+    entries.push(new_handler_video_brightness());
+    entries.push(new_handler_video_contrast());
+    entries.push(new_handler_video_screenshot_scaled());
+    entries.push(new_handler_video_recording_frame_rate());
+    entries.push(new_handler_video_recording_max_seconds());
+    entries.push(new_handler_video_text_graphics());
+    entries.push(new_handler_video_use_hw_accel());
+    entries.push(new_handler_video_renderer());
+    entries.push(new_handler_video_use_vsync());
+    entries.push(new_handler_video_frame_skip());
+    entries.push(new_handler_video_overlay());
+    entries.push(new_handler_video_notifications());
+    entries.push(new_handler_video_character_generator());
+    entries.push(new_handler_video_character_generator_file());
+    entries.push(new_handler_video_lowercase_mod());
+    entries.push(new_handler_video_hires_board());
+    entries.push(new_handler_video_bus_contention());
+
+    let obsolete_entries: Vec<String> = Vec::new();
+
+    ConfigSection {
+        section_name:     "video".to_owned(),
+        entries:          entries.into_boxed_slice(),
+        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    }
+}
+
+// The cassette section and entries:
+fn update_line_cassette_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_file.clone();
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_file(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_file != new_val {
+        config_items.cassette_file = new_val.clone();
+        match new_val {
+            Some(value) => {
+                Some(format!("file = {}", value))
+            },
+            None => {
+                Some("file = none".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn update_line_cassette_file_format(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_file_format;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_file_format(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_file_format != new_val {
+        config_items.cassette_file_format = new_val;
+        match new_val {
+            cassette::Format::CAS => {
+                Some("file_format = CAS".to_owned())
+            },
+            cassette::Format::CPT => {
+                Some("file_format = CPT".to_owned())
+            },
+            cassette::Format::WAV => {
+                Some("file_format = WAV".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn update_line_cassette_file_offset(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_file_offset;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_file_offset(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_file_offset != new_val {
+        config_items.cassette_file_offset = new_val;
+        Some(format!("file_offset = {}", new_val))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_copy(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_copy;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_copy(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_copy != new_val {
+        config_items.cassette_wav_copy = new_val;
+        Some(format!("wav_copy = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_fast_load(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_fast_load;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_fast_load(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_fast_load != new_val {
+        config_items.cassette_fast_load = new_val;
+        Some(format!("fast_load = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_threshold(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_threshold;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_threshold(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_threshold != new_val {
+        config_items.cassette_wav_threshold = new_val;
+        Some(format!("wav_threshold = {}", new_val))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_noise_floor(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_noise_floor;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_noise_floor(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_noise_floor != new_val {
+        config_items.cassette_wav_noise_floor = new_val;
+        Some(format!("wav_noise_floor = {}", new_val))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_dc_filter(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_dc_filter;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_dc_filter(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_dc_filter != new_val {
+        config_items.cassette_wav_dc_filter = new_val;
+        Some(format!("wav_dc_filter = {}", if new_val { "true" } else { "false" }))
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_channel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_channel;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_channel(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_channel != new_val {
+        config_items.cassette_wav_channel = new_val;
+        match new_val {
+            cassette::WavChannel::Left => {
+                Some("wav_channel = left".to_owned())
+            },
+            cassette::WavChannel::Right => {
+                Some("wav_channel = right".to_owned())
+            },
+            cassette::WavChannel::Mix => {
+                Some("wav_channel = mix".to_owned())
+            },
+        }
+    } else {
+        None
+    }
+}
+fn update_line_cassette_wav_speed(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.cassette_wav_speed;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_cassette_wav_speed(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.cassette_wav_speed != new_val {
+        config_items.cassette_wav_speed = new_val;
+        Some(format!("wav_speed = {}", new_val))
+    } else {
+        None
+    }
+}
+fn parse_entry_cassette_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+
+    if argument.to_uppercase() == "NONE" {
+        config_items.cassette_file = None;
+    } else {
+        config_items.cassette_file = Some(argument);
+    }
+
+    Ok(())
+}
+fn parse_entry_cassette_file_format(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_uppercase();
+
+    if compare_str == "CAS" {
+        config_items.cassette_file_format = cassette::Format::CAS;
+        Ok(())
+    } else if compare_str == "CPT" {
+        config_items.cassette_file_format = cassette::Format::CPT;
+        Ok(())
+    } else if compare_str == "WAV" {
+        config_items.cassette_file_format = cassette::Format::WAV;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidCassetteFormatSpecifier(info_source))
+    }
+}
+fn parse_entry_cassette_file_offset(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<usize>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    config_items.cassette_file_offset = argument;
+    Ok(())
+}
+fn parse_entry_cassette_wav_copy(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.cassette_wav_copy = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn parse_entry_cassette_fast_load(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.cassette_fast_load = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn parse_entry_cassette_wav_threshold(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if (1..=100).contains(&argument) {
+        config_items.cassette_wav_threshold = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::WavThresholdOutOfRange(info_source, argument))
+    }
+}
+fn parse_entry_cassette_wav_noise_floor(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if argument <= 127 {
+        config_items.cassette_wav_noise_floor = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::WavNoiseFloorOutOfRange(info_source, argument))
+    }
+}
+fn parse_entry_cassette_wav_dc_filter(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.cassette_wav_dc_filter = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        }
+    }
+}
+fn parse_entry_cassette_wav_channel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
+
+    if compare_str == "left" {
+        config_items.cassette_wav_channel = cassette::WavChannel::Left;
+        Ok(())
+    } else if compare_str == "right" {
+        config_items.cassette_wav_channel = cassette::WavChannel::Right;
+        Ok(())
+    } else if compare_str == "mix" {
+        config_items.cassette_wav_channel = cassette::WavChannel::Mix;
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidWavChannelSpecifier(info_source))
+    }
+}
+fn parse_entry_cassette_wav_speed(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
+
+    if (50..=200).contains(&argument) {
+        config_items.cassette_wav_speed = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::WavSpeedOutOfRange(info_source, argument))
+    }
+}
+fn new_handler_cassette_file() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# The name of the cassette file currently in the tape drive.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# You can either specify a full path to a cassette file, a simple file name".to_owned());
+    default_text.push("# if you want the file to be located in the configuration directory, or the".to_owned());
+    default_text.push("# keyword `none' to leave the tape drive empty.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If the specified file doesn't exist yet, it will be created.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("file = \"none\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "file".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteFile,
+        update_line:  update_line_cassette_file,
+        parse_entry:  parse_entry_cassette_file,
+    }
+}
+fn new_handler_cassette_file_format() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Cassette file format selection (CAS, CPT or WAV):".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Currently, the emulator supports three cassette file formats:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     CAS - A file containing the recovered bytes from the cassette.".to_owned());
+    default_text.push("#           It is a fairly compact format, and it's compatible with other".to_owned());
+    default_text.push("#           TRS-80 emulators that have cassette support.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     CPT - Cassette Pulse Train - A file containing exact values and timing".to_owned());
+    default_text.push("#           (to the nearest microsecond) of the signals the TRS-80 cassette".to_owned());
+    default_text.push("#           routine sends to the cassette output port to be recorded on the".to_owned());
+    default_text.push("#           tape.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#           This format, originating from Tim Mann's xtrs emulator, emulates".to_owned());
+    default_text.push("#           a perfect, noise-free cassette, so any cassette routines that even".to_owned());
+    default_text.push("#           halfway worked on real hardware should work with it.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#     WAV - An audio recording of a real tape (uncompressed 8 or 16-bit PCM).".to_owned());
+    default_text.push("#           The pulses are picked out of the recorded signal as it's played".to_owned());
+    default_text.push("#           back, so both 250 and 500 baud tapes can be loaded.  Recordings".to_owned());
+    default_text.push("#           are stored as audio that can be played into a real machine.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("file_format = \"CAS\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "file_format".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteFileFormat,
+        update_line:  update_line_cassette_file_format,
+        parse_entry:  parse_entry_cassette_file_format,
     }
 }
+fn new_handler_cassette_file_offset() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Current byte offset into the cassette.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# This value indicates how far the cassette is currently wound past the".to_owned());
+    default_text.push("# beginning, in bytes.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The cassette can be rewound to arbitrary locations, and thus several different".to_owned());
+    default_text.push("# records/files can be recorded and later loaded from a single cassette, as long".to_owned());
+    default_text.push("# as you keep track of where the different records/files are located, or request".to_owned());
+    default_text.push("# files based on their filename (Level II BASIC).  See `/help cassette' in the".to_owned());
+    default_text.push("# curses-based user interface for more details.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Please note that if a format like CPT is used, setting the offset to arbitrary".to_owned());
+    default_text.push("# locations might cause the data to be incorrectly parsed (since it may easily".to_owned());
+    default_text.push("# get out of alignment), it is therefore advised to only explicitly set this".to_owned());
+    default_text.push("# parameters to known-good values (ie. the beginning and end locations of the".to_owned());
+    default_text.push("# individual records/files on the tape).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("file_offset = 0".to_owned());
+    default_text.push("".to_owned());
 
-fn new_handler_general_level_1_rom() -> ConfigEntry {
+    ConfigEntry {
+        entry_name:   "file_offset".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteFileOffset,
+        update_line:  update_line_cassette_file_offset,
+        parse_entry:  parse_entry_cassette_file_offset,
+    }
+}
+fn new_handler_cassette_wav_copy() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
 
     default_text.push("".to_owned());
-    default_text.push("; The name of the system ROM image files (name, path, or the keyword `none').".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; You only need to provide one of these rom files.  The three fields exist".to_owned());
-    default_text.push("; solely for the sake of convenience, when you want to have different roms".to_owned());
-    default_text.push("; and to be able to switch between them without having to keep changing".to_owned());
-    default_text.push("; the names here in the configuration file.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; If you specify a name, the program will look for the rom file in the".to_owned());
-    default_text.push("; configuration directory, which is where this file resides.  If you want".to_owned());
-    default_text.push("; to store the rom(s) in a different directory, specify a full path to the".to_owned());
-    default_text.push("; rom files.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("level_1_rom = none".to_owned());
+    default_text.push("# Whether to keep an audio copy of recordings made into CAS or CPT cassettes.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If enabled, each time the emulated machine records something onto the tape,".to_owned());
+    default_text.push("# the generated pulses are also written into a WAV file named after the".to_owned());
+    default_text.push("# cassette file, with `.wav' appended (for example `tape.cas.wav').  The copy".to_owned());
+    default_text.push("# only holds the most recent recording, and can be played back into a real".to_owned());
+    default_text.push("# TRS-80 or loaded into other emulators.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_copy = false".to_owned());
+    default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "level_1_rom".to_owned(),
+        entry_name:   "wav_copy".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::RomChange(1),
-        update_line:  update_line_general_level_1_rom,
-        parse_entry:  parse_entry_general_level_1_rom,
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavCopy,
+        update_line:  update_line_cassette_wav_copy,
+        parse_entry:  parse_entry_cassette_wav_copy,
     }
 }
-fn new_handler_general_level_2_rom() -> ConfigEntry {
+fn new_handler_cassette_fast_load() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
 
-    default_text.push("level_2_rom = none".to_owned());
+    default_text.push("".to_owned());
+    default_text.push("# Whether to load CAS cassettes instantly when using the Level II ROM.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If enabled, the cassette reading routines of the Level II ROM are bypassed,".to_owned());
+    default_text.push("# and the bytes are handed to them straight from the CAS file, so that".to_owned());
+    default_text.push("# `CLOAD' and `SYSTEM' finish right away instead of taking as long as a real".to_owned());
+    default_text.push("# tape would.  Programs with their own loading routines, and other cassette".to_owned());
+    default_text.push("# formats, still load at the normal speed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("fast_load = false".to_owned());
+    default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "level_2_rom".to_owned(),
+        entry_name:   "fast_load".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::RomChange(2),
-        update_line:  update_line_general_level_2_rom,
-        parse_entry:  parse_entry_general_level_2_rom,
+        apply_action: ConfigChangeApplyAction::UpdateCassetteFastLoad,
+        update_line:  update_line_cassette_fast_load,
+        parse_entry:  parse_entry_cassette_fast_load,
     }
 }
-fn new_handler_general_misc_rom() -> ConfigEntry {
+fn new_handler_cassette_wav_threshold() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
 
-    default_text.push("misc_rom = none".to_owned());
+    default_text.push("".to_owned());
+    default_text.push("# WAV input decoding parameters:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The following entries control how the pulses are picked out of recordings".to_owned());
+    default_text.push("# of real tapes.  The defaults work for most clean recordings, but marginal".to_owned());
+    default_text.push("# ones can often still be read after some tweaking.  Use `/cassette index'".to_owned());
+    default_text.push("# to see what the decoder finds on the tape, and which blocks of it have bad".to_owned());
+    default_text.push("# checksums.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# How far the signal has to swing to count as a pulse, in percent of its".to_owned());
+    default_text.push("# recent peak level (1 to 100).  Lower it if pulses get missed, raise it if".to_owned());
+    default_text.push("# noise gets picked up as pulses.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_threshold = 50".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "misc_rom".to_owned(),
+        entry_name:   "wav_threshold".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::RomChange(3),
-        update_line:  update_line_general_misc_rom,
-        parse_entry:  parse_entry_general_misc_rom,
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavDecoding,
+        update_line:  update_line_cassette_wav_threshold,
+        parse_entry:  parse_entry_cassette_wav_threshold,
     }
 }
-fn new_handler_general_default_rom() -> ConfigEntry {
+fn new_handler_cassette_wav_noise_floor() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
 
     default_text.push("".to_owned());
-    default_text.push("; Which of the three rom files to use (1 to 3); this can be overridden on".to_owned());
-    default_text.push("; program startup, using the -1, -2 and -3 command-line arguments.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("default_rom = 2".to_owned());
+    default_text.push("# The smallest swing of the signal that can count as a pulse, in 8-bit".to_owned());
+    default_text.push("# sample units (0 to 127).  Raise it for recordings with a lot of hiss.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_noise_floor = 8".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "default_rom".to_owned(),
+        entry_name:   "wav_noise_floor".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::UpdateDefaultRomSelection,
-        update_line:  update_line_general_default_rom,
-        parse_entry:  parse_entry_general_default_rom,
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavDecoding,
+        update_line:  update_line_cassette_wav_noise_floor,
+        parse_entry:  parse_entry_cassette_wav_noise_floor,
     }
 }
-fn new_handler_general_ram_size() -> ConfigEntry {
+fn new_handler_cassette_wav_dc_filter() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
 
     default_text.push("".to_owned());
-    default_text.push("; The amount of memory the machine has installed.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; Common values are 4K, 16K, 32K and 48K.  You can have at most 48K installed.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; You can specify the amount of memory either in bytes (without a suffix), or".to_owned());
-    default_text.push("; in kilobytes, by appending the K suffix to the number.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("ram_size = 16K".to_owned());
+    default_text.push("# Whether to compensate for a DC offset in the recording, by measuring the".to_owned());
+    default_text.push("# pulses against the average of the signal instead of against zero.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_dc_filter = true".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "ram_size".to_owned(),
+        entry_name:   "wav_dc_filter".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeRamSize,
-        update_line:  update_line_general_ram_size,
-        parse_entry:  parse_entry_general_ram_size,
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavDecoding,
+        update_line:  update_line_cassette_wav_dc_filter,
+        parse_entry:  parse_entry_cassette_wav_dc_filter,
+    }
+}
+fn new_handler_cassette_wav_channel() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
+
+    default_text.push("".to_owned());
+    default_text.push("# Which channel of a stereo recording to decode (left, right or mix).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_channel = \"left\"".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "wav_channel".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavDecoding,
+        update_line:  update_line_cassette_wav_channel,
+        parse_entry:  parse_entry_cassette_wav_channel,
     }
 }
+fn new_handler_cassette_wav_speed() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
 
-fn new_general_section() -> ConfigSection {
+    default_text.push("".to_owned());
+    default_text.push("# The speed to play WAV recordings back at, in percent (50 to 200).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Tapes recorded on a deck that ran too slow can be sped up with values above".to_owned());
+    default_text.push("# 100, ones recorded on a deck that ran too fast slowed down with values".to_owned());
+    default_text.push("# below it, to get them within what the ROM routines tolerate.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("wav_speed = 100".to_owned());
+    default_text.push("".to_owned());
+
+    ConfigEntry {
+        entry_name:   "wav_speed".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateCassetteWavDecoding,
+        update_line:  update_line_cassette_wav_speed,
+        parse_entry:  parse_entry_cassette_wav_speed,
+    }
+}
+fn new_cassette_section() -> ConfigSection {
     let mut entries: Vec<ConfigEntry> = Vec::new();
 
-    entries.push(new_handler_general_level_1_rom());
-    entries.push(new_handler_general_level_2_rom());
-    entries.push(new_handler_general_misc_rom());
-    entries.push(new_handler_general_default_rom());
-    entries.push(new_handler_general_ram_size());
+    entries.push(new_handler_cassette_file());
+    entries.push(new_handler_cassette_file_format());
+    entries.push(new_handler_cassette_file_offset());
+    entries.push(new_handler_cassette_wav_copy());
+    entries.push(new_handler_cassette_fast_load());
+    entries.push(new_handler_cassette_wav_threshold());
+    entries.push(new_handler_cassette_wav_noise_floor());
+    entries.push(new_handler_cassette_wav_dc_filter());
+    entries.push(new_handler_cassette_wav_channel());
+    entries.push(new_handler_cassette_wav_speed());
 
     let obsolete_entries: Vec<String> = Vec::new();
 
     ConfigSection {
-        section_name:     "General".to_owned(),
+        section_name:     "cassette".to_owned(),
         entries:          entries.into_boxed_slice(),
         obsolete_entries: obsolete_entries.into_boxed_slice(),
     }
 }
 
-// The keyboard section and entries:
-fn update_line_keyboard_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.keyboard_ms_per_keypress;
+// The audio section and entries:
+fn update_line_audio_enabled(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_enabled;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_keyboard_ms_per_keypress(info_source, config_items) {
+    let failed_read = match parse_entry_audio_enabled(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.keyboard_ms_per_keypress != new_val {
-        config_items.keyboard_ms_per_keypress = new_val;
-        Some(format!("ms_per_keypress = {}", new_val))
+    if failed_read || config_items.audio_enabled != new_val {
+        config_items.audio_enabled = new_val;
+        Some(format!("enabled = {}", if new_val { "true" } else { "false" }))
     } else {
         None
     }
 }
+fn update_line_audio_volume(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_volume;
 
-fn parse_entry_keyboard_ms_per_keypress(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = match info_source.argument_text().parse::<u32>() {
-        Ok(result) => { result },
-        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_audio_volume(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
     };
 
-    config_items.keyboard_ms_per_keypress = argument;
-    Ok(())
-}
-
-fn new_handler_keyboard_ms_per_keypress() -> ConfigEntry {
-    let mut default_text: Vec<String> = Vec::new();
-    default_text.push("".to_owned());
-    default_text.push("; The minimum time it takes to press down or release a key, in miliseconds.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; The purpose of this is to make sure that the input routine can catch the".to_owned());
-    default_text.push("; keyboard updates, since there's no dedicated circuitry for this in the".to_owned());
-    default_text.push("; machine, just the CPU probing the keyboard matrix.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; A value between 5 to 50 is recommended.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("ms_per_keypress = 20".to_owned());
-    default_text.push("".to_owned());
-
-    ConfigEntry {
-        entry_name:   "ms_per_keypress".to_owned(),
-        default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::UpdateMsPerKeypress,
-        update_line:  update_line_keyboard_ms_per_keypress,
-        parse_entry:  parse_entry_keyboard_ms_per_keypress,
+    // Update only if we really need to update:
+    if failed_read || config_items.audio_volume != new_val {
+        config_items.audio_volume = new_val;
+        Some(format!("volume = {}", new_val))
+    } else {
+        None
     }
 }
+fn update_line_audio_sample_rate(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_sample_rate;
 
-fn new_keyboard_section() -> ConfigSection {
-    let mut entries: Vec<ConfigEntry> = Vec::new();
-    entries.push(new_handler_keyboard_ms_per_keypress());
-
-    let obsolete_entries: Vec<String> = Vec::new();
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_audio_sample_rate(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
 
-    ConfigSection {
-        section_name:     "Keyboard".to_owned(),
-        entries:          entries.into_boxed_slice(),
-        obsolete_entries: obsolete_entries.into_boxed_slice(),
+    // Update only if we really need to update:
+    if failed_read || config_items.audio_sample_rate != new_val {
+        config_items.audio_sample_rate = new_val;
+        Some(format!("sample_rate = {}", new_val))
+    } else {
+        None
     }
 }
-
-// The video section and entries:
-fn update_line_video_windowed_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_windowed_resolution;
+fn update_line_audio_buffer_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_buffer_size;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_windowed_resolution(info_source, config_items) {
+    let failed_read = match parse_entry_audio_buffer_size(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_windowed_resolution != new_val {
-        config_items.video_windowed_resolution = new_val;
-        let (width, height) = new_val;
-        Some(format!("windowed_resolution = {}x{}", width, height))
+    if failed_read || config_items.audio_buffer_size != new_val {
+        config_items.audio_buffer_size = new_val;
+        Some(format!("buffer_size = {}", new_val))
     } else {
         None
     }
 }
-fn update_line_video_fullscreen_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_fullscreen_resolution;
+fn update_line_audio_latency(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_latency;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_fullscreen_resolution(info_source, config_items) {
+    let failed_read = match parse_entry_audio_latency(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_fullscreen_resolution != new_val {
-        config_items.video_fullscreen_resolution = new_val;
-        let (width, height) = new_val;
-        Some(format!("fullscreen_resolution = {}x{}", width, height))
+    if failed_read || config_items.audio_latency != new_val {
+        config_items.audio_latency = new_val;
+        Some(format!("latency = {}", new_val))
     } else {
         None
     }
 }
-fn parse_entry_video_windowed_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_resolution_argument(info_source.argument_text().as_str()) {
-        Some(resolution) => {
-            config_items.video_windowed_resolution = resolution;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidResolutionSpecifier(info_source))
-        }
-    }
-}
-fn parse_entry_video_fullscreen_resolution(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_resolution_argument(info_source.argument_text().as_str()) {
-        Some(resolution) => {
-            config_items.video_fullscreen_resolution = resolution;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidResolutionSpecifier(info_source))
+fn update_line_audio_resampling(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_resampling;
+
+    // Re-parse the entry, to see if it really changed and to see whether
+    // an update really is neccessary.  On failure assume yes.
+    let failed_read = match parse_entry_audio_resampling(info_source, config_items) {
+        Ok(..)  => { false },
+        Err(..) => { true  },
+    };
+
+    // Update only if we really need to update:
+    if failed_read || config_items.audio_resampling != new_val {
+        config_items.audio_resampling = new_val;
+        match new_val {
+            sound::Resampling::Low => {
+                Some("resampling = low".to_owned())
+            },
+            sound::Resampling::High => {
+                Some("resampling = high".to_owned())
+            },
         }
+    } else {
+        None
     }
 }
 
-fn update_line_video_bg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_bg_color;
+fn update_line_audio_mute_tape(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_mute_tape;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_bg_color(info_source, config_items) {
+    let failed_read = match parse_entry_audio_mute_tape(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_bg_color != new_val {
-        config_items.video_bg_color = new_val;
-        let (red, green, blue) = new_val;
-        Some(format!("bg_color = #{:02X}{:02X}{:02X}", red, green, blue))
+    if failed_read || config_items.audio_mute_tape != new_val {
+        config_items.audio_mute_tape = new_val;
+        Some(format!("mute_tape = {}", if new_val { "true" } else { "false" }))
     } else {
         None
     }
 }
-fn update_line_video_fg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_fg_color;
+fn update_line_audio_mute_program(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_mute_program;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_fg_color(info_source, config_items) {
+    let failed_read = match parse_entry_audio_mute_program(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_fg_color != new_val {
-        config_items.video_fg_color = new_val;
-        let (red, green, blue) = new_val;
-        Some(format!("fg_color = #{:02X}{:02X}{:02X}", red, green, blue))
+    if failed_read || config_items.audio_mute_program != new_val {
+        config_items.audio_mute_program = new_val;
+        Some(format!("mute_program = {}", if new_val { "true" } else { "false" }))
     } else {
         None
     }
 }
-fn parse_entry_video_bg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_color_argument(info_source.argument_text().as_str()) {
-        Some(color) => {
-            config_items.video_bg_color = color;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidColorSpecifier(info_source))
-        }
-    }
-}
-fn parse_entry_video_fg_color(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_color_argument(info_source.argument_text().as_str()) {
-        Some(color) => {
-            config_items.video_fg_color = color;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidColorSpecifier(info_source))
-        }
-    }
-}
-
-fn update_line_video_desktop_fullscreen_mode(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_desktop_fullscreen_mode;
+fn update_line_audio_mute_orchestra(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_mute_orchestra;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_desktop_fullscreen_mode(info_source, config_items) {
+    let failed_read = match parse_entry_audio_mute_orchestra(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_desktop_fullscreen_mode != new_val {
-        config_items.video_desktop_fullscreen_mode = new_val;
-        Some(format!("desktop_fullscreen_mode = {}", if new_val { "true" } else { "false" }))
+    if failed_read || config_items.audio_mute_orchestra != new_val {
+        config_items.audio_mute_orchestra = new_val;
+        Some(format!("mute_orchestra = {}", if new_val { "true" } else { "false" }))
     } else {
         None
     }
 }
-fn parse_entry_video_desktop_fullscreen_mode(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_bool_argument(info_source.argument_text().as_str()) {
-        Some(value) => {
-            config_items.video_desktop_fullscreen_mode = value;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidBoolSpecifier(info_source))
-        }
-    }
-}
-
-fn update_line_video_use_hw_accel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_use_hw_accel;
+fn update_line_audio_mute_ay(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_mute_ay;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_use_hw_accel(info_source, config_items) {
+    let failed_read = match parse_entry_audio_mute_ay(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_use_hw_accel != new_val {
-        config_items.video_use_hw_accel = new_val;
-        Some(format!("use_hw_accel = {}", if new_val { "true" } else { "false" }))
+    if failed_read || config_items.audio_mute_ay != new_val {
+        config_items.audio_mute_ay = new_val;
+        Some(format!("mute_ay = {}", if new_val { "true" } else { "false" }))
     } else {
         None
     }
 }
-fn parse_entry_video_use_hw_accel(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    match parse_bool_argument(info_source.argument_text().as_str()) {
-        Some(value) => {
-            config_items.video_use_hw_accel = value;
-            Ok(())
-        },
-        None => {
-            Err(ConfigError::InvalidBoolSpecifier(info_source))
-        }
-    }
-}
-
-fn update_line_video_use_vsync(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_use_vsync;
+fn update_line_audio_ay_port(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.audio_ay_port;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_use_vsync(info_source, config_items) {
+    let failed_read = match parse_entry_audio_ay_port(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.video_use_vsync != new_val {
-        config_items.video_use_vsync = new_val;
-        Some(format!("use_vsync = {}", if new_val { "true" } else { "false" }))
+    if failed_read || config_items.audio_ay_port != new_val {
+        config_items.audio_ay_port = new_val;
+        match new_val {
+            Some(port) => { Some(format!("ay_port = {:02X}h", port)) },
+            None       => { Some("ay_port = none".to_owned()) },
+        }
     } else {
         None
     }
 }
-fn parse_entry_video_use_vsync(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+
+fn parse_entry_audio_enabled(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
     match parse_bool_argument(info_source.argument_text().as_str()) {
         Some(value) => {
-            config_items.video_use_vsync = value;
+            config_items.audio_enabled = value;
             Ok(())
         },
         None => {
             Err(ConfigError::InvalidBoolSpecifier(info_source))
-        }
+        },
     }
 }
+fn parse_entry_audio_volume(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
 
-fn update_line_video_character_generator(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_character_generator;
+    if argument <= 100 {
+        config_items.audio_volume = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::VolumeOutOfRange(info_source, argument))
+    }
+}
+fn parse_entry_audio_sample_rate(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+    };
 
-    // Re-parse the entry, to see if it really changed and to see whether
-    // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_character_generator(info_source, config_items) {
-        Ok(..)  => { false },
-        Err(..) => { true  },
+    if (8_000..=96_000).contains(&argument) {
+        config_items.audio_sample_rate = argument;
+        Ok(())
+    } else {
+        Err(ConfigError::SampleRateOutOfRange(info_source, argument))
+    }
+}
+fn parse_entry_audio_buffer_size(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match info_source.argument_text().parse::<u32>() {
+        Ok(result) => { result },
+        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
     };
 
-    // Update only if we really need to update:
-    if failed_read || config_items.video_character_generator != new_val {
-        config_items.video_character_generator = new_val;
-        Some(format!("character_generator = {}", new_val))
+    if (64..=8192).contains(&argument) && argument.is_power_of_two() {
+        config_items.audio_buffer_size = argument;
+        Ok(())
     } else {
-        None
+        Err(ConfigError::BufferSizeOutOfRange(info_source, argument))
     }
 }
-fn parse_entry_video_character_generator(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+fn parse_entry_audio_latency(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
     let argument = match info_source.argument_text().parse::<u32>() {
         Ok(result) => { result },
         Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
     };
 
-    if argument >= 1 && argument <= 3 {
-        config_items.video_character_generator = argument;
+    if (10..=1000).contains(&argument) {
+        config_items.audio_latency = argument;
         Ok(())
     } else {
-        Err(ConfigError::CharacterGeneratorOutOfRange(info_source, argument))
+        Err(ConfigError::LatencyOutOfRange(info_source, argument))
     }
 }
+fn parse_entry_audio_resampling(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
+    let compare_str = argument.to_lowercase();
 
-fn update_line_video_lowercase_mod(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.video_lowercase_mod;
-
-    // Re-parse the entry, to see if it really changed and to see whether
-    // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_video_lowercase_mod(info_source, config_items) {
-        Ok(..)  => { false },
-        Err(..) => { true  },
-    };
-
-    // Update only if we really need to update:
-    if failed_read || config_items.video_lowercase_mod != new_val {
-        config_items.video_lowercase_mod = new_val;
-        Some(format!("lowercase_mod = {}", if new_val { "true" } else { "false" }))
+    if compare_str == "low" {
+        config_items.audio_resampling = sound::Resampling::Low;
+        Ok(())
+    } else if compare_str == "high" {
+        config_items.audio_resampling = sound::Resampling::High;
+        Ok(())
     } else {
-        None
+        Err(ConfigError::InvalidResamplingSpecifier(info_source))
     }
 }
-fn parse_entry_video_lowercase_mod(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+fn parse_entry_audio_mute_tape(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
     match parse_bool_argument(info_source.argument_text().as_str()) {
         Some(value) => {
-            config_items.video_lowercase_mod = value;
+            config_items.audio_mute_tape = value;
             Ok(())
         },
         None => {
             Err(ConfigError::InvalidBoolSpecifier(info_source))
-        }
+        },
+    }
+}
+fn parse_entry_audio_mute_program(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.audio_mute_program = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        },
+    }
+}
+fn parse_entry_audio_mute_orchestra(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.audio_mute_orchestra = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        },
+    }
+}
+fn parse_entry_audio_mute_ay(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    match parse_bool_argument(info_source.argument_text().as_str()) {
+        Some(value) => {
+            config_items.audio_mute_ay = value;
+            Ok(())
+        },
+        None => {
+            Err(ConfigError::InvalidBoolSpecifier(info_source))
+        },
     }
 }
+fn parse_entry_audio_ay_port(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = info_source.argument_text();
 
+    if argument.to_lowercase() == "none" {
+        config_items.audio_ay_port = None;
+        return Ok(());
+    }
+    let port = match util::parse_u32_from_str(&argument) {
+        Some(port) if port < (memory::CAS_MODESEL_BASE as u32) => { port as u16 },
+        _ => { return Err(ConfigError::InvalidAyPort(info_source)); },
+    };
+    let taken = [memory::ORCH_BASE, memory::ORCH_BASE + (memory::ORCH_SIZE - 1), memory::CAS_MODESEL_BASE];
+    if taken.contains(&port) || taken.contains(&(port + 1)) {
+        return Err(ConfigError::InvalidAyPort(info_source));
+    }
+    config_items.audio_ay_port = Some(port as u8);
+    Ok(())
+}
 
-fn new_handler_video_windowed_resolution() -> ConfigEntry {
+fn new_handler_audio_enabled() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; The screen resolution, as WIDTHxHEIGHT, in windowed and full-screen mode.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; The native resolution of the emulator is 512x384 (4:3 aspect ratio),".to_owned());
-    default_text.push("; recommended are multiples of this resolution, like 1024x768.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; I'd advise against 648x480, as it looks quite crummy because of the scaling.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; The fullscreen resolution is only taken into account if the true fullscreen".to_owned());
-    default_text.push("; mode is selected.  In the desktop fullscreen mode, the emulator adapts to".to_owned());
-    default_text.push("; your current screen resolution.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("windowed_resolution = 512x384".to_owned());
+    default_text.push("# Whether to play the signal of the cassette port through the speakers.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# This lets you hear the tapes being loaded and saved, which helps to".to_owned());
+    default_text.push("# tell whether there's anything on the tape at all, as well as the sounds".to_owned());
+    default_text.push("# made by programs that toggle the cassette output.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("enabled = false".to_owned());
+    default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "windowed_resolution".to_owned(),
+        entry_name:   "enabled".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeWindowedResolution,
-        update_line:  update_line_video_windowed_resolution,
-        parse_entry:  parse_entry_video_windowed_resolution,
+        apply_action: ConfigChangeApplyAction::UpdateSoundEnabled,
+        update_line:  update_line_audio_enabled,
+        parse_entry:  parse_entry_audio_enabled,
     }
 }
-fn new_handler_video_fullscreen_resolution() -> ConfigEntry {
+fn new_handler_audio_volume() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-    default_text.push("fullscreen_resolution = 1024x768".to_owned());
+    default_text.push("".to_owned());
+    default_text.push("# The volume of the sound, from 0 to 100.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("volume = 50".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "fullscreen_resolution".to_owned(),
+        entry_name:   "volume".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeFullscreenResolution,
-        update_line:  update_line_video_fullscreen_resolution,
-        parse_entry:  parse_entry_video_fullscreen_resolution,
+        apply_action: ConfigChangeApplyAction::UpdateSoundVolume,
+        update_line:  update_line_audio_volume,
+        parse_entry:  parse_entry_audio_volume,
     }
 }
-fn new_handler_video_bg_color() -> ConfigEntry {
+fn new_handler_audio_sample_rate() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; The colors to use for the screen background and foreground, specified using".to_owned());
-    default_text.push("; the hex (#RRGGBB) format.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; By default, the background is black, #000000, and the foreground is green,".to_owned());
-    default_text.push("; #00FF00; other common choices for the foreground are amber, #FFBF00, and".to_owned());
-    default_text.push("; gray, #A8A8A8.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("bg_color = #000000".to_owned());
+    default_text.push("# The sample rate of the sound output, in Hz (8000 to 96000).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Lower rates take less processing power, but high-pitched sounds suffer.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("sample_rate = 44100".to_owned());
+    default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "bg_color".to_owned(),
+        entry_name:   "sample_rate".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeColor,
-        update_line:  update_line_video_bg_color,
-        parse_entry:  parse_entry_video_bg_color,
+        apply_action: ConfigChangeApplyAction::UpdateSoundOutput,
+        update_line:  update_line_audio_sample_rate,
+        parse_entry:  parse_entry_audio_sample_rate,
     }
 }
-fn new_handler_video_fg_color() -> ConfigEntry {
+fn new_handler_audio_buffer_size() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
-    default_text.push("fg_color = #00FF00".to_owned());
+    default_text.push("".to_owned());
+    default_text.push("# The size of the chunks the sound card is fed in, in samples (a power of".to_owned());
+    default_text.push("# two from 64 to 8192).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# Smaller chunks get the sound out sooner, larger ones are more forgiving of".to_owned());
+    default_text.push("# a busy system.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("buffer_size = 1024".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "fg_color".to_owned(),
+        entry_name:   "buffer_size".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeColor,
-        update_line:  update_line_video_fg_color,
-        parse_entry:  parse_entry_video_fg_color,
+        apply_action: ConfigChangeApplyAction::UpdateSoundOutput,
+        update_line:  update_line_audio_buffer_size,
+        parse_entry:  parse_entry_audio_buffer_size,
     }
 }
-fn new_handler_video_desktop_fullscreen_mode() -> ConfigEntry {
+fn new_handler_audio_latency() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; Use the desktop fullscreen mode (true or false).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; If set to true, the emulator doesn't change the resolution of your screen".to_owned());
-    default_text.push("; when going into full-screen mode, and instead acts as a borderless window".to_owned());
-    default_text.push("; that takes up the whole screen.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("desktop_fullscreen_mode = false".to_owned());
+    default_text.push("# How much sound to keep queued up ahead of the sound card, in milliseconds".to_owned());
+    default_text.push("# (10 to 1000).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# If the sound keeps breaking up, which can be checked with `/sound stats',".to_owned());
+    default_text.push("# raise this value, slow machines and wireless headphones might need a few".to_owned());
+    default_text.push("# hundred milliseconds.  Lower values make the sound follow the emulated".to_owned());
+    default_text.push("# machine more closely.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("latency = 50".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "desktop_fullscreen_mode".to_owned(),
+        entry_name:   "latency".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeFullscreenResolution,
-        update_line:  update_line_video_desktop_fullscreen_mode,
-        parse_entry:  parse_entry_video_desktop_fullscreen_mode,
+        apply_action: ConfigChangeApplyAction::UpdateSoundOutput,
+        update_line:  update_line_audio_latency,
+        parse_entry:  parse_entry_audio_latency,
     }
 }
-fn new_handler_video_use_hw_accel() -> ConfigEntry {
+fn new_handler_audio_resampling() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; Use hardware video acceleration (true or false).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; With video acceleration enabled, the emulator will use your graphics card".to_owned());
-    default_text.push("; to render the screen directly.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; This is mainly useful when not using the emulator's native resolution, as it".to_owned());
-    default_text.push("; allows the GPU to stretch the image, instead of having the CPU stretch it.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("use_hw_accel = false".to_owned());
+    default_text.push("# The quality of the conversion of the machine's signal into samples (low".to_owned());
+    default_text.push("# or high).".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# With the high quality, the signal is averaged over each sample, which keeps".to_owned());
+    default_text.push("# high-pitched sounds clean.  With the low quality, it's only looked at once".to_owned());
+    default_text.push("# per sample, which keeps the waveform sharper, but adds aliasing.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("resampling = \"high\"".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "use_hw_accel".to_owned(),
+        entry_name:   "resampling".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeHwAccelUsage,
-        update_line:  update_line_video_use_hw_accel,
-        parse_entry:  parse_entry_video_use_hw_accel,
+        apply_action: ConfigChangeApplyAction::UpdateSoundOutput,
+        update_line:  update_line_audio_resampling,
+        parse_entry:  parse_entry_audio_resampling,
     }
 }
-fn new_handler_video_use_vsync() -> ConfigEntry {
+fn new_handler_audio_mute_tape() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; Use vertical synchronization (true or false).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; Vith vsync enabled, the screen contents are updated in sync with the screen's".to_owned());
-    default_text.push("; refresh rate.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("use_vsync = false".to_owned());
+    default_text.push("# Whether to mute the sound of the tapes being loaded, that is, the cassette".to_owned());
+    default_text.push("# port signal while the cassette motor runs.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("mute_tape = false".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "use_vsync".to_owned(),
+        entry_name:   "mute_tape".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeVsyncUsage,
-        update_line:  update_line_video_use_vsync,
-        parse_entry:  parse_entry_video_use_vsync,
+        apply_action: ConfigChangeApplyAction::UpdateSoundMixer,
+        update_line:  update_line_audio_mute_tape,
+        parse_entry:  parse_entry_audio_mute_tape,
     }
 }
-fn new_handler_video_character_generator() -> ConfigEntry {
+fn new_handler_audio_mute_program() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Whether to mute the sound made by programs toggling the cassette output".to_owned());
+    default_text.push("# while the cassette motor is off.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("mute_program = false".to_owned());
+    default_text.push("".to_owned());
 
+    ConfigEntry {
+        entry_name:   "mute_program".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateSoundMixer,
+        update_line:  update_line_audio_mute_program,
+        parse_entry:  parse_entry_audio_mute_program,
+    }
+}
+fn new_handler_audio_mute_orchestra() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
     default_text.push("".to_owned());
-    default_text.push("; Character generator to use (1 to 3).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; There are three variants of the character generator commonly found in".to_owned());
-    default_text.push("; a TRS-80 Model I, available for you to choose:".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";     1 - A very old version of the Model I font, found in only a few machines,".to_owned());
-    default_text.push(";         that has standard ASCII [ \\ ] ^ instead of directional arrows.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";         Level II basic puts odd symbols from positions 0-31 onto the screen".to_owned());
-    default_text.push(";         if you enable the lowercase mod.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";     2 - This is the standard Model I character generator found in machines".to_owned());
-    default_text.push(";         without the Radio Shack lowercase modification, including the".to_owned());
-    default_text.push(";         arrows.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";         Just like with the previous character generator, Level II basic".to_owned());
-    default_text.push(";         puts odd symbols onto the screen if you enable the lowercase mod.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";     3 - This is the replacement character generator you got with the".to_owned());
-    default_text.push(";         Radio Shack lowercase mod.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";         Positions 0-31 are a copy of the uppercase letters, to work around".to_owned());
-    default_text.push(";         a bug in the Level II ROM.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";         All characters without descenders are moved up one row.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("character_generator = 2".to_owned());
+    default_text.push("# Whether to mute the output of the Orchestra-85 music card.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("mute_orchestra = false".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "character_generator".to_owned(),
+        entry_name:   "mute_orchestra".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeCharacterGenerator,
-        update_line:  update_line_video_character_generator,
-        parse_entry:  parse_entry_video_character_generator,
+        apply_action: ConfigChangeApplyAction::UpdateSoundMixer,
+        update_line:  update_line_audio_mute_orchestra,
+        parse_entry:  parse_entry_audio_mute_orchestra,
     }
 }
-fn new_handler_video_lowercase_mod() -> ConfigEntry {
+fn new_handler_audio_mute_ay() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
+    default_text.push("".to_owned());
+    default_text.push("# Whether to mute the output of the AY-3-8910 sound board.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("mute_ay = false".to_owned());
+    default_text.push("".to_owned());
 
+    ConfigEntry {
+        entry_name:   "mute_ay".to_owned(),
+        default_text: default_text.into_boxed_slice(),
+        apply_action: ConfigChangeApplyAction::UpdateSoundMixer,
+        update_line:  update_line_audio_mute_ay,
+        parse_entry:  parse_entry_audio_mute_ay,
+    }
+}
+fn new_handler_audio_ay_port() -> ConfigEntry {
+    let mut default_text: Vec<String> = Vec::new();
     default_text.push("".to_owned());
-    default_text.push("; Use the lowercase mod (true or false).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; The original TRS-80 Model I machines lacked the ability to display lowercase".to_owned());
-    default_text.push("; characters, but this could be remedied by a modification.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; It is advised to use character generator 3 with this modification enabled,".to_owned());
-    default_text.push("; as without it, Level II basic puts odd symbols onto the screen instead of".to_owned());
-    default_text.push("; the regular uppercase letters.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("lowercase_mod = false".to_owned());
+    default_text.push("# The IO port of an AY-3-8910 sound board, or none if there's no such board".to_owned());
+    default_text.push("# installed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The board takes up this port, which selects the register of the chip, and".to_owned());
+    default_text.push("# the one after it, which accesses the selected register.  The default is".to_owned());
+    default_text.push("# F8h, where the chip is in the Colour Genie, which the programs written for".to_owned());
+    default_text.push("# its sound expect.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("ay_port = \"F8h\"".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "lowercase_mod".to_owned(),
+        entry_name:   "ay_port".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::ChangeLowercaseModUsage,
-        update_line:  update_line_video_lowercase_mod,
-        parse_entry:  parse_entry_video_lowercase_mod,
+        apply_action: ConfigChangeApplyAction::UpdateSoundBoard,
+        update_line:  update_line_audio_ay_port,
+        parse_entry:  parse_entry_audio_ay_port,
     }
 }
 
-fn new_video_section() -> ConfigSection {
+fn new_audio_section() -> ConfigSection {
     let mut entries: Vec<ConfigEntry> = Vec::new();
 
-    entries.push(new_handler_video_windowed_resolution());
-    entries.push(new_handler_video_fullscreen_resolution());
-    entries.push(new_handler_video_bg_color());
-    entries.push(new_handler_video_fg_color());
-    entries.push(new_handler_video_desktop_fullscreen_mode());
-    entries.push(new_handler_video_use_hw_accel());
-    entries.push(new_handler_video_use_vsync());
-    entries.push(new_handler_video_character_generator());
-    entries.push(new_handler_video_lowercase_mod());
+    entries.push(new_handler_audio_enabled());
+    entries.push(new_handler_audio_volume());
+    entries.push(new_handler_audio_sample_rate());
+    entries.push(new_handler_audio_buffer_size());
+    entries.push(new_handler_audio_latency());
+    entries.push(new_handler_audio_resampling());
+    entries.push(new_handler_audio_mute_tape());
+    entries.push(new_handler_audio_mute_program());
+    entries.push(new_handler_audio_mute_orchestra());
+    entries.push(new_handler_audio_mute_ay());
+    entries.push(new_handler_audio_ay_port());
 
     let obsolete_entries: Vec<String> = Vec::new();
 
     ConfigSection {
-        section_name:     "Video".to_owned(),
+        section_name:     "audio".to_owned(),
         entries:          entries.into_boxed_slice(),
         obsolete_entries: obsolete_entries.into_boxed_slice(),
     }
 }
 
-// The cassette section and entries:
-fn update_line_cassette_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.cassette_file.clone();
+fn update_line_controller_joystick_port(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.controller_joystick_port;
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_cassette_file(info_source, config_items) {
+    let failed_read = match parse_entry_controller_joystick_port(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.cassette_file != new_val {
-        config_items.cassette_file = new_val.clone();
+    if failed_read || config_items.controller_joystick_port != new_val {
+        config_items.controller_joystick_port = new_val;
         match new_val {
-            Some(value) => {
-                Some(format!("file = {}", value))
-            },
-            None => {
-                Some("file = none".to_owned())
-            },
+            Some(port) => { Some(format!("joystick_port = {:02X}h", port)) },
+            None       => { Some("joystick_port = none".to_owned()) },
         }
     } else {
         None
     }
 }
-fn update_line_cassette_file_format(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.cassette_file_format;
+fn update_line_controller_bindings(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
+    let new_val = config_items.controller_bindings.clone();
 
     // Re-parse the entry, to see if it really changed and to see whether
     // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_cassette_file_format(info_source, config_items) {
+    let failed_read = match parse_entry_controller_bindings(info_source, config_items) {
         Ok(..)  => { false },
         Err(..) => { true  },
     };
 
     // Update only if we really need to update:
-    if failed_read || config_items.cassette_file_format != new_val {
-        config_items.cassette_file_format = new_val;
-        match new_val {
-            cassette::Format::CAS => {
-                Some("file_format = CAS".to_owned())
-            },
-            cassette::Format::CPT => {
-                Some("file_format = CPT".to_owned())
-            },
-        }
+    if failed_read || config_items.controller_bindings != new_val {
+        let line = format!("bindings = {}", sdl_controller::controller_bindings_text(&new_val));
+        config_items.controller_bindings = new_val;
+        Some(line)
     } else {
         None
     }
 }
-fn update_line_cassette_file_offset(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Option<String> {
-    let new_val = config_items.cassette_file_offset;
-
-    // Re-parse the entry, to see if it really changed and to see whether
-    // an update really is neccessary.  On failure assume yes.
-    let failed_read = match parse_entry_cassette_file_offset(info_source, config_items) {
-        Ok(..)  => { false },
-        Err(..) => { true  },
-    };
 
-    // Update only if we really need to update:
-    if failed_read || config_items.cassette_file_offset != new_val {
-        config_items.cassette_file_offset = new_val;
-        Some(format!("file_offset = {}", new_val))
-    } else {
-        None
-    }
-}
-fn parse_entry_cassette_file(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+fn parse_entry_controller_joystick_port(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
     let argument = info_source.argument_text();
 
-    if argument.to_uppercase() == "NONE" {
-        config_items.cassette_file = None;
-    } else {
-        config_items.cassette_file = Some(argument);
+    if argument.to_lowercase() == "none" {
+        config_items.controller_joystick_port = None;
+        return Ok(());
     }
-
-    Ok(())
-}
-fn parse_entry_cassette_file_format(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = info_source.argument_text();
-    let compare_str = argument.to_uppercase();
-
-    if compare_str == "CAS" {
-        config_items.cassette_file_format = cassette::Format::CAS;
-        Ok(())
-    } else if compare_str == "CPT" {
-        config_items.cassette_file_format = cassette::Format::CPT;
-        Ok(())
-    } else {
-        Err(ConfigError::InvalidCassetteFormatSpecifier(info_source))
+    let port = match util::parse_u32_from_str(&argument) {
+        Some(port) if port < (memory::CAS_MODESEL_BASE as u32) => { port as u16 },
+        _ => { return Err(ConfigError::InvalidJoystickPort(info_source)); },
+    };
+    if port == memory::ORCH_BASE || port == (memory::ORCH_BASE + (memory::ORCH_SIZE - 1)) {
+        return Err(ConfigError::InvalidJoystickPort(info_source));
     }
+    config_items.controller_joystick_port = Some(port as u8);
+    Ok(())
 }
-fn parse_entry_cassette_file_offset(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
-    let argument = match info_source.argument_text().parse::<usize>() {
-        Ok(result) => { result },
-        Err(error) => { return Err(ConfigError::EntryIntParsingError(info_source, error)); },
+fn parse_entry_controller_bindings(info_source: ConfigInfoSource, config_items: &mut ConfigItems) -> Result<(), ConfigError> {
+    let argument = match sdl_controller::parse_controller_bindings(&info_source.argument_text()) {
+        Some(bindings) => { bindings },
+        None => { return Err(ConfigError::InvalidControllerBindingsSpecifier(info_source)); },
     };
 
-    config_items.cassette_file_offset = argument;
+    config_items.controller_bindings = argument;
     Ok(())
 }
-fn new_handler_cassette_file() -> ConfigEntry {
-    let mut default_text: Vec<String> = Vec::new();
-
-    default_text.push("".to_owned());
-    default_text.push("; The name of the cassette file currently in the tape drive.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; You can either specify a full path to a cassette file, a simple file name".to_owned());
-    default_text.push("; if you want the file to be located in the configuration directory, or the".to_owned());
-    default_text.push("; keyword `none' to leave the tape drive empty.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; If the specified file doesn't exist yet, it will be created.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("file = none".to_owned());
-    default_text.push("".to_owned());
 
-    ConfigEntry {
-        entry_name:   "file".to_owned(),
-        default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::UpdateCassetteFile,
-        update_line:  update_line_cassette_file,
-        parse_entry:  parse_entry_cassette_file,
-    }
-}
-fn new_handler_cassette_file_format() -> ConfigEntry {
+fn new_handler_controller_joystick_port() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; Cassette file format selection (CAS or CPT):".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; Currently, the emulator supports two cassette file formats:".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";     CAS - A file containing the recovered bytes from the cassette.".to_owned());
-    default_text.push(";           It is a fairly compact format, and it's compatible with other".to_owned());
-    default_text.push(";           TRS-80 emulators that have cassette support.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";     CPT - Cassette Pulse Train - A file containing exact values and timing".to_owned());
-    default_text.push(";           (to the nearest microsecond) of the signals the TRS-80 cassette".to_owned());
-    default_text.push(";           routine sends to the cassette output port to be recorded on the".to_owned());
-    default_text.push(";           tape.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push(";           This format, originating from Tim Mann's xtrs emulator, emulates".to_owned());
-    default_text.push(";           a perfect, noise-free cassette, so any cassette routines that even".to_owned());
-    default_text.push(";           halfway worked on real hardware should work with it.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("file_format = CAS".to_owned());
+    default_text.push("# The IO port of a joystick interface like the ones made by Alpha Products".to_owned());
+    default_text.push("# and Big Five Software, or none if there's no such interface installed.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The games supporting a joystick read it at port 00h, which nothing else".to_owned());
+    default_text.push("# uses.  The joystick is moved by the controls of game controllers, or by".to_owned());
+    default_text.push("# the keys of the keyboard, bound to it below or with the `key_bindings'".to_owned());
+    default_text.push("# entry of the `[keyboard]' section.  The port must not be one of the ports".to_owned());
+    default_text.push("# of the AY-3-8910 sound board or of the high resolution graphics board.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("joystick_port = \"00h\"".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "file_format".to_owned(),
+        entry_name:   "joystick_port".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::UpdateCassetteFileFormat,
-        update_line:  update_line_cassette_file_format,
-        parse_entry:  parse_entry_cassette_file_format,
+        apply_action: ConfigChangeApplyAction::UpdateJoystick,
+        update_line:  update_line_controller_joystick_port,
+        parse_entry:  parse_entry_controller_joystick_port,
     }
 }
-fn new_handler_cassette_file_offset() -> ConfigEntry {
+fn new_handler_controller_bindings() -> ConfigEntry {
     let mut default_text: Vec<String> = Vec::new();
-
     default_text.push("".to_owned());
-    default_text.push("; Current byte offset into the cassette.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; This value indicates how far the cassette is currently wound past the".to_owned());
-    default_text.push("; beginning, in bytes.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; The cassette can be rewound to arbitrary locations, and thus several different".to_owned());
-    default_text.push("; records/files can be recorded and later loaded from a single cassette, as long".to_owned());
-    default_text.push("; as you keep track of where the different records/files are located, or request".to_owned());
-    default_text.push("; files based on their filename (Level II BASIC).  See `/help cassette' in the".to_owned());
-    default_text.push("; curses-based user interface for more details.".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("; Please note that if a format like CPT is used, setting the offset to arbitrary".to_owned());
-    default_text.push("; locations might cause the data to be incorrectly parsed (since it may easily".to_owned());
-    default_text.push("; get out of alignment), it is therefore advised to only explicitly set this".to_owned());
-    default_text.push("; parameters to known-good values (ie. the beginning and end locations of the".to_owned());
-    default_text.push("; individual records/files on the tape).".to_owned());
-    default_text.push(";".to_owned());
-    default_text.push("file_offset = 0".to_owned());
+    default_text.push("# Changes to what the controls of game controllers do, as a comma-separated".to_owned());
+    default_text.push("# list of `<control>:<target>' pairs, or `none' to keep the default layout.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# The buttons are named as in the Button enum of SDL2, `A', `B', `X', `Y',".to_owned());
+    default_text.push("# `Back', `Guide', `Start', `LeftStick', `RightStick', `LeftShoulder',".to_owned());
+    default_text.push("# `RightShoulder', `DPadUp', `DPadDown', `DPadLeft' and `DPadRight', the".to_owned());
+    default_text.push("# directions of the sticks are `LeftX-', `LeftX+', `LeftY-', `LeftY+',".to_owned());
+    default_text.push("# `RightX-', `RightX+', `RightY-' and `RightY+', and the triggers are".to_owned());
+    default_text.push("# `TriggerLeft' and `TriggerRight'.  The targets are the keys of the machine,".to_owned());
+    default_text.push("# named as in the `key_bindings' entry of the `[keyboard]' section, the".to_owned());
+    default_text.push("# joystick, `joy_up', `joy_down', `joy_left', `joy_right' and `joy_fire', the".to_owned());
+    default_text.push("# `turbo', the `warp', `slow_motion', `frame_advance', or `none' to make the".to_owned());
+    default_text.push("# control do nothing.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# By default, the d-pad and the left stick are the arrow keys, A and B are".to_owned());
+    default_text.push("# the space bar, Start is ENTER and Back is BREAK.".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("# For example, to play with the joystick instead of the arrow keys:".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("#   bindings = \"DPadUp:joy_up, DPadDown:joy_down, DPadLeft:joy_left, DPadRight:joy_right, LeftY-:joy_up, LeftY+:joy_down, LeftX-:joy_left, LeftX+:joy_right, A:joy_fire\"".to_owned());
+    default_text.push("#".to_owned());
+    default_text.push("bindings = \"none\"".to_owned());
     default_text.push("".to_owned());
 
     ConfigEntry {
-        entry_name:   "file_offset".to_owned(),
+        entry_name:   "bindings".to_owned(),
         default_text: default_text.into_boxed_slice(),
-        apply_action: ConfigChangeApplyAction::UpdateCassetteFileOffset,
-        update_line:  update_line_cassette_file_offset,
-        parse_entry:  parse_entry_cassette_file_offset,
+        apply_action: ConfigChangeApplyAction::UpdateControllerBindings,
+        update_line:  update_line_controller_bindings,
+        parse_entry:  parse_entry_controller_bindings,
     }
 }
-fn new_cassette_section() -> ConfigSection {
-    let mut entries: Vec<ConfigEntry> = Vec::new();
 
-    entries.push(new_handler_cassette_file());
-    entries.push(new_handler_cassette_file_format());
-    entries.push(new_handler_cassette_file_offset());
+fn new_controller_section() -> ConfigSection {
+    let mut entries: Vec<ConfigEntry> = Vec::new();
 
-    let mut obsolete_entries: Vec<String> = Vec::new();
+    entries.push(new_handler_controller_joystick_port());
+    entries.push(new_handler_controller_bindings());
 
-    obsolete_entries.push("input_cassette".to_owned());
-    obsolete_entries.push("output_cassette".to_owned());
-    obsolete_entries.push("input_cassette_format".to_owned());
-    obsolete_entries.push("output_cassette_format".to_owned());
+    let obsolete_entries: Vec<String> = Vec::new();
 
     ConfigSection {
-        section_name:    "Cassette".to_owned(),
+        section_name:     "controller".to_owned(),
         entries:          entries.into_boxed_slice(),
         obsolete_entries: obsolete_entries.into_boxed_slice(),
     }
@@ -0,0 +1,245 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, warn};
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::net;
+use std::path;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+
+// The remote control lets other programs drive the emulator over a socket,
+// with the same commands as the curses-based user interface, a line each.
+//
+// Every message of the emulator is sent to all of the connected clients as
+// a line starting with `* ', and once the logic core has carried out the
+// commands a client sent before, it gets a line saying `ok', so it knows
+// that the messages about them came already.
+//
+// The socket is either a TCP port, which only listens on the loopback
+// interface, a TCP address with the host given, or the path of a Unix
+// socket.
+//
+// Whoever connects can use all of the commands, including the ones which
+// write files on the host.  The Unix socket is kept safe by its permissions,
+// but any program can connect to a TCP port, even a web page, through the
+// browser.  So the first line sent over TCP has to be `token <token>', with
+// the token the emulator writes into the `remote.token' file of the
+// configuration directory when it starts, and a connection starting with a
+// HTTP request is dropped right away.  A TCP address which isn't on the
+// loopback interface is refused, unless it's explicitly asked for with
+// `--remote-public'.
+//
+
+// A client which doesn't take the lines for this long is dropped, rather
+// than holding up the user interface.
+const WRITE_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+const TOKEN_FILE_NAME: &str = "remote.token";
+
+enum RemoteEvent {
+    Connected(usize, Box<dyn Write + Send>),
+    Line(usize, String),
+    Closed(usize),
+}
+
+pub struct RemoteControl {
+    event_rx:    mpsc::Receiver<RemoteEvent>,
+    clients:     Vec<(usize, Box<dyn Write + Send>)>,
+    socket_path: Option<path::PathBuf>,    // Removed when done.
+    token_path:  Option<path::PathBuf>,    // Likewise.
+}
+
+impl RemoteControl {
+    pub fn new(address: &str, public: bool, config_dir: &path::Path) -> io::Result<RemoteControl> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let token_path = config_dir.join(TOKEN_FILE_NAME);
+
+        let socket_path = if let Ok(port) = address.parse::<u16>() {
+            let listener = net::TcpListener::bind((net::Ipv4Addr::LOCALHOST, port))?;
+            RemoteControl::accept_tcp(listener, RemoteControl::write_token(&token_path)?, event_tx);
+            None
+        } else if address.contains(':') && !address.contains('/') {
+            let socket_addresses: Vec<net::SocketAddr> = net::ToSocketAddrs::to_socket_addrs(address)?.collect();
+            if socket_addresses.iter().any(|socket_address| !socket_address.ip().is_loopback()) {
+                if !public {
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, "the address isn't on the loopback interface, which needs `--remote-public'"));
+                }
+                warn!("The remote commands on `{}' can come from other computers, and the token goes over the network unencrypted, anyone who can see it can use all of the commands, including the ones writing files.", address);
+            }
+            let listener = net::TcpListener::bind(&socket_addresses[..])?;
+            RemoteControl::accept_tcp(listener, RemoteControl::write_token(&token_path)?, event_tx);
+            None
+        } else {
+            RemoteControl::accept_unix(path::Path::new(address), event_tx)?;
+            Some(path::PathBuf::from(address))
+        };
+
+        Ok(RemoteControl {
+            event_rx,
+            clients: Vec::new(),
+            token_path: if socket_path.is_none() { Some(token_path) } else { None },
+            socket_path,
+        })
+    }
+    // Make up a token nobody else can guess, from the random keys which the
+    // standard library seeds its hash maps with, and write it into the file
+    // only the user can read.
+    fn write_token(token_path: &path::Path) -> io::Result<String> {
+        let mut token = String::new();
+        for _ in 0..2 {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(time::SystemTime::now().duration_since(time::UNIX_EPOCH).map(|elapsed| elapsed.as_nanos()).unwrap_or(0));
+            token.push_str(&format!("{:016x}", hasher.finish()));
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        writeln!(options.open(token_path)?, "{}", token)?;
+        info!("The token of the remote control is in `{}'.", token_path.display());
+        Ok(token)
+    }
+    fn accept_tcp(listener: net::TcpListener, token: String, event_tx: mpsc::Sender<RemoteEvent>) {
+        thread::Builder::new().name("remote_control".to_owned()).spawn(move || {
+            for (client, stream) in listener.incoming().flatten().enumerate() {
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                if let Ok(writer) = stream.try_clone() {
+                    RemoteControl::serve_client(client, stream, Box::new(writer), Some(token.clone()), &event_tx);
+                }
+            }
+        }).unwrap();
+    }
+    #[cfg(unix)]
+    fn accept_unix(socket_path: &path::Path, event_tx: mpsc::Sender<RemoteEvent>) -> io::Result<()> {
+        let listener = std::os::unix::net::UnixListener::bind(socket_path)?;
+        thread::Builder::new().name("remote_control".to_owned()).spawn(move || {
+            for (client, stream) in listener.incoming().flatten().enumerate() {
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                if let Ok(writer) = stream.try_clone() {
+                    RemoteControl::serve_client(client, stream, Box::new(writer), None, &event_tx);
+                }
+            }
+        }).unwrap();
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    fn accept_unix(_socket_path: &path::Path, _event_tx: mpsc::Sender<RemoteEvent>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets aren't available on this system, use a TCP port"))
+    }
+    // Read the lines of a client on a thread of its own, once it sent the
+    // token, if there's one.
+    fn serve_client<R: io::Read + Send + 'static>(client: usize, reader: R, mut writer: Box<dyn Write + Send>, token: Option<String>, event_tx: &mpsc::Sender<RemoteEvent>) {
+        let event_tx = event_tx.clone();
+        thread::Builder::new().name(format!("remote_client_{}", client)).spawn(move || {
+            let mut lines = io::BufReader::new(reader).lines();
+            if let Some(token) = token {
+                let first_line = match lines.next() {
+                    Some(Ok(line)) => { line },
+                    _ => { return; },
+                };
+                if looks_like_http(&first_line) {
+                    warn!("Dropped a remote control connection which sent a HTTP request, maybe from a web page.");
+                    return;
+                }
+                if first_line.trim().strip_prefix("token ").map(|sent| sent.trim()) != Some(token.as_str()) {
+                    warn!("Dropped a remote control connection which didn't start with the token of `{}'.", TOKEN_FILE_NAME);
+                    let _ = writeln!(writer, "error: the first line has to be `token <token>', with the token of the `{}' file", TOKEN_FILE_NAME);
+                    return;
+                }
+                if writeln!(writer, "ok").and_then(|_| writer.flush()).is_err() {
+                    return;
+                }
+            }
+            if event_tx.send(RemoteEvent::Connected(client, writer)).is_err() {
+                return;
+            }
+            for line in lines {
+                match line {
+                    Ok(line) => {
+                        if event_tx.send(RemoteEvent::Line(client, line)).is_err() {
+                            return;
+                        }
+                    },
+                    Err(..) => { break; },
+                }
+            }
+            let _ = event_tx.send(RemoteEvent::Closed(client));
+        }).unwrap();
+    }
+    // The commands sent since the last call, along with the clients which
+    // sent them.
+    pub fn take_commands(&mut self) -> Vec<(usize, String)> {
+        let mut commands = Vec::new();
+
+        for event in self.event_rx.try_iter() {
+            match event {
+                RemoteEvent::Connected(client, writer) => {
+                    self.clients.push((client, writer));
+                },
+                RemoteEvent::Line(client, line) => {
+                    commands.push((client, line));
+                },
+                RemoteEvent::Closed(client) => {
+                    self.clients.retain(|(connected, _)| *connected != client);
+                },
+            }
+        }
+        commands
+    }
+    pub fn send_message(&mut self, message: &str) {
+        self.send_line(None, &format!("* {}", message));
+    }
+    pub fn send_done(&mut self, client: usize) {
+        self.send_line(Some(client), "ok");
+    }
+    // Send a line to one client, or to all of them, the ones which can't
+    // take it any more are dropped.
+    fn send_line(&mut self, to_client: Option<usize>, line: &str) {
+        self.clients.retain_mut(|(client, writer)| {
+            if to_client.is_some() && to_client != Some(*client) {
+                return true;
+            }
+            writeln!(writer, "{}", line).and_then(|_| writer.flush()).is_ok()
+        });
+    }
+}
+
+impl Drop for RemoteControl {
+    fn drop(&mut self) {
+        if let Some(socket_path) = &self.socket_path {
+            let _ = fs::remove_file(socket_path);
+        }
+        if let Some(token_path) = &self.token_path {
+            let _ = fs::remove_file(token_path);
+        }
+    }
+}
+
+// Whether the line is the start of a HTTP request, like the ones a browser
+// sends, which can't be told to send the token first.
+fn looks_like_http(line: &str) -> bool {
+    const METHODS: [&str; 8] = ["GET ", "HEAD ", "POST ", "PUT ", "DELETE ", "CONNECT ", "OPTIONS ", "PATCH "];
+    line.contains(" HTTP/") || METHODS.iter().any(|method| line.starts_with(method))
+}
@@ -0,0 +1,380 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::info;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::mem;
+use std::path;
+use std::rc::Rc;
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Scope, AST, INT};
+
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::keyboard;
+use trs80m1_rs_core::machine;
+use trs80m1_rs_core::memory;
+use trs80m1_rs_core::memory::{MemIO, MemoryChip};
+use trs80m1_rs_core::z80::cpu;
+use crate::emulator::{EmulatorCommand, EmulatorCassetteCommand};
+
+
+// Scripts in the Rhai language, for the trainers of games, the automated
+// tests of programs and the recordings of demos, run in the logic core with
+// `/rhai run' and `/rhai eval'.  Besides what the language has, they get:
+//
+//   peek(address), poke(address, value)      - the memory, as the CPU sees it
+//   reg(name), set_reg(name, value)          - a, f, b, c, d, e, h, l, af, bc,
+//                                              de, hl, ix, iy, sp, pc, i, r
+//   cycles()                                 - the T-states run so far
+//   on_breakpoint(address, fn), clear_breakpoint(address)
+//   on_frame(fn), clear_frame()              - called with each video frame
+//   type_text(text), key(name)               - clear, break, up, down, left,
+//                                              right
+//   cassette_insert(file), cassette_rewind(), cassette_eject(), open(file)
+//   pause(), unpause(), reset(), screenshot()
+//   log(text)                                - the same as print(text)
+//
+// The callbacks are called with the machine stopped right before the
+// instruction at the breakpoint, or at the end of the slice the frame ended
+// in.  The script sees a copy of the machine which is taken before each
+// call, the changes of the memory and the registers are put into the
+// machine after it, and the commands are carried out after it too.
+//
+// A script which runs for too long, like in an endless loop, is stopped
+// after this many operations of the language, so the emulator doesn't hang:
+//
+pub const MAX_OPERATIONS: u64 = 10_000_000;
+
+// The machine, as lent to the functions of the script.
+struct Lent {
+    memory:         Vec<u8>,
+    writes:         Vec<(u16, u8)>,
+    regs:           cpu::Z80Regs,
+    regs_changed:   bool,
+    cycles:         u64,
+    commands:       Vec<EmulatorCommand>,
+    breakpoints:    HashMap<u16, FnPtr>,
+    breakpoint_map: Vec<bool>,      // Whether there's one at the address.
+    on_frame:       Option<FnPtr>,
+}
+
+pub struct Script {
+    engine:     Engine,
+    functions:  AST,                // Of the scripts run so far.
+    scope:      Scope<'static>,     // Their global variables.
+    lent:       Rc<RefCell<Lent>>,
+    last_frame: u64,
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+impl Script {
+    pub fn new() -> Script {
+        let lent = Rc::new(RefCell::new(Lent {
+            memory:         vec![0xFF; 0x10000],
+            writes:         Vec::new(),
+            regs:           cpu::Z80Regs::default(),
+            regs_changed:   false,
+            cycles:         0,
+            commands:       Vec::new(),
+            breakpoints:    HashMap::new(),
+            breakpoint_map: vec![false; 0x10000],
+            on_frame:       None,
+        }));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| { info!("{}", text); });
+        engine.on_debug(|text, _source, _position| { info!("{}", text); });
+        register_functions(&mut engine, &lent);
+
+        Script {
+            engine,
+            functions:  AST::empty(),
+            scope:      Scope::new(),
+            lent,
+            last_frame: 0,
+        }
+    }
+    // Run the script in the file, its functions and global variables are
+    // kept for the ones run after it, and for `/rhai eval'.
+    pub fn run(&mut self, file_path: &path::Path, machine: &mut machine::Machine) -> Result<Vec<EmulatorCommand>, String> {
+        let ast = self.engine.compile_file(file_path.to_path_buf()).map_err(|error| error.to_string())?;
+        self.execute(ast, machine).map(|(_, commands)| commands)
+    }
+    // Evaluate a piece of code, with the value of its last expression, if
+    // it's got one.
+    pub fn eval(&mut self, code: &str, machine: &mut machine::Machine) -> Result<(Option<String>, Vec<EmulatorCommand>), String> {
+        let ast = self.engine.compile(code).map_err(|error| error.to_string())?;
+        self.execute(ast, machine)
+    }
+    fn execute(&mut self, ast: AST, machine: &mut machine::Machine) -> Result<(Option<String>, Vec<EmulatorCommand>), String> {
+        let ast = self.functions.merge(&ast);
+        self.functions = ast.clone_functions_only();
+
+        self.lend(machine);
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut self.scope, &ast);
+        let commands = self.take_back(machine);
+        match result {
+            Ok(value) if value.is_unit() => { Ok((None, commands)) },
+            Ok(value) => { Ok((Some(value.to_string()), commands)) },
+            Err(error) => { Err(error.to_string()) },
+        }
+    }
+    pub fn breakpoint_at(&self, address: u16) -> bool {
+        self.lent.borrow().breakpoint_map[address as usize]
+    }
+    // Call the callback of the breakpoint at the program counter.
+    pub fn breakpoint(&mut self, machine: &mut machine::Machine) -> Result<Vec<EmulatorCommand>, String> {
+        let callback = self.lent.borrow().breakpoints.get(&machine.cpu.regs.pc).cloned();
+        match callback {
+            Some(callback) => { self.call(callback, machine) },
+            None => { Ok(Vec::new()) },
+        }
+    }
+    // Call the callback of the frames, if a video frame ended since it was
+    // called the last time.
+    pub fn frame(&mut self, machine: &mut machine::Machine) -> Result<Vec<EmulatorCommand>, String> {
+        let frame = machine.cycles() / (machine::CPU_HZ / machine::FRAME_RATE) as u64;
+        if frame == self.last_frame {
+            return Ok(Vec::new());
+        }
+        self.last_frame = frame;
+        let callback = self.lent.borrow().on_frame.clone();
+        match callback {
+            Some(callback) => { self.call(callback, machine) },
+            None => { Ok(Vec::new()) },
+        }
+    }
+    fn call(&mut self, callback: FnPtr, machine: &mut machine::Machine) -> Result<Vec<EmulatorCommand>, String> {
+        self.lend(machine);
+        let result = callback.call::<Dynamic>(&self.engine, &self.functions, ());
+        let commands = self.take_back(machine);
+        match result {
+            Ok(_) => { Ok(commands) },
+            Err(error) => { Err(error.to_string()) },
+        }
+    }
+    // Copy the machine for the functions of the script.
+    fn lend(&self, machine: &mut machine::Machine) {
+        let mut lent = self.lent.borrow_mut();
+        let memory_system = &mut machine.memory_system;
+
+        let rom = memory_system.rom_chip.chip_data();
+        lent.memory[memory::ROM_BASE as usize..][..rom.len()].copy_from_slice(rom);
+        for address in (memory::ROM_BASE + memory::ROM_SIZE)..memory::RAM_BASE {
+            lent.memory[address as usize] = memory_system.peek_byte(address);
+        }
+        let ram = memory_system.ram_chip.chip_data();
+        lent.memory[memory::RAM_BASE as usize..][..ram.len()].copy_from_slice(ram);
+
+        lent.regs = machine.cpu.regs.clone();
+        lent.regs_changed = false;
+        lent.cycles = machine.cycles();
+    }
+    // Put the changes the script made into the machine, and hand over the
+    // commands it gave.
+    fn take_back(&self, machine: &mut machine::Machine) -> Vec<EmulatorCommand> {
+        let mut lent = self.lent.borrow_mut();
+        for (address, value) in lent.writes.drain(..) {
+            machine.memory_system.write_byte(address, value);
+        }
+        if lent.regs_changed {
+            machine.cpu.regs = lent.regs.clone();
+        }
+        mem::take(&mut lent.commands)
+    }
+}
+
+fn register_functions(engine: &mut Engine, lent: &Rc<RefCell<Lent>>) {
+    let shared = lent.clone();
+    engine.register_fn("peek", move |address: INT| -> ScriptResult<INT> {
+        Ok(shared.borrow().memory[address_arg(address)? as usize] as INT)
+    });
+    let shared = lent.clone();
+    engine.register_fn("poke", move |address: INT, value: INT| -> ScriptResult<()> {
+        let (address, value) = (address_arg(address)?, byte_arg(value)?);
+        let mut lent = shared.borrow_mut();
+        lent.memory[address as usize] = value;
+        lent.writes.push((address, value));
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("reg", move |name: &str| -> ScriptResult<INT> {
+        Ok(get_register(&shared.borrow().regs, name)? as INT)
+    });
+    let shared = lent.clone();
+    engine.register_fn("set_reg", move |name: &str, value: INT| -> ScriptResult<()> {
+        let mut lent = shared.borrow_mut();
+        set_register(&mut lent.regs, name, value)?;
+        lent.regs_changed = true;
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("cycles", move || -> INT {
+        shared.borrow().cycles as INT
+    });
+    let shared = lent.clone();
+    engine.register_fn("on_breakpoint", move |address: INT, callback: FnPtr| -> ScriptResult<()> {
+        let address = address_arg(address)?;
+        let mut lent = shared.borrow_mut();
+        lent.breakpoints.insert(address, callback);
+        lent.breakpoint_map[address as usize] = true;
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("clear_breakpoint", move |address: INT| -> ScriptResult<()> {
+        let address = address_arg(address)?;
+        let mut lent = shared.borrow_mut();
+        lent.breakpoints.remove(&address);
+        lent.breakpoint_map[address as usize] = false;
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("on_frame", move |callback: FnPtr| {
+        shared.borrow_mut().on_frame = Some(callback);
+    });
+    let shared = lent.clone();
+    engine.register_fn("clear_frame", move || {
+        shared.borrow_mut().on_frame = None;
+    });
+    let shared = lent.clone();
+    engine.register_fn("type_text", move |text: &str| {
+        shared.borrow_mut().commands.push(EmulatorCommand::TypeText(text.to_owned()));
+    });
+    let shared = lent.clone();
+    engine.register_fn("key", move |name: &str| -> ScriptResult<()> {
+        let key = special_key(name).ok_or_else(|| format!("there's no key named `{}'", name))?;
+        shared.borrow_mut().commands.push(EmulatorCommand::TypeSpecialKey(key));
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("cassette_insert", move |file: &str| -> ScriptResult<()> {
+        let format = cassette::Format::from_path(path::Path::new(file)).ok_or_else(|| format!("the format of `{}' isn't known by its extension", file))?;
+        shared.borrow_mut().commands.push(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Insert { format, file: file.to_owned(), auto_load: false }));
+        Ok(())
+    });
+    let shared = lent.clone();
+    engine.register_fn("cassette_rewind", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Rewind));
+    });
+    let shared = lent.clone();
+    engine.register_fn("cassette_eject", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::CassetteCommand(EmulatorCassetteCommand::Eject));
+    });
+    let shared = lent.clone();
+    engine.register_fn("open", move |file: &str| {
+        shared.borrow_mut().commands.push(EmulatorCommand::OpenFile(file.to_owned()));
+    });
+    let shared = lent.clone();
+    engine.register_fn("pause", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::Pause);
+    });
+    let shared = lent.clone();
+    engine.register_fn("unpause", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::Unpause);
+    });
+    let shared = lent.clone();
+    engine.register_fn("reset", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::ResetSoft);
+    });
+    let shared = lent.clone();
+    engine.register_fn("screenshot", move || {
+        shared.borrow_mut().commands.push(EmulatorCommand::Screenshot(None));
+    });
+    engine.register_fn("log", |text: &str| {
+        info!("{}", text);
+    });
+}
+
+fn address_arg(address: INT) -> ScriptResult<u16> {
+    u16::try_from(address).map_err(|_| format!("the address {} is out of range", address).into())
+}
+
+fn byte_arg(value: INT) -> ScriptResult<u8> {
+    u8::try_from(value).map_err(|_| format!("the value {} doesn't fit into a byte", value).into())
+}
+
+fn special_key(name: &str) -> Option<keyboard::SpecialKey> {
+    match name.to_lowercase().as_str() {
+        "clear" => { Some(keyboard::SpecialKey::Clear) },
+        "break" => { Some(keyboard::SpecialKey::Break) },
+        "up"    => { Some(keyboard::SpecialKey::Up) },
+        "down"  => { Some(keyboard::SpecialKey::Down) },
+        "left"  => { Some(keyboard::SpecialKey::Left) },
+        "right" => { Some(keyboard::SpecialKey::Right) },
+        _       => { None },
+    }
+}
+
+fn get_register(regs: &cpu::Z80Regs, name: &str) -> ScriptResult<u16> {
+    let value = match name.to_lowercase().as_str() {
+        "a"  => { regs.a as u16 },
+        "f"  => { regs.flags.to_byte() as u16 },
+        "b"  => { regs.bc >> 8 },
+        "c"  => { regs.bc & 0xFF },
+        "d"  => { regs.de >> 8 },
+        "e"  => { regs.de & 0xFF },
+        "h"  => { regs.hl >> 8 },
+        "l"  => { regs.hl & 0xFF },
+        "af" => { ((regs.a as u16) << 8) | regs.flags.to_byte() as u16 },
+        "bc" => { regs.bc },
+        "de" => { regs.de },
+        "hl" => { regs.hl },
+        "ix" => { regs.ix },
+        "iy" => { regs.iy },
+        "sp" => { regs.sp },
+        "pc" => { regs.pc },
+        "i"  => { regs.i as u16 },
+        "r"  => { regs.r as u16 },
+        _    => { return Err(format!("there's no register named `{}'", name).into()); },
+    };
+    Ok(value)
+}
+
+fn set_register(regs: &mut cpu::Z80Regs, name: &str, value: INT) -> ScriptResult<()> {
+    let name = name.to_lowercase();
+    let wide = matches!(name.as_str(), "af" | "bc" | "de" | "hl" | "ix" | "iy" | "sp" | "pc");
+    let value = if wide { address_arg(value)? } else { byte_arg(value)? as u16 };
+    let high = |pair: u16| { (pair & 0x00FF) | (value << 8) };
+    let low = |pair: u16| { (pair & 0xFF00) | value };
+    match name.as_str() {
+        "a"  => { regs.a = value as u8; },
+        "f"  => { regs.flags = cpu::Z80Flags::from_byte(value as u8); },
+        "b"  => { regs.bc = high(regs.bc); },
+        "c"  => { regs.bc = low(regs.bc); },
+        "d"  => { regs.de = high(regs.de); },
+        "e"  => { regs.de = low(regs.de); },
+        "h"  => { regs.hl = high(regs.hl); },
+        "l"  => { regs.hl = low(regs.hl); },
+        "af" => {
+            regs.a = (value >> 8) as u8;
+            regs.flags = cpu::Z80Flags::from_byte(value as u8);
+        },
+        "bc" => { regs.bc = value; },
+        "de" => { regs.de = value; },
+        "hl" => { regs.hl = value; },
+        "ix" => { regs.ix = value; },
+        "iy" => { regs.iy = value; },
+        "sp" => { regs.sp = value; },
+        "pc" => { regs.pc = value; },
+        "i"  => { regs.i = value as u8; },
+        "r"  => { regs.r = value as u8; },
+        _    => { return Err(format!("there's no register named `{}'", name).into()); },
+    }
+    Ok(())
+}
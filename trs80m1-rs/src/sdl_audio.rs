@@ -0,0 +1,190 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, warn, error};
+
+use std::time;
+
+
+// Playback of the sound generated by the emulated machine:
+//
+// The samples arrive in batches, as the emulation runs, and are put into an
+// SDL2 audio queue.  To keep the sound in step with the emulation, the queue
+// is kept at around the configured latency worth of sound:
+//
+//  - When the queue runs dry (at the start, after a pause, or when the
+//    emulation falls behind), it's primed with silence first, so that the
+//    sound card has something to play while the next batch is generated.
+//
+//  - When the emulation gets ahead of the sound card, the batches are
+//    squeezed to fit under MAX_LATENCY_FACTOR times the latency, instead of
+//    being thrown away wholesale, which would leave gaps in the sound.
+//
+// The queue running dry while batches keep coming in is an underrun, which
+// is heard as the sound breaking up.  Those, along with the squeezed out
+// samples, are counted, so that the latency can be tuned.
+//
+const MAX_LATENCY_FACTOR: usize = 4;
+
+// If no batch arrived for this long, the emulation was paused or stopped
+// making sound, and the queue running dry isn't an underrun.
+const UNDERRUN_WINDOW_MS: u64 = 500;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct AudioSettings {
+    pub enabled:      bool,
+    pub volume:       u32,   // In percent.
+    pub sample_rate:  u32,   // In Hz.
+    pub buffer_size:  u32,   // In samples.
+    pub latency:      u32,   // In milliseconds.
+}
+
+#[derive(Default)]
+struct AudioStatistics {
+    batches:     u64,
+    underruns:   u64,
+    dropped:     u64,
+    max_queued:  usize,
+}
+
+pub struct SdlAudio {
+    queue:       Option<sdl2::audio::AudioQueue<i16>>,
+    settings:    Option<AudioSettings>,
+    last_batch:  Option<time::Instant>,
+    statistics:  AudioStatistics,
+}
+
+impl Default for SdlAudio {
+    fn default() -> SdlAudio {
+        SdlAudio::new()
+    }
+}
+
+impl SdlAudio {
+    pub fn new() -> SdlAudio {
+        SdlAudio {
+            queue:      None,
+            settings:   None,
+            last_batch: None,
+            statistics: AudioStatistics::default(),
+        }
+    }
+    pub fn set_output(&mut self, sdl_ctxt: &sdl2::Sdl, settings: AudioSettings) {
+        let reopen = match self.settings {
+            Some(old) => { old.sample_rate != settings.sample_rate || old.buffer_size != settings.buffer_size },
+            None => { true },
+        };
+        self.settings = Some(settings);
+
+        if !settings.enabled || reopen {
+            self.queue = None;
+        }
+        if settings.enabled && self.queue.is_none() {
+            let spec = sdl2::audio::AudioSpecDesired {
+                freq:     Some(settings.sample_rate as i32),
+                channels: Some(1),
+                samples:  Some(settings.buffer_size as u16),
+            };
+            let queue = sdl_ctxt.audio().and_then(|audio_ctxt| audio_ctxt.open_queue::<i16, _>(None, &spec));
+
+            match queue {
+                Ok(queue) => {
+                    queue.resume();
+                    self.queue = Some(queue);
+                    self.last_batch = None;
+                    self.statistics = AudioStatistics::default();
+                },
+                Err(error) => {
+                    error!("Failed to open the SDL2 audio device: {}.", error);
+                },
+            }
+        }
+    }
+    pub fn play(&mut self, samples: Vec<i16>) {
+        let (queue, settings) = match (&self.queue, self.settings) {
+            (Some(queue), Some(settings)) => { (queue, settings) },
+            _ => { return; },
+        };
+        let target = latency_samples(&settings);
+        let mut queued = (queue.size() as usize) / 2;
+
+        let now = time::Instant::now();
+        if queued == 0 {
+            if let Some(last_batch) = self.last_batch {
+                if now.duration_since(last_batch) < time::Duration::from_millis(UNDERRUN_WINDOW_MS) {
+                    self.statistics.underruns += 1;
+                }
+            }
+            if let Err(error) = queue.queue_audio(&vec![0; target]) {
+                warn!("Failed to queue sound samples: {}.", error);
+            }
+            queued = target;
+        }
+        self.last_batch = Some(now);
+        self.statistics.batches += 1;
+        self.statistics.max_queued = self.statistics.max_queued.max(queued);
+
+        let room = (target * MAX_LATENCY_FACTOR).saturating_sub(queued);
+        let samples = if samples.len() > room {
+            self.statistics.dropped += (samples.len() - room) as u64;
+            squeeze(&samples, room)
+        } else {
+            samples
+        };
+
+        let scaled: Vec<i16> = samples.iter().map(|sample| (((*sample as i32) * (settings.volume as i32)) / 100) as i16).collect();
+        if let Err(error) = queue.queue_audio(&scaled) {
+            warn!("Failed to queue sound samples: {}.", error);
+        }
+    }
+    pub fn report_statistics(&self) {
+        let (queue, settings) = match (&self.queue, self.settings) {
+            (Some(queue), Some(settings)) => { (queue, settings) },
+            _ => {
+                info!("The sound output is disabled.");
+                return;
+            },
+        };
+        let to_ms = |samples: usize| { (samples * 1000) / (settings.sample_rate as usize) };
+        let queued = (queue.size() as usize) / 2;
+
+        info!("Sound output statistics:");
+        info!("");
+        info!("    Sample rate:         {} Hz", settings.sample_rate);
+        info!("    Buffer size:         {} samples ({} ms)", settings.buffer_size, to_ms(settings.buffer_size as usize));
+        info!("    Target latency:      {} ms", settings.latency);
+        info!("    Currently queued:    {} ms", to_ms(queued));
+        info!("    Most queued:         {} ms", to_ms(self.statistics.max_queued));
+        info!("    Batches played:      {}", self.statistics.batches);
+        info!("    Underruns:           {}", self.statistics.underruns);
+        info!("    Dropped samples:     {}", self.statistics.dropped);
+        info!("");
+        if self.statistics.underruns > 0 {
+            info!("If the sound breaks up, try raising the `latency' or `buffer_size' entries in the audio section of the config file.");
+        }
+    }
+}
+
+// The configured latency in samples, which can't be less than what the sound
+// card takes at once:
+fn latency_samples(settings: &AudioSettings) -> usize {
+    (((settings.sample_rate as usize) * (settings.latency as usize)) / 1000).max(settings.buffer_size as usize)
+}
+
+// Shorten a batch of samples to the given length, by dropping samples evenly
+// across it.
+fn squeeze(samples: &[i16], length: usize) -> Vec<i16> {
+    (0..length).map(|index| samples[(index * samples.len()) / length]).collect()
+}
@@ -0,0 +1,227 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::{info, warn};
+use sdl2::controller::{Axis, Button};
+
+use std::collections::HashMap;
+
+use crate::sdl_keyboard;
+
+
+// Game controllers are supported through the game controller API of SDL2,
+// which gives every controller it knows the same layout, that of an XBox
+// controller.  Each button, and each direction of each stick, acts as a key
+// of the keyboard of the machine, or as a direction or the fire button of the
+// emulated joystick interface.
+//
+// The sticks are treated as pushed in a direction once they're past half
+// way, and so are the triggers.  The joystick interface is the one emulated by
+// the joystick module of the core, which the keys of the keyboard can move
+// as well.
+//
+const AXIS_THRESHOLD: i16 = 16384;
+
+// A button, or a direction of a stick or of a trigger.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Control {
+    Button(Button),
+    AxisMinus(Axis),
+    AxisPlus(Axis),
+}
+
+// The buttons which can be bound, named as in the Button enum of SDL2, and
+// the axes, whose directions are named with `-' or `+' after the name of the
+// axis, except for the triggers, which only go one way.
+const BUTTONS: [Button; 21] = [
+    Button::A, Button::B, Button::X, Button::Y, Button::Back, Button::Guide, Button::Start,
+    Button::LeftStick, Button::RightStick, Button::LeftShoulder, Button::RightShoulder,
+    Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight,
+    Button::Misc1, Button::Paddle1, Button::Paddle2, Button::Paddle3, Button::Paddle4,
+    Button::Touchpad,
+];
+const STICK_AXES: [Axis; 4] = [Axis::LeftX, Axis::LeftY, Axis::RightX, Axis::RightY];
+const TRIGGER_AXES: [Axis; 2] = [Axis::TriggerLeft, Axis::TriggerRight];
+
+fn control_name(control: Control) -> String {
+    match control {
+        Control::Button(button) => { format!("{:?}", button) },
+        Control::AxisMinus(axis) => { format!("{:?}-", axis) },
+        Control::AxisPlus(axis) if TRIGGER_AXES.contains(&axis) => { format!("{:?}", axis) },
+        Control::AxisPlus(axis) => { format!("{:?}+", axis) },
+    }
+}
+
+fn control(name: &str) -> Option<Control> {
+    let mut controls = BUTTONS.iter().map(|button| Control::Button(*button))
+        .chain(STICK_AXES.iter().flat_map(|axis| [Control::AxisMinus(*axis), Control::AxisPlus(*axis)]))
+        .chain(TRIGGER_AXES.iter().map(|axis| Control::AxisPlus(*axis)));
+
+    controls.find(|control| control_name(*control).eq_ignore_ascii_case(name))
+}
+
+// By default, the d-pad and the left stick are the arrow keys, A and B are
+// the space bar, Start is ENTER and Back is BREAK, which is what most of the
+// games played with the keyboard use.
+const DEFAULT_LAYOUT: [(Control, &str); 12] = [
+    (Control::Button(Button::DPadUp), "up"),
+    (Control::Button(Button::DPadDown), "down"),
+    (Control::Button(Button::DPadLeft), "left"),
+    (Control::Button(Button::DPadRight), "right"),
+    (Control::AxisMinus(Axis::LeftY), "up"),
+    (Control::AxisPlus(Axis::LeftY), "down"),
+    (Control::AxisMinus(Axis::LeftX), "left"),
+    (Control::AxisPlus(Axis::LeftX), "right"),
+    (Control::Button(Button::A), "space"),
+    (Control::Button(Button::B), "space"),
+    (Control::Button(Button::Start), "enter"),
+    (Control::Button(Button::Back), "break"),
+];
+
+fn new_control_map(bindings: &[ControllerBinding]) -> HashMap<Control, sdl_keyboard::Target> {
+    let mut map = HashMap::new();
+
+    for (control, name) in DEFAULT_LAYOUT.iter() {
+        map.insert(*control, sdl_keyboard::target(name).expect("A target of the default controller layout doesn't exist"));
+    }
+    for binding in bindings {
+        match binding.target {
+            Some(target) => { map.insert(binding.control, target); },
+            None => { map.remove(&binding.control); },
+        }
+    }
+    map
+}
+
+// A change to the default layout, binding a control of the game controllers
+// to a key of the machine or to the joystick interface, or to nothing.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ControllerBinding {
+    control: Control,
+    target:  Option<sdl_keyboard::Target>,
+}
+
+// Parse the controller bindings of the configuration file, a comma-separated
+// list of `<control>:<target>' pairs, or `none'.
+pub fn parse_controller_bindings(text: &str) -> Option<Vec<ControllerBinding>> {
+    let mut bindings = Vec::new();
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Some(bindings);
+    }
+    for binding in text.split(',') {
+        let (control_name, target_name) = binding.split_once(':')?;
+        let control = control(control_name.trim())?;
+        let target_name = target_name.trim();
+        let target = if target_name.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            Some(sdl_keyboard::target(target_name)?)
+        };
+        bindings.push(ControllerBinding { control, target });
+    }
+    Some(bindings)
+}
+
+pub fn controller_bindings_text(bindings: &[ControllerBinding]) -> String {
+    if bindings.is_empty() {
+        return "none".to_owned();
+    }
+    bindings.iter().map(|binding| {
+        format!("{}:{}", control_name(binding.control), match binding.target {
+            Some(target) => { sdl_keyboard::target_name(target) },
+            None => { "none" },
+        })
+    }).collect::<Vec<String>>().join(", ")
+}
+
+// A change of a control of one of the controllers, which is identified by
+// its SDL2 instance ID.
+pub struct ControlChange {
+    pub controller: u32,
+    pub control:    Control,
+    pub pressed:    bool,
+}
+
+pub struct SdlControllers {
+    subsystem:   Option<sdl2::GameControllerSubsystem>,
+    controllers: HashMap<u32, sdl2::controller::GameController>,
+    axes:        HashMap<(u32, Axis), i8>, // -1, 0 or 1, which way it's pushed.
+    control_map: HashMap<Control, sdl_keyboard::Target>,
+}
+
+impl SdlControllers {
+    // Without the subsystem, the controllers are ignored.
+    pub fn new(subsystem: Option<sdl2::GameControllerSubsystem>) -> SdlControllers {
+        SdlControllers {
+            subsystem,
+            controllers: HashMap::new(),
+            axes:        HashMap::new(),
+            control_map: new_control_map(&[]),
+        }
+    }
+    pub fn set_bindings(&mut self, bindings: &[ControllerBinding]) {
+        self.control_map = new_control_map(bindings);
+    }
+    pub fn target(&self, control: Control) -> Option<sdl_keyboard::Target> {
+        self.control_map.get(&control).copied()
+    }
+
+    // The controllers present at the start are announced as added too.
+    pub fn add_controller(&mut self, joystick_index: u32) {
+        if let Some(subsystem) = &self.subsystem {
+            match subsystem.open(joystick_index) {
+                Ok(controller) => {
+                    info!("Game controller `{}' connected.", controller.name());
+                    self.controllers.insert(controller.instance_id(), controller);
+                },
+                Err(error) => {
+                    warn!("Failed to open game controller {}: {}.", joystick_index, error);
+                },
+            }
+        }
+    }
+    pub fn remove_controller(&mut self, instance_id: u32) {
+        if let Some(controller) = self.controllers.remove(&instance_id) {
+            info!("Game controller `{}' disconnected.", controller.name());
+        }
+        self.axes.retain(|(controller, _), _| *controller != instance_id);
+    }
+
+    // Turn the movement of an axis into the presses and releases of its
+    // directions.
+    pub fn move_axis(&mut self, controller: u32, axis: Axis, value: i16) -> Vec<ControlChange> {
+        let direction = if value <= -AXIS_THRESHOLD {
+            -1
+        } else if value >= AXIS_THRESHOLD {
+            1
+        } else {
+            0
+        };
+        let previous = self.axes.insert((controller, axis), direction).unwrap_or(0);
+        let mut changes = Vec::new();
+
+        if previous != direction {
+            if previous != 0 {
+                let control = if previous < 0 { Control::AxisMinus(axis) } else { Control::AxisPlus(axis) };
+                changes.push(ControlChange { controller, control, pressed: false });
+            }
+            if direction != 0 {
+                let control = if direction < 0 { Control::AxisMinus(axis) } else { Control::AxisPlus(axis) };
+                changes.push(ControlChange { controller, control, pressed: true });
+            }
+        }
+        changes
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use sdl2;
+
+use std::time;
+
+use trs80m1_rs_core::machine;
+use trs80m1_rs_core::memory;
+use trs80m1_rs_core::video;
+use trs80m1_rs_core::z80::cpu;
+use trs80m1_rs_core::z80::disasm;
+
+use crate::sdl_video;
+
+
+// The debugger window shows the registers of the CPU, the instructions
+// starting at the program counter and a part of the memory, as text drawn
+// with the character generator font of the screen, in its colors.
+//
+// The logic core describes the state of the machine as lines of text, which
+// it sends over to the front-end every UPDATE_INTERVAL while the window is
+// open, so the emulation isn't slowed down by it.
+//
+pub const UPDATE_INTERVAL:   time::Duration = time::Duration::from_millis(100);
+
+const COLUMNS:           u32 = 40;
+const ROWS:              u32 = 25;
+const WINDOW_SCALE:      u32 = 2;
+const DISASSEMBLY_LINES: usize = 10;
+const MEMORY_LINES:      u16 = 8;
+const MEMORY_LINE_BYTES: u16 = 8;
+const MAX_SHOWN_BYTES:   usize = 4;
+const DUMP_LINE_BYTES:   u16 = 16;
+
+pub fn flags_string(flags: &cpu::Z80Flags) -> String {
+    [(flags.sign, 'S'), (flags.zero, 'Z'), (flags.half_carry, 'H'),
+     (flags.parity_overflow, 'P'), (flags.add_sub, 'N'), (flags.carry, 'C')]
+        .iter().map(|(set, name)| if *set { *name } else { '-' }).collect()
+}
+
+// Describe the state of the machine, for showing it in the window.
+pub fn describe_machine(machine: &mut machine::Machine, memory_address: u16) -> Vec<String> {
+    let mut lines = Vec::new();
+    let cpu = &machine.cpu;
+    let regs = &cpu.regs;
+    let af = ((regs.a as u16) << 8) | (regs.flags.to_byte() as u16);
+    let af_prime = ((regs.a_prime as u16) << 8) | (regs.flags_prime.to_byte() as u16);
+    let int_mode = match cpu.im {
+        cpu::InterruptMode::Mode0         => { "0" },
+        cpu::InterruptMode::Mode1         => { "1" },
+        cpu::InterruptMode::Mode2         => { "2" },
+        cpu::InterruptMode::ModeUndefined => { "?" },
+    };
+
+    lines.push(format!("PC {:04X}  SP {:04X}  IX {:04X}  IY {:04X}", regs.pc, regs.sp, regs.ix, regs.iy));
+    lines.push(format!("AF {:04X}  BC {:04X}  DE {:04X}  HL {:04X}", af, regs.bc, regs.de, regs.hl));
+    lines.push(format!("AF'{:04X}  BC'{:04X}  DE'{:04X}  HL'{:04X}", af_prime, regs.bc_prime, regs.de_prime, regs.hl_prime));
+    lines.push(format!("I {:02X}  R {:02X}  IM {}  IFF1 {}  IFF2 {}", regs.i, regs.r, int_mode, cpu.iff1 as u8, cpu.iff2 as u8));
+    lines.push(format!("FLAGS {}{}", flags_string(&regs.flags), if cpu.halted { "  HALTED" } else { "" }));
+    lines.push(String::new());
+
+    let memory = &mut machine.memory_system;
+    let mut address = machine.cpu.regs.pc;
+    for line in 0..DISASSEMBLY_LINES {
+        let (mnemonic, size) = disasm::disassemble(|addr| memory.peek_byte(addr), address);
+        let bytes: String = (0..(size as usize).min(MAX_SHOWN_BYTES))
+            .map(|offset| format!("{:02X}", memory.peek_byte(address.wrapping_add(offset as u16))))
+            .collect();
+        lines.push(format!("{}{:04X}  {:<8} {}", if line == 0 { '>' } else { ' ' }, address, bytes, mnemonic));
+        address = address.wrapping_add(size);
+    }
+    lines.push(String::new());
+
+    for line in 0..MEMORY_LINES {
+        let address = memory_address.wrapping_add(line * MEMORY_LINE_BYTES);
+        lines.push(memory_line(memory, address, MEMORY_LINE_BYTES));
+    }
+    lines
+}
+
+// A line of memory, in hex and as text.
+fn memory_line(memory: &mut memory::MemorySystem, address: u16, length: u16) -> String {
+    let bytes: Vec<u8> = (0..length).map(|offset| memory.peek_byte(address.wrapping_add(offset))).collect();
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    let text: String = bytes.iter().map(|byte| if (0x20..0x7F).contains(byte) { *byte as char } else { '.' }).collect();
+    format!("{:04X}  {}  {}", address, hex.join(" "), text)
+}
+
+// The lines for `memory dump', of 16 bytes each, which stop at the end of
+// the address space.
+pub fn dump_memory(machine: &mut machine::Machine, address: u16, length: u32) -> Vec<String> {
+    let end = (address as u32 + length).min(0x10000);
+    (address as u32..end).step_by(DUMP_LINE_BYTES as usize).map(|line_address| {
+        memory_line(&mut machine.memory_system, line_address as u16, (end - line_address).min(DUMP_LINE_BYTES as u32) as u16)
+    }).collect()
+}
+
+// Draw the lines into an RGB bitmap of COLUMNS by ROWS characters.
+fn rasterize_text(lines: &[String], font: &[u8], bg_color: (u8, u8, u8), fg_color: (u8, u8, u8)) -> Vec<u8> {
+    let (bg_red, bg_green, bg_blue) = bg_color;
+    let (fg_red, fg_green, fg_blue) = fg_color;
+
+    sdl_video::rasterize_text(lines, COLUMNS, ROWS, font).iter().flat_map(|lit| {
+        if *lit != 0 { [fg_red, fg_green, fg_blue] } else { [bg_red, bg_green, bg_blue] }
+    }).collect()
+}
+
+pub struct DebugWindow {
+    canvas: sdl2::render::Canvas<sdl2::video::Window>,
+}
+
+impl DebugWindow {
+    pub fn open(video_ctxt: &sdl2::VideoSubsystem) -> Result<DebugWindow, String> {
+        let width = COLUMNS * video::GLYPH_WIDTH;
+        let height = ROWS * video::GLYPH_HEIGHT;
+
+        let window = video_ctxt.window("TRS-80 Model I Emulator - Debugger", width * WINDOW_SCALE, height * WINDOW_SCALE)
+                               .resizable().build().map_err(|error| error.to_string())?;
+        let mut canvas = window.into_canvas().build().map_err(|error| error.to_string())?;
+
+        // The text keeps its proportions however the window is resized.
+        canvas.set_logical_size(width, height).map_err(|error| error.to_string())?;
+        Ok(DebugWindow { canvas })
+    }
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+    pub fn show(&mut self, lines: &[String], font: &[u8], bg_color: (u8, u8, u8), fg_color: (u8, u8, u8)) -> Result<(), String> {
+        let width = COLUMNS * video::GLYPH_WIDTH;
+        let height = ROWS * video::GLYPH_HEIGHT;
+        let pixels = rasterize_text(lines, font, bg_color, fg_color);
+
+        let texture_creator = self.canvas.texture_creator();
+        let mut texture = texture_creator.create_texture_static(sdl2::pixels::PixelFormatEnum::RGB24, width, height)
+                                         .map_err(|error| error.to_string())?;
+        texture.update(None, &pixels, (width * 3) as usize).map_err(|error| error.to_string())?;
+
+        let (bg_red, bg_green, bg_blue) = bg_color;
+        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(bg_red, bg_green, bg_blue));
+        self.canvas.clear();
+        self.canvas.copy(&texture, None, None)?;
+        self.canvas.present();
+        Ok(())
+    }
+}
@@ -14,421 +14,807 @@
 //
 
 use log::{info, warn, error};
+use sdl2::keyboard::Scancode;
 
 use std::collections::HashMap;
 use std::sync::mpsc;
+use std::time;
 
 use crate::emulator;
+use crate::sdl_controller;
+use crate::sdl_magnifier;
+use crate::sdl_menu;
+use crate::sdl_vkeyboard;
+use trs80m1_rs_core::joystick;
 use trs80m1_rs_core::keyboard;
 
 
-struct KeyDesc {
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct KeyDesc {
     row:    u8,
     column: u8,
 }
-enum RedundantKeyVariant {
-    Left,
-    Right,
+
+// The keys of the keyboard matrix of the emulated machine, by row and column,
+// as they're named in the key bindings.
+const MATRIX_KEYS: [[&str; 8]; 8] = [
+    ["at",    "a",     "b",     "c",         "d",     "e",     "f",      "g"],
+    ["h",     "i",     "j",     "k",         "l",     "m",     "n",      "o"],
+    ["p",     "q",     "r",     "s",         "t",     "u",     "v",      "w"],
+    ["x",     "y",     "z",     "",          "",      "",      "",       ""],
+    ["0",     "1",     "2",     "3",         "4",     "5",     "6",      "7"],
+    ["8",     "9",     "colon", "semicolon", "comma", "minus", "period", "slash"],
+    ["enter", "clear", "break", "up",        "down",  "left",  "right",  "space"],
+    ["shift", "",      "",      "",          "",      "",      "",       ""],
+];
+
+fn matrix_key(name: &str) -> Option<KeyDesc> {
+    for (row, names) in MATRIX_KEYS.iter().enumerate() {
+        for (column, key_name) in names.iter().enumerate() {
+            if !key_name.is_empty() && key_name.eq_ignore_ascii_case(name) {
+                return Some(KeyDesc { row: row as u8, column: 1 << column });
+            }
+        }
+    }
+    None
 }
-struct RedundantKeyDesc {
-    control_index: usize,
-    variant:       RedundantKeyVariant,
-    row:           u8,
-    column:        u8,
+
+fn matrix_key_name(key: KeyDesc) -> &'static str {
+    MATRIX_KEYS[key.row as usize][key.column.trailing_zeros() as usize]
 }
 
-#[derive(Copy, Clone)]   // Traits needed for initialization.
-struct RedundantKeyControl {
-    left_key_pressed:  bool,
-    right_key_pressed: bool,
+// What a key or a control of a game controller does, pressing a key of the
+// machine, or moving the stick of the joystick interface, or pressing its
+// fire button, or running the emulation at full speed while it's held, or
+// switching the warp on or off, or going through the slow motion speeds, or
+// running the paused machine for a video frame.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Target {
+    Key(KeyDesc),
+    Joystick(u8),   // One of the JOY_* bits.
+    Turbo,
+    Warp,
+    SlowMotion,
+    FrameAdvance,
 }
 
-fn new_key_map() -> HashMap<i32, KeyDesc> {
-    use sdl2::keyboard::Scancode;
-    let mut map = HashMap::new();
+// The turbo is on F10 by default, the warp on Scroll Lock, and the frame
+// advance on Pause.  The slow motion isn't on any key unless it's bound.
+const TURBO_KEY:         Scancode = Scancode::F10;
+const WARP_KEY:          Scancode = Scancode::ScrollLock;
+const FRAME_ADVANCE_KEY: Scancode = Scancode::Pause;
+
+const JOYSTICK_TARGETS: [(&str, u8); 5] = [
+    ("joy_up",    joystick::JOY_UP),
+    ("joy_down",  joystick::JOY_DOWN),
+    ("joy_left",  joystick::JOY_LEFT),
+    ("joy_right", joystick::JOY_RIGHT),
+    ("joy_fire",  joystick::JOY_FIRE),
+];
+
+pub fn target(name: &str) -> Option<Target> {
+    if name.eq_ignore_ascii_case("turbo") {
+        return Some(Target::Turbo);
+    }
+    if name.eq_ignore_ascii_case("warp") {
+        return Some(Target::Warp);
+    }
+    if name.eq_ignore_ascii_case("slow_motion") {
+        return Some(Target::SlowMotion);
+    }
+    if name.eq_ignore_ascii_case("frame_advance") {
+        return Some(Target::FrameAdvance);
+    }
+    match JOYSTICK_TARGETS.iter().find(|(target_name, _)| target_name.eq_ignore_ascii_case(name)) {
+        Some((_, bit)) => { Some(Target::Joystick(*bit)) },
+        None => { matrix_key(name).map(Target::Key) },
+    }
+}
 
-    map.insert(Scancode::LeftBracket as i32,
-               KeyDesc { row: 0, column: 0b0000_0001 });
-    map.insert(Scancode::A as i32,
-               KeyDesc { row: 0, column: 0b0000_0010 });
-    map.insert(Scancode::B as i32,
-               KeyDesc { row: 0, column: 0b0000_0100 });
-    map.insert(Scancode::C as i32,
-               KeyDesc { row: 0, column: 0b0000_1000 });
-    map.insert(Scancode::D as i32,
-               KeyDesc { row: 0, column: 0b0001_0000 });
-    map.insert(Scancode::E as i32,
-               KeyDesc { row: 0, column: 0b0010_0000 });
-    map.insert(Scancode::F as i32,
-               KeyDesc { row: 0, column: 0b0100_0000 });
-    map.insert(Scancode::G as i32,
-               KeyDesc { row: 0, column: 0b1000_0000 });
-    map.insert(Scancode::H as i32,
-               KeyDesc { row: 1, column: 0b0000_0001 });
-    map.insert(Scancode::I as i32,
-               KeyDesc { row: 1, column: 0b0000_0010 });
-    map.insert(Scancode::J as i32,
-               KeyDesc { row: 1, column: 0b0000_0100 });
-    map.insert(Scancode::K as i32,
-               KeyDesc { row: 1, column: 0b0000_1000 });
-    map.insert(Scancode::L as i32,
-               KeyDesc { row: 1, column: 0b0001_0000 });
-    map.insert(Scancode::M as i32,
-               KeyDesc { row: 1, column: 0b0010_0000 });
-    map.insert(Scancode::N as i32,
-               KeyDesc { row: 1, column: 0b0100_0000 });
-    map.insert(Scancode::O as i32,
-               KeyDesc { row: 1, column: 0b1000_0000 });
-    map.insert(Scancode::P as i32,
-               KeyDesc { row: 2, column: 0b0000_0001 });
-    map.insert(Scancode::Q as i32,
-               KeyDesc { row: 2, column: 0b0000_0010 });
-    map.insert(Scancode::R as i32,
-               KeyDesc { row: 2, column: 0b0000_0100 });
-    map.insert(Scancode::S as i32,
-               KeyDesc { row: 2, column: 0b0000_1000 });
-    map.insert(Scancode::T as i32,
-               KeyDesc { row: 2, column: 0b0001_0000 });
-    map.insert(Scancode::U as i32,
-               KeyDesc { row: 2, column: 0b0010_0000 });
-    map.insert(Scancode::V as i32,
-               KeyDesc { row: 2, column: 0b0100_0000 });
-    map.insert(Scancode::W as i32,
-               KeyDesc { row: 2, column: 0b1000_0000 });
-    map.insert(Scancode::X as i32,
-               KeyDesc { row: 3, column: 0b0000_0001 });
-    map.insert(Scancode::Y as i32,
-               KeyDesc { row: 3, column: 0b0000_0010 });
-    map.insert(Scancode::Z as i32,
-               KeyDesc { row: 3, column: 0b0000_0100 });
-    map.insert(Scancode::Minus as i32,
-               KeyDesc { row: 5, column: 0b0000_0100 });
-    map.insert(Scancode::Semicolon as i32,
-               KeyDesc { row: 5, column: 0b0000_1000 });
-    map.insert(Scancode::Comma as i32,
-               KeyDesc { row: 5, column: 0b0001_0000 });
-    map.insert(Scancode::Equals as i32,
-               KeyDesc { row: 5, column: 0b0010_0000 });
-    map.insert(Scancode::Slash as i32,
-               KeyDesc { row: 5, column: 0b1000_0000 });
-    map.insert(Scancode::Up as i32,
-               KeyDesc { row: 6, column: 0b0000_1000 });
-    map.insert(Scancode::Down as i32,
-               KeyDesc { row: 6, column: 0b0001_0000 });
-    map.insert(Scancode::Right as i32,
-               KeyDesc { row: 6, column: 0b0100_0000 });
-    map.insert(Scancode::Space as i32,
-               KeyDesc { row: 6, column: 0b1000_0000 });
+pub fn target_name(target: Target) -> &'static str {
+    match target {
+        Target::Key(key) => { matrix_key_name(key) },
+        Target::Joystick(bit) => {
+            JOYSTICK_TARGETS.iter().find(|(_, target_bit)| *target_bit == bit).map(|(name, _)| *name).unwrap_or("none")
+        },
+        Target::Turbo => { "turbo" },
+        Target::Warp => { "warp" },
+        Target::SlowMotion => { "slow_motion" },
+        Target::FrameAdvance => { "frame_advance" },
+    }
+}
 
-    map
+// Whether the key of the machine types a character, or is the shift key.
+fn is_character_key(key: KeyDesc) -> bool {
+    key.row <= 5 || key.row == 7 || (key.row == 6 && matrix_key_name(key) == "space")
 }
 
-fn new_redundant_key_map() -> HashMap<i32, RedundantKeyDesc> {
-    use sdl2::keyboard::Scancode;
-    let mut map = HashMap::new();
+// How the keys of the PC keyboard are mapped to the keys of the machine:
+//
+//  - Positional:  by where they are on the keyboard, as in the default layout
+//                 and the key bindings, which leaves the punctuation of the
+//                 machine in the places of the US layout.
+//  - Symbolic:    by the characters they type on the layout of the host, so
+//                 that the punctuation comes out right on other layouts too,
+//                 shift included.  Only the keys which don't type characters,
+//                 such as ENTER, BREAK, CLEAR and the arrows, are mapped by
+//                 where they are.
+//
+#[derive(Copy, Clone, PartialEq, Eq, Debug)] // For the config system.
+pub enum KeyMapping {
+    Positional,
+    Symbolic,
+}
 
-    // I don't know this for certain, but I think that with the number pad
-    // present on the TRS-80 Model I, the keys on the number pad cross the
-    // same wires as the ones on the main keyboard, acting as redundant keys.
-    //
-    // If this is not so, please report it as a bug.
-
-    // Number 0:
-    map.insert(Scancode::Num0 as i32,
-               RedundantKeyDesc {
-                   control_index: 0,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0000_0001,
-               });
-    map.insert(Scancode::Kp0 as i32,
-               RedundantKeyDesc {
-                   control_index: 0,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0000_0001,
-               });
-
-    // Number 1:
-    map.insert(Scancode::Num1 as i32,
-               RedundantKeyDesc {
-                   control_index: 1,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0000_0010,
-               });
-    map.insert(Scancode::Kp1 as i32,
-               RedundantKeyDesc {
-                   control_index: 1,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0000_0010,
-               });
-
-    // Number 2:
-    map.insert(Scancode::Num2 as i32,
-               RedundantKeyDesc {
-                   control_index: 2,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0000_0100,
-               });
-    map.insert(Scancode::Kp2 as i32,
-               RedundantKeyDesc {
-                   control_index: 2,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0000_0100,
-               });
-
-    // Number 3:
-    map.insert(Scancode::Num3 as i32,
-               RedundantKeyDesc {
-                   control_index: 3,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0000_1000,
-               });
-    map.insert(Scancode::Kp3 as i32,
-               RedundantKeyDesc {
-                   control_index: 3,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0000_1000,
-               });
-
-    // Number 4:
-    map.insert(Scancode::Num4 as i32,
-               RedundantKeyDesc {
-                   control_index: 4,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0001_0000,
-               });
-    map.insert(Scancode::Kp4 as i32,
-               RedundantKeyDesc {
-                   control_index: 4,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0001_0000,
-               });
-
-    // Number 5:
-    map.insert(Scancode::Num5 as i32,
-               RedundantKeyDesc {
-                   control_index: 5,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0010_0000,
-               });
-    map.insert(Scancode::Kp5 as i32,
-               RedundantKeyDesc {
-                   control_index: 5,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0010_0000,
-               });
-
-    // Number 6:
-    map.insert(Scancode::Num6 as i32,
-               RedundantKeyDesc {
-                   control_index: 6,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b0100_0000,
-               });
-    map.insert(Scancode::Kp6 as i32,
-               RedundantKeyDesc {
-                   control_index: 6,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b0100_0000,
-               });
-
-    // Number 7:
-    map.insert(Scancode::Num7 as i32,
-               RedundantKeyDesc {
-                   control_index: 7,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           4,
-                   column:        0b1000_0000,
-               });
-    map.insert(Scancode::Kp7 as i32,
-               RedundantKeyDesc {
-                   control_index: 7,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           4,
-                   column:        0b1000_0000,
-               });
-
-    // Number 8:
-    map.insert(Scancode::Num8 as i32,
-               RedundantKeyDesc {
-                   control_index: 8,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           5,
-                   column:        0b0000_0001,
-               });
-    map.insert(Scancode::Kp8 as i32,
-               RedundantKeyDesc {
-                   control_index: 8,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           5,
-                   column:        0b0000_0001,
-               });
-
-    // Number 9:
-    map.insert(Scancode::Num9 as i32,
-               RedundantKeyDesc {
-                   control_index: 9,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           5,
-                   column:        0b0000_0010,
-               });
-    map.insert(Scancode::Kp9 as i32,
-               RedundantKeyDesc {
-                   control_index: 9,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           5,
-                   column:        0b0000_0010,
-               });
-
-    // There are two enter keys, the main one and the one on the number pad.
-    map.insert(Scancode::Return as i32,
-               RedundantKeyDesc {
-                   control_index: 10,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           6,
-                   column:        0b0000_0001,
-               });
-    map.insert(Scancode::KpEnter as i32,
-               RedundantKeyDesc {
-                   control_index: 10,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           6,
-                   column:        0b0000_0001,
-               });
-
-    // There are two period keys, the main one and the one on the number pad.
-    map.insert(Scancode::Period as i32,
-               RedundantKeyDesc {
-                   control_index: 11,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           5,
-                   column:        0b0100_0000,
-               });
-    map.insert(Scancode::KpPeriod as i32,
-               RedundantKeyDesc {
-                   control_index: 11,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           5,
-                   column:        0b0100_0000,
-               });
-
-    // The break key is represented by F1 and Insert.
-    map.insert(Scancode::F1 as i32,
-               RedundantKeyDesc {
-                   control_index: 12,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           6,
-                   column:        0b0000_0100,
-               });
-    map.insert(Scancode::Insert as i32,
-               RedundantKeyDesc {
-                   control_index: 12,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           6,
-                   column:        0b0000_0100,
-               });
-
-    // The clear key is represented by F2 and Delete.
-    map.insert(Scancode::F2 as i32,
-               RedundantKeyDesc {
-                   control_index: 13,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           6,
-                   column:        0b0000_0010,
-               });
-    map.insert(Scancode::Delete as i32,
-               RedundantKeyDesc {
-                   control_index: 13,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           6,
-                   column:        0b0000_0010,
-               });
-
-    // The left-arrow key is represented by the left arrow and backspace.
-    map.insert(Scancode::Backspace as i32,
-               RedundantKeyDesc {
-                   control_index: 14,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           6,
-                   column:        0b0010_0000,
-               });
-    map.insert(Scancode::Left as i32,
-               RedundantKeyDesc {
-                   control_index: 14,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           6,
-                   column:        0b0010_0000,
-               });
-
-    // Both shift keys act as a single key in the Model I.
-    map.insert(Scancode::LShift as i32,
-               RedundantKeyDesc {
-                   control_index: 15,
-                   variant:       RedundantKeyVariant::Left,
-                   row:           7,
-                   column:        0b0000_0001,
-               });
-    map.insert(Scancode::RShift as i32,
-               RedundantKeyDesc {
-                   control_index: 15,
-                   variant:       RedundantKeyVariant::Right,
-                   row:           7,
-                   column:        0b0000_0001,
-               });
+// The real machine has no auto-repeat, a key held down is read as held down
+// for as long as it is, which is what the programs expect.  For comfort, the
+// emulator can repeat the last key pressed on the PC keyboard after a delay,
+// by releasing and pressing it again, the way a PC keyboard repeats its keys.
+// SHIFT isn't repeated, and neither are the controls of game controllers.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct KeyRepeat {
+    pub delay:    time::Duration,
+    pub interval: time::Duration,
+}
 
+// The keys of the PC keyboard which can be bound, named as in the Scancode
+// enum of SDL2.  The ones controlling the emulator are left out.
+const BINDABLE_KEYS: [Scancode; 95] = [
+    Scancode::A, Scancode::B, Scancode::C, Scancode::D, Scancode::E, Scancode::F, Scancode::G,
+    Scancode::H, Scancode::I, Scancode::J, Scancode::K, Scancode::L, Scancode::M, Scancode::N,
+    Scancode::O, Scancode::P, Scancode::Q, Scancode::R, Scancode::S, Scancode::T, Scancode::U,
+    Scancode::V, Scancode::W, Scancode::X, Scancode::Y, Scancode::Z,
+    Scancode::Num0, Scancode::Num1, Scancode::Num2, Scancode::Num3, Scancode::Num4,
+    Scancode::Num5, Scancode::Num6, Scancode::Num7, Scancode::Num8, Scancode::Num9,
+    Scancode::F1, Scancode::F2, Scancode::F10,
+    Scancode::Return, Scancode::Escape, Scancode::Backspace, Scancode::Tab, Scancode::Space,
+    Scancode::Minus, Scancode::Equals, Scancode::LeftBracket, Scancode::RightBracket,
+    Scancode::Backslash, Scancode::NonUsHash, Scancode::NonUsBackslash, Scancode::Semicolon,
+    Scancode::Apostrophe, Scancode::Grave, Scancode::Comma, Scancode::Period, Scancode::Slash,
+    Scancode::CapsLock, Scancode::PrintScreen, Scancode::ScrollLock, Scancode::Pause,
+    Scancode::Insert, Scancode::Home, Scancode::PageUp, Scancode::Delete, Scancode::End,
+    Scancode::PageDown, Scancode::Right, Scancode::Left, Scancode::Down, Scancode::Up,
+    Scancode::NumLockClear, Scancode::KpDivide, Scancode::KpMultiply, Scancode::KpMinus,
+    Scancode::KpPlus, Scancode::KpEnter, Scancode::KpPeriod,
+    Scancode::Kp0, Scancode::Kp1, Scancode::Kp2, Scancode::Kp3, Scancode::Kp4,
+    Scancode::Kp5, Scancode::Kp6, Scancode::Kp7, Scancode::Kp8, Scancode::Kp9,
+    Scancode::Application, Scancode::LCtrl, Scancode::LShift, Scancode::LAlt,
+    Scancode::RCtrl, Scancode::RShift, Scancode::RAlt,
+];
+
+fn bindable_key(name: &str) -> Option<Scancode> {
+    BINDABLE_KEYS.iter().copied().find(|scancode| format!("{:?}", scancode).eq_ignore_ascii_case(name))
+}
+
+// The default layout puts the keys where they are on the keyboard of the
+// machine, as far as the PC keyboard allows, with the keys it doesn't have
+// on the function keys and the ones above the arrows.
+//
+// I don't know this for certain, but I think that with the number pad
+// present on the TRS-80 Model I, the keys on the number pad cross the
+// same wires as the ones on the main keyboard, acting as redundant keys.
+//
+// If this is not so, please report it as a bug.
+const DEFAULT_LAYOUT: [(Scancode, &str); 68] = [
+    (Scancode::LeftBracket, "at"),
+    (Scancode::A, "a"), (Scancode::B, "b"), (Scancode::C, "c"), (Scancode::D, "d"),
+    (Scancode::E, "e"), (Scancode::F, "f"), (Scancode::G, "g"), (Scancode::H, "h"),
+    (Scancode::I, "i"), (Scancode::J, "j"), (Scancode::K, "k"), (Scancode::L, "l"),
+    (Scancode::M, "m"), (Scancode::N, "n"), (Scancode::O, "o"), (Scancode::P, "p"),
+    (Scancode::Q, "q"), (Scancode::R, "r"), (Scancode::S, "s"), (Scancode::T, "t"),
+    (Scancode::U, "u"), (Scancode::V, "v"), (Scancode::W, "w"), (Scancode::X, "x"),
+    (Scancode::Y, "y"), (Scancode::Z, "z"),
+    (Scancode::Num0, "0"), (Scancode::Num1, "1"), (Scancode::Num2, "2"), (Scancode::Num3, "3"),
+    (Scancode::Num4, "4"), (Scancode::Num5, "5"), (Scancode::Num6, "6"), (Scancode::Num7, "7"),
+    (Scancode::Num8, "8"), (Scancode::Num9, "9"),
+    (Scancode::Kp0, "0"), (Scancode::Kp1, "1"), (Scancode::Kp2, "2"), (Scancode::Kp3, "3"),
+    (Scancode::Kp4, "4"), (Scancode::Kp5, "5"), (Scancode::Kp6, "6"), (Scancode::Kp7, "7"),
+    (Scancode::Kp8, "8"), (Scancode::Kp9, "9"),
+    (Scancode::Minus, "colon"), (Scancode::Semicolon, "semicolon"), (Scancode::Comma, "comma"),
+    (Scancode::Equals, "minus"), (Scancode::Period, "period"), (Scancode::KpPeriod, "period"),
+    (Scancode::Slash, "slash"),
+    (Scancode::Return, "enter"), (Scancode::KpEnter, "enter"),
+    (Scancode::F2, "clear"), (Scancode::Delete, "clear"),
+    (Scancode::F1, "break"), (Scancode::Insert, "break"),
+    (Scancode::Up, "up"), (Scancode::Down, "down"),
+    (Scancode::Backspace, "left"), (Scancode::Left, "left"),
+    (Scancode::Right, "right"), (Scancode::Space, "space"),
+    (Scancode::LShift, "shift"), (Scancode::RShift, "shift"),
+];
+
+// With the symbolic mapping, the keys of the default layout which type
+// characters are left out, the text typed with them is used instead.  The
+// bindings of the key profile go on top of the key bindings.
+fn new_key_map(bindings: &[KeyBinding], profile: &[KeyBinding], mapping: KeyMapping) -> HashMap<i32, Target> {
+    let mut map = HashMap::new();
+
+    for (scancode, name) in DEFAULT_LAYOUT.iter() {
+        let key = matrix_key(name).expect("A key of the default layout is missing from the keyboard matrix");
+        if mapping == KeyMapping::Positional || !is_character_key(key) {
+            map.insert(*scancode as i32, Target::Key(key));
+        }
+    }
+    map.insert(TURBO_KEY as i32, Target::Turbo);
+    map.insert(WARP_KEY as i32, Target::Warp);
+    map.insert(FRAME_ADVANCE_KEY as i32, Target::FrameAdvance);
+    for binding in bindings.iter().chain(profile.iter()) {
+        match binding.target {
+            Some(target) => { map.insert(binding.scancode as i32, target); },
+            None => { map.remove(&(binding.scancode as i32)); },
+        }
+    }
     map
 }
 
+// A change to the default layout, binding a key of the PC keyboard to a key
+// of the emulated machine or to the joystick interface, or to none.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct KeyBinding {
+    scancode: Scancode,
+    target:   Option<Target>,
+}
+
+// Parse the key bindings of the configuration file, a comma-separated list
+// of `<key>:<machine key>' pairs, or `none'.
+pub fn parse_key_bindings(text: &str) -> Option<Vec<KeyBinding>> {
+    let mut bindings = Vec::new();
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Some(bindings);
+    }
+    for binding in text.split(',') {
+        let (key_name, machine_key_name) = binding.split_once(':')?;
+        let scancode = bindable_key(key_name.trim())?;
+        let machine_key_name = machine_key_name.trim();
+        let target = if machine_key_name.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            Some(target(machine_key_name)?)
+        };
+        bindings.push(KeyBinding { scancode, target });
+    }
+    Some(bindings)
+}
+
+pub fn key_bindings_text(bindings: &[KeyBinding]) -> String {
+    if bindings.is_empty() {
+        return "none".to_owned();
+    }
+    bindings.iter().map(|binding| {
+        format!("{:?}:{}", binding.scancode, match binding.target {
+            Some(target) => { target_name(target) },
+            None => { "none" },
+        })
+    }).collect::<Vec<String>>().join(", ")
+}
+
+// A key profile is a named set of key bindings for playing games, which can
+// be switched to and from while the emulator runs, with `/keyboard profile',
+// and which goes on top of the key bindings while it's chosen.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeyProfile {
+    pub name: String,
+    bindings: Vec<KeyBinding>,
+}
+
+// The profiles which come with the emulator, the ones in the configuration
+// file replace them if they have the same name:
+//
+//  - numpad:    the number pad as the arrows, with 5 and 0 as the space bar,
+//               for the games which move with the arrows.
+//  - wasd:      W, A, S and D as the arrows.
+//  - invaders:  for the shooting games which move left and right with the
+//               arrows and fire with the space bar, the arrows, and Z and X
+//               move, Up, the Ctrl keys and the Alt keys fire.
+//  - joystick:  the number pad as the joystick interface, with 5 and 0 as
+//               the fire button.
+//
+const BUILTIN_PROFILES: [(&str, &str); 4] = [
+    ("numpad",   "Kp8:up, Kp2:down, Kp4:left, Kp6:right, Kp5:space, Kp0:space"),
+    ("wasd",     "W:up, A:left, S:down, D:right"),
+    ("invaders", "Z:left, X:right, Up:space, LCtrl:space, RCtrl:space, LAlt:space, RAlt:space"),
+    ("joystick", "Kp8:joy_up, Kp2:joy_down, Kp4:joy_left, Kp6:joy_right, Kp5:joy_fire, Kp0:joy_fire"),
+];
+
+// Parse the key profiles of the configuration file, a list of
+// `<name>(<key bindings>)' profiles, or `none'.
+pub fn parse_key_profiles(text: &str) -> Option<Vec<KeyProfile>> {
+    let mut profiles = Vec::new();
+    let mut rest = text.trim();
+    if rest.eq_ignore_ascii_case("none") {
+        return Some(profiles);
+    }
+    while !rest.is_empty() {
+        let (name, after_name) = rest.split_once('(')?;
+        let (bindings, after_bindings) = after_name.split_once(')')?;
+        let name = name.trim().to_lowercase();
+        if !is_valid_profile_name(&name) || name == "none" {
+            return None;
+        }
+        profiles.push(KeyProfile { name, bindings: parse_key_bindings(bindings)? });
+        rest = after_bindings.trim_start();
+    }
+    Some(profiles)
+}
+
+pub fn key_profiles_text(profiles: &[KeyProfile]) -> String {
+    if profiles.is_empty() {
+        return "none".to_owned();
+    }
+    profiles.iter().map(|profile| {
+        format!("{}({})", profile.name, key_bindings_text(&profile.bindings))
+    }).collect::<Vec<String>>().join(" ")
+}
+
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+// The bindings of the profile of the given name, looked up among the ones of
+// the configuration file first.
+pub fn key_profile(name: &str, profiles: &[KeyProfile]) -> Option<Vec<KeyBinding>> {
+    match profiles.iter().find(|profile| profile.name.eq_ignore_ascii_case(name)) {
+        Some(profile) => { Some(profile.bindings.clone()) },
+        None => {
+            let (_, bindings) = BUILTIN_PROFILES.iter().find(|(builtin_name, _)| builtin_name.eq_ignore_ascii_case(name))?;
+            Some(parse_key_bindings(bindings).expect("A built-in key profile is invalid"))
+        },
+    }
+}
+
+pub fn key_profile_names(profiles: &[KeyProfile]) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_PROFILES.iter().map(|(name, _)| (*name).to_owned()).collect();
+    for profile in profiles {
+        if !names.contains(&profile.name) {
+            names.push(profile.name.clone());
+        }
+    }
+    names
+}
+
+// A key of the keyboard, a control of one of the game controllers, or a key
+// of the on-screen keyboard.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+enum Input {
+    Key(i32),
+    Control(u32, sdl_controller::Control),
+    Virtual(usize),
+}
+
+// A press or a release of the left mouse button, or a tap, in a window,
+// at the position in its coordinates.
+pub struct Click {
+    pub window_id: u32,
+    pub x:         i32,
+    pub y:         i32,
+    pub pressed:   bool,
+    pub at:        time::Instant,
+}
+
+// The timestamps of the events older than this are taken as wrong, as the
+// events are handled at least a few times a second.
+const MAX_EVENT_AGE: time::Duration = time::Duration::from_secs(1);
+
 pub struct SdlKeyboard {
-    key_map:                 HashMap<i32, KeyDesc>,
-    redundant_key_map:       HashMap<i32, RedundantKeyDesc>,
-    redundant_key_ctl:       [RedundantKeyControl; 16],
+    key_bindings:            Vec<KeyBinding>,
+    profile_bindings:        Vec<KeyBinding>,
+    key_mapping:             KeyMapping,
+    key_map:                 HashMap<i32, Target>,
+    held_keys:               HashMap<Input, KeyDesc>, // What the held keys press.
+    key_repeat:              Option<KeyRepeat>,
+    repeating:               Option<(Input, KeyDesc, time::Instant)>, // And when it's repeated next.
+    controllers:             sdl_controller::SdlControllers,
+    held_joystick:           HashMap<Input, u8>,      // The joystick bits held.
+    held_turbo:              Vec<Input>,
     cycles_per_keypress:     u32,
+    magnifier_window:        Option<u32>,
+    magnifier_keys:          Vec<sdl2::keyboard::Scancode>,
+    virtual_keyboard:        sdl_vkeyboard::VirtualKeyboard,
+    menu:                    Option<sdl_menu::Menu>,
+    clicks:                  Vec<Click>,
+    timer:                   Option<sdl2::TimerSubsystem>,
+    event_time:              time::Instant, // When the event being handled happened.
 }
 
 impl SdlKeyboard {
-    pub fn new(cycles_per_keypress: u32) -> SdlKeyboard {
+    // Without the timer subsystem, the events are taken as having happened
+    // when they're handled.
+    pub fn new(cycles_per_keypress: u32,
+               controller_ctxt:     Option<sdl2::GameControllerSubsystem>,
+               timer_ctxt:          Option<sdl2::TimerSubsystem>) -> SdlKeyboard {
         SdlKeyboard {
-            key_map:              new_key_map(),
-            redundant_key_map:    new_redundant_key_map(),
-            redundant_key_ctl:    [RedundantKeyControl {
-                                      left_key_pressed: false,
-                                      right_key_pressed: false,
-                                  }; 16],
-
+            key_bindings:         Vec::new(),
+            profile_bindings:     Vec::new(),
+            key_mapping:          KeyMapping::Positional,
+            key_map:              new_key_map(&[], &[], KeyMapping::Positional),
+            held_keys:            HashMap::new(),
+            key_repeat:           None,
+            repeating:            None,
+            controllers:          sdl_controller::SdlControllers::new(controller_ctxt),
+            held_joystick:        HashMap::new(),
+            held_turbo:           Vec::new(),
             cycles_per_keypress,
+            magnifier_window:     None,
+            magnifier_keys:       Vec::new(),
+            virtual_keyboard:     sdl_vkeyboard::VirtualKeyboard::new(),
+            menu:                 None,
+            clicks:               Vec::new(),
+            timer:                timer_ctxt,
+            event_time:           time::Instant::now(),
         }
     }
 
     pub fn set_cycles_per_keypress(&mut self, cycles_per_keypress: u32) {
         self.cycles_per_keypress = cycles_per_keypress;
     }
+    // The keys held down keep pressing what they were bound to until they're
+    // released.
+    pub fn set_key_bindings(&mut self, bindings: &[KeyBinding]) {
+        self.key_bindings = bindings.to_vec();
+        self.key_map = new_key_map(&self.key_bindings, &self.profile_bindings, self.key_mapping);
+    }
+    // An empty profile is no profile.
+    pub fn set_key_profile(&mut self, bindings: &[KeyBinding]) {
+        self.profile_bindings = bindings.to_vec();
+        self.key_map = new_key_map(&self.key_bindings, &self.profile_bindings, self.key_mapping);
+    }
+    pub fn set_key_mapping(&mut self, mapping: KeyMapping) {
+        self.key_mapping = mapping;
+        self.key_map = new_key_map(&self.key_bindings, &self.profile_bindings, self.key_mapping);
+    }
+    pub fn set_controller_bindings(&mut self, bindings: &[sdl_controller::ControllerBinding]) {
+        self.controllers.set_bindings(bindings);
+    }
+    // None turns the auto-repeat off.
+    pub fn set_key_repeat(&mut self, repeat: Option<KeyRepeat>) {
+        self.key_repeat = repeat;
+        self.repeating = None;
+    }
 
-    // Handle SDL events.
+    // With the symbolic mapping, the text typed on the host is typed into
+    // the machine a character at a time, with the characters it doesn't
+    // have skipped.
+    fn type_text(&self, text: &str, keycode_tx: &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+        let (events, _) = keyboard::pasted_text_to_key_events(text, self.cycles_per_keypress, self.cycles_per_keypress);
+        for event in events {
+            keycode_tx.send(event).unwrap();
+        }
+    }
+
+    // Several keys can be bound to the same key of the machine, or to the
+    // same direction of the joystick, it's only released once all of them
+    // are.
+    fn press_input(&mut self,
+                   input:      Input,
+                   target:     Target,
+                   emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>,
+                   keycode_tx: &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+
+        if self.held_keys.contains_key(&input) || self.held_joystick.contains_key(&input) || self.held_turbo.contains(&input) {
+            return;
+        }
+        match target {
+            Target::Key(key) => {
+                if !self.held_keys.values().any(|held| *held == key) {
+                    keycode_tx.send(keyboard::KeyboardQueueEntry {
+                        action: keyboard::KeyboardQueueEntryAction::Press,
+                        row:    key.row,
+                        column: key.column,
+                        delay:  self.cycles_per_keypress,
+                        sent:   Some(self.event_time),
+                    }).unwrap();
+                }
+                self.held_keys.insert(input, key);
+
+                if let (Some(repeat), Input::Key(..)) = (self.key_repeat, input) {
+                    if matrix_key_name(key) != "shift" {
+                        self.repeating = Some((input, key, time::Instant::now() + repeat.delay));
+                    }
+                }
+            },
+            Target::Joystick(bit) => {
+                self.held_joystick.insert(input, bit);
+                self.send_joystick(emu_cmd_tx);
+            },
+            Target::Turbo => {
+                if self.held_turbo.is_empty() {
+                    emu_cmd_tx.send(emulator::EmulatorCommand::SetTurbo(true)).unwrap();
+                }
+                self.held_turbo.push(input);
+            },
+            Target::Warp => {
+                emu_cmd_tx.send(emulator::EmulatorCommand::SetWarp(None)).unwrap();
+            },
+            Target::SlowMotion => {
+                emu_cmd_tx.send(emulator::EmulatorCommand::CycleSlowMotion).unwrap();
+            },
+            Target::FrameAdvance => {
+                emu_cmd_tx.send(emulator::EmulatorCommand::AdvanceFrames(1)).unwrap();
+            },
+        }
+    }
+    fn release_input(&mut self,
+                     input:      Input,
+                     emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>,
+                     keycode_tx: &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+
+        if self.repeating.map(|(repeating, _, _)| repeating) == Some(input) {
+            self.repeating = None;
+        }
+        if let Some(key) = self.held_keys.remove(&input) {
+            if !self.held_keys.values().any(|held| *held == key) {
+                keycode_tx.send(keyboard::KeyboardQueueEntry {
+                    action: keyboard::KeyboardQueueEntryAction::Release,
+                    row:    key.row,
+                    column: key.column,
+                    delay:  self.cycles_per_keypress,
+                    sent:   Some(self.event_time),
+                }).unwrap();
+            }
+        }
+        if self.held_joystick.remove(&input).is_some() {
+            self.send_joystick(emu_cmd_tx);
+        }
+        if self.held_turbo.contains(&input) {
+            self.held_turbo.retain(|held| *held != input);
+            if self.held_turbo.is_empty() {
+                emu_cmd_tx.send(emulator::EmulatorCommand::SetTurbo(false)).unwrap();
+            }
+        }
+    }
+    // Repeat the key being held, if it's time, falling behind rather than
+    // catching up if the events weren't handled for a while.
+    fn repeat_key(&mut self, keycode_tx: &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+        if let (Some(repeat), Some((input, key, next))) = (self.key_repeat, self.repeating) {
+            let now = time::Instant::now();
+            if now >= next {
+                keycode_tx.send(keyboard::KeyboardQueueEntry {
+                    action: keyboard::KeyboardQueueEntryAction::Release,
+                    row:    key.row,
+                    column: key.column,
+                    delay:  self.cycles_per_keypress,
+                    sent:   None,
+                }).unwrap();
+                keycode_tx.send(keyboard::KeyboardQueueEntry {
+                    action: keyboard::KeyboardQueueEntryAction::Press,
+                    row:    key.row,
+                    column: key.column,
+                    delay:  self.cycles_per_keypress,
+                    sent:   None,
+                }).unwrap();
+                self.repeating = Some((input, key, (next + repeat.interval).max(now)));
+            }
+        }
+    }
+    fn send_joystick(&self, emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>) {
+        let pressed = self.held_joystick.values().fold(0, |pressed, bit| pressed | bit);
+        emu_cmd_tx.send(emulator::EmulatorCommand::SetJoystick(pressed)).unwrap();
+    }
+
+    // The controls of the game controllers press the keys of the machine the
+    // same way as the keys of the keyboard do, or move the joystick.
+    fn change_control(&mut self,
+                      change:      sdl_controller::ControlChange,
+                      capture_kbd: bool,
+                      emu_cmd_tx:  &mpsc::Sender<emulator::EmulatorCommand>,
+                      keycode_tx:  &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+
+        let input = Input::Control(change.controller, change.control);
+        if !change.pressed {
+            self.release_input(input, emu_cmd_tx, keycode_tx);
+        } else if capture_kbd {
+            if let Some(target) = self.controllers.target(change.control) {
+                self.press_input(input, target, emu_cmd_tx, keycode_tx);
+            }
+        }
+    }
+    // What was held on a controller which got unplugged is released.
+    fn remove_controller(&mut self,
+                         instance_id: u32,
+                         emu_cmd_tx:  &mpsc::Sender<emulator::EmulatorCommand>,
+                         keycode_tx:  &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+
+        let held: Vec<Input> = self.held_keys.keys().chain(self.held_joystick.keys()).chain(self.held_turbo.iter()).copied().collect();
+        for input in held {
+            if let Input::Control(controller, control) = input {
+                if controller == instance_id {
+                    self.change_control(sdl_controller::ControlChange { controller, control, pressed: false }, false, emu_cmd_tx, keycode_tx);
+                }
+            }
+        }
+        self.controllers.remove_controller(instance_id);
+    }
+
+    // The keys controlling the view of the magnifier window, if it's open,
+    // are collected instead of being typed while it has the keyboard focus.
+    pub fn set_magnifier_window(&mut self, window_id: Option<u32>) {
+        self.magnifier_window = window_id;
+        self.magnifier_keys.clear();
+    }
+    pub fn take_magnifier_keys(&mut self) -> Vec<sdl2::keyboard::Scancode> {
+        std::mem::take(&mut self.magnifier_keys)
+    }
+
+    // While the on-screen keyboard is shown, the clicks are collected, to be
+    // placed on the screen by the front-end, which knows where it is in the
+    // window.
+    pub fn virtual_keyboard(&self) -> &sdl_vkeyboard::VirtualKeyboard {
+        &self.virtual_keyboard
+    }
+    pub fn take_clicks(&mut self) -> Vec<Click> {
+        std::mem::take(&mut self.clicks)
+    }
+    // Hiding the on-screen keyboard releases its keys, returns whether it's
+    // shown now.
+    pub fn toggle_virtual_keyboard(&mut self,
+                                   emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>,
+                                   keycode_tx: &mpsc::Sender<keyboard::KeyboardQueueEntry>) -> bool {
+
+        let shown = !self.virtual_keyboard.shown();
+        self.event_time = time::Instant::now();
+        if !shown {
+            let held = [self.virtual_keyboard.take_clicked(), self.virtual_keyboard.take_latched()];
+            for key in held.iter().flatten() {
+                self.release_input(Input::Virtual(*key), emu_cmd_tx, keycode_tx);
+            }
+            self.clicks.clear();
+        }
+        self.virtual_keyboard.set_shown(shown);
+        shown
+    }
+    // A click on the on-screen keyboard, at the given position in the dots
+    // of the screen.  SHIFT is latched, the other keys are held down until
+    // the button is released, wherever the mouse is by then, which releases
+    // the latched SHIFT too.
+    pub fn click_virtual_keyboard(&mut self,
+                                  x:           i32,
+                                  y:           i32,
+                                  pressed:     bool,
+                                  at:          time::Instant,
+                                  capture_kbd: bool,
+                                  emu_cmd_tx:  &mpsc::Sender<emulator::EmulatorCommand>,
+                                  keycode_tx:  &mpsc::Sender<keyboard::KeyboardQueueEntry>) {
+
+        self.event_time = at;
+        if !pressed {
+            if let Some(key) = self.virtual_keyboard.take_clicked() {
+                self.release_input(Input::Virtual(key), emu_cmd_tx, keycode_tx);
+                if let Some(shift) = self.virtual_keyboard.take_latched() {
+                    self.release_input(Input::Virtual(shift), emu_cmd_tx, keycode_tx);
+                }
+            }
+        } else if capture_kbd {
+            match self.virtual_keyboard.key_at(x, y) {
+                Some(key) if self.virtual_keyboard.is_shift(key) => {
+                    match self.virtual_keyboard.take_latched() {
+                        Some(shift) => {
+                            self.release_input(Input::Virtual(shift), emu_cmd_tx, keycode_tx);
+                        },
+                        None => {
+                            self.press_input(Input::Virtual(key), self.virtual_keyboard.target(key), emu_cmd_tx, keycode_tx);
+                            self.virtual_keyboard.set_latched(Some(key));
+                        },
+                    }
+                },
+                Some(key) if self.virtual_keyboard.clicked().is_none() => {
+                    self.press_input(Input::Virtual(key), self.virtual_keyboard.target(key), emu_cmd_tx, keycode_tx);
+                    self.virtual_keyboard.set_clicked(Some(key));
+                },
+                _ => { },
+            }
+        }
+    }
+
+    // The menu is put together by the logic core, and shown until an item
+    // is picked, or until it's closed.
+    pub fn menu(&self) -> Option<&sdl_menu::Menu> {
+        self.menu.as_ref()
+    }
+    pub fn set_menu(&mut self, menu: Option<sdl_menu::Menu>) {
+        self.menu = menu;
+    }
+    // While the menu is open, the keys move around it instead of being
+    // typed.
+    fn menu_key(&mut self, scancode: Scancode, emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>) {
+        let menu = match &mut self.menu {
+            Some(menu) => { menu },
+            None => { return; },
+        };
+        match scancode {
+            Scancode::Up => {
+                menu.move_selection(-1);
+            },
+            Scancode::Down => {
+                menu.move_selection(1);
+            },
+            Scancode::Return | Scancode::KpEnter | Scancode::Right => {
+                self.pick_menu_item(emu_cmd_tx);
+            },
+            Scancode::Escape | Scancode::Backspace | Scancode::Left => {
+                if menu.back() {
+                    return;
+                }
+                self.menu = None;
+            },
+            _ => { },
+        }
+    }
+    fn pick_menu_item(&mut self, emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>) {
+        if let Some(menu) = &mut self.menu {
+            match menu.pick() {
+                sdl_menu::Picked::Entered => { },
+                sdl_menu::Picked::Command(command) => {
+                    self.menu = None;
+                    emu_cmd_tx.send(command).unwrap();
+                },
+                sdl_menu::Picked::Closed => {
+                    self.menu = None;
+                },
+            }
+        }
+    }
+    // A click on the menu, at the given position in the dots of the screen,
+    // picks the item under it, and one beside the menu closes it.  Returns
+    // whether the click was taken by the menu, the releases of the button
+    // aren't, they may release a key of the on-screen keyboard.
+    pub fn click_menu(&mut self,
+                      x:          i32,
+                      y:          i32,
+                      pressed:    bool,
+                      emu_cmd_tx: &mpsc::Sender<emulator::EmulatorCommand>) -> bool {
+
+        let menu = match &mut self.menu {
+            Some(menu) if pressed => { menu },
+            _ => { return false; },
+        };
+        if !menu.contains(x, y) {
+            self.menu = None;
+        } else if menu.select_at(x, y) {
+            self.pick_menu_item(emu_cmd_tx);
+        }
+        true
+    }
+
+    // When an event happened, from its timestamp, in the milliseconds since
+    // SDL2 was initialized, so that the keys reach the keyboard matrix at
+    // the right point of the emulated time, rather than when the events got
+    // handled.
+    fn event_instant(&self, timestamp: u32) -> time::Instant {
+        let now = time::Instant::now();
+        match &self.timer {
+            Some(timer) => {
+                let age = time::Duration::from_millis(timer.ticks().wrapping_sub(timestamp) as u64);
+                if age <= MAX_EVENT_AGE { now.checked_sub(age).unwrap_or(now) } else { now }
+            },
+            None => { now },
+        }
+    }
+
+    // Handle SDL events, `closed_window' is set to the ID of a window the
+    // user asked to close, if any.
     pub fn handle_events(&mut self,
                          emu_cmd_tx:         &mpsc::Sender<emulator::EmulatorCommand>,
                          event_pump:         &mut sdl2::EventPump,
                          fullscreen_toggle:  &mut bool,
+                         keyboard_toggle:    &mut bool,
+                         closed_window:      &mut Option<u32>,
                          keycode_tx:         &mpsc::Sender<keyboard::KeyboardQueueEntry>,
                          capture_kbd:        bool) {
         *fullscreen_toggle = false;
+        *keyboard_toggle = false;
+        *closed_window = None;
 
         for event in event_pump.poll_iter() {
+            self.event_time = self.event_instant(event.get_timestamp());
             match event {
 
+                // Keys moving the view of the magnifier, which keep moving it
+                // while they're held down.
+                sdl2::event::Event::KeyDown { scancode: Some(scancode), window_id, .. }
+                    if Some(window_id) == self.magnifier_window && sdl_magnifier::is_view_key(scancode) => {
+
+                    self.magnifier_keys.push(scancode);
+                },
+
                 // Handle user keyboard key presses.
                 sdl2::event::Event::KeyDown { repeat, scancode: scancode_in, .. } => {
 
@@ -439,6 +825,11 @@ impl SdlKeyboard {
 
                                 match scancode {
 
+                                    // F3 cycles through the status overlay levels
+                                    sdl2::keyboard::Scancode::F3 => {
+                                        emu_cmd_tx.send(emulator::EmulatorCommand::CycleOverlay).unwrap();
+                                    },
+
                                     // F4 (un)pauses the emulated machine
                                     sdl2::keyboard::Scancode::F4 => {
                                         emu_cmd_tx.send(emulator::EmulatorCommand::TogglePause).unwrap();
@@ -449,63 +840,50 @@ impl SdlKeyboard {
                                         emu_cmd_tx.send(emulator::EmulatorCommand::ResetHard).unwrap();
                                     },
 
+                                    // F6 opens or closes the magnifier window
+                                    sdl2::keyboard::Scancode::F6 => {
+                                        emu_cmd_tx.send(emulator::EmulatorCommand::SetMagnifier(None)).unwrap();
+                                    },
+
+                                    // F7 types in the text in the clipboard
+                                    sdl2::keyboard::Scancode::F7 => {
+                                        emu_cmd_tx.send(emulator::EmulatorCommand::PasteClipboard).unwrap();
+                                    },
+
+                                    // F8 shows or hides the on-screen keyboard
+                                    sdl2::keyboard::Scancode::F8 => {
+                                        *keyboard_toggle = true;
+                                    },
+
+                                    // F9 opens the menu, which the logic core
+                                    // puts together, or closes it
+                                    sdl2::keyboard::Scancode::F9 => {
+                                        if self.menu.take().is_none() {
+                                            emu_cmd_tx.send(emulator::EmulatorCommand::ShowMenu).unwrap();
+                                        }
+                                    },
+
                                     // F11 toggles the full-screen mode
                                     sdl2::keyboard::Scancode::F11 => {
                                         *fullscreen_toggle = true;
                                     },
 
+                                    // F12 takes a screenshot
+                                    sdl2::keyboard::Scancode::F12 => {
+                                        emu_cmd_tx.send(emulator::EmulatorCommand::Screenshot(None)).unwrap();
+                                    },
+
                                     // General key handling:
-                                    _ => { if capture_kbd {
-
-                                        // Check whether it's a regular key:
-                                        match self.key_map.get(&(scancode as i32)) {
-
-                                            // Simply press down supported keys.
-                                            Some(entry) => {
-                                                keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                    action: keyboard::KeyboardQueueEntryAction::Press,
-                                                    row:    entry.row,
-                                                    column: entry.column,
-                                                    delay:  self.cycles_per_keypress,
-                                                }).unwrap();
-                                            },
-
-                                            // Check whether it's a redundant key:
-                                            None => {
-                                                match self.redundant_key_map.get(&(scancode as i32)) {
-                                                    Some(entry) => {
-                                                        match entry.variant {
-                                                            RedundantKeyVariant::Left => {
-                                                                if !self.redundant_key_ctl[entry.control_index].right_key_pressed {
-                                                                    keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                                        action: keyboard::KeyboardQueueEntryAction::Press,
-                                                                        row:    entry.row,
-                                                                        column: entry.column,
-                                                                        delay:  self.cycles_per_keypress,
-                                                                    }).unwrap();
-                                                                }
-                                                                self.redundant_key_ctl[entry.control_index].left_key_pressed = true;
-                                                            },
-                                                            RedundantKeyVariant::Right => {
-                                                                if !self.redundant_key_ctl[entry.control_index].left_key_pressed {
-                                                                    keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                                        action: keyboard::KeyboardQueueEntryAction::Press,
-                                                                        row:    entry.row,
-                                                                        column: entry.column,
-                                                                        delay:  self.cycles_per_keypress,
-                                                                    }).unwrap();
-                                                                }
-                                                                self.redundant_key_ctl[entry.control_index].right_key_pressed = true;
-                                                            },
-                                                        }
-                                                    }
-
-                                                    // Unsupported keys are simply ignored.
-                                                    None => { },
-                                                }
-                                            },
+                                    _ => {
+                                        if self.menu.is_some() {
+                                            self.menu_key(scancode, emu_cmd_tx);
+                                        } else if capture_kbd {
+                                            // Unsupported keys are simply ignored.
+                                            if let Some(target) = self.key_map.get(&(scancode as i32)).copied() {
+                                                self.press_input(Input::Key(scancode as i32), target, emu_cmd_tx, keycode_tx);
+                                            }
                                         }
-                                    }},
+                                    },
                                 }
                             },
                             None => { },
@@ -523,70 +901,68 @@ impl SdlKeyboard {
                                 // The match's here in case we'd like to add special actions upon key release.
                                 match scancode {
 
-                                    // General key handling:
-                                    _ => { if capture_kbd {
-
-                                        // Check whether it's a regular key:
-                                        match self.key_map.get(&(scancode as i32)) {
-
-                                            // Simply release supported keys.
-                                            Some(entry) => {
-                                                keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                    action: keyboard::KeyboardQueueEntryAction::Release,
-                                                    row:    entry.row,
-                                                    column: entry.column,
-                                                    delay:  self.cycles_per_keypress,
-                                                }).unwrap();
-                                            },
-
-                                            // Check whether it's a redundant key:
-                                            None => {
-                                                match self.redundant_key_map.get(&(scancode as i32)) {
-                                                    Some(entry) => {
-                                                        match entry.variant {
-                                                            RedundantKeyVariant::Left => {
-                                                                if !self.redundant_key_ctl[entry.control_index].right_key_pressed {
-                                                                    keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                                        action: keyboard::KeyboardQueueEntryAction::Release,
-                                                                        row:    entry.row,
-                                                                        column: entry.column,
-                                                                        delay:  self.cycles_per_keypress,
-                                                                    }).unwrap();
-                                                                }
-                                                                self.redundant_key_ctl[entry.control_index].left_key_pressed = false;
-                                                            },
-                                                            RedundantKeyVariant::Right => {
-                                                                if !self.redundant_key_ctl[entry.control_index].left_key_pressed {
-                                                                    keycode_tx.send(keyboard::KeyboardQueueEntry {
-                                                                        action: keyboard::KeyboardQueueEntryAction::Release,
-                                                                        row:    entry.row,
-                                                                        column: entry.column,
-                                                                        delay:  self.cycles_per_keypress,
-                                                                    }).unwrap();
-                                                                }
-                                                                self.redundant_key_ctl[entry.control_index].right_key_pressed = false;
-                                                            },
-                                                        }
-                                                    }
-
-                                                    // Unsupported keys are simply ignored.
-                                                    None => { },
-                                                }
-                                            },
-                                        }
-                                    }},
+                                    // General key handling, the keys pressed
+                                    // while the keyboard was captured are
+                                    // released even if it no longer is:
+                                    _ => {
+                                        self.release_input(Input::Key(scancode as i32), emu_cmd_tx, keycode_tx);
+                                    },
                                 }
                             },
                             None => { },
                         }
                     }
                 },
+                // Handle the text typed, with the symbolic mapping.
+                sdl2::event::Event::TextInput { text, .. } if capture_kbd && self.key_mapping == KeyMapping::Symbolic && self.menu.is_none() => {
+                    self.type_text(&text, keycode_tx);
+                },
+                // Game controllers being plugged in and unplugged, and their
+                // buttons and sticks.
+                sdl2::event::Event::ControllerDeviceAdded { which, .. } => {
+                    self.controllers.add_controller(which);
+                },
+                sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
+                    self.remove_controller(which, emu_cmd_tx, keycode_tx);
+                },
+                sdl2::event::Event::ControllerButtonDown { which, button, .. } => {
+                    let change = sdl_controller::ControlChange { controller: which, control: sdl_controller::Control::Button(button), pressed: true };
+                    self.change_control(change, capture_kbd, emu_cmd_tx, keycode_tx);
+                },
+                sdl2::event::Event::ControllerButtonUp { which, button, .. } => {
+                    let change = sdl_controller::ControlChange { controller: which, control: sdl_controller::Control::Button(button), pressed: false };
+                    self.change_control(change, capture_kbd, emu_cmd_tx, keycode_tx);
+                },
+                sdl2::event::Event::ControllerAxisMotion { which, axis, value, .. } => {
+                    for change in self.controllers.move_axis(which, axis, value) {
+                        self.change_control(change, capture_kbd, emu_cmd_tx, keycode_tx);
+                    }
+                },
+                // Clicks and taps, on the on-screen keyboard or the menu if
+                // either is shown, touch screens send them as the left mouse
+                // button.
+                sdl2::event::Event::MouseButtonDown { window_id, mouse_btn: sdl2::mouse::MouseButton::Left, x, y, .. } if self.virtual_keyboard.shown() || self.menu.is_some() => {
+                    self.clicks.push(Click { window_id, x, y, pressed: true, at: self.event_time });
+                },
+                sdl2::event::Event::MouseButtonUp { window_id, mouse_btn: sdl2::mouse::MouseButton::Left, x, y, .. } if self.virtual_keyboard.shown() || self.menu.is_some() => {
+                    self.clicks.push(Click { window_id, x, y, pressed: false, at: self.event_time });
+                },
+                // Files dragged onto the window are opened by the logic core.
+                sdl2::event::Event::DropFile { filename, .. } => {
+                    emu_cmd_tx.send(emulator::EmulatorCommand::OpenFile(filename)).unwrap();
+                },
                 sdl2::event::Event::Quit {..} => {
                     emu_cmd_tx.send(emulator::EmulatorCommand::Terminate).unwrap();
                 },
+                // With more than one window open, closing one of them
+                // doesn't quit.
+                sdl2::event::Event::Window { window_id, win_event: sdl2::event::WindowEvent::Close, .. } => {
+                    *closed_window = Some(window_id);
+                },
                 // Ignore any unrecognized events.
                 _ => { },
             }
         }
+        self.repeat_key(keycode_tx);
     }
 }
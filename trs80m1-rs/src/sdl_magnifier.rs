@@ -0,0 +1,134 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use sdl2::keyboard::Scancode;
+
+use trs80m1_rs_core::video;
+
+
+// The magnifier window shows an enlarged part of the screen, for reading the
+// text when it's too small to make out on the whole screen.  The part is
+// 1/zoom of the width and the height of the screen, so with the default zoom
+// of 4, it's 16 characters by 4 lines.
+//
+// While the window has the keyboard focus, the arrow keys move the view by a
+// character, Page Up and Page Down change the zoom, and Home and End jump to
+// the start and the end of the line.  The other keys are typed into the
+// emulated machine as usual, so the text can be followed while typing.
+//
+const MIN_ZOOM:          u32 = 2;
+const MAX_ZOOM:          u32 = 8;
+const DEFAULT_ZOOM:      u32 = 4;
+const WINDOW_WIDTH:      u32 = 768;
+const WINDOW_HEIGHT:     u32 = 576;
+
+// Whether the key controls the view of the magnifier.
+pub fn is_view_key(scancode: Scancode) -> bool {
+    matches!(scancode, Scancode::Left | Scancode::Right | Scancode::Up | Scancode::Down |
+                       Scancode::PageUp | Scancode::PageDown | Scancode::Home | Scancode::End)
+}
+
+pub struct MagnifierWindow {
+    canvas: sdl2::render::Canvas<sdl2::video::Window>,
+    zoom:   u32,
+    x:      u32, // The top left corner of the view, in dots of the screen.
+    y:      u32,
+    glyph:  u32, // The width of a character in the current mode, in dots.
+}
+
+impl MagnifierWindow {
+    pub fn open(video_ctxt: &sdl2::VideoSubsystem) -> Result<MagnifierWindow, String> {
+        let window = video_ctxt.window("TRS-80 Model I Emulator - Magnifier", WINDOW_WIDTH, WINDOW_HEIGHT)
+                               .resizable().build().map_err(|error| error.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|error| error.to_string())?;
+
+        Ok(MagnifierWindow { canvas, zoom: DEFAULT_ZOOM, x: 0, y: 0, glyph: video::GLYPH_WIDTH })
+    }
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+    fn view_size(&self) -> (u32, u32) {
+        (video::SCREEN_WIDTH / self.zoom, video::SCREEN_HEIGHT / self.zoom)
+    }
+    // Keep the view on the screen, at whole characters so that they aren't
+    // cut in half at the left and top edges, unless it's at the right or the
+    // bottom edge.
+    fn set_view(&mut self, x: i64, y: i64) {
+        let (width, height) = self.view_size();
+        let x = x.max(0) as u32;
+        let y = y.max(0) as u32;
+        self.x = (x - (x % self.glyph)).min(video::SCREEN_WIDTH - width);
+        self.y = (y - (y % video::GLYPH_HEIGHT_S)).min(video::SCREEN_HEIGHT - height);
+    }
+    fn set_zoom(&mut self, zoom: u32) {
+        let (width, height) = self.view_size();
+        let (center_x, center_y) = (self.x + (width / 2), self.y + (height / 2));
+
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let (width, height) = self.view_size();
+        self.set_view((center_x as i64) - ((width / 2) as i64), (center_y as i64) - ((height / 2) as i64));
+    }
+    pub fn handle_key(&mut self, scancode: Scancode) {
+        let (x, y) = (self.x as i64, self.y as i64);
+        let (column, line) = (self.glyph as i64, video::GLYPH_HEIGHT_S as i64);
+
+        match scancode {
+            Scancode::Left     => { self.set_view(x - column, y); },
+            Scancode::Right    => { self.set_view(x + column, y); },
+            Scancode::Up       => { self.set_view(x, y - line); },
+            Scancode::Down     => { self.set_view(x, y + line); },
+            Scancode::Home     => { self.set_view(0, y); },
+            Scancode::End      => { self.set_view(video::SCREEN_WIDTH as i64, y); },
+            Scancode::PageUp   => { self.set_zoom(self.zoom + 1); },
+            Scancode::PageDown => { self.set_zoom(self.zoom - 1); },
+            _ => { },
+        }
+    }
+    pub fn show(&mut self, frame: &video::VideoFrame, font: &[u8], bg_color: (u8, u8, u8), fg_color: (u8, u8, u8)) -> Result<(), String> {
+        // Line the view up with the characters again when the mode changes.
+        let glyph = if frame.modesel { video::GLYPH_WIDTH_W } else { video::GLYPH_WIDTH };
+        if glyph != self.glyph {
+            self.glyph = glyph;
+            self.set_view(self.x as i64, self.y as i64);
+        }
+
+        let (width, height) = self.view_size();
+        let bitmap = frame.rasterize(font);
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+
+        for y in self.y..(self.y + height) {
+            let start = ((y * video::SCREEN_WIDTH) + self.x) as usize;
+            for lit in &bitmap[start..(start + (width as usize))] {
+                let (red, green, blue) = if *lit != 0 { fg_color } else { bg_color };
+                pixels.extend_from_slice(&[red, green, blue]);
+            }
+        }
+
+        let texture_creator = self.canvas.texture_creator();
+        let mut texture = texture_creator.create_texture_static(sdl2::pixels::PixelFormatEnum::RGB24, width, height)
+                                         .map_err(|error| error.to_string())?;
+        texture.update(None, &pixels, (width * 3) as usize).map_err(|error| error.to_string())?;
+
+        // The view keeps its proportions however the window is resized.
+        self.canvas.set_logical_size(width, height).map_err(|error| error.to_string())?;
+
+        let (bg_red, bg_green, bg_blue) = bg_color;
+        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(bg_red, bg_green, bg_blue));
+        self.canvas.clear();
+        self.canvas.copy(&texture, None, None)?;
+        self.canvas.present();
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::emulator;
+use crate::sdl_video;
+use trs80m1_rs_core::video::*;
+
+
+// The menu, opened with F9, offers the common actions of the emulator over
+// the screen of its window, for the ones who'd rather not use the commands.
+// The logic core puts it together, as it knows what can be done at the
+// moment, like which cassettes there are in the library, and the front-end
+// shows it until an item is picked, or until it's closed.
+//
+// The items are picked with the arrows and ENTER, or with the mouse, and
+// Escape goes back out of a sub-menu.  It's drawn as text, like the on-screen
+// keyboard, with the item selected in inverse video, and in uppercase, as
+// not all of the character generators have the lowercase letters.
+//
+
+// The longer menus scroll, so that they fit onto the screen.
+const MAX_ITEMS_SHOWN: usize = 12;
+
+pub enum MenuAction {
+    Command(emulator::EmulatorCommand), // Sent to the logic core.
+    Submenu(Vec<MenuItem>),
+    Close,
+}
+
+pub struct MenuItem {
+    label:  String,
+    action: MenuAction,
+}
+
+impl MenuItem {
+    pub fn new(label: &str, action: MenuAction) -> MenuItem {
+        MenuItem {
+            label: label.to_uppercase(),
+            action,
+        }
+    }
+}
+
+// What picking an item did.
+pub enum Picked {
+    Entered,
+    Command(emulator::EmulatorCommand),
+    Closed,
+}
+
+struct MenuPage {
+    title:    String,
+    items:    Vec<MenuItem>,
+    selected: usize,
+}
+
+pub struct Menu {
+    pages: Vec<MenuPage>, // The sub-menus entered, the last one is shown.
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<MenuItem>) -> Menu {
+        Menu {
+            pages: vec![MenuPage { title: title.to_uppercase(), items, selected: 0 }],
+        }
+    }
+    fn page(&self) -> &MenuPage {
+        self.pages.last().expect("The menu has no page to show")
+    }
+    fn page_mut(&mut self) -> &mut MenuPage {
+        self.pages.last_mut().expect("The menu has no page to show")
+    }
+    // Move the selection by the given number of items, around the ends.
+    pub fn move_selection(&mut self, offset: i32) {
+        let page = self.page_mut();
+        if !page.items.is_empty() {
+            let count = page.items.len() as i32;
+            page.selected = ((page.selected as i32) + offset).rem_euclid(count) as usize;
+        }
+    }
+    // Enter the sub-menu of the selected item, or take what it does, the
+    // menu is to be closed then.
+    pub fn pick(&mut self) -> Picked {
+        let page = self.page_mut();
+        let item = match page.items.get_mut(page.selected) {
+            Some(item) => { item },
+            None => { return Picked::Closed; },
+        };
+        match std::mem::replace(&mut item.action, MenuAction::Close) {
+            MenuAction::Submenu(items) => {
+                let title = item.label.clone();
+                self.pages.push(MenuPage { title, items, selected: 0 });
+                Picked::Entered
+            },
+            MenuAction::Command(command) => { Picked::Command(command) },
+            MenuAction::Close => { Picked::Closed },
+        }
+    }
+    // Go back out of a sub-menu, its items are put back into the item
+    // which leads to it.  Returns false if the menu is to be closed.
+    pub fn back(&mut self) -> bool {
+        if self.pages.len() < 2 {
+            return false;
+        }
+        let page = self.pages.pop().expect("The menu has no page to go back from");
+        let parent = self.page_mut();
+        parent.items[parent.selected].action = MenuAction::Submenu(page.items);
+        true
+    }
+
+    // The title, a row under it, and the items shown, which start at the
+    // returned one, with a column around them.
+    fn layout(&self) -> (usize, usize, u32, u32) {
+        let page = self.page();
+        let shown = page.items.len().min(MAX_ITEMS_SHOWN);
+        let first = (page.selected + 1).saturating_sub(MAX_ITEMS_SHOWN);
+        let longest = page.items.iter().map(|item| item.label.chars().count()).chain(std::iter::once(page.title.chars().count())).max().unwrap_or(0);
+        let columns = ((longest as u32) + 2).min(SCREEN_COLS);
+        (first, shown, columns, (shown as u32) + 2)
+    }
+    // The part of the screen the menu covers, in its dots, in the middle.
+    pub fn area(&self) -> (i32, i32, u32, u32) {
+        let (_, _, columns, rows) = self.layout();
+        let width = columns * GLYPH_WIDTH;
+        let height = rows * GLYPH_HEIGHT_S;
+        let x = (SCREEN_WIDTH - width) / 2;
+        let y = (SCREEN_HEIGHT - height) / 2;
+        (x as i32, y as i32, width, height)
+    }
+
+    // The menu as a bitmap of `columns' by `rows' characters, like the ones
+    // of sdl_video::rasterize_text, to be stretched over its area.  The title
+    // is underlined, and the selected item is lit.
+    pub fn rasterize(&self, font: &[u8]) -> (u32, u32, Vec<u8>) {
+        let page = self.page();
+        let (first, shown, columns, rows) = self.layout();
+        let mut lines = vec![format!(" {}", page.title), String::new()];
+        lines.extend(page.items.iter().skip(first).take(shown).map(|item| format!(" {}", item.label)));
+        let mut bitmap = sdl_video::rasterize_text(&lines, columns, rows, font);
+
+        let width = columns * GLYPH_WIDTH;
+        let underline = GLYPH_HEIGHT + (GLYPH_HEIGHT / 2);
+        for x in GLYPH_WIDTH..(width - GLYPH_WIDTH) {
+            bitmap[((underline * width) + x) as usize] = 1;
+        }
+        if shown > 0 {
+            let selected_row = (page.selected - first + 2) as u32;
+            for y in (selected_row * GLYPH_HEIGHT)..((selected_row + 1) * GLYPH_HEIGHT) {
+                for x in 0..width {
+                    let dot = &mut bitmap[((y * width) + x) as usize];
+                    *dot ^= 1;
+                }
+            }
+        }
+        (width, rows * GLYPH_HEIGHT, bitmap)
+    }
+    // Whether the given position, in the dots of the screen, is on the menu.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (area_x, area_y, width, height) = self.area();
+        x >= area_x && y >= area_y && x < area_x + (width as i32) && y < area_y + (height as i32)
+    }
+    // Select the item at the given position, returns whether there's one.
+    pub fn select_at(&mut self, x: i32, y: i32) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+        let (_, area_y, _, _) = self.area();
+        let (first, shown, _, _) = self.layout();
+        let row = (((y - area_y) as u32) / GLYPH_HEIGHT_S) as usize;
+        if row < 2 || row - 2 >= shown {
+            return false;
+        }
+        self.page_mut().selected = first + row - 2;
+        true
+    }
+}
@@ -13,19 +13,392 @@
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 //
 
+use log::warn;
 use sdl2;
 
+use std::collections::VecDeque;
+use std::time;
+
 use trs80m1_rs_core::fonts;
+use trs80m1_rs_core::hires;
+use trs80m1_rs_core::machine;
 use trs80m1_rs_core::video::*;
 
 
+// How the screen is scaled up to the size of the window:
+//
+//  - Integer:  by a whole number of times, so that every dot of the screen
+//              is the same size, with a border around it.
+//  - Aspect:   as large as fits, keeping the 4:3 aspect ratio of the screen,
+//              with bars on the sides if the window is wider or taller.
+//  - Stretch:  to fill the whole window, whatever its shape.
+//
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum Scaling {
+    Integer,
+    Aspect,
+    Stretch,
+}
+
+// What draws the picture into the window:
+//
+//  - Sdl:      the SDL2 renderer, either hardware accelerated or not, as set
+//              with the `use_hw_accel' option.
+//  - OpenGl:   OpenGL directly, with shaders, which needs the emulator to be
+//              built with the `opengl' feature (see gl_video.rs).
+//
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum Renderer {
+    Sdl,
+    OpenGl,
+}
+
+// Post-processing that makes the picture look more like it did on the CRT
+// monitor of the machine, each of the effects has an intensity in percent,
+// 0 turns it off:
+//
+//  - Scanlines:  the gaps between the lines traced by the electron beam, the
+//                second of the two rows of each scanline is darkened.
+//  - Mask:       the stripes of red, green and blue phosphors, every column
+//                of the screen is tinted towards one of them in turn.
+//  - Curvature:  the bulge of the glass, the edges of the picture are bent
+//                inwards, by up to MAX_CURVATURE of its size at the corners.
+//  - Persistence: the afterglow of the phosphors, each frame is blended with
+//                the previous ones, this is the part of the brightness that
+//                is kept from one frame to the next.  Programs which flicker
+//                objects on alternate frames rely on it to show them.
+//
+// The screen is drawn into a texture first, and the effects are applied to
+// it before it's scaled up to the window.
+//
+#[derive(Copy, Clone, PartialEq, Default)]
+pub struct CrtEffects {
+    pub scanlines:   u32,
+    pub mask:        u32,
+    pub curvature:   u32,
+    pub persistence: u32,
+}
+
+impl CrtEffects {
+    pub fn enabled(&self) -> bool {
+        self.scanlines > 0 || self.mask > 0 || self.curvature > 0 || self.persistence > 0
+    }
+}
+
+// How many of the frames of the machine are left out between the ones which
+// are drawn, to take some of the load off of slow computers:
+//
+//  - Auto:     as many as it takes for drawing them to keep up with the
+//              machine, none if the computer is fast enough.
+//  - Fixed:    always the given number.
+//
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum FrameSkip {
+    Auto,
+    Fixed(u32),
+}
+
+pub const MAX_FRAME_SKIP: u32 = 9;
+
+// Decides which frames are drawn.
+//
+// The time drawing a frame takes is averaged, not counting the wait for the
+// vertical blank in presenting it, since with vertical synchronization, it's
+// the display which paces the drawing.  In the automatic mode, frames are
+// skipped once the drawing takes up most of the time the machine spends on
+// the frames between the drawn ones, and drawn again once it gets well below
+// that.
+pub struct FramePacer {
+    frame_skip:   FrameSkip,
+    skip:         u32,  // The number of frames left out at the moment.
+    skipped:      u32,  // Frames left out since the last one drawn.
+    draw_time_ns: u32,
+}
+
+impl FramePacer {
+    pub fn new(frame_skip: FrameSkip) -> FramePacer {
+        let mut pacer = FramePacer {
+            frame_skip,
+            skip:         0,
+            skipped:      0,
+            draw_time_ns: 0,
+        };
+        pacer.set_frame_skip(frame_skip);
+        pacer
+    }
+    pub fn set_frame_skip(&mut self, frame_skip: FrameSkip) {
+        self.frame_skip = frame_skip;
+        self.skip = match frame_skip {
+            FrameSkip::Auto => { 0 },
+            FrameSkip::Fixed(skip) => { skip.min(MAX_FRAME_SKIP) },
+        };
+        self.skipped = 0;
+    }
+    // Whether the next frame should be drawn, or left out.
+    pub fn draw_next(&mut self) -> bool {
+        if self.skipped >= self.skip {
+            self.skipped = 0;
+            true
+        } else {
+            self.skipped += 1;
+            false
+        }
+    }
+    // Account for the time drawing a frame took.
+    pub fn frame_drawn(&mut self, duration: time::Duration) {
+        let duration_ns = duration.as_nanos().min(1_000_000_000) as u32;
+        self.draw_time_ns = self.draw_time_ns - (self.draw_time_ns / 8) + (duration_ns / 8);
+
+        if self.frame_skip == FrameSkip::Auto {
+            let budget_ns = (self.skip + 1) * machine::NS_PER_FRAME;
+
+            if self.draw_time_ns > (budget_ns / 4) * 3 && self.skip < MAX_FRAME_SKIP {
+                self.skip += 1;
+            } else if self.skip > 0 && self.draw_time_ns < (self.skip * machine::NS_PER_FRAME) / 2 {
+                self.skip -= 1;
+            }
+        }
+    }
+}
+
+// How much the status overlay in the corner of the window shows:
+//
+//  - Off:      nothing, other than the speed while the warp is on.
+//  - Speed:    the speed of the emulation, in percent of the real machine,
+//              and the number of frames drawn per second.
+//  - Full:     the speed and the frame rate, along with the tape counter and
+//              the state of the cassette motor.
+//
+#[derive(Copy, Clone, PartialEq, Debug)] // For the config system.
+pub enum OverlayLevel {
+    Off,
+    Speed,
+    Full,
+}
+
+impl OverlayLevel {
+    // The next level, for cycling through them with a key.
+    pub fn next(self) -> OverlayLevel {
+        match self {
+            OverlayLevel::Off   => { OverlayLevel::Speed },
+            OverlayLevel::Speed => { OverlayLevel::Full },
+            OverlayLevel::Full  => { OverlayLevel::Off },
+        }
+    }
+}
+
+// The state of the machine shown by the overlay, as reported by the logic
+// core every OVERLAY_UPDATE_INTERVAL.
+#[derive(Clone, Default)]
+pub struct MachineStatus {
+    pub speed:        Option<u32>, // None while the machine isn't running.
+    pub warp:         bool,
+    pub tape_counter: Option<(usize, usize)>,
+    pub tape_motor:   bool,
+}
+
+pub const OVERLAY_UPDATE_INTERVAL: time::Duration = time::Duration::from_millis(500);
+
+// The distance of the overlay from the corner, in dots of the screen.
+pub const OVERLAY_MARGIN: u32 = 4;
+
+pub struct Overlay {
+    level:         OverlayLevel,
+    status:        MachineStatus,
+    frames:        u32,
+    frame_rate:    u32,
+    counted_since: time::Instant,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        Overlay {
+            level:         OverlayLevel::Off,
+            status:        MachineStatus::default(),
+            frames:        0,
+            frame_rate:    0,
+            counted_since: time::Instant::now(),
+        }
+    }
+    pub fn set_level(&mut self, level: OverlayLevel) {
+        self.level = level;
+    }
+    pub fn set_status(&mut self, status: MachineStatus) {
+        self.status = status;
+    }
+    // Count a frame drawn into the window, the frame rate is worked out
+    // about once a second, and returned when it is, for the status bar of
+    // the curses-based interface.
+    pub fn frame_drawn(&mut self) -> Option<u32> {
+        self.frames += 1;
+
+        let elapsed = self.counted_since.elapsed();
+        if elapsed >= time::Duration::from_secs(1) {
+            self.frame_rate = ((self.frames as u128 * 1_000) / elapsed.as_millis()) as u32;
+            self.frames = 0;
+            self.counted_since = time::Instant::now();
+            Some(self.frame_rate)
+        } else {
+            None
+        }
+    }
+    // The lines of text shown, none with the overlay turned off.
+    pub fn lines(&self) -> Vec<String> {
+        let speed = match self.status.speed {
+            Some(speed) => { format!("{}%", speed) },
+            None => { "STOPPED".to_owned() },
+        };
+        match self.level {
+            OverlayLevel::Off if self.status.warp => {
+                vec![format!("WARP {}", speed)]
+            },
+            OverlayLevel::Off => {
+                Vec::new()
+            },
+            OverlayLevel::Speed => {
+                vec![format!("{}{} {} FPS", if self.status.warp { "WARP " } else { "" }, speed, self.frame_rate)]
+            },
+            OverlayLevel::Full => {
+                let tape = match self.status.tape_counter {
+                    Some((position, length)) => { format!("TAPE {}/{}", position, length) },
+                    None => { "TAPE EMPTY".to_owned() },
+                };
+                vec![format!("{} {}", if self.status.warp { "WARP" } else { "SPEED" }, speed),
+                     format!("{} FPS", self.frame_rate),
+                     format!("{}{}", tape, if self.status.tape_motor { " MOTOR" } else { "" })]
+            },
+        }
+    }
+}
+
+// The notifications shown for a while at the bottom of the window, about
+// what the commands did, like inserting a cassette or saving the state of
+// the machine, so that they can be seen without looking at the terminal.
+pub const NOTIFICATION_TIME: time::Duration = time::Duration::from_secs(3);
+const MAX_NOTIFICATIONS: usize = 3;
+
+pub struct Notifications {
+    enabled: bool,
+    shown:   VecDeque<(String, time::Instant)>,
+}
+
+impl Notifications {
+    pub fn new() -> Notifications {
+        Notifications {
+            enabled: true,
+            shown:   VecDeque::new(),
+        }
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.shown.clear();
+        }
+    }
+    // The text is shortened to fit on a row of the screen, the oldest
+    // notification goes away if there are too many.
+    pub fn push(&mut self, text: &str) {
+        if !self.enabled {
+            return;
+        }
+        let text: String = text.to_uppercase().chars().take((SCREEN_COLS as usize) - 2).collect();
+        self.shown.push_back((text, time::Instant::now()));
+        while self.shown.len() > MAX_NOTIFICATIONS {
+            self.shown.pop_front();
+        }
+    }
+    // The lines still shown, the ones shown for long enough are dropped.
+    pub fn lines(&mut self) -> Vec<String> {
+        self.shown.retain(|(_, shown_at)| shown_at.elapsed() < NOTIFICATION_TIME);
+        self.shown.iter().map(|(text, _)| format!(" {} ", text)).collect()
+    }
+}
+
+// The part of the screen the notifications with the given lines cover, in
+// its dots, in the middle of the bottom.
+pub fn notifications_area(lines: &[String]) -> (i32, i32, u32, u32) {
+    let (columns, rows) = overlay_size(lines);
+    let width = columns * GLYPH_WIDTH;
+    let height = rows * GLYPH_HEIGHT_S;
+    let x = (SCREEN_WIDTH - width) / 2;
+    let y = SCREEN_HEIGHT - height - OVERLAY_MARGIN;
+    (x as i32, y as i32, width, height)
+}
+
+pub const MAX_CURVATURE: f32 = 0.08;
+pub const MAX_PERSISTENCE: u32 = 95;
+
+// The brightness and contrast knobs of the monitor, in percent, 100 leaves
+// the colors as they are.  The contrast sets the gain of the signal, and the
+// brightness the level of black, moving all of the colors up or down by up
+// to half of the full range.  They're applied to the colors of the screen,
+// so all of the renderers show them.
+pub const MAX_BRIGHTNESS: u32 = 200;
+pub const MAX_CONTRAST: u32 = 200;
+
+pub fn adjust_color(color: (u8, u8, u8), brightness: u32, contrast: u32) -> (u8, u8, u8) {
+    let gain = (contrast.min(MAX_CONTRAST) as f32) / 100.0;
+    let offset = ((brightness.min(MAX_BRIGHTNESS) as f32) - 100.0) / 200.0;
+    let adjust = |value: u8| {
+        ((((value as f32) / 255.0) * gain + offset).clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+    let (red, green, blue) = color;
+    (adjust(red), adjust(green), adjust(blue))
+}
+
+// The size of the strips the picture is cut into to bend it, in dots:
+const CURVATURE_STRIP: u32 = 2;
+
+pub struct CrtTextures<'t> {
+    screen:     sdl2::render::Texture<'t>,
+    bent:       sdl2::render::Texture<'t>,
+    glow:       sdl2::render::Texture<'t>,
+    glow_valid: bool,           // Whether `glow' holds the previous frame.
+    mask:       sdl2::render::Texture<'t>,
+    mask_level: u32,
+}
+
+// The textures the screen is drawn with, the glyphs, and the text layer of
+// the screen, which is kept from frame to frame, so that only the characters
+// that changed have to be drawn into it again.  Without support for render
+// targets, all of the characters are drawn for each frame instead.
+pub struct ScreenTextures<'t> {
+    narrow: Box<[sdl2::render::Texture<'t>]>,
+    wide:   Box<[sdl2::render::Texture<'t>]>,
+    text:   Option<sdl2::render::Texture<'t>>,
+    drawn:  Option<(bool, bool)>, // The mode of the text layer.
+    dirty:  DirtyCells,           // Not drawn into it yet.
+}
+
+impl<'t> ScreenTextures<'t> {
+    pub fn new(video_bg_color:  (u8, u8, u8),
+               video_fg_color:  (u8, u8, u8),
+               font:            &[u8],
+               texture_creator: &'t sdl2::render::TextureCreator<sdl2::video::WindowContext>) -> ScreenTextures<'t> {
+
+        let (narrow, wide) = generate_glyph_textures(video_bg_color, video_fg_color, font, texture_creator);
+        let text = match texture_creator.create_texture_target(sdl2::pixels::PixelFormatEnum::ARGB8888, SCREEN_WIDTH, SCREEN_HEIGHT) {
+            Ok(mut texture) => {
+                texture.set_blend_mode(sdl2::render::BlendMode::None);
+                Some(texture)
+            },
+            Err(..) => { None },
+        };
+        ScreenTextures { narrow, wide, text, drawn: None, dirty: DirtyCells::default() }
+    }
+    // Add the characters changed since the previous frame was drawn.
+    pub fn add_dirty(&mut self, dirty: &DirtyCells) {
+        add_dirty(&mut self.dirty, dirty);
+    }
+}
+
 fn rgb888_into_rgb332(red: u8, green: u8, blue: u8) -> u8 {
     (red    & 0b111_000_00) |
     ((green & 0b111_000_00) >> 3) |
     ((blue  & 0b110_000_00) >> 6)
 }
 
-fn font_for_cg_num(character_generator: u32) -> &'static [u8] {
+pub fn font_for_cg_num(character_generator: u32) -> &'static [u8] {
     match character_generator {
         1 => { &fonts::FONT_CG0 },
         2 => { &fonts::FONT_CG1 },
@@ -34,10 +407,11 @@ fn font_for_cg_num(character_generator: u32) -> &'static [u8] {
     }
 }
 
-// Generate textures for the screen tiles.
+// Generate textures for the screen tiles, one for each of the glyphs, see
+// fonts::glyph_index().
 pub fn generate_glyph_textures<'t>(video_bg_color:  (u8, u8, u8),
                                    video_fg_color:  (u8, u8, u8),
-                                   video_character_generator: u32,
+                                   font:            &[u8],
                                    texture_creator: &'t sdl2::render::TextureCreator<sdl2::video::WindowContext>)
            -> (Box<[sdl2::render::Texture<'t>]>, Box<[sdl2::render::Texture<'t>]>) {
 
@@ -50,20 +424,11 @@ pub fn generate_glyph_textures<'t>(video_bg_color:  (u8, u8, u8),
     let (red, green, blue) = video_fg_color;
     let fg_color = rgb888_into_rgb332(red, green, blue);
 
-    let font = font_for_cg_num(video_character_generator);
-
 
-    for glyph_iter in 0..256 {
+    for glyph_iter in 0..fonts::GLYPH_TOTAL {
         let mut texture = texture_creator.create_texture(sdl2::pixels::PixelFormatEnum::RGB332,
             sdl2::render::TextureAccess::Static, GLYPH_WIDTH, GLYPH_HEIGHT_S).unwrap();
-        let font_glyph: &[u8];
-        if (glyph_iter & 0x80) == 0 {
-            let font_index = ((glyph_iter as u32) * fonts::FONT_GLYPH_BYTES) as usize;
-            font_glyph = &font[font_index..(font_index + (fonts::FONT_GLYPH_BYTES as usize))];
-        } else {
-            let graph_index = (((glyph_iter & 0b0011_1111) as u32) * fonts::FONT_GLYPH_BYTES) as usize;
-            font_glyph = &fonts::GRAPH_FONT[graph_index..(graph_index + (fonts::FONT_GLYPH_BYTES as usize))];
-        }
+        let font_glyph = fonts::glyph_rows(font, glyph_iter);
         assert!(font_glyph.len() == (GLYPH_HEIGHT as usize));
 
         let mut pixel_data: [u8; (GLYPH_WIDTH * GLYPH_HEIGHT_S) as usize] = [bg_color; (GLYPH_WIDTH * GLYPH_HEIGHT_S) as usize];
@@ -84,17 +449,10 @@ pub fn generate_glyph_textures<'t>(video_bg_color:  (u8, u8, u8),
 
         narrow.push(texture);
     }
-    for glyph_iter in 0..256 {
+    for glyph_iter in 0..fonts::GLYPH_TOTAL {
         let mut texture = texture_creator.create_texture(sdl2::pixels::PixelFormatEnum::RGB332,
             sdl2::render::TextureAccess::Static, GLYPH_WIDTH_W, GLYPH_HEIGHT_S).unwrap();
-        let font_glyph: &[u8];
-        if (glyph_iter & 0x80) == 0 {
-            let font_index = ((glyph_iter as u32) * fonts::FONT_GLYPH_BYTES) as usize;
-            font_glyph = &font[font_index..(font_index + (fonts::FONT_GLYPH_BYTES as usize))];
-        } else {
-            let graph_index = (((glyph_iter & 0b0011_1111) as u32) * fonts::FONT_GLYPH_BYTES) as usize;
-            font_glyph = &fonts::GRAPH_FONT[graph_index..(graph_index + (fonts::FONT_GLYPH_BYTES as usize))];
-        }
+        let font_glyph = fonts::glyph_rows(font, glyph_iter);
         assert!(font_glyph.len() == (GLYPH_HEIGHT as usize));
 
         let mut pixel_data: [u8; (GLYPH_WIDTH_W * GLYPH_HEIGHT_S) as usize] = [bg_color; (GLYPH_WIDTH_W * GLYPH_HEIGHT_S) as usize];
@@ -118,34 +476,446 @@ pub fn generate_glyph_textures<'t>(video_bg_color:  (u8, u8, u8),
         wide.push(texture);
     }
 
-    assert!(narrow.len() == 256);
-    assert!(wide.len() == 256);
+    assert!(narrow.len() == (fonts::GLYPH_TOTAL as usize));
+    assert!(wide.len() == (fonts::GLYPH_TOTAL as usize));
     (narrow.into_boxed_slice(), wide.into_boxed_slice())
 }
 
-// Render the screen contents:
+// Create the textures used for the CRT effects, which requires the renderer
+// to support render targets.
+pub fn generate_crt_textures<'t>(texture_creator: &'t sdl2::render::TextureCreator<sdl2::video::WindowContext>) -> Result<CrtTextures<'t>, String> {
+    let mut screen = texture_creator.create_texture_target(sdl2::pixels::PixelFormatEnum::ARGB8888, SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+    let mut bent = texture_creator.create_texture_target(sdl2::pixels::PixelFormatEnum::ARGB8888, SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+    let mut glow = texture_creator.create_texture_target(sdl2::pixels::PixelFormatEnum::ARGB8888, SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+    let mut mask = texture_creator.create_texture_static(sdl2::pixels::PixelFormatEnum::RGB24, SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+
+    screen.set_blend_mode(sdl2::render::BlendMode::None);
+    bent.set_blend_mode(sdl2::render::BlendMode::None);
+    glow.set_blend_mode(sdl2::render::BlendMode::None);
+    mask.set_blend_mode(sdl2::render::BlendMode::Mod);
+
+    Ok(CrtTextures {
+        screen,
+        bent,
+        glow,
+        glow_valid: false,
+        mask,
+        mask_level: 0,
+    })
+}
+
+// Fill in the phosphor mask for the given intensity, the texture is used to
+// multiply the screen with.
+fn update_mask(textures: &mut CrtTextures, intensity: u32) {
+    if textures.mask_level == intensity {
+        return;
+    }
+    let dimmed = (255 - (intensity.min(100) * 255) / 100) as u8;
+    let mut pixel_data = vec![0u8; (SCREEN_WIDTH * SCREEN_HEIGHT * 3) as usize];
+
+    for (index, pixel) in pixel_data.chunks_mut(3).enumerate() {
+        let phosphor = (index % (SCREEN_WIDTH as usize)) % 3;
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            *value = if channel == phosphor { 255 } else { dimmed };
+        }
+    }
+    textures.mask.update(None, &pixel_data, (SCREEN_WIDTH * 3) as usize).unwrap();
+    textures.mask_level = intensity;
+}
+
+// Set up the renderer for the selected scaling mode.  The scaling is done
+// with the nearest-neighbor filter, the default of SDL2.
+pub fn set_scaling(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, scaling: Scaling) -> Result<(), String> {
+    match scaling {
+        Scaling::Integer | Scaling::Aspect => {
+            canvas.set_logical_size(SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+            canvas.set_integer_scale(scaling == Scaling::Integer)
+        },
+        Scaling::Stretch => {
+            // Clears the logical size, the scale is set for each frame.
+            canvas.set_logical_size(0, 0).map_err(|error| error.to_string())?;
+            canvas.set_integer_scale(false)
+        },
+    }
+}
+
+// Render the screen contents, the caller presents them:
 pub fn render(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
-              narrow: &Box<[sdl2::render::Texture]>,
-              wide: &Box<[sdl2::render::Texture]>,
-              frame: &VideoFrame) {
+              screen: &mut ScreenTextures,
+              frame: &VideoFrame,
+              fg_color: (u8, u8, u8),
+              scaling: Scaling) {
 
+    if scaling == Scaling::Stretch {
+        if let Ok((width, height)) = canvas.output_size() {
+            canvas.set_scale((width as f32) / (SCREEN_WIDTH as f32), (height as f32) / (SCREEN_HEIGHT as f32)).unwrap();
+        }
+    }
+    update_text_layer(canvas, screen, frame);
     canvas.clear();
-    if !frame.modesel {
-        for glyph_y in 0..SCREEN_ROWS {
-            for glyph_x in 0..SCREEN_COLS {
-                let glyph_texture = &narrow[frame.memory[((glyph_y * SCREEN_COLS) as usize) + (glyph_x as usize)] as usize];
-                let dest = sdl2::rect::Rect::new((glyph_x as i32) * (GLYPH_WIDTH as i32), (glyph_y as i32) * (GLYPH_HEIGHT_S as i32), GLYPH_WIDTH, GLYPH_HEIGHT_S);
-                canvas.copy(glyph_texture, None, Some(dest)).unwrap();
+    draw_screen(canvas, screen, frame, fg_color);
+}
+
+// Render the screen contents with the CRT effects applied, the caller
+// presents them.
+pub fn render_crt(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                  screen: &mut ScreenTextures,
+                  frame: &VideoFrame,
+                  fg_color: (u8, u8, u8),
+                  scaling: Scaling,
+                  textures: &mut CrtTextures,
+                  effects: CrtEffects) -> Result<(), String> {
+
+    update_mask(textures, effects.mask);
+    update_text_layer(canvas, screen, frame);
+    let draw_color = canvas.draw_color();
+
+    // The new frame is mixed into the afterglow of the previous ones, by
+    // drawing it over them with the transparency of the part that fades
+    // away.  The bent texture is free to hold the new frame until then.
+    if effects.persistence > 0 {
+        canvas.with_texture_canvas(&mut textures.bent, |bent_canvas| {
+            bent_canvas.clear();
+            draw_screen(bent_canvas, screen, frame, fg_color);
+        }).map_err(|error| error.to_string())?;
+
+        let fade = if textures.glow_valid {
+            255 - ((effects.persistence.min(MAX_PERSISTENCE) * 255) / 100) as u8
+        } else {
+            255
+        };
+        textures.bent.set_blend_mode(sdl2::render::BlendMode::Blend);
+        textures.bent.set_alpha_mod(fade);
+        let bent = &textures.bent;
+        let result = canvas.with_texture_canvas(&mut textures.glow, |glow_canvas| {
+            glow_canvas.copy(bent, None, None).unwrap();
+        });
+        textures.bent.set_blend_mode(sdl2::render::BlendMode::None);
+        textures.bent.set_alpha_mod(255);
+        result.map_err(|error| error.to_string())?;
+        textures.glow_valid = true;
+    } else {
+        textures.glow_valid = false;
+    }
+
+    let mask = &textures.mask;
+    let glow = &textures.glow;
+    canvas.with_texture_canvas(&mut textures.screen, |screen_canvas| {
+        if effects.persistence > 0 {
+            screen_canvas.copy(glow, None, None).unwrap();
+        } else {
+            screen_canvas.clear();
+            draw_screen(screen_canvas, screen, frame, fg_color);
+        }
+
+        if effects.scanlines > 0 {
+            let alpha = ((effects.scanlines.min(100) * 255) / 100) as u8;
+            let gaps: Vec<sdl2::rect::Rect> = (0..(SCREEN_HEIGHT / 2)).map(|line| sdl2::rect::Rect::new(0, (line * 2 + 1) as i32, SCREEN_WIDTH, 1)).collect();
+            screen_canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+            screen_canvas.set_draw_color(sdl2::pixels::Color::RGBA(0, 0, 0, alpha));
+            screen_canvas.fill_rects(&gaps).unwrap();
+            screen_canvas.set_blend_mode(sdl2::render::BlendMode::None);
+        }
+        if effects.mask > 0 {
+            screen_canvas.copy(mask, None, None).unwrap();
+        }
+    }).map_err(|error| error.to_string())?;
+
+    if effects.curvature > 0 {
+        let amount = MAX_CURVATURE * (effects.curvature.min(100) as f32) / 100.0;
+        let screen = &textures.screen;
+
+        // The sides are bent by shrinking the rows, away from the middle:
+        canvas.with_texture_canvas(&mut textures.bent, |bent_canvas| {
+            bent_canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
+            bent_canvas.clear();
+            for row in 0..(SCREEN_HEIGHT / CURVATURE_STRIP) {
+                let y = row * CURVATURE_STRIP;
+                let width = bent_size(SCREEN_WIDTH, y + (CURVATURE_STRIP / 2), SCREEN_HEIGHT, amount);
+                let source = sdl2::rect::Rect::new(0, y as i32, SCREEN_WIDTH, CURVATURE_STRIP);
+                let dest = sdl2::rect::Rect::new(((SCREEN_WIDTH - width) / 2) as i32, y as i32, width, CURVATURE_STRIP);
+                bent_canvas.copy(screen, Some(source), Some(dest)).unwrap();
             }
+        }).map_err(|error| error.to_string())?;
+
+        // And the top and bottom by shrinking the columns:
+        let bent = &textures.bent;
+        canvas.with_texture_canvas(&mut textures.screen, |screen_canvas| {
+            screen_canvas.clear();
+            for column in 0..(SCREEN_WIDTH / CURVATURE_STRIP) {
+                let x = column * CURVATURE_STRIP;
+                let height = bent_size(SCREEN_HEIGHT, x + (CURVATURE_STRIP / 2), SCREEN_WIDTH, amount);
+                let source = sdl2::rect::Rect::new(x as i32, 0, CURVATURE_STRIP, SCREEN_HEIGHT);
+                let dest = sdl2::rect::Rect::new(x as i32, ((SCREEN_HEIGHT - height) / 2) as i32, CURVATURE_STRIP, height);
+                screen_canvas.copy(bent, Some(source), Some(dest)).unwrap();
+            }
+        }).map_err(|error| error.to_string())?;
+    }
+
+    if scaling == Scaling::Stretch {
+        if let Ok((width, height)) = canvas.output_size() {
+            canvas.set_scale((width as f32) / (SCREEN_WIDTH as f32), (height as f32) / (SCREEN_HEIGHT as f32)).unwrap();
         }
-    } else {
-        for glyph_y in 0..SCREEN_ROWS {
-            for glyph_x in 0..SCREEN_COLS_W {
-                let glyph_texture = &wide[frame.memory[((glyph_y * SCREEN_COLS) as usize) + ((glyph_x * 2) as usize)] as usize];
-                let dest = sdl2::rect::Rect::new((glyph_x as i32) * (GLYPH_WIDTH_W as i32), (glyph_y as i32) * (GLYPH_HEIGHT_S as i32), GLYPH_WIDTH_W, GLYPH_HEIGHT_S);
-                canvas.copy(glyph_texture, None, Some(dest)).unwrap();
+    }
+    canvas.set_draw_color(draw_color);
+    canvas.clear();
+    canvas.copy(&textures.screen, None, None)?;
+    Ok(())
+}
+
+// Read back the picture rendered into the window, at the resolution of the
+// window, as rows of RGB pixels.  The scaling has to be set up again
+// afterwards, since it would only read the area of the screen otherwise.
+pub fn capture_window(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, scaling: Scaling) -> Result<(u32, u32, Vec<u8>), String> {
+    let (width, height) = canvas.output_size()?;
+
+    canvas.set_logical_size(0, 0).map_err(|error| error.to_string())?;
+    canvas.set_scale(1.0, 1.0)?;
+    let pixels = canvas.read_pixels(None, sdl2::pixels::PixelFormatEnum::RGB24);
+    set_scaling(canvas, scaling)?;
+
+    Ok((width, height, pixels?))
+}
+
+// Render the screen contents at their native resolution, without any of
+// the CRT effects, and read them back as rows of RGB pixels.
+pub fn capture_native(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                      texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+                      screen: &mut ScreenTextures,
+                      frame: &VideoFrame,
+                      fg_color: (u8, u8, u8)) -> Result<(u32, u32, Vec<u8>), String> {
+
+    update_text_layer(canvas, screen, frame);
+
+    let mut texture = texture_creator.create_texture_target(sdl2::pixels::PixelFormatEnum::ARGB8888, SCREEN_WIDTH, SCREEN_HEIGHT).map_err(|error| error.to_string())?;
+    let mut pixels = Err("the picture wasn't rendered".to_owned());
+
+    canvas.with_texture_canvas(&mut texture, |texture_canvas| {
+        texture_canvas.clear();
+        draw_screen(texture_canvas, screen, frame, fg_color);
+        pixels = texture_canvas.read_pixels(None, sdl2::pixels::PixelFormatEnum::RGB24);
+    }).map_err(|error| error.to_string())?;
+
+    Ok((SCREEN_WIDTH, SCREEN_HEIGHT, pixels?))
+}
+
+// The screen contents at their native resolution, drawn without any of the
+// renderers, as rows of RGB pixels.
+pub fn rasterize_rgb(frame: &VideoFrame, font: &[u8], bg_color: (u8, u8, u8), fg_color: (u8, u8, u8)) -> (u32, u32, Vec<u8>) {
+    let (bg_red, bg_green, bg_blue) = bg_color;
+    let (fg_red, fg_green, fg_blue) = fg_color;
+
+    let pixels = frame.rasterize(font).iter().flat_map(|lit| {
+        if *lit != 0 { [fg_red, fg_green, fg_blue] } else { [bg_red, bg_green, bg_blue] }
+    }).collect();
+    (SCREEN_WIDTH, SCREEN_HEIGHT, pixels)
+}
+
+// Draw lines of text into a bitmap of `columns' by `rows' characters, each
+// GLYPH_WIDTH dots wide and GLYPH_HEIGHT dots tall, stored row by row, 1
+// for the lit dots and 0 for the rest, using the given character generator
+// font.  Characters the font doesn't have are drawn as `.'.
+pub fn rasterize_text(lines: &[String], columns: u32, rows: u32, font: &[u8]) -> Vec<u8> {
+    let width = columns * GLYPH_WIDTH;
+    let mut bitmap = vec![0u8; (width * rows * GLYPH_HEIGHT) as usize];
+
+    for (row, line) in lines.iter().take(rows as usize).enumerate() {
+        for (column, character) in line.chars().take(columns as usize).enumerate() {
+            let glyph = if (' '..='~').contains(&character) { character as usize } else { '.' as usize };
+            let glyph_rows = &font[(glyph * (fonts::FONT_GLYPH_BYTES as usize))..((glyph + 1) * (fonts::FONT_GLYPH_BYTES as usize))];
+
+            for (glyph_y, dots) in glyph_rows.iter().enumerate() {
+                for glyph_x in 0..GLYPH_WIDTH {
+                    if (dots & (1 << glyph_x)) != 0 {
+                        let x = ((column as u32) * GLYPH_WIDTH) + glyph_x;
+                        let y = ((row as u32) * GLYPH_HEIGHT) + (glyph_y as u32);
+                        bitmap[((y * width) + x) as usize] = 1;
+                    }
+                }
             }
         }
     }
-    canvas.present();
+    bitmap
+}
+
+// The size of the overlay showing the given lines, in characters.
+pub fn overlay_size(lines: &[String]) -> (u32, u32) {
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    (columns as u32, lines.len() as u32)
+}
+
+// Draw the overlay into the top left corner of the screen, over what's
+// already been rendered, the caller presents it.
+pub fn render_overlay(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                      texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+                      lines: &[String],
+                      font: &[u8],
+                      bg_color: (u8, u8, u8),
+                      fg_color: (u8, u8, u8)) -> Result<(), String> {
+
+    let (columns, rows) = overlay_size(lines);
+    let (width, height) = (columns * GLYPH_WIDTH, rows * GLYPH_HEIGHT);
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let bitmap = rasterize_text(lines, columns, rows, font);
+    let area = sdl2::rect::Rect::new(OVERLAY_MARGIN as i32, OVERLAY_MARGIN as i32, width, height);
+    render_bitmap(canvas, texture_creator, &bitmap, width, height, area, bg_color, fg_color)
+}
+
+// Draw a bitmap of `width' by `height' dots, 1 for the lit ones and 0 for
+// the rest, stretched over the given area of the screen, in its dots, over
+// what's already been rendered, the caller presents it.
+pub fn render_bitmap(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+                     bitmap: &[u8],
+                     width: u32,
+                     height: u32,
+                     area: sdl2::rect::Rect,
+                     bg_color: (u8, u8, u8),
+                     fg_color: (u8, u8, u8)) -> Result<(), String> {
+
+    let (bg_red, bg_green, bg_blue) = bg_color;
+    let (fg_red, fg_green, fg_blue) = fg_color;
+    let pixels: Vec<u8> = bitmap.iter().flat_map(|lit| {
+        if *lit != 0 { [fg_red, fg_green, fg_blue] } else { [bg_red, bg_green, bg_blue] }
+    }).collect();
+
+    let mut texture = texture_creator.create_texture_static(sdl2::pixels::PixelFormatEnum::RGB24, width, height).map_err(|error| error.to_string())?;
+    texture.update(None, &pixels, (width * 3) as usize).map_err(|error| error.to_string())?;
+    canvas.copy(&texture, None, Some(area))
+}
+
+// The length of a row or column of the picture at the given position across
+// it, shrunk more the further it is from the middle.
+fn bent_size(size: u32, position: u32, across: u32, amount: f32) -> u32 {
+    let offset = ((position as f32) / (across as f32)) * 2.0 - 1.0;
+    ((size as f32) * (1.0 - amount * offset * offset)).round() as u32
+}
+
+// Draw the characters that changed since the text layer was last updated
+// into it, or all of them, if the mode of the screen changed.  It has to be
+// done before drawing into any of the other textures, drawing into a texture
+// while drawing into another one doesn't return to the first.
+fn update_text_layer(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                     screen: &mut ScreenTextures,
+                     frame: &VideoFrame) {
+
+    let text = match &mut screen.text {
+        Some(text) => { text },
+        None => { return; },
+    };
+    let mode = (frame.modesel, frame.alternate);
+    let dirty = if screen.drawn == Some(mode) { Some(&screen.dirty) } else { None };
+    let (narrow, wide) = (&screen.narrow, &screen.wide);
+
+    match canvas.with_texture_canvas(text, |text_canvas| { draw_glyphs(text_canvas, narrow, wide, frame, dirty); }) {
+        Ok(..) => {
+            screen.drawn = Some(mode);
+            screen.dirty = DirtyCells::default();
+        },
+        Err(error) => {
+            warn!("Failed to draw into the text layer, drawing the whole screen for each frame instead: {}.", error);
+            screen.text = None;
+        },
+    }
+}
+
+fn draw_screen(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+               screen: &ScreenTextures,
+               frame: &VideoFrame,
+               fg_color: (u8, u8, u8)) {
+
+    match &screen.text {
+        Some(text) => {
+            canvas.copy(text, None, Some(sdl2::rect::Rect::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT))).unwrap();
+        },
+        None => {
+            draw_glyphs(canvas, &screen.narrow, &screen.wide, frame, None);
+        },
+    }
+    draw_glitches(canvas, &screen.narrow, &screen.wide, frame);
+    if let Some(hires) = frame.hires_bitmap() {
+        draw_hires(canvas, hires, fg_color);
+    }
+}
+
+// The first dot of the screen covered by the given column of the bitmap of
+// the graphics board, the pixels are 4/3 of a dot wide.
+fn hires_dot_x(x: u32) -> u32 {
+    (x * SCREEN_WIDTH).div_ceil(hires::HIRES_WIDTH)
+}
+
+// Draw the lit pixels of the graphics board over the text, filling in each
+// run of lit pixels on a row at once.
+fn draw_hires(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+              hires: &hires::HiResFrame,
+              fg_color: (u8, u8, u8)) {
+
+    let dot_height = SCREEN_HEIGHT / hires::HIRES_HEIGHT;
+    let mut runs: Vec<sdl2::rect::Rect> = Vec::new();
+
+    for y in 0..hires::HIRES_HEIGHT {
+        let mut x = 0;
+        while x < hires::HIRES_WIDTH {
+            if !hires.pixel_at(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < hires::HIRES_WIDTH && hires.pixel_at(x, y) {
+                x += 1;
+            }
+            let dot_start = hires_dot_x(start);
+            runs.push(sdl2::rect::Rect::new(dot_start as i32, (y * dot_height) as i32, hires_dot_x(x) - dot_start, dot_height));
+        }
+    }
+    if runs.is_empty() {
+        return;
+    }
+
+    let (red, green, blue) = fg_color;
+    let draw_color = canvas.draw_color();
+    canvas.set_draw_color(sdl2::pixels::Color::RGB(red, green, blue));
+    canvas.fill_rects(&runs).unwrap();
+    canvas.set_draw_color(draw_color);
+}
+
+// Draw the characters of the screen, only the ones marked as changed, if
+// given.  In the 32-column mode, each is at twice its column in the video RAM.
+fn draw_glyphs(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+               narrow: &[sdl2::render::Texture],
+               wide: &[sdl2::render::Texture],
+               frame: &VideoFrame,
+               dirty: Option<&DirtyCells>) {
+
+    let (glyphs, width, stride) = if frame.modesel { (wide, GLYPH_WIDTH_W, 2) } else { (narrow, GLYPH_WIDTH, 1) };
+    for glyph_y in 0..SCREEN_ROWS {
+        for glyph_x in 0..frame.columns() {
+            if let Some(dirty) = dirty {
+                if (dirty[glyph_y as usize] & (1 << (glyph_x * stride))) == 0 {
+                    continue;
+                }
+            }
+            let glyph_texture = &glyphs[fonts::glyph_index(frame.glyph_at(glyph_y, glyph_x), frame.alternate) as usize];
+            let dest = sdl2::rect::Rect::new((glyph_x * width) as i32, (glyph_y * GLYPH_HEIGHT_S) as i32, width, GLYPH_HEIGHT_S);
+            canvas.copy(glyph_texture, None, Some(dest)).unwrap();
+        }
+    }
+}
+
+// The glitches replace a single row of a glyph, two dots tall, they're drawn
+// over the text layer, so that they're gone again with the next frame.
+fn draw_glitches(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+                 narrow: &[sdl2::render::Texture],
+                 wide: &[sdl2::render::Texture],
+                 frame: &VideoFrame) {
+
+    let (glyphs, width) = if frame.modesel { (wide, GLYPH_WIDTH_W) } else { (narrow, GLYPH_WIDTH) };
+    for (row, column, glyph_y, value) in frame.shown_glitches() {
+        let glyph_texture = &glyphs[fonts::glyph_index(value, frame.alternate) as usize];
+        let source = sdl2::rect::Rect::new(0, (glyph_y * 2) as i32, width, 2);
+        let dest = sdl2::rect::Rect::new((column * width) as i32, ((row * GLYPH_HEIGHT_S) + (glyph_y * 2)) as i32, width, 2);
+        canvas.copy(glyph_texture, Some(source), Some(dest)).unwrap();
+    }
 }
@@ -0,0 +1,185 @@
+// Copyright (c) 2017, 2018, 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use crate::sdl_keyboard;
+use crate::sdl_video;
+use trs80m1_rs_core::video::*;
+
+
+// The on-screen keyboard shows the keyboard of the Model I at the bottom of
+// the screen, with keys which can be clicked with the mouse, or tapped on a
+// touch screen, for typing without a keyboard, or the keys a keyboard lacks.
+//
+// The keys are drawn as text, in inverse video, with the characters twice as
+// tall as the dots are wide, like the ones of the machine.  A clicked key is
+// held down until the mouse button is released, except for SHIFT, which stays
+// down until the next key is released, or until it's clicked again.
+//
+// The layout follows the keyboard of the machine, with the arrows spelled
+// out, as the character generators don't all have them.
+//
+const LAYOUT: [&[(&str, &str, u32)]; 5] = [
+    &[("1", "1", 3), ("2", "2", 3), ("3", "3", 3), ("4", "4", 3), ("5", "5", 3), ("6", "6", 3),
+      ("7", "7", 3), ("8", "8", 3), ("9", "9", 3), ("0", "0", 3), (":", "colon", 3), ("-", "minus", 3),
+      ("BREAK", "break", 7)],
+    &[("UP", "up", 4), ("Q", "q", 3), ("W", "w", 3), ("E", "e", 3), ("R", "r", 3), ("T", "t", 3),
+      ("Y", "y", 3), ("U", "u", 3), ("I", "i", 3), ("O", "o", 3), ("P", "p", 3), ("@", "at", 3),
+      ("<-", "left", 4), ("->", "right", 4)],
+    &[("DN", "down", 4), ("A", "a", 3), ("S", "s", 3), ("D", "d", 3), ("F", "f", 3), ("G", "g", 3),
+      ("H", "h", 3), ("J", "j", 3), ("K", "k", 3), ("L", "l", 3), (";", "semicolon", 3),
+      ("ENTER", "enter", 7), ("CLEAR", "clear", 7)],
+    &[("SHIFT", "shift", 7), ("Z", "z", 3), ("X", "x", 3), ("C", "c", 3), ("V", "v", 3), ("B", "b", 3),
+      ("N", "n", 3), ("M", "m", 3), (",", "comma", 3), (".", "period", 3), ("/", "slash", 3),
+      ("SHIFT", "shift", 7)],
+    &[("SPACE", "space", 31)],
+];
+
+struct VirtualKey {
+    label:   &'static str,
+    target:  sdl_keyboard::Target,
+    shift:   bool,
+    row:     u32,
+    column:  u32,   // Of the left edge, in characters.
+    columns: u32,
+}
+
+pub struct VirtualKeyboard {
+    keys:    Vec<VirtualKey>,
+    columns: u32,
+    shown:   bool,
+    clicked: Option<usize>, // The key held down with the mouse.
+    latched: Option<usize>, // The SHIFT key clicked, if it's down.
+}
+
+impl Default for VirtualKeyboard {
+    fn default() -> VirtualKeyboard {
+        VirtualKeyboard::new()
+    }
+}
+
+impl VirtualKeyboard {
+    pub fn new() -> VirtualKeyboard {
+        let row_width = |row: &[(&str, &str, u32)]| -> u32 {
+            row.iter().map(|(_, _, columns)| columns + 1).sum::<u32>() - 1
+        };
+        let columns = LAYOUT.iter().map(|row| row_width(row)).max().unwrap_or(0);
+        let mut keys = Vec::new();
+
+        // Each row is centered, with a column between the keys.
+        for (row, layout_row) in LAYOUT.iter().enumerate() {
+            let mut column = (columns - row_width(layout_row)) / 2;
+            for (label, name, key_columns) in layout_row.iter() {
+                keys.push(VirtualKey {
+                    label,
+                    target:  sdl_keyboard::target(name).expect("A key of the on-screen keyboard is missing from the keyboard matrix"),
+                    shift:   *name == "shift",
+                    row:     row as u32,
+                    column,
+                    columns: *key_columns,
+                });
+                column += key_columns + 1;
+            }
+        }
+        VirtualKeyboard {
+            keys,
+            columns,
+            shown:   false,
+            clicked: None,
+            latched: None,
+        }
+    }
+    pub fn shown(&self) -> bool {
+        self.shown
+    }
+    pub fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    // The part of the screen the keyboard covers, in its dots, the bottom
+    // of the screen, as far from the edge as the status overlay is from the
+    // top.
+    pub fn area(&self) -> (i32, i32, u32, u32) {
+        let width = self.columns * GLYPH_WIDTH;
+        let height = (LAYOUT.len() as u32) * GLYPH_HEIGHT_S;
+        let x = (SCREEN_WIDTH - width) / 2;
+        let y = SCREEN_HEIGHT - sdl_video::OVERLAY_MARGIN - height;
+        (x as i32, y as i32, width, height)
+    }
+
+    // The keyboard as a bitmap of `columns' by `rows' characters, like the
+    // ones of sdl_video::rasterize_text, to be stretched over its area.  The
+    // keys are lit, except for the bottom row of dots, which separates them
+    // from the row below, and the ones held down aren't.
+    pub fn rasterize(&self, font: &[u8]) -> (u32, u32, Vec<u8>) {
+        let rows = LAYOUT.len() as u32;
+        let mut lines = vec![String::new(); LAYOUT.len()];
+        for key in self.keys.iter() {
+            let line = &mut lines[key.row as usize];
+            let padding = (key.columns - (key.label.len() as u32)) / 2;
+            let start = key.column + padding;
+            while (line.len() as u32) < start {
+                line.push(' ');
+            }
+            line.push_str(key.label);
+        }
+        let mut bitmap = sdl_video::rasterize_text(&lines, self.columns, rows, font);
+
+        let width = self.columns * GLYPH_WIDTH;
+        for (index, key) in self.keys.iter().enumerate() {
+            if self.clicked == Some(index) || self.latched == Some(index) {
+                continue;
+            }
+            for y in (key.row * GLYPH_HEIGHT)..((key.row + 1) * GLYPH_HEIGHT - 1) {
+                for x in (key.column * GLYPH_WIDTH)..((key.column + key.columns) * GLYPH_WIDTH) {
+                    let dot = &mut bitmap[((y * width) + x) as usize];
+                    *dot ^= 1;
+                }
+            }
+        }
+        (width, rows * GLYPH_HEIGHT, bitmap)
+    }
+
+    // The key at the given position, in the dots of the screen.
+    pub fn key_at(&self, x: i32, y: i32) -> Option<usize> {
+        let (area_x, area_y, width, height) = self.area();
+        if x < area_x || y < area_y || x >= area_x + (width as i32) || y >= area_y + (height as i32) {
+            return None;
+        }
+        let row = ((y - area_y) as u32) / GLYPH_HEIGHT_S;
+        let column = ((x - area_x) as u32) / GLYPH_WIDTH;
+        self.keys.iter().position(|key| key.row == row && column >= key.column && column < key.column + key.columns)
+    }
+    pub fn target(&self, key: usize) -> sdl_keyboard::Target {
+        self.keys[key].target
+    }
+    pub fn is_shift(&self, key: usize) -> bool {
+        self.keys[key].shift
+    }
+    pub fn clicked(&self) -> Option<usize> {
+        self.clicked
+    }
+    pub fn set_clicked(&mut self, key: Option<usize>) {
+        self.clicked = key;
+    }
+    pub fn take_clicked(&mut self) -> Option<usize> {
+        self.clicked.take()
+    }
+    pub fn set_latched(&mut self, key: Option<usize>) {
+        self.latched = key;
+    }
+    pub fn take_latched(&mut self) -> Option<usize> {
+        self.latched.take()
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::error;
+
+use std::fs;
+use std::path;
+
+use crate::util;
+
+
+// The settings of the titles which need something special, like the
+// lowercase mod or the joystick, kept in the `titles' directory of the
+// configuration directory, a small TOML file for each:
+//
+//     match = ["defense.cas", "crc32:1c0f4a7e"]
+//     speed = 100
+//
+//     [video]
+//     lowercase_mod = true
+//
+// The `match' list has the names of the cassette and /CMD files of the
+// title, or the CRC-32 of their contents, and the sections have entries
+// of the configuration file, which are used in place of the ones from the
+// file for as long as the title is attached.  The `speed' is the speed
+// limit, like with the `--speed' option.
+//
+pub const TITLES_DIR_NAME: &str = "titles";
+
+pub struct TitleSettings {
+    pub name:        String,                // The name of its file, without the extension.
+    patterns:        Vec<String>,
+    pub speed_limit: Option<Option<u32>>,
+    pub entries:     Vec<(String, String)>, // The entry specifiers and the values.
+}
+
+impl TitleSettings {
+    fn load(file_path: &path::Path) -> Result<TitleSettings, String> {
+        let text = fs::read_to_string(file_path).map_err(|error| error.to_string())?;
+        let table = text.parse::<toml::Table>().map_err(|error| error.message().trim().replace('\n', ", "))?;
+
+        let mut patterns = Vec::new();
+        let mut speed_limit = None;
+        let mut entries = Vec::new();
+
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("match", toml::Value::String(pattern)) => {
+                    patterns.push(pattern.to_lowercase());
+                },
+                ("match", toml::Value::Array(array)) => {
+                    for item in array {
+                        match item {
+                            toml::Value::String(pattern) => { patterns.push(pattern.to_lowercase()); },
+                            other => { return Err(format!("`{}' in the `match' list isn't a file name or a CRC-32", other)); },
+                        }
+                    }
+                },
+                ("speed", value) => {
+                    match util::parse_speed_limit(&value_text(value.clone())) {
+                        Some(limit) => { speed_limit = Some(limit); },
+                        None => { return Err(format!("the speed `{}' isn't a percentage from 10 to 1000, or `unlimited'", value)); },
+                    }
+                },
+                (section, toml::Value::Table(section_entries)) => {
+                    for (entry, value) in section_entries {
+                        entries.push((format!("{}_{}", section, entry), value_text(value)));
+                    }
+                },
+                (key, _) => {
+                    return Err(format!("`{}' isn't a section, `match' or `speed'", key));
+                },
+            }
+        }
+        if patterns.is_empty() {
+            return Err("there's no `match' list saying which files it's for".to_owned());
+        }
+
+        Ok(TitleSettings {
+            name: file_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+            patterns,
+            speed_limit,
+            entries,
+        })
+    }
+    // The CRC-32 is only worked out if there's a pattern which needs it.
+    fn matches(&self, file_name: &str, crc: &mut impl FnMut() -> Option<u32>) -> bool {
+        self.patterns.iter().any(|pattern| {
+            match pattern.strip_prefix("crc32:") {
+                Some(hex) => {
+                    match u32::from_str_radix(hex.trim(), 16) {
+                        Ok(wanted) => { crc() == Some(wanted) },
+                        Err(..) => { false },
+                    }
+                },
+                None => { *pattern == file_name },
+            }
+        })
+    }
+}
+
+// The strings are taken without their quotes, the way the configuration
+// file's handlers take them.
+fn value_text(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => { text },
+        other => { other.to_string() },
+    }
+}
+
+// Find the settings of the title a cassette or a /CMD file belongs to.  The
+// files which can't be read are reported and skipped.
+pub fn find(config_dir: &path::Path, media_path: &path::Path) -> Option<TitleSettings> {
+    let mut title_files: Vec<path::PathBuf> = match fs::read_dir(config_dir.join(TITLES_DIR_NAME)) {
+        Ok(dir_entries) => {
+            dir_entries.filter_map(|dir_entry| dir_entry.ok().map(|dir_entry| dir_entry.path()))
+                       .filter(|file_path| file_path.extension().map(|extension| extension.to_string_lossy().to_lowercase() == "toml").unwrap_or(false))
+                       .collect()
+        },
+        Err(..) => { return None; },
+    };
+    title_files.sort();
+
+    let file_name = media_path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mut crc_cache = None;
+    let mut crc = || -> Option<u32> {
+        if crc_cache.is_none() {
+            crc_cache = Some(fs::read(media_path).ok().map(|bytes| util::crc32(&[&bytes])));
+        }
+        crc_cache.unwrap()
+    };
+
+    for file_path in title_files {
+        match TitleSettings::load(&file_path) {
+            Ok(title) => {
+                if title.matches(&file_name, &mut crc) {
+                    return Some(title);
+                }
+            },
+            Err(error) => {
+                error!("Failed to read the title settings `{}': {}.", file_path.display(), error);
+            },
+        }
+    }
+    None
+}
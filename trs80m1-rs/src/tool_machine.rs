@@ -0,0 +1,65 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use log::error;
+
+use std::path;
+
+use trs80m1_rs_core::cassette;
+use trs80m1_rs_core::keyboard;
+use trs80m1_rs_core::machine;
+use trs80m1_rs_core::util::Sink;
+
+
+// The emulated machine of the command line modes which run it without
+// showing it, like `frames' and `batch'.
+//
+// The machine has 48K of RAM and the standard character generator, so that
+// the results don't depend on the configuration file.
+//
+pub const RAM_SIZE:    u16 = 0xC000;
+const MS_PER_KEYPRESS: u32 = 20;
+
+// The cassette drive only logs what it does, nobody watches its events.
+pub struct IgnoredEvents;
+
+impl Sink<cassette::CassetteEvent> for IgnoredEvents {
+    fn push(&mut self, _value: cassette::CassetteEvent) {
+    }
+}
+
+// Power on the machine with the given ROM image and cassette.
+pub fn power_on(rom_path: &str, cassette: Option<(path::PathBuf, cassette::Format)>) -> Option<machine::Machine> {
+    let rom_path = path::PathBuf::from(rom_path);
+    if !rom_path.is_file() {
+        error!("The ROM image `{}' doesn't exist.", rom_path.display());
+        return None;
+    }
+    let (cassette_file_path, cassette_file_format) = match cassette {
+        Some((cas_path, format)) => { (Some(cas_path), format) },
+        None => { (None, cassette::Format::CAS) },
+    };
+    let mut machine = machine::Machine::new(RAM_SIZE, vec![(rom_path, 0)], false, cassette_file_path, cassette_file_format, 0, machine::CPU_HZ / machine::FRAME_RATE);
+    machine.power_on();
+    Some(machine)
+}
+
+// Type in the text, with `\n' pressing Enter, at the pace of a typist.
+pub fn type_text(machine: &mut machine::Machine, text: &str) {
+    let cycles_per_keypress = (machine::CPU_HZ * MS_PER_KEYPRESS) / 1_000;
+    for event in keyboard::text_to_key_events(text, cycles_per_keypress).unwrap_or_default() {
+        machine.devices.keyboard.add_keyboard_event(event);
+    }
+}
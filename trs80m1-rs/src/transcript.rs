@@ -0,0 +1,102 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+use std::time;
+
+use crate::util;
+
+
+// The transcript of the session, for the bug reports and for documenting
+// the work done with the emulator: the commands entered, the replies to
+// them and the logged messages, each with the time it came at since the
+// start.  It's kept apart from the messages on the screen, so clearing or
+// filtering them leaves it alone, and it's written into a file with
+// `/messages export'.
+//
+// Only the newest entries are kept, so that a session running for days
+// doesn't eat up the memory.
+//
+pub const MAX_ENTRIES: usize = 50_000;
+
+enum Kind {
+    Command,
+    Reply,
+    Logged(String),                 // The level and the module.
+}
+
+struct Entry {
+    elapsed: time::Duration,
+    kind:    Kind,
+    text:    String,
+}
+
+pub struct Transcript {
+    started:      time::Instant,
+    started_text: String,
+    entries:      VecDeque<Entry>,
+    dropped:      usize,
+}
+
+impl Transcript {
+    pub fn new() -> Transcript {
+        Transcript {
+            started:      time::Instant::now(),
+            started_text: util::date_time(),
+            entries:      VecDeque::new(),
+            dropped:      0,
+        }
+    }
+    pub fn command(&mut self, command: &str) {
+        self.add(Kind::Command, command.to_owned());
+    }
+    pub fn reply(&mut self, text: &str) {
+        self.add(Kind::Reply, text.to_owned());
+    }
+    pub fn logged(&mut self, level_name: &str, module: &str, text: &str) {
+        self.add(Kind::Logged(format!("{} {}", level_name, module)), text.to_owned());
+    }
+    fn add(&mut self, kind: Kind, text: String) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(Entry { elapsed: self.started.elapsed(), kind, text });
+    }
+    pub fn write(&self, file_path: &path::Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(file_path)?);
+
+        writeln!(file, "trs80m1-rs session transcript, started on {}.", self.started_text)?;
+        if self.dropped > 0 {
+            writeln!(file, "The first {} entries were dropped to save memory.", self.dropped)?;
+        }
+        writeln!(file)?;
+
+        for entry in &self.entries {
+            let label = match &entry.kind {
+                Kind::Command               => { "command" },
+                Kind::Reply                 => { "reply" },
+                Kind::Logged(source)        => { source.as_str() },
+            };
+            let millis = entry.elapsed.as_millis();
+            writeln!(file, "[+{:02}:{:02}:{:02}.{:03}] {:<16} {}", millis / 3_600_000, (millis / 60_000) % 60, (millis / 1000) % 60, millis % 1000, label, entry.text)?;
+        }
+        file.flush()
+    }
+}
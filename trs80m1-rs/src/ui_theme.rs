@@ -0,0 +1,209 @@
+// Copyright (c) 2023 Marek Benc <benc.marek.elektro98@proton.me>
+//
+// Permission to use, copy, modify, and distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+//
+
+// The color themes of the curses-based interface.  A theme gives the colors
+// of each part of the interface, as a foreground and a background color, and
+// the colors of the configuration file can be set on top of it, as a list of
+// `<part>=<foreground>/<background>' settings, like `warning=red/default'.
+//
+// The `default' color is the one the terminal uses on its own, which keeps
+// the light theme readable on terminals with a white background.
+//
+const DEFAULT_COLOR: i16 = -1;
+
+const COLOR_NAMES: [(&str, i16); 9] = [
+    ("default", DEFAULT_COLOR),
+    ("black",   pancurses::COLOR_BLACK),
+    ("red",     pancurses::COLOR_RED),
+    ("green",   pancurses::COLOR_GREEN),
+    ("yellow",  pancurses::COLOR_YELLOW),
+    ("blue",    pancurses::COLOR_BLUE),
+    ("magenta", pancurses::COLOR_MAGENTA),
+    ("cyan",    pancurses::COLOR_CYAN),
+    ("white",   pancurses::COLOR_WHITE),
+];
+
+pub const PART_NAMES: [&str; 9] = ["status", "status-on", "status-off", "status-accent", "message", "warning", "error", "machine", "prompt"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    Dark,
+    Light,
+    Colorblind,     // Blue and yellow instead of green and red.
+    Monochrome,
+}
+
+pub const THEMES: [(&str, Theme); 4] = [
+    ("dark",       Theme::Dark),
+    ("light",      Theme::Light),
+    ("colorblind", Theme::Colorblind),
+    ("monochrome", Theme::Monochrome),
+];
+
+// The foreground and the background colors of the parts of the interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThemeColors {
+    pub status:        (i16, i16),   // The status strips.
+    pub status_on:     (i16, i16),   // `power on', `running' and the motor.
+    pub status_off:    (i16, i16),   // `power off'.
+    pub status_accent: (i16, i16),   // The brackets around the states.
+    pub message:       (i16, i16),   // The messages of the emulator.
+    pub warning:       (i16, i16),
+    pub error:         (i16, i16),
+    pub machine:       (i16, i16),   // The text printed by the machine.
+    pub prompt:        (i16, i16),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorSetting {
+    pub part:   String,
+    pub colors: (i16, i16),
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Theme> {
+        let name = name.to_lowercase();
+        THEMES.iter().find(|(theme_name, _)| *theme_name == name).map(|(_, theme)| *theme)
+    }
+    pub fn name(&self) -> &'static str {
+        THEMES.iter().find(|(_, theme)| theme == self).map(|(name, _)| *name).unwrap_or("dark")
+    }
+    pub fn colors(&self) -> ThemeColors {
+        use pancurses::{COLOR_BLACK, COLOR_RED, COLOR_GREEN, COLOR_YELLOW, COLOR_BLUE, COLOR_MAGENTA, COLOR_CYAN, COLOR_WHITE};
+
+        match *self {
+            Theme::Dark => {
+                ThemeColors {
+                    status:        (COLOR_WHITE,   COLOR_BLUE),
+                    status_on:     (COLOR_GREEN,   COLOR_BLUE),
+                    status_off:    (COLOR_RED,     COLOR_BLUE),
+                    status_accent: (COLOR_CYAN,    COLOR_BLUE),
+                    message:       (COLOR_YELLOW,  COLOR_BLACK),
+                    warning:       (COLOR_MAGENTA, COLOR_BLACK),
+                    error:         (COLOR_RED,     COLOR_BLACK),
+                    machine:       (COLOR_WHITE,   COLOR_BLACK),
+                    prompt:        (COLOR_WHITE,   COLOR_BLACK),
+                }
+            },
+            Theme::Light => {
+                ThemeColors {
+                    status:        (COLOR_WHITE,   COLOR_BLUE),
+                    status_on:     (COLOR_GREEN,   COLOR_BLUE),
+                    status_off:    (COLOR_RED,     COLOR_BLUE),
+                    status_accent: (COLOR_CYAN,    COLOR_BLUE),
+                    message:       (COLOR_BLUE,    DEFAULT_COLOR),
+                    warning:       (COLOR_MAGENTA, DEFAULT_COLOR),
+                    error:         (COLOR_RED,     DEFAULT_COLOR),
+                    machine:       (DEFAULT_COLOR, DEFAULT_COLOR),
+                    prompt:        (DEFAULT_COLOR, DEFAULT_COLOR),
+                }
+            },
+            Theme::Colorblind => {
+                ThemeColors {
+                    status:        (COLOR_WHITE,   COLOR_BLUE),
+                    status_on:     (COLOR_YELLOW,  COLOR_BLUE),
+                    status_off:    (COLOR_WHITE,   COLOR_BLUE),
+                    status_accent: (COLOR_CYAN,    COLOR_BLUE),
+                    message:       (COLOR_CYAN,    COLOR_BLACK),
+                    warning:       (COLOR_YELLOW,  COLOR_BLACK),
+                    error:         (COLOR_MAGENTA, COLOR_BLACK),
+                    machine:       (COLOR_WHITE,   COLOR_BLACK),
+                    prompt:        (COLOR_WHITE,   COLOR_BLACK),
+                }
+            },
+            Theme::Monochrome => {
+                ThemeColors {
+                    status:        (COLOR_BLACK,   COLOR_WHITE),
+                    status_on:     (COLOR_BLACK,   COLOR_WHITE),
+                    status_off:    (COLOR_BLACK,   COLOR_WHITE),
+                    status_accent: (COLOR_BLACK,   COLOR_WHITE),
+                    message:       (DEFAULT_COLOR, DEFAULT_COLOR),
+                    warning:       (DEFAULT_COLOR, DEFAULT_COLOR),
+                    error:         (DEFAULT_COLOR, DEFAULT_COLOR),
+                    machine:       (DEFAULT_COLOR, DEFAULT_COLOR),
+                    prompt:        (DEFAULT_COLOR, DEFAULT_COLOR),
+                }
+            },
+        }
+    }
+}
+
+impl ThemeColors {
+    // The colors of the theme, with the settings of the configuration file
+    // on top of them.
+    pub fn new(theme: Theme, settings: &[ColorSetting]) -> ThemeColors {
+        let mut colors = theme.colors();
+        for setting in settings {
+            if let Some(part_colors) = colors.part_mut(&setting.part) {
+                *part_colors = setting.colors;
+            }
+        }
+        colors
+    }
+    fn part_mut(&mut self, part: &str) -> Option<&mut (i16, i16)> {
+        match part {
+            "status"        => { Some(&mut self.status) },
+            "status-on"     => { Some(&mut self.status_on) },
+            "status-off"    => { Some(&mut self.status_off) },
+            "status-accent" => { Some(&mut self.status_accent) },
+            "message"       => { Some(&mut self.message) },
+            "warning"       => { Some(&mut self.warning) },
+            "error"         => { Some(&mut self.error) },
+            "machine"       => { Some(&mut self.machine) },
+            "prompt"        => { Some(&mut self.prompt) },
+            _               => { None },
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Option<i16> {
+    COLOR_NAMES.iter().find(|(color_name, _)| *color_name == name).map(|(_, color)| *color)
+}
+
+fn color_name(color: i16) -> &'static str {
+    COLOR_NAMES.iter().find(|(_, value)| *value == color).map(|(name, _)| *name).unwrap_or("default")
+}
+
+// Parse the color settings of the configuration file, a list of
+// `<part>=<foreground>/<background>' settings, or `none'.
+pub fn parse_color_settings(text: &str) -> Option<Vec<ColorSetting>> {
+    let text = text.trim().to_lowercase();
+    let mut settings: Vec<ColorSetting> = Vec::new();
+    if text == "none" {
+        return Some(settings);
+    }
+    for setting in text.split_whitespace() {
+        let (part, colors) = setting.split_once('=')?;
+        let (foreground, background) = colors.split_once('/')?;
+        if !PART_NAMES.contains(&part) {
+            return None;
+        }
+        let colors = (parse_color(foreground)?, parse_color(background)?);
+
+        // A later setting of the same part replaces the earlier one.
+        settings.retain(|setting| setting.part != part);
+        settings.push(ColorSetting { part: part.to_owned(), colors });
+    }
+    Some(settings)
+}
+
+pub fn color_settings_text(settings: &[ColorSetting]) -> String {
+    if settings.is_empty() {
+        return "none".to_owned();
+    }
+    settings.iter().map(|setting| {
+        format!("{}={}/{}", setting.part, color_name(setting.colors.0), color_name(setting.colors.1))
+    }).collect::<Vec<String>>().join(" ")
+}
@@ -13,6 +13,7 @@
 // OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 //
 
+use log::warn;
 use pancurses;
 use unicode_width;
 
@@ -21,12 +22,28 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::ffi::OsStr;
+use std::fs;
 use std::path;
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
-
-use crate::emulator::{EmulatorCommand, EmulatorCassetteCommand, EmulatorConfigCommand, EmulatorStatus};
+use std::time::{Duration, Instant};
+
+use trs80m1_rs_core::keyboard;
+use trs80m1_rs_core::sound;
+use trs80m1_rs_core::video;
+use crate::aliases;
+use crate::commands;
+use crate::completion;
+use crate::file_picker;
+use crate::i18n;
+use crate::layout;
+use crate::proj_config;
+use crate::remote_control;
+use crate::transcript;
+use crate::sdl_keyboard;
+use crate::sdl_video;
+use crate::ui_theme;
+use crate::emulator::{EmulatorCommand, PictureSetting, EmulatorCassetteCommand, EmulatorDiskCommand, EmulatorSoundCommand, EmulatorRecordCommand, EmulatorTimerCommand, EmulatorScriptCommand, EmulatorDebuggerCommand, EmulatorConfigCommand, EmulatorStatus, CancelFlag};
 use trs80m1_rs_core::cassette;
 use crate::util;
 
@@ -38,6 +55,12 @@ const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
 const MIN_SCREEN_WIDTH:            usize = 45;
 const MIN_SCREEN_HEIGHT:           usize = 10;
 
+// With the screen of the machine shown above the lines, in the
+// terminal-only mode:
+const MIN_TERMINAL_SCREEN_WIDTH:   usize = video::SCREEN_COLS as usize;
+const MIN_TERMINAL_SCREEN_HEIGHT:  usize = 24;
+const MACHINE_SCREEN_ROWS:         usize = video::SCREEN_ROWS as usize;
+
 const LINES_TOP_OFFSET:            usize = 1;
 const LINES_BOTTOM_OFFSET:         usize = 2;
 
@@ -49,6 +72,11 @@ const BOTTOM_STRIP_BOTTOM_OFFSET:  usize = 1;
 
 const MAX_SCREEN_LINES:            usize = 5000;
 const MAX_HISTORY_ENTRIES:         usize = 500;
+const MAX_PICKER_NAME_WIDTH:      usize = 24;
+
+// The history of the prompt is kept in this file of the configuration
+// directory, so that the commands of the earlier runs can be recalled.
+const HISTORY_FILE_NAME:           &str  = "history.txt";
 
 
 // Possible color pairs:
@@ -59,6 +87,28 @@ const COLOR_PAIR_STRIP_CYAN:  u8 = 4;
 const COLOR_PAIR_EMSG:        u8 = 5;
 const COLOR_PAIR_MMSG:        u8 = 6;
 const COLOR_PAIR_PROMPT:      u8 = 7;
+const COLOR_PAIR_WARNING:     u8 = 8;
+const COLOR_PAIR_ERROR:       u8 = 9;
+
+// Where the commands come from, and where the messages go.
+pub enum Console {
+    Curses,
+
+    // The commands come a line each, like in a script, and the messages
+    // go to the standard output.
+    Headless(mpsc::Receiver<String>),
+
+    // The graphical interface sends the commands the same way, and gets the
+    // messages and the state of the machine for its debugger panel back.
+    #[cfg(feature = "egui")]
+    Gui(mpsc::Receiver<String>, mpsc::Sender<GuiEvent>),
+}
+
+#[cfg(feature = "egui")]
+pub enum GuiEvent {
+    Message(String),
+    DebugState(Vec<String>),
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum ScreenLineType {
@@ -66,9 +116,43 @@ pub enum ScreenLineType {
     MachineMessage { complete: bool },
 }
 
+// The logged messages of the emulator can be narrowed down to the ones of a
+// given level or a more severe one, and to the ones of a given module.  The
+// rest of the lines, like the replies to the commands, are always shown.
+#[derive(Clone, Debug)]
+struct MessageFilter {
+    level:  log::Level,
+    module: Option<String>,
+}
+
+impl MessageFilter {
+    fn shows(&self, log_source: &Option<(log::Level, String)>) -> bool {
+        match log_source {
+            Some((level, module)) => {
+                *level <= self.level && self.module.as_ref().is_none_or(|shown_module| shown_module == module)
+            },
+            None => { true },
+        }
+    }
+    fn is_active(&self) -> bool {
+        self.level < log::Level::Info || self.module.is_some()
+    }
+}
+
+fn log_level_name(level: log::Level) -> &'static str {
+    match level {
+        log::Level::Error => { "error" },
+        log::Level::Warn  => { "warning" },
+        log::Level::Info  => { "info" },
+        log::Level::Debug => { "debug" },
+        log::Level::Trace => { "trace" },
+    }
+}
+
 #[derive(Clone, Debug)]
 struct ScreenLine {
     line_type:                 ScreenLineType,
+    log_source:                Option<(log::Level, String)>,   // The level and module, for logged messages.
     line_codes:                Vec<char>,
     line_widths:               Vec<u8>,
     cached_screen_width:       usize,
@@ -83,6 +167,7 @@ impl ScreenLine {
     fn new(line_type: ScreenLineType, screen_width: usize) -> ScreenLine {
         ScreenLine {
             line_type,
+            log_source:                None,
             line_codes:                Vec::new(),
             line_widths:               Vec::new(),
             cached_screen_width:       screen_width,
@@ -100,6 +185,21 @@ impl ScreenLine {
 
         line
     }
+    // The logged warnings and errors stand out from the other messages.
+    fn color_pair(&self) -> u8 {
+        match self.line_type {
+            ScreenLineType::EmulatorMessage => {
+                match self.log_source {
+                    Some((log::Level::Error, _)) => { COLOR_PAIR_ERROR },
+                    Some((log::Level::Warn, _))  => { COLOR_PAIR_WARNING },
+                    _                            => { COLOR_PAIR_EMSG },
+                }
+            },
+            ScreenLineType::MachineMessage {..} => {
+                COLOR_PAIR_MMSG
+            },
+        }
+    }
     fn append_char(&mut self, to_add: char) {
 
         let ch_scr_width = match unicode_width::UnicodeWidthChar::width(to_add) {
@@ -292,9 +392,19 @@ enum HelpEntry {
     Machine,
     Memory,
     Cassette,
+    Disk,
+    Video,
+    Keyboard,
+    Sound,
+    Record,
+    Timer,
+    Rhai,
+    Debugger,
     Config,
     Exit,
     Alias { alias_name: String, aliased_name: String, help_entry: String },
+    Summary(&'static commands::Command),
+    SubCommand { command_name: String, sub_command_name: String },
     Default,
 }
 
@@ -307,11 +417,22 @@ enum MessagesSubCommandArgInclusive {
     Machine,
     Both,
 }
+enum MessagesScroll {
+    Up,
+    Down,
+    Top,
+    Bottom,
+}
 enum MessagesSubCommand {
     Show   (MessagesSubCommandArgExclusive),
     Hide   (MessagesSubCommandArgExclusive),
     Toggle (MessagesSubCommandArgExclusive),
     Clear  (MessagesSubCommandArgInclusive),
+    Level  (log::Level),
+    Module (Option<String>),        // None shows the messages of all modules.
+    Filter,
+    Scroll (MessagesScroll),
+    Export (String),                // The file the transcript is written into.
 }
 
 enum PauseType {
@@ -325,6 +446,33 @@ enum MachineSubCommand {
     Restore,
     SwitchRom(u32),
     Pause(PauseType),
+    Paste,
+    Type { file: String },
+    TypeText(String),
+    Latency,
+    Clock,
+    Status(Option<bool>),           // None toggles the status pane.
+    Warp(Option<bool>),             // None toggles the warp.
+    Speed(Option<Option<u32>>),     // None shows the speed limit.
+    Advance(u32),                   // The number of video frames.
+    SaveState(Option<String>),      // None for the default file.
+    LoadState(Option<String>),
+}
+
+enum KeyboardSubCommand {
+    ShowProfiles,
+    Profile(Option<String>),        // None turns the profile off.
+}
+
+enum VideoSubCommand {
+    Fullscreen(Option<bool>),
+    Scaling(sdl_video::Scaling),
+    Colors { fg_color: (u8, u8, u8), bg_color: Option<(u8, u8, u8)> },
+    Screenshot(Option<bool>),
+    Text { file: Option<String> },
+    Charset(Option<bool>),
+    Picture(Option<(PictureSetting, i32, bool)>), // None shows the settings.
+    Magnifier(Option<bool>),
 }
 
 enum MemorySubCommandArgExclusive {
@@ -339,6 +487,7 @@ enum MemorySubCommandArgInclusive {
 enum MemorySubCommand {
     Load { device: MemorySubCommandArgExclusive, path: path::PathBuf, offset: u16 },
     Wipe { device: MemorySubCommandArgInclusive },
+    Dump { address: u16, length: u32 },
 }
 
 enum ParsedUserCommand {
@@ -347,7 +496,17 @@ enum ParsedUserCommand {
     Machine  (MachineSubCommand),
     Memory   (MemorySubCommand),
     Cassette (EmulatorCassetteCommand),
+    Disk     (EmulatorDiskCommand),
+    Video    (VideoSubCommand),
+    Keyboard (KeyboardSubCommand),
+    Sound    (EmulatorSoundCommand),
+    Record   (EmulatorRecordCommand),
+    Timer    (EmulatorTimerCommand),
+    Rhai     (EmulatorScriptCommand),
+    Debugger (EmulatorDebuggerCommand),
+    DebuggerPane (Option<bool>),
     Config   (EmulatorConfigCommand),
+    Browse   { purpose: file_picker::Purpose, dir: Option<String> },
 
     CommandMissingParameter  { sup_command_name: String, sub_command_name: String, parameter_desc: String, parameter_desc_ia: String },
     CommandMissingSubcommand { sup_command_name: String },
@@ -380,6 +539,10 @@ impl ParsedUserCommand {
                           };
 
         if command == "help" {
+            // With a sub-command, its usage is shown.
+            if let (Some((sub_command, _)), Some((parameter_1, _))) = (&sub_command, &parameter_1) {
+                return ParsedUserCommand::Help(HelpEntry::SubCommand { command_name: sub_command.clone(), sub_command_name: parameter_1.clone() });
+            }
             match sub_command {
                 Some ((sub_command, sub_command_raw)) => {
                     if sub_command == "help" {
@@ -392,6 +555,22 @@ impl ParsedUserCommand {
                         ParsedUserCommand::Help(HelpEntry::Memory)
                     } else if sub_command == "cassette" {
                         ParsedUserCommand::Help(HelpEntry::Cassette)
+                    } else if sub_command == "disk" {
+                        ParsedUserCommand::Help(HelpEntry::Disk)
+                    } else if sub_command == "video" {
+                        ParsedUserCommand::Help(HelpEntry::Video)
+                    } else if sub_command == "keyboard" {
+                        ParsedUserCommand::Help(HelpEntry::Keyboard)
+                    } else if sub_command == "sound" {
+                        ParsedUserCommand::Help(HelpEntry::Sound)
+                    } else if sub_command == "record" {
+                        ParsedUserCommand::Help(HelpEntry::Record)
+                    } else if sub_command == "timer" {
+                        ParsedUserCommand::Help(HelpEntry::Timer)
+                    } else if sub_command == "rhai" {
+                        ParsedUserCommand::Help(HelpEntry::Rhai)
+                    } else if sub_command == "debugger" {
+                        ParsedUserCommand::Help(HelpEntry::Debugger)
                     } else if sub_command == "config" {
                         ParsedUserCommand::Help(HelpEntry::Config)
                     } else if sub_command == "exit" || sub_command == "quit" {
@@ -402,6 +581,8 @@ impl ParsedUserCommand {
                         ParsedUserCommand::Help(HelpEntry::Alias { alias_name: sub_command, aliased_name: "machine pause on".to_owned(), help_entry: "machine".to_owned() })
                     } else if sub_command == "unpause" {
                         ParsedUserCommand::Help(HelpEntry::Alias { alias_name: sub_command, aliased_name: "machine pause off".to_owned(), help_entry: "machine".to_owned() })
+                    } else if let Some(command) = commands::find(&sub_command) {
+                        ParsedUserCommand::Help(HelpEntry::Summary(command))
                     } else {
                         ParsedUserCommand::InvalidCommand { command_name: sub_command_raw }
                     }
@@ -471,6 +652,64 @@ impl ParsedUserCommand {
                         } else {
                             ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: selection_raw, parameter_desc: "selection".to_owned() }
                         }
+                    } else if sub_command == "level" {
+                        let (level, level_raw) = match parameter_1 {
+                                                     Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                     None => {
+                                                         return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "level".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                                     },
+                                                 };
+                        if level == "error" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Level(log::Level::Error))
+                        } else if level == "warning" || level == "warn" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Level(log::Level::Warn))
+                        } else if level == "info" || level == "all" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Level(log::Level::Info))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: level_raw, parameter_desc: "level".to_owned() }
+                        }
+                    } else if sub_command == "module" {
+                        match parameter_1 {
+                            Some((module, _)) => {
+                                if module == "all" {
+                                    ParsedUserCommand::Messages(MessagesSubCommand::Module(None))
+                                } else {
+                                    ParsedUserCommand::Messages(MessagesSubCommand::Module(Some(module)))
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "module".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "filter" {
+                        ParsedUserCommand::Messages(MessagesSubCommand::Filter)
+                    } else if sub_command == "export" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Messages(MessagesSubCommand::Export(file))
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "scroll" {
+                        let (direction, direction_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => {
+                                                                 return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "direction".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                                             },
+                                                         };
+                        if direction == "up" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Scroll(MessagesScroll::Up))
+                        } else if direction == "down" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Scroll(MessagesScroll::Down))
+                        } else if direction == "top" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Scroll(MessagesScroll::Top))
+                        } else if direction == "bottom" {
+                            ParsedUserCommand::Messages(MessagesSubCommand::Scroll(MessagesScroll::Bottom))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: direction_raw, parameter_desc: "direction".to_owned() }
+                        }
                     } else {
                         ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
                     }
@@ -548,6 +787,94 @@ impl ParsedUserCommand {
                         }
                     } else if sub_command == "unpause" {
                         ParsedUserCommand::Machine(MachineSubCommand::Pause(PauseType::Unpause))
+                    } else if sub_command == "paste" {
+                        ParsedUserCommand::Machine(MachineSubCommand::Paste)
+                    } else if sub_command == "type" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Machine(MachineSubCommand::Type { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "type-text" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(text) => {
+                                ParsedUserCommand::Machine(MachineSubCommand::TypeText(text.replace("\\n", "\n")))
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "text".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "latency" {
+                        ParsedUserCommand::Machine(MachineSubCommand::Latency)
+                    } else if sub_command == "clock" {
+                        ParsedUserCommand::Machine(MachineSubCommand::Clock)
+                    } else if sub_command == "status" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Status(Some(true)))
+                        } else if state_str == "off" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Status(Some(false)))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Status(None))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "pane state".to_owned() }
+                        }
+                    } else if sub_command == "warp" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Warp(Some(true)))
+                        } else if state_str == "off" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Warp(Some(false)))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Machine(MachineSubCommand::Warp(None))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "warp state".to_owned() }
+                        }
+                    } else if sub_command == "speed" {
+                        match parameter_1 {
+                            Some((speed_str, speed_str_raw)) => {
+                                match util::parse_speed_limit(&speed_str) {
+                                    Some(speed_limit) => {
+                                        ParsedUserCommand::Machine(MachineSubCommand::Speed(Some(speed_limit)))
+                                    },
+                                    None => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: speed_str_raw, parameter_desc: "speed".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Machine(MachineSubCommand::Speed(None))
+                            },
+                        }
+                    } else if sub_command == "advance" {
+                        match parameter_1 {
+                            Some((_, frames_str)) => {
+                                match util::parse_u32_from_str(frames_str.as_str()) {
+                                    Some(frames) if frames > 0 => {
+                                        ParsedUserCommand::Machine(MachineSubCommand::Advance(frames))
+                                    },
+                                    _ => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: frames_str, parameter_desc: "number of frames".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Machine(MachineSubCommand::Advance(1))
+                            },
+                        }
+                    } else if sub_command == "save-state" {
+                        ParsedUserCommand::Machine(MachineSubCommand::SaveState(util::get_starting_at_word(command_string, 3)))
+                    } else if sub_command == "load-state" {
+                        ParsedUserCommand::Machine(MachineSubCommand::LoadState(util::get_starting_at_word(command_string, 3)))
                     } else {
                         ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
                     }
@@ -613,6 +940,32 @@ impl ParsedUserCommand {
                             return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: device_str_raw, parameter_desc: "device".to_owned() };
                         };
                         ParsedUserCommand::Memory(MemorySubCommand::Wipe { device: device })
+                    } else if sub_command == "dump" {
+                        let address = match parameter_1 {
+                                          Some((_, address_str)) => {
+                                              match util::parse_u32_from_str(address_str.as_str()) {
+                                                  Some(address) if address <= 0xFFFF => { address as u16 },
+                                                  _ => {
+                                                      return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: address_str, parameter_desc: "address".to_owned() };
+                                                  },
+                                              }
+                                          },
+                                          None => {
+                                              return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "address".to_owned(), parameter_desc_ia: "an".to_owned() };
+                                          },
+                                      };
+                        let length = match parameter_2 {
+                                         Some((_, length_str)) => {
+                                             match util::parse_u32_from_str(length_str.as_str()) {
+                                                 Some(length) if length > 0 && length <= 0x10000 => { length },
+                                                 _ => {
+                                                     return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: length_str, parameter_desc: "length".to_owned() };
+                                                 },
+                                             }
+                                         },
+                                         None => { 64 },
+                                     };
+                        ParsedUserCommand::Memory(MemorySubCommand::Dump { address, length })
                     } else {
                         ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
                     }
@@ -624,28 +977,41 @@ impl ParsedUserCommand {
         } else if command == "cassette" {
             match sub_command {
                 Some((sub_command, sub_command_raw)) => {
-                    if sub_command == "insert" {
-                        let (format_str, format_str_raw) = match parameter_1 {
-                                                               Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
-                                                               None => {
-                                                                   return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "format".to_owned(), parameter_desc_ia: "a".to_owned() };
-                                                               },
-                                                           };
+                    if sub_command == "insert" || sub_command == "load" {
+                        let format_str = match parameter_1 {
+                                             Some((parameter_1, _)) => { parameter_1 },
+                                             None => {
+                                                 return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                             },
+                                         };
                         let format = if format_str == "cas" {
-                            cassette::Format::CAS
+                            Some(cassette::Format::CAS)
                         } else if format_str == "cpt" {
-                            cassette::Format::CPT
+                            Some(cassette::Format::CPT)
+                        } else if format_str == "wav" {
+                            Some(cassette::Format::WAV)
                         } else {
-                            return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: format_str_raw, parameter_desc: "format".to_owned() };
+                            None
                         };
-                        match util::get_starting_at_word(command_string, 4) {
-                            Some(file) => {
-                                ParsedUserCommand::Cassette(EmulatorCassetteCommand::Insert { format: format, file: file })
+
+                        // The format may be left out, if the file name says
+                        // what it is:
+                        let (format, file) = match (format, util::get_starting_at_word(command_string, 4)) {
+                            (Some(format), Some(file)) => { (format, file) },
+                            (Some(_), None) => {
+                                return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() };
                             },
-                            None => {
-                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            (None, _) => {
+                                let file = util::get_starting_at_word(command_string, 3).unwrap();
+                                match cassette::Format::from_path(file.as_ref()) {
+                                    Some(format) => { (format, file) },
+                                    None => {
+                                        return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "format".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                    },
+                                }
                             },
-                        }
+                        };
+                        ParsedUserCommand::Cassette(EmulatorCassetteCommand::Insert { format: format, file: file, auto_load: sub_command == "load" })
                     } else if sub_command == "seek" {
                         let position_str = match parameter_1 {
                                                Some((_, parameter_1_raw)) => { parameter_1_raw },
@@ -663,10 +1029,68 @@ impl ParsedUserCommand {
                         }
                     } else if sub_command == "eject" {
                         ParsedUserCommand::Cassette(EmulatorCassetteCommand::Eject)
+                    } else if sub_command == "undo" {
+                        ParsedUserCommand::Cassette(EmulatorCassetteCommand::Undo)
                     } else if sub_command == "erase" {
                         ParsedUserCommand::Cassette(EmulatorCassetteCommand::Erase)
                     } else if sub_command == "rewind" {
                         ParsedUserCommand::Cassette(EmulatorCassetteCommand::Rewind)
+                    } else if sub_command == "ff" {
+                        ParsedUserCommand::Cassette(EmulatorCassetteCommand::FastForward)
+                    } else if sub_command == "index" {
+                        ParsedUserCommand::Cassette(EmulatorCassetteCommand::Index)
+                    } else if sub_command == "motor" {
+                        match parameter_1 {
+                            Some((state, state_raw)) => {
+                                if state == "on" {
+                                    ParsedUserCommand::Cassette(EmulatorCassetteCommand::Motor { state: Some(true) })
+                                } else if state == "off" {
+                                    ParsedUserCommand::Cassette(EmulatorCassetteCommand::Motor { state: Some(false) })
+                                } else if state == "auto" {
+                                    ParsedUserCommand::Cassette(EmulatorCassetteCommand::Motor { state: None })
+                                } else {
+                                    ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_raw, parameter_desc: "motor state".to_owned() }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "motor state".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "goto" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(recording) => {
+                                ParsedUserCommand::Cassette(EmulatorCassetteCommand::GoTo { recording })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "recording".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "library" {
+                        match parameter_1 {
+                            Some((_, index_str)) => {
+                                match index_str.parse::<usize>() {
+                                    Ok(index) => {
+                                        match parameter_2 {
+                                            Some((ref action, _)) if action == "load" => {
+                                                ParsedUserCommand::Cassette(EmulatorCassetteCommand::LibraryInsert { index, auto_load: true })
+                                            },
+                                            Some((_, action_raw)) => {
+                                                ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: action_raw, parameter_desc: "action".to_owned() }
+                                            },
+                                            None => {
+                                                ParsedUserCommand::Cassette(EmulatorCassetteCommand::LibraryInsert { index, auto_load: false })
+                                            },
+                                        }
+                                    },
+                                    Err(_) => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: index_str, parameter_desc: "number".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Cassette(EmulatorCassetteCommand::Library { browse: true })
+                            },
+                        }
                     } else {
                         ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
                     }
@@ -675,36 +1099,105 @@ impl ParsedUserCommand {
                     ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
                 },
             }
-        } else if command == "config" {
+        } else if command == "disk" {
             match sub_command {
-                Some ((sub_command, sub_command_raw)) => {
-                    if sub_command == "list" {
-                        ParsedUserCommand::Config(EmulatorConfigCommand::List)
-                    } else if sub_command == "show" {
-                        let entry_specifier = match parameter_1 {
-                                                  Some((_, parameter_1_raw)) => { parameter_1_raw },
-                                                  None => {
-                                                      return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "entry specifier".to_owned(), parameter_desc_ia: "an".to_owned() };
-                                                  },
-                                              };
-                        ParsedUserCommand::Config(EmulatorConfigCommand::Show { entry_specifier: entry_specifier })
-                    } else if sub_command == "change" {
-                        let entry_specifier = match parameter_1 {
-                                                  Some((_, parameter_1_raw)) => { parameter_1_raw },
-                                                  None => {
-                                                      return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "entry specifier".to_owned(), parameter_desc_ia: "an".to_owned() };
-                                                  },
-                                              };
-                        let equals_sign = match parameter_2 {
-                                              Some((_, parameter_2_raw)) => { parameter_2_raw },
-                                              None => {
-                                                  return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "new value specifier".to_owned(), parameter_desc_ia: "a".to_owned() };
-                                              },
-                                          };
-                        if equals_sign.chars().next().expect("Some((_, parameter_2_raw)) implies non-zero length") != '=' {
-                            return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: equals_sign, parameter_desc: "new value specifier".to_owned() };
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "dir" || sub_command == "commit" || sub_command == "discard" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                if sub_command == "dir" {
+                                    ParsedUserCommand::Disk(EmulatorDiskCommand::Directory { file: file })
+                                } else if sub_command == "commit" {
+                                    ParsedUserCommand::Disk(EmulatorDiskCommand::Commit { file: file })
+                                } else {
+                                    ParsedUserCommand::Disk(EmulatorDiskCommand::Discard { file: file })
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "read" || sub_command == "write" {
+                        let track_str = match parameter_1 {
+                                            Some((_, parameter_1_raw)) => { parameter_1_raw },
+                                            None => {
+                                                return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "track".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                            },
+                                        };
+                        let sector_str = match parameter_2 {
+                                             Some((_, parameter_2_raw)) => { parameter_2_raw },
+                                             None => {
+                                                 return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "sector".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                             },
+                                         };
+
+                        // The side is optional, specified as `track:side'.
+                        let (track_num_str, side_str) = match track_str.find(':') {
+                                                            Some(position) => { (track_str[..position].to_owned(), track_str[(position + 1)..].to_owned()) },
+                                                            None => { (track_str.clone(), "0".to_owned()) },
+                                                        };
+                        let track = match util::parse_u32_from_str(&track_num_str) {
+                                        Some(track) if track <= 0xFF => { track as u8 },
+                                        _ => {
+                                            return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: track_str, parameter_desc: "track".to_owned() };
+                                        },
+                                    };
+                        let side = match util::parse_u32_from_str(&side_str) {
+                                       Some(side) if side <= 1 => { side as u8 },
+                                       _ => {
+                                           return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: track_str, parameter_desc: "side".to_owned() };
+                                       },
+                                   };
+                        let sector = match util::parse_u32_from_str(&sector_str) {
+                                         Some(sector) if sector <= 0xFF => { sector as u8 },
+                                         _ => {
+                                             return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: sector_str, parameter_desc: "sector".to_owned() };
+                                         },
+                                     };
+
+                        if sub_command == "read" {
+                            match util::get_starting_at_word(command_string, 5) {
+                                Some(file) => {
+                                    ParsedUserCommand::Disk(EmulatorDiskCommand::ReadSector { file: file, track: track, side: side, sector: sector })
+                                },
+                                None => {
+                                    ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                                },
+                            }
+                        } else {
+                            let offset_str = match parameter_3 {
+                                                 Some((_, parameter_3_raw)) => { parameter_3_raw },
+                                                 None => {
+                                                     return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "offset".to_owned(), parameter_desc_ia: "an".to_owned() };
+                                                 },
+                                             };
+                            let offset = match util::parse_u32_from_str(&offset_str) {
+                                             Some(offset) => { offset as usize },
+                                             None => {
+                                                 return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: offset_str, parameter_desc: "offset".to_owned() };
+                                             },
+                                         };
+                            let bytes_str = match util::get_word(command_string, 6) {
+                                                Some(bytes_str) => { bytes_str },
+                                                None => {
+                                                    return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "byte string".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                                },
+                                            };
+                            let bytes = match util::parse_hex_bytes(&bytes_str) {
+                                            Some(bytes) => { bytes },
+                                            None => {
+                                                return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: bytes_str, parameter_desc: "byte string".to_owned() };
+                                            },
+                                        };
+                            match util::get_starting_at_word(command_string, 7) {
+                                Some(file) => {
+                                    ParsedUserCommand::Disk(EmulatorDiskCommand::WriteSector { file: file, track: track, side: side, sector: sector, offset: offset, bytes: bytes })
+                                },
+                                None => {
+                                    ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                                },
+                            }
                         }
-                        ParsedUserCommand::Config(EmulatorConfigCommand::Change { entry_specifier: entry_specifier, invocation_text: command_string.to_owned() })
                     } else {
                         ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
                     }
@@ -713,145 +1206,900 @@ impl ParsedUserCommand {
                     ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
                 },
             }
-        } else {
-            ParsedUserCommand::InvalidCommand { command_name: command_raw }
-        }
-    }
-}
-
-
-pub struct UserInterface {
-    window:                      pancurses::Window,
-    exit_request:                bool,
-    logic_core_thread_running:   bool,
-    video_thread_running:        bool,
-
-    screen_width:                usize,
-    screen_height:               usize,
-    screen_too_small:            bool,
-
-    redraw_text_area:            bool,
-    redraw_status:               bool,
-    redraw_prompt:               bool,
-    redraw_everything:           bool,
-
-    max_screen_lines:            usize,
-    screen_lines:                VecDeque<ScreenLine>,
-    cached_screen_total_rows:    usize,
-    cached_screen_free_rows:     usize,
-    cached_penult_line_exists:   bool,
-    cached_last_line_exists:     bool,
-    bottom_rows_skip:            usize,
-    lines_added_scrolled_up:     bool,
-    emulator_msg_shown:          bool,
-    machine_msg_shown:           bool,
-
-    prompt_text:                 ScreenLine,
-    prompt_curs_code_pos:        usize,  // cursor position at a Rust char
-    prompt_curs_cell_pos:        usize,  // cursor position at a screen cell
-    prompt_scroll_cells:         usize,
-
-    prompt_history:              VecDeque<ScreenLine>,
-    prompt_history_max_entries:  usize,
-    prompt_history_pos:          usize,  // 0 refers to prompt_text
-
-    cpu_halted:                  bool,
-    machine_powered_on:          bool,
-    machine_paused:              bool,
-}
-
-impl UserInterface {
-    pub fn new() -> Option<UserInterface> {
-
-        let window = pancurses::initscr();
-        pancurses::start_color();
-        pancurses::cbreak();
-        pancurses::noecho();
-        pancurses::nonl();
-        window.nodelay(true);
-        window.keypad(true);
-
-        pancurses::init_pair(COLOR_PAIR_STRIP_GRAY   as i16,  pancurses::COLOR_WHITE,  pancurses::COLOR_BLUE);
-        pancurses::init_pair(COLOR_PAIR_STRIP_RED    as i16,  pancurses::COLOR_RED,    pancurses::COLOR_BLUE);
-        pancurses::init_pair(COLOR_PAIR_STRIP_GREEN  as i16,  pancurses::COLOR_GREEN,  pancurses::COLOR_BLUE);
-        pancurses::init_pair(COLOR_PAIR_STRIP_CYAN   as i16,  pancurses::COLOR_CYAN,   pancurses::COLOR_BLUE);
-        pancurses::init_pair(COLOR_PAIR_EMSG         as i16,  pancurses::COLOR_YELLOW, pancurses::COLOR_BLACK);
-        pancurses::init_pair(COLOR_PAIR_MMSG         as i16,  pancurses::COLOR_WHITE,  pancurses::COLOR_BLACK);
-        pancurses::init_pair(COLOR_PAIR_PROMPT       as i16,  pancurses::COLOR_WHITE,  pancurses::COLOR_BLACK);
-
-        let mut user_interface = UserInterface {
-                                     window,
-                                     exit_request:                false,
-                                     logic_core_thread_running:   false,
-                                     video_thread_running:        false,
-
-                                     screen_width:                0,
-                                     screen_height:               0,
-                                     screen_too_small:            true,
-
-                                     redraw_text_area:            false,
-                                     redraw_status:               false,
-                                     redraw_prompt:               false,
-                                     redraw_everything:           true,
-
-                                     max_screen_lines:            MAX_SCREEN_LINES,
-                                     screen_lines:                VecDeque::with_capacity(MAX_SCREEN_LINES),
-                                     cached_screen_total_rows:    0,
-                                     cached_screen_free_rows:     0,
-                                     cached_penult_line_exists:   false,
-                                     cached_last_line_exists:     false,
-                                     bottom_rows_skip:            0,
-                                     lines_added_scrolled_up:     false,
-                                     emulator_msg_shown:          true,
-                                     machine_msg_shown:           true,
-
-                                     prompt_text:                 ScreenLine::new(ScreenLineType::EmulatorMessage, 0),
-                                     prompt_curs_code_pos:        0,
-                                     prompt_curs_cell_pos:        0,
-                                     prompt_scroll_cells:         0,
-
-                                     prompt_history:              VecDeque::with_capacity(MAX_HISTORY_ENTRIES),
+        } else if command == "video" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "fullscreen" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::Video(VideoSubCommand::Fullscreen(Some(true)))
+                        } else if state_str == "off" {
+                            ParsedUserCommand::Video(VideoSubCommand::Fullscreen(Some(false)))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Video(VideoSubCommand::Fullscreen(None))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "full-screen state".to_owned() }
+                        }
+                    } else if sub_command == "scaling" {
+                        match parameter_1 {
+                            Some((mode, mode_raw)) => {
+                                if mode == "integer" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Scaling(sdl_video::Scaling::Integer))
+                                } else if mode == "aspect" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Scaling(sdl_video::Scaling::Aspect))
+                                } else if mode == "stretch" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Scaling(sdl_video::Scaling::Stretch))
+                                } else {
+                                    ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: mode_raw, parameter_desc: "scaling mode".to_owned() }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "scaling mode".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "screenshot" {
+                        match parameter_1 {
+                            Some((size, size_raw)) => {
+                                if size == "native" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Screenshot(Some(false)))
+                                } else if size == "scaled" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Screenshot(Some(true)))
+                                } else {
+                                    ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: size_raw, parameter_desc: "screenshot resolution".to_owned() }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Video(VideoSubCommand::Screenshot(None))
+                            },
+                        }
+                    } else if sub_command == "text" {
+                        ParsedUserCommand::Video(VideoSubCommand::Text { file: util::get_starting_at_word(command_string, 3) })
+                    } else if sub_command == "charset" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "alternate" {
+                            ParsedUserCommand::Video(VideoSubCommand::Charset(Some(true)))
+                        } else if state_str == "graphics" {
+                            ParsedUserCommand::Video(VideoSubCommand::Charset(Some(false)))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Video(VideoSubCommand::Charset(None))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "character set".to_owned() }
+                        }
+                    } else if sub_command == "magnifier" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::Video(VideoSubCommand::Magnifier(Some(true)))
+                        } else if state_str == "off" {
+                            ParsedUserCommand::Video(VideoSubCommand::Magnifier(Some(false)))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Video(VideoSubCommand::Magnifier(None))
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "magnifier state".to_owned() }
+                        }
+                    } else if sub_command == "picture" {
+                        match parameter_1 {
+                            Some((setting_str, setting_str_raw)) => {
+                                let setting = if setting_str == "brightness" {
+                                    Some(PictureSetting::Brightness)
+                                } else if setting_str == "contrast" {
+                                    Some(PictureSetting::Contrast)
+                                } else if setting_str == "scanlines" {
+                                    Some(PictureSetting::Scanlines)
+                                } else {
+                                    None
+                                };
+                                match (setting, parameter_2) {
+                                    (Some(setting), Some((_, level_str))) => {
+                                        // A sign makes the level relative.
+                                        let relative = level_str.starts_with('+') || level_str.starts_with('-');
+                                        match level_str.parse::<i32>() {
+                                            Ok(level) if relative || level >= 0 => {
+                                                ParsedUserCommand::Video(VideoSubCommand::Picture(Some((setting, level, relative))))
+                                            },
+                                            _ => {
+                                                ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: level_str, parameter_desc: "level".to_owned() }
+                                            },
+                                        }
+                                    },
+                                    (Some(..), None) => {
+                                        ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "level".to_owned(), parameter_desc_ia: "a".to_owned() }
+                                    },
+                                    (None, _) => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: setting_str_raw, parameter_desc: "picture setting".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Video(VideoSubCommand::Picture(None))
+                            },
+                        }
+                    } else if sub_command == "colors" {
+                        match parameter_1 {
+                            Some((colors, colors_raw)) => {
+                                if colors == "white" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Colors { fg_color: (0xFF, 0xFF, 0xFF), bg_color: Some((0x00, 0x00, 0x00)) })
+                                } else if colors == "green" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Colors { fg_color: (0x00, 0xFF, 0x00), bg_color: Some((0x00, 0x00, 0x00)) })
+                                } else if colors == "amber" {
+                                    ParsedUserCommand::Video(VideoSubCommand::Colors { fg_color: (0xFF, 0xBF, 0x00), bg_color: Some((0x00, 0x00, 0x00)) })
+                                } else {
+                                    match proj_config::parse_color_argument(colors.as_str()) {
+                                        Some(fg_color) => {
+                                            match parameter_2 {
+                                                Some((bg_color, bg_color_raw)) => {
+                                                    match proj_config::parse_color_argument(bg_color.as_str()) {
+                                                        Some(bg_color) => {
+                                                            ParsedUserCommand::Video(VideoSubCommand::Colors { fg_color, bg_color: Some(bg_color) })
+                                                        },
+                                                        None => {
+                                                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: bg_color_raw, parameter_desc: "background color".to_owned() }
+                                                        },
+                                                    }
+                                                },
+                                                None => {
+                                                    ParsedUserCommand::Video(VideoSubCommand::Colors { fg_color, bg_color: None })
+                                                },
+                                            }
+                                        },
+                                        None => {
+                                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: colors_raw, parameter_desc: "color scheme".to_owned() }
+                                        },
+                                    }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "color scheme".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "keyboard" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "profile" {
+                        match parameter_1 {
+                            Some((name, name_raw)) => {
+                                if name == "none" || name == "off" {
+                                    ParsedUserCommand::Keyboard(KeyboardSubCommand::Profile(None))
+                                } else if sdl_keyboard::is_valid_profile_name(&name) {
+                                    ParsedUserCommand::Keyboard(KeyboardSubCommand::Profile(Some(name)))
+                                } else {
+                                    ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: name_raw, parameter_desc: "profile name".to_owned() }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::Keyboard(KeyboardSubCommand::ShowProfiles)
+                            },
+                        }
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "sound" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "stats" {
+                        ParsedUserCommand::Sound(EmulatorSoundCommand::Statistics)
+                    } else if sub_command == "mixer" {
+                        ParsedUserCommand::Sound(EmulatorSoundCommand::Mixer)
+                    } else if sub_command == "volume" {
+                        match parameter_1 {
+                            Some((_, volume_str)) => {
+                                match volume_str.parse::<u32>() {
+                                    Ok(volume) => {
+                                        ParsedUserCommand::Sound(EmulatorSoundCommand::Volume { volume })
+                                    },
+                                    Err(_) => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: volume_str, parameter_desc: "volume".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "volume".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "mute" || sub_command == "unmute" {
+                        let muted = sub_command == "mute";
+                        match parameter_1 {
+                            Some((source, source_raw)) => {
+                                if source == "tape" {
+                                    ParsedUserCommand::Sound(EmulatorSoundCommand::Mute { source: sound::Source::Tape, muted })
+                                } else if source == "program" {
+                                    ParsedUserCommand::Sound(EmulatorSoundCommand::Mute { source: sound::Source::Program, muted })
+                                } else if source == "orchestra" {
+                                    ParsedUserCommand::Sound(EmulatorSoundCommand::Mute { source: sound::Source::Orchestra, muted })
+                                } else if source == "ay" {
+                                    ParsedUserCommand::Sound(EmulatorSoundCommand::Mute { source: sound::Source::Ay, muted })
+                                } else {
+                                    ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: source_raw, parameter_desc: "sound source".to_owned() }
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "sound source".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "record" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "audio" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Record(EmulatorRecordCommand::Audio { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "video" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Record(EmulatorRecordCommand::Video { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "input" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Record(EmulatorRecordCommand::Input { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "replay" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Record(EmulatorRecordCommand::Replay { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "stop" {
+                        ParsedUserCommand::Record(EmulatorRecordCommand::Stop)
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "timer" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "start" {
+                        ParsedUserCommand::Timer(EmulatorTimerCommand::Start)
+                    } else if sub_command == "lap" {
+                        ParsedUserCommand::Timer(EmulatorTimerCommand::Lap)
+                    } else if sub_command == "stop" {
+                        ParsedUserCommand::Timer(EmulatorTimerCommand::Stop)
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "rhai" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "run" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(file) => {
+                                ParsedUserCommand::Rhai(EmulatorScriptCommand::Run { file })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "file".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "eval" {
+                        match util::get_starting_at_word(command_string, 3) {
+                            Some(code) => {
+                                ParsedUserCommand::Rhai(EmulatorScriptCommand::Eval { code })
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "code".to_owned(), parameter_desc_ia: "a".to_owned() }
+                            },
+                        }
+                    } else if sub_command == "stop" {
+                        ParsedUserCommand::Rhai(EmulatorScriptCommand::Stop)
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "debugger" {
+            match sub_command {
+                Some((sub_command, sub_command_raw)) => {
+                    if sub_command == "window" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::Debugger(EmulatorDebuggerCommand::Window { state: Some(true) })
+                        } else if state_str == "off" {
+                            ParsedUserCommand::Debugger(EmulatorDebuggerCommand::Window { state: Some(false) })
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::Debugger(EmulatorDebuggerCommand::Window { state: None })
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "window state".to_owned() }
+                        }
+                    } else if sub_command == "pane" {
+                        let (state_str, state_str_raw) = match parameter_1 {
+                                                             Some((parameter_1, parameter_1_raw)) => { (parameter_1, parameter_1_raw) },
+                                                             None => { ("toggle".to_owned(), "toggle".to_owned()) },
+                                                         };
+                        if state_str == "on" {
+                            ParsedUserCommand::DebuggerPane(Some(true))
+                        } else if state_str == "off" {
+                            ParsedUserCommand::DebuggerPane(Some(false))
+                        } else if state_str == "toggle" {
+                            ParsedUserCommand::DebuggerPane(None)
+                        } else {
+                            ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: state_str_raw, parameter_desc: "pane state".to_owned() }
+                        }
+                    } else if sub_command == "memory" {
+                        match parameter_1 {
+                            Some((_, address_str)) => {
+                                match util::parse_u32_from_str(address_str.as_str()) {
+                                    Some(address) if address <= 0xFFFF => {
+                                        ParsedUserCommand::Debugger(EmulatorDebuggerCommand::Memory { address: address as u16 })
+                                    },
+                                    _ => {
+                                        ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: address_str, parameter_desc: "address".to_owned() }
+                                    },
+                                }
+                            },
+                            None => {
+                                ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "address".to_owned(), parameter_desc_ia: "an".to_owned() }
+                            },
+                        }
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "config" {
+            match sub_command {
+                Some ((sub_command, sub_command_raw)) => {
+                    if sub_command == "list" {
+                        ParsedUserCommand::Config(EmulatorConfigCommand::List)
+                    } else if sub_command == "dump" {
+                        ParsedUserCommand::Config(EmulatorConfigCommand::Dump)
+                    } else if sub_command == "show" {
+                        let entry_specifier = match parameter_1 {
+                                                  Some((_, parameter_1_raw)) => { parameter_1_raw },
+                                                  None => {
+                                                      return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "entry specifier".to_owned(), parameter_desc_ia: "an".to_owned() };
+                                                  },
+                                              };
+                        ParsedUserCommand::Config(EmulatorConfigCommand::Show { entry_specifier: entry_specifier })
+                    } else if sub_command == "change" {
+                        let entry_specifier = match parameter_1 {
+                                                  Some((_, parameter_1_raw)) => { parameter_1_raw },
+                                                  None => {
+                                                      return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "entry specifier".to_owned(), parameter_desc_ia: "an".to_owned() };
+                                                  },
+                                              };
+                        let equals_sign = match parameter_2 {
+                                              Some((_, parameter_2_raw)) => { parameter_2_raw },
+                                              None => {
+                                                  return ParsedUserCommand::CommandMissingParameter { sup_command_name: command, sub_command_name: sub_command, parameter_desc: "new value specifier".to_owned(), parameter_desc_ia: "a".to_owned() };
+                                              },
+                                          };
+                        if equals_sign.chars().next().expect("Some((_, parameter_2_raw)) implies non-zero length") != '=' {
+                            return ParsedUserCommand::InvalidParameter { sup_command_name: command, sub_command_name: sub_command, parameter_text: equals_sign, parameter_desc: "new value specifier".to_owned() };
+                        }
+                        ParsedUserCommand::Config(EmulatorConfigCommand::Change { entry_specifier: entry_specifier, invocation_text: command_string.to_owned() })
+                    } else {
+                        ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else if command == "browse" {
+            match sub_command {
+                Some ((sub_command, sub_command_raw)) => {
+                    match file_picker::Purpose::from_name(&sub_command) {
+                        Some(purpose) => {
+                            ParsedUserCommand::Browse { purpose, dir: util::get_starting_at_word(command_string, 3) }
+                        },
+                        None => {
+                            ParsedUserCommand::InvalidSubCommand { sup_command_name: command, sub_command_name: sub_command_raw }
+                        },
+                    }
+                },
+                None => {
+                    ParsedUserCommand::CommandMissingSubcommand { sup_command_name: command }
+                },
+            }
+        } else {
+            ParsedUserCommand::InvalidCommand { command_name: command_raw }
+        }
+    }
+}
+
+
+pub struct UserInterface {
+    window:                      Option<pancurses::Window>,  // None in the headless mode
+    exit_request:                bool,
+    logic_core_thread_running:   bool,
+    video_thread_running:        bool,
+
+    screen_width:                usize,
+    screen_height:               usize,
+    screen_too_small:            bool,
+
+    redraw_text_area:            bool,
+    redraw_status:               bool,
+    redraw_prompt:               bool,
+    redraw_everything:           bool,
+
+    max_screen_lines:            usize,
+    screen_lines:                VecDeque<ScreenLine>,
+    cached_screen_total_rows:    usize,
+    cached_screen_free_rows:     usize,
+    cached_penult_line_exists:   bool,
+    cached_last_line_exists:     bool,
+    bottom_rows_skip:            usize,
+    lines_added_scrolled_up:     bool,
+    emulator_msg_shown:          bool,
+    machine_msg_shown:           bool,
+    message_filter:              MessageFilter,
+    logged_modules:              Vec<String>,    // The modules which logged messages so far.
+    transcript:                  transcript::Transcript,
+
+    prompt_text:                 ScreenLine,
+    prompt_curs_code_pos:        usize,  // cursor position at a Rust char
+    prompt_curs_cell_pos:        usize,  // cursor position at a screen cell
+    prompt_scroll_cells:         usize,
+
+    prompt_history:              VecDeque<ScreenLine>,
+    prompt_history_max_entries:  usize,
+    prompt_history_pos:          usize,  // 0 refers to prompt_text
+    history_save_failed:         bool,
+
+    // The reverse search through the history, started with Ctrl+R, with the
+    // history position and the cursor to go back to if it's cancelled.
+    prompt_search:               Option<String>,
+    prompt_search_origin:        (usize, usize),
+
+    // The file names typed on the prompt are completed from here.
+    config_dir:                  path::PathBuf,
+
+    // The file picker, drawn in place of the messages while it's open.
+    file_picker:                 Option<file_picker::FilePicker>,
+
+    cpu_halted:                  bool,
+    machine_powered_on:          bool,
+    machine_paused:              bool,
+    machine_warp:                bool,
+    cassette_counter:            Option<(usize, usize)>,
+    cassette_motor:              (bool, bool),
+    performance:                 (Option<u32>, Option<u32>), // Speed, FPS.
+    clock:                       Option<u64>,    // The emulated seconds since power-on.
+    progress:                    Option<(String, usize, usize)>,
+    cancel_flag:                 CancelFlag,
+
+    // The terminal-only mode, the rows of the screen of the machine come
+    // from the terminal front-end, and the keys can be sent to the machine.
+    machine_screen_rx:           Option<mpsc::Receiver<Vec<String>>>,
+    machine_screen:              Vec<String>,
+    redraw_machine_screen:       bool,
+
+    // The registers, the speed and the media of the machine, updated while
+    // it runs, if the pane is shown.
+    status_pane_shown:           bool,
+    status_pane:                 Vec<String>,
+    redraw_status_pane:          bool,
+
+    // The registers, the instructions and the memory of the machine, the
+    // same as in the debugger window, if the pane is shown.
+    debugger_pane_shown:         bool,
+    debugger_pane:               Vec<String>,
+    redraw_debugger_pane:        bool,
+
+    // The sizes of the panes, and the one with the keyboard focus.
+    layout:                      layout::Layout,
+
+    // The lines read from the standard input in the headless mode, or sent
+    // by the graphical interface, which also takes the messages.
+    headless_input_rx:           Option<mpsc::Receiver<String>>,
+    headless_unattended:         bool, // Nobody is there to confirm them.
+    #[cfg(feature = "egui")]
+    gui_event_tx:                Option<mpsc::Sender<GuiEvent>>,
+
+    // The lines of the scripts which are yet to be run, after a wait, and
+    // whether they're run unattended, so the actions which are otherwise
+    // asked for twice aren't.
+    script_lines:                VecDeque<(String, bool)>,
+    unattended:                  bool,
+    script_wait_until:           Option<Instant>,
+
+    // The command aliases of the configuration file.
+    aliases:                     Vec<aliases::Alias>,
+
+    // The colors of the parts of the curses-based interface.
+    theme_colors:                ui_theme::ThemeColors,
+
+    // The programs driving the emulator over a socket, if it's enabled.
+    remote_control:              Option<remote_control::RemoteControl>,
+}
+
+impl UserInterface {
+    // The lines of the standard input, for the headless mode.
+    pub fn stdin_lines() -> mpsc::Receiver<String> {
+        let (input_tx, input_rx) = mpsc::channel();
+        thread::Builder::new().name("headless_input".to_owned()).spawn(move || {
+            for line in io::stdin().lines() {
+                match line {
+                    Ok(line) => {
+                        if input_tx.send(line).is_err() {
+                            return;
+                        }
+                    },
+                    Err(..) => { return; },
+                }
+            }
+        }).unwrap();
+        input_rx
+    }
+    pub fn new(config_dir: path::PathBuf, machine_screen_rx: Option<mpsc::Receiver<Vec<String>>>, console: Console) -> Option<UserInterface> {
+
+        #[cfg(feature = "egui")]
+        let gui_event_tx = match &console {
+            Console::Gui(_, event_tx) => { Some(event_tx.clone()) },
+            _ => { None },
+        };
+        // Only the curses-based interface touches the terminal.
+        let headless_unattended = matches!(console, Console::Headless(..));
+        let (window, headless_input_rx) = match console {
+            Console::Headless(input_rx) => {
+                (None, Some(input_rx))
+            },
+            #[cfg(feature = "egui")]
+            Console::Gui(input_rx, _) => {
+                (None, Some(input_rx))
+            },
+            Console::Curses => {
+                let window = pancurses::initscr();
+                pancurses::start_color();
+                pancurses::cbreak();
+                pancurses::noecho();
+                pancurses::nonl();
+                window.nodelay(true);
+                window.keypad(true);
+
+                // The themes can keep the colors of the terminal.
+                pancurses::use_default_colors();
+
+                (Some(window), None)
+            },
+        };
+
+        let mut user_interface = UserInterface {
+                                     window,
+                                     exit_request:                false,
+                                     logic_core_thread_running:   false,
+                                     video_thread_running:        false,
+
+                                     screen_width:                0,
+                                     screen_height:               0,
+                                     screen_too_small:            true,
+
+                                     redraw_text_area:            false,
+                                     redraw_status:               false,
+                                     redraw_prompt:               false,
+                                     redraw_everything:           true,
+
+                                     max_screen_lines:            MAX_SCREEN_LINES,
+                                     screen_lines:                VecDeque::with_capacity(MAX_SCREEN_LINES),
+                                     cached_screen_total_rows:    0,
+                                     cached_screen_free_rows:     0,
+                                     cached_penult_line_exists:   false,
+                                     cached_last_line_exists:     false,
+                                     bottom_rows_skip:            0,
+                                     lines_added_scrolled_up:     false,
+                                     emulator_msg_shown:          true,
+                                     machine_msg_shown:           true,
+                                     message_filter:              MessageFilter { level: log::Level::Info, module: None },
+                                     logged_modules:              Vec::new(),
+                                     transcript:                  transcript::Transcript::new(),
+
+                                     prompt_text:                 ScreenLine::new(ScreenLineType::EmulatorMessage, 0),
+                                     prompt_curs_code_pos:        0,
+                                     prompt_curs_cell_pos:        0,
+                                     prompt_scroll_cells:         0,
+
+                                     prompt_history:              VecDeque::with_capacity(MAX_HISTORY_ENTRIES),
                                      prompt_history_max_entries:  MAX_HISTORY_ENTRIES,
                                      prompt_history_pos:          0,
+                                     history_save_failed:         false,
+
+                                     prompt_search:               None,
+                                     prompt_search_origin:        (0, 0),
+
+                                     config_dir,
+
+                                     file_picker:                 None,
 
                                      cpu_halted:                  false,
                                      machine_powered_on:          false,
                                      machine_paused:              false,
+                                     machine_warp:                false,
+                                     cassette_counter:            None,
+                                     cassette_motor:              (false, false),
+                                     performance:                 (None, None),
+                                     clock:                       None,
+                                     progress:                    None,
+                                     cancel_flag:                 CancelFlag::new(),
+
+                                     machine_screen_rx,
+                                     machine_screen:              Vec::new(),
+                                     redraw_machine_screen:       false,
+
+                                     status_pane_shown:           false,
+                                     status_pane:                 Vec::new(),
+                                     redraw_status_pane:          false,
+
+                                     debugger_pane_shown:         false,
+                                     debugger_pane:               Vec::new(),
+                                     redraw_debugger_pane:        false,
+
+                                     layout:                      layout::Layout::new(),
+
+                                     headless_input_rx,
+                                     headless_unattended,
+                                     #[cfg(feature = "egui")]
+                                     gui_event_tx,
+
+                                     script_lines:                VecDeque::new(),
+                                     unattended:                  false,
+                                     script_wait_until:           None,
+                                     aliases:                     Vec::new(),
+
+                                     theme_colors:                ui_theme::Theme::Dark.colors(),
+
+                                     remote_control:              None,
                                  };
-        user_interface.handle_resize_event();
+        if user_interface.window.is_some() {
+            user_interface.init_color_pairs();
+            user_interface.handle_resize_event();
+        }
+        user_interface.load_prompt_history();
 
         Some(user_interface)
     }
+    // Run the commands of a script, one per line, as if they were typed on
+    // the prompt.  The slash in front of them can be left out, and the empty
+    // lines and the ones starting with `#' are skipped.  Scripts can also
+    // `wait <seconds>' before going on with the next command.
+    pub fn run_script(&mut self, script_path: &path::Path) {
+        let script = match fs::read_to_string(script_path) {
+            Ok(script) => { script },
+            Err(error) => {
+                self.emulator_message(&i18n::tr_format("Failed to read the script `{}': {}.", &[&script_path.display(), &error]));
+                return;
+            },
+        };
+        self.emulator_message(&i18n::tr_format("Running the script `{}'.", &[&script_path.display()]));
+
+        self.script_lines.extend(script.lines().map(|line| (line.to_owned(), true)));
+    }
+    // The aliases named like the commands, or like `wait' of the scripts,
+    // would never be used.
+    pub fn set_aliases(&mut self, aliases: Vec<aliases::Alias>) {
+        self.aliases.clear();
+        for alias in aliases {
+            if completion::is_command(&alias.name) || alias.name == "wait" {
+                warn!("The alias `{}' is named like a command, so it's never used.", alias.name);
+            } else {
+                self.aliases.push(alias);
+            }
+        }
+    }
+    // The flag the long operations of the logic core are cancelled with.
+    pub fn set_cancel_flag(&mut self, cancel_flag: CancelFlag) {
+        self.cancel_flag = cancel_flag;
+    }
+    pub fn set_theme(&mut self, theme_colors: ui_theme::ThemeColors) {
+        self.theme_colors = theme_colors;
+        if self.window.is_some() {
+            self.init_color_pairs();
+            self.redraw_everything = true;
+        }
+    }
+    // The lines already shown stay in the language they were written in.
+    pub fn set_language(&mut self, language: &str) {
+        i18n::set_language(language);
+        if self.window.is_some() {
+            self.redraw_everything = true;
+        }
+    }
+    fn init_color_pairs(&self) {
+        let colors = &self.theme_colors;
+        pancurses::init_pair(COLOR_PAIR_STRIP_GRAY   as i16,  colors.status.0,        colors.status.1);
+        pancurses::init_pair(COLOR_PAIR_STRIP_RED    as i16,  colors.status_off.0,    colors.status_off.1);
+        pancurses::init_pair(COLOR_PAIR_STRIP_GREEN  as i16,  colors.status_on.0,     colors.status_on.1);
+        pancurses::init_pair(COLOR_PAIR_STRIP_CYAN   as i16,  colors.status_accent.0, colors.status_accent.1);
+        pancurses::init_pair(COLOR_PAIR_EMSG         as i16,  colors.message.0,       colors.message.1);
+        pancurses::init_pair(COLOR_PAIR_MMSG         as i16,  colors.machine.0,       colors.machine.1);
+        pancurses::init_pair(COLOR_PAIR_PROMPT       as i16,  colors.prompt.0,        colors.prompt.1);
+        pancurses::init_pair(COLOR_PAIR_WARNING      as i16,  colors.warning.0,       colors.warning.1);
+        pancurses::init_pair(COLOR_PAIR_ERROR        as i16,  colors.error.0,         colors.error.1);
+    }
+    pub fn set_remote_control(&mut self, remote_control: remote_control::RemoteControl) {
+        self.remote_control = Some(remote_control);
+    }
+    // Each line from a client of the remote control is a command, and once
+    // the logic core gets to the acknowledgement sent after it, the client
+    // is told it's done.
+    fn handle_remote_commands(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
+        let commands = match &mut self.remote_control {
+            Some(remote_control) => { remote_control.take_commands() },
+            None => { return; },
+        };
+        for (client, line) in commands {
+            let command = line.trim();
+            let command = command.strip_prefix('/').unwrap_or(command);
+            if !command.is_empty() {
+                self.transcript.command(&format!("/{}", command));
+                self.execute_command_as(emu_cmd_tx, command, true);
+            }
+            // After `exit', the logic core may be gone already.
+            let _ = emu_cmd_tx.send(EmulatorCommand::Acknowledge(client));
+        }
+    }
+    fn run_script_lines(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
+        while let Some((line, unattended)) = self.script_lines.pop_front() {
+            if let Some(wait_until) = self.script_wait_until {
+                if Instant::now() < wait_until {
+                    self.script_lines.push_front((line, unattended));
+                    return;
+                }
+                self.script_wait_until = None;
+            }
+            let command = line.trim();
+            let command = command.strip_prefix('/').unwrap_or(command);
+            if command.is_empty() || command.starts_with('#') {
+                continue;
+            }
+            self.transcript.command(&format!("/{}", command));
+
+            match util::get_word(command, 1) {
+                Some(ref word) if word.to_lowercase() == "wait" => {
+                    let time_str = util::get_word(command, 2).unwrap_or_default();
+                    match time_str.parse::<f64>() {
+                        Ok(seconds) if seconds >= 0.0 && seconds.is_finite() => {
+                            self.script_wait_until = Some(Instant::now() + Duration::from_secs_f64(seconds));
+                        },
+                        _ => {
+                            self.emulator_message(&i18n::tr_format("Invalid time to wait in the script: `{}'.", &[&time_str]));
+                        },
+                    }
+                },
+                _ => {
+                    self.execute_command_as(emu_cmd_tx, command, unattended);
+                },
+            }
+        }
+    }
+    // The scripts, the standard input of the headless mode and the remote
+    // clients can't ask for an action again, so the logic core carries out
+    // their commands without a confirmation.
+    fn execute_command_as(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, command: &str, unattended: bool) {
+        let outer = self.unattended;
+        if unattended != outer {
+            let _ = emu_cmd_tx.send(EmulatorCommand::SetUnattended(unattended));
+        }
+        self.unattended = unattended;
+        self.execute_command(emu_cmd_tx, command);
+        self.unattended = outer;
+        if unattended != outer {
+            let _ = emu_cmd_tx.send(EmulatorCommand::SetUnattended(outer));
+        }
+    }
     pub fn run(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, emu_stat_rx: &mpsc::Receiver<EmulatorStatus>, msg_source: &util::MessageLogger) {
         let sleep_len = Duration::from_millis(10);
         let mut waiting_for_logic_core_thread = true;
         let mut waiting_for_video_thread = true;
 
         while !self.exit_request || ((waiting_for_logic_core_thread || self.logic_core_thread_running) || (waiting_for_video_thread || self.video_thread_running)) {
-            self.handle_user_input(emu_cmd_tx);
+            if self.window.is_some() {
+                self.handle_user_input(emu_cmd_tx);
+            } else {
+                self.handle_headless_input();
+            }
+            self.run_script_lines(emu_cmd_tx);
+            self.handle_remote_commands(emu_cmd_tx);
 
             for emulator_status in emu_stat_rx.try_iter() {
-                self.handle_emulator_status_info(emulator_status, &mut waiting_for_logic_core_thread, &mut waiting_for_video_thread);
+                self.handle_emulator_status_info(emulator_status, msg_source, &mut waiting_for_logic_core_thread, &mut waiting_for_video_thread);
             }
-            match msg_source.collect_messages() {
-                Some(messages) => {
-                    for logged_msg in messages {
-                        self.emulator_message(logged_msg.as_str());
-                    }
-                },
-                None => { },
+            let machine_screen = match &self.machine_screen_rx {
+                Some(machine_screen_rx) => { machine_screen_rx.try_iter().last() },
+                None => { None },
+            };
+            if let Some(machine_screen) = machine_screen {
+                self.machine_screen = machine_screen;
+                self.redraw_machine_screen = true;
+            }
+            self.show_logged_messages(msg_source);
+            if self.window.is_some() {
+                self.update_screen();
             }
-            self.update_screen();
             thread::sleep(sleep_len);
         }
     }
-    fn handle_emulator_status_info(&mut self, emulator_status: EmulatorStatus, waiting_for_logic_core_thread: &mut bool, waiting_for_video_thread: &mut bool) {
+    // In the headless mode, each line of the standard input is a command,
+    // the same as in a script, and so is each one the graphical interface
+    // sends.
+    fn handle_headless_input(&mut self) {
+        if let Some(input_rx) = &self.headless_input_rx {
+            let unattended = self.headless_unattended;
+            self.script_lines.extend(input_rx.try_iter().map(|line| (line, unattended)));
+        }
+    }
+    fn window(&self) -> &pancurses::Window {
+        self.window.as_ref().expect("there's no curses window in the headless mode")
+    }
+    fn show_logged_messages(&mut self, msg_source: &util::MessageLogger) {
+        match msg_source.collect_messages() {
+            Some(messages) => {
+                for logged_msg in messages {
+                    self.logged_message(logged_msg);
+                }
+            },
+            None => { },
+        }
+    }
+    fn handle_emulator_status_info(&mut self, emulator_status: EmulatorStatus, msg_source: &util::MessageLogger, waiting_for_logic_core_thread: &mut bool, waiting_for_video_thread: &mut bool) {
 
         match emulator_status {
             EmulatorStatus::Created => {
                 self.logic_core_thread_running = true;
                 *waiting_for_logic_core_thread = false;
-                self.emulator_message("Logic core thread started.");
+                self.emulator_message(&i18n::tr("Logic core thread started."));
             },
             EmulatorStatus::Destroyed => {
                 if !self.exit_request {
@@ -866,7 +2114,7 @@ impl UserInterface {
             EmulatorStatus::VideoThreadCreated => {
                 self.video_thread_running = true;
                 *waiting_for_video_thread = false;
-                self.emulator_message("SDL2 front-end thread started.");
+                self.emulator_message(&i18n::tr("SDL2 front-end thread started."));
             },
             EmulatorStatus::VideoThreadDestroyed => {
                 if !self.exit_request {
@@ -899,6 +2147,12 @@ impl UserInterface {
                     self.redraw_status = true;
                 }
             },
+            EmulatorStatus::Warp(warp) => {
+                if self.machine_warp != warp {
+                    self.machine_warp = warp;
+                    self.redraw_status = true;
+                }
+            },
             EmulatorStatus::CpuHalted => {
                 if !self.cpu_halted {
                     self.cpu_halted = true;
@@ -911,20 +2165,120 @@ impl UserInterface {
                     self.redraw_status = true;
                 }
             },
+            EmulatorStatus::CassetteCounter(counter) => {
+                if self.cassette_counter != counter {
+                    self.cassette_counter = counter;
+                    self.redraw_status = true;
+                }
+            },
+            EmulatorStatus::CassetteMotor { running, forced } => {
+                if self.cassette_motor != (running, forced) {
+                    self.cassette_motor = (running, forced);
+                    self.redraw_status = true;
+                }
+            },
+            EmulatorStatus::Performance { speed, frame_rate } => {
+                if self.performance != (speed, frame_rate) {
+                    self.performance = (speed, frame_rate);
+                    self.redraw_status = true;
+                }
+            },
+            EmulatorStatus::Clock(clock) => {
+                self.clock = clock;
+                self.redraw_status = true;
+            },
+            EmulatorStatus::Progress(progress) => {
+                self.progress = progress;
+                self.redraw_status = true;
+            },
+            EmulatorStatus::DebugState(lines) => {
+                #[cfg(feature = "egui")]
+                if let Some(event_tx) = &self.gui_event_tx {
+                    let _ = event_tx.send(GuiEvent::DebugState(lines));
+                    return;
+                }
+                if self.debugger_pane_shown {
+                    self.debugger_pane = lines;
+                    self.redraw_debugger_pane = true;
+                }
+            },
+            EmulatorStatus::SuggestCommand(command) => {
+                self.suggest_command(&command);
+            },
+            EmulatorStatus::BrowseCassettes(dir) => {
+                self.show_file_picker(file_picker::Purpose::Cassette, dir);
+            },
+            EmulatorStatus::Aliases(aliases) => {
+                self.set_aliases(aliases);
+            },
+            EmulatorStatus::UiTheme(theme_colors) => {
+                self.set_theme(theme_colors);
+            },
+            EmulatorStatus::Language(language) => {
+                self.set_language(&language);
+            },
+            EmulatorStatus::StatusPane(lines) => {
+                if self.status_pane_shown {
+                    self.status_pane = lines;
+                    self.redraw_status_pane = true;
+                }
+            },
+            EmulatorStatus::Acknowledged(client) => {
+                // The messages logged before the acknowledgement go first.
+                self.show_logged_messages(msg_source);
+                if let Some(remote_control) = &mut self.remote_control {
+                    remote_control.send_done(client);
+                }
+            },
         }
     }
     pub fn handle_user_input(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
         loop {
-            let user_input = self.window.getch();
+            let user_input = self.window().getch();
+            if self.file_picker.is_some() {
+                match user_input {
+                    Some(input) => {
+                        self.file_picker_handle_key(emu_cmd_tx, input);
+                        continue;
+                    },
+                    None => {
+                        break;
+                    },
+                }
+            }
+            if self.layout.focus() == layout::Pane::Machine {
+                match user_input {
+                    Some(input) => {
+                        self.handle_machine_key(emu_cmd_tx, input);
+                        continue;
+                    },
+                    None => {
+                        break;
+                    },
+                }
+            }
+            if matches!(self.layout.focus(), layout::Pane::Status | layout::Pane::Debugger | layout::Pane::Messages) {
+                if let Some(input) = user_input {
+                    if self.handle_pane_key(input) {
+                        continue;
+                    }
+                }
+            }
             match user_input {
                 Some(input) => {
+                    if self.prompt_search.is_some() && self.prompt_search_handle_key(&input) {
+                        continue;
+                    }
                     match input {
                         pancurses::Input::KeyResize     => { self.handle_resize_event() },
 
                         pancurses::Input::KeyF1         => { self.execute_command(emu_cmd_tx, "help"); },
+                        pancurses::Input::KeyF6         => { self.focus_next_pane(); },
 
                         pancurses::Input::KeyNPage      => { self.scroll_lines_down(); },
                         pancurses::Input::KeyPPage      => { self.scroll_lines_up(); },
+                        pancurses::Input::KeySHome      => { self.scroll_lines_top(); },
+                        pancurses::Input::KeySEnd       => { self.scroll_lines_bottom(); },
 
                         pancurses::Input::KeyLeft       => { self.prompt_move_cursor_left(); },
                         pancurses::Input::KeyRight      => { self.prompt_move_cursor_right(); },
@@ -948,9 +2302,12 @@ impl UserInterface {
                             if (input_char as u32) < 0x20 {
                                 match input_char as u8 {
                                     0x08  => { self.prompt_handle_backspace_key(); },                        // Backspace (w32)
-                                    0x0C  => { self.window.clearok(true); self.redraw_everything = true; },  // CTRL+L
+                                    0x09  => { self.prompt_handle_tab_key(); },                              // Tab
+                                    0x0C  => { self.window().clearok(true); self.redraw_everything = true; },  // CTRL+L
                                     0x0D  => { self.prompt_handle_enter_key(emu_cmd_tx); },                  // Enter
+                                    0x12  => { self.prompt_start_search(); },                                // CTRL+R
                                     0x15  => { self.prompt_handle_ctrl_u(); },                               // CTRL+U
+                                    0x1B  => { self.cancel_progress(); },                                    // Escape
                                     _     => { },
                                 }
                             } else {
@@ -967,9 +2324,208 @@ impl UserInterface {
             }
         }
     }
+    // Escape cancels the long operation of the logic core, while there's
+    // one going on.
+    fn cancel_progress(&mut self) {
+        if self.progress.is_some() {
+            self.cancel_flag.cancel();
+        }
+    }
+    // While a command is being typed, Tab completes the word before the
+    // cursor, otherwise, in the terminal-only mode, it switches the keyboard
+    // between the command line and the emulated machine.
+    fn prompt_handle_tab_key(&mut self) {
+        if self.prompt_history_pos > 0 {
+            self.prompt_text = self.prompt_history[self.prompt_history_pos - 1].clone();
+            self.prompt_history_pos = 0;
+        }
+        if self.prompt_text.line_codes.first() != Some(&'/') || self.prompt_curs_code_pos == 0 {
+            self.toggle_keyboard_capture();
+            return;
+        }
+        let typed: String = self.prompt_text.line_codes[1..self.prompt_curs_code_pos].iter().collect();
+        let completion = completion::complete(&typed, &self.config_dir, &self.aliases);
+        let typed_word: String = typed.chars().skip(completion.word_start).collect();
+
+        let replacement = match completion.candidates.len() {
+            0 => {
+                pancurses::beep();
+                return;
+            },
+            1 => {
+                let candidate = &completion.candidates[0];
+                if candidate.ends_with('/') { candidate.clone() } else { format!("{} ", candidate) }
+            },
+            _ => {
+                let common = completion::common_prefix(&completion.candidates);
+                if common.chars().count() > typed_word.chars().count() {
+                    common
+                } else {
+                    self.emulator_message(&completion.candidates.join("  "));
+                    return;
+                }
+            },
+        };
+        for _ in 0..typed_word.chars().count() {
+            self.prompt_handle_backspace_key();
+        }
+        for ch in replacement.chars() {
+            self.prompt_insert_char(ch);
+        }
+    }
+    // In the terminal-only mode, Tab switches the keyboard between the
+    // command line and the emulated machine.
+    fn toggle_keyboard_capture(&mut self) {
+        if self.machine_screen_rx.is_some() {
+            if self.layout.focus() == layout::Pane::Machine {
+                self.set_focus(layout::Pane::Prompt);
+            } else {
+                self.set_focus(layout::Pane::Machine);
+            }
+        }
+    }
+    fn set_focus(&mut self, pane: layout::Pane) {
+        if self.layout.focus() != pane {
+            self.layout.set_focus(pane);
+            self.redraw_status = true;
+            self.redraw_debugger_pane = true;
+        }
+    }
+    // F6 moves the focus on to the next of the panes shown, from the top of
+    // the screen down.
+    fn focus_next_pane(&mut self) {
+        let mut shown = Vec::new();
+        if self.machine_screen_rx.is_some() {
+            shown.push(layout::Pane::Machine);
+        }
+        if self.status_pane_shown {
+            shown.push(layout::Pane::Status);
+        }
+        if self.debugger_pane_shown {
+            shown.push(layout::Pane::Debugger);
+        }
+        shown.push(layout::Pane::Messages);
+        shown.push(layout::Pane::Prompt);
+
+        self.layout.focus_next(&shown);
+        self.redraw_status = true;
+        self.redraw_debugger_pane = true;
+    }
+    // The keys of the status pane, the debugger pane and the messages while
+    // they have the focus, the arrows scroll them, and `+' and `-' change
+    // the size of the first two.  The rest of the keys give the focus back
+    // to the command line, and the ones which aren't used up here, like the
+    // characters, are handled there.
+    fn handle_pane_key(&mut self, input: pancurses::Input) -> bool {
+        let pane = self.layout.focus();
+        let page = if pane == layout::Pane::Messages { (self.screen_height / 2) as isize } else { self.layout.rows(pane).max(1) as isize };
+        match input {
+            pancurses::Input::KeyResize |
+            pancurses::Input::KeyF1     |
+            pancurses::Input::KeyF6     => { false },
+
+            pancurses::Input::KeyUp     => { self.scroll_pane(pane, -1); true },
+            pancurses::Input::KeyDown   => { self.scroll_pane(pane, 1); true },
+            pancurses::Input::KeyPPage  => { self.scroll_pane(pane, -page); true },
+            pancurses::Input::KeyNPage  => { self.scroll_pane(pane, page); true },
+            pancurses::Input::KeyHome   => { self.scroll_pane(pane, isize::MIN); true },
+            pancurses::Input::KeyEnd    => { self.scroll_pane(pane, isize::MAX); true },
+
+            pancurses::Input::Character('+') => { self.resize_pane(pane, true); true },
+            pancurses::Input::Character('-') => { self.resize_pane(pane, false); true },
+            pancurses::Input::Character('\t') => { self.focus_next_pane(); true },
+
+            pancurses::Input::KeyEnter |
+            pancurses::Input::Character('\r') |
+            pancurses::Input::Character('\x1B') => { self.set_focus(layout::Pane::Prompt); true },
+
+            _ => {
+                self.set_focus(layout::Pane::Prompt);
+                false
+            },
+        }
+    }
+    // Scroll the pane by the given number of rows, the negative ones towards
+    // its start, which for the messages are the older ones.
+    fn scroll_pane(&mut self, pane: layout::Pane, rows: isize) {
+        match pane {
+            layout::Pane::Messages => {
+                if rows == isize::MIN {
+                    self.scroll_lines_top();
+                } else if rows == isize::MAX {
+                    self.scroll_lines_bottom();
+                } else if rows < 0 {
+                    self.scroll_lines_up_by(rows.unsigned_abs());
+                } else {
+                    self.scroll_lines_down_by(rows as usize);
+                }
+            },
+            layout::Pane::Status => {
+                self.layout.scroll_by(pane, rows, self.status_pane.len());
+                self.redraw_status_pane = true;
+            },
+            layout::Pane::Debugger => {
+                self.layout.scroll_by(pane, rows, self.debugger_pane.len());
+                self.redraw_debugger_pane = true;
+            },
+            _ => { },
+        }
+    }
+    fn resize_pane(&mut self, pane: layout::Pane, grow: bool) {
+        let spare_rows = (self.screen_height - LINES_BOTTOM_OFFSET).saturating_sub(self.lines_top_offset() + layout::MIN_MESSAGE_ROWS);
+        if self.layout.resize(pane, grow, spare_rows) {
+            self.redraw_everything = true;
+        } else {
+            pancurses::beep();
+        }
+    }
+    // Type a key in on the keyboard of the emulated machine, the keys
+    // which don't produce characters are bound the same way as in the
+    // SDL2-based interface, where the terminal has them.
+    fn handle_machine_key(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, input: pancurses::Input) {
+        let special_key = match input {
+            pancurses::Input::KeyResize     => { self.handle_resize_event(); return; },
+            pancurses::Input::KeyF1         => { self.execute_command(emu_cmd_tx, "help"); return; },
+            pancurses::Input::KeyF6         => { self.focus_next_pane(); return; },
+            pancurses::Input::KeyNPage      => { self.scroll_lines_down(); return; },
+            pancurses::Input::KeyPPage      => { self.scroll_lines_up(); return; },
+            pancurses::Input::KeySHome      => { self.scroll_lines_top(); return; },
+            pancurses::Input::KeySEnd       => { self.scroll_lines_bottom(); return; },
+
+            pancurses::Input::KeyLeft       => { keyboard::SpecialKey::Left },
+            pancurses::Input::KeyRight      => { keyboard::SpecialKey::Right },
+            pancurses::Input::KeyUp         => { keyboard::SpecialKey::Up },
+            pancurses::Input::KeyDown       => { keyboard::SpecialKey::Down },
+            pancurses::Input::KeyBackspace  => { keyboard::SpecialKey::Left },
+            pancurses::Input::KeyIC         => { keyboard::SpecialKey::Break },
+            pancurses::Input::KeyDC         => { keyboard::SpecialKey::Clear },
+            pancurses::Input::KeyHome       => { keyboard::SpecialKey::Clear },
+
+            pancurses::Input::KeyEnter      => { emu_cmd_tx.send(EmulatorCommand::TypeText("\n".to_owned())).unwrap(); return; },
+
+            pancurses::Input::Character(input_char) => {
+                match input_char {
+                    '\t'              => { self.toggle_keyboard_capture(); return; },
+                    '\x0C'            => { self.window().clearok(true); self.redraw_everything = true; return; },  // CTRL+L
+                    '\x08' | '\x7F'   => { keyboard::SpecialKey::Left },    // Backspace
+                    '\x1B'            => { keyboard::SpecialKey::Break },   // Escape
+                    '\r' | '\n'       => { emu_cmd_tx.send(EmulatorCommand::TypeText("\n".to_owned())).unwrap(); return; },
+                    _ => {
+                        if !input_char.is_control() {
+                            emu_cmd_tx.send(EmulatorCommand::TypeText(input_char.to_string())).unwrap();
+                        }
+                        return;
+                    },
+                }
+            },
+
+            _ => { return; },
+        };
+        emu_cmd_tx.send(EmulatorCommand::TypeSpecialKey(special_key)).unwrap();
+    }
     fn handle_resize_event(&mut self) {
-        let new_width  = self.window.get_max_x();
-        let new_height = self.window.get_max_y();
+        let new_width  = self.window().get_max_x();
+        let new_height = self.window().get_max_y();
 
         if (new_width  as usize) != self.screen_width  ||
            (new_height as usize) != self.screen_height {
@@ -978,17 +2534,22 @@ impl UserInterface {
             self.screen_width  = new_width  as usize;
             self.screen_height = new_height as usize;
 
-            if self.screen_width < MIN_SCREEN_WIDTH ||
-               self.screen_height < MIN_SCREEN_HEIGHT {
-                self.screen_too_small = true;
-            } else {
-                self.screen_too_small = false;
-                self.prompt_scroll_cells = 0;
-                self.scroll_prompt_if_needed();
-            }
+            self.check_screen_size();
             self.redraw_everything = true;
         }
     }
+    fn check_screen_size(&mut self) {
+        self.fit_panes();
+        let (min_width, min_height) = self.min_screen_size();
+        if self.screen_width < min_width ||
+           self.screen_height < min_height {
+            self.screen_too_small = true;
+        } else {
+            self.screen_too_small = false;
+            self.prompt_scroll_cells = 0;
+            self.scroll_prompt_if_needed();
+        }
+    }
     pub fn execute_command(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, input_str: &str) {
 
         let command = match util::get_word(input_str, 1) {
@@ -1003,7 +2564,7 @@ impl UserInterface {
         } else if command == "nmi" {
 
             emu_cmd_tx.send(EmulatorCommand::NmiRequest).unwrap();
-            self.emulator_message("Issued a NMI request.");
+            self.emulator_message(&i18n::tr("Issued a NMI request."));
 
         // Alias for "clear screen":
         } else if command == "clear" || command == "cls" {
@@ -1016,8 +2577,56 @@ impl UserInterface {
         } else if command == "unpause" {
             self.execute_command(emu_cmd_tx, "machine pause off")
 
+        } else if command == "aliases" {
+            self.show_aliases();
+
         } else {
-            self.execute_parsed_command(emu_cmd_tx, ParsedUserCommand::parse(input_str));
+            let parsed_command = ParsedUserCommand::parse(input_str);
+
+            // The aliases of the configuration file, which can't be named
+            // like the commands.
+            if let ParsedUserCommand::InvalidCommand { .. } = parsed_command {
+                if command == "help" {
+                    if let Some(alias) = util::get_word(input_str, 2).and_then(|name| aliases::find(&self.aliases, &name)) {
+                        let message = format!("`{}' is an alias for `{}', see: /aliases", alias.name, alias.commands);
+                        self.emulator_message(&message);
+                        return;
+                    }
+                } else if let Some(expansion) = aliases::expand(&self.aliases, input_str) {
+                    self.run_alias_commands(emu_cmd_tx, expansion);
+                    return;
+                }
+            }
+            self.execute_parsed_command(emu_cmd_tx, parsed_command);
+        }
+    }
+    // The commands of an alias go before the rest of the script, so that
+    // they can wait, like the scripts do.
+    fn run_alias_commands(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, expansion: Result<Vec<String>, String>) {
+        match expansion {
+            Ok(commands) => {
+                for command in commands.into_iter().rev() {
+                    self.script_lines.push_front((command, self.unattended));
+                }
+                self.run_script_lines(emu_cmd_tx);
+            },
+            Err(error) => {
+                self.emulator_message(&error);
+            },
+        }
+    }
+    fn show_aliases(&mut self) {
+        if self.aliases.is_empty() {
+            self.emulator_message(&i18n::tr("No command aliases are set up, see the `aliases' entry of the [interface] section of the configuration file."));
+            return;
+        }
+        let name_width = self.aliases.iter().map(|alias| alias.name.chars().count()).max().unwrap_or(0);
+        let lines: Vec<String> = self.aliases.iter().map(|alias| format!("    {:width$} - {}", alias.name, alias.commands, width = name_width)).collect();
+
+        self.emulator_message(&i18n::tr("Command aliases of the configuration file:"));
+        self.emulator_message("");
+        for line in lines {
+            self.emulator_message(&line);
         }
     }
     fn execute_parsed_command(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, command: ParsedUserCommand) {
@@ -1037,127 +2646,327 @@ impl UserInterface {
             ParsedUserCommand::Cassette(sub_command) => {
                 self.execute_cassette_subcommand(emu_cmd_tx, sub_command);
             },
+            ParsedUserCommand::Disk(sub_command) => {
+                self.execute_disk_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Video(sub_command) => {
+                self.execute_video_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Keyboard(sub_command) => {
+                self.execute_keyboard_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Sound(sub_command) => {
+                self.execute_sound_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Record(sub_command) => {
+                self.execute_record_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Timer(sub_command) => {
+                self.execute_timer_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Rhai(sub_command) => {
+                self.execute_rhai_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::Debugger(sub_command) => {
+                self.execute_debugger_subcommand(emu_cmd_tx, sub_command);
+            },
+            ParsedUserCommand::DebuggerPane(state) => {
+                let shown = state.unwrap_or(!self.debugger_pane_shown);
+                self.set_debugger_pane(emu_cmd_tx, shown);
+            },
             ParsedUserCommand::Config(sub_command) => {
                 self.execute_config_subcommand(emu_cmd_tx, sub_command);
             },
+            ParsedUserCommand::Browse { purpose, dir } => {
+                self.open_file_picker(purpose, dir);
+            },
             ParsedUserCommand::CommandMissingParameter  { sup_command_name, sub_command_name, parameter_desc, parameter_desc_ia } => {
-                self.emulator_message(format!("The `{} {}' command requires {} {} parameter, see: /help {}", sup_command_name, sub_command_name, parameter_desc_ia, parameter_desc, sup_command_name).as_str());
+                self.emulator_message(&i18n::tr_format("The `{} {}' command requires {} {} parameter, see: /help {}", &[&sup_command_name, &sub_command_name, &parameter_desc_ia, &i18n::tr(&parameter_desc), &sup_command_name]));
             },
             ParsedUserCommand::CommandMissingSubcommand { sup_command_name } => {
-                self.emulator_message(format!("The `{}' command requires a sub-command, see: /help {}", sup_command_name, sup_command_name).as_str());
+                self.emulator_message(&i18n::tr_format("The `{}' command requires a sub-command, see: /help {}", &[&sup_command_name, &sup_command_name]));
             },
             ParsedUserCommand::InvalidCommand           { command_name } => {
-                 self.emulator_message(format!("Unknown command `{}'.  See `/help' with no argument for a list of supported commands.", command_name).as_str());
+                 self.emulator_message(&i18n::tr_format("Unknown command `{}'.  See `/help' with no argument for a list of supported commands.", &[&command_name]));
             },
             ParsedUserCommand::InvalidSubCommand        { sup_command_name, sub_command_name } => {
-                self.emulator_message(format!("Invalid sub-command `{}' for the `{}' command, see: /help {}", sub_command_name, sup_command_name, sup_command_name).as_str());
+                self.emulator_message(&i18n::tr_format("Invalid sub-command `{}' for the `{}' command, see: /help {}", &[&sub_command_name, &sup_command_name, &sup_command_name]));
             },
             ParsedUserCommand::InvalidParameter         { sup_command_name, sub_command_name, parameter_text, parameter_desc } => {
-                self.emulator_message(format!("Invalid {} parameter `{}' for the `{} {}' command, see: /help {}", parameter_desc, parameter_text, sup_command_name, sub_command_name, sup_command_name).as_str());
+                self.emulator_message(&i18n::tr_format("Invalid {} parameter `{}' for the `{} {}' command, see: /help {}", &[&i18n::tr(&parameter_desc), &parameter_text, &sup_command_name, &sub_command_name, &sup_command_name]));
             },
         }
     }
     fn show_help_entry(&mut self, help_entry: HelpEntry) {
         match help_entry {
             HelpEntry::Default => {
-                self.emulator_message("Key bindings in the SDL2-based interface:");
+                self.emulator_message(&i18n::tr("Key bindings in the SDL2-based interface:"));
                 self.emulator_message("");
-                self.emulator_message("    F1, Insert  - bindings for the `break' key.");
-                self.emulator_message("    F2, Delete  - bindings for the `clear' key.");
-                self.emulator_message("    F4          - pauses/unpauses emulation, alias for `machine pause toggle'.");
-                self.emulator_message("    F5          - performs a full system reset, alias for `machine reset full'.");
-                self.emulator_message("    F11         - toggles the full-screen mode.");
+                self.emulator_message(&i18n::tr("    F1, Insert  - bindings for the `break' key."));
+                self.emulator_message(&i18n::tr("    F2, Delete  - bindings for the `clear' key."));
+                self.emulator_message(&i18n::tr("    F3          - cycles the status overlay between off, speed and full."));
+                self.emulator_message(&i18n::tr("    F4          - pauses/unpauses emulation, alias for `machine pause toggle'."));
+                self.emulator_message(&i18n::tr("    F5          - performs a full system reset, alias for `machine reset full'."));
+                self.emulator_message(&i18n::tr("    F11         - toggles the full-screen mode."));
+                self.emulator_message(&i18n::tr("    F12         - takes a screenshot, alias for `video screenshot'."));
+                self.emulator_message(&i18n::tr("    Scroll Lock - toggles the warp, alias for `machine warp'."));
+                self.emulator_message(&i18n::tr("    Pause       - pauses, then runs one video frame, alias for `machine advance'."));
                 self.emulator_message("");
-                self.emulator_message("Available commands in the curses-based interface:");
+                self.emulator_message(&i18n::tr("Available commands in the curses-based interface:"));
                 self.emulator_message("");
-                self.emulator_message("    help        - shows information about other commands.");
-                self.emulator_message("    messages    - manages the messages on the curses-based interface.");
-                self.emulator_message("    machine     - allows you to change the state of the emulated machine.");
-                self.emulator_message("    memory      - allows you to change the state of the memory system.");
-                self.emulator_message("    cassette    - allows you to change the state of the cassette drive.");
-                self.emulator_message("    config      - allows you to change configuration settings.");
+                for command in commands::COMMANDS.iter().filter(|command| !command.summary.is_empty()) {
+                    self.emulator_message(&format!("    {:<11} - {}", command.name, i18n::tr(command.summary)));
+                }
                 self.emulator_message("");
-                self.emulator_message("    F1          - alias for `help', pressing F1 shows this message.");
-                self.emulator_message("    clear, cls  - aliases for `messages clear all'.");
-                self.emulator_message("    pause       - alias for `machine pause on'.");
-                self.emulator_message("    unpause     - alias for `machine pause off'.");
+                self.emulator_message(&i18n::tr("    F1          - alias for `help', pressing F1 shows this message."));
+                self.emulator_message(&i18n::tr("    Up, Down    - recall the earlier commands, which are kept between the runs of the emulator."));
+                self.emulator_message(&i18n::tr("    Ctrl+R      - searches the earlier commands for the text typed after it, again for older ones."));
+                self.emulator_message(&i18n::tr("    Tab         - completes the commands, their options and file names, and in the terminal-only mode,"));
+                self.emulator_message(&i18n::tr("                  switches the keys between the command line and the machine when no command is typed."));
+                self.emulator_message(&i18n::tr("    F6          - moves the keyboard focus on to the next pane, which the arrows then scroll, `+' and `-'"));
+                self.emulator_message(&i18n::tr("                  change the size of the status and debugger panes, and Esc returns to the command line."));
+                self.emulator_message(&i18n::tr("    Esc         - cancels decoding a WAV cassette, while its progress is shown in the status bar."));
+                self.emulator_message(&i18n::tr("    clear, cls  - aliases for `messages clear all'."));
+                self.emulator_message(&i18n::tr("    pause       - alias for `machine pause on'."));
+                self.emulator_message(&i18n::tr("    unpause     - alias for `machine pause off'."));
                 self.emulator_message("");
-                self.emulator_message("Type `/help command' for more information about specific commands.");
+                self.emulator_message(&i18n::tr("Type `/help command' for more information about specific commands, and `/help command sub-command'"));
+                self.emulator_message(&i18n::tr("for the usage of a sub-command, with examples."));
             },
             HelpEntry::Help => {
-                self.emulator_message("The `help' command is used to explain the commands that are available in the curses-based user interface of the emulator.");
+                self.emulator_message(&i18n::tr("The `help' command is used to explain the commands that are available in the curses-based user interface of the emulator."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("For a list of commands, type `/help' with no argument."));
                 self.emulator_message("");
-                self.emulator_message("For a list of commands, type `/help' with no argument.");
+                self.emulator_message(&i18n::tr("For more information about a specific command, type `/help command', where `command' is one of the comands returned by `/help'."));
                 self.emulator_message("");
-                self.emulator_message("For more information about a specific command, type `/help command', where `command' is one of the comands returned by `/help'.");
+                self.emulator_message(&i18n::tr("For the usage of one of the sub-commands of a command, with examples, type `/help command sub-command', like `/help machine save-state'."));
             },
             HelpEntry::Messages => {
-                self.emulator_message("The `messages' command has the following sub-commands:");
+                self.emulator_message(&i18n::tr("The `messages' command has the following sub-commands:"));
                 self.emulator_message("");
-                self.emulator_message("    messages show <machine|emulator>      - makes the given type of messages visible.");
-                self.emulator_message("    messages hide <machine|emulator>      - makes the given type of messages invisible.");
-                self.emulator_message("    messages toggle <machine|emulator>    - toggles the visibility of messages of the given type.");
-                self.emulator_message("    messages clear <machine|emulator|all> - clears/removes messages of the given type.");
+                self.show_usage("messages");
                 self.emulator_message("");
-                self.emulator_message("`emulator' messages are ones that are emitted by the emulator itself, `machine' messages are emitted by the emulated machine.");
+                self.emulator_message(&i18n::tr("`emulator' messages are ones that are emitted by the emulator itself, `machine' messages are emitted by the emulated machine."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The messages logged by the emulator can be narrowed down to a level, or to the module which logged them, the filtered ones are kept and come back once the filter is lifted.  Page Up and Page Down scroll through the messages, Shift+Home and Shift+End jump to the oldest and the newest ones."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("`messages export' writes all of the session into a text file, the commands entered, the replies to them and the logged messages, each with the time since the start of the emulator, including the cleared and the filtered ones, for the bug reports or for documenting the work done."));
             },
             HelpEntry::Machine => {
-                self.emulator_message("The `machine' command has the following sub-commands:");
+                self.emulator_message(&i18n::tr("The `machine' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("machine");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("With no argument, `machine reset' performs a CPU reset, and `machine pause' pauses the machine's emulation."));
                 self.emulator_message("");
-                self.emulator_message("    machine power <on|off>        - powers the machine on or off.");
-                self.emulator_message("    machine reset [cpu|full]      - performs a CPU reset, or a full reset.");
-                self.emulator_message("    machine restore               - puts the machine into a default state.");
-                self.emulator_message("    machine switch-rom <num>      - change the currently used BASIC rom (Level 1 or 2, or 3 for misc rom).");
-                self.emulator_message("    machine pause [on|off|toggle] - pauses or unpauses the machine.");
-                self.emulator_message("    machine unpause               - alias for `machine pause off'.");
+                self.emulator_message(&i18n::tr("The `machine paste' command types the text in the clipboard into the emulated machine, same as pressing F7 in the emulator window, which makes it easy to enter a BASIC listing copied from elsewhere.  The line breaks are typed as ENTER, and the characters that aren't on the keyboard of the machine are skipped.  How fast the keys are typed, and how long to wait after each line, is set with the `paste_ms_per_keypress' and `paste_ms_per_line' entries in the keyboard section of the config file, and the keys wait for the program to read them, unless `paste_wait_for_scan' is off."));
                 self.emulator_message("");
-                self.emulator_message("With no argument, `machine reset' performs a CPU reset, and `machine pause' pauses the machine's emulation.");
+                self.emulator_message(&i18n::tr("The `machine type' command types in a file the same way, at the rate set with the `type_chars_per_second' entry, for demonstrations and test scripts.  The keys which don't type characters are written as tokens in braces: {ENTER}, {SPACE}, {CLEAR}, {BREAK}, {UP}, {DOWN}, {LEFT} and {RIGHT}.  A file name which isn't absolute is relative to the configuration directory."));
                 self.emulator_message("");
-                self.emulator_message("The `machine switch-rom' command is used for changing the currently selected system ROM.  Plese note that switching the ROM involves restarting the machine, so any unsaved progress will be lost.  Valid options are 1 for Level 1 BASIC, 2 for Level 2 BASIC, and 3 for the miscellaneous rom.");
+                self.emulator_message(&i18n::tr("The `machine type-text' command types in the rest of the command line, for startup scripts, like `/machine type-text CLOAD\\n'."));
                 self.emulator_message("");
-                self.emulator_message("The `machine restore' command, on the other hand, is useful for when you've been messing around with the `memory load' and `memory wipe' commands, and want to get back to a normal state by restoring the currently selected system ROM.");
+                self.emulator_message(&i18n::tr("The `machine latency' command shows the average and the longest time between the front-end seeing a key pressed or released in the emulator window, and the key reaching the keyboard of the emulated machine, over the keys since the command was last used."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `machine status' command shows a pane under the top strip with the registers of the CPU, the speed of the emulation, the state of the interrupts, the selected ROM and the cassette in the drive, which is updated ten times a second while the machine runs.  With no argument, it toggles the pane."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `machine speed' command changes the speed limit set with the `--speed' option, or by the settings of the title attached, for example to 50 or 10 percent for watching fast game logic in slow motion.  The `machine advance' command runs the paused machine up to the end of the next video frame, or of as many frames as given, for going through timing-sensitive routines a frame at a time.  If the machine is running, it's paused instead, where it is."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `machine save-state' command saves the registers of the CPU and the contents of the RAM and of the video memory into a file, and `machine load-state' puts them back, for continuing a program from where it was.  The ROM, the cassette and the keyboard aren't saved, and the state can only be loaded with as much RAM as it was saved with.  Without a file, `snapshot.sav' is used, which is what the menu of the emulator window (F9) saves and loads.  A file name which isn't absolute is relative to the configuration directory."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `machine switch-rom' command is used for changing the currently selected system ROM.  Plese note that switching the ROM involves restarting the machine, so any unsaved progress will be lost.  Valid options are 1 for Level 1 BASIC, 2 for Level 2 BASIC, and 3 for the miscellaneous rom."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `machine restore' command, on the other hand, is useful for when you've been messing around with the `memory load' and `memory wipe' commands, and want to get back to a normal state by restoring the currently selected system ROM."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("A full reset of the running machine, saving a state, a sound or a screen recording over an existing file, and discarding the changes in the overlay of a disk image have to be asked for twice within five seconds, the first time only shows a warning.  Which of them need it is set with the `confirm_actions' entry in the interface section of the config file, as a list of `reset', `overwrite' and `discard', or `none'."));
             },
             HelpEntry::Memory => {
-                self.emulator_message("The `memory' command has the following sub-commands:");
+                self.emulator_message(&i18n::tr("The `memory' command has the following sub-commands:"));
                 self.emulator_message("");
-                self.emulator_message("    memory load <rom|ram> <file> [offset] - loads a file into either ram or rom.");
-                self.emulator_message("    memory wipe <rom|ram|all>             - clears the contents of rom, ram, or both.");
+                self.show_usage("memory");
                 self.emulator_message("");
-                self.emulator_message("The offset specifier in `memory load' can be in either decimal, octal, binary or hexadecimal notation.  The default is decimal, a prefix of 0b means binary, 0x means hexadecimal, 0 means octal, and a postfix of h means hexadecimal.");
+                self.emulator_message(&i18n::tr("The offset specifier in `memory load' can be in either decimal, octal, binary or hexadecimal notation.  The default is decimal, a prefix of 0b means binary, 0x means hexadecimal, 0 means octal, and a postfix of h means hexadecimal."));
                 self.emulator_message("");
-                self.emulator_message("In the current implementation, file names may not contain spaces and non-ascii characters.  Also, if you pass `default' as the filename to `memory load rom', it will load a default rom image from a pre-defined location.");
+                self.emulator_message(&i18n::tr("In the current implementation, file names may not contain spaces and non-ascii characters.  Also, if you pass `default' as the filename to `memory load rom', it will load a default rom image from a pre-defined location."));
             },
             HelpEntry::Cassette => {
-                self.emulator_message("The `cassette' command has the following sub-commands:");
+                self.emulator_message(&i18n::tr("The `cassette' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("cassette");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The position argument to `/cassette seek' is a byte offset within the cassette file.  The current position and the length of the inserted cassette are shown by the tape counter in the status bar, as `tape <position>/<length>'."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The file argument to the `/cassette load' command can either be a plain file name, which means a file with that name in the configuration directory, or a full path.  If the specified file doesn't exists, it will be created.  The format argument can be either CAS, CPT or WAV, and can be left out if the file has one of those as its extension.  CAS files from other emulators with recordings that aren't lined up with their sync bytes are fixed up when they're inserted, and CPT files from xtrs can be used as they are.  Recordings made into CAS or CPT cassettes can also be saved as WAV audio, see the `wav_copy' entry in the cassette section of the config file."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The cassette library is the directory set by the `library_dir' entry in the cassette section of the config file, the configuration directory by default.  For CAS files, the names of the BASIC programs and SYSTEM files recorded on them are shown, along with the load addresses."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The cassette motor is normally switched by a relay in the machine, the motor state is shown in the status bar while it runs, or while it's forced on or off by `/cassette motor'.  Turning the machine off hands the motor back to the relay."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("When loading a cassette, `CLOAD' is typed in for BASIC programs, and `SYSTEM' followed by the file name for machine language programs, which are also started with `/' once the tape stops.  The emulated machine should be waiting at the `READY' prompt, and only CAS and WAV cassettes are supported."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("WAV cassettes are decoded as a whole for `/cassette index', `/cassette goto' and `/cassette load', and the index also lists the blocks of SYSTEM files that came out with a bad checksum.  If a recording doesn't decode cleanly, the `wav_threshold', `wav_noise_floor', `wav_dc_filter', `wav_channel' and `wav_speed' entries in the cassette section of the config file can be adjusted, they apply both to the index and to loading the tape in the emulated machine."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The `cassette undo' command puts back the cassette which was in the drive before the last insert or eject, wound to where its tape was, and using it again swaps the cassettes back."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("In the current implementation, file names may not contain non-ascii characters, since there is no way to enter such characters in this user interface."));
+            },
+            HelpEntry::Video => {
+                self.emulator_message(&i18n::tr("The `video' command has the following sub-commands:"));
                 self.emulator_message("");
-                self.emulator_message("    cassette insert <format> <file> - loads a file into the cassette drive.");
-                self.emulator_message("    cassette eject                  - removes the currently inserted cassette from the drive.");
-                self.emulator_message("    cassette erase                  - clears the contents of the inserted cassette.");
-                self.emulator_message("    cassette seek   <position>      - rewinds the tape to the specified location.");
-                self.emulator_message("    cassette rewind                 - rewinds the tape to the beginning.");
+                self.show_usage("video");
                 self.emulator_message("");
-                self.emulator_message("The position argument to `/cassette seek' is a byte offset within the cassette file.  To get the current value of this offset, issue `/config show cassette_file_offset'.");
+                self.emulator_message(&i18n::tr("The scaling mode is either `integer', which scales the screen by a whole number of times, so that all the dots of the characters are the same size, `aspect', which scales it as large as fits while keeping its 4:3 aspect ratio, or `stretch', which fills the whole window.  It's saved in the config file."));
                 self.emulator_message("");
-                self.emulator_message("The file argument to the `/cassette load' command can either be a plain file name, which means a file with that name in the configuration directory, or a full path.  If the specified file doesn't exists, it will be created.  The format argument can be either CAS or CPT.");
+                self.emulator_message(&i18n::tr("The color scheme is either `white', `green' or `amber', each with a black background, like the monochrome monitors of the time.  Other colors are given in the hex (#RRGGBB) format, and the background is kept as it is if only the foreground color is given.  The colors are saved as the `fg_color' and `bg_color' entries in the config file."));
                 self.emulator_message("");
-                self.emulator_message("In the current implementation, file names may not contain non-ascii characters, since there is no way to enter such characters in this user interface.");
+                self.emulator_message(&i18n::tr("Screenshots are saved into the directory set with the `screenshot_dir' entry of the config file, named after the date and time they were taken at.  A `native' screenshot is 512x384 pixels large, one for each dot of the screen, and a `scaled' one is taken of the emulator window as it is, including the CRT effects.  With no argument, the `screenshot_scaled' entry chooses between the two, same as when pressing F12 in the emulator window."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The text on the screen is copied as plain text, one line for each row of the screen.  The block graphics characters are written out either as the block sextant characters of Unicode, or as `#' if any of their blocks is lit, as set with the `text_graphics' entry of the config file.  The file name can either be a plain file name, which means a file with that name in the configuration directory, or a full path, an existing file is overwritten."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The character generators of the Japanese and the European machines have an alternate character set, katakana or accented letters, which is shown instead of the block graphics characters 0xC0-0xFF while it's switched in.  It comes with a 2 KB dump of such a character generator, loaded with the `character_generator_file' entry of the config file.  Programs switch it in with bit 3 of port ECh, like in the Model III, and this command switches it by hand.  The characters need the lowercase mod to be shown.  With no argument, `video charset' toggles the switch."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The picture settings are `brightness' and `contrast', from 0 to 200 percent with 100 leaving the colors as they are, like the knobs of a monitor, and `scanlines', how dark the gaps between the scanlines are, from 0 to 100 percent.  A level starting with `+' or `-' is added to the current one, like `video picture brightness +10', which makes it easy to tune the picture while watching it.  The levels are saved as the `brightness', `contrast' and `crt_scanlines' entries of the config file.  With no arguments, `video picture' shows the current levels."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The magnifier window shows a part of the screen enlarged, 16 characters by 4 lines at first, for reading the text comfortably.  While the window has the keyboard focus, the arrow keys move the part shown by a character, Home and End jump to the start and the end of the line, and Page Up and Page Down zoom in and out.  The other keys are typed into the emulated machine as usual.  With no argument, `video magnifier' opens or closes the window, same as pressing F6 in the emulator window."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("With no argument, `video fullscreen' toggles the mode, same as pressing F11 in the emulator window.  The mode is saved in the config file, so the emulator starts up in the mode it was last left in.  Whether the full-screen mode changes the resolution of the screen is set with the `desktop_fullscreen_mode' and `fullscreen_resolution' entries in the video section of the config file, the picture keeps its aspect ratio either way."));
+            },
+            HelpEntry::Keyboard => {
+                self.emulator_message(&i18n::tr("The `keyboard' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("keyboard");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("A key profile is a named set of key bindings for playing games, which goes on top of the `key_bindings' of the keyboard section of the config file while it's in use, so that the controls of a game can be put where they're comfortable without changing the config file for each game.  The emulator comes with the `numpad', `wasd', `invaders' and `joystick' profiles, more can be added with the `profiles' entry of the keyboard section."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("With no argument, `keyboard profile' lists the profiles, and shows which one is in use.  The profile in use is saved in the config file."));
+            },
+            HelpEntry::Sound => {
+                self.emulator_message(&i18n::tr("The `sound' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("sound");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The sound sources are `tape', the cassette port signal while the cassette motor runs, which is mostly the noise of tapes being loaded and saved, `program', the same signal while the motor is off, which is how most programs make sound, `orchestra', the output of the Orchestra-85 music card, and `ay', the output of the AY-3-8910 sound board, which is installed with the `ay_port' entry.  The mixer settings are saved in the audio section of the config file."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("Underruns happen when the sound card runs out of sound to play, which is heard as the sound breaking up, and dropped samples are sound that had to be skipped since the emulation got too far ahead of the sound card.  The sound output is set up in the audio section of the config file, the `latency' and `buffer_size' entries trade the delay of the sound for its resilience to a busy system."));
+            },
+            HelpEntry::Record => {
+                self.emulator_message(&i18n::tr("The `record' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("record");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The sound recording holds the same sound as the speakers, with the muted sources left out, but isn't affected by the master volume, and works even if the sound output is disabled.  It's made at the sample rate set in the audio section of the config file, and stops if that gets changed."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The screen recording is made at the frame rate set with the `recording_frame_rate' option of the video section of the config file, in the colors and with the character generator the screen had when it started, and stops by itself once it's as long as set with the `recording_max_seconds' option.  It only runs while the machine does, so pausing the machine pauses the recording as well."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The file name can either be a plain file name, which means a file with that name in the configuration directory, or a full path, an existing file is overwritten."));
+            },
+            HelpEntry::Timer => {
+                self.emulator_message(&i18n::tr("The `timer' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("timer");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The emulated time is counted from the T-states the CPU ran, so it's the time the program would take on a real machine, no matter how fast the emulator runs, and it stands still while the machine is paused.  The real time is shown next to it, for comparison.  Scripts can start and stop the timer around the commands typing in a program, to compare the ways of doing the same thing, like a BASIC and an assembly version of a routine."));
+            },
+            HelpEntry::Rhai => {
+                self.emulator_message(&i18n::tr("The `rhai' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("rhai");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("Scripts in the Rhai language can read and change the memory and the registers of the machine, call their functions when the CPU reaches an address or with every video frame, type on the keyboard and change the cassettes, for game trainers, automated tests of programs and demo recordings.  The functions they get are listed in the README file.  Only the emulator built with the `scripting' feature has them."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The functions and the global variables of the scripts run so far are kept, so `rhai eval' can call them, until `rhai stop' throws them away along with the breakpoints and callbacks.  A callback which fails stops the script, and so does one which runs for too long."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The file name can either be a plain file name, which means a file with that name in the configuration directory, or a full path."));
+            },
+            HelpEntry::Debugger => {
+                self.emulator_message(&i18n::tr("The `debugger' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("debugger");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The debugger window is a second window next to the emulator window, which shows the registers of the CPU, the instructions starting at the program counter, and 64 bytes of the memory, both in hex and as text.  It's updated ten times a second, even while the machine is paused, and can be resized or closed like any other window."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The debugger pane shows the same in the curses-based interface, above the messages.  While it has the keyboard focus, which F6 moves between the panes, the arrow keys and Page Up and Page Down scroll it, and `+' and `-' make it taller or shorter, the messages keep at least three rows."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The address can be given in decimal, or in hex either as `0x3C00' or `3C00h'."));
+            },
+            HelpEntry::Disk => {
+                self.emulator_message(&i18n::tr("The `disk' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("disk");
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The emulator doesn't emulate the Expansion Interface and its floppy disk drives yet, so the emulated machine can't access disk images, but their contents can be examined using this command.  It operates on an image file, not a drive: nothing gets mounted, and the running machine isn't affected by it.  Supported image formats are JV1, JV3, DMK and IMD (read-only)."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("When a disk image is modified in copy-on-write mode (see `disk insert --cow' on the command line), the changes are stored in an overlay file next to the image, named like the image with `.cow' appended.  The image itself stays untouched until the changes are committed.  The `disk write' command writes into the overlay if the image already has one, and into the image itself otherwise.  The `disk commit' and `disk discard' commands work on the overlay of the image file given, not on a drive of the running machine."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The sector commands bypass the file system, they're meant for inspecting and repairing boot sectors and directories.  The numbers can be given in decimal, or in hex with the `0x' prefix or the `h' postfix."));
+                self.emulator_message("");
+                self.emulator_message(&i18n::tr("The file argument can either be a plain file name, which means a file with that name in the configuration directory, or a full path."));
             },
             HelpEntry::Config => {
-                self.emulator_message("The `config' command has the following sub-commands:");
+                self.emulator_message(&i18n::tr("The `config' command has the following sub-commands:"));
+                self.emulator_message("");
+                self.show_usage("config");
                 self.emulator_message("");
-                self.emulator_message("    list                               - shows all config entries and their current value.");
-                self.emulator_message("    show   <section>_<entry>           - shows the value of the given config entry.");
-                self.emulator_message("    change <section>_<entry> = <value> - changes the value of the given config entry.");
+                self.emulator_message(&i18n::tr("Invoking `config change' causes the configuration file to be updated, as well as applying the change, if possible."));
                 self.emulator_message("");
-                self.emulator_message("Invoking `config change' causes the configuration file to be updated, as well as applying the change, if possible.");
+                self.emulator_message(&i18n::tr("The configuration file can also be edited while the emulator runs, the changes are applied once it's saved, except for the ones to the hardware of the machine or the tape in it, which take effect after a restart."));
             },
             HelpEntry::Alias { alias_name, aliased_name, help_entry } => {
-                self.emulator_message(format!("The `{}' command is an alias for `{}', see `/help {}' for more information.", alias_name, aliased_name, help_entry).as_str());
+                self.emulator_message(&i18n::tr_format("The `{}' command is an alias for `{}', see `/help {}' for more information.", &[&alias_name, &aliased_name, &help_entry]));
             },
             HelpEntry::Exit => {
-                self.emulator_message("The `exit' or `quit' command closes the emulator program.");
+                self.emulator_message(&i18n::tr("The `exit' or `quit' command closes the emulator program."));
+            },
+            HelpEntry::Summary(command) => {
+                self.emulator_message(&i18n::tr_format("The `{}' command {}", &[&command.name, &i18n::tr(command.summary)]));
+            },
+            HelpEntry::SubCommand { command_name, sub_command_name } => {
+                self.show_sub_command_help(&command_name, &sub_command_name);
+            },
+        }
+    }
+    // The usage of the sub-commands of a command, from the table of commands.
+    fn show_usage(&mut self, command_name: &str) {
+        let command = commands::find(command_name).expect("The command isn't in the table of commands");
+        for line in command.usage_lines(None) {
+            self.emulator_message(&line);
+        }
+    }
+    fn show_sub_command_help(&mut self, command_name: &str, sub_command_name: &str) {
+        let command = match commands::find(command_name) {
+            Some(command) if !command.sub_commands.is_empty() => { command },
+            Some(..) => {
+                self.emulator_message(&i18n::tr_format("The `{}' command has no sub-commands, see: /help {}", &[&command_name, &command_name]));
+                return;
+            },
+            None => {
+                self.emulator_message(&i18n::tr_format("Unknown command `{}'.  See `/help' with no argument for a list of supported commands.", &[&command_name]));
+                return;
+            },
+        };
+        let sub_command = match command.sub_command(sub_command_name) {
+            Some(sub_command) => { sub_command },
+            None => {
+                self.emulator_message(&i18n::tr_format("Invalid sub-command `{}' for the `{}' command, see: /help {}", &[&sub_command_name, &command.name, &command.name]));
+                return;
             },
+        };
+
+        self.emulator_message(&i18n::tr_format("Usage of the `{} {}' command:", &[&command.name, &sub_command.name]));
+        self.emulator_message("");
+        for line in command.usage_lines(Some(sub_command)) {
+            self.emulator_message(&line);
         }
+        if !sub_command.examples.is_empty() {
+            self.emulator_message("");
+            self.emulator_message(&i18n::tr("Examples:"));
+            self.emulator_message("");
+            for example in sub_command.examples {
+                self.emulator_message(&format!("    /{}", example));
+            }
+        }
+        self.emulator_message("");
+        self.emulator_message(&i18n::tr_format("See `/help {}' for more information.", &[&command.name]));
     }
     fn execute_messages_subcommand(&mut self, sub_command: MessagesSubCommand) {
         match sub_command {
@@ -1212,46 +3021,120 @@ impl UserInterface {
                     },
                 }
             },
+            MessagesSubCommand::Level(level) => {
+                self.message_filter.level = level;
+                self.message_filter_changed();
+                if level == log::Level::Info {
+                    self.emulator_message(&i18n::tr("Showing the messages of all levels."));
+                } else {
+                    self.emulator_message(&i18n::tr_format("Showing the messages of the level `{}' and the more severe ones.", &[&log_level_name(level)]));
+                }
+            },
+            MessagesSubCommand::Module(module) => {
+                match &module {
+                    Some(module) => {
+                        if !self.logged_modules.contains(module) {
+                            self.emulator_message(&i18n::tr_format("Note: the module `{}' hasn't logged any messages so far.", &[&module]));
+                        }
+                        self.emulator_message(&i18n::tr_format("Showing only the messages of the module `{}'.", &[&module]));
+                    },
+                    None => {
+                        self.emulator_message(&i18n::tr("Showing the messages of all modules."));
+                    },
+                }
+                self.message_filter.module = module;
+                self.message_filter_changed();
+            },
+            MessagesSubCommand::Filter => {
+                self.show_message_filter();
+            },
+            MessagesSubCommand::Scroll(direction) => {
+                match direction {
+                    MessagesScroll::Up     => { self.scroll_lines_up(); },
+                    MessagesScroll::Down   => { self.scroll_lines_down(); },
+                    MessagesScroll::Top    => { self.scroll_lines_top(); },
+                    MessagesScroll::Bottom => { self.scroll_lines_bottom(); },
+                }
+            },
+            MessagesSubCommand::Export(file) => {
+                self.export_transcript(&file);
+            },
+        }
+    }
+    // The transcript is written relative to the configuration directory, like
+    // the other files the emulator writes.
+    fn export_transcript(&mut self, file: &str) {
+        let file_path = self.config_dir.join(file);
+        match self.transcript.write(&file_path) {
+            Ok(()) => {
+                self.emulator_message(&i18n::tr_format("The transcript of the session was written into `{}'.", &[&file_path.display()]));
+            },
+            Err(error) => {
+                self.emulator_message(&i18n::tr_format("Failed to write the transcript into `{}': {}.", &[&file_path.display(), &error]));
+            },
         }
     }
     fn show_emulator_messages(&mut self) {
         if self.emulator_msg_shown {
-            self.emulator_message("Emulator messages already visible.");
+            self.emulator_message(&i18n::tr("Emulator messages already visible."));
         } else {
             self.emulator_msg_shown = true;
             self.bottom_rows_skip = 0;
             self.redraw_text_area = true;
-            self.emulator_message("Emulator messages shown.");
+            self.emulator_message(&i18n::tr("Emulator messages shown."));
         }
     }
     fn hide_emulator_messages(&mut self) {
         if !self.emulator_msg_shown {
-            self.emulator_message("Emulator messages already hidden.");
+            self.emulator_message(&i18n::tr("Emulator messages already hidden."));
         } else {
             self.emulator_msg_shown = false;
             self.bottom_rows_skip = 0;
             self.redraw_text_area = true;
-            self.emulator_message("Emulator messages hidden.");
+            self.emulator_message(&i18n::tr("Emulator messages hidden."));
         }
     }
     fn show_machine_messages(&mut self) {
         if self.machine_msg_shown {
-            self.emulator_message("Machine messages already visible.");
+            self.emulator_message(&i18n::tr("Machine messages already visible."));
         } else {
             self.machine_msg_shown = true;
             self.bottom_rows_skip = 0;
             self.redraw_text_area = true;
-            self.emulator_message("Machine messages shown.");
+            self.emulator_message(&i18n::tr("Machine messages shown."));
         }
     }
     fn hide_machine_messages(&mut self) {
         if !self.machine_msg_shown {
-            self.emulator_message("Machine messages already hidden.");
+            self.emulator_message(&i18n::tr("Machine messages already hidden."));
         } else {
             self.machine_msg_shown = false;
             self.bottom_rows_skip = 0;
             self.redraw_text_area = true;
-            self.emulator_message("Machine messages hidden.");
+            self.emulator_message(&i18n::tr("Machine messages hidden."));
+        }
+    }
+    fn message_filter_changed(&mut self) {
+        self.bottom_rows_skip = 0;
+        self.redraw_text_area = true;
+        self.redraw_status = true;
+    }
+    fn show_message_filter(&mut self) {
+        let level_text = if self.message_filter.level == log::Level::Info {
+            i18n::tr("all levels")
+        } else {
+            i18n::tr_format("`{}' and the more severe ones", &[&log_level_name(self.message_filter.level)])
+        };
+        let module_text = match &self.message_filter.module {
+            Some(module) => { i18n::tr_format("only `{}'", &[module]) },
+            None => { i18n::tr("all modules") },
+        };
+        self.emulator_message(&i18n::tr_format("Logged messages shown: {}, {}.", &[&level_text, &module_text]));
+        if self.logged_modules.is_empty() {
+            self.emulator_message(&i18n::tr("No modules have logged any messages so far."));
+        } else {
+            let modules = self.logged_modules.join(", ");
+            self.emulator_message(&i18n::tr_format("Modules which logged messages: {}.", &[&modules]));
         }
     }
     fn clear_machine_messages(&mut self) {
@@ -1267,7 +3150,7 @@ impl UserInterface {
         self.screen_lines = new_screen_lines;
         self.bottom_rows_skip = 0;
         self.redraw_text_area = true;
-        self.emulator_message("Machine messages cleared.");
+        self.emulator_message(&i18n::tr("Machine messages cleared."));
     }
     fn clear_emulator_messages(&mut self) {
         let mut new_screen_lines = VecDeque::with_capacity(self.max_screen_lines);
@@ -1282,13 +3165,13 @@ impl UserInterface {
         self.screen_lines = new_screen_lines;
         self.bottom_rows_skip = 0;
         self.redraw_text_area = true;
-        self.emulator_message("Emulator messages cleared.");
+        self.emulator_message(&i18n::tr("Emulator messages cleared."));
     }
     fn clear_all_messages(&mut self) {
         self.screen_lines = VecDeque::with_capacity(self.max_screen_lines);
         self.bottom_rows_skip = 0;
         self.redraw_text_area = true;
-        self.emulator_message("All messages cleared.");
+        self.emulator_message(&i18n::tr("All messages cleared."));
     }
     fn execute_machine_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: MachineSubCommand) {
         match sub_command {
@@ -1329,12 +3212,80 @@ impl UserInterface {
                     },
                 }
             },
+            MachineSubCommand::Paste => {
+                emu_cmd_tx.send(EmulatorCommand::PasteClipboard).unwrap();
+            },
+            MachineSubCommand::Type { file } => {
+                emu_cmd_tx.send(EmulatorCommand::TypeFile(file)).unwrap();
+            },
+            MachineSubCommand::TypeText(text) => {
+                emu_cmd_tx.send(EmulatorCommand::TypeText(text)).unwrap();
+            },
+            MachineSubCommand::Latency => {
+                emu_cmd_tx.send(EmulatorCommand::ShowKeyLatency).unwrap();
+            },
+            MachineSubCommand::Clock => {
+                emu_cmd_tx.send(EmulatorCommand::ShowClock).unwrap();
+            },
+            MachineSubCommand::Status(state) => {
+                let shown = state.unwrap_or(!self.status_pane_shown);
+                self.set_status_pane(emu_cmd_tx, shown);
+            },
+            MachineSubCommand::Warp(state) => {
+                emu_cmd_tx.send(EmulatorCommand::SetWarp(state)).unwrap();
+            },
+            MachineSubCommand::Speed(Some(speed_limit)) => {
+                emu_cmd_tx.send(EmulatorCommand::SetSpeedLimit(speed_limit)).unwrap();
+            },
+            MachineSubCommand::Speed(None) => {
+                emu_cmd_tx.send(EmulatorCommand::ShowSpeedLimit).unwrap();
+            },
+            MachineSubCommand::Advance(frames) => {
+                emu_cmd_tx.send(EmulatorCommand::AdvanceFrames(frames)).unwrap();
+            },
+            MachineSubCommand::SaveState(file) => {
+                emu_cmd_tx.send(EmulatorCommand::SaveState { file }).unwrap();
+            },
+            MachineSubCommand::LoadState(file) => {
+                emu_cmd_tx.send(EmulatorCommand::LoadState { file }).unwrap();
+            },
+        }
+    }
+    // While the status pane is shown, the logic core keeps sending the lines
+    // of it, which are drawn under the top strip.
+    fn set_status_pane(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, shown: bool) {
+        if self.window.is_none() {
+            self.emulator_message(&i18n::tr("The machine status pane is only shown in the curses-based interface."));
+            return;
+        }
+        self.status_pane_shown = shown;
+        self.status_pane.clear();
+        if !shown && self.layout.focus() == layout::Pane::Status {
+            self.set_focus(layout::Pane::Prompt);
+        }
+        emu_cmd_tx.send(EmulatorCommand::SetStatusPane(shown)).unwrap();
+        self.check_screen_size();
+        self.redraw_everything = true;
+    }
+    // Likewise for the debugger pane, which is drawn above the messages.
+    fn set_debugger_pane(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, shown: bool) {
+        if self.window.is_none() {
+            self.emulator_message(&i18n::tr("The debugger pane is only shown in the curses-based interface."));
+            return;
+        }
+        self.debugger_pane_shown = shown;
+        self.debugger_pane.clear();
+        if !shown && self.layout.focus() == layout::Pane::Debugger {
+            self.set_focus(layout::Pane::Prompt);
         }
+        emu_cmd_tx.send(EmulatorCommand::DebuggerCommand(EmulatorDebuggerCommand::Panel { state: shown })).unwrap();
+        self.check_screen_size();
+        self.redraw_everything = true;
     }
     fn power_off_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
 
         if !self.machine_powered_on {
-            self.emulator_message("The machine is already powered off.");
+            self.emulator_message(&i18n::tr("The machine is already powered off."));
         } else {
             emu_cmd_tx.send(EmulatorCommand::PowerOff).unwrap();
         }
@@ -1342,7 +3293,7 @@ impl UserInterface {
     fn power_on_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
 
         if self.machine_powered_on {
-            self.emulator_message("The machine is already powered on.");
+            self.emulator_message(&i18n::tr("The machine is already powered on."));
         } else {
             emu_cmd_tx.send(EmulatorCommand::PowerOn).unwrap();
         }
@@ -1350,9 +3301,9 @@ impl UserInterface {
     fn restore_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
 
         if self.machine_powered_on {
-            self.emulator_message("Cannot restore the machine while it's running.");
+            self.emulator_message(&i18n::tr("Cannot restore the machine while it's running."));
         } else {
-            self.emulator_message("The machine shall be restored back to its factory-original state.");
+            self.emulator_message(&i18n::tr("The machine shall be restored back to its factory-original state."));
             emu_cmd_tx.send(EmulatorCommand::Pause).unwrap();
             emu_cmd_tx.send(EmulatorCommand::ResetHard).unwrap();
             emu_cmd_tx.send(EmulatorCommand::PowerOff).unwrap();
@@ -1366,26 +3317,26 @@ impl UserInterface {
         if self.machine_powered_on {
             emu_cmd_tx.send(EmulatorCommand::ResetHard).unwrap();
         } else {
-            self.emulator_message("Cannot reset a powered-off machine.");
+            self.emulator_message(&i18n::tr("Cannot reset a powered-off machine."));
         }
     }
     fn reset_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
         if self.machine_powered_on {
             emu_cmd_tx.send(EmulatorCommand::ResetSoft).unwrap();
         } else {
-            self.emulator_message("Cannot reset a powered-off machine.");
+            self.emulator_message(&i18n::tr("Cannot reset a powered-off machine."));
         }
     }
     fn pause_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
         if self.machine_paused {
-            self.emulator_message("The machine emulation is already paused.");
+            self.emulator_message(&i18n::tr("The machine emulation is already paused."));
         } else {
             emu_cmd_tx.send(EmulatorCommand::Pause).unwrap();
         }
     }
     fn unpause_machine(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
         if !self.machine_paused {
-            self.emulator_message("The machine emulation is already not paused.");
+            self.emulator_message(&i18n::tr("The machine emulation is already not paused."));
         } else {
             emu_cmd_tx.send(EmulatorCommand::Unpause).unwrap();
         }
@@ -1420,16 +3371,84 @@ impl UserInterface {
                     },
                 }
             },
+            MemorySubCommand::Dump { address, length } => {
+                emu_cmd_tx.send(EmulatorCommand::DumpMemory { address, length }).unwrap();
+            },
         }
     }
     fn execute_cassette_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorCassetteCommand) {
+        // Without the curses-based interface, there's no file picker to
+        // browse the library in, so it gets listed.
+        let sub_command = match sub_command {
+            EmulatorCassetteCommand::Library { .. } => { EmulatorCassetteCommand::Library { browse: self.window.is_some() } },
+            sub_command => { sub_command },
+        };
         emu_cmd_tx.send(EmulatorCommand::CassetteCommand(sub_command)).unwrap();
     }
+    fn execute_disk_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorDiskCommand) {
+        emu_cmd_tx.send(EmulatorCommand::DiskCommand(sub_command)).unwrap();
+    }
+    fn execute_video_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: VideoSubCommand) {
+        match sub_command {
+            VideoSubCommand::Fullscreen(state) => {
+                emu_cmd_tx.send(EmulatorCommand::SetFullscreen(state)).unwrap();
+            },
+            VideoSubCommand::Scaling(scaling) => {
+                emu_cmd_tx.send(EmulatorCommand::SetScaling(scaling)).unwrap();
+            },
+            VideoSubCommand::Colors { fg_color, bg_color } => {
+                emu_cmd_tx.send(EmulatorCommand::SetColors { fg_color, bg_color }).unwrap();
+            },
+            VideoSubCommand::Screenshot(scaled) => {
+                emu_cmd_tx.send(EmulatorCommand::Screenshot(scaled)).unwrap();
+            },
+            VideoSubCommand::Text { file } => {
+                emu_cmd_tx.send(EmulatorCommand::ScreenText { file }).unwrap();
+            },
+            VideoSubCommand::Charset(state) => {
+                emu_cmd_tx.send(EmulatorCommand::SetAlternateCharset(state)).unwrap();
+            },
+            VideoSubCommand::Picture(Some((setting, level, relative))) => {
+                emu_cmd_tx.send(EmulatorCommand::SetPicture { setting, level, relative }).unwrap();
+            },
+            VideoSubCommand::Picture(None) => {
+                emu_cmd_tx.send(EmulatorCommand::ShowPicture).unwrap();
+            },
+            VideoSubCommand::Magnifier(state) => {
+                emu_cmd_tx.send(EmulatorCommand::SetMagnifier(state)).unwrap();
+            },
+        }
+    }
+    fn execute_keyboard_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: KeyboardSubCommand) {
+        match sub_command {
+            KeyboardSubCommand::ShowProfiles => {
+                emu_cmd_tx.send(EmulatorCommand::ShowKeyProfiles).unwrap();
+            },
+            KeyboardSubCommand::Profile(name) => {
+                emu_cmd_tx.send(EmulatorCommand::SetKeyProfile(name)).unwrap();
+            },
+        }
+    }
+    fn execute_sound_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorSoundCommand) {
+        emu_cmd_tx.send(EmulatorCommand::SoundCommand(sub_command)).unwrap();
+    }
+    fn execute_debugger_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorDebuggerCommand) {
+        emu_cmd_tx.send(EmulatorCommand::DebuggerCommand(sub_command)).unwrap();
+    }
+    fn execute_record_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorRecordCommand) {
+        emu_cmd_tx.send(EmulatorCommand::RecordCommand(sub_command)).unwrap();
+    }
+    fn execute_timer_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorTimerCommand) {
+        emu_cmd_tx.send(EmulatorCommand::TimerCommand(sub_command)).unwrap();
+    }
+    fn execute_rhai_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorScriptCommand) {
+        emu_cmd_tx.send(EmulatorCommand::ScriptCommand(sub_command)).unwrap();
+    }
     fn execute_config_subcommand(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, sub_command: EmulatorConfigCommand) {
         emu_cmd_tx.send(EmulatorCommand::ConfigCommand(sub_command)).unwrap();
     }
     fn send_to_console(&mut self, _input_str: String) {
-        self.emulator_message("Serial console interface not yet implemented.");
+        self.emulator_message(&i18n::tr("Serial console interface not yet implemented."));
     }
     // Note: insert_row_pos should be the row for which prev_line_last_col is valid,
     //       i.e. the previous line's last row if already_drawn_rows == 0, or the line's
@@ -1448,28 +3467,28 @@ impl UserInterface {
             let mut insert_lines = up_push_lines_to_insert;
 
             // pdcurses requires that the cursor is in the scroll region before configuring it.
-            self.window.mv(insert_row_pos + (LINES_TOP_OFFSET as i32), (self.screen_width - 1) as i32);
-            self.window.setscrreg(LINES_TOP_OFFSET as i32, insert_row_pos + (LINES_TOP_OFFSET as i32));
-            self.window.scrollok(true);
+            self.window().mv(insert_row_pos + (self.lines_top_offset() as i32), (self.screen_width - 1) as i32);
+            self.window().setscrreg(self.lines_top_offset() as i32, insert_row_pos + (self.lines_top_offset() as i32));
+            self.window().scrollok(true);
             let one_col_space = ' ';
 
-            self.window.attron(pancurses::colorpair::ColorPair(0));
+            self.window().attron(pancurses::colorpair::ColorPair(0));
             while insert_lines > 0 {
-                self.window.addch(one_col_space);
-                self.window.mv(insert_row_pos + (LINES_TOP_OFFSET as i32), (self.screen_width - 1) as i32);
+                self.window().addch(one_col_space);
+                self.window().mv(insert_row_pos + (self.lines_top_offset() as i32), (self.screen_width - 1) as i32);
                 insert_lines -= 1;
                 line_insert_y_start -= 1;
             }
-            self.window.attroff(pancurses::colorpair::ColorPair(0));
-            self.window.scrollok(false);
+            self.window().attroff(pancurses::colorpair::ColorPair(0));
+            self.window().scrollok(false);
 
             if line_insert_y_start > -1 {
                 match prev_row_last_col {
                     Some((last_col_start_pos, last_col_str, color_pair)) => {
 
-                        self.window.attron(pancurses::colorpair::ColorPair(color_pair));
-                        self.window.mvaddstr(line_insert_y_start + (LINES_TOP_OFFSET as i32), last_col_start_pos as i32, last_col_str);
-                        self.window.attroff(pancurses::colorpair::ColorPair(color_pair));
+                        self.window().attron(pancurses::colorpair::ColorPair(color_pair));
+                        self.window().mvaddstr(line_insert_y_start + (self.lines_top_offset() as i32), last_col_start_pos as i32, last_col_str);
+                        self.window().attroff(pancurses::colorpair::ColorPair(color_pair));
                     },
                     None => {
                     },
@@ -1480,9 +3499,9 @@ impl UserInterface {
         if !is_last_line && down_push_lines_to_insert > 0 {
             let mut insert_lines = down_push_lines_to_insert;
 
-            self.window.mv(line_insert_y_start + 1 + (LINES_TOP_OFFSET as i32), 0);
+            self.window().mv(line_insert_y_start + 1 + (self.lines_top_offset() as i32), 0);
             while insert_lines > 0 {
-                self.window.insertln();
+                self.window().insertln();
                 insert_lines -= 1;
             }
 
@@ -1493,14 +3512,7 @@ impl UserInterface {
 
         let rows_scrolled_over = if line_insert_y_start < -1 { (-1 - line_insert_y_start) as usize } else { 0 };
 
-        let color_pair = match line.line_type {
-            ScreenLineType::EmulatorMessage => {
-                COLOR_PAIR_EMSG
-            },
-            ScreenLineType::MachineMessage {..} => {
-                COLOR_PAIR_MMSG
-            },
-        };
+        let color_pair = line.color_pair();
 
         let mut out_cols_str = String::new();
 
@@ -1518,16 +3530,40 @@ impl UserInterface {
                                           (rows_to_add - rows_scrolled_over) * self.screen_width + if already_drawn_rows > 0 { self.screen_width - last_row_already_drawn_cols } else { 0 },
                                           true);
 
-        self.window.attron(pancurses::colorpair::ColorPair(color_pair));
+        self.window().attron(pancurses::colorpair::ColorPair(color_pair));
         if already_drawn_rows > 0 && last_row_already_drawn_cols < self.screen_width {
-            self.window.mv(line_insert_y_start + (rows_scrolled_over as i32) + (LINES_TOP_OFFSET as i32), last_row_already_drawn_cols as i32);
+            self.window().mv(line_insert_y_start + (rows_scrolled_over as i32) + (self.lines_top_offset() as i32), last_row_already_drawn_cols as i32);
         } else {
-            self.window.mv(line_insert_y_start + (rows_scrolled_over as i32) + (LINES_TOP_OFFSET as i32) + 1, 0);
+            self.window().mv(line_insert_y_start + (rows_scrolled_over as i32) + (self.lines_top_offset() as i32) + 1, 0);
         }
-        self.window.addstr(&out_cols_str);
-        self.window.attroff(pancurses::colorpair::ColorPair(color_pair));
+        self.window().addstr(&out_cols_str);
+        self.window().attroff(pancurses::colorpair::ColorPair(color_pair));
     }
     pub fn add_screen_line(&mut self, line_content: &str, line_type: ScreenLineType) {
+        self.add_screen_line_from(line_content, line_type, None);
+    }
+    fn add_screen_line_from(&mut self, line_content: &str, line_type: ScreenLineType, log_source: Option<(log::Level, String)>) {
+        if self.window.is_none() {
+            // Without the lines to scroll through, the filtered messages are
+            // simply dropped.
+            if !self.message_filter.shows(&log_source) {
+                return;
+            }
+            #[cfg(feature = "egui")]
+            if let Some(event_tx) = &self.gui_event_tx {
+                if line_type == ScreenLineType::EmulatorMessage {
+                    let _ = event_tx.send(GuiEvent::Message(line_content.to_owned()));
+                }
+                return;
+            }
+            println!("{}", line_content);
+            return;
+        }
+        // The lines can't be drawn over the file picker, they're shown once
+        // it's closed.
+        if self.file_picker.is_some() {
+            self.redraw_text_area = true;
+        }
         enum Action {
             SimplyAppend,
             AppendAndSwap,
@@ -1561,7 +3597,7 @@ impl UserInterface {
         };
         let msg_shown = match line_type {
             ScreenLineType::EmulatorMessage => {
-                self.emulator_msg_shown
+                self.emulator_msg_shown && self.message_filter.shows(&log_source)
             }
             ScreenLineType::MachineMessage {..} => {
                 self.machine_msg_shown
@@ -1571,6 +3607,7 @@ impl UserInterface {
         match action {
             Action::SimplyAppend => {
                 let mut new_line = ScreenLine::new_from_str(line_content, line_type, self.screen_width);
+                new_line.log_source = log_source;
 
                 if msg_shown && self.cached_screen_total_rows == 0 {
 
@@ -1591,10 +3628,10 @@ impl UserInterface {
                     for line in self.screen_lines.iter() {
                         let (line_visible, color_pair) = match line.line_type {
                             ScreenLineType::EmulatorMessage => {
-                                (self.emulator_msg_shown, COLOR_PAIR_EMSG)
+                                (self.emulator_msg_shown && self.message_filter.shows(&line.log_source), line.color_pair())
                             },
                             ScreenLineType::MachineMessage {..} => {
-                                (self.machine_msg_shown, COLOR_PAIR_MMSG)
+                                (self.machine_msg_shown, line.color_pair())
                             },
                         };
                         if line_visible {
@@ -1643,6 +3680,7 @@ impl UserInterface {
             },
             Action::AppendAndSwap => {
                 let mut new_line = ScreenLine::new_from_str(line_content, line_type, self.screen_width);
+                new_line.log_source = log_source;
 
                 if msg_shown && self.cached_screen_total_rows == 0 {
 
@@ -1664,10 +3702,10 @@ impl UserInterface {
                     for line in self.screen_lines.iter_mut() {
                         let (line_visible, color_pair) = match line.line_type {
                             ScreenLineType::EmulatorMessage => {
-                                (self.emulator_msg_shown, COLOR_PAIR_EMSG)
+                                (self.emulator_msg_shown && self.message_filter.shows(&line.log_source), line.color_pair())
                             },
                             ScreenLineType::MachineMessage {..} => {
-                                (self.machine_msg_shown, COLOR_PAIR_MMSG)
+                                (self.machine_msg_shown, line.color_pair())
                             },
                         };
                         if !last_line_found {
@@ -1734,14 +3772,7 @@ impl UserInterface {
 
                 let line_old_last_col =
                     if msg_shown {
-                        let color_pair = match last_line.line_type {
-                            ScreenLineType::EmulatorMessage => {
-                                COLOR_PAIR_EMSG
-                            },
-                            ScreenLineType::MachineMessage {..} => {
-                                COLOR_PAIR_MMSG
-                            },
-                        };
+                        let color_pair = last_line.color_pair();
                         if last_line.cached_last_scr_col {
                             Some((last_line.cached_last_scr_col_pos, last_line.cached_last_scr_col_str.to_owned(), color_pair))
                         } else {
@@ -1799,8 +3830,23 @@ impl UserInterface {
         }
     }
     fn emulator_message(&mut self, line_content: &str) {
+        self.transcript.reply(line_content);
+        if let Some(remote_control) = &mut self.remote_control {
+            remote_control.send_message(line_content);
+        }
         self.add_screen_line(line_content, ScreenLineType::EmulatorMessage);
     }
+    fn logged_message(&mut self, message: util::LoggedMessage) {
+        if let Some(remote_control) = &mut self.remote_control {
+            remote_control.send_message(&message.text);
+        }
+        self.transcript.logged(log_level_name(message.level), &message.module, &message.text);
+        if !self.logged_modules.contains(&message.module) {
+            self.logged_modules.push(message.module.clone());
+            self.logged_modules.sort();
+        }
+        self.add_screen_line_from(&message.text, ScreenLineType::EmulatorMessage, Some((message.level, message.module)));
+    }
     fn machine_line_add_char(&mut self, char_to_add: char) {
         self.add_screen_line(format!("{}", char_to_add).as_str(), ScreenLineType::MachineMessage { complete: false });
     }
@@ -1808,18 +3854,172 @@ impl UserInterface {
         self.add_screen_line("", ScreenLineType::MachineMessage { complete: true });
     }
     fn scroll_lines_up(&mut self) {
+        self.scroll_lines_up_by(self.screen_height / 2);
+    }
+    fn scroll_lines_up_by(&mut self, rows: usize) {
         // The user can request to scroll as much as they wish, the lines rendering
         // routine will then normalize this value.
-        self.bottom_rows_skip += self.screen_height / 2;
+        self.bottom_rows_skip += rows;
         self.redraw_text_area = true;
     }
-    fn scroll_lines_down(&mut self) {
-        if self.bottom_rows_skip >= (self.screen_height / 2) {
-            self.bottom_rows_skip -= self.screen_height / 2;
-        } else {
-            self.bottom_rows_skip = 0;
+    fn open_file_picker(&mut self, purpose: file_picker::Purpose, dir: Option<String>) {
+        if self.window.is_none() {
+            self.emulator_message(&i18n::tr("The file picker is only available in the curses-based interface."));
+            return;
+        }
+        let dir = match dir {
+            Some(dir) => { self.config_dir.join(dir) },
+            None => { self.config_dir.clone() },
+        };
+        self.show_file_picker(purpose, dir);
+    }
+    fn show_file_picker(&mut self, purpose: file_picker::Purpose, dir: path::PathBuf) {
+        if self.window.is_none() {
+            return;
+        }
+        if !dir.is_dir() {
+            self.emulator_message(&i18n::tr_format("`{}' isn't a directory.", &[&dir.display()]));
+            return;
+        }
+        self.file_picker = Some(file_picker::FilePicker::new(purpose, dir));
+        self.redraw_text_area = true;
+    }
+    fn close_file_picker(&mut self) {
+        self.file_picker = None;
+        self.redraw_text_area = true;
+    }
+    fn file_picker_handle_key(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, input: pancurses::Input) {
+        let page_rows = (self.cached_screen_total_rows.max(3) - 2) as isize;
+        let picker = self.file_picker.as_mut().expect("the keys only go to the file picker while it's open");
+        match input {
+            pancurses::Input::KeyResize => { self.handle_resize_event(); },
+            pancurses::Input::KeyUp     => { picker.move_by(-1); },
+            pancurses::Input::KeyDown   => { picker.move_by(1); },
+            pancurses::Input::KeyPPage  => { picker.move_by(-page_rows); },
+            pancurses::Input::KeyNPage  => { picker.move_by(page_rows); },
+            pancurses::Input::KeyHome   => { picker.move_to_start(); },
+            pancurses::Input::KeyEnd    => { picker.move_to_end(); },
+            pancurses::Input::KeyLeft | pancurses::Input::KeyBackspace | pancurses::Input::Character('\x08') | pancurses::Input::Character('\x7F') => {
+                picker.go_up();
+            },
+            pancurses::Input::KeyRight | pancurses::Input::KeyEnter | pancurses::Input::Character('\r') | pancurses::Input::Character('\n') => {
+                if let Some(file) = picker.choose() {
+                    let purpose = picker.purpose;
+                    self.close_file_picker();
+                    self.use_picked_file(emu_cmd_tx, purpose, &file);
+                    return;
+                }
+            },
+            pancurses::Input::Character('\x07') | pancurses::Input::Character('\x1B') => {
+                self.close_file_picker();
+                return;
+            },
+            pancurses::Input::Character(input_char) if !input_char.is_control() => {
+                let found = picker.jump_to(input_char);
+                if !found {
+                    pancurses::beep();
+                }
+            },
+            _ => { },
+        }
+        self.redraw_text_area = true;
+    }
+    // Do with the chosen file what the picker was opened for.  The ROMs can
+    // go into more than one slot, so the command is left to be finished.
+    fn use_picked_file(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>, purpose: file_picker::Purpose, file: &path::Path) {
+        match purpose {
+            file_picker::Purpose::Cassette => {
+                let command = format!("cassette insert {}", file.display());
+                self.transcript.command(&format!("/{}", command));
+                self.execute_command(emu_cmd_tx, &command);
+            },
+            file_picker::Purpose::Disk => {
+                let command = format!("disk dir {}", file.display());
+                self.transcript.command(&format!("/{}", command));
+                self.execute_command(emu_cmd_tx, &command);
+            },
+            file_picker::Purpose::Rom => {
+                self.emulator_message(&i18n::tr("Choose the ROM to replace on the command line, and press Enter."));
+                self.set_prompt_text(&format!("config change paths_level_2_rom = {}", file.display()));
+            },
+            file_picker::Purpose::Script => {
+                self.run_script(file);
+            },
+        }
+    }
+    fn render_file_picker(&mut self) {
+        let lines_top_offset = self.lines_top_offset();
+        let avail_screen_rows = self.screen_height - LINES_BOTTOM_OFFSET - lines_top_offset;
+        let list_rows = avail_screen_rows.saturating_sub(2);
+        let screen_width = self.screen_width;
+
+        let window = self.window.as_ref().expect("there's no curses window in the headless mode");
+        let picker = self.file_picker.as_mut().expect("only drawn while it's open");
+        let first_shown = picker.first_shown(list_rows);
+
+        let fit = |text: &str| -> String {
+            let text: String = text.chars().take(screen_width.saturating_sub(1)).collect();
+            format!(" {:width$}", text, width = screen_width.saturating_sub(1))
+        };
+
+        // The contents of the cassettes go into a column after the names.
+        let name_width = picker.entries.iter().filter(|entry| entry.contents.is_some()).map(|entry| entry.name.chars().count()).max().unwrap_or(0).min(MAX_PICKER_NAME_WIDTH);
+
+        window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+        window.mv(lines_top_offset as i32, 0);
+        window.addstr(fit(&i18n::tr_format("{} in {}:", &[&i18n::tr(picker.purpose.title()), &picker.dir.display()])));
+        window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+
+        window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_MMSG));
+        for row in 0..list_rows {
+            let index = first_shown + row;
+            let text = match (&picker.error, picker.entries.get(index)) {
+                (Some(error), _) if row == 0 => { format!("  {}", i18n::tr_format("Failed to read the directory: {}.", &[error])) },
+                (None, Some(entry)) => {
+                    match entry.contents {
+                        Some(ref contents) => { format!("  {:width$}  {}", entry.name, contents, width = name_width) },
+                        None => { format!("  {}{}", entry.name, if entry.is_dir { "/" } else { "" }) },
+                    }
+                },
+                _ => { String::new() },
+            };
+            if picker.error.is_none() && index == picker.selected {
+                window.attron(pancurses::A_REVERSE);
+            }
+            window.mv((lines_top_offset + 1 + row) as i32, 0);
+            window.addstr(fit(&text));
+            window.attroff(pancurses::A_REVERSE);
+        }
+        window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_MMSG));
+
+        window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+        window.mv((lines_top_offset + avail_screen_rows - 1) as i32, 0);
+        window.addstr(fit(&i18n::tr("Enter chooses, Backspace goes up a directory, Escape cancels.")));
+        window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+    }
+    fn scroll_lines_top(&mut self) {
+        // All of the rows, the lines rendering routine brings it down to the
+        // ones which are shown.
+        let screen_width = self.screen_width;
+        self.bottom_rows_skip = self.screen_lines.iter_mut().map(|line| line.screen_rows(screen_width)).sum();
+        self.redraw_text_area = true;
+    }
+    fn scroll_lines_bottom(&mut self) {
+        self.bottom_rows_skip = 0;
+
+        if self.lines_added_scrolled_up {
+            self.lines_added_scrolled_up = false;
+            self.redraw_status = true;
         }
 
+        self.redraw_text_area = true;
+    }
+    fn scroll_lines_down(&mut self) {
+        self.scroll_lines_down_by(self.screen_height / 2);
+    }
+    fn scroll_lines_down_by(&mut self, rows: usize) {
+        self.bottom_rows_skip = self.bottom_rows_skip.saturating_sub(rows);
+
         if self.bottom_rows_skip == 0 && self.lines_added_scrolled_up {
             self.lines_added_scrolled_up = false;
             self.redraw_status = true;
@@ -2054,12 +4254,37 @@ impl UserInterface {
             self.scroll_prompt_if_needed();
         }
     }
+    // Put a command on the command line for the user to finish, replacing
+    // what's typed there, with the cursor at its end.
+    fn suggest_command(&mut self, command: &str) {
+        if self.window.is_none() {
+            self.emulator_message(&i18n::tr_format("The dropped file can be opened with `/{}', with what's missing filled in.", &[&command.trim_end()]));
+            return;
+        }
+        self.emulator_message(&i18n::tr("The dropped file can be opened in more than one way, finish the command on the command line and press Enter."));
+        self.set_prompt_text(command);
+    }
+    fn set_prompt_text(&mut self, command: &str) {
+        self.prompt_search = None;
+        self.prompt_history_pos = 0;
+        self.prompt_text = ScreenLine::new(ScreenLineType::EmulatorMessage, 0);
+        self.prompt_curs_code_pos = 0;
+        self.prompt_curs_cell_pos = 0;
+        self.prompt_scroll_cells = 0;
+        for ch in format!("/{}", command).chars() {
+            self.prompt_insert_char(ch);
+        }
+        self.redraw_prompt = true;
+    }
     fn prompt_handle_enter_key(&mut self, emu_cmd_tx: &mpsc::Sender<EmulatorCommand>) {
         let entered_text_line = match self.prompt_history_pos {
             0 => { self.prompt_text.clone() },
             _ => { self.prompt_history[self.prompt_history_pos - 1].clone() },
         };
         let entered_text = entered_text_line.to_string();
+        if !entered_text.is_empty() {
+            self.transcript.command(&entered_text);
+        }
 
         self.prompt_add_to_history(&entered_text_line);
         self.prompt_text = ScreenLine::new(ScreenLineType::EmulatorMessage, 0);
@@ -2094,32 +4319,170 @@ impl UserInterface {
 
         self.prompt_history.truncate(self.prompt_history_max_entries - 1);
         self.prompt_history.push_front(to_add.clone());
+        self.save_prompt_history();
+    }
+    fn load_prompt_history(&mut self) {
+        let history_path = self.config_dir.join(HISTORY_FILE_NAME);
+        let history_text = match fs::read_to_string(&history_path) {
+            Ok(text) => { text },
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read the command history from `{}': {}.", history_path.display(), error);
+                }
+                return;
+            },
+        };
+
+        // The file has the oldest entries first.
+        for line in history_text.lines().filter(|line| !line.is_empty()) {
+            self.prompt_history.truncate(self.prompt_history_max_entries - 1);
+            self.prompt_history.push_front(ScreenLine::new_from_str(line, ScreenLineType::EmulatorMessage, 0));
+        }
+    }
+    fn save_prompt_history(&mut self) {
+        let mut history_text = String::new();
+        for line in self.prompt_history.iter().rev() {
+            history_text.push_str(&line.to_string());
+            history_text.push('\n');
+        }
+
+        let history_path = self.config_dir.join(HISTORY_FILE_NAME);
+        match fs::write(&history_path, history_text) {
+            Ok(..) => { },
+            Err(error) => {
+                // Only complain once, not after every command.
+                if !self.history_save_failed {
+                    warn!("Failed to save the command history into `{}': {}.", history_path.display(), error);
+                    self.history_save_failed = true;
+                }
+            },
+        }
+    }
+    fn prompt_start_search(&mut self) {
+        self.prompt_search = Some(String::new());
+        self.prompt_search_origin = (self.prompt_history_pos, self.prompt_curs_code_pos);
+        self.redraw_prompt = true;
+    }
+    // Handle a key during the reverse search, the typed characters are added
+    // to the searched text, Ctrl+R goes on to the next older match, and
+    // Escape or Ctrl+G cancel the search.  Other keys leave the found entry
+    // on the prompt and are then handled as usual, so Enter runs it.
+    fn prompt_search_handle_key(&mut self, input: &pancurses::Input) -> bool {
+        let mut query = match self.prompt_search.take() {
+            Some(query) => { query },
+            None => { return false; },
+        };
+        match *input {
+            pancurses::Input::KeyResize => {
+                self.handle_resize_event();
+            },
+            pancurses::Input::KeyBackspace | pancurses::Input::Character('\x08') | pancurses::Input::Character('\x7F') => {
+                if query.pop().is_none() {
+                    pancurses::beep();
+                } else if !query.is_empty() {
+                    self.prompt_search_history(&query, 1);
+                }
+            },
+            pancurses::Input::Character('\x12') => {
+                if query.is_empty() || !self.prompt_search_history(&query, self.prompt_history_pos + 1) {
+                    pancurses::beep();
+                }
+            },
+            pancurses::Input::Character('\x07') | pancurses::Input::Character('\x1B') => {
+                let (history_pos, curs_code_pos) = self.prompt_search_origin;
+                self.prompt_history_pos = history_pos;
+                self.prompt_curs_code_pos = curs_code_pos;
+                self.calc_prompt_curs_cell_pos();
+                self.scroll_prompt_if_needed();
+                self.redraw_prompt = true;
+                return true;
+            },
+            pancurses::Input::Character(input_char) if !input_char.is_control() => {
+                query.push(input_char);
+                if !self.prompt_search_history(&query, self.prompt_history_pos) {
+                    query.pop();
+                    pancurses::beep();
+                }
+            },
+            _ => {
+                self.prompt_curs_code_pos = match self.prompt_history_pos {
+                    0 => { self.prompt_text.line_codes.len() },
+                    _ => { self.prompt_history[self.prompt_history_pos - 1].line_codes.len() },
+                };
+                self.calc_prompt_curs_cell_pos();
+                self.scroll_prompt_if_needed();
+                self.redraw_prompt = true;
+                return false;
+            },
+        }
+        self.prompt_search = Some(query);
+        self.redraw_prompt = true;
+        true
+    }
+    // Find the first history entry from the given position on, towards the
+    // older ones, which contains the searched text.
+    fn prompt_search_history(&mut self, query: &str, start_pos: usize) -> bool {
+        for history_pos in start_pos.max(1)..=self.prompt_history.len() {
+            if self.prompt_history[history_pos - 1].to_string().contains(query) {
+                self.prompt_history_pos = history_pos;
+                return true;
+            }
+        }
+        false
     }
     pub fn update_screen(&mut self) {
 
         if self.redraw_everything {
 
-            self.window.erase();
+            self.window().erase();
 
             if self.screen_too_small {
-                self.window.mv(0, 0);
-                self.window.addstr(format!("Screen too small, minimum size is {} rows, {} cols.", MIN_SCREEN_HEIGHT, MIN_SCREEN_WIDTH));
+                self.window().mv(0, 0);
+                let (min_width, min_height) = self.min_screen_size();
+                self.window().addstr(i18n::tr_format("Screen too small, minimum size is {} rows, {} cols.", &[&min_height, &min_width]));
             } else {
-                self.render_lines(false);
+                self.render_machine_screen();
+                self.render_status_pane();
+                self.render_debugger_pane();
+                if self.file_picker.is_some() {
+                    self.render_file_picker();
+                } else {
+                    self.render_lines(false);
+                }
                 self.render_status_strips();
                 self.render_prompt();
             }
 
+            self.redraw_machine_screen = false;
+            self.redraw_status_pane = false;
+            self.redraw_debugger_pane = false;
             self.redraw_text_area = false;
             self.redraw_status = false;
             self.redraw_prompt = false;
             self.redraw_everything = false;
 
-        } else {
+        } else if !self.screen_too_small {
+
+            if self.redraw_machine_screen {
+                self.render_machine_screen();
+                self.redraw_machine_screen = false;
+            }
+            if self.redraw_status_pane {
+                self.render_status_pane();
+                self.redraw_status_pane = false;
+            }
+            if self.redraw_debugger_pane {
+                self.render_debugger_pane();
+                self.redraw_debugger_pane = false;
+            }
 
             if self.redraw_text_area {
 
-                self.render_lines(true);
+                if self.file_picker.is_some() {
+                    self.render_file_picker();
+                } else {
+                    self.render_lines(true);
+                }
 
                 self.redraw_text_area = false;
             }
@@ -2135,7 +4498,123 @@ impl UserInterface {
         }
 
         self.set_cursor_pos();
-        self.window.refresh();
+        self.window().refresh();
+    }
+    fn min_screen_size(&self) -> (usize, usize) {
+        let (min_width, min_height) = if self.machine_screen_rx.is_some() {
+            (MIN_TERMINAL_SCREEN_WIDTH, MIN_TERMINAL_SCREEN_HEIGHT)
+        } else {
+            (MIN_SCREEN_WIDTH, MIN_SCREEN_HEIGHT)
+        };
+        // The panes are shrunk down to a row to fit, the debugger pane has
+        // a strip on top of it.
+        let status_rows = if self.status_pane_shown { 1 } else { 0 };
+        let debugger_rows = if self.debugger_pane_shown { 2 } else { 0 };
+        (min_width, min_height + status_rows + debugger_rows)
+    }
+    // Shrink the panes when the screen gets smaller, for the messages to keep
+    // a few rows.
+    fn fit_panes(&mut self) {
+        let strip_rows = if self.debugger_pane_shown { 1 } else { 0 };
+        let available_rows = (self.screen_height.saturating_sub(LINES_BOTTOM_OFFSET)).saturating_sub(self.status_pane_top_offset() + strip_rows + layout::MIN_MESSAGE_ROWS);
+        self.layout.fit(available_rows, self.status_pane_shown, self.debugger_pane_shown);
+    }
+    // In the terminal-only mode, the status pane and the lines start below
+    // the screen of the machine and the strip under it.
+    fn status_pane_top_offset(&self) -> usize {
+        if self.machine_screen_rx.is_some() {
+            LINES_TOP_OFFSET + MACHINE_SCREEN_ROWS + 1
+        } else {
+            LINES_TOP_OFFSET
+        }
+    }
+    fn debugger_pane_top_offset(&self) -> usize {
+        if self.status_pane_shown {
+            self.status_pane_top_offset() + self.layout.rows(layout::Pane::Status)
+        } else {
+            self.status_pane_top_offset()
+        }
+    }
+    fn lines_top_offset(&self) -> usize {
+        if self.debugger_pane_shown {
+            self.debugger_pane_top_offset() + 1 + self.layout.rows(layout::Pane::Debugger)
+        } else {
+            self.debugger_pane_top_offset()
+        }
+    }
+    fn render_status_pane(&mut self) {
+        if !self.status_pane_shown {
+            return;
+        }
+        let top = self.status_pane_top_offset();
+        let scroll = self.layout.scroll(layout::Pane::Status);
+
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        for row in 0..self.layout.rows(layout::Pane::Status) {
+            self.window().mv((top + row) as i32, 0);
+            self.window().hline(0x20, self.screen_width as i32);
+
+            if let Some(text) = self.status_pane.get(scroll + row) {
+                let text: String = text.chars().take(self.screen_width.saturating_sub(2)).collect();
+                self.window().mvaddstr((top + row) as i32, 1, text);
+            }
+        }
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+    }
+    fn render_debugger_pane(&mut self) {
+        if !self.debugger_pane_shown {
+            return;
+        }
+        let top = self.debugger_pane_top_offset();
+        let scroll = self.layout.scroll(layout::Pane::Debugger);
+
+        // The strip on top of it, highlighted while the pane has the focus:
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        self.window().mv(top as i32, 0);
+        self.window().hline(0x20, self.screen_width as i32);
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+        self.window().mv(top as i32, 1);
+        if self.layout.focus() == layout::Pane::Debugger {
+            self.window().attron(pancurses::A_BOLD);
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            self.window().addstr(i18n::tr("Debugger"));
+            self.window().attroff(pancurses::A_BOLD);
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+        } else {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addstr(i18n::tr("Debugger"));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        }
+
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+        for row in 0..self.layout.rows(layout::Pane::Debugger) {
+            let y_pos = (top + 1 + row) as i32;
+            self.window().mv(y_pos, 0);
+            self.window().hline(0x20, self.screen_width as i32);
+
+            if let Some(text) = self.debugger_pane.get(scroll + row) {
+                let text: String = text.chars().take(self.screen_width.saturating_sub(2)).collect();
+                self.window().mvaddstr(y_pos, 1, text);
+            }
+        }
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_EMSG));
+    }
+    fn render_machine_screen(&mut self) {
+        if self.machine_screen_rx.is_none() {
+            return;
+        }
+        let left = (self.screen_width - MIN_TERMINAL_SCREEN_WIDTH) / 2;
+
+        for row in 0..MACHINE_SCREEN_ROWS {
+            self.window().mv((LINES_TOP_OFFSET + row) as i32, 0);
+            self.window().hline(0x20, self.screen_width as i32);
+
+            if let Some(text) = self.machine_screen.get(row) {
+                let text: String = text.chars().take(MIN_TERMINAL_SCREEN_WIDTH).collect();
+                self.window().mvaddstr((LINES_TOP_OFFSET + row) as i32, left as i32, text);
+            }
+        }
     }
     // Description:
     //
@@ -2143,7 +4622,8 @@ impl UserInterface {
     // window.  It draws them from bottom to top.
     //
     fn render_lines(&mut self, clear_area: bool) {
-        let avail_screen_rows = self.screen_height - LINES_BOTTOM_OFFSET - LINES_TOP_OFFSET;
+        let lines_top_offset = self.lines_top_offset();
+        let avail_screen_rows = self.screen_height - LINES_BOTTOM_OFFSET - lines_top_offset;
         let mut screen_rows_to_draw = 0;
         let mut screen_rows_to_scroll_over = 0;
 
@@ -2152,19 +4632,19 @@ impl UserInterface {
 
         if clear_area {
             let hline_length = self.screen_width as i32;
-            self.window.attron(pancurses::colorpair::ColorPair(0));
+            self.window().attron(pancurses::colorpair::ColorPair(0));
             for row in 0..=(avail_screen_rows-1) {
-                self.window.mv((row + LINES_TOP_OFFSET) as i32, 0);
-                self.window.hline(0x20 /*'+'*/, hline_length);
+                self.window().mv((row + lines_top_offset) as i32, 0);
+                self.window().hline(0x20 /*'+'*/, hline_length);
             }
-            self.window.attroff(pancurses::colorpair::ColorPair(0));
+            self.window().attroff(pancurses::colorpair::ColorPair(0));
         }
 
         for line in self.screen_lines.iter_mut() {
             // Skip lines which aren't to be shown:
             match line.line_type {
                 ScreenLineType::EmulatorMessage => {
-                    if !self.emulator_msg_shown {
+                    if !self.emulator_msg_shown || !self.message_filter.shows(&line.log_source) {
                         continue;
                     }
                 },
@@ -2199,16 +4679,18 @@ impl UserInterface {
         }
 
         if screen_rows_to_draw > 0 {
-            let mut y_pos = (avail_screen_rows as i32) - 1 + (LINES_TOP_OFFSET as i32);
+            let mut y_pos = (avail_screen_rows as i32) - 1 + (lines_top_offset as i32);
             if avail_screen_rows > screen_rows_to_draw {
                 y_pos -= (avail_screen_rows as i32) - (screen_rows_to_draw as i32);
             }
 
+            // The lines are borrowed mutably, so the window is borrowed on its own.
+            let window = self.window.as_ref().expect("there's no curses window in the headless mode");
             for line in self.screen_lines.iter_mut() {
                 // Skip lines which aren't to be shown:
                 match line.line_type {
                     ScreenLineType::EmulatorMessage => {
-                        if !self.emulator_msg_shown {
+                        if !self.emulator_msg_shown || !self.message_filter.shows(&line.log_source) {
                             continue;
                         }
                     },
@@ -2236,32 +4718,29 @@ impl UserInterface {
                 }
                 let cur_line_screen_rows_print = cur_line_screen_rows_print;
 
-                let color_pair = match line.line_type {
-                    ScreenLineType::EmulatorMessage     => { COLOR_PAIR_EMSG },
-                    ScreenLineType::MachineMessage {..} => { COLOR_PAIR_MMSG },
-                };
+                let color_pair = line.color_pair();
 
 
                 let new_y_pos = y_pos - (cur_line_screen_rows_print as i32) + 1;
 
-                let screen_rows_to_skip = if new_y_pos < LINES_TOP_OFFSET as i32 {
-                    ((LINES_TOP_OFFSET as i32) - new_y_pos) as usize
+                let screen_rows_to_skip = if new_y_pos < lines_top_offset as i32 {
+                    ((lines_top_offset as i32) - new_y_pos) as usize
                 } else {
                     0
                 };
 
-                self.window.mv(new_y_pos + screen_rows_to_skip as i32, 0);
+                window.mv(new_y_pos + screen_rows_to_skip as i32, 0);
 
                 let mut out_cols_str = String::new();
                 let _out_cols_str_cols = line.prepare_utf8str_for_cols(&mut out_cols_str, self.screen_width, screen_rows_to_skip * self.screen_width, (cur_line_screen_rows_print - screen_rows_to_skip) * self.screen_width, true);
                 let out_cols_str = out_cols_str;
 
-                self.window.attron(pancurses::colorpair::ColorPair(color_pair));
-                self.window.addstr(out_cols_str);
-                self.window.attroff(pancurses::colorpair::ColorPair(color_pair));
+                window.attron(pancurses::colorpair::ColorPair(color_pair));
+                window.addstr(out_cols_str);
+                window.attroff(pancurses::colorpair::ColorPair(color_pair));
 
                 y_pos = new_y_pos - 1;
-                if y_pos < LINES_TOP_OFFSET as i32 {
+                if y_pos < lines_top_offset as i32 {
                     break;
                 }
             }
@@ -2270,101 +4749,296 @@ impl UserInterface {
     fn render_status_strips(&mut self) {
 
         // Color the strips:
-        self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
-        self.window.mv(TOP_STRIP_TOP_OFFSET as i32, 0);
-        self.window.hline(0x20, self.screen_width as i32);
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        self.window().mv(TOP_STRIP_TOP_OFFSET as i32, 0);
+        self.window().hline(0x20, self.screen_width as i32);
 
-        self.window.mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, 0);
-        self.window.hline(0x20, self.screen_width as i32);
+        self.window().mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, 0);
+        self.window().hline(0x20, self.screen_width as i32);
 
         // Write in some text:
-        self.window.mv(TOP_STRIP_TOP_OFFSET as i32, 1);
-        self.window.addstr(format!("{} v{} - TRS-80 Model I emulator", PROGRAM_NAME, PROGRAM_VERSION).as_str());
-        self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        self.window().mv(TOP_STRIP_TOP_OFFSET as i32, 1);
+        self.window().addstr(format!("{} v{} - {}", PROGRAM_NAME, PROGRAM_VERSION, i18n::tr("TRS-80 Model I emulator")).as_str());
+
+        // The strip under the screen of the machine, in the terminal-only
+        // mode, tells where the keys go:
+        if self.machine_screen_rx.is_some() {
+            self.window().mv((LINES_TOP_OFFSET + MACHINE_SCREEN_ROWS) as i32, 0);
+            self.window().hline(0x20, self.screen_width as i32);
+            self.window().mv((LINES_TOP_OFFSET + MACHINE_SCREEN_ROWS) as i32, 1);
+            if self.layout.focus() == layout::Pane::Machine {
+                self.window().addstr(i18n::tr("Keys go to the machine, Tab switches to the command line"));
+            } else {
+                self.window().addstr(i18n::tr("Keys go to the command line, Tab switches to the machine"));
+            }
+        }
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
 
-        self.window.mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, 1);
+        self.window().mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, 1);
 
-        self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
-        self.window.addch('[');
-        self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        self.window().addch('[');
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
 
-        self.window.attron(pancurses::A_BOLD);
+        self.window().attron(pancurses::A_BOLD);
         if self.machine_powered_on {
-            self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
-            self.window.addstr("power on");
-            self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            self.window().addstr(i18n::tr("power on"));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
         } else {
-            self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_RED));
-            self.window.addstr("power off");
-            self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_RED));
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_RED));
+            self.window().addstr(i18n::tr("power off"));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_RED));
         }
-        self.window.attroff(pancurses::A_BOLD);
+        self.window().attroff(pancurses::A_BOLD);
 
-        self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
-        self.window.addch(']');
-        self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        self.window().addch(']');
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
 
         if self.machine_powered_on || self.machine_paused {
-            self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
-            self.window.addch(' ');
-            self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
 
-            self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
-            self.window.addch('[');
-            self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
 
             if self.machine_paused {
-                self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
-                self.window.addstr("paused");
-                self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+                self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+                self.window().addstr(i18n::tr("paused"));
+                self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
             } else if self.cpu_halted {
-                self.window.attron(pancurses::A_BOLD);
-                self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
-                self.window.addstr("halted");
-                self.window.attroff(pancurses::A_BOLD);
-                self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+                self.window().attron(pancurses::A_BOLD);
+                self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+                self.window().addstr(i18n::tr("halted"));
+                self.window().attroff(pancurses::A_BOLD);
+                self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            } else {
+                self.window().attron(pancurses::A_BOLD);
+                self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+                self.window().addstr(i18n::tr("running"));
+                self.window().attroff(pancurses::A_BOLD);
+                self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            }
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        // The pane with the focus, unless it's the command line, or the screen
+        // of the machine, which has a strip of its own:
+        if matches!(self.layout.focus(), layout::Pane::Status | layout::Pane::Debugger | layout::Pane::Messages) {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            self.window().attron(pancurses::A_BOLD);
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            self.window().addstr(i18n::tr(self.layout.focus().name()));
+            self.window().attroff(pancurses::A_BOLD);
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        // A long operation of the logic core, with a bar of how far it got:
+        if let Some((task, done, total)) = &self.progress {
+            let percent = (*done * 100).checked_div(*total).unwrap_or(0).min(100);
+            let filled = percent / 10;
+            let bar = format!("{}{}", "#".repeat(filled), "-".repeat(10 - filled));
+            let progress_text = format!("{} {} {}%", i18n::tr(task), bar, percent);
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            self.window().attron(pancurses::A_BOLD);
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+            self.window().addstr(&progress_text);
+            self.window().attroff(pancurses::A_BOLD);
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        // The speed is only known while the machine runs, and the frame
+        // rate only with the emulator window:
+        if let Some(speed) = self.performance.0 {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            let performance_text = match (self.performance.1, self.machine_warp) {
+                (Some(frame_rate), false) => { i18n::tr_format("speed {}% {} fps", &[&speed, &frame_rate]) },
+                (None, false)             => { i18n::tr_format("speed {}%", &[&speed]) },
+                (Some(frame_rate), true)  => { i18n::tr_format("warp {}% {} fps", &[&speed, &frame_rate]) },
+                (None, true)              => { i18n::tr_format("warp {}%", &[&speed]) },
+            };
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addstr(&performance_text);
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        // The emulated time since the machine was powered on, which runs
+        // slower or faster than the real one along with the speed:
+        if let Some(clock) = self.clock {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addstr(i18n::tr_format("clock {}", &[&util::clock_text(clock)]));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        if let Some((position, length)) = self.cassette_counter {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addstr(i18n::tr_format("tape {}/{}", &[&position, &length]));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        // The motor is only shown while it runs, or while it's forced:
+        let (motor_running, motor_forced) = self.cassette_motor;
+        if motor_running || motor_forced {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            let motor_text = match (motor_running, motor_forced) {
+                (true,  false) => { i18n::tr("motor on") },
+                (true,  true)  => { i18n::tr("motor forced on") },
+                (false, _)     => { i18n::tr("motor forced off") },
+            };
+            if motor_running {
+                self.window().attron(pancurses::A_BOLD);
+                self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+                self.window().addstr(&motor_text);
+                self.window().attroff(pancurses::A_BOLD);
+                self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
             } else {
-                self.window.attron(pancurses::A_BOLD);
-                self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
-                self.window.addstr("running");
-                self.window.attroff(pancurses::A_BOLD);
-                self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GREEN));
+                self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+                self.window().addstr(&motor_text);
+                self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
             }
 
-            self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
-            self.window.addch(']');
-            self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+        }
+
+        if self.message_filter.is_active() {
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addch(' ');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch('[');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+            self.window().addstr(i18n::tr("filtered"));
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+
+            self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
+            self.window().addch(']');
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_CYAN));
         }
 
-        self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
         if self.lines_added_scrolled_up {
-            self.window.mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, (self.screen_width as i32) - 1 - 10);
-            self.window.addstr("-- more --");
+            let more_text = i18n::tr("-- more --");
+            let more_width = unicode_width::UnicodeWidthStr::width(more_text.as_str());
+            self.window().mv((self.screen_height - BOTTOM_STRIP_BOTTOM_OFFSET) as i32 - 1, (self.screen_width as i32) - 1 - more_width as i32);
+            self.window().addstr(&more_text);
         }
-        self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_STRIP_GRAY));
     }
     fn render_prompt(&mut self) {
-        self.window.attron(pancurses::colorpair::ColorPair(COLOR_PAIR_PROMPT));
+        self.window().attron(pancurses::colorpair::ColorPair(COLOR_PAIR_PROMPT));
 
-        self.window.mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, 0);
-        self.window.hline(0x20, self.screen_width as i32);
-        self.window.addstr("> ");
+        self.window().mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, 0);
+        self.window().hline(0x20, self.screen_width as i32);
+        self.window().addstr("> ");
 
-        self.window.mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, PROMPT_TEXT_OFFSET as i32);
+        self.window().mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, PROMPT_TEXT_OFFSET as i32);
         let current_prompt_text = match self.prompt_history_pos {
             0 => { &mut self.prompt_text },
             _ => { &mut self.prompt_history[self.prompt_history_pos - 1] },
         };
 
+        // While searching, the searched text goes in front of the found entry,
+        // which is shown from its start.
+        if let Some(ref query) = self.prompt_search {
+            let mut search_line = ScreenLine::new_from_str(&format!("(reverse-i-search)`{}': {}", query, current_prompt_text.to_string()), ScreenLineType::EmulatorMessage, 0);
+            let mut out_cols_str = String::new();
+            search_line.prepare_utf8str_for_cols(&mut out_cols_str, self.screen_width, 0, self.screen_width - PROMPT_TEXT_OFFSET, false);
+
+            self.window().addstr(out_cols_str);
+            self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_PROMPT));
+            return;
+        }
+
         let mut out_cols_str = String::new();
         let _out_cols_str_cols = current_prompt_text.prepare_utf8str_for_cols(&mut out_cols_str, self.screen_width, self.prompt_scroll_cells, self.screen_width - PROMPT_TEXT_OFFSET, false);
         let out_cols_str = out_cols_str;
 
-        self.window.addstr(out_cols_str);
-        self.window.attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_PROMPT));
+        self.window().addstr(out_cols_str);
+        self.window().attroff(pancurses::colorpair::ColorPair(COLOR_PAIR_PROMPT));
     }
     fn set_cursor_pos(&self) {
-        self.window.mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, (self.prompt_curs_cell_pos + PROMPT_TEXT_OFFSET - self.prompt_scroll_cells) as i32);
+        if let Some(ref query) = self.prompt_search {
+            let query_cells = unicode_width::UnicodeWidthStr::width(format!("(reverse-i-search)`{}", query).as_str());
+            self.window().mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, (query_cells + PROMPT_TEXT_OFFSET).min(self.screen_width - 1) as i32);
+            return;
+        }
+        self.window().mv((self.screen_height - PROMPT_BOTTOM_OFFSET) as i32 - 1, (self.prompt_curs_cell_pos + PROMPT_TEXT_OFFSET - self.prompt_scroll_cells) as i32);
     }
     pub fn enter_key_to_close_on_windows() {
         match cfg!(target_os = "windows") {
@@ -2390,6 +5064,8 @@ impl UserInterface {
 
 impl Drop for UserInterface {
     fn drop(&mut self) {
-        pancurses::endwin();
+        if self.window.is_some() {
+            pancurses::endwin();
+        }
     }
 }
@@ -17,14 +17,24 @@ use log::{Record, Level, LevelFilter, Metadata};
 
 use std::vec::Vec;
 use std::sync::Mutex;
+use std::time;
 
 // The message logging mechanism used in the project is having a shared
 // message logging buffer that various parts of the code submit messages
 // to, which are then collected by a user interface module and displayed
 // in a scrollable text buffer.
 //
+// The level and the module of each message are kept along with its text, so
+// that the user interface can filter them.
+//
+pub struct LoggedMessage {
+    pub level:  Level,
+    pub module: String,     // The last part of the module path, like `cassette'.
+    pub text:   String,
+}
+
 struct MessageLoggerState {
-    messages:       Vec<String>,
+    messages:       Vec<LoggedMessage>,
     stdouterr_echo: bool,
 }
 pub struct MessageLogger {
@@ -50,7 +60,7 @@ impl MessageLogger {
             },
         }
     }
-    pub fn collect_messages(&self) -> Option<Vec<String>> {
+    pub fn collect_messages(&self) -> Option<Vec<LoggedMessage>> {
         match self.state.lock() {
             Ok(mut state) => {
                 if state.messages.len() > 0 {
@@ -94,7 +104,12 @@ impl log::Log for MessageLogger {
                             println!("{}", message);
                         }
                     }
-                    state.messages.push(message);
+                    let module = record.module_path().unwrap_or(record.target());
+                    state.messages.push(LoggedMessage {
+                        level:  record.level(),
+                        module: module.rsplit("::").next().unwrap_or(module).to_owned(),
+                        text:   message,
+                    });
                 },
                 Err(error) => {
                     eprintln!("{}", message);
@@ -177,6 +192,26 @@ pub fn get_starting_at_word(input: &str, order: usize) -> Option<String> {
     }
 }
 
+// The following routine parses a string of hexadecimal digit pairs, like
+// `C30040', into the bytes they represent.
+//
+// It either returns Some(bytes) on success, or None on failure.
+pub fn parse_hex_bytes(input: &str) -> Option<Vec<u8>> {
+    let digits: Vec<char> = input.trim().chars().collect();
+    if digits.is_empty() || (digits.len() & 1) != 0 {
+        return None;
+    }
+    let mut bytes = Vec::new();
+
+    for pair in digits.chunks(2) {
+        match (pair[0].to_digit(16), pair[1].to_digit(16)) {
+            (Some(high), Some(low)) => { bytes.push(((high << 4) | low) as u8); },
+            _ => { return None; },
+        }
+    }
+    Some(bytes)
+}
+
 // The following routine parses a 32-bit unsigned number from a string,
 // accepting the `0x-', `0-' and `0b-' prefixes for hex, octal and binary, and
 // the `-h' postfix for hex.  The `_' is stripped from input.
@@ -199,6 +234,9 @@ pub fn parse_u32_from_str(input_in: &str) -> Option<u32> {
             let second_char = input_chars.next().expect(".expect() call: Second character retrieval in util::parse_u32_from_str()");
             match second_char {
                 'x' => { base = 16; skip_start = 2 },
+                // The numbers with the `-h' postfix can start with a zero
+                // too, like `0C3h' or `00h'.
+                _ if input.ends_with('h') => { },
                 'b' => { base = 2;  skip_start = 2 },
                  _  => { base = 8;  skip_start = 1 },
             }
@@ -236,3 +274,78 @@ pub fn parse_u32_from_str(input_in: &str) -> Option<u32> {
 
     Some(accumulator)
 }
+
+// The CRC-32 of the parts of the data taken one after another, as used by
+// the PNG images, and to tell the files of the titles apart.
+pub fn crc32(data: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for part in data {
+        for byte in part.iter() {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if (crc & 1) != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            }
+        }
+    }
+    !crc
+}
+
+// The speed limit of the emulated machine, as a percentage of the speed of
+// the real one from 10 to 1000, optionally with the `%', or `unlimited'.
+//
+// It either returns Some(limit) on success, with None for unlimited, or None
+// on failure.
+pub fn parse_speed_limit(input: &str) -> Option<Option<u32>> {
+    let input = input.trim();
+    if input.to_lowercase() == "unlimited" {
+        return Some(None);
+    }
+    match input.trim_end_matches('%').parse::<u32>() {
+        Ok(percent) if (10..=1000).contains(&percent) => { Some(Some(percent)) },
+        _ => { None },
+    }
+}
+
+// A number of seconds in the `H:MM:SS' format, for the clocks.
+pub fn clock_text(seconds: u64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, (seconds / 60) % 60, seconds % 60)
+}
+
+// The following routine returns the current date and time (in UTC) in the
+// `YYYYMMDD-HHMMSS' format, meant to be used in file names.
+pub fn timestamp() -> String {
+    let (year, month, day, hour, minute, second) = utc_now();
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hour, minute, second)
+}
+
+// The same in the `YYYY-MM-DD HH:MM:SS UTC' format, meant to be read.
+pub fn date_time() -> String {
+    let (year, month, day, hour, minute, second) = utc_now();
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, hour, minute, second)
+}
+
+// The current date and time in UTC, as the year, month, day, hour, minute
+// and second.
+fn utc_now() -> (i64, i64, i64, i64, i64, i64) {
+    let seconds = match time::SystemTime::now().duration_since(time::UNIX_EPOCH) {
+        Ok(duration) => { duration.as_secs() as i64 },
+        Err(..) => { 0 },
+    };
+    let days = seconds.div_euclid(86_400);
+    let time_of_day = seconds.rem_euclid(86_400);
+
+    // Convert the days since the epoch into a date of the proleptic
+    // Gregorian calendar, going through 400-year eras of 146097 days, with
+    // the years starting in March, so that the leap day ends up at the end.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, time_of_day / 3600, (time_of_day / 60) % 60, time_of_day % 60)
+}